@@ -0,0 +1,9 @@
+// Генерує код gRPC-сервера з proto/blazing_search.proto (див. grpc_service)
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::configure()
+        .build_client(false)
+        .compile_protos_with_config(config, &["proto/blazing_search.proto"], &["proto"])?;
+    Ok(())
+}
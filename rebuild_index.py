@@ -1,14 +1,28 @@
 import json
+import os
 import sys
 import io
 
 # Set UTF-8 output for Windows console
 sys.stdout = io.TextIOWrapper(sys.stdout.buffer, encoding='utf-8')
 
-print("Rebuilding inverted index from documents_index.json...")
+# Index directory (same as --index-dir of blazing_SEARCH), defaults to the current one
+index_dir = sys.argv[1] if len(sys.argv) > 1 else '.'
+
+# The active generation is recorded in the manifest; fall back to the legacy fixed names
+documents_path = os.path.join(index_dir, 'documents_index.json')
+inverted_path = os.path.join(index_dir, 'inverted_index.json')
+manifest_path = os.path.join(index_dir, 'index_manifest.json')
+if os.path.exists(manifest_path):
+    with open(manifest_path, 'r', encoding='utf-8') as f:
+        manifest = json.load(f)
+    documents_path = manifest['documents_index']
+    inverted_path = manifest['inverted_index']
+
+print(f"Rebuilding inverted index from {documents_path}...")
 
 # Load documents_index
-with open('documents_index.json', 'r', encoding='utf-8') as f:
+with open(documents_path, 'r', encoding='utf-8') as f:
     doc_index = json.load(f)
 
 print(f"Loaded {len(doc_index['documents'])} documents")
@@ -93,8 +107,8 @@ for doc_idx, doc in enumerate(doc_index['documents']):
 print(f"Built inverted index with {len(inverted_index['word_to_docs'])} unique words")
 
 # Save inverted index
-print("Saving inverted_index.json...")
-with open('inverted_index.json', 'w', encoding='utf-8') as f:
+print(f"Saving {inverted_path}...")
+with open(inverted_path, 'w', encoding='utf-8') as f:
     json.dump(inverted_index, f, ensure_ascii=False)
 
 print("Done! Inverted index rebuilt successfully.")
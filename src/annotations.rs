@@ -0,0 +1,186 @@
+//! Примітки до абзаців: користувач залишає нотатку до конкретного абзацу документа
+//! ("скасовано наказом №42"). Примітки зберігаються поза індексом, в annotations.json у
+//! папці індексів, за стабільним doc_id документа і хешем тексту абзацу - тож переживають
+//! переіндексацію: заміна файлу зберігає doc_id, а абзац знаходиться за текстом, навіть
+//! якщо зсунувся. Повна перебудова індексу призначає нові doc_id, примітки до неї
+//! лишаються у файлі, але більше не показуються.
+
+use crate::tr;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Найбільша довжина примітки, символів
+pub const MAX_ANNOTATION_LENGTH: usize = 2000;
+
+/// Примітка до абзацу
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub id: u64,
+    /// Хеш тексту абзацу (див. paragraph_hash)
+    pub paragraph_hash: String,
+    pub text: String,
+    /// Ім'я з історії пошуку або IP-адреса
+    pub author: String,
+    /// Unix timestamp
+    pub created_at: u64,
+}
+
+/// Примітки за документами: doc_id -> примітки
+type DocumentAnnotations = BTreeMap<u64, Vec<Annotation>>;
+
+pub struct AnnotationStore {
+    path: String,
+    annotations: Mutex<DocumentAnnotations>,
+    /// Зростає з кожною зміною: результати пошуку з примітками застаріли (див. search_etag)
+    revision: AtomicU64,
+}
+
+/// Хеш абзацу без різниці в пробілах: переформатування документа не відриває примітку
+pub fn paragraph_hash(text: &str) -> String {
+    let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    Sha256::digest(normalized.as_bytes())[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl AnnotationStore {
+    /// Завантажує збережені примітки; пошкоджений файл - попередження і порожні примітки
+    pub fn load(path: String) -> Self {
+        let annotations = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("{}", tr!(AnnotationsLoadFailed, path, e));
+                DocumentAnnotations::new()
+            }),
+            Err(e) if Path::new(&path).exists() => {
+                warn!("{}", tr!(AnnotationsLoadFailed, path, e));
+                DocumentAnnotations::new()
+            }
+            Err(_) => DocumentAnnotations::new(),
+        };
+        Self { path, annotations: Mutex::new(annotations), revision: AtomicU64::new(0) }
+    }
+
+    /// Додає примітку до абзацу з текстом paragraph документа doc_id і зберігає файл
+    pub fn add(&self, doc_id: u64, paragraph: &str, text: &str, author: &str) -> Annotation {
+        let mut annotations = self.lock();
+        let id = annotations.values().flatten().map(|a| a.id).max().unwrap_or(0) + 1;
+        let annotation = Annotation {
+            id,
+            paragraph_hash: paragraph_hash(paragraph),
+            text: text.trim().to_string(),
+            author: author.to_string(),
+            created_at: unix_now(),
+        };
+        annotations.entry(doc_id).or_default().push(annotation.clone());
+        self.save(&annotations);
+        self.revision.fetch_add(1, Ordering::Relaxed);
+        annotation
+    }
+
+    /// Видаляє примітку; false - примітки з таким id немає
+    pub fn remove(&self, id: u64) -> bool {
+        let mut annotations = self.lock();
+        let Some(doc_id) = annotations.iter().find(|(_, list)| list.iter().any(|a| a.id == id)).map(|(doc_id, _)| *doc_id)
+        else {
+            return false;
+        };
+        if let Some(list) = annotations.get_mut(&doc_id) {
+            list.retain(|a| a.id != id);
+            if list.is_empty() {
+                annotations.remove(&doc_id);
+            }
+        }
+        self.save(&annotations);
+        self.revision.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Усі примітки документа в порядку додавання
+    pub fn for_document(&self, doc_id: u64) -> Vec<Annotation> {
+        self.lock().get(&doc_id).cloned().unwrap_or_default()
+    }
+
+    /// Примітки до абзаців документа: для кожного абзацу - його примітки (порожній список,
+    /// якщо приміток немає; хеші рахуються лише для документів з примітками)
+    pub fn for_paragraphs<'a>(&self, doc_id: u64, paragraphs: impl IntoIterator<Item = &'a str>) -> Vec<Vec<Annotation>> {
+        let annotations = self.for_document(doc_id);
+        paragraphs
+            .into_iter()
+            .map(|paragraph| {
+                if annotations.is_empty() {
+                    return Vec::new();
+                }
+                let hash = paragraph_hash(paragraph);
+                annotations.iter().filter(|a| a.paragraph_hash == hash).cloned().collect()
+            })
+            .collect()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, DocumentAnnotations> {
+        match self.annotations.lock() {
+            Ok(annotations) => annotations,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Записує через тимчасовий файл, щоб збій не пошкодив примітки
+    fn save(&self, annotations: &DocumentAnnotations) {
+        let temp_path = format!("{}.tmp", self.path);
+        let result = serde_json::to_string_pretty(annotations)
+            .map_err(std::io::Error::from)
+            .and_then(|json| fs::write(&temp_path, json))
+            .and_then(|()| fs::rename(&temp_path, &self.path));
+        if let Err(e) = result {
+            warn!("{}", tr!(AnnotationsSaveFailed, self.path, e));
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+
+    #[test]
+    fn test_annotations_follow_paragraph_text_and_persist() {
+        let dir = TempIndexDir::new("annotations");
+        let path = dir.join("annotations.json").to_string_lossy().to_string();
+
+        let store = AnnotationStore::load(path.clone());
+        let first = store.add(7, "1. Петренку П.П. надати відпустку", " Скасовано наказом №42 ", "оператор");
+        let second = store.add(7, "2. Іваненка І.І. призначити", "Перевірити дату", "оператор");
+        store.add(9, "1. Петренку П.П. надати відпустку", "Інший документ", "оператор");
+        assert_eq!((first.id, second.id, first.text.as_str()), (1, 2, "Скасовано наказом №42"));
+        assert_eq!(store.revision(), 3);
+
+        // Після переіндексації абзац зсунувся і змінилися пробіли - примітка лишається при ньому
+        let store = AnnotationStore::load(path.clone());
+        let paragraphs = ["Вступ", "1.  Петренку П.П. надати\tвідпустку", "3. Інший абзац"];
+        let found = store.for_paragraphs(7, paragraphs);
+        assert!(found[0].is_empty() && found[2].is_empty());
+        assert_eq!(found[1].iter().map(|a| a.id).collect::<Vec<_>>(), vec![1]);
+        assert!(store.for_paragraphs(8, paragraphs).iter().all(Vec::is_empty));
+
+        assert!(store.remove(1));
+        assert!(!store.remove(1));
+        let store = AnnotationStore::load(path);
+        assert_eq!(store.for_document(7).iter().map(|a| a.id).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(store.for_document(9).len(), 1);
+    }
+}
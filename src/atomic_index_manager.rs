@@ -1,17 +1,106 @@
-use std::path::Path;
-use std::fs::{self, OpenOptions};
+use log::{info, warn};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::time::{Duration, Instant};
 use fs4::fs_std::FileExt;
 use chrono::{DateTime, Local};
+use crate::classifier::Classifier;
 use crate::document_record::DocumentIndex;
+use crate::error::IndexError;
 use crate::inverted_index::InvertedIndex;
-use crate::folder_processor::FolderProcessor;
+use crate::folder_processor::{FolderProcessor, DEFAULT_EXCLUDED_FOLDERS};
+use crate::index_backup::{self, BackupPolicy};
+use crate::index_compression::IndexCompression;
+use crate::index_delta::IndexDelta;
+use crate::index_repair::{self, RepairReport, ValidationReport};
+use crate::index_storage::{FsStorage, IndexStorage};
+use crate::index_store::{store_is_empty, IndexStore, StoreChanges};
+use crate::index_checksum::{checksum_path, write_checksum};
+use crate::index_transaction::IndexTransaction;
+use crate::indexing_checkpoint::{IndexingCheckpoint, CHECKPOINT_DIR_NAME};
+use crate::inverted_rebuild::{RebuildStats, SegmentedRebuild, REBUILD_DIR_NAME};
+use crate::inverted_segments::{self, DEFAULT_MAX_SEGMENTS};
+use crate::mutation_journal::MutationJournal;
+use crate::shutdown::Shutdown;
+use crate::index_manifest::{
+    file_sha256, generation_path, generations_match, parse_generation, resolve_active_paths, sibling_path, sync_file,
+    sync_parent_dir, unix_now, GenerationStamped, IndexManifest, SnapshotEntry, TransactionLog, WalRecord,
+};
+
+/// Файл блокування (поруч з індексами), спільний для всіх процесів, що оновлюють індекси
+const LOCK_FILE_NAME: &str = "index_update.lock";
+
+/// Папка з "квитками" процесів, що чекають на блокування (для показу позиції в черзі)
+const LOCK_QUEUE_DIR_NAME: &str = "index_update.queue";
+
+/// Скільки за замовчуванням чекати, поки інший процес завершить оновлення
+pub(crate) const DEFAULT_LOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Інтервал повторних спроб отримати блокування
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Як часто повідомляти про очікування в черзі
+const LOCK_PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Квиток черги, який стільки не оновлювався, - залишок процесу, що аварійно завершився
+/// (процеси в черзі оновлюють свої квитки з кожною спробою)
+const QUEUE_TICKET_STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// Скільки останніх поколінь індексів зберігати для відкату
+pub(crate) const DEFAULT_SNAPSHOT_RETENTION: usize = 5;
+
+/// Як часто виконувати планове ущільнення, якщо є tombstone-слоти
+const COMPACTION_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// Частка tombstone-слотів, після якої ущільнення виконується позачергово
+const COMPACTION_TOMBSTONE_RATIO: f64 = 0.2;
+
+/// Резервні копії індексу документів: його не можна відновити без повної переіндексації
+pub(crate) const DEFAULT_DOCUMENTS_BACKUP_POLICY: BackupPolicy =
+    BackupPolicy::new(10, Some(Duration::from_secs(30 * 24 * 3600)));
+
+/// Резервні копії інвертованого індексу: великий і перебудовується з індексу документів
+pub(crate) const DEFAULT_INVERTED_BACKUP_POLICY: BackupPolicy =
+    BackupPolicy::new(3, Some(Duration::from_secs(7 * 24 * 3600)));
 
 /// Менеджер для атомарного оновлення індексів
 /// Забезпечує, що обидва індекси (документний та інвертований) 
-/// оновлюються разом або не оновлюються взагалі
+/// оновлюються разом або не оновлюються взагалі.
+/// Активне покоління файлів визначається маніфестом (див. index_manifest).
 pub struct AtomicIndexManager {
     pub documents_index_path: String,
     pub inverted_index_path: String,
+    /// fsync файлів покоління, маніфесту, журналу та папки перед перемиканням.
+    /// Захищає від обрізаних індексів після втрати живлення ціною повільнішого збереження.
+    pub durable_writes: bool,
+    /// Скільки чекати на блокування, якщо оновлення вже виконує інший процес
+    /// (Duration::ZERO - помилка одразу, як раніше)
+    pub lock_wait_timeout: Duration,
+    /// Скільки останніх успішних поколінь зберігати як знімки для відкату (мінімум 1 - активне)
+    pub snapshot_retention: usize,
+    /// Ротація датованих резервних копій попереднього індексу документів
+    pub documents_backup_policy: BackupPolicy,
+    /// Ротація датованих резервних копій попереднього інвертованого індексу
+    pub inverted_backup_policy: BackupPolicy,
+    /// Стиснення нових файлів поколінь; читаються файли в будь-якому форматі
+    pub compression: IndexCompression,
+    /// Скільки сегментів інвертованого індексу накопичується до фонового злиття
+    /// (0 - щоразу повний файл), див. inverted_segments
+    pub max_segments: usize,
+    /// Папки, які не індексуються (див. FolderProcessor)
+    pub excluded_folders: Vec<String>,
+    /// Класифікатор видів наказів для оброблених документів
+    classifier: Option<Arc<Classifier>>,
+    /// Запит зупинки: інкрементне оновлення зберігає вже оброблені файли і завершується
+    shutdown: Option<Shutdown>,
+    /// Файлові операції запису поколінь, маніфесту та резервних копій
+    storage: Arc<dyn IndexStorage>,
+    /// Сховище індексів замість файлів поколінь (storage.backend); знімків для відкату
+    /// і резервних копій у ньому немає
+    store: Option<Arc<dyn IndexStore>>,
 }
 
 impl AtomicIndexManager {
@@ -19,188 +108,912 @@ impl AtomicIndexManager {
         Self {
             documents_index_path: documents_path.to_string(),
             inverted_index_path: inverted_path.to_string(),
+            durable_writes: true,
+            lock_wait_timeout: DEFAULT_LOCK_WAIT_TIMEOUT,
+            snapshot_retention: DEFAULT_SNAPSHOT_RETENTION,
+            documents_backup_policy: DEFAULT_DOCUMENTS_BACKUP_POLICY,
+            inverted_backup_policy: DEFAULT_INVERTED_BACKUP_POLICY,
+            compression: IndexCompression::None,
+            max_segments: DEFAULT_MAX_SEGMENTS,
+            excluded_folders: DEFAULT_EXCLUDED_FOLDERS.iter().map(|f| f.to_string()).collect(),
+            classifier: None,
+            shutdown: None,
+            storage: Arc::new(FsStorage),
+            store: None,
+        }
+    }
+
+    /// Підміняє файлові операції сховищем, що імітує збої
+    #[cfg(test)]
+    pub(crate) fn with_storage(mut self, storage: Arc<dyn IndexStorage>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Зберігає індекси у сховищі (див. index_store) замість файлів поколінь
+    pub fn with_store(mut self, store: Arc<dyn IndexStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Сховище індексів, якщо індекси зберігаються не у файлах поколінь
+    pub fn store(&self) -> Option<&Arc<dyn IndexStore>> {
+        self.store.as_ref()
+    }
+
+    /// Чи є збережені індекси (файл активного покоління або версія у сховищі)
+    pub fn has_indices(&self) -> bool {
+        match &self.store {
+            Some(store) => matches!(store.generation(), Ok(Some(_))),
+            None => Path::new(&self.active_paths().0).exists(),
         }
     }
 
-    /// Атомарно зберігає обидва індекси
-    /// Використовує систему тимчасових файлів та транзакційний підхід
+    /// Задає політики зберігання резервних копій для кожного файлу індексу
+    pub fn with_backup_policies(mut self, documents: BackupPolicy, inverted: BackupPolicy) -> Self {
+        self.documents_backup_policy = documents;
+        self.inverted_backup_policy = inverted;
+        self
+    }
+
+    /// Задає, скільки сегментів інвертованого індексу накопичується до злиття
+    pub fn with_max_segments(mut self, max_segments: usize) -> Self {
+        self.max_segments = max_segments;
+        self
+    }
+
+    /// Задає кількість збережених знімків
+    pub fn with_snapshot_retention(mut self, retention: usize) -> Self {
+        self.snapshot_retention = retention.max(1);
+        self
+    }
+
+    /// Задає час очікування блокування оновлення
+    pub fn with_lock_wait_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_wait_timeout = timeout;
+        self
+    }
+
+    /// Вмикає або вимикає fsync під час збереження індексів
+    pub fn with_durable_writes(mut self, durable_writes: bool) -> Self {
+        self.durable_writes = durable_writes;
+        self
+    }
+
+    /// Задає стиснення файлів індексу, записаних після цього
+    pub fn with_compression(mut self, compression: IndexCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Задає папки, які не індексуються
+    pub fn with_excluded_folders(mut self, excluded_folders: Vec<String>) -> Self {
+        self.excluded_folders = excluded_folders;
+        self
+    }
+
+    /// Задає класифікатор видів наказів (діє на документи, оброблені після цього)
+    pub fn with_classifier(mut self, classifier: Arc<Classifier>) -> Self {
+        self.classifier = Some(classifier);
+        self
+    }
+
+    /// Дозволяє перервати інкрементне оновлення і очікування блокування при зупинці процесу
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    fn folder_processor(&self) -> FolderProcessor {
+        let mut checkpoint = IndexingCheckpoint::new(
+            sibling_path(&self.documents_index_path, CHECKPOINT_DIR_NAME),
+            self.storage.clone(),
+        );
+        checkpoint.durable_writes = self.durable_writes;
+
+        let processor = FolderProcessor::new()
+            .with_excluded_folders(self.excluded_folders.clone())
+            .with_checkpoint(checkpoint);
+        match &self.classifier {
+            Some(classifier) => processor.with_classifier(classifier.clone()),
+            None => processor,
+        }
+    }
+
+    fn shutdown_requested(&self) -> bool {
+        self.shutdown.as_ref().is_some_and(Shutdown::is_requested)
+    }
+
+    /// Починає транзакцію зміни окремих документів (див. IndexTransaction).
+    /// Блокування оновлення утримується до commit/abort.
+    pub fn begin(&self) -> Result<IndexTransaction<'_>, IndexError> {
+        IndexTransaction::begin(self)
+    }
+
+    /// Атомарно зберігає обидва індекси під блокуванням оновлення
     pub fn save_indices_atomically(
         &self,
         document_index: &DocumentIndex,
         inverted_index: &InvertedIndex,
-    ) -> Result<(), String> {
-        println!("🔄 Початок атомарного збереження індексів...");
+    ) -> Result<(), IndexError> {
+        let _lock = self.acquire_update_lock()?;
+        self.commit(document_index, inverted_index, StoreChanges::All).map(|_| ())
+    }
 
-        // Етап 1: Створюємо тимчасові файли для обох індексів
-        let temp_doc_path = format!("{}.atomic_temp", self.documents_index_path);
-        let temp_inv_path = format!("{}.atomic_temp", self.inverted_index_path);
-        
-        // Створюємо резервні копії існуючих файлів
-        let backup_doc_path = format!("{}.atomic_backup", self.documents_index_path);
-        let backup_inv_path = format!("{}.atomic_backup", self.inverted_index_path);
+    /// Записує нову версію індексів: у сховище або новим поколінням файлів.
+    /// Викликається тільки під блокуванням. Повертає номер версії (покоління).
+    fn commit(
+        &self,
+        document_index: &DocumentIndex,
+        inverted_index: &InvertedIndex,
+        changes: StoreChanges,
+    ) -> Result<u64, IndexError> {
+        let Some(store) = &self.store else {
+            return self.commit_generation(document_index, inverted_index, &changes);
+        };
 
-        // Очищуємо старі тимчасові файли якщо вони є
-        let _ = fs::remove_file(&temp_doc_path);
-        let _ = fs::remove_file(&temp_inv_path);
+        match &changes {
+            StoreChanges::All => info!("🔄 Збереження індексів у сховище..."),
+            StoreChanges::Slots(slots) => info!("🔄 Збереження {} змінених документів у сховище...", slots.len()),
+        }
+        let generation = store.commit(document_index, inverted_index, &changes)?;
+        info!("✅ Індекси збережено у сховище (версія {})", generation);
+        Ok(generation)
+    }
 
-        println!("📝 Збереження в тимчасові файли...");
-        
-        // Етап 2: Зберігаємо обидва індекси в тимчасові файли
-        if let Err(e) = self.save_document_index_to_temp(&temp_doc_path, document_index) {
-            // Очищуємо тимчасові файли при помилці
-            let _ = fs::remove_file(&temp_doc_path);
-            let _ = fs::remove_file(&temp_inv_path);
-            return Err(format!("Помилка збереження індексу документів в тимчасовий файл: {}", e));
+    /// Індекс документів активної версії
+    pub fn load_document_index(&self) -> Result<DocumentIndex, IndexError> {
+        match &self.store {
+            Some(store) => store.load_documents()?.ok_or_else(store_is_empty),
+            None => DocumentIndex::load_from_file(&self.active_paths().0),
         }
+    }
 
-        if let Err(e) = self.save_inverted_index_to_temp(&temp_inv_path, inverted_index) {
-            // Очищуємо тимчасові файли при помилці
-            let _ = fs::remove_file(&temp_doc_path);
-            let _ = fs::remove_file(&temp_inv_path);
-            return Err(format!("Помилка збереження інвертованого індексу в тимчасовий файл: {}", e));
+    /// Інвертований індекс активної версії
+    fn load_inverted_index(&self) -> Result<InvertedIndex, IndexError> {
+        match &self.store {
+            Some(store) => store.load_inverted()?.ok_or_else(store_is_empty),
+            None => InvertedIndex::load_from_file(&self.active_paths().1),
         }
+    }
 
-        println!("💾 Створення резервних копій...");
-        
-        // Етап 3: Створюємо резервні копії існуючих файлів
-        if Path::new(&self.documents_index_path).exists() {
-            if let Err(e) = fs::copy(&self.documents_index_path, &backup_doc_path) {
-                // Очищуємо тимчасові файли при помилці
-                let _ = fs::remove_file(&temp_doc_path);
-                let _ = fs::remove_file(&temp_inv_path);
-                return Err(format!("Помилка створення резервної копії індексу документів: {}", e));
-            }
+    /// Обидва індекси активної версії
+    fn load_indices(&self) -> Result<(DocumentIndex, InvertedIndex), IndexError> {
+        match &self.store {
+            Some(store) => store.load()?.ok_or_else(store_is_empty),
+            None => Ok((self.load_document_index()?, self.load_inverted_index()?)),
+        }
+    }
+
+    /// Записує нове покоління індексів. Викликається тільки під блокуванням.
+    /// Нове покоління записується у файли з номером покоління, після чого
+    /// маніфест атомарно перемикається на них. Старі файли ніколи не перезаписуються,
+    /// тому збій на будь-якому етапі залишає попереднє покоління цілим.
+    fn commit_generation(
+        &self,
+        document_index: &DocumentIndex,
+        inverted_index: &InvertedIndex,
+        changes: &StoreChanges,
+    ) -> Result<u64, IndexError> {
+        info!("🔄 Початок атомарного збереження індексів...");
+
+        // Завершуємо або відкочуємо незавершену транзакцію з попереднього запуску
+        self.recover_pending_transaction();
+
+        let previous = IndexManifest::load(&self.documents_index_path);
+        let generation = previous.as_ref().map_or(1, |m| m.latest_generation() + 1);
+
+        let new_doc_path = generation_path(&self.documents_index_path, generation);
+        let new_inv_path = generation_path(&self.inverted_index_path, generation);
+
+        // Інвертований індекс - лише змінені слоти поверх файлу активного покоління, якщо можна
+        let segment = self.inverted_segment(previous.as_ref(), inverted_index, changes);
+        if let Some((segment, layers)) = &segment {
+            info!(
+                "🧩 Інвертований індекс записується сегментом: {} слів {} змінених документів поверх {} шарів",
+                segment.word_to_docs.len(),
+                segment.segment.as_ref().map_or(0, |header| header.slots.len()),
+                layers.len()
+            );
         }
 
-        if Path::new(&self.inverted_index_path).exists() {
-            if let Err(e) = fs::copy(&self.inverted_index_path, &backup_inv_path) {
-                // Очищуємо тимчасові файли при помилці
-                let _ = fs::remove_file(&temp_doc_path);
-                let _ = fs::remove_file(&temp_inv_path);
-                let _ = fs::remove_file(&backup_doc_path);
-                return Err(format!("Помилка створення резервної копії інвертованого індексу: {}", e));
+        // Етап 1: Фіксуємо намір у журналі транзакцій
+        let wal = TransactionLog::new(&self.documents_index_path).durable(self.durable_writes);
+        wal.append(&WalRecord::Begin {
+            generation,
+            documents_index: new_doc_path.clone(),
+            inverted_index: new_inv_path.clone(),
+            timestamp: unix_now(),
+        })?;
+
+        info!("📝 Збереження покоління {}...", generation);
+
+        // Етап 2: Записуємо обидва індекси у файли нового покоління
+        if let Err(e) = self.save_document_index_to_file(&new_doc_path, document_index, generation) {
+            self.abort_generation(&new_doc_path, &new_inv_path);
+            return Err(e);
+        }
+
+        let inverted_file = segment.as_ref().map_or(inverted_index, |(segment, _)| segment);
+        if let Err(e) = self.save_inverted_index_to_file(&new_inv_path, inverted_file, generation) {
+            self.abort_generation(&new_doc_path, &new_inv_path);
+            return Err(e);
+        }
+
+        // Контрольні суми файлів покоління: у маніфест (для validate) і поруч з файлами,
+        // щоб завантаження відкинуло обрізаний чи пошкоджений файл ще до парсингу
+        let storage = self.storage.as_ref();
+        let checksums = file_sha256(&new_doc_path)
+            .and_then(|doc| Ok((doc, file_sha256(&new_inv_path)?)))
+            .and_then(|(doc, inv)| {
+                write_checksum(storage, &new_doc_path, &doc)?;
+                write_checksum(storage, &new_inv_path, &inv)?;
+                Ok((doc, inv))
+            });
+        let (documents_sha256, inverted_sha256) = match checksums {
+            Ok((doc, inv)) => (Some(doc), Some(inv)),
+            Err(e) => {
+                self.abort_generation(&new_doc_path, &new_inv_path);
+                return Err(e);
+            }
+        };
+
+        // Файли покоління мають бути на диску до того, як на них вкаже маніфест
+        if self.durable_writes {
+            let synced = sync_file(storage, &new_doc_path)
+                .and_then(|_| sync_file(storage, &new_inv_path))
+                .and_then(|_| sync_file(storage, checksum_path(&new_doc_path)))
+                .and_then(|_| sync_file(storage, checksum_path(&new_inv_path)))
+                .and_then(|_| sync_parent_dir(storage, &new_doc_path));
+
+            if let Err(e) = synced {
+                self.abort_generation(&new_doc_path, &new_inv_path);
+                return Err(e);
             }
         }
 
-        println!("🔄 Атомарне переміщення файлів...");
+        // Резервні копії попереднього активного покоління (до перемикання маніфесту)
+        self.backup_active_indices();
+
+        // Етап 3: Атомарно перемикаємо маніфест на нове покоління
+        info!("🔄 Перемикання маніфесту на покоління {}...", generation);
+        let committed_at = unix_now();
+        let snapshots = self.retained_snapshots(
+            previous.as_ref(),
+            SnapshotEntry {
+                generation,
+                documents_index: new_doc_path.clone(),
+                inverted_index: new_inv_path.clone(),
+                committed_at,
+                total_documents: document_index.total_documents,
+                documents_sha256,
+                inverted_sha256,
+                inverted_base: segment.map(|(_, layers)| layers).unwrap_or_default(),
+            },
+        );
+
+        let manifest = IndexManifest {
+            generation,
+            documents_index: new_doc_path,
+            inverted_index: new_inv_path,
+            committed_at,
+            snapshots,
+        };
+
+        if let Err(e) = manifest.store(self.storage.as_ref(), &self.documents_index_path, self.durable_writes) {
+            self.abort_generation(&manifest.documents_index, &manifest.inverted_index);
+            return Err(e);
+        }
+
+        if let Err(e) = wal.append(&WalRecord::Commit { generation, timestamp: unix_now() }) {
+            // Маніфест вже перемкнуто - транзакція фактично завершена
+            warn!("⚠️ Не вдалося записати підтвердження в журнал: {}", e);
+        }
+        wal.clear();
 
-        // Етап 4: Атомарно переміщуємо тимчасові файли на місце основних
-        // На Windows потрібно спочатку видалити існуючий файл перед rename
+        // Етап 4: Прибираємо файли поколінь, що вийшли за межі зберігання знімків
+        info!("🧹 Очищення застарілих поколінь...");
+        self.remove_stale_generations(&manifest);
+        self.rotate_backups();
+
+        info!("✅ Атомарне збереження індексів завершено успішно (покоління {})!", generation);
+        Ok(generation)
+    }
+
+    /// Сегмент інвертованого індексу для нового покоління та його нижчі шари (див.
+    /// inverted_segments). None - записується повний файл: змінились не окремі слоти,
+    /// індекс перебудовано в пам'яті (покоління 0) чи завантажено не з активного файлу,
+    /// або ланцюжок уже вдвічі довший за max_segments, бо фонового злиття не було.
+    fn inverted_segment(
+        &self,
+        previous: Option<&IndexManifest>,
+        inverted_index: &InvertedIndex,
+        changes: &StoreChanges,
+    ) -> Option<(InvertedIndex, Vec<String>)> {
+        let StoreChanges::Slots(slots) = changes else {
+            return None;
+        };
+        let previous = previous?;
+        if self.max_segments == 0 || inverted_index.generation != previous.generation {
+            return None;
+        }
+
+        let mut layers = previous.active_snapshot()?.inverted_base.clone();
+        if layers.len() + 1 >= self.max_segments * 2 || !Path::new(&previous.inverted_index).exists() {
+            return None;
+        }
+
+        let segment = inverted_segments::segment_of(inverted_index, slots, &previous.inverted_index);
+        layers.push(previous.inverted_index.clone());
+        Some((segment, layers))
+    }
+
+    /// Скільки сегментів інвертованого індексу лежить поверх повного файлу активного покоління
+    pub fn active_segments(&self) -> usize {
+        if self.store.is_some() {
+            return 0;
+        }
+        IndexManifest::load(&self.documents_index_path)
+            .and_then(|manifest| manifest.active_snapshot().map(|snapshot| snapshot.inverted_base.len()))
+            .unwrap_or(0)
+    }
+
+    /// Чи накопичилось достатньо сегментів для фонового злиття
+    pub fn segments_due_for_merge(&self) -> bool {
+        self.max_segments > 0 && self.active_segments() >= self.max_segments
+    }
+
+    /// Зливає сегменти активного покоління: записує новим поколінням повний інвертований
+    /// індекс (разом з незмінним індексом документів). Повертає порожню дельту для
+    /// пошукового движка або None, якщо зливати нічого.
+    pub fn merge_segments(&self) -> Result<Option<IndexDelta>, IndexError> {
+        if self.active_segments() == 0 {
+            return Ok(None);
+        }
+        let _lock = self.acquire_update_lock()?;
+
+        // Незавершені зміни - спершу в індекс, інакше нове покоління зробило б журнал застарілим
+        self.replay_mutation_journal()?;
+
+        // Поки чекали на блокування, сегменти могли вже злити
+        let segments = self.active_segments();
+        if segments == 0 {
+            return Ok(None);
+        }
+
+        info!("🧩 Злиття {} сегментів інвертованого індексу в повний файл...", segments);
+        let (doc_index, inv_index) = self.load_indices()?;
+        let delta = IndexDelta::new(&doc_index, &[], &[]);
+        let generation = self.commit(&doc_index, &inv_index, StoreChanges::All)?;
+        Ok(delta.map(|delta| delta.with_generation(generation)))
+    }
+
+    /// Файл блокування в папці індексів: окремі папки індексів не блокують одна одну
+    fn lock_file_path(&self) -> PathBuf {
+        sibling_path(&self.documents_index_path, LOCK_FILE_NAME)
+    }
+
+    fn lock_queue_dir(&self) -> PathBuf {
+        sibling_path(&self.documents_index_path, LOCK_QUEUE_DIR_NAME)
+    }
+
+    /// Шляхи до активних файлів індексів (з маніфесту або старі фіксовані назви)
+    pub fn active_paths(&self) -> (String, String) {
+        resolve_active_paths(&self.documents_index_path, &self.inverted_index_path)
+    }
+
+    /// Отримує ексклюзивне блокування оновлення індексів.
+    /// Якщо його утримує інший процес - чекає до lock_wait_timeout, періодично
+    /// повідомляючи позицію в черзі. Черга - у порядку надходження: блокування пробує взяти
+    /// лише перший у черзі, а новий процес не обганяє тих, хто вже чекає.
+    /// Блокування знімається при drop результату.
+    ///
+    /// Очікування блокує потік (до lock_wait_timeout), тож з асинхронного коду викликається
+    /// через tokio::task::spawn_blocking.
+    pub(crate) fn acquire_update_lock(&self) -> Result<UpdateLock, IndexError> {
+        // Не обрізаємо файл до отримання блокування - в ньому інформація про власника
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(self.lock_file_path())
+            .map_err(|e| IndexError::io("Помилка створення lock файлу", e))?;
+
+        let queue_dir = self.lock_queue_dir();
+        let others_waiting = QueueTicket::waiting(&queue_dir) > 0;
+        if others_waiting || file.try_lock_exclusive().is_err() {
+            if self.lock_wait_timeout.is_zero() {
+                return Err(IndexError::Locked("⚠️ Інший процес вже оновлює індекси. Очікуйте завершення.".to_string()));
+            }
+
+            let ticket = QueueTicket::register(&queue_dir)?;
+            let started = Instant::now();
+            let mut last_report: Option<Instant> = None;
 
-        // Спочатку переміщуємо індекс документів
-        if Path::new(&self.documents_index_path).exists() {
-            // Пробуємо видалити з кількома спробами (файл може бути тимчасово зайнятий)
-            let mut attempts = 0;
             loop {
-                match fs::remove_file(&self.documents_index_path) {
-                    Ok(_) => break,
-                    Err(_e) if attempts < 3 => {
-                        attempts += 1;
-                        std::thread::sleep(std::time::Duration::from_millis(100));
-                    }
-                    Err(e) => {
-                        self.restore_from_backups(&backup_doc_path, &backup_inv_path);
-                        let _ = fs::remove_file(&temp_doc_path);
-                        let _ = fs::remove_file(&temp_inv_path);
-                        return Err(format!("Помилка видалення старого індексу документів після {} спроб: {}", attempts + 1, e));
-                    }
+                if last_report.is_none_or(|t| t.elapsed() >= LOCK_PROGRESS_INTERVAL) {
+                    info!(
+                        "⏳ Індекси оновлює інший процес ({}), позиція в черзі: {}, очікування {} с з {} с",
+                        lock_holder_info(&self.lock_file_path()),
+                        ticket.position(),
+                        started.elapsed().as_secs(),
+                        self.lock_wait_timeout.as_secs()
+                    );
+                    last_report = Some(Instant::now());
+                }
+
+                std::thread::sleep(LOCK_RETRY_INTERVAL);
+                ticket.touch();
+
+                if self.shutdown_requested() {
+                    return Err(IndexError::Locked("⏹️ Очікування блокування перервано: процес зупиняється".to_string()));
+                }
+
+                if ticket.position() == 1 && file.try_lock_exclusive().is_ok() {
+                    break;
+                }
+
+                if started.elapsed() >= self.lock_wait_timeout {
+                    return Err(IndexError::Locked(format!(
+                        "⚠️ Інший процес оновлює індекси довше {} с ({}). Спробуйте пізніше.",
+                        self.lock_wait_timeout.as_secs(),
+                        lock_holder_info(&self.lock_file_path())
+                    )));
                 }
             }
         }
 
-        if let Err(e) = fs::rename(&temp_doc_path, &self.documents_index_path) {
-            // При помилці відновлюємо з резервних копій
-            self.restore_from_backups(&backup_doc_path, &backup_inv_path);
-            let _ = fs::remove_file(&temp_inv_path);
-            return Err(format!("Помилка переміщення індексу документів: {}", e));
+        // Записуємо інформацію про власника для процесів, що чекають у черзі
+        let _ = file.set_len(0);
+        let _ = writeln!(
+            &file,
+            "pid {} з {}",
+            std::process::id(),
+            Local::now().format("%H:%M:%S")
+        );
+
+        info!("🔒 Отримано ексклюзивний доступ до оновлення індексів");
+        Ok(UpdateLock { _file: file })
+    }
+
+    /// Спроба отримати блокування без очікування
+    fn try_acquire_update_lock(&self) -> Option<UpdateLock> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.lock_file_path())
+            .ok()?;
+
+        file.try_lock_exclusive().ok()?;
+        Some(UpdateLock { _file: file })
+    }
+
+    /// Відновлення після збою посеред транзакції.
+    /// Якщо маніфест вже вказує на незавершене покоління - воно вважається зафіксованим,
+    /// інакше його файли видаляються, а активним лишається попереднє покоління.
+    pub fn recover_pending_transaction(&self) {
+        let wal = TransactionLog::new(&self.documents_index_path);
+
+        if let Some(WalRecord::Begin { generation, documents_index, inverted_index, .. }) = wal.pending_transaction() {
+            let active_generation = IndexManifest::load(&self.documents_index_path).map(|m| m.generation);
+
+            if active_generation == Some(generation) {
+                info!("ℹ️ Покоління {} було перемкнуто до збою - транзакцію завершено", generation);
+            } else {
+                info!("🔄 Відкат незавершеного покоління {} з журналу транзакцій...", generation);
+                self.abort_generation(&documents_index, &inverted_index);
+            }
         }
 
-        // Потім переміщуємо інвертований індекс
-        if Path::new(&self.inverted_index_path).exists() {
-            // Пробуємо видалити з кількома спробами (файл може бути тимчасово зайнятий)
-            let mut attempts = 0;
-            loop {
-                match fs::remove_file(&self.inverted_index_path) {
-                    Ok(_) => break,
-                    Err(_e) if attempts < 3 => {
-                        attempts += 1;
-                        std::thread::sleep(std::time::Duration::from_millis(100));
-                    }
-                    Err(e) => {
-                        self.restore_from_backups(&backup_doc_path, &backup_inv_path);
-                        let _ = fs::remove_file(&temp_inv_path);
-                        return Err(format!("Помилка видалення старого інвертованого індексу після {} спроб: {}", attempts + 1, e));
-                    }
-                }
+        wal.clear();
+    }
+
+    /// Видаляє файли незавершеного покоління та очищує журнал
+    fn abort_generation(&self, doc_path: &str, inv_path: &str) {
+        for path in [doc_path, inv_path] {
+            let _ = self.storage.remove_file(Path::new(path));
+            let _ = self.storage.remove_file(Path::new(&checksum_path(path)));
+        }
+        TransactionLog::new(&self.documents_index_path).clear();
+    }
+
+    /// Створює датовані резервні копії поточних активних файлів індексів
+    fn backup_active_indices(&self) {
+        let (active_doc_path, active_inv_path) = self.active_paths();
+
+        // Сегмент без нижчих шарів - лише частина індексу: копіюються тільки повні файли
+        let inverted_is_segment = self.active_segments() > 0;
+
+        let targets = [
+            (&active_doc_path, &self.documents_index_path, &self.documents_backup_policy),
+            (&active_inv_path, &self.inverted_index_path, &self.inverted_backup_policy),
+        ];
+
+        for (active_path, base_path, policy) in targets {
+            if !policy.is_enabled() || !Path::new(active_path).exists() {
+                continue;
+            }
+            if inverted_is_segment && active_path == &active_inv_path {
+                continue;
+            }
+
+            match index_backup::create_backup(self.storage.as_ref(), active_path, base_path) {
+                Ok(backup_path) => info!("💾 Резервна копія: {}", backup_path.display()),
+                Err(e) => warn!("⚠️ {}", e),
             }
         }
+    }
+
+    /// Видаляє резервні копії, що не вкладаються в політики зберігання
+    fn rotate_backups(&self) {
+        let storage = self.storage.as_ref();
+        let removed = index_backup::rotate_backups(storage, &self.documents_index_path, &self.documents_backup_policy)
+            + index_backup::rotate_backups(storage, &self.inverted_index_path, &self.inverted_backup_policy);
 
-        if let Err(e) = fs::rename(&temp_inv_path, &self.inverted_index_path) {
-            // При помилці відновлюємо з резервних копій
-            self.restore_from_backups(&backup_doc_path, &backup_inv_path);
-            return Err(format!("Помилка переміщення інвертованого індексу: {}", e));
+        if removed > 0 {
+            info!("🧹 Видалено {} застарілих резервних копій", removed);
         }
+    }
 
-        println!("🧹 Очищення резервних копій...");
-        
-        // Етап 5: Видаляємо резервні копії після успішного збереження
-        let _ = fs::remove_file(&backup_doc_path);
-        let _ = fs::remove_file(&backup_inv_path);
+    /// Список знімків з нового маніфесту: попередні знімки + нове покоління,
+    /// обрізаний до snapshot_retention найновіших
+    fn retained_snapshots(&self, previous: Option<&IndexManifest>, new_entry: SnapshotEntry) -> Vec<SnapshotEntry> {
+        let mut snapshots = match previous {
+            Some(manifest) if !manifest.snapshots.is_empty() => manifest.snapshots.clone(),
+            // Маніфест до появи знімків - зберігаємо його активне покоління як перший знімок
+            Some(manifest) => vec![SnapshotEntry {
+                generation: manifest.generation,
+                documents_index: manifest.documents_index.clone(),
+                inverted_index: manifest.inverted_index.clone(),
+                committed_at: manifest.committed_at,
+                total_documents: DocumentIndex::load_from_file(&manifest.documents_index)
+                    .map(|index| index.total_documents)
+                    .unwrap_or(0),
+                documents_sha256: file_sha256(&manifest.documents_index).ok(),
+                inverted_sha256: file_sha256(&manifest.inverted_index).ok(),
+                inverted_base: Vec::new(),
+            }],
+            None => Vec::new(),
+        };
+
+        snapshots.retain(|s| s.generation != new_entry.generation);
+        snapshots.push(new_entry);
+        snapshots.sort_by_key(|s| s.generation);
+
+        let retention = self.snapshot_retention.max(1);
+        if snapshots.len() > retention {
+            let excess = snapshots.len() - retention;
+            snapshots.drain(..excess);
+        }
 
-        println!("✅ Атомарне збереження індексів завершено успішно!");
+        snapshots
+    }
+
+    /// Список збережених знімків (від старих до нових) та номер активного покоління
+    pub fn list_snapshots(&self) -> (Vec<SnapshotEntry>, Option<u64>) {
+        if let Some(store) = &self.store {
+            return (Vec::new(), store.generation().ok().flatten());
+        }
+        match IndexManifest::load(&self.documents_index_path) {
+            Some(manifest) => (manifest.snapshots, Some(manifest.generation)),
+            None => (Vec::new(), None),
+        }
+    }
+
+    /// Відкочує індекси до збереженого знімку без переіндексації.
+    /// Знімок лишається у списку, тому можна повернутися і на новіше покоління.
+    pub fn rollback_to(&self, generation: u64) -> Result<(), IndexError> {
+        if self.store.is_some() {
+            return Err(IndexError::NotFound("Сховище індексу не зберігає знімків для відкату".to_string()));
+        }
+        let _lock = self.acquire_update_lock()?;
+
+        let mut manifest = IndexManifest::load(&self.documents_index_path)
+            .ok_or_else(|| IndexError::NotFound("Маніфест індексів не знайдено - знімків немає".to_string()))?;
+
+        let snapshot = manifest
+            .snapshots
+            .iter()
+            .find(|s| s.generation == generation)
+            .cloned()
+            .ok_or_else(|| IndexError::NotFound(format!("Знімок покоління {} не знайдено", generation)))?;
+
+        if manifest.generation == generation {
+            info!("ℹ️ Покоління {} вже активне", generation);
+            return Ok(());
+        }
+
+        // Перевіряємо, що файли знімку цілі, до перемикання маніфесту
+        let doc_index = DocumentIndex::load_from_file(&snapshot.documents_index)
+            .map_err(|e| IndexError::Corrupt(format!("Знімок {} пошкоджено (індекс документів): {}", generation, e)))?;
+        let inv_index = InvertedIndex::load_from_file(&snapshot.inverted_index)
+            .map_err(|e| IndexError::Corrupt(format!("Знімок {} пошкоджено (інвертований індекс): {}", generation, e)))?;
+
+        if !generations_match(doc_index.generation, inv_index.generation) {
+            return Err(IndexError::Corrupt(format!(
+                "Знімок {} пошкоджено: індекси з різних поколінь ({} і {})",
+                generation, doc_index.generation, inv_index.generation
+            )));
+        }
+
+        info!(
+            "⏪ Відкат з покоління {} на покоління {} ({} документів)...",
+            manifest.generation, generation, doc_index.total_documents
+        );
+
+        manifest.generation = snapshot.generation;
+        manifest.documents_index = snapshot.documents_index;
+        manifest.inverted_index = snapshot.inverted_index;
+        manifest.committed_at = unix_now();
+        manifest.store(self.storage.as_ref(), &self.documents_index_path, self.durable_writes)?;
         Ok(())
     }
 
+    /// Видаляє файли поколінь, яких немає серед знімків маніфесту, а також старі файли з фіксованими назвами
+    fn remove_stale_generations(&self, manifest: &IndexManifest) {
+        // Нижчі шари сегментів живих знімків лишаються, хоч їхні покоління вже не в маніфесті
+        let layers: HashSet<String> = manifest
+            .snapshots
+            .iter()
+            .flat_map(|snapshot| &snapshot.inverted_base)
+            .filter_map(|layer| Path::new(layer).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect();
+
+        for base_path in [&self.documents_index_path, &self.inverted_index_path] {
+            let mut stale_files = Vec::new();
+
+            // Файли старого формату (до появи маніфесту)
+            if Path::new(base_path).exists() {
+                stale_files.push(base_path.to_string());
+            }
+
+            let dir = Path::new(base_path)
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+
+            if let Ok(entries) = fs::read_dir(dir) {
+                for entry in entries.filter_map(|e| e.ok()) {
+                    let file_name = entry.file_name().to_string_lossy().to_string();
+                    if let Some(generation) = parse_generation(base_path, &file_name) {
+                        let retained = generation == manifest.generation
+                            || manifest.snapshots.iter().any(|s| s.generation == generation)
+                            || layers.contains(&file_name);
+                        if !retained {
+                            stale_files.push(entry.path().to_string_lossy().to_string());
+                        }
+                    }
+                }
+            }
+
+            for file in stale_files {
+                // Файл може бути тимчасово відкритий пошуковим движком - не критично,
+                // його буде прибрано під час наступного збереження
+                if let Err(e) = self.storage.remove_file(Path::new(&file)) {
+                    warn!("⚠️ Не вдалося видалити застарілий файл {}: {}", file, e);
+                }
+                let _ = self.storage.remove_file(Path::new(&checksum_path(&file)));
+            }
+        }
+    }
+
     /// Виконує повне інкрементне оновлення індексів з атомарним збереженням
     pub fn perform_incremental_update_atomically(
         &self,
         folder_path: &str,
-    ) -> Result<UpdateStats, String> {
+    ) -> Result<UpdateStats, IndexError> {
         let now: DateTime<Local> = Local::now();
         let time_str = now.format("%H:%M:%S").to_string();
-        println!("🚀 [{time_str}] Початок інкрементного оновлення з атомарним збереженням...");
-        
-        // Створюємо lock файл для запобігання одночасному доступу
-        let lock_file_path = "index_update.lock";
-        let lock_file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(lock_file_path)
-            .map_err(|e| format!("Помилка створення lock файлу: {}", e))?;
+        info!("🚀 [{time_str}] Початок інкрементного оновлення з атомарним збереженням...");
         
-        // Намагаємося отримати ексклюзивний lock
-        match lock_file.try_lock_exclusive() {
-            Ok(_) => {
-                println!("🔒 [{time_str}] Отримано ексклюзивний доступ до оновлення індексів");
-            },
-            Err(_) => {
-                return Err("⚠️ Інший процес вже оновлює індекси. Очікуйте завершення.".to_string());
+        // Отримуємо ексклюзивне блокування (з очікуванням, якщо оновлює інший процес).
+        // Lock файл не видаляємо: інакше процес, що вже відкрив старий файл, і процес,
+        // що створив новий, могли б одночасно "отримати" блокування різних файлів.
+        let _lock = self.acquire_update_lock()?;
+
+        self.perform_update_with_lock(folder_path)
+    }
+    
+    /// Повна переіндексація: всі документи парсяться заново, обидва індекси будуються
+    /// з нуля і записуються новим поколінням (попереднє лишається знімком для відкату)
+    pub fn rebuild_from_folder(&self, folder_path: &str) -> Result<UpdateStats, IndexError> {
+        let _lock = self.acquire_update_lock()?;
+
+        let mut processor = self.folder_processor();
+        let doc_index = processor.process_folder_incremental(folder_path, None)?;
+
+        let stats = UpdateStats {
+            processed: processor.processed_files,
+            skipped: processor.skipped_files,
+            deleted: processor.deleted_files,
+            interrupted: false,
+            errors: processor.errors.clone(),
+            delta: None,
+        };
+
+        self.apply_changes(&processor, doc_index, None)?;
+        processor.clear_checkpoint();
+        Ok(stats)
+    }
+
+    /// Індексує лише перелічені файли, без обходу папки (наприклад, список з журналу
+    /// robocopy після ручних виправлень). Відносні шляхи рахуються від folder_path;
+    /// файли поза folder_path відхиляються - наступна індексація папки вважала б їх
+    /// видаленими. Наявний файл додається або оновлюється, відсутній - видаляється з індексу.
+    pub fn index_files(&self, folder_path: &str, files: &[String]) -> Result<UpdateStats, IndexError> {
+        let processor = self.folder_processor();
+        let mut tx = self.begin()?;
+        let mut skipped = 0;
+        let mut errors = Vec::new();
+        let mut interrupted = false;
+        let mut seen = std::collections::HashSet::new();
+
+        for listed in files {
+            let Some(file_path) = resolve_listed_file(folder_path, listed) else {
+                continue;
+            };
+            if !seen.insert(file_path.clone()) {
+                continue;
+            }
+            if self.shutdown_requested() {
+                warn!("⏸️ Індексацію списку перервано зупинкою: оброблені файли буде збережено");
+                interrupted = true;
+                break;
             }
+
+            let path = Path::new(&file_path);
+            if !path.starts_with(folder_path) {
+                errors.push(format!("Файл поза папкою {}: {}", folder_path, file_path));
+                continue;
+            }
+            if !processor.is_docx_file(path) {
+                skipped += 1;
+                continue;
+            }
+
+            let existing = tx.find_by_path(&file_path);
+            if !path.is_file() {
+                match existing {
+                    Some(doc_id) => tx.remove_document(doc_id)?,
+                    None => errors.push(format!("Файл не знайдено: {}", file_path)),
+                }
+                continue;
+            }
+
+            match processor.process_docx_file(&file_path) {
+                Ok(document) => match existing {
+                    Some(doc_id) => tx.update_document(doc_id, document)?,
+                    None => {
+                        tx.add_document(document);
+                    }
+                },
+                Err(e) => errors.push(format!("Помилка обробки {}: {}", file_path, e)),
+            }
+        }
+
+        for error in &errors {
+            warn!("⚠️ {}", error);
+        }
+
+        let mut stats = tx.commit()?;
+        stats.skipped = skipped;
+        stats.errors = errors;
+        stats.interrupted = interrupted;
+        Ok(stats)
+    }
+
+    /// Внутрішня функція для виконання оновлення під lock'ом
+    fn perform_update_with_lock(&self, folder_path: &str) -> Result<UpdateStats, IndexError> {
+        let (existing_doc_index, existing_inv_index) = self.load_active_indices();
+
+        // Виконуємо інкрементну обробку; перервана обробка фіксує вже оброблені файли,
+        // решту наступне оновлення знайде як нові або змінені
+        let mut processor = self.folder_processor();
+        if let Some(shutdown) = &self.shutdown {
+            processor = processor.with_shutdown(shutdown.clone());
+        }
+        let updated_doc_index = processor.process_folder_incremental(folder_path, existing_doc_index)?;
+
+        let mut stats = UpdateStats {
+            processed: processor.processed_files,
+            skipped: processor.skipped_files,
+            deleted: processor.deleted_files,
+            interrupted: processor.interrupted,
+            errors: processor.errors.clone(),
+            delta: None,
+        };
+
+        // Якщо є зміни, оновлюємо індекси атомарно
+        if stats.has_changes() {
+            stats.delta = self.apply_changes(&processor, updated_doc_index, existing_inv_index)?;
+        } else {
+            info!("ℹ️ Зміни не виявлено, індекси залишаються незмінними");
+        }
+        processor.clear_checkpoint();
+
+        Ok(stats)
+    }
+
+    /// Номер активного покоління: версія сховища або покоління з маніфесту
+    fn active_generation(&self) -> Option<u64> {
+        match &self.store {
+            Some(store) => store.generation().ok().flatten(),
+            None => IndexManifest::load(&self.documents_index_path).map(|m| m.generation),
+        }
+    }
+
+    /// Відтворює зміни документів, записані в журнал перед збоєм (див. mutation_journal).
+    /// Викликається тільки під блокуванням. Пакет відтворюється лише поверх покоління, з
+    /// якого його зібрано: якщо покоління вже перемкнуто (збій після маніфесту) або індекси
+    /// відтоді змінено інакше, журнал відкидається. Повертає записане покоління.
+    pub(crate) fn replay_mutation_journal(&self) -> Result<Option<u64>, IndexError> {
+        let journal = MutationJournal::new(&self.documents_index_path).durable(self.durable_writes);
+        if !journal.exists() {
+            return Ok(None);
+        }
+
+        let Some(delta) = journal.pending() else {
+            warn!("⚠️ Журнал змін обірвано до кінця пакета - зміни не потрапили в індекс і будуть знайдені наступною індексацією");
+            journal.clear();
+            return Ok(None);
+        };
+        if self.active_generation() != Some(delta.base_generation) {
+            info!("ℹ️ Зміни з журналу вже записано або індекси змінено інакше - журнал відкинуто");
+            journal.clear();
+            return Ok(None);
+        }
+
+        info!(
+            "🔁 Відтворення журналу змін після збою: {} нових/змінених, {} видалених документів...",
+            delta.changed.len(),
+            delta.deleted.len()
+        );
+        let (mut doc_index, mut inv_index) = self.load_indices()?;
+        if !delta.apply(&mut doc_index, &mut inv_index) {
+            warn!("⚠️ Слоти журналу змін не відповідають індексу - журнал відкинуто");
+            journal.clear();
+            return Ok(None);
+        }
+
+        let mut slots: Vec<usize> = delta.changed.iter().map(|(slot, _)| *slot).collect();
+        slots.extend_from_slice(&delta.deleted);
+        let generation = self.commit(&doc_index, &inv_index, StoreChanges::Slots(slots))?;
+        journal.clear();
+        info!("✅ Зміни з журналу відтворено (покоління {})", generation);
+        Ok(Some(generation))
+    }
+
+    /// Завантажує індекси активного покоління (None - файлу немає або він не читається).
+    /// Викликається тільки під блокуванням: спершу відтворюється журнал змін після збою.
+    pub(crate) fn load_active_indices(&self) -> (Option<DocumentIndex>, Option<InvertedIndex>) {
+        if let Err(e) = self.replay_mutation_journal() {
+            warn!("⚠️ Не вдалося відтворити журнал змін: {}", e);
+        }
+
+        if let Some(store) = &self.store {
+            return match store.load() {
+                Ok(Some((doc_index, inv_index))) => (Some(doc_index), Some(inv_index)),
+                Ok(None) => (None, None),
+                Err(e) => {
+                    warn!("⚠️ Не вдалося завантажити індекси зі сховища: {}", e);
+                    (None, None)
+                }
+            };
         }
-        
-        // Виконуємо оновлення в блоку, щоб гарантувати звільнення lock'у
-        let result = self.perform_update_with_lock(folder_path);
-        
-        // Lock файл буде автоматично розблокований при виході зі scope
-        // Але ми також можемо явно його видалити
-        let _ = fs::remove_file(lock_file_path);
-        
-        result
-    }
-    
-    /// Внутрішня функція для виконання оновлення під lock'ом
-    fn perform_update_with_lock(&self, folder_path: &str) -> Result<UpdateStats, String> {
 
-        let now: DateTime<Local> = Local::now();
-        let _time_str = now.format("%H:%M:%S").to_string();
-        
-        // Завантажуємо існуючі індекси
-        let existing_doc_index = if Path::new(&self.documents_index_path).exists() {
-            match DocumentIndex::load_from_file(&self.documents_index_path) {
+        let (active_doc_path, active_inv_path) = self.active_paths();
+
+        let existing_doc_index = if Path::new(&active_doc_path).exists() {
+            match DocumentIndex::load_from_file(&active_doc_path) {
                 Ok(index) => Some(index),
                 Err(e) => {
-                    println!("⚠️ Не вдалося завантажити існуючий індекс документів: {}", e);
+                    warn!("⚠️ Не вдалося завантажити існуючий індекс документів: {}", e);
+                    if let Some((doc_index, inv_index)) = self.restore_from_backup() {
+                        return (Some(doc_index), Some(inv_index));
+                    }
                     None
                 }
             }
@@ -208,11 +1021,11 @@ impl AtomicIndexManager {
             None
         };
 
-        let existing_inv_index = if Path::new(&self.inverted_index_path).exists() {
-            match InvertedIndex::load_from_file(&self.inverted_index_path) {
+        let existing_inv_index = if Path::new(&active_inv_path).exists() {
+            match InvertedIndex::load_from_file(&active_inv_path) {
                 Ok(index) => Some(index),
                 Err(e) => {
-                    println!("⚠️ Не вдалося завантажити існуючий інвертований індекс: {}", e);
+                    warn!("⚠️ Не вдалося завантажити існуючий інвертований індекс: {}", e);
                     None
                 }
             }
@@ -220,236 +1033,525 @@ impl AtomicIndexManager {
             None
         };
 
-        // Виконуємо інкрементну обробку
-        let mut processor = FolderProcessor::new();
-        let updated_doc_index = processor.process_folder_incremental(folder_path, existing_doc_index)?;
-
-        let stats = UpdateStats {
-            processed: processor.processed_files,
-            skipped: processor.skipped_files,
-            deleted: processor.deleted_files,
+        // Інвертований індекс з іншого оновлення не можна доповнювати інкрементно:
+        // індекс документів - джерело істини, тому перебудовуємо інвертований з нього
+        let existing_inv_index = match (&existing_doc_index, existing_inv_index) {
+            (Some(doc_index), Some(inv_index)) if !generations_match(doc_index.generation, inv_index.generation) => {
+                warn!(
+                    "⚠️ Індекси з різних поколінь (документи: {}, інвертований: {}) - інвертований індекс буде перебудовано",
+                    doc_index.generation, inv_index.generation
+                );
+                Some(InvertedIndex::rebuild_from_scratch(doc_index))
+            }
+            (_, inv_index) => inv_index,
         };
 
-        // Якщо є зміни, оновлюємо індекси атомарно
-        if stats.has_changes() {
-            let update_time: DateTime<Local> = Local::now();
-            let update_time_str = update_time.format("%H:%M:%S").to_string();
-            
-            println!("📊 [{update_time_str}] Зміни виявлено, оновлення індексів...");
-
-            // КРОК 1: СПОЧАТКУ видаляємо записи про видалені файли та коригуємо індекси
-            // ВАЖЛИВО: використовуємо індекси ДО видалення з document_index
-            let mut updated_inv_index = existing_inv_index.unwrap_or_else(|| {
-                println!("⚠️  Створення нового порожнього інвертованого індексу");
-                let mut empty_idx = InvertedIndex::new();
-                empty_idx.total_documents = updated_doc_index.total_documents;
-                empty_idx
-            });
-
-            if !processor.deleted_indices.is_empty() {
-                println!("🗑️  Очищення інвертованого індексу від {} видалених документів (ДО оновлення нових)", processor.deleted_indices.len());
-                updated_inv_index.remove_deleted_documents(&processor.deleted_indices);
-            }
+        (existing_doc_index, existing_inv_index)
+    }
 
-            // КРОК 2: ПОТІМ оновлюємо інвертований індекс для нових/змінених документів
-            // Тепер всі індекси в інвертованому індексі скориговані і відповідають document_index
-            if !processor.new_or_updated_indices.is_empty() {
-                println!("🔄 Оновлення інвертованого індексу для {} нових/змінених документів", processor.new_or_updated_indices.len());
+    /// Відновлює пошкоджений індекс з найновішої цілої датованої резервної копії (див.
+    /// index_backup). Файли активного покоління не перезаписуються: копія записується новим
+    /// поколінням, інвертований індекс перебудовується з неї. Викликається тільки під блокуванням.
+    fn restore_from_backup(&self) -> Option<(DocumentIndex, InvertedIndex)> {
+        for backup in index_backup::list_backups(&self.documents_index_path) {
+            let backup_path = backup.path.to_string_lossy().to_string();
+            let mut doc_index = match DocumentIndex::load_from_file(&backup_path) {
+                Ok(doc_index) => doc_index,
+                Err(e) => {
+                    warn!("⚠️ Резервна копія {} не читається: {}", backup_path, e);
+                    continue;
+                }
+            };
 
-                // Детальний лог документів для відстеження
-                for &idx in &processor.new_or_updated_indices {
-                    if let Some(doc) = updated_doc_index.documents.get(idx) {
-                        println!("   - Документ {}: {}", idx, doc.file_name);
-                    } else {
-                        println!("   - Документ {}: НЕ ЗНАЙДЕНО В DOCUMENT_INDEX!", idx);
-                    }
+            info!("🔄 Відновлення індексу з резервної копії {}...", backup_path);
+            let mut inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+            return match self.commit(&doc_index, &inv_index, StoreChanges::All) {
+                Ok(generation) => {
+                    doc_index.generation = generation;
+                    inv_index.generation = generation;
+                    info!("✅ Індекс відновлено з резервної копії (покоління {})", generation);
+                    Some((doc_index, inv_index))
                 }
+                Err(e) => {
+                    warn!("⚠️ Не вдалося записати відновлений індекс: {}", e);
+                    None
+                }
+            };
+        }
 
-                // Оновлюємо інвертований індекс
-                updated_inv_index.update_incremental(&updated_doc_index, &processor.new_or_updated_indices);
-            }
+        warn!("⚠️ Цілої резервної копії індексу документів немає");
+        None
+    }
+
+    /// Переносить зміни документів (зафіксовані в processor) в інвертований індекс
+    /// і записує нове покоління. Викликається тільки під блокуванням.
+    /// Повертає зміни для оновлення пошукового движка в пам'яті, якщо слоти інших
+    /// документів не зсунулись (див. IndexDelta).
+    pub(crate) fn apply_changes(
+        &self,
+        processor: &FolderProcessor,
+        mut updated_doc_index: DocumentIndex,
+        existing_inv_index: Option<InvertedIndex>,
+    ) -> Result<Option<IndexDelta>, IndexError> {
+        let update_time: DateTime<Local> = Local::now();
+        let update_time_str = update_time.format("%H:%M:%S").to_string();
+
+        info!("📊 [{update_time_str}] Зміни виявлено, оновлення індексів...");
+
+        let inverted_existed = existing_inv_index.is_some();
+        let mut updated_inv_index = existing_inv_index.unwrap_or_else(|| {
+            warn!("⚠️  Створення нового порожнього інвертованого індексу");
+            let mut empty_idx = InvertedIndex::new();
+            empty_idx.total_documents = updated_doc_index.total_documents;
+            empty_idx
+        });
+
+        // КРОК 0: Якщо документи пересортовано за датою - перенумеровуємо існуючі записи
+        if let Some(slot_map) = &processor.slot_map {
+            let removed = updated_inv_index.remap_documents(slot_map);
+            info!("📅 Записи інвертованого індексу перенумеровано після впорядкування за датою (видалено {} застарілих)", removed);
+        }
 
-            // Оновлюємо загальну кількість документів
-            updated_inv_index.total_documents = updated_doc_index.total_documents;
+        // КРОК 1: Видалені файли вже позначено tombstone - їхні записи в інвертованому
+        // індексі лишаються до ущільнення, а слоти інших документів не змінюються
+        if !processor.deleted_indices.is_empty() {
+            info!("🪦 {} документів позначено видаленими (tombstone)", processor.deleted_indices.len());
+        }
 
-            // ❌ ВИМКНЕНО: Повне перебудування занадто повільне і блокує файли
-            // Замість цього використовуємо інкрементне оновлення
-            // println!("🔄 Повне перебудування інвертованого індексу після сортування документів...");
-            // updated_inv_index = InvertedIndex::rebuild_from_scratch(&updated_doc_index);
+        // КРОК 2: Оновлюємо інвертований індекс для нових/змінених документів
+        if !processor.new_or_updated_indices.is_empty() {
+            info!("🔄 Оновлення інвертованого індексу для {} нових/змінених документів", processor.new_or_updated_indices.len());
 
-            // Очищуємо дублікати записів після оновлення
-            let duplicates_removed = updated_inv_index.remove_duplicate_entries();
-            if duplicates_removed > 0 {
-                println!("🧹 Видалено {} дублікатів записів після оновлення індексу", duplicates_removed);
+            // Детальний лог документів для відстеження
+            for &idx in &processor.new_or_updated_indices {
+                if let Some(doc) = updated_doc_index.documents.get(idx) {
+                    info!("   - Документ {}: {}", idx, doc.file_name);
+                } else {
+                    info!("   - Документ {}: НЕ ЗНАЙДЕНО В DOCUMENT_INDEX!", idx);
+                }
             }
 
-            // Атомарно зберігаємо обидва індекси
-            self.save_indices_atomically(&updated_doc_index, &updated_inv_index)?;
-            
-            let end_time: DateTime<Local> = Local::now();
-            let end_time_str = end_time.format("%H:%M:%S").to_string();
-            println!("✅ [{end_time_str}] Інкрементне оновлення завершено успішно!");
+            // Оновлюємо інвертований індекс
+            updated_inv_index.update_incremental(&updated_doc_index, &processor.new_or_updated_indices);
+        }
+
+        // Оновлюємо загальну кількість документів
+        updated_inv_index.total_documents = updated_doc_index.total_documents;
+
+        // Очищуємо дублікати записів після оновлення
+        let duplicates_removed = updated_inv_index.remove_duplicate_entries();
+        if duplicates_removed > 0 {
+            info!("🧹 Видалено {} дублікатів записів після оновлення індексу", duplicates_removed);
+        }
+
+        // Планове ущільнення tombstone-слотів
+        let compaction_due = Self::is_compaction_due(&updated_doc_index);
+        if compaction_due {
+            Self::compact_indices(&mut updated_doc_index, &mut updated_inv_index);
+        }
+
+        // Перестановка і ущільнення зсувають слоти - тоді движок перечитує покоління повністю,
+        // а сховище переписує всі рядки
+        let slots_kept = processor.slot_map.is_none() && !compaction_due;
+        let delta = if slots_kept {
+            IndexDelta::new(&updated_doc_index, &processor.new_or_updated_indices, &processor.deleted_indices)
         } else {
-            println!("ℹ️ Зміни не виявлено, індекси залишаються незмінними");
+            None
+        };
+        let changes = if slots_kept && inverted_existed {
+            let mut slots = processor.new_or_updated_indices.clone();
+            slots.extend_from_slice(&processor.deleted_indices);
+            StoreChanges::Slots(slots)
+        } else {
+            StoreChanges::All
+        };
+
+        // Зміни документів - у журнал до запису покоління, щоб збій посеред збереження
+        // не вимагав повної переіндексації. Невдалий запис покоління лишає журнал для
+        // відтворення наступним оновленням.
+        let journal = MutationJournal::new(&self.documents_index_path).durable(self.durable_writes);
+        if let Some(delta) = &delta {
+            journal.record(delta)?;
         }
 
-        Ok(stats)
+        // Атомарно зберігаємо обидва індекси (блокування вже утримуємо)
+        let generation = self.commit(&updated_doc_index, &updated_inv_index, changes)?;
+        journal.clear();
+
+        let end_time: DateTime<Local> = Local::now();
+        let end_time_str = end_time.format("%H:%M:%S").to_string();
+        info!("✅ [{end_time_str}] Інкрементне оновлення завершено успішно!");
+
+        Ok(delta.map(|delta| delta.with_generation(generation)))
     }
 
-    /// Збереження індексу документів в тимчасовий файл
-    fn save_document_index_to_temp(&self, temp_path: &str, index: &DocumentIndex) -> Result<(), String> {
+    /// Збереження індексу документів у файл покоління (з номером покоління всередині)
+    fn save_document_index_to_file(&self, path: &str, index: &DocumentIndex, generation: u64) -> Result<(), IndexError> {
         use std::io::{BufWriter};
 
-        let file = fs::File::create(temp_path)
-            .map_err(|e| format!("Помилка створення тимчасового файлу індексу документів: {}", e))?;
+        let file = self.storage.create(Path::new(path))
+            .map_err(|e| IndexError::io("Помилка створення файлу індексу документів", e))?;
 
         let writer = BufWriter::with_capacity(1024 * 1024, file); // 1MB буфер
 
-        serde_json::to_writer_pretty(writer, index)
+        let writer = self.compression.write_json(writer, &GenerationStamped { generation, index }, true)
             .map_err(|e| {
-                // Видаляємо пошкоджений тимчасовий файл
-                let _ = fs::remove_file(temp_path);
-                format!("Помилка серіалізації індексу документів: {}", e)
+                // Видаляємо пошкоджений файл
+                let _ = self.storage.remove_file(Path::new(path));
+                IndexError::json("Помилка серіалізації індексу документів", e)
+            })?;
+
+        // Явно скидаємо буфер: помилка запису при drop була б проігнорована
+        writer.into_inner()
+            .map_err(|e| e.into_error())
+            .and_then(|mut file| file.flush())
+            .map_err(|e| {
+                let _ = self.storage.remove_file(Path::new(path));
+                IndexError::io("Помилка запису файлу індексу документів", e)
             })?;
 
         Ok(())
     }
 
-    /// Збереження інвертованого індексу в тимчасовий файл
-    fn save_inverted_index_to_temp(&self, temp_path: &str, index: &InvertedIndex) -> Result<(), String> {
-        let json = serde_json::to_string(index)
-            .map_err(|e| format!("Помилка серіалізації інвертованого індексу: {}", e))?;
+    /// Збереження інвертованого індексу у файл покоління (з номером покоління всередині)
+    fn save_inverted_index_to_file(&self, path: &str, index: &InvertedIndex, generation: u64) -> Result<(), IndexError> {
+        let json = self.compression.to_vec(&GenerationStamped { generation, index })
+            .map_err(|e| IndexError::json("Помилка серіалізації інвертованого індексу", e))?;
 
-        fs::write(temp_path, json)
+        self.storage.write(Path::new(path), &json)
             .map_err(|e| {
-                // Видаляємо пошкоджений тимчасовий файл
-                let _ = fs::remove_file(temp_path);
-                format!("Помилка запису тимчасового файлу інвертованого індексу: {}", e)
+                // Видаляємо пошкоджений файл
+                let _ = self.storage.remove_file(Path::new(path));
+                IndexError::io("Помилка запису файлу інвертованого індексу", e)
             })?;
 
         Ok(())
     }
 
-    /// Відновлення з резервних копій при помилках
-    fn restore_from_backups(&self, backup_doc_path: &str, backup_inv_path: &str) {
-        println!("🔄 Відновлення з резервних копій через помилку...");
-        
-        if Path::new(backup_doc_path).exists() {
-            if let Err(e) = fs::rename(backup_doc_path, &self.documents_index_path) {
-                println!("❌ Помилка відновлення індексу документів: {}", e);
+    /// Перевірка цілісності індексів
+    pub fn validate_indices(&self) -> Result<bool, IndexError> {
+        info!("🔍 Перевірка цілісності індексів...");
+
+        if self.store.is_none() {
+            let (active_doc_path, active_inv_path) = self.active_paths();
+
+            // Перевіряємо існування файлів
+            if !Path::new(&active_doc_path).exists() {
+                return Err(IndexError::NotFound("Файл індексу документів не існує".to_string()));
             }
-        }
-        
-        if Path::new(backup_inv_path).exists() {
-            if let Err(e) = fs::rename(backup_inv_path, &self.inverted_index_path) {
-                println!("❌ Помилка відновлення інвертованого індексу: {}", e);
+
+            if !Path::new(&active_inv_path).exists() {
+                return Err(IndexError::NotFound("Файл інвертованого індексу не існує".to_string()));
             }
         }
-        
-        println!("✅ Відновлення завершено");
+
+        // Завантажуємо та перевіряємо індекси
+        let (mut doc_index, mut inv_index) = self.load_indices()?;
+
+        // Перехресна перевірка та виправлення (див. команду repair)
+        let report = index_repair::repair_indices(&mut doc_index, &mut inv_index);
+
+        // Якщо потрібно виправлення, фіксуємо виправлені індекси як нове покоління
+        if report.has_fixes() {
+            info!("🔧 Виправлення виявлених проблем: {}", report);
+            self.save_indices_atomically(&doc_index, &inv_index)?;
+            info!("✅ Проблеми виправлено та збережено");
+        }
+
+        info!("✅ Індекси валідні та синхронізовані");
+        Ok(true)
+    }
+    
+    /// Чи настав час ущільнення: є tombstone-слоти і минув інтервал або їх забагато
+    fn is_compaction_due(doc_index: &DocumentIndex) -> bool {
+        let tombstones = doc_index.tombstone_count();
+        if tombstones == 0 {
+            return false;
+        }
+
+        let since_last = unix_now().saturating_sub(doc_index.last_compacted_at);
+        let ratio = tombstones as f64 / doc_index.documents.len() as f64;
+
+        since_last >= COMPACTION_INTERVAL.as_secs() || ratio >= COMPACTION_TOMBSTONE_RATIO
     }
 
-    /// Перевірка цілісності індексів
-    pub fn validate_indices(&self) -> Result<bool, String> {
-        println!("🔍 Перевірка цілісності індексів...");
+    /// Прибирає tombstone-слоти і перенумеровує записи інвертованого індексу (O(індексу)).
+    /// Повертає кількість прибраних слотів.
+    fn compact_indices(doc_index: &mut DocumentIndex, inv_index: &mut InvertedIndex) -> usize {
+        let tombstones = doc_index.tombstone_count();
+        info!("🗜️ Ущільнення індексів: {} tombstone-слотів...", tombstones);
+
+        let slot_map = doc_index.compact();
+        let removed_entries = inv_index.remap_documents(&slot_map);
+        inv_index.total_documents = doc_index.total_documents;
+
+        info!("✅ Ущільнення завершено: видалено {} записів інвертованого індексу", removed_entries);
+        tombstones
+    }
+
+    /// Примусове ущільнення індексів (команда compact). Повертає кількість прибраних слотів.
+    pub fn compact(&self) -> Result<usize, IndexError> {
+        let _lock = self.acquire_update_lock()?;
+        let (mut doc_index, mut inv_index) = self.load_indices()?;
 
-        // Перевіряємо існування файлів
-        if !Path::new(&self.documents_index_path).exists() {
-            return Err("Файл індексу документів не існує".to_string());
+        if doc_index.tombstone_count() == 0 {
+            return Ok(0);
         }
 
-        if !Path::new(&self.inverted_index_path).exists() {
-            return Err("Файл інвертованого індексу не існує".to_string());
+        let compacted = Self::compact_indices(&mut doc_index, &mut inv_index);
+        self.commit(&doc_index, &inv_index, StoreChanges::All)?;
+        Ok(compacted)
+    }
+
+    /// Повна перевірка цілісності активного покоління без жодних змін:
+    /// маніфест, контрольні суми, узгодженість індексів, кількість параграфів
+    pub fn check_integrity(&self) -> ValidationReport {
+        if let Some(store) = &self.store {
+            return Self::check_store_integrity(store.as_ref());
         }
 
-        // Завантажуємо та перевіряємо індекси
-        let doc_index = DocumentIndex::load_from_file(&self.documents_index_path)
-            .map_err(|e| format!("Помилка завантаження індексу документів: {}", e))?;
+        let manifest = IndexManifest::load(&self.documents_index_path);
+        let (active_doc_path, active_inv_path) = self.active_paths();
 
-        let mut inv_index = InvertedIndex::load_from_file(&self.inverted_index_path)
-            .map_err(|e| format!("Помилка завантаження інвертованого індексу: {}", e))?;
+        let mut report = ValidationReport {
+            checked_at: unix_now(),
+            generation: manifest.as_ref().map(|m| m.generation),
+            ..Default::default()
+        };
 
-        // Перевіряємо відповідність кількості документів
-        let mut needs_repair = false;
-        if doc_index.total_documents != inv_index.total_documents {
-            println!("⚠️ Невідповідність кількості документів: doc_index={}, inv_index={}", 
-                     doc_index.total_documents, inv_index.total_documents);
-            inv_index.total_documents = doc_index.total_documents;
-            needs_repair = true;
+        let mut files = Vec::new();
+        for path in [&active_doc_path, &active_inv_path] {
+            if !Path::new(path).exists() {
+                files.push(format!("файл не існує: {}", path));
+            }
+        }
+        if manifest.is_none() {
+            // Старе розташування без маніфесту - не помилка, але контрольних сум немає
+            info!("ℹ️ Маніфест індексів відсутній, перевіряються файли з фіксованими назвами");
         }
+        let files_ok = files.is_empty();
+        report.add("files", files);
 
-        // Очищуємо дублікати та невалідні записи
-        let duplicates_removed = inv_index.remove_duplicate_entries();
-        if duplicates_removed > 0 {
-            needs_repair = true;
+        if !files_ok {
+            return report;
         }
-        
-        let cleaned = inv_index.cleanup();
-        if cleaned > 0 {
-            needs_repair = true;
+
+        let mut checksums = Vec::new();
+        if let Some(snapshot) = manifest.as_ref().and_then(|m| m.active_snapshot()) {
+            for (path, expected) in [
+                (&active_doc_path, &snapshot.documents_sha256),
+                (&active_inv_path, &snapshot.inverted_sha256),
+            ] {
+                let Some(expected) = expected else { continue };
+                match file_sha256(path) {
+                    Ok(actual) if &actual == expected => {}
+                    Ok(actual) => checksums.push(format!(
+                        "{}: очікувалось {}, фактично {}",
+                        path, expected, actual
+                    )),
+                    Err(e) => checksums.push(e.to_string()),
+                }
+            }
+        }
+        report.add("checksum", checksums);
+
+        let doc_index = DocumentIndex::load_from_file(&active_doc_path);
+        let inv_index = InvertedIndex::load_from_file(&active_inv_path);
+
+        let mut loading = Vec::new();
+        if let Err(e) = &doc_index {
+            loading.push(format!("індекс документів: {}", e));
         }
+        if let Err(e) = &inv_index {
+            loading.push(format!("інвертований індекс: {}", e));
+        }
+        report.add("load", loading);
 
-        // Якщо потрібно виправлення, зберігаємо оновлений індекс
-        if needs_repair {
-            println!("🔧 Виправлення виявлених проблем інвертованого індексу...");
-            if let Err(e) = inv_index.save_to_file(&self.inverted_index_path) {
-                return Err(format!("Не вдалося зберегти виправлений індекс: {}", e));
+        if let (Ok(mut doc_index), Ok(mut inv_index)) = (doc_index, inv_index) {
+            let mut generations = Vec::new();
+            if !generations_match(doc_index.generation, inv_index.generation) {
+                generations.push(format!(
+                    "індекс документів з покоління {}, інвертований - з покоління {}",
+                    doc_index.generation, inv_index.generation
+                ));
+            }
+            if let Some(manifest) = &manifest {
+                for (name, generation) in [("індекс документів", doc_index.generation), ("інвертований індекс", inv_index.generation)] {
+                    if !generations_match(manifest.generation, generation) {
+                        generations.push(format!(
+                            "{}: покоління у файлі {}, у маніфесті {}",
+                            name, generation, manifest.generation
+                        ));
+                    }
+                }
             }
-            println!("✅ Проблеми виправлено та збережено");
+            report.add("generation", generations);
+
+            // Ремонтний прохід по завантажених копіях - нічого не зберігається
+            let repair = index_repair::repair_indices(&mut doc_index, &mut inv_index);
+            report.add_consistency_checks(&repair);
         }
 
-        println!("✅ Індекси валідні та синхронізовані");
-        Ok(true)
+        report
     }
-    
+
+    /// check_integrity для сховища: замість файлів, маніфесту і контрольних сум - перевірка
+    /// самої бази; покоління обох індексів однакові за побудовою (одна транзакція)
+    fn check_store_integrity(store: &dyn IndexStore) -> ValidationReport {
+        let mut report = ValidationReport {
+            checked_at: unix_now(),
+            generation: store.generation().ok().flatten(),
+            ..Default::default()
+        };
+
+        let problems = store.verify();
+        let store_ok = problems.is_empty();
+        report.add("store", problems);
+        if !store_ok {
+            return report;
+        }
+
+        match store.load() {
+            Ok(Some((mut doc_index, mut inv_index))) => {
+                report.add("load", Vec::new());
+                let repair = index_repair::repair_indices(&mut doc_index, &mut inv_index);
+                report.add_consistency_checks(&repair);
+            }
+            Ok(None) => report.add("load", vec!["сховище порожнє".to_string()]),
+            Err(e) => report.add("load", vec![e.to_string()]),
+        }
+
+        report
+    }
+
+    /// Повний ремонт індексів: перехресна перевірка кожного запису з індексом документів.
+    /// При dry_run лише формує звіт, нічого не зберігаючи.
+    pub fn repair(&self, dry_run: bool) -> Result<RepairReport, IndexError> {
+        let _lock = self.acquire_update_lock()?;
+
+        let mut doc_index = self.load_document_index()?;
+
+        // Якщо інвертований індекс не читається взагалі - ремонтувати нічого, перебудовуємо
+        // Якщо інвертований індекс не читається взагалі або записаний іншим оновленням -
+        // ремонтувати нічого, перебудовуємо
+        let (mut inv_index, inverted_rebuilt) = match self.load_inverted_index() {
+            Ok(inv_index) if generations_match(doc_index.generation, inv_index.generation) => (inv_index, false),
+            Ok(inv_index) => {
+                warn!(
+                    "⚠️ Інвертований індекс з покоління {}, індекс документів - з {}, буде перебудовано",
+                    inv_index.generation, doc_index.generation
+                );
+                (InvertedIndex::rebuild_from_scratch(&doc_index), true)
+            }
+            Err(e) => {
+                warn!("⚠️ Інвертований індекс не завантажено ({}), буде перебудовано", e);
+                (InvertedIndex::rebuild_from_scratch(&doc_index), true)
+            }
+        };
+
+        info!("🔧 Ремонт індексів ({} документів)...", doc_index.documents.len());
+        let mut report = index_repair::repair_indices(&mut doc_index, &mut inv_index);
+        report.inverted_rebuilt = inverted_rebuilt;
+        report.generation = match &self.store {
+            Some(store) => store.generation().ok().flatten(),
+            None => IndexManifest::load(&self.documents_index_path).map(|m| m.generation),
+        };
+        report.dry_run = dry_run;
+
+        if report.has_fixes() && !dry_run {
+            self.commit(&doc_index, &inv_index, StoreChanges::All)?;
+            report.saved = true;
+        }
+
+        Ok(report)
+    }
+
+    /// Повне перебудування інвертованого індексу з активного індексу документів.
+    /// Сегменти пишуться поступово (див. inverted_rebuild), а результат стає активним лише
+    /// через перемикання маніфесту, тож перерваний запуск не погіршує стан індексів -
+    /// наступний продовжить з останнього записаного сегмента.
+    pub fn rebuild_inverted_index(&self) -> Result<RebuildStats, IndexError> {
+        let _lock = self.acquire_update_lock()?;
+
+        let doc_index = self.load_document_index()?;
+        // Сегменти сховища прив'язуються до версії: вона змінюється з кожним записом
+        let source_sha256 = match &self.store {
+            Some(_) => format!("версія {}", doc_index.generation),
+            None => file_sha256(&self.active_paths().0)?,
+        };
+
+        let mut rebuild = SegmentedRebuild::new(
+            sibling_path(&self.documents_index_path, REBUILD_DIR_NAME),
+            self.storage.as_ref(),
+        );
+        rebuild.durable_writes = self.durable_writes;
+
+        let (inv_index, stats) = rebuild.run(&doc_index, &source_sha256)?;
+        self.commit(&doc_index, &inv_index, StoreChanges::All)?;
+        rebuild.clear();
+
+        Ok(stats)
+    }
+
     /// Метод для повного ребілду інвертованого індексу при критичних помилках
-    pub fn rebuild_inverted_index_if_needed(&self) -> Result<bool, String> {
-        println!("🔧 Перевірка необхідності перебудування інвертованого індексу...");
-        
+    pub fn rebuild_inverted_index_if_needed(&self) -> Result<bool, IndexError> {
+        info!("🔧 Перевірка необхідності перебудування інвертованого індексу...");
+
         // Завантажуємо індекс документів
-        let doc_index = DocumentIndex::load_from_file(&self.documents_index_path)
-            .map_err(|e| format!("Помилка завантаження індексу документів: {}", e))?;
+        let doc_index = self.load_document_index()?;
             
         // Спробуємо завантажити інвертований індекс
-        let inv_index_result = InvertedIndex::load_from_file(&self.inverted_index_path);
+        let inv_index_result = self.load_inverted_index();
         
         let should_rebuild = match inv_index_result {
             Ok(inv_index) => {
                 // Перевіряємо критичні невідповідності
                 let docs_count_diff = (doc_index.total_documents as i32 - inv_index.total_documents as i32).abs();
                 if docs_count_diff > 10 {
-                    println!("⚠️ Критична невідповідність кількості документів: різниця {} документів", docs_count_diff);
+                    warn!("⚠️ Критична невідповідність кількості документів: різниця {} документів", docs_count_diff);
                     true
                 } else if inv_index.word_to_docs.is_empty() && doc_index.total_documents > 0 {
-                    println!("⚠️ Інвертований індекс порожній при наявності документів");
+                    warn!("⚠️ Інвертований індекс порожній при наявності документів");
                     true
                 } else {
                     false
                 }
             }
             Err(e) => {
-                println!("⚠️ Критична помилка інвертованого індексу: {}", e);
+                warn!("⚠️ Критична помилка інвертованого індексу: {}", e);
                 true
             }
         };
         
         if should_rebuild {
-            println!("🔄 Повне перебудування інвертованого індексу...");
-            let new_inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
-            
-            // Зберігаємо новий індекс
-            self.save_indices_atomically(&doc_index, &new_inv_index)?;
-            
-            println!("✅ Інвертований індекс успішно перебудовано");
+            let stats = self.rebuild_inverted_index()?;
+            info!("✅ Інвертований індекс успішно перебудовано ({})", stats);
             Ok(true)
         } else {
-            println!("✅ Перебудування не потрібне");
+            info!("✅ Перебудування не потрібне");
             Ok(false)
         }
     }
 
     /// Очищення всіх тимчасових та резервних файлів
+    /// Незавершена транзакція з журналу відкочується, а залишки старої схеми видаляються
     pub fn cleanup_temp_files(&self) {
+        // Журнали можна відкочувати і відтворювати тільки коли ніхто не виконує транзакцію
+        match self.try_acquire_update_lock() {
+            Some(_lock) => {
+                self.recover_pending_transaction();
+                if let Err(e) = self.replay_mutation_journal() {
+                    warn!("⚠️ Не вдалося відтворити журнал змін: {}", e);
+                }
+            }
+            None => {
+                info!("ℹ️ Індекси оновлює інший процес - відновлення журналу пропущено");
+                return;
+            }
+        }
+
+        QueueTicket::remove_stale(&self.lock_queue_dir());
+
         let temp_files = vec![
             format!("{}.atomic_temp", self.documents_index_path),
             format!("{}.atomic_temp", self.inverted_index_path),
@@ -464,20 +1566,42 @@ impl AtomicIndexManager {
         for temp_file in temp_files {
             if Path::new(&temp_file).exists() {
                 if let Err(e) = fs::remove_file(&temp_file) {
-                    println!("⚠️ Не вдалося видалити тимчасовий файл {}: {}", temp_file, e);
+                    warn!("⚠️ Не вдалося видалити тимчасовий файл {}: {}", temp_file, e);
                 } else {
-                    println!("🧹 Видалено тимчасовий файл: {}", temp_file);
+                    info!("🧹 Видалено тимчасовий файл: {}", temp_file);
                 }
             }
         }
     }
 }
 
+/// Шлях з переліку файлів: порожні рядки і рядки з # пропускаються, відносний шлях,
+/// що не починається з folder_path, рахується від folder_path
+fn resolve_listed_file(folder_path: &str, listed: &str) -> Option<String> {
+    let listed = listed.trim();
+    if listed.is_empty() || listed.starts_with('#') {
+        return None;
+    }
+
+    let path = Path::new(listed);
+    if path.is_absolute() || path.starts_with(folder_path) {
+        Some(listed.to_string())
+    } else {
+        Some(Path::new(folder_path).join(path).to_string_lossy().to_string())
+    }
+}
+
 #[derive(Debug)]
 pub struct UpdateStats {
     pub processed: usize,
     pub skipped: usize,
     pub deleted: usize,
+    /// Обробку перервано зупинкою процесу (збережено лише оброблені файли)
+    pub interrupted: bool,
+    /// Файли, які не вдалося обробити (пошкоджені DOCX тощо), з причиною
+    pub errors: Vec<String>,
+    /// Зміни записаного покоління для оновлення движка без повного перезавантаження
+    pub delta: Option<IndexDelta>,
 }
 
 impl UpdateStats {
@@ -492,6 +1616,424 @@ impl std::fmt::Display for UpdateStats {
             f,
             "оброблено: {}, пропущено: {}, видалено: {}",
             self.processed, self.skipped, self.deleted
-        )
+        )?;
+        if !self.errors.is_empty() {
+            write!(f, ", помилок: {}", self.errors.len())?;
+        }
+        if self.interrupted {
+            write!(f, " (перервано зупинкою)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Утримуване блокування оновлення індексів (знімається при drop)
+pub(crate) struct UpdateLock {
+    _file: File,
+}
+
+/// "Квиток" процесу, що чекає на блокування. Позиція в черзі - кількість
+/// квитків, зареєстрованих раніше за наш.
+struct QueueTicket {
+    path: PathBuf,
+    queue_dir: PathBuf,
+}
+
+impl QueueTicket {
+    fn register(queue_dir: &Path) -> Result<Self, IndexError> {
+        fs::create_dir_all(queue_dir)
+            .map_err(|e| IndexError::io("Помилка створення черги блокування", e))?;
+
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path = queue_dir.join(format!("{:024}-{}", nanos, std::process::id()));
+
+        File::create(&path).map_err(|e| IndexError::io("Помилка реєстрації в черзі блокування", e))?;
+        Ok(Self { path, queue_dir: queue_dir.to_path_buf() })
+    }
+
+    /// Позиція в черзі (1 - наступний). Квитки, що давно не оновлювались, вважаються
+    /// залишками процесів, що аварійно завершилися.
+    fn position(&self) -> usize {
+        let own_name = self.path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+        Self::count_live(&self.queue_dir, |name| name < own_name.as_os_str()) + 1
+    }
+
+    /// Скільки процесів зараз чекає в черзі
+    fn waiting(queue_dir: &Path) -> usize {
+        Self::count_live(queue_dir, |_| true)
+    }
+
+    /// Позначає, що процес досі чекає
+    fn touch(&self) {
+        if let Ok(file) = OpenOptions::new().write(true).open(&self.path) {
+            let _ = file.set_modified(std::time::SystemTime::now());
+        }
+    }
+
+    /// Живі квитки, назви яких відповідають фільтру
+    fn count_live(queue_dir: &Path, name_filter: impl Fn(&std::ffi::OsStr) -> bool) -> usize {
+        fs::read_dir(queue_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| name_filter(&e.file_name()))
+                    .filter(|e| {
+                        e.metadata()
+                            .and_then(|m| m.modified())
+                            .map(|t| t.elapsed().unwrap_or_default() <= QUEUE_TICKET_STALE_AFTER)
+                            .unwrap_or(false)
+                    })
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Видаляє квитки процесів, що аварійно завершилися під час очікування
+    fn remove_stale(queue_dir: &Path) {
+        if let Ok(entries) = fs::read_dir(queue_dir) {
+            for entry in entries.filter_map(|e| e.ok()) {
+                let is_stale = entry
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .map(|t| t.elapsed().unwrap_or_default() > DEFAULT_LOCK_WAIT_TIMEOUT * 2)
+                    .unwrap_or(false);
+
+                if is_stale {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+    }
+}
+
+impl Drop for QueueTicket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Опис процесу, що зараз утримує блокування (зі вмісту lock файлу)
+fn lock_holder_info(lock_file_path: &Path) -> String {
+    match fs::read_to_string(lock_file_path) {
+        Ok(content) if !content.trim().is_empty() => content.trim().to_string(),
+        _ => "невідомий процес".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+    use crate::document_record::DocumentRecord;
+    use crate::index_storage::testing::{Fault, FaultyStorage};
+
+    fn indices(texts: &[&str]) -> (DocumentIndex, InvertedIndex) {
+        let mut doc_index = DocumentIndex::new();
+        for (i, text) in texts.iter().enumerate() {
+            doc_index.push_document(DocumentRecord::from_texts(&format!("{}.docx", i), [*text]));
+        }
+        doc_index.recount();
+        let inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+        (doc_index, inv_index)
+    }
+
+    #[test]
+    fn test_lock_waiters_are_served_in_arrival_order() {
+        let dir = TempIndexDir::new("lock_queue");
+        let waiting = dir.manager().with_lock_wait_timeout(Duration::from_secs(1));
+
+        // Блокування вільне, але в черзі вже чекає інший процес - новий його не обганяє
+        let earlier = QueueTicket::register(&waiting.lock_queue_dir()).unwrap();
+        assert!(matches!(dir.manager().acquire_update_lock(), Err(IndexError::Locked(_))));
+        assert!(matches!(waiting.acquire_update_lock(), Err(IndexError::Locked(_))));
+
+        drop(earlier);
+        let lock = waiting.acquire_update_lock().unwrap();
+        assert!(matches!(dir.manager().acquire_update_lock(), Err(IndexError::Locked(_))));
+        drop(lock);
+        assert!(dir.manager().acquire_update_lock().is_ok());
+    }
+
+    #[test]
+    fn test_failed_commit_keeps_previous_generation() {
+        let faults = [
+            (Fault::Rename, "index_manifest.json"),
+            (Fault::DiskFull(16), "inverted_index.2"),
+            (Fault::Create, "documents_index.2"),
+            (Fault::Sync, "documents_index.2"),
+        ];
+
+        for (case, (fault, path_fragment)) in faults.into_iter().enumerate() {
+            let dir = TempIndexDir::new(&format!("storage_test_{}", case));
+
+            let (doc_index, inv_index) = indices(&["наказ"]);
+            dir.manager().save_indices_atomically(&doc_index, &inv_index).unwrap();
+
+            // Збій посеред збереження наступного покоління
+            let faulty = dir.manager().with_storage(Arc::new(FaultyStorage::new().fail(fault, path_fragment)));
+            let (next_doc_index, next_inv_index) = indices(&["наказ", "рапорт"]);
+            assert!(faulty.save_indices_atomically(&next_doc_index, &next_inv_index).is_err(), "{:?}", fault);
+
+            // Активним лишається ціле попереднє покоління, залишків нового немає
+            let active = dir.manager();
+            let manifest = IndexManifest::load(&active.documents_index_path).unwrap();
+            assert_eq!(manifest.generation, 1, "{:?}", fault);
+            let (active_doc_path, _) = active.active_paths();
+            assert_eq!(DocumentIndex::load_from_file(&active_doc_path).unwrap().total_documents, 1);
+            assert!(!dir.join("documents_index.2.json").exists(), "{:?}", fault);
+            assert!(!dir.join("inverted_index.2.json").exists(), "{:?}", fault);
+            assert!(!dir.join("documents_index.2.json.sha256").exists(), "{:?}", fault);
+            assert!(TransactionLog::new(&active.documents_index_path).pending_transaction().is_none());
+
+            // Наступна спроба без збою проходить штатно
+            active.save_indices_atomically(&next_doc_index, &next_inv_index).unwrap();
+            assert_eq!(IndexManifest::load(&active.documents_index_path).unwrap().generation, 2);
+        }
+    }
+
+    #[test]
+    fn test_begin_without_commit_rolled_back_through_storage() {
+        let dir = TempIndexDir::new("wal_recovery_test");
+
+        let (doc_index, inv_index) = indices(&["наказ"]);
+        dir.manager().save_indices_atomically(&doc_index, &inv_index).unwrap();
+
+        // Збій після запису файлів покоління 2, але до перемикання маніфесту
+        let storage = Arc::new(FaultyStorage::new());
+        let recovering = dir.manager().with_storage(storage.clone());
+        let doc_path = generation_path(&recovering.documents_index_path, 2);
+        let inv_path = generation_path(&recovering.inverted_index_path, 2);
+        TransactionLog::new(&recovering.documents_index_path)
+            .append(&WalRecord::Begin {
+                generation: 2,
+                documents_index: doc_path.clone(),
+                inverted_index: inv_path.clone(),
+                timestamp: unix_now(),
+            })
+            .unwrap();
+        let leftovers = [doc_path.clone(), checksum_path(&doc_path), inv_path.clone(), checksum_path(&inv_path)];
+        for path in &leftovers {
+            fs::write(path, "{}").unwrap();
+        }
+
+        recovering.recover_pending_transaction();
+
+        // Файли і контрольні суми покоління видалено через сховище, активним лишається перше
+        let removed = storage.removed();
+        for path in &leftovers {
+            assert!(!Path::new(path).exists(), "{}", path);
+            assert!(removed.contains(&PathBuf::from(path)), "{}", path);
+        }
+        assert!(TransactionLog::new(&recovering.documents_index_path).pending_transaction().is_none());
+        assert_eq!(IndexManifest::load(&recovering.documents_index_path).unwrap().generation, 1);
+    }
+
+    #[test]
+    fn test_mutation_journal_replayed_after_failed_commit() {
+        let dir = TempIndexDir::new("mutation_replay_test");
+
+        let (doc_index, inv_index) = indices(&["наказ"]);
+        dir.manager().save_indices_atomically(&doc_index, &inv_index).unwrap();
+
+        // Документ додано, але запис покоління 2 обривається на перемиканні маніфесту
+        let faulty = dir.manager().with_storage(Arc::new(FaultyStorage::new().fail(Fault::Rename, "index_manifest.json")));
+        let (mut added, _) = indices(&["рапорт"]);
+        added.documents[0].file_path = "1.docx".to_string();
+        let mut tx = faulty.begin().unwrap();
+        tx.add_document(added.documents.remove(0));
+        assert!(tx.commit().is_err());
+        let journal = MutationJournal::new(&faulty.documents_index_path);
+        assert!(journal.pending().is_some());
+
+        // Наступне оновлення відтворює зміни з журналу замість повної переіндексації
+        let manager = dir.manager();
+        let (doc_index, inv_index) = manager.load_active_indices();
+        let doc_index = doc_index.unwrap();
+        assert_eq!(doc_index.generation, 2);
+        assert_eq!(doc_index.total_documents, 2);
+        assert_eq!(doc_index.documents[1].file_path, "1.docx");
+        let postings = &inv_index.unwrap().word_to_docs[&crate::stemmer::stem_word("рапорт")];
+        assert_eq!(postings[0].doc_index, 1);
+        assert_eq!(IndexManifest::load(&manager.documents_index_path).unwrap().generation, 2);
+        assert!(!journal.exists());
+
+        // Журнал, зібраний з уже заміненого покоління, не відтворюється
+        let mut stale = doc_index.clone();
+        stale.generation = 1;
+        journal.record(&IndexDelta::new(&stale, &[0], &[]).unwrap()).unwrap();
+        assert_eq!(manager.replay_mutation_journal().unwrap(), None);
+        assert!(!journal.exists());
+    }
+
+    #[test]
+    fn test_mixed_generations_are_detected_and_repaired() {
+        let dir = TempIndexDir::new("generation_test");
+        let manager = dir.manager();
+
+        let (doc_index, inv_index) = indices(&["наказ"]);
+        manager.save_indices_atomically(&doc_index, &inv_index).unwrap();
+        let (doc_index, inv_index) = indices(&["наказ", "рапорт"]);
+        manager.save_indices_atomically(&doc_index, &inv_index).unwrap();
+
+        // Інвертований індекс попереднього оновлення (цілий, разом з контрольною сумою)
+        // потрапив у активне покоління
+        fs::copy(dir.join("inverted_index.1.json"), dir.join("inverted_index.2.json")).unwrap();
+        fs::copy(dir.join("inverted_index.1.json.sha256"), dir.join("inverted_index.2.json.sha256")).unwrap();
+        let (doc_index, inv_index) = manager.load_active_indices();
+        assert_eq!(doc_index.unwrap().generation, 2);
+        assert!(inv_index.unwrap().word_to_docs.contains_key(&crate::stemmer::stem_word("рапорт")));
+
+        let check = |name: &str| manager.check_integrity().checks.into_iter().find(|c| c.name == name).unwrap();
+        assert!(!check("generation").passed);
+
+        let report = manager.repair(false).unwrap();
+        assert!(report.inverted_rebuilt && report.saved);
+        assert!(check("generation").passed);
+    }
+
+    #[test]
+    fn test_bit_rot_in_generation_file_detected_before_load() {
+        let dir = TempIndexDir::new("checksum_test");
+        let manager = dir.manager();
+
+        let (doc_index, inv_index) = indices(&["наказ"]);
+        manager.save_indices_atomically(&doc_index, &inv_index).unwrap();
+        let (doc_index, inv_index) = indices(&["наказ", "рапорт"]);
+        manager.save_indices_atomically(&doc_index, &inv_index).unwrap();
+        assert!(dir.join("documents_index.2.json.sha256").exists());
+        assert!(dir.join("inverted_index.2.json.sha256").exists());
+
+        // Один біт у назві файлу документа: JSON валідний, але вміст уже не той
+        let path = dir.join("documents_index.2.json");
+        let mut bytes = fs::read(&path).unwrap();
+        let at = bytes.windows(6).position(|w| w == b"1.docx").unwrap();
+        bytes[at] ^= 0x01;
+        fs::write(&path, &bytes).unwrap();
+        let path = path.to_string_lossy().to_string();
+        assert!(matches!(DocumentIndex::load_from_file(&path), Err(IndexError::Corrupt(_))));
+        assert!(!manager.check_integrity().is_healthy());
+
+        // Обрізаний інвертований індекс
+        let path = dir.join("inverted_index.2.json");
+        let len = fs::metadata(&path).unwrap().len();
+        fs::File::options().write(true).open(&path).unwrap().set_len(len - 1).unwrap();
+        assert!(InvertedIndex::load_from_file(&path.to_string_lossy()).is_err());
+
+        // Контрольні суми застарілих поколінь прибираються разом з файлами
+        let manager = manager.with_snapshot_retention(1);
+        manager.save_indices_atomically(&doc_index, &inv_index).unwrap();
+        assert!(!dir.join("documents_index.1.json.sha256").exists());
+        assert!(dir.join("documents_index.3.json.sha256").exists());
+    }
+
+    #[test]
+    fn test_corrupt_generation_restored_from_backup_as_new_generation() {
+        let dir = TempIndexDir::new("backup_restore_test");
+        let manager = dir.manager();
+
+        // Перед записом покоління 2 активне покоління 1 копіюється в index_backups
+        let (doc_index, inv_index) = indices(&["наказ"]);
+        manager.save_indices_atomically(&doc_index, &inv_index).unwrap();
+        let (doc_index, inv_index) = indices(&["наказ", "рапорт"]);
+        manager.save_indices_atomically(&doc_index, &inv_index).unwrap();
+        assert_eq!(index_backup::list_backups(&manager.documents_index_path).len(), 1);
+
+        let corrupt_path = dir.join("documents_index.2.json");
+        fs::write(&corrupt_path, "{").unwrap();
+
+        // Копія записується поколінням 3, пошкоджений файл покоління 2 не перезаписується
+        let (doc_index, inv_index) = manager.load_active_indices();
+        let (doc_index, inv_index) = (doc_index.unwrap(), inv_index.unwrap());
+        assert_eq!((doc_index.total_documents, doc_index.generation, inv_index.generation), (1, 3, 3));
+        assert_eq!(IndexManifest::load(&manager.documents_index_path).unwrap().generation, 3);
+        assert_eq!(fs::read_to_string(&corrupt_path).unwrap(), "{");
+        assert_eq!(DocumentIndex::load_from_file(&manager.active_paths().0).unwrap().total_documents, 1);
+    }
+
+    #[test]
+    fn test_incremental_updates_write_segments_until_merged() {
+        let dir = TempIndexDir::new("segments_test");
+        let manager = dir.manager().with_max_segments(2).with_snapshot_retention(1);
+
+        let (doc_index, inv_index) = indices(&["наказ про відпустку"]);
+        manager.save_indices_atomically(&doc_index, &inv_index).unwrap();
+        assert_eq!(manager.active_segments(), 0);
+
+        // Кожне додавання документа - сегмент лише з його записами поверх попереднього файлу
+        for (generation, text) in [(2, "рапорт про відрядження"), (3, "наказ про премію")] {
+            let (mut added, _) = indices(&[text]);
+            added.documents[0].file_path = format!("{}.docx", generation);
+            let mut tx = manager.begin().unwrap();
+            tx.add_document(added.documents.remove(0));
+            tx.commit().unwrap();
+
+            let segment: InvertedIndex =
+                serde_json::from_slice(&fs::read(dir.join(format!("inverted_index.{}.json", generation))).unwrap()).unwrap();
+            assert_eq!(segment.segment.unwrap().slots, vec![generation as usize - 1]);
+            assert!(segment.word_to_docs.values().flatten().all(|p| p.doc_index == generation as usize - 1));
+        }
+        assert_eq!(manager.active_segments(), 2);
+        assert!(manager.segments_due_for_merge());
+
+        // Нижчі шари лишаються, хоч їхніх поколінь уже немає серед знімків
+        assert!(dir.join("inverted_index.1.json").exists());
+        assert!(!dir.join("documents_index.1.json").exists());
+
+        let (doc_index, inv_index) = manager.load_indices().unwrap();
+        assert_eq!(inv_index.generation, 3);
+        let rebuilt = InvertedIndex::rebuild_from_scratch(&doc_index);
+        for (word, positions) in &rebuilt.word_to_docs {
+            let slots: Vec<usize> = inv_index.word_to_docs[word].iter().map(|p| p.doc_index).collect();
+            assert_eq!(slots, positions.iter().map(|p| p.doc_index).collect::<Vec<_>>(), "{}", word);
+        }
+        assert!(manager.check_integrity().is_healthy());
+
+        // Злиття - нове покоління з повним файлом; старі шари прибираються
+        let delta = manager.merge_segments().unwrap().unwrap();
+        assert_eq!((delta.base_generation, delta.generation), (3, 4));
+        assert!(delta.changed.is_empty() && delta.deleted.is_empty());
+        assert_eq!(manager.active_segments(), 0);
+        assert!(manager.merge_segments().unwrap().is_none());
+        assert!(!dir.join("inverted_index.1.json").exists());
+        let merged = InvertedIndex::load_from_file(&manager.active_paths().1).unwrap();
+        assert_eq!(merged.word_to_docs.len(), inv_index.word_to_docs.len());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_index_files_from_list() {
+        let dir = TempIndexDir::new("files_from_test");
+        let cache = dir.join("cache").to_string_lossy().to_string();
+        let manager = dir.manager();
+
+        let (mut doc_index, _) = indices(&["наказ", "рапорт"]);
+        for (i, document) in doc_index.documents.iter_mut().enumerate() {
+            document.file_path = format!("{}/{}.docx", cache, i);
+        }
+        let inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+        manager.save_indices_atomically(&doc_index, &inv_index).unwrap();
+
+        // Файлу 0.docx у кеші вже немає - його запис видаляється; решта рядків списку
+        // не чіпає індекс: коментар, повтор, невідомий файл, файл поза кешем, не DOCX
+        let files = vec![
+            "# виправлено вручну".to_string(),
+            "0.docx".to_string(),
+            String::new(),
+            format!("{}/0.docx\r", cache),
+            "9.docx".to_string(),
+            "/elsewhere/1.docx".to_string(),
+            "опис.txt".to_string(),
+        ];
+        let stats = manager.index_files(&cache, &files).unwrap();
+        assert_eq!((stats.processed, stats.deleted, stats.skipped), (0, 1, 1));
+        assert_eq!(stats.errors.len(), 2, "{:?}", stats.errors);
+
+        let (doc_index, _) = manager.load_active_indices();
+        let doc_index = doc_index.unwrap();
+        assert_eq!(doc_index.total_documents, 1);
+        assert!(doc_index.documents.iter().any(|d| !d.deleted && d.file_path.ends_with("/1.docx")));
+        assert_eq!(IndexManifest::load(&manager.documents_index_path).unwrap().generation, 2);
+    }
+}
@@ -1,56 +1,125 @@
 use crate::atomic_index_manager::{AtomicIndexManager, UpdateStats};
+use crate::config::Config;
+use crate::error::{IndexError, SearchError, SyncError};
+use crate::failure_alerts::{AlertNotifier, FailureTracker, Mailer};
+use crate::saved_queries::{self, NotificationPolicy, SavedQueryNotifier, SavedQueryStore};
 use crate::search_engine::SearchEngine;
+use crate::shutdown::Shutdown;
+use crate::sync_recovery::{self, CopyJournal};
+use crate::sync_report::{self, SyncReport};
+use crate::tr;
 use chrono::{DateTime, Local};
+use log::{error, info, warn};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::time::interval;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, interval_at, Instant};
 
 pub struct AutoIndexer {
     folder_path: String,      // Мережева папка \\salem\Documents\Наказі
     local_cache_path: String, // Локальна копія файлів
-    index_file_path: String,
-    inverted_index_path: String,
+    sync_history_path: String,
+    sync_journal_path: String,
+    sync_interval: Duration,
+    /// Налаштування менеджера індексів (збереження, резервні копії, виключені папки)
+    config: Config,
     search_engine: Arc<SearchEngine>,
+    /// Запит зупинки: між циклами зупиняє індексер, під час індексації - обробку папки
+    /// після чергового файлу (оброблене фіксується атомарно)
+    shutdown: Option<Shutdown>,
+    /// Перезавантажена конфігурація: новий інтервал перевірки діє з наступного циклу
+    config_updates: Option<watch::Receiver<Config>>,
+    /// Збережені запити, які перевіряються по нових і змінених документах після індексації
+    saved_queries: Option<Arc<SavedQueryStore>>,
 }
 
 impl AutoIndexer {
-    pub fn new(search_engine: Arc<SearchEngine>) -> Self {
+    pub fn new(search_engine: Arc<SearchEngine>, config: &Config) -> Self {
+        let paths = config.index_paths();
         Self {
-            folder_path: "/mnt/salem-documents/Накази".to_string(),
-            // folder_path: "C:\\Users\\vladr\\Desktop\\НАКАЗИ\\".to_string(),
-            local_cache_path: "./nakazi_cache".to_string(),
-            index_file_path: "documents_index.json".to_string(),
-            inverted_index_path: "inverted_index.json".to_string(),
+            folder_path: config.paths.remote_folder.clone(),
+            local_cache_path: config.paths.local_cache.clone(),
+            sync_history_path: paths.sync_history(),
+            sync_journal_path: paths.sync_journal(),
+            sync_interval: Duration::from_secs(config.indexing.sync_interval_secs),
+            config: config.clone(),
             search_engine,
+            shutdown: None,
+            config_updates: None,
+            saved_queries: None,
         }
     }
 
-    pub async fn start_background_indexing(&self) {
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    pub fn with_config_updates(mut self, updates: watch::Receiver<Config>) -> Self {
+        self.config_updates = Some(updates);
+        self
+    }
+
+    pub fn with_saved_queries(mut self, saved_queries: Arc<SavedQueryStore>) -> Self {
+        self.saved_queries = Some(saved_queries);
+        self
+    }
+
+    /// Запускає фонові цикли; задача завершується після запиту зупинки (with_shutdown)
+    pub async fn start_background_indexing(&self) -> JoinHandle<()> {
         let folder_path = self.folder_path.clone();
         let local_cache_path = self.local_cache_path.clone();
-        let index_file_path = self.index_file_path.clone();
-        let inverted_index_path = self.inverted_index_path.clone();
+        let mut sync_interval = self.sync_interval;
+        let config = self.config.clone();
+        let sync_history_path = self.sync_history_path.clone();
+        let journal = CopyJournal::new(&self.sync_journal_path);
         let search_engine = Arc::clone(&self.search_engine);
+        let shutdown = self.shutdown.clone();
+        let mut config_updates = self.config_updates.clone();
+        let saved_queries = self.saved_queries.clone();
 
         tokio::spawn(async move {
-            let mut interval_timer = interval(Duration::from_secs(180)); //оновлення наказів
+            let mut interval_timer = interval(sync_interval); //оновлення наказів
             let mut first_run = true;
+            // Канали перевірено в Config::validate
+            let notifier = AlertNotifier::from_config(&config.alerts).ok().flatten().map(Arc::new);
+            let mut failure_tracker = FailureTracker::new(&config.alerts);
+            let saved_query_notifier =
+                Arc::new(SavedQueryNotifier::new(
+                    Mailer::from_config(&config.alerts).ok().flatten(),
+                    NotificationPolicy::from_config(&config.alerts),
+                ));
+            let redactor = config.redaction_policy().redactor();
+            // Фонове злиття сегментів утримує блокування оновлення
+            let mut segment_merge: Option<JoinHandle<()>> = None;
 
             loop {
-                interval_timer.tick().await;
+                tokio::select! {
+                    _ = interval_timer.tick() => {}
+                    _ = shutdown_requested(&shutdown) => {
+                        info!("{}", tr!(AutoIndexerStopped));
+                        break;
+                    }
+                    updated = sync_interval_changed(&mut config_updates, sync_interval) => {
+                        // Новий інтервал відлічується від перезавантаження конфігурації
+                        sync_interval = updated;
+                        interval_timer = interval_at(Instant::now() + sync_interval, sync_interval);
+                        continue;
+                    }
+                }
 
                 let now: DateTime<Local> = Local::now();
                 let time_str = now.format("%H:%M:%S").to_string();
+                // Збої циклу і файли з помилками обробки - для сповіщень
+                let mut cycle_failures = Vec::new();
+                let mut parse_errors = None;
 
                 if first_run {
-                    println!("");
-                    println!(
-                        "🚀 [{time_str}] Запуск автоматичної перевірки файлів кожні 180 секунд..."
-                    );
+                    info!("{}", tr!(AutoCheckStarting, time_str, sync_interval.as_secs()));
                     first_run = false;
                 } else {
-                    println!("");
-                    println!("🔄 [{time_str}] Автоматична перевірка файлів...");
+                    info!("{}", tr!(AutoCheckRunning, time_str));
                 }
 
                 // КРОК 1: Перевіряємо чи є зміни на сервері (для синхронізації)
@@ -59,70 +128,102 @@ impl AutoIndexer {
                 {
                     Ok(has_changes) => {
                         if has_changes {
-                            println!(
-                                "📥 [{time_str}] Виявлено зміни на сервері - копіюємо файли..."
-                            );
+                            info!("{}", tr!(RemoteChangesDetected, time_str));
                         } else {
                             let end_time_str = Local::now().format("%H:%M:%S").to_string();
-                            println!(
-                                "ℹ️ [{end_time_str}] Змін на сервері не виявлено - пропускаємо копіювання"
-                            );
+                            info!("{}", tr!(RemoteUnchanged, end_time_str));
                         }
                         has_changes
                     }
-                    Err(e) => {
+                    Err(SyncError::ShareOffline(remote)) => {
                         // 🔒 ОФЛАЙН-РЕЖИМ: Мережа недоступна
                         let end_time_str = Local::now().format("%H:%M:%S").to_string();
-                        println!("⚠️ [{end_time_str}] {}", e);
-                        println!("💡 [{end_time_str}] Працюємо в офлайн-режимі з локальним кешем");
+                        warn!("{}", tr!(OfflineMode, end_time_str, remote));
+                        info!("{}", tr!(OfflineHint, end_time_str));
                         false // Не синхронізуємо, але продовжуємо перевіряти індекс
                     }
+                    Err(e) => {
+                        let end_time_str = Local::now().format("%H:%M:%S").to_string();
+                        warn!("{}", tr!(RemoteCheckFailed, end_time_str, e));
+                        cycle_failures.push(e.to_string());
+                        false
+                    }
                 };
 
                 // КРОК 2: Копіюємо файли з сервера ТІЛЬКИ якщо є зміни
                 if should_sync {
-                    if let Err(e) = Self::sync_to_local_cache(&folder_path, &local_cache_path).await
-                    {
-                        let end_time_str = Local::now().format("%H:%M:%S").to_string();
-                        println!("❌ [{end_time_str}] Помилка копіювання: {e}");
+                    let report = Self::sync_to_local_cache(&folder_path, &local_cache_path, &journal).await;
+                    let end_time_str = Local::now().format("%H:%M:%S").to_string();
+
+                    if report.aborted {
                         // Не продовжуємо цикл - перевіримо індекс нижче
+                        error!("{}", tr!(CopyFailed, end_time_str, report.errors.join("; ")));
+                        cycle_failures.push(report.errors.join("; "));
+                    } else {
+                        info!("{}", tr!(SyncCompleted, end_time_str, report));
                     }
+
+                    if let Err(e) = sync_report::append_to_history(&sync_history_path, &report) {
+                        warn!("{}", tr!(SyncReportSaveFailed, end_time_str, e));
+                    }
+                }
+
+                // Синхронізацію завершено; нову індексацію під час зупинки не починаємо
+                if shutdown.as_ref().is_some_and(Shutdown::is_requested) {
+                    info!("{}", tr!(AutoIndexerStopped));
+                    break;
                 }
 
                 // КРОК 3: ЗАВЖДИ перевіряємо чи кеш синхронізований з індексом
                 // Це захищає від ситуації коли копіювання відбулося, але індексування перервалося
                 let cache_needs_indexing = match Self::check_cache_vs_index(
                     &local_cache_path,
-                    &index_file_path,
+                    &config.index_manager(),
                 )
                 .await
                 {
                     Ok(needs_indexing) => {
                         if needs_indexing {
-                            println!(
-                                "🔍 [{time_str}] Виявлено неіндексовані файли в кеші - запускаємо індексацію..."
-                            );
+                            info!("{}", tr!(UnindexedFilesFound, time_str));
                         } else {
                             let end_time_str = Local::now().format("%H:%M:%S").to_string();
-                            println!(
-                                "✅ [{end_time_str}] Кеш синхронізований з індексом - індексування не потрібне"
-                            );
+                            info!("{}", tr!(CacheInSync, end_time_str));
                         }
                         needs_indexing
                     }
                     Err(e) => {
-                        println!("⚠️ Помилка перевірки кешу vs індекс: {}", e);
+                        warn!("{}", tr!(CacheCheckFailed, e));
                         true // Перестраховуємось - індексуємо
                     }
                 };
 
-                // КРОК 4: Індексуємо ТІЛЬКИ якщо потрібно
-                if cache_needs_indexing {
+                // КРОК 4: Індексуємо ТІЛЬКИ якщо потрібно. Поки триває злиття сегментів, цикл
+                // пропускається: кеш перевіриться знову наступного разу
+                let merge_running = segment_merge.as_ref().is_some_and(|merge| !merge.is_finished());
+                if cache_needs_indexing && merge_running {
+                    info!("{}", tr!(IndexingDeferredForMerge, time_str));
+                } else if cache_needs_indexing {
+                    // Перед парсингом перекопійовуємо файли, обрізані незавершеною синхронізацією
+                    let recovery = sync_recovery::recover_partial_files(&folder_path, &local_cache_path, &journal);
+                    if recovery.found_problems() {
+                        info!("{}", tr!(CacheRecovery, time_str, recovery));
+                        for error in &recovery.errors {
+                            warn!("   ⚠️ {error}");
+                        }
+                    }
+
+                    // Знімок версій документів - щоб після оновлення знайти нові та змінені
+                    let versions_before = saved_queries
+                        .as_ref()
+                        .filter(|store| !store.is_empty())
+                        .map(|_| search_engine.with_indices(|_, doc_index, _| saved_queries::document_versions(doc_index)));
+
                     match Self::perform_incremental_update(
                         &local_cache_path, // 👈 Індексуємо локальні файли з кешу
-                        &index_file_path,
-                        &inverted_index_path,
+                        &config,
                         &search_engine,
+                        shutdown.as_ref(),
+                        &mut segment_merge,
                     )
                     .await
                     {
@@ -131,56 +232,99 @@ impl AutoIndexer {
                             let end_time_str = end_time.format("%H:%M:%S").to_string();
 
                             if stats.has_changes() {
-                                println!(
-                                    "✅ [{end_time_str}] Автоматичне оновлення завершено: {stats}"
-                                );
+                                info!("{}", tr!(AutoUpdateCompleted, end_time_str, stats));
                             } else {
-                                println!("ℹ️ [{end_time_str}] Індексація завершена без змін");
+                                info!("{}", tr!(IndexingNoChanges, end_time_str));
+                            }
+                            parse_errors = Some(stats.errors);
+
+                            if let (Some(store), Some(before)) = (&saved_queries, &versions_before) {
+                                let changed = search_engine
+                                    .with_indices(|_, doc_index, _| saved_queries::changed_documents(before, doc_index));
+                                let found = saved_queries::run(store, &search_engine, changed, redactor.as_deref()).await;
+                                // Доставка не затримує наступний цикл
+                                for hits in found {
+                                    info!("{}", hits.alert().subject);
+                                    let notifier = Arc::clone(&saved_query_notifier);
+                                    tokio::task::spawn_blocking(move || notifier.send(&hits));
+                                }
                             }
                         }
                         Err(e) => {
                             let end_time_str = Local::now().format("%H:%M:%S").to_string();
-                            println!("❌ [{end_time_str}] Помилка індексації: {e}");
+                            error!("{}", tr!(IndexingFailed, end_time_str, e));
+                            cycle_failures.push(e.to_string());
                         }
                     }
                 }
+
+                // Доставка сповіщення (пошта, webhook) не затримує наступний цикл
+                for alert in failure_tracker.finish_cycle(cycle_failures, parse_errors.as_deref()) {
+                    error!("{}", alert.subject);
+                    if let Some(notifier) = &notifier {
+                        let notifier = Arc::clone(notifier);
+                        tokio::task::spawn_blocking(move || notifier.send(&alert));
+                    }
+                }
             }
-        });
+        })
     }
 
     async fn perform_incremental_update(
         folder_path: &str,
-        index_file_path: &str,
-        inverted_index_path: &str,
+        config: &Config,
         search_engine: &Arc<SearchEngine>,
-    ) -> Result<UpdateStats, String> {
+        shutdown: Option<&Shutdown>,
+        segment_merge: &mut Option<JoinHandle<()>>,
+    ) -> Result<UpdateStats, IndexError> {
         // Створюємо атомарний менеджер індексів
-        let index_manager = AtomicIndexManager::new(index_file_path, inverted_index_path);
-
-        // Очищуємо старі тимчасові файли
-        index_manager.cleanup_temp_files();
+        let mut index_manager = config.index_manager();
+        if let Some(shutdown) = shutdown {
+            index_manager = index_manager.with_shutdown(shutdown.clone());
+        }
 
-        // Виконуємо атомарне інкрементне оновлення
-        match index_manager.perform_incremental_update_atomically(folder_path) {
+        // Очікування блокування (до storage.lock_wait_timeout_secs) і сама індексація блокують
+        // потік, тож виконуються поза робочими потоками tokio, які обслуговують пошук
+        let folder_path = folder_path.to_string();
+        let (index_manager, result) = tokio::task::spawn_blocking(move || {
+            // Очищуємо старі тимчасові файли
+            index_manager.cleanup_temp_files();
+
+            // Виконуємо атомарне інкрементне оновлення
+            let result = index_manager.perform_incremental_update_atomically(&folder_path);
+            (index_manager, result)
+        })
+        .await
+        .map_err(|e| IndexError::io(tr!(AtomicUpdateAborted), std::io::Error::other(e)))?;
+
+        match result {
             Ok(stats) => {
                 // Якщо є зміни, оновлюємо SearchEngine
                 if stats.has_changes() {
-                    // Перевіряємо цілісність індексів перед оновленням пошукового движка
-                    if let Err(e) = index_manager.validate_indices() {
-                        println!("⚠️ Попередження при перевірці цілісності індексів: {}", e);
+                    // Перевіряємо цілісність індексів перед повним перезавантаженням движка.
+                    // Дельта змінює лише свої слоти, тож файли заради неї не перечитуємо.
+                    if stats.delta.is_none() {
+                        if let Err(e) = index_manager.validate_indices() {
+                            warn!("{}", tr!(IntegrityWarning, e));
+                        }
                     }
 
                     // Оновлюємо SearchEngine
-                    if let Err(e) = Self::reload_search_engine(search_engine, index_file_path).await
-                    {
-                        println!("⚠️  Помилка оновлення пошукового движка: {}", e);
+                    if let Err(e) = Self::reload_search_engine(search_engine, &stats).await {
+                        warn!("{}", tr!(SearchEngineReloadFailed, e));
                     }
                 }
 
+                // Накопичені сегменти інвертованого індексу зливаються у фоні, не затримуючи
+                // цикл; поки злиття триває, наступні цикли індексацію пропускають
+                if index_manager.segments_due_for_merge() {
+                    *segment_merge = Some(Self::spawn_segment_merge(index_manager, Arc::clone(search_engine)));
+                }
+
                 Ok(stats)
             }
             Err(e) => {
-                println!("❌ Помилка атомарного оновлення: {}", e);
+                error!("{}", tr!(AtomicUpdateFailed, e));
                 // Очищуємо тимчасові файли при помилці
                 index_manager.cleanup_temp_files();
                 Err(e)
@@ -188,13 +332,30 @@ impl AutoIndexer {
         }
     }
 
-    async fn reload_search_engine(
-        search_engine: &Arc<SearchEngine>,
-        index_file_path: &str,
-    ) -> Result<(), String> {
-        // Використовуємо новий метод reload для оновлення існуючого SearchEngine
-        search_engine.reload(index_file_path)?;
-        println!("✅ Пошуковий індекс успішно оновлено в пам'яті");
+    fn spawn_segment_merge(index_manager: AtomicIndexManager, search_engine: Arc<SearchEngine>) -> JoinHandle<()> {
+        tokio::task::spawn_blocking(move || {
+            info!("{}", tr!(SegmentMergeStarted, index_manager.active_segments()));
+            match index_manager.merge_segments() {
+                Ok(Some(delta)) => {
+                    info!("{}", tr!(SegmentsMerged, delta.generation));
+                    // Документи не змінились - движок лише переходить на нове покоління
+                    if let Err(e) = search_engine.apply_delta(&delta) {
+                        warn!("{}", tr!(SearchEngineReloadFailed, e));
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => warn!("{}", tr!(SegmentMergeFailed, e)),
+            }
+        })
+    }
+
+    async fn reload_search_engine(search_engine: &Arc<SearchEngine>, stats: &UpdateStats) -> Result<(), SearchError> {
+        // Змінені документи переносимо в пам'ять напряму, інакше перечитуємо покоління
+        match &stats.delta {
+            Some(delta) => search_engine.apply_delta(delta)?,
+            None => search_engine.reload()?,
+        }
+        info!("{}", tr!(SearchEngineReloaded));
 
         Ok(())
     }
@@ -216,16 +377,13 @@ impl AutoIndexer {
         }
 
         // Пробуємо прочитати вміст папки для перевірки доступу
-        match std::fs::read_dir(path) {
-            Ok(_) => true,
-            Err(_) => false,
-        }
+        std::fs::read_dir(path).is_ok()
     }
 
     /// Збирає метадані файлів (шлях, розмір, дата модифікації) БЕЗ читання вмісту
     /// ВАЖЛИВО: Зберігає ВІДНОСНІ шляхи для коректного порівняння
     /// Фільтрує тільки файли з папок-років
-    fn collect_metadata(path: &str) -> Result<Vec<(String, u64, std::time::SystemTime)>, String> {
+    fn collect_metadata(path: &str) -> Result<Vec<(String, u64, std::time::SystemTime)>, SyncError> {
         use std::path::Path;
         use walkdir::WalkDir;
 
@@ -234,7 +392,7 @@ impl AutoIndexer {
 
         // Перевіряємо, чи існує шлях
         if !base_path.exists() {
-            return Err(format!("Шлях не існує або недоступний: {}", path));
+            return Err(SyncError::ShareOffline(path.to_string()));
         }
 
         for entry in WalkDir::new(path)
@@ -267,10 +425,9 @@ impl AutoIndexer {
     }
 
     /// Перевіряє чи є неіндексовані файли в локальному кеші
-    /// Порівнює файли в nakazi_cache з тими що є в documents_index.json
+    /// Порівнює файли в nakazi_cache з тими що є в індексі документів
     /// Повертає: Ok(true) - потрібно індексувати, Ok(false) - все синхронізовано
-    async fn check_cache_vs_index(cache_path: &str, index_file_path: &str) -> Result<bool, String> {
-        use crate::document_record::DocumentIndex;
+    async fn check_cache_vs_index(cache_path: &str, index_manager: &AtomicIndexManager) -> Result<bool, IndexError> {
         use std::path::Path;
 
         // Якщо кешу немає - нічого індексувати
@@ -283,7 +440,7 @@ impl AutoIndexer {
             Ok(metadata) => metadata,
             Err(e) => {
                 // Помилка читання кешу - краще перестрахуватися та запустити індексацію
-                println!("⚠️  Помилка читання кешу: {}", e);
+                warn!("{}", tr!(CacheReadFailed, e));
                 return Ok(true);
             }
         };
@@ -293,19 +450,19 @@ impl AutoIndexer {
             return Ok(false);
         }
 
-        // Завантажуємо існуючий індекс
-        let existing_index = match DocumentIndex::load_from_file(index_file_path) {
+        // Завантажуємо існуючий індекс (активне покоління з маніфесту або сховища)
+        let existing_index = match index_manager.load_document_index() {
             Ok(index) => index,
             Err(_) => {
                 // Індексу немає - потрібно створити
-                println!("ℹ️  Індекс не знайдено - потрібне повне індексування");
+                info!("{}", tr!(IndexMissingFullIndexing));
                 return Ok(true);
             }
         };
 
         // Створюємо мапу індексованих файлів: шлях → (розмір, час модифікації)
         let mut indexed_files = std::collections::HashMap::new();
-        for doc in &existing_index.documents {
+        for doc in existing_index.documents.iter().filter(|d| !d.deleted) {
             // Отримуємо відносний шлях (прибираємо префікс nakazi_cache/)
             let relative_path = if let Some(rel) = doc.file_path.strip_prefix(cache_path) {
                 rel.trim_start_matches('\\')
@@ -329,13 +486,13 @@ impl AutoIndexer {
                 Some((indexed_size, indexed_modified)) => {
                     // Файл є в індексі - перевіряємо чи він не змінився
                     if cache_size != indexed_size || cache_modified_secs > *indexed_modified {
-                        println!("🔄 Файл змінився: {}", cache_file_path);
+                        info!("{}", tr!(CacheFileChanged, cache_file_path));
                         return Ok(true); // Файл оновлено
                     }
                 }
                 None => {
                     // Файл є в кеші, але немає в індексі!
-                    println!("➕ Новий файл в кеші: {}", cache_file_path);
+                    info!("{}", tr!(CacheFileAdded, cache_file_path));
                     return Ok(true);
                 }
             }
@@ -349,7 +506,7 @@ impl AutoIndexer {
 
         for indexed_file in indexed_files.keys() {
             if !cache_files_set.contains(indexed_file) {
-                println!("➖ Файл видалено з кешу: {}", indexed_file);
+                info!("{}", tr!(CacheFileRemoved, indexed_file));
                 return Ok(true);
             }
         }
@@ -359,17 +516,13 @@ impl AutoIndexer {
     }
 
     /// Швидка перевірка - порівнює метадані без копіювання файлів
-    /// Повертає: Ok(true) - є зміни, Ok(false) - немає змін, Err - мережа недоступна
-    async fn check_for_changes(remote_path: &str, local_cache_path: &str) -> Result<bool, String> {
+    /// Повертає: Ok(true) - є зміни, Ok(false) - немає змін, Err(ShareOffline) - мережа недоступна
+    async fn check_for_changes(remote_path: &str, local_cache_path: &str) -> Result<bool, SyncError> {
         use std::path::Path;
 
         // 🔒 КРИТИЧНА ПЕРЕВІРКА: Чи доступна мережева папка?
         if !Self::is_network_path_accessible(remote_path) {
-            return Err(format!(
-                "🌐 ОФЛАЙН-РЕЖИМ: Мережева папка недоступна: {}\n\
-                 💾 Працюємо з існуючим локальним кешем без оновлень",
-                remote_path
-            ));
+            return Err(SyncError::ShareOffline(remote_path.to_string()));
         }
 
         // Якщо локального кешу немає - потрібно копіювати
@@ -425,15 +578,23 @@ impl AutoIndexer {
     }
 
     /// Синхронізує файли з сервера на локальний диск (копіює нові/оновлені, видаляє застарілі)
-    async fn sync_to_local_cache(remote_path: &str, local_cache_path: &str) -> Result<(), String> {
+    /// Помилки окремих файлів не зупиняють цикл, а потрапляють у звіт
+    async fn sync_to_local_cache(
+        remote_path: &str,
+        local_cache_path: &str,
+        journal: &CopyJournal,
+    ) -> SyncReport {
         use std::collections::HashSet;
         use std::fs;
         use std::path::Path;
         use walkdir::WalkDir;
 
+        let mut report = SyncReport::start(remote_path, local_cache_path);
+
         // Створюємо локальну папку якщо не існує
-        fs::create_dir_all(local_cache_path)
-            .map_err(|e| format!("Помилка створення кешу: {}", e))?;
+        if let Err(e) = fs::create_dir_all(local_cache_path) {
+            return report.abort(format!("Помилка створення кешу: {}", e));
+        }
 
         // Збираємо список всіх файлів на сервері
         let mut remote_files = HashSet::new();
@@ -446,9 +607,13 @@ impl AutoIndexer {
         {
             if entry.file_type().is_file() {
                 let remote_file = entry.path();
-                let relative_path = remote_file
-                    .strip_prefix(remote_path)
-                    .map_err(|e| format!("Помилка шляху: {}", e))?;
+                let relative_path = match remote_file.strip_prefix(remote_path) {
+                    Ok(relative_path) => relative_path,
+                    Err(e) => {
+                        report.errors.push(format!("Помилка шляху: {}", e));
+                        continue;
+                    }
+                };
 
                 // Фільтруємо файли - тільки папки з роками
                 if !Self::should_sync_file(relative_path) {
@@ -484,14 +649,20 @@ impl AutoIndexer {
                 if should_copy {
                     // Створюємо підпапки якщо потрібно
                     if let Some(parent) = local_file.parent() {
-                        fs::create_dir_all(parent)
-                            .map_err(|e| format!("Помилка створення папки: {}", e))?;
+                        if let Err(e) = fs::create_dir_all(parent) {
+                            report.errors.push(format!("Помилка створення папки: {}", e));
+                            continue;
+                        }
                     }
 
-                    // Копіюємо файл
-                    fs::copy(remote_file, &local_file).map_err(|e| {
-                        format!("Помилка копіювання {}: {}", remote_file.display(), e)
-                    })?;
+                    // Копіюємо файл (через журнал і тимчасовий .partial файл)
+                    match sync_recovery::copy_with_journal(journal, remote_file, &local_file, relative_path) {
+                        Ok(bytes) => {
+                            report.files_copied += 1;
+                            report.bytes_transferred += bytes;
+                        }
+                        Err(e) => report.errors.push(e.to_string()),
+                    }
                 }
             }
         }
@@ -504,19 +675,52 @@ impl AutoIndexer {
         {
             if entry.file_type().is_file() {
                 let local_file = entry.path();
-                let relative_path = local_file
-                    .strip_prefix(local_cache_path)
-                    .map_err(|e| format!("Помилка шляху: {}", e))?;
+                let relative_path = match local_file.strip_prefix(local_cache_path) {
+                    Ok(relative_path) => relative_path,
+                    Err(e) => {
+                        report.errors.push(format!("Помилка шляху: {}", e));
+                        continue;
+                    }
+                };
 
                 // Якщо файлу немає на сервері - видаляємо
                 if !remote_files.contains(relative_path) {
-                    fs::remove_file(local_file).map_err(|e| {
-                        format!("Помилка видалення {}: {}", local_file.display(), e)
-                    })?;
+                    match fs::remove_file(local_file) {
+                        Ok(_) => report.files_deleted += 1,
+                        Err(e) => report.errors.push(format!(
+                            "Помилка видалення {}: {}",
+                            local_file.display(),
+                            e
+                        )),
+                    }
                 }
             }
         }
 
-        Ok(())
+        report.finish()
+    }
+}
+
+/// Завершується, коли перезавантажена конфігурація змінює інтервал перевірки
+async fn sync_interval_changed(updates: &mut Option<watch::Receiver<Config>>, current: Duration) -> Duration {
+    let Some(updates) = updates else {
+        return std::future::pending().await;
+    };
+    loop {
+        if updates.changed().await.is_err() {
+            return std::future::pending().await;
+        }
+        let updated = Duration::from_secs(updates.borrow_and_update().indexing.sync_interval_secs);
+        if updated != current {
+            return updated;
+        }
+    }
+}
+
+/// Завершується, коли запитано зупинку (без with_shutdown - ніколи)
+async fn shutdown_requested(shutdown: &Option<Shutdown>) {
+    match shutdown {
+        Some(shutdown) => shutdown.wait().await,
+        None => std::future::pending().await,
     }
 }
@@ -0,0 +1,129 @@
+//! Класифікація наказів за видом (кадровий, відрядження, відпустки, стягнення ...)
+//! під час індексації. Правила - [[classification.categories]]: ключові слова шукаються
+//! в усьому тексті, а заголовки розділів - лише на початку абзаців ("Про відрядження",
+//! "По особовому складу") і важать більше. Документ отримує категорію з найбільшою
+//! вагою; за рівної ваги - ту, що вище в списку. Після зміни правил потрібен rebuild.
+
+use crate::config::CategoryRuleConfig;
+use crate::document_record::Paragraph;
+use crate::search_engine::SearchEngineResult;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// Вага абзацу, що починається заголовком розділу категорії (ключове слово - 1)
+const SECTION_WEIGHT: usize = 3;
+
+/// Нумерація і лапки на початку абзацу: "3. ", "«2.1. "
+static LEADING_NUMBERING_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[\s«]*(?:\d+(?:\.\d+)*\.?\s*)?").unwrap());
+
+/// Вбудовані правила (діють, якщо в конфігурації не задано власних)
+pub fn default_categories() -> Vec<CategoryRuleConfig> {
+    let rule = |name: &str, keywords: &[&str], sections: &[&str]| CategoryRuleConfig {
+        name: name.to_string(),
+        keywords: keywords.iter().map(|k| k.to_string()).collect(),
+        sections: sections.iter().map(|s| s.to_string()).collect(),
+    };
+    vec![
+        rule("відпустки", &["відпустк"], &["про відпустк", "відпустки"]),
+        rule("відрядження", &["відрядженн", "відрядит"], &["про відрядження", "відрядження"]),
+        rule("стягнення", &["стягненн", "догану", "догана", "дисциплінарн"], &["про притягнення", "про накладення"]),
+        rule("заохочення", &["заохоченн", "оголосити подяку", "нагородит", "преміюват"], &["про заохочення"]),
+        rule(
+            "кадровий",
+            &["призначити на посаду", "звільнити", "зарахувати до списків", "виключити зі списків", "прийняти на посаду"],
+            &["по особовому складу", "з особового складу"],
+        ),
+    ]
+}
+
+/// Правила категорій у нижньому регістрі
+pub struct Classifier {
+    rules: Vec<CategoryRuleConfig>,
+}
+
+impl Classifier {
+    pub fn new(rules: &[CategoryRuleConfig]) -> Self {
+        let lowercase = |phrases: &[String]| phrases.iter().map(|p| p.trim().to_lowercase()).filter(|p| !p.is_empty()).collect();
+        Self {
+            rules: rules
+                .iter()
+                .map(|rule| CategoryRuleConfig {
+                    name: rule.name.clone(),
+                    keywords: lowercase(&rule.keywords),
+                    sections: lowercase(&rule.sections),
+                })
+                .collect(),
+        }
+    }
+
+    /// Категорія документа; None - жодне правило не спрацювало
+    pub fn classify(&self, paragraphs: &[Paragraph]) -> Option<String> {
+        let paragraphs: Vec<String> = paragraphs.iter().map(|p| p.text.to_lowercase()).collect();
+
+        let mut best: Option<(&str, usize)> = None;
+        for rule in &self.rules {
+            let score: usize = paragraphs
+                .iter()
+                .map(|text| {
+                    let heading = LEADING_NUMBERING_REGEX.replace(text, "");
+                    if rule.sections.iter().any(|s| heading.starts_with(s.as_str())) {
+                        SECTION_WEIGHT
+                    } else {
+                        rule.keywords.iter().filter(|k| text.contains(k.as_str())).count()
+                    }
+                })
+                .sum();
+            if score > 0 && best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((&rule.name, score));
+            }
+        }
+        best.map(|(name, _)| name.to_string())
+    }
+}
+
+/// Кількість знайдених документів кожної категорії (документи без категорії не враховуються)
+pub fn category_counts(results: &[SearchEngineResult]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for category in results.iter().filter_map(|r| r.category.as_ref()) {
+        *counts.entry(category.clone()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Лишає документи вказаної категорії; None або порожній рядок - без фільтра
+pub fn filter_by_category(results: &mut Vec<SearchEngineResult>, category: Option<&str>) {
+    if let Some(category) = category.map(str::trim).filter(|c| !c.is_empty()) {
+        results.retain(|r| r.category.as_deref() == Some(category));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraphs(texts: &[&str]) -> Vec<Paragraph> {
+        texts.iter().map(|t| Paragraph::new(t.to_string())).collect()
+    }
+
+    #[test]
+    fn test_heaviest_category_wins() {
+        let classifier = Classifier::new(&default_categories());
+
+        let leave = paragraphs(&["НАКАЗ", "1. Надати солдату Петренку П.П. щорічну основну відпустку", "з 05.02.2024"]);
+        assert_eq!(classifier.classify(&leave).as_deref(), Some("відпустки"));
+
+        // Заголовок розділу важить більше за ключове слово в тексті
+        let trip = paragraphs(&[
+            "«2. Про відрядження",
+            "Відрядити сержанта Іваненка І.І. до м. Києва після повернення з відпустки",
+        ]);
+        assert_eq!(classifier.classify(&trip).as_deref(), Some("відрядження"));
+
+        let staff = paragraphs(&["ПО ОСОБОВОМУ СКЛАДУ", "Призначити на посаду стрільця"]);
+        assert_eq!(classifier.classify(&staff).as_deref(), Some("кадровий"));
+
+        assert_eq!(classifier.classify(&paragraphs(&["Про організацію чергування"])), None);
+        assert!(Classifier::new(&[]).classify(&leave).is_none());
+    }
+}
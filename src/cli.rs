@@ -0,0 +1,272 @@
+use blazing_search::config::Config;
+use blazing_search::error::ConfigError;
+use blazing_search::near_duplicates::DEFAULT_MAX_DISTANCE;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+/// Пошук по наказах: веб-сервер, індексація та обслуговування індексів.
+/// Налаштування беруться з blazing_search.toml; параметри командного рядка мають пріоритет.
+#[derive(Parser, Debug, Clone)]
+#[command(name = "blazing_SEARCH", version, arg_required_else_help = true)]
+pub struct Cli {
+    /// Файл конфігурації (за замовчуванням blazing_search.toml у робочій папці, якщо є)
+    #[arg(long, global = true, value_name = "ФАЙЛ")]
+    pub config: Option<PathBuf>,
+
+    /// Профіль з файлу конфігурації ([profiles.<НАЗВА>]), наприклад prod або test
+    /// (за замовчуванням - змінна BLAZING_PROFILE, якщо задана)
+    #[arg(long, global = true, value_name = "НАЗВА")]
+    pub profile: Option<String>,
+
+    /// Папка з індексами, маніфестом, блокуванням, резервними копіями та звітами
+    #[arg(long, global = true, value_name = "ПАПКА")]
+    pub index_dir: Option<PathBuf>,
+
+    /// Колекція ([[collections]] або основна) для індексації, пошуку та обслуговування;
+    /// serve обслуговує всі колекції
+    #[arg(long, global = true, value_name = "НАЗВА")]
+    pub collection: Option<String>,
+
+    /// Рівень журналу: error, warn, info, debug, trace або специфікація з модулями,
+    /// наприклад "info,blazing_search::folder_processor=debug" (має пріоритет над BLAZING_LOGGING_LEVEL і RUST_LOG)
+    #[arg(long, global = true, value_name = "РІВЕНЬ")]
+    pub log_level: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Звідки брати документи для індексації
+#[derive(clap::Args, Debug, Clone)]
+pub struct SourceArgs {
+    /// Мережева папка з документами
+    #[arg(long, value_name = "ПАПКА")]
+    pub remote: Option<String>,
+
+    /// Локальний кеш, в який копіюються документи перед індексацією
+    #[arg(long, value_name = "ПАПКА")]
+    pub cache: Option<String>,
+}
+
+impl SourceArgs {
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(remote) = &self.remote {
+            config.paths.remote_folder = remote.clone();
+        }
+        if let Some(cache) = &self.cache {
+            config.paths.local_cache = cache.clone();
+        }
+    }
+}
+
+impl Cli {
+    /// Накладає параметри командного рядка на завантажену конфігурацію
+    /// (помилка - невідома колекція)
+    pub fn apply(&self, config: &mut Config) -> Result<(), ConfigError> {
+        if let (Some(collection), false) = (&self.collection, matches!(self.command, Command::Serve { .. })) {
+            config.select_collection(collection)?;
+        }
+        if let Some(index_dir) = &self.index_dir {
+            config.paths.index_dir = index_dir.clone();
+        }
+        if let Some(log_level) = &self.log_level {
+            config.logging.level = log_level.clone();
+        }
+
+        match &self.command {
+            Command::Serve { port, pid_file, .. } => {
+                if let Some(port) = port {
+                    config.server.port = *port;
+                }
+                if let Some(pid_file) = pid_file {
+                    config.server.pid_file = Some(pid_file.clone());
+                }
+            }
+            Command::Index { source, .. } | Command::Rebuild { source, .. } => source.apply(config),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Веб-інтерфейс пошуку з фоновою індексацією
+    #[command(alias = "web")]
+    Serve {
+        /// Порт веб-сервера
+        #[arg(long)]
+        port: Option<u16>,
+
+        /// Перейти у фон (класичний демон; під systemd з Type=notify не потрібно)
+        #[arg(long)]
+        daemon: bool,
+
+        /// Записати PID процесу в файл (видаляється при зупинці)
+        #[arg(long, value_name = "ФАЙЛ")]
+        pid_file: Option<PathBuf>,
+    },
+
+    /// Синхронізує кеш з мережевою папкою та інкрементно оновлює індекси
+    Index {
+        #[command(flatten)]
+        source: SourceArgs,
+
+        /// Індексувати лише файли зі списку (по шляху в рядку, "-" - зі стандартного входу)
+        /// без синхронізації і обходу папки; відносні шляхи - від локального кешу
+        #[arg(long, value_name = "ФАЙЛ", conflicts_with = "remote")]
+        files_from: Option<PathBuf>,
+    },
+
+    /// Пошук з командного рядка
+    Search {
+        /// Пошуковий запит
+        #[arg(required = true, num_args = 1..)]
+        query: Vec<String>,
+
+        /// Повний пошук (всі документи, а не лише найновіші)
+        #[arg(long)]
+        full: bool,
+
+        /// Скільки документів показати
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Термінальний інтерфейс пошуку (для роботи на сервері через SSH/RDP без браузера)
+    Tui,
+
+    /// Повна переіндексація: всі документи парсяться заново, індекси будуються з нуля
+    Rebuild {
+        /// Лише перебудувати інвертований індекс з активного індексу документів
+        /// (без синхронізації і парсингу; перерваний запуск продовжується)
+        #[arg(long, conflicts_with_all = ["remote", "cache"])]
+        inverted: bool,
+
+        #[command(flatten)]
+        source: SourceArgs,
+    },
+
+    /// Перевіряє цілісність індексів без змін (код виходу 1 при проблемах)
+    Validate {
+        /// Додатково зберегти звіт у JSON
+        #[arg(long, value_name = "ФАЙЛ")]
+        report: Option<String>,
+    },
+
+    /// Статистика індексів: документи за роками, слова, розміри файлів, час індексації
+    Stats {
+        /// Вивести у JSON замість таблиці
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Словник індексу: терміни з кількістю документів (для списків стоп-слів і синонімів,
+    /// пошуку сміттєвих токенів)
+    Terms {
+        /// Скільки термінів вивести
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+
+        /// Найрідкісніші терміни замість найчастіших
+        #[arg(long)]
+        rarest: bool,
+
+        /// Вивести у JSON замість таблиці
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Групи майже однакових документів (той самий наказ під різними назвами)
+    Duplicates {
+        /// Скільки бітів відбитка SimHash можуть відрізнятися (0 - лише однаковий текст)
+        #[arg(long, default_value_t = DEFAULT_MAX_DISTANCE)]
+        max_distance: u32,
+
+        /// Вивести у JSON замість таблиці
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Ремонтує індекси та записує машинозчитуваний звіт
+    Repair {
+        /// Лише звіт, нічого не зберігати
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Шлях до звіту (за замовчуванням repair_report.json у папці індексів)
+        #[arg(long, value_name = "ФАЙЛ")]
+        report: Option<String>,
+    },
+
+    /// Знімки індексів
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+
+    /// Відкочує індекси до вказаного знімку
+    Rollback {
+        /// Номер покоління (див. snapshot list)
+        generation: u64,
+    },
+
+    /// Примусове ущільнення видалених документів і злиття сегментів інвертованого індексу
+    Compact,
+
+    /// Зливає індекс іншого екземпляра (напр. проіндексований на іншій машині) з локальним
+    Import {
+        /// Папка індексів іншого екземпляра (з documents_index.json або index_manifest.json)
+        #[arg(value_name = "ПАПКА")]
+        from: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum SnapshotAction {
+    /// Показує збережені знімки
+    List,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_dir_in_any_position_and_typos_rejected() {
+        let cli = Cli::try_parse_from(["blazing_SEARCH", "repair", "--index-dir", "/srv/a", "--dry-run"]).unwrap();
+        assert_eq!(cli.index_dir, Some(PathBuf::from("/srv/a")));
+        assert!(matches!(cli.command, Command::Repair { dry_run: true, report: None }));
+
+        let cli = Cli::try_parse_from([
+            "blazing_SEARCH", "--index-dir", "/srv/b", "web", "--port", "9000", "--pid-file", "/run/blazing.pid",
+        ])
+        .unwrap();
+        assert_eq!(cli.index_dir, Some(PathBuf::from("/srv/b")));
+        assert!(matches!(cli.command, Command::Serve { port: Some(9000), daemon: false, .. }));
+
+        // Параметри командного рядка мають пріоритет над файлом конфігурації
+        let mut config = Config::default();
+        config.paths.local_cache = "/srv/cache".to_string();
+        cli.apply(&mut config).unwrap();
+        assert_eq!(config.paths.index_dir, PathBuf::from("/srv/b"));
+        assert_eq!(config.server.port, 9000);
+        assert_eq!(config.server.pid_file, Some(PathBuf::from("/run/blazing.pid")));
+        assert_eq!(config.paths.local_cache, "/srv/cache");
+
+        // Помилка в назві команди більше не запускає повну індексацію
+        assert!(Cli::try_parse_from(["blazing_SEARCH", "serv"]).is_err());
+        assert!(Cli::try_parse_from(["blazing_SEARCH"]).is_err());
+        assert!(Cli::try_parse_from(["blazing_SEARCH", "web", "--index-dir"]).is_err());
+
+        // Перебудова інвертованого індексу не читає мережеву папку
+        let cli = Cli::try_parse_from(["blazing_SEARCH", "rebuild", "--inverted"]).unwrap();
+        assert!(matches!(cli.command, Command::Rebuild { inverted: true, .. }));
+        assert!(Cli::try_parse_from(["blazing_SEARCH", "rebuild", "--inverted", "--cache", "/tmp"]).is_err());
+
+        // Список файлів читається з кешу, мережева папка не потрібна
+        let cli = Cli::try_parse_from(["blazing_SEARCH", "index", "--files-from", "-", "--cache", "/srv/cache"]).unwrap();
+        assert!(matches!(&cli.command, Command::Index { files_from: Some(list), .. } if list == std::path::Path::new("-")));
+        assert!(Cli::try_parse_from(["blazing_SEARCH", "index", "--files-from", "-", "--remote", "/mnt"]).is_err());
+    }
+}
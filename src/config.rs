@@ -0,0 +1,1272 @@
+use crate::atomic_index_manager::{
+    AtomicIndexManager, DEFAULT_DOCUMENTS_BACKUP_POLICY, DEFAULT_INVERTED_BACKUP_POLICY,
+    DEFAULT_LOCK_WAIT_TIMEOUT, DEFAULT_SNAPSHOT_RETENTION,
+};
+use crate::classifier::{default_categories, Classifier};
+use crate::document_date::{FilenameDateFormat, DEFAULT_FILENAME_DATE_FORMATS};
+use crate::error::ConfigError;
+use crate::failure_alerts::{AlertNotifier, DEFAULT_ALERT_FAILURE_THRESHOLD, DEFAULT_ALERT_PARSE_ERROR_JUMP};
+use crate::folder_processor::DEFAULT_EXCLUDED_FOLDERS;
+use crate::index_backup::BackupPolicy;
+use crate::index_compression::IndexCompression;
+use crate::index_paths::{IndexPaths, DEFAULT_INDEX_DIR};
+use crate::index_store::{IndexStore, StorageBackend};
+use crate::logging::{self, DEFAULT_LOG_FILE_NAME, DEFAULT_LOG_KEEP_FILES, DEFAULT_LOG_LEVEL, DEFAULT_LOG_MAX_FILE_SIZE_MB};
+use crate::inverted_segments::DEFAULT_MAX_SEGMENTS;
+use crate::integrity_monitor::{
+    DEFAULT_ALERT_THRESHOLD, DEFAULT_SAMPLE_SIZE, INTEGRITY_CHECK_INTERVAL, INTEGRITY_CHECK_START_DELAY,
+};
+use crate::messages::Locale;
+use crate::query_rewrite::{QueryRewriter, RegexRewriter};
+use crate::redaction::{default_patterns as default_redaction_patterns, RedactionPolicy, Redactor};
+use crate::relevance_feedback::DEFAULT_FEEDBACK_MAX_SHIFT;
+use crate::search_admission::{DEFAULT_MAX_CONCURRENT_SEARCHES, DEFAULT_SEARCH_QUEUE_SIZE};
+use crate::search_engine::{
+    default_paragraph_filters, ResultLimits, SearchEngine, DEFAULT_MAX_PARAGRAPHS, DEFAULT_MAX_RESULTS,
+    DEFAULT_PROXIMITY_WINDOW, DEFAULT_QUERY_TIMEOUT_MS, DEFAULT_QUICK_MODE_DOCUMENTS, DEFAULT_QUICK_MODE_MONTHS,
+    VIEW_MODES,
+};
+use crate::search_history::DEFAULT_HISTORY_MAX_ENTRIES;
+use crate::slow_query_log::DEFAULT_SLOW_QUERY_THRESHOLD_MS;
+use crate::sqlite_store::SqliteIndexStore;
+use crate::stemmer::StemmerKind;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Файл конфігурації, який шукається в робочій папці, якщо --config не вказано
+pub const DEFAULT_CONFIG_FILE: &str = "blazing_search.toml";
+
+/// Префікс змінних середовища: BLAZING_<РОЗДІЛ>_<ПАРАМЕТР>, наприклад BLAZING_SERVER_PORT
+pub const ENV_PREFIX: &str = "BLAZING_";
+
+/// Змінна середовища з назвою профілю, якщо --profile не вказано
+pub const PROFILE_ENV: &str = "BLAZING_PROFILE";
+
+/// Розділ файлу з іменованими профілями: [profiles.<назва>.<розділ>]
+const PROFILES_KEY: &str = "profiles";
+
+const SECONDS_PER_DAY: u64 = 24 * 3600;
+
+/// Запас на завершення циклу індексації при зупинці; TimeoutStopSec у systemd має бути більшим
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 60;
+
+/// Назва основної колекції ([paths]), якщо paths.collection_name не задано
+pub const DEFAULT_COLLECTION_NAME: &str = "Накази";
+
+/// Скільки документів бот Telegram показує у відповіді
+pub const DEFAULT_TELEGRAM_MAX_RESULTS: usize = 5;
+
+/// Налаштування екземпляра. Усі поля необов'язкові: відсутні беруться за замовчуванням,
+/// тому порожній файл (або його відсутність) відповідає поведінці без конфігурації.
+/// Параметри командного рядка мають пріоритет над файлом.
+///
+/// Файл може містити іменовані профілі, наприклад [profiles.test.paths] і
+/// [profiles.test.server]: вибраний профіль (--profile test) накладається поверх
+/// основних параметрів, тож тестовий екземпляр працює поряд з робочим з одного файлу.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub paths: PathsConfig,
+    pub server: ServerConfig,
+    pub indexing: IndexingConfig,
+    pub search: SearchConfig,
+    pub classification: ClassificationConfig,
+    pub storage: StorageConfig,
+    pub integrity: IntegrityConfig,
+    pub telegram: TelegramConfig,
+    pub alerts: AlertsConfig,
+    pub history: HistoryConfig,
+    pub redaction: RedactionConfig,
+    pub logging: LoggingConfig,
+    /// Додаткові колекції ([[collections]]): свої мережева папка, кеш і індекси,
+    /// той самий сервер; основна колекція - [paths]
+    pub collections: Vec<CollectionConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    /// Назва основної колекції (вибір колекції в запиті пошуку)
+    pub collection_name: String,
+    /// Мережева папка з наказами
+    pub remote_folder: String,
+    /// Мережева папка з фото для пошуку файлів
+    pub photo_folder: String,
+    /// Локальна копія мережевої папки, яку індексуємо
+    pub local_cache: String,
+    /// Папка з індексами, маніфестом, блокуванням, резервними копіями та звітами
+    pub index_dir: PathBuf,
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        Self {
+            collection_name: DEFAULT_COLLECTION_NAME.to_string(),
+            remote_folder: "/mnt/salem-documents/Накази".to_string(),
+            photo_folder: "/mnt/salem-documents/ФОТО ВК".to_string(),
+            local_cache: "./nakazi_cache".to_string(),
+            index_dir: PathBuf::from(DEFAULT_INDEX_DIR),
+        }
+    }
+}
+
+/// Незалежна колекція документів, наприклад «Розпорядження» поряд з «Наказами»:
+/// індексується окремо, шукається вибором collection у запиті
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CollectionConfig {
+    pub name: String,
+    pub remote_folder: String,
+    pub local_cache: String,
+    pub index_dir: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub port: u16,
+    /// SHA-256 (hex) пароля для відкриття файлів на сервері
+    pub open_file_password_sha256: String,
+    /// SHA-256 (hex) пароля адміністратора для /api/admin/* (заголовок Authorization: Bearer);
+    /// None - адміністративні запити вимкнено
+    pub admin_password_sha256: Option<String>,
+    /// Мова відповідей API та журналу веб-режиму: "uk" або "en"
+    pub locale: Locale,
+    /// Файл з PID процесу сервера (для PIDFile= у systemd або init-скриптів)
+    pub pid_file: Option<PathBuf>,
+    /// Скільки чекати завершення поточного циклу індексації при зупинці (SIGTERM)
+    pub shutdown_timeout_secs: u64,
+    /// Порт gRPC-сервера для інтеграції між службами; None - gRPC вимкнено
+    pub grpc_port: Option<u16>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            port: 8080,
+            open_file_password_sha256: "a988eeb9e14e5f6397d1b25e18771278e79b1c46676dce686762f387026bc0f8"
+                .to_string(),
+            admin_password_sha256: None,
+            locale: Locale::default(),
+            pid_file: None,
+            shutdown_timeout_secs: DEFAULT_SHUTDOWN_TIMEOUT_SECS,
+            grpc_port: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndexingConfig {
+    /// Як часто фоновий індексер перевіряє мережеву папку
+    pub sync_interval_secs: u64,
+    /// Як часто оновлювати список файлів папки з фото
+    pub file_index_refresh_secs: u64,
+    /// Папки (за назвою, без урахування регістру), які не індексуються
+    pub excluded_folders: Vec<String>,
+    /// Формати дати в назвах файлів, у порядку перевірки (впорядкування наказів за датою)
+    pub filename_date_formats: Vec<FilenameDateFormat>,
+}
+
+impl Default for IndexingConfig {
+    fn default() -> Self {
+        Self {
+            sync_interval_secs: 180,
+            file_index_refresh_secs: 180,
+            excluded_folders: DEFAULT_EXCLUDED_FOLDERS.iter().map(|f| f.to_string()).collect(),
+            filename_date_formats: DEFAULT_FILENAME_DATE_FORMATS.to_vec(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    /// Швидкий пошук переглядає накази, датовані за стільки останніх місяців;
+    /// None - останні quick_mode_documents документів
+    pub quick_mode_months: Option<u32>,
+    /// Скільки найновіших документів переглядає швидкий пошук без quick_mode_months
+    pub quick_mode_documents: usize,
+    /// Стемер для індексу і запитів; після зміни потрібна повна переіндексація (rebuild)
+    pub stemmer: StemmerKind,
+    /// Скільки символів допускається між сусідніми словами запиту в абзаці
+    pub proximity_window: usize,
+    /// Правила переписування запитів перед пошуком (застосовуються по черзі)
+    pub query_rewrites: Vec<QueryRewriteConfig>,
+    /// Запити, довші за цей час (мс), записуються в журнал повільних запитів; 0 - вимкнено
+    pub slow_query_threshold_ms: u64,
+    /// На скільки позицій оцінки користувачів (👍/👎) можуть зсунути документ; 0 - не зсувати
+    pub feedback_max_shift: usize,
+    /// Скільки документів щонайбільше повертає один запит (запит може зменшити); 0 - без обмеження
+    pub max_results: usize,
+    /// Скільки абзаців документа щонайбільше потрапляє у відповідь; 0 - без обмеження
+    pub max_paragraphs: usize,
+    /// Час на один запит (мс), після якого повертаються неповні результати; 0 - без обмеження
+    pub query_timeout_ms: u64,
+    /// Скільки пошуків виконується одночасно; 0 - без обмеження
+    pub max_concurrent: usize,
+    /// Скільки пошуків чекає на виконання; решта відхиляється (HTTP 429)
+    pub queue_size: usize,
+    /// Вид перегляду -> початки абзаців, які не вважаються збігами (без різниці в регістрі);
+    /// запит може передати власний список
+    pub paragraph_filters: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            quick_mode_documents: DEFAULT_QUICK_MODE_DOCUMENTS,
+            quick_mode_months: Some(DEFAULT_QUICK_MODE_MONTHS),
+            stemmer: StemmerKind::default(),
+            proximity_window: DEFAULT_PROXIMITY_WINDOW,
+            query_rewrites: Vec::new(),
+            slow_query_threshold_ms: DEFAULT_SLOW_QUERY_THRESHOLD_MS,
+            feedback_max_shift: DEFAULT_FEEDBACK_MAX_SHIFT,
+            max_results: DEFAULT_MAX_RESULTS,
+            max_paragraphs: DEFAULT_MAX_PARAGRAPHS,
+            query_timeout_ms: DEFAULT_QUERY_TIMEOUT_MS,
+            max_concurrent: DEFAULT_MAX_CONCURRENT_SEARCHES,
+            queue_size: DEFAULT_SEARCH_QUEUE_SIZE,
+            paragraph_filters: default_paragraph_filters(),
+        }
+    }
+}
+
+/// Правило переписування запиту: регулярний вираз і заміна ($1 - група виразу)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct QueryRewriteConfig {
+    pub pattern: String,
+    pub replacement: String,
+}
+
+/// Класифікація наказів за видом під час індексації; після зміни правил потрібен rebuild
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClassificationConfig {
+    /// Правила категорій; порожній список - класифікацію вимкнено
+    pub categories: Vec<CategoryRuleConfig>,
+}
+
+impl Default for ClassificationConfig {
+    fn default() -> Self {
+        Self { categories: default_categories() }
+    }
+}
+
+/// Категорія: ключові слова (будь-де в тексті) і заголовки розділів (початок абзацу)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CategoryRuleConfig {
+    pub name: String,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub sections: Vec<String>,
+}
+
+/// Політика резервних копій одного файлу індексу
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct BackupConfig {
+    pub max_count: usize,
+    #[serde(default)]
+    pub max_age_days: Option<u64>,
+}
+
+impl From<BackupPolicy> for BackupConfig {
+    fn from(policy: BackupPolicy) -> Self {
+        Self {
+            max_count: policy.max_count,
+            max_age_days: policy.max_age.map(|age| age.as_secs() / SECONDS_PER_DAY),
+        }
+    }
+}
+
+impl From<BackupConfig> for BackupPolicy {
+    fn from(config: BackupConfig) -> Self {
+        BackupPolicy::new(
+            config.max_count,
+            config.max_age_days.map(|days| Duration::from_secs(days * SECONDS_PER_DAY)),
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    pub durable_writes: bool,
+    pub lock_wait_timeout_secs: u64,
+    pub snapshot_retention: usize,
+    pub documents_backups: BackupConfig,
+    pub inverted_backups: BackupConfig,
+    /// Стиснення файлів індексу: none, zstd або gzip (старі файли читаються в будь-якому разі)
+    pub compression: IndexCompression,
+    /// Де зберігаються індекси: json (файли поколінь) або sqlite (index.sqlite)
+    pub backend: StorageBackend,
+    /// Скільки сегментів інвертованого індексу накопичується до фонового злиття
+    /// (0 - щоразу повний файл)
+    pub max_segments: usize,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            durable_writes: true,
+            lock_wait_timeout_secs: DEFAULT_LOCK_WAIT_TIMEOUT.as_secs(),
+            snapshot_retention: DEFAULT_SNAPSHOT_RETENTION,
+            documents_backups: DEFAULT_DOCUMENTS_BACKUP_POLICY.into(),
+            inverted_backups: DEFAULT_INVERTED_BACKUP_POLICY.into(),
+            compression: IndexCompression::None,
+            backend: StorageBackend::Json,
+            max_segments: DEFAULT_MAX_SEGMENTS,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct IntegrityConfig {
+    pub interval_secs: u64,
+    pub start_delay_secs: u64,
+    pub sample_size: usize,
+    /// Частка невідповідностей у вибірці, після якої піднімається тривога
+    pub alert_threshold: f64,
+    /// Адреса webhook для тривог (змінна INTEGRITY_ALERT_WEBHOOK має пріоритет)
+    pub alert_webhook: Option<String>,
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: INTEGRITY_CHECK_INTERVAL.as_secs(),
+            start_delay_secs: INTEGRITY_CHECK_START_DELAY.as_secs(),
+            sample_size: DEFAULT_SAMPLE_SIZE,
+            alert_threshold: DEFAULT_ALERT_THRESHOLD,
+            alert_webhook: None,
+        }
+    }
+}
+
+/// Бот Telegram для пошуку з телефона; вмикається, якщо задано token
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelegramConfig {
+    /// Токен бота від @BotFather (краще передавати змінною BLAZING_TELEGRAM_TOKEN)
+    pub token: Option<String>,
+    /// Чати (chat id), яким дозволено шукати; решті бот відмовляє
+    pub allowed_chats: Vec<i64>,
+    /// Скільки документів показувати у відповіді
+    pub max_results: usize,
+}
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            allowed_chats: Vec::new(),
+            max_results: DEFAULT_TELEGRAM_MAX_RESULTS,
+        }
+    }
+}
+
+/// Сповіщення про збої фонової синхронізації та індексації (webhook і/або пошта)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertsConfig {
+    /// Після скількох невдалих циклів поспіль надсилати сповіщення
+    pub failure_threshold: u32,
+    /// На скільки має зрости кількість файлів з помилками обробки за цикл
+    pub parse_error_jump: usize,
+    /// Адреса, на яку надсилається JSON зі сповіщенням
+    pub webhook: Option<String>,
+    /// Сервер SMTP; без нього пошта не надсилається
+    pub smtp_server: Option<String>,
+    pub smtp_port: u16,
+    /// STARTTLS обов'язковий; false - без шифрування (лише для внутрішнього поштового релею)
+    pub smtp_starttls: bool,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub smtp_from: String,
+    pub smtp_to: Vec<String>,
+    /// Хости, на які збережені запити можуть надсилати webhook (порожньо - webhook недоступний):
+    /// адреси задають користувачі, тож без переліку сервер ходив би куди завгодно
+    pub saved_query_webhook_hosts: Vec<String>,
+    /// Домени пошти для сповіщень збережених запитів (порожньо - пошта недоступна)
+    pub saved_query_email_domains: Vec<String>,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: DEFAULT_ALERT_FAILURE_THRESHOLD,
+            parse_error_jump: DEFAULT_ALERT_PARSE_ERROR_JUMP,
+            webhook: None,
+            smtp_server: None,
+            smtp_port: 587,
+            smtp_starttls: true,
+            smtp_username: None,
+            smtp_password: None,
+            smtp_from: "blazing-search@localhost".to_string(),
+            smtp_to: Vec::new(),
+            saved_query_webhook_hosts: Vec::new(),
+            saved_query_email_domains: Vec::new(),
+        }
+    }
+}
+
+/// Історія пошуку користувачів на сервері ("нещодавні запити" у веб-інтерфейсі)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// false - запити і останні візити ("нове з минулого візиту") не зберігаються
+    pub enabled: bool,
+    /// Скільки останніх запитів зберігати для кожного користувача
+    pub max_entries: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries: DEFAULT_HISTORY_MAX_ENTRIES,
+        }
+    }
+}
+
+/// Маскування чутливих даних у відповідях API (індекс не змінюється)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactionConfig {
+    pub enabled: bool,
+    /// SHA-256 (hex) пароля ролі "unredacted" - перегляду без маскування; None - ролі немає
+    pub unredacted_password_sha256: Option<String>,
+    /// Правила маскування (застосовуються по черзі)
+    pub patterns: Vec<RedactionRuleConfig>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            unredacted_password_sha256: None,
+            patterns: default_redaction_patterns(),
+        }
+    }
+}
+
+/// Правило маскування: регулярний вираз і маска (за замовчуванням "[назва]")
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RedactionRuleConfig {
+    pub name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub replacement: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// Рівень журналу або специфікація з модулями ("info,blazing_search::folder_processor=debug").
+    /// Пріоритет: --log-level > BLAZING_LOGGING_LEVEL > RUST_LOG > файл
+    pub level: String,
+    /// Папка файлів журналу; None - журнал лише в консоль
+    pub directory: Option<PathBuf>,
+    /// Назва файлу журналу без розширення (до неї додається .log)
+    pub file_name: String,
+    /// Файл ротується, коли перевищить цей розмір
+    pub max_file_size_mb: u64,
+    /// Додатково ротувати файл щодоби
+    pub rotate_daily: bool,
+    /// Скільки ротованих файлів зберігати (старіші видаляються)
+    pub keep_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_LOG_LEVEL.to_string(),
+            directory: None,
+            file_name: DEFAULT_LOG_FILE_NAME.to_string(),
+            max_file_size_mb: DEFAULT_LOG_MAX_FILE_SIZE_MB,
+            rotate_daily: true,
+            keep_files: DEFAULT_LOG_KEEP_FILES,
+        }
+    }
+}
+
+impl Config {
+    /// Завантажує конфігурацію: вказаний файл (має існувати) або blazing_search.toml
+    /// з робочої папки, якщо він є; інакше - значення за замовчуванням.
+    /// Поверх файлу накладаються профіль (profile або змінна BLAZING_PROFILE)
+    /// і змінні середовища BLAZING_*.
+    pub fn load(path: Option<&Path>, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let profile = profile
+            .map(str::to_string)
+            .or_else(|| std::env::var(PROFILE_ENV).ok())
+            .filter(|p| !p.trim().is_empty());
+
+        let path = Self::file_path(path);
+
+        let mut config = match &path {
+            Some(path) => {
+                let text = fs::read_to_string(path)
+                    .map_err(|e| ConfigError::Read { path: path.display().to_string(), source: e })?;
+                // Повідомлення конфігурації - у stderr, щоб не псувати машинозчитуваний вивід (stats --json)
+                let config = Self::parse_profile(&text, profile.as_deref())
+                    .map_err(|e| ConfigError::Parse { path: path.display().to_string(), source: e })?;
+                match &profile {
+                    Some(profile) => eprintln!("⚙️ Конфігурацію завантажено: {} (профіль {})", path.display(), profile),
+                    None => eprintln!("⚙️ Конфігурацію завантажено: {}", path.display()),
+                }
+                config
+            }
+            None if profile.is_some() => {
+                return Err(ConfigError::Invalid(format!(
+                    "профіль {} вказано, але файл конфігурації {} не знайдено",
+                    profile.unwrap_or_default(),
+                    DEFAULT_CONFIG_FILE
+                )));
+            }
+            None => Self::default(),
+        };
+
+        let applied = config.apply_env(|name| std::env::var(name).ok())?;
+        if !applied.is_empty() {
+            eprintln!("⚙️ Параметри зі змінних середовища: {}", applied.join(", "));
+        }
+
+        // Змінні з префіксом, яких немає в конфігурації - найімовірніше одруківка
+        for (name, _) in std::env::vars() {
+            if name.starts_with(ENV_PREFIX) && !ENV_VARIABLES.contains(&name.as_str()) {
+                eprintln!("⚠️ Невідома змінна середовища {} ігнорується", name);
+            }
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Файл, з якого load читає конфігурацію: вказаний або blazing_search.toml
+    /// з робочої папки, якщо він є
+    pub fn file_path(path: Option<&Path>) -> Option<PathBuf> {
+        match path {
+            Some(path) => Some(path.to_path_buf()),
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => Some(PathBuf::from(DEFAULT_CONFIG_FILE)),
+            None => None,
+        }
+    }
+
+    /// Накладає змінні середовища BLAZING_* (var повертає значення змінної за назвою).
+    /// Повертає назви застосованих змінних; значення секретів не виводяться.
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<Vec<String>, ConfigError> {
+        let mut env = EnvOverrides { var, applied: Vec::new() };
+
+        env.parse("PATHS_COLLECTION_NAME", &mut self.paths.collection_name)?;
+        env.parse("PATHS_REMOTE_FOLDER", &mut self.paths.remote_folder)?;
+        env.parse("PATHS_PHOTO_FOLDER", &mut self.paths.photo_folder)?;
+        env.parse("PATHS_LOCAL_CACHE", &mut self.paths.local_cache)?;
+        env.parse("PATHS_INDEX_DIR", &mut self.paths.index_dir)?;
+
+        env.parse("SERVER_PORT", &mut self.server.port)?;
+        env.parse("SERVER_OPEN_FILE_PASSWORD_SHA256", &mut self.server.open_file_password_sha256)?;
+        // Пароль у відкритому вигляді зручно передавати як секрет контейнера; зберігається лише хеш
+        if let Some(password) = env.value("SERVER_OPEN_FILE_PASSWORD") {
+            self.server.open_file_password_sha256 = password_sha256(&password);
+        }
+        env.parse_optional("SERVER_ADMIN_PASSWORD_SHA256", &mut self.server.admin_password_sha256)?;
+        if let Some(password) = env.value("SERVER_ADMIN_PASSWORD") {
+            self.server.admin_password_sha256 = Some(password_sha256(&password));
+        }
+        env.parse("SERVER_LOCALE", &mut self.server.locale)?;
+        env.parse_optional("SERVER_PID_FILE", &mut self.server.pid_file)?;
+        env.parse("SERVER_SHUTDOWN_TIMEOUT_SECS", &mut self.server.shutdown_timeout_secs)?;
+        env.parse_optional("SERVER_GRPC_PORT", &mut self.server.grpc_port)?;
+
+        env.parse("INDEXING_SYNC_INTERVAL_SECS", &mut self.indexing.sync_interval_secs)?;
+        env.parse("INDEXING_FILE_INDEX_REFRESH_SECS", &mut self.indexing.file_index_refresh_secs)?;
+        if let Some(folders) = env.value("INDEXING_EXCLUDED_FOLDERS") {
+            self.indexing.excluded_folders = folders
+                .split(',')
+                .map(|f| f.trim().to_string())
+                .filter(|f| !f.is_empty())
+                .collect();
+        }
+        if let Some(formats) = env.value("INDEXING_FILENAME_DATE_FORMATS") {
+            self.indexing.filename_date_formats = formats
+                .split(',')
+                .map(str::trim)
+                .filter(|f| !f.is_empty())
+                .map(|f| {
+                    f.parse().map_err(|_| ConfigError::Env {
+                        name: format!("{}INDEXING_FILENAME_DATE_FORMATS", ENV_PREFIX),
+                        value: formats.clone(),
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+        }
+
+        env.parse("SEARCH_QUICK_MODE_DOCUMENTS", &mut self.search.quick_mode_documents)?;
+        env.parse_optional("SEARCH_QUICK_MODE_MONTHS", &mut self.search.quick_mode_months)?;
+        env.parse("SEARCH_STEMMER", &mut self.search.stemmer)?;
+        env.parse("SEARCH_PROXIMITY_WINDOW", &mut self.search.proximity_window)?;
+        env.parse("SEARCH_SLOW_QUERY_THRESHOLD_MS", &mut self.search.slow_query_threshold_ms)?;
+        env.parse("SEARCH_FEEDBACK_MAX_SHIFT", &mut self.search.feedback_max_shift)?;
+        env.parse("SEARCH_MAX_RESULTS", &mut self.search.max_results)?;
+        env.parse("SEARCH_MAX_PARAGRAPHS", &mut self.search.max_paragraphs)?;
+        env.parse("SEARCH_QUERY_TIMEOUT_MS", &mut self.search.query_timeout_ms)?;
+        env.parse("SEARCH_MAX_CONCURRENT", &mut self.search.max_concurrent)?;
+        env.parse("SEARCH_QUEUE_SIZE", &mut self.search.queue_size)?;
+
+        env.parse("STORAGE_DURABLE_WRITES", &mut self.storage.durable_writes)?;
+        env.parse("STORAGE_LOCK_WAIT_TIMEOUT_SECS", &mut self.storage.lock_wait_timeout_secs)?;
+        env.parse("STORAGE_SNAPSHOT_RETENTION", &mut self.storage.snapshot_retention)?;
+        env.parse("STORAGE_DOCUMENTS_BACKUPS_MAX_COUNT", &mut self.storage.documents_backups.max_count)?;
+        env.parse_optional("STORAGE_DOCUMENTS_BACKUPS_MAX_AGE_DAYS", &mut self.storage.documents_backups.max_age_days)?;
+        env.parse("STORAGE_INVERTED_BACKUPS_MAX_COUNT", &mut self.storage.inverted_backups.max_count)?;
+        env.parse_optional("STORAGE_INVERTED_BACKUPS_MAX_AGE_DAYS", &mut self.storage.inverted_backups.max_age_days)?;
+        env.parse("STORAGE_COMPRESSION", &mut self.storage.compression)?;
+        env.parse("STORAGE_BACKEND", &mut self.storage.backend)?;
+        env.parse("STORAGE_MAX_SEGMENTS", &mut self.storage.max_segments)?;
+
+        env.parse("INTEGRITY_INTERVAL_SECS", &mut self.integrity.interval_secs)?;
+        env.parse("INTEGRITY_START_DELAY_SECS", &mut self.integrity.start_delay_secs)?;
+        env.parse("INTEGRITY_SAMPLE_SIZE", &mut self.integrity.sample_size)?;
+        env.parse("INTEGRITY_ALERT_THRESHOLD", &mut self.integrity.alert_threshold)?;
+        env.parse_optional("INTEGRITY_ALERT_WEBHOOK", &mut self.integrity.alert_webhook)?;
+
+        env.parse_optional("TELEGRAM_TOKEN", &mut self.telegram.token)?;
+        if let Some(chats) = env.value("TELEGRAM_ALLOWED_CHATS") {
+            self.telegram.allowed_chats = chats
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(|c| {
+                    c.parse().map_err(|_| ConfigError::Env {
+                        name: format!("{}TELEGRAM_ALLOWED_CHATS", ENV_PREFIX),
+                        value: chats.clone(),
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        env.parse("TELEGRAM_MAX_RESULTS", &mut self.telegram.max_results)?;
+
+        env.parse("ALERTS_FAILURE_THRESHOLD", &mut self.alerts.failure_threshold)?;
+        env.parse("ALERTS_PARSE_ERROR_JUMP", &mut self.alerts.parse_error_jump)?;
+        env.parse_optional("ALERTS_WEBHOOK", &mut self.alerts.webhook)?;
+        env.parse_optional("ALERTS_SMTP_SERVER", &mut self.alerts.smtp_server)?;
+        env.parse("ALERTS_SMTP_PORT", &mut self.alerts.smtp_port)?;
+        env.parse("ALERTS_SMTP_STARTTLS", &mut self.alerts.smtp_starttls)?;
+        env.parse_optional("ALERTS_SMTP_USERNAME", &mut self.alerts.smtp_username)?;
+        env.parse_optional("ALERTS_SMTP_PASSWORD", &mut self.alerts.smtp_password)?;
+        env.parse("ALERTS_SMTP_FROM", &mut self.alerts.smtp_from)?;
+        if let Some(recipients) = env.value("ALERTS_SMTP_TO") {
+            self.alerts.smtp_to = recipients
+                .split(',')
+                .map(|r| r.trim().to_string())
+                .filter(|r| !r.is_empty())
+                .collect();
+        }
+        if let Some(hosts) = env.value("ALERTS_SAVED_QUERY_WEBHOOK_HOSTS") {
+            self.alerts.saved_query_webhook_hosts = hosts
+                .split(',')
+                .map(|h| h.trim().to_string())
+                .filter(|h| !h.is_empty())
+                .collect();
+        }
+        if let Some(domains) = env.value("ALERTS_SAVED_QUERY_EMAIL_DOMAINS") {
+            self.alerts.saved_query_email_domains = domains
+                .split(',')
+                .map(|d| d.trim().to_string())
+                .filter(|d| !d.is_empty())
+                .collect();
+        }
+
+        env.parse("HISTORY_ENABLED", &mut self.history.enabled)?;
+        env.parse("HISTORY_MAX_ENTRIES", &mut self.history.max_entries)?;
+
+        env.parse("REDACTION_ENABLED", &mut self.redaction.enabled)?;
+        env.parse_optional("REDACTION_UNREDACTED_PASSWORD_SHA256", &mut self.redaction.unredacted_password_sha256)?;
+        if let Some(password) = env.value("REDACTION_UNREDACTED_PASSWORD") {
+            self.redaction.unredacted_password_sha256 = Some(password_sha256(&password));
+        }
+
+        // Стандартна змінна Rust-журналів перекриває файл, а явний BLAZING_LOGGING_LEVEL - її
+        if let Some(level) = (env.var)("RUST_LOG").filter(|v| !v.trim().is_empty()) {
+            self.logging.level = level.trim().to_string();
+            env.applied.push("RUST_LOG".to_string());
+        }
+        env.parse("LOGGING_LEVEL", &mut self.logging.level)?;
+        env.parse_optional("LOGGING_DIRECTORY", &mut self.logging.directory)?;
+        env.parse("LOGGING_FILE_NAME", &mut self.logging.file_name)?;
+        env.parse("LOGGING_MAX_FILE_SIZE_MB", &mut self.logging.max_file_size_mb)?;
+        env.parse("LOGGING_ROTATE_DAILY", &mut self.logging.rotate_daily)?;
+        env.parse("LOGGING_KEEP_FILES", &mut self.logging.keep_files)?;
+
+        Ok(env.applied)
+    }
+
+    /// Розбирає TOML без перевірки значень (див. validate); профілі не накладаються
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        Self::parse_profile(text, None)
+    }
+
+    /// Розбирає TOML і накладає профіль [profiles.<profile>] поверх основних параметрів:
+    /// вкладені розділи зливаються, окремі параметри профілю замінюють основні
+    pub fn parse_profile(text: &str, profile: Option<&str>) -> Result<Self, toml::de::Error> {
+        let mut table: toml::Table = toml::from_str(text)?;
+        let profiles = match table.remove(PROFILES_KEY) {
+            Some(toml::Value::Table(profiles)) => profiles,
+            Some(_) => return Err(serde::de::Error::custom("profiles має бути таблицею профілів")),
+            None => toml::Table::new(),
+        };
+
+        if let Some(profile) = profile {
+            match profiles.get(profile) {
+                Some(toml::Value::Table(overrides)) => merge_tables(&mut table, overrides.clone()),
+                _ => {
+                    let available: Vec<&str> = profiles.keys().map(String::as_str).collect();
+                    let available = if available.is_empty() { "немає".to_string() } else { available.join(", ") };
+                    return Err(serde::de::Error::custom(format!(
+                        "профіль {} не знайдено (доступні: {})",
+                        profile, available
+                    )));
+                }
+            }
+        }
+
+        table.try_into()
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.indexing.sync_interval_secs == 0 || self.indexing.file_index_refresh_secs == 0 {
+            return Err(ConfigError::Invalid("інтервали індексації мають бути більші за 0".to_string()));
+        }
+        if self.indexing.filename_date_formats.is_empty() {
+            return Err(ConfigError::Invalid("indexing.filename_date_formats не може бути порожнім".to_string()));
+        }
+        if self.search.quick_mode_months == Some(0) {
+            return Err(ConfigError::Invalid("search.quick_mode_months має бути більше за 0 (none - за кількістю документів)".to_string()));
+        }
+        if self.integrity.interval_secs == 0 {
+            return Err(ConfigError::Invalid("integrity.interval_secs має бути більше за 0".to_string()));
+        }
+        if !(0.0..=1.0).contains(&self.integrity.alert_threshold) {
+            return Err(ConfigError::Invalid("integrity.alert_threshold має бути від 0 до 1".to_string()));
+        }
+        if self.server.open_file_password_sha256.len() != 64 {
+            return Err(ConfigError::Invalid("server.open_file_password_sha256 має бути SHA-256 у hex (64 символи)".to_string()));
+        }
+        if let Some(hash) = &self.server.admin_password_sha256 {
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ConfigError::Invalid("server.admin_password_sha256 має бути SHA-256 у hex (64 символи)".to_string()));
+            }
+        }
+        if self.server.grpc_port == Some(self.server.port) {
+            return Err(ConfigError::Invalid("server.grpc_port має відрізнятися від server.port".to_string()));
+        }
+        if self.logging.max_file_size_mb == 0 || self.logging.keep_files == 0 {
+            return Err(ConfigError::Invalid("logging.max_file_size_mb і logging.keep_files мають бути більші за 0".to_string()));
+        }
+        if let Err(e) = logging::parse_level(&self.logging.level) {
+            return Err(ConfigError::Invalid(format!("logging.level: {}", e)));
+        }
+        if self.logging.file_name.trim().is_empty() {
+            return Err(ConfigError::Invalid("logging.file_name не може бути порожнім".to_string()));
+        }
+        // Бот без списку чатів відповідав би будь-кому, хто його знайде
+        if self.telegram.token.is_some() && self.telegram.allowed_chats.is_empty() {
+            return Err(ConfigError::Invalid("telegram.allowed_chats не може бути порожнім, якщо задано telegram.token".to_string()));
+        }
+        if self.telegram.max_results == 0 {
+            return Err(ConfigError::Invalid("telegram.max_results має бути більше за 0".to_string()));
+        }
+        if self.alerts.failure_threshold == 0 || self.alerts.parse_error_jump == 0 {
+            return Err(ConfigError::Invalid("alerts.failure_threshold і alerts.parse_error_jump мають бути більші за 0".to_string()));
+        }
+        AlertNotifier::from_config(&self.alerts)?;
+        if self.history.max_entries == 0 {
+            return Err(ConfigError::Invalid("history.max_entries має бути більше за 0".to_string()));
+        }
+        let mut category_names = std::collections::HashSet::new();
+        for rule in &self.classification.categories {
+            let name = rule.name.trim();
+            if name.is_empty() || !category_names.insert(name.to_lowercase()) {
+                return Err(ConfigError::Invalid(format!("classification.categories: порожня або повторена назва категорії '{}'", name)));
+            }
+            if rule.keywords.iter().chain(&rule.sections).all(|phrase| phrase.trim().is_empty()) {
+                return Err(ConfigError::Invalid(format!("classification.categories: категорія '{}' без ключових слів і розділів", name)));
+            }
+        }
+        if self.redaction.unredacted_password_sha256.as_ref().is_some_and(|hash| hash.len() != 64) {
+            return Err(ConfigError::Invalid("redaction.unredacted_password_sha256 має бути SHA-256 у hex (64 символи)".to_string()));
+        }
+        if self.redaction.patterns.iter().any(|rule| rule.name.trim().is_empty()) {
+            return Err(ConfigError::Invalid("redaction.patterns: порожня назва правила".to_string()));
+        }
+        if let Err(e) = Redactor::new(&self.redaction.patterns) {
+            return Err(ConfigError::Invalid(format!("redaction.patterns: {}", e)));
+        }
+        if let Err(e) = RegexRewriter::new(&self.search.query_rewrites) {
+            return Err(ConfigError::Invalid(format!("search.query_rewrites: {}", e)));
+        }
+        if let Some(view_mode) = self.search.paragraph_filters.keys().find(|mode| !VIEW_MODES.contains(&mode.as_str())) {
+            return Err(ConfigError::Invalid(format!(
+                "search.paragraph_filters: невідомий вид перегляду '{}' (можливі: {})",
+                view_mode,
+                VIEW_MODES.join(", ")
+            )));
+        }
+        self.validate_collections()
+    }
+
+    /// Назви колекцій не повторюються, а папки індексів і кеші не спільні: дві колекції
+    /// в одній папці індексів перезаписували б одна одній покоління
+    fn validate_collections(&self) -> Result<(), ConfigError> {
+        let mut names = std::collections::HashSet::new();
+        let mut index_dirs = std::collections::HashSet::new();
+        let mut caches = std::collections::HashSet::new();
+        for config in self.collection_configs() {
+            let paths = &config.paths;
+            let name = paths.collection_name.trim();
+            if name.is_empty() || !names.insert(name.to_lowercase()) {
+                return Err(ConfigError::Invalid(format!("collections: порожня або повторена назва колекції '{}'", name)));
+            }
+            if !index_dirs.insert(paths.index_dir.clone()) {
+                return Err(ConfigError::Invalid(format!("collections: колекція '{}' має власну папку індексів ({} уже зайнята)", name, paths.index_dir.display())));
+            }
+            if !caches.insert(paths.local_cache.clone()) {
+                return Err(ConfigError::Invalid(format!("collections: колекція '{}' має власний локальний кеш ({} уже зайнятий)", name, paths.local_cache)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Конфігурація для кожної колекції: спершу основна, далі [[collections]] за порядком
+    pub fn collection_configs(&self) -> Vec<Config> {
+        let mut primary = self.clone();
+        primary.collections = Vec::new();
+        std::iter::once(primary)
+            .chain(self.collections.iter().map(|collection| {
+                let mut config = self.clone();
+                config.paths = PathsConfig {
+                    collection_name: collection.name.clone(),
+                    remote_folder: collection.remote_folder.clone(),
+                    photo_folder: self.paths.photo_folder.clone(),
+                    local_cache: collection.local_cache.clone(),
+                    index_dir: collection.index_dir.clone(),
+                };
+                config.collections = Vec::new();
+                config
+            }))
+            .collect()
+    }
+
+    /// Працювати з колекцією name (без різниці в регістрі) замість основної
+    pub fn select_collection(&mut self, name: &str) -> Result<(), ConfigError> {
+        let selected = self
+            .collection_configs()
+            .into_iter()
+            .find(|config| config.paths.collection_name.trim().to_lowercase() == name.trim().to_lowercase());
+        match selected {
+            Some(config) => {
+                *self = config;
+                Ok(())
+            }
+            None => {
+                let available: Vec<String> =
+                    self.collection_configs().into_iter().map(|config| config.paths.collection_name).collect();
+                Err(ConfigError::Invalid(format!(
+                    "колекцію {} не знайдено (доступні: {})",
+                    name,
+                    available.join(", ")
+                )))
+            }
+        }
+    }
+
+    pub fn index_paths(&self) -> IndexPaths {
+        IndexPaths::new(&self.paths.index_dir)
+    }
+
+    /// Пошуковий движок з налаштуваннями пошуку з конфігурації (індекси ще не завантажені)
+    pub fn search_engine(&self) -> SearchEngine {
+        let mut engine = SearchEngine::new(&self.index_paths());
+        if let Some(store) = self.index_store() {
+            engine = engine.with_store(store);
+        }
+        self.apply_search_settings(&engine);
+        engine
+    }
+
+    /// Налаштування пошуку, які можна змінювати на ходу (також при перезавантаженні конфігурації)
+    pub fn apply_search_settings(&self, engine: &SearchEngine) {
+        engine.set_quick_mode_documents(self.search.quick_mode_documents);
+        engine.set_quick_mode_months(self.search.quick_mode_months);
+        engine.set_proximity_window(self.search.proximity_window);
+        engine.set_slow_query_threshold_ms(self.search.slow_query_threshold_ms);
+        engine.set_feedback_max_shift(self.search.feedback_max_shift);
+        engine.set_result_limits(ResultLimits {
+            max_results: self.search.max_results,
+            max_paragraphs: self.search.max_paragraphs,
+        });
+        engine.set_query_timeout_ms(self.search.query_timeout_ms);
+        engine.set_admission_limits(self.search.max_concurrent, self.search.queue_size);
+        engine.set_paragraph_filters(&self.search.paragraph_filters);
+
+        // Вирази перевіряються в validate
+        let rewriter = RegexRewriter::new(&self.search.query_rewrites).ok().filter(|r| !r.is_empty());
+        engine.set_query_rewriter(rewriter.map(|r| Arc::new(r) as Arc<dyn QueryRewriter>));
+        engine.mark_settings_changed();
+    }
+
+    /// Хто бачить тексти без маскування (див. redaction)
+    pub fn redaction_policy(&self) -> RedactionPolicy {
+        // Вирази перевіряються в validate
+        RedactionPolicy::from_config(&self.redaction).unwrap_or_default()
+    }
+
+    /// Менеджер індексів з налаштуваннями збереження з конфігурації
+    pub fn index_manager(&self) -> AtomicIndexManager {
+        let paths = self.index_paths();
+        let storage = &self.storage;
+
+        let manager = AtomicIndexManager::new(&paths.documents_index(), &paths.inverted_index())
+            .with_durable_writes(storage.durable_writes)
+            .with_lock_wait_timeout(Duration::from_secs(storage.lock_wait_timeout_secs))
+            .with_snapshot_retention(storage.snapshot_retention)
+            .with_backup_policies(storage.documents_backups.into(), storage.inverted_backups.into())
+            .with_compression(storage.compression)
+            .with_max_segments(storage.max_segments)
+            .with_excluded_folders(self.indexing.excluded_folders.clone())
+            .with_classifier(Arc::new(Classifier::new(&self.classification.categories)));
+        match self.index_store() {
+            Some(store) => manager.with_store(store),
+            None => manager,
+        }
+    }
+
+    /// Сховище індексів для storage.backend (None - файли поколінь)
+    pub fn index_store(&self) -> Option<Arc<dyn IndexStore>> {
+        match self.storage.backend {
+            StorageBackend::Json => None,
+            StorageBackend::Sqlite => Some(Arc::new(
+                SqliteIndexStore::new(self.index_paths().sqlite_index()).with_durable_writes(self.storage.durable_writes),
+            )),
+        }
+    }
+}
+
+/// Накладає параметри профілю: вкладені таблиці зливаються, решта значень замінюється
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(value)) => merge_tables(base, value),
+            (Some(slot), value) => *slot = value,
+            (None, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Усі змінні середовища, які розуміє Config (BLAZING_PROFILE і ті, що накладає apply_env)
+const ENV_VARIABLES: &[&str] = &[
+    PROFILE_ENV,
+    "BLAZING_PATHS_COLLECTION_NAME",
+    "BLAZING_PATHS_REMOTE_FOLDER",
+    "BLAZING_PATHS_PHOTO_FOLDER",
+    "BLAZING_PATHS_LOCAL_CACHE",
+    "BLAZING_PATHS_INDEX_DIR",
+    "BLAZING_SERVER_PORT",
+    "BLAZING_SERVER_OPEN_FILE_PASSWORD_SHA256",
+    "BLAZING_SERVER_OPEN_FILE_PASSWORD",
+    "BLAZING_SERVER_ADMIN_PASSWORD_SHA256",
+    "BLAZING_SERVER_ADMIN_PASSWORD",
+    "BLAZING_SERVER_LOCALE",
+    "BLAZING_SERVER_PID_FILE",
+    "BLAZING_SERVER_SHUTDOWN_TIMEOUT_SECS",
+    "BLAZING_SERVER_GRPC_PORT",
+    "BLAZING_INDEXING_SYNC_INTERVAL_SECS",
+    "BLAZING_INDEXING_FILE_INDEX_REFRESH_SECS",
+    "BLAZING_INDEXING_EXCLUDED_FOLDERS",
+    "BLAZING_INDEXING_FILENAME_DATE_FORMATS",
+    "BLAZING_SEARCH_QUICK_MODE_DOCUMENTS",
+    "BLAZING_SEARCH_QUICK_MODE_MONTHS",
+    "BLAZING_SEARCH_STEMMER",
+    "BLAZING_SEARCH_PROXIMITY_WINDOW",
+    "BLAZING_SEARCH_SLOW_QUERY_THRESHOLD_MS",
+    "BLAZING_SEARCH_FEEDBACK_MAX_SHIFT",
+    "BLAZING_SEARCH_MAX_RESULTS",
+    "BLAZING_SEARCH_MAX_PARAGRAPHS",
+    "BLAZING_SEARCH_QUERY_TIMEOUT_MS",
+    "BLAZING_SEARCH_MAX_CONCURRENT",
+    "BLAZING_SEARCH_QUEUE_SIZE",
+    "BLAZING_STORAGE_DURABLE_WRITES",
+    "BLAZING_STORAGE_LOCK_WAIT_TIMEOUT_SECS",
+    "BLAZING_STORAGE_SNAPSHOT_RETENTION",
+    "BLAZING_STORAGE_DOCUMENTS_BACKUPS_MAX_COUNT",
+    "BLAZING_STORAGE_DOCUMENTS_BACKUPS_MAX_AGE_DAYS",
+    "BLAZING_STORAGE_INVERTED_BACKUPS_MAX_COUNT",
+    "BLAZING_STORAGE_INVERTED_BACKUPS_MAX_AGE_DAYS",
+    "BLAZING_STORAGE_COMPRESSION",
+    "BLAZING_STORAGE_BACKEND",
+    "BLAZING_STORAGE_MAX_SEGMENTS",
+    "BLAZING_INTEGRITY_INTERVAL_SECS",
+    "BLAZING_INTEGRITY_START_DELAY_SECS",
+    "BLAZING_INTEGRITY_SAMPLE_SIZE",
+    "BLAZING_INTEGRITY_ALERT_THRESHOLD",
+    "BLAZING_INTEGRITY_ALERT_WEBHOOK",
+    "BLAZING_TELEGRAM_TOKEN",
+    "BLAZING_TELEGRAM_ALLOWED_CHATS",
+    "BLAZING_TELEGRAM_MAX_RESULTS",
+    "BLAZING_ALERTS_FAILURE_THRESHOLD",
+    "BLAZING_ALERTS_PARSE_ERROR_JUMP",
+    "BLAZING_ALERTS_WEBHOOK",
+    "BLAZING_ALERTS_SMTP_SERVER",
+    "BLAZING_ALERTS_SMTP_PORT",
+    "BLAZING_ALERTS_SMTP_STARTTLS",
+    "BLAZING_ALERTS_SMTP_USERNAME",
+    "BLAZING_ALERTS_SMTP_PASSWORD",
+    "BLAZING_ALERTS_SMTP_FROM",
+    "BLAZING_ALERTS_SMTP_TO",
+    "BLAZING_ALERTS_SAVED_QUERY_WEBHOOK_HOSTS",
+    "BLAZING_ALERTS_SAVED_QUERY_EMAIL_DOMAINS",
+    "BLAZING_HISTORY_ENABLED",
+    "BLAZING_HISTORY_MAX_ENTRIES",
+    "BLAZING_REDACTION_ENABLED",
+    "BLAZING_REDACTION_UNREDACTED_PASSWORD_SHA256",
+    "BLAZING_REDACTION_UNREDACTED_PASSWORD",
+    "BLAZING_LOGGING_LEVEL",
+    "BLAZING_LOGGING_DIRECTORY",
+    "BLAZING_LOGGING_FILE_NAME",
+    "BLAZING_LOGGING_MAX_FILE_SIZE_MB",
+    "BLAZING_LOGGING_ROTATE_DAILY",
+    "BLAZING_LOGGING_KEEP_FILES",
+];
+
+/// Читання окремих змінних BLAZING_* з перетворенням у тип параметра
+struct EnvOverrides<F: Fn(&str) -> Option<String>> {
+    var: F,
+    applied: Vec<String>,
+}
+
+impl<F: Fn(&str) -> Option<String>> EnvOverrides<F> {
+    /// Непорожнє значення змінної BLAZING_<key>
+    fn value(&mut self, key: &str) -> Option<String> {
+        let name = format!("{}{}", ENV_PREFIX, key);
+        debug_assert!(ENV_VARIABLES.contains(&name.as_str()), "{} немає в ENV_VARIABLES", name);
+
+        let value = (self.var)(&name).filter(|v| !v.trim().is_empty())?;
+        self.applied.push(name);
+        Some(value.trim().to_string())
+    }
+
+    fn parse<T: FromStr>(&mut self, key: &str, target: &mut T) -> Result<(), ConfigError> {
+        if let Some(value) = self.value(key) {
+            *target = value
+                .parse()
+                .map_err(|_| ConfigError::Env { name: format!("{}{}", ENV_PREFIX, key), value: value.clone() })?;
+        }
+        Ok(())
+    }
+
+    /// Для необов'язкових параметрів значення "none" вимикає параметр
+    fn parse_optional<T: FromStr>(&mut self, key: &str, target: &mut Option<T>) -> Result<(), ConfigError> {
+        match self.value(key) {
+            Some(value) if value.eq_ignore_ascii_case("none") => *target = None,
+            Some(value) => {
+                *target = Some(
+                    value
+                        .parse()
+                        .map_err(|_| ConfigError::Env { name: format!("{}{}", ENV_PREFIX, key), value: value.clone() })?,
+                )
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+/// SHA-256 пароля у вигляді hex-рядка (у конфігурації зберігається лише хеш)
+pub fn password_sha256(password: &str) -> String {
+    Sha256::digest(password.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Чи відповідає пароль хешу з конфігурації. Хеші порівнюються за сталий час (без
+/// раннього виходу на першому відмінному символі), регістр hex конфігурації не важить
+pub fn password_matches(password: &str, expected_sha256: &str) -> bool {
+    let actual = password_sha256(password);
+    let expected = expected_sha256.to_ascii_lowercase();
+    actual.len() == expected.len()
+        && actual.bytes().zip(expected.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_config_keeps_defaults() {
+        let config = Config::parse(
+            r#"
+            [paths]
+            remote_folder = "/srv/накази"
+
+            [storage]
+            snapshot_retention = 2
+            documents_backups = { max_count = 4 }
+
+            [search]
+            stemmer = "none"
+
+            [logging]
+            directory = "/var/log/blazing"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.paths.remote_folder, "/srv/накази");
+        assert_eq!(config.paths.local_cache, PathsConfig::default().local_cache);
+        assert_eq!(config.storage.snapshot_retention, 2);
+        assert_eq!(config.storage.documents_backups.max_age_days, None);
+        assert_eq!(config.storage.inverted_backups.max_age_days, Some(7));
+        assert_eq!(config.search.stemmer, StemmerKind::None);
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(config.logging.directory, Some(PathBuf::from("/var/log/blazing")));
+        assert_eq!(config.logging.keep_files, DEFAULT_LOG_KEEP_FILES);
+
+        // Помилка в назві параметра не ігнорується мовчки
+        assert!(Config::parse("[server]\nprot = 9000").is_err());
+        assert!(Config::parse("[integrity]\nalert_threshold = 2.0").unwrap().validate().is_err());
+        assert!(Config::parse("[[classification.categories]]\nname = \"порожня\"").unwrap().validate().is_err());
+        assert_eq!(config.classification.categories.len(), default_categories().len());
+    }
+
+    #[test]
+    fn test_profile_overrides_base_values() {
+        let text = r#"
+            [paths]
+            remote_folder = "/srv/накази"
+            local_cache = "./cache"
+
+            [server]
+            port = 8080
+
+            [profiles.test.paths]
+            remote_folder = "/srv/тестовий-архів"
+
+            [profiles.test.server]
+            port = 8081
+
+            [profiles.prod.indexing]
+            sync_interval_secs = 60
+            "#;
+
+        // Без профілю діють основні параметри
+        let config = Config::parse(text).unwrap();
+        assert_eq!((config.paths.remote_folder.as_str(), config.server.port), ("/srv/накази", 8080));
+
+        let config = Config::parse_profile(text, Some("test")).unwrap();
+        assert_eq!(config.paths.remote_folder, "/srv/тестовий-архів");
+        assert_eq!(config.paths.local_cache, "./cache");
+        assert_eq!(config.server.port, 8081);
+        assert_eq!(config.indexing.sync_interval_secs, 180);
+
+        let error = Config::parse_profile(text, Some("staging")).unwrap_err().to_string();
+        assert!(error.contains("prod, test"), "{error}");
+        // Одруківка в розділі профілю - така сама помилка, як в основних параметрах
+        assert!(Config::parse_profile("[profiles.test.sever]\nport = 1", Some("test")).is_err());
+    }
+
+    #[test]
+    fn test_env_overrides_file_values() {
+        let mut config = Config::parse("[server]\nport = 9000\n[integrity]\nalert_webhook = \"http://a\"").unwrap();
+        let env = [
+            ("BLAZING_SERVER_PORT", "9100"),
+            ("BLAZING_PATHS_LOCAL_CACHE", "/srv/cache"),
+            ("BLAZING_SERVER_OPEN_FILE_PASSWORD", "секрет"),
+            ("BLAZING_INDEXING_EXCLUDED_FOLDERS", ".git, Архів"),
+            ("BLAZING_INDEXING_FILENAME_DATE_FORMATS", "YYYY-MM-DD, dd.mm.yyyy"),
+            ("BLAZING_STORAGE_INVERTED_BACKUPS_MAX_AGE_DAYS", "none"),
+            ("BLAZING_INTEGRITY_ALERT_WEBHOOK", ""),
+            ("BLAZING_SERVER_LOCALE", "en"),
+            ("BLAZING_LOGGING_LEVEL", "warn"),
+            ("RUST_LOG", "info,blazing_search::folder_processor=debug"),
+        ];
+        let var = |name: &str| env.iter().find(|(n, _)| *n == name).map(|(_, v)| v.to_string());
+
+        let applied = config.apply_env(var).unwrap();
+        assert_eq!(applied.len(), 9);
+        assert_eq!(config.server.port, 9100);
+        assert_eq!(config.paths.local_cache, "/srv/cache");
+        assert_eq!(config.server.locale, Locale::En);
+        assert_eq!(config.server.open_file_password_sha256, password_sha256("секрет"));
+        assert_eq!(config.indexing.excluded_folders, vec![".git", "Архів"]);
+        assert_eq!(
+            config.indexing.filename_date_formats,
+            vec![FilenameDateFormat::IsoDate, FilenameDateFormat::DayMonthYear]
+        );
+        assert_eq!(config.storage.inverted_backups.max_age_days, None);
+        // Явний BLAZING_LOGGING_LEVEL перекриває RUST_LOG
+        assert_eq!(config.logging.level, "warn");
+        // Порожня змінна не перекриває значення з файлу
+        assert_eq!(config.integrity.alert_webhook.as_deref(), Some("http://a"));
+
+        let bad = |name: &str| (name == "BLAZING_SERVER_PORT").then(|| "вісім".to_string());
+        assert!(Config::default().apply_env(bad).is_err());
+
+        // Без BLAZING_LOGGING_LEVEL рівень береться з RUST_LOG
+        let rust_log = |name: &str| (name == "RUST_LOG").then(|| "debug".to_string());
+        let mut config = Config::default();
+        config.apply_env(rust_log).unwrap();
+        assert_eq!(config.logging.level, "debug");
+    }
+
+    #[test]
+    fn test_collections_have_own_paths() {
+        let text = r#"
+            [paths]
+            remote_folder = "/srv/накази"
+
+            [search]
+            max_results = 50
+
+            [[collections]]
+            name = "Розпорядження"
+            remote_folder = "/srv/розпорядження"
+            local_cache = "./rozp_cache"
+            index_dir = "./rozp_index"
+            "#;
+        let mut config = Config::parse(text).unwrap();
+        config.validate().unwrap();
+
+        let collections = config.collection_configs();
+        let names: Vec<&str> = collections.iter().map(|c| c.paths.collection_name.as_str()).collect();
+        assert_eq!(names, vec![DEFAULT_COLLECTION_NAME, "Розпорядження"]);
+        // Решта параметрів - спільні для всіх колекцій
+        assert_eq!(collections[1].search.max_results, 50);
+        assert_eq!(collections[1].paths.photo_folder, config.paths.photo_folder);
+
+        config.select_collection("розпорядження").unwrap();
+        assert_eq!(config.paths.remote_folder, "/srv/розпорядження");
+        assert_eq!(config.paths.index_dir, PathBuf::from("./rozp_index"));
+        assert!(config.select_collection("Накази").is_err());
+
+        // Дві колекції з однією папкою індексів перезаписували б одна одній покоління
+        let shared = text.replace("./rozp_index", DEFAULT_INDEX_DIR);
+        assert!(Config::parse(&shared).unwrap().validate().is_err());
+        let duplicate = text.replace("Розпорядження", "накази");
+        assert!(Config::parse(&duplicate).unwrap().validate().is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Перезавантаження конфігурації без перезапуску сервера. Файл перевіряється раз на
+//! CONFIG_WATCH_INTERVAL; безпечні зміни (LIVE_SETTINGS: інтервали, рівень журналу,
+//! параметри пошуку) застосовуються одразу, а зміни, що потребують перезапуску
+//! (шляхи, порт, сховище, стемер), відхиляються з попередженням у журналі.
+
+use crate::config::Config;
+use crate::error::ConfigError;
+use crate::logging;
+use crate::tr;
+use log::{info, warn};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Як часто перевіряється час зміни файлу конфігурації
+pub const CONFIG_WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Параметри (розділ.параметр), які застосовуються без перезапуску.
+/// Решта - зокрема нові параметри, доки їх сюди не додано - потребує перезапуску.
+pub const LIVE_SETTINGS: &[&str] = &[
+    "logging.level",
+    "indexing.sync_interval_secs",
+    "indexing.file_index_refresh_secs",
+    "integrity.interval_secs",
+    "integrity.sample_size",
+    "integrity.alert_threshold",
+    "search.quick_mode_documents",
+    "search.quick_mode_months",
+    "search.proximity_window",
+    "search.query_rewrites",
+    "search.slow_query_threshold_ms",
+    "search.feedback_max_shift",
+    "search.max_results",
+    "search.max_paragraphs",
+    "search.query_timeout_ms",
+    "search.max_concurrent",
+    "search.queue_size",
+    "search.paragraph_filters",
+];
+
+/// Звідки перечитувати конфігурацію: файл, за яким стежимо, і завантаження
+/// (з профілем, змінними середовища і параметрами командного рядка, як при запуску)
+pub struct ConfigSource {
+    pub path: PathBuf,
+    load: Box<dyn Fn() -> Result<Config, ConfigError> + Send>,
+}
+
+impl ConfigSource {
+    pub fn new(path: PathBuf, load: impl Fn() -> Result<Config, ConfigError> + Send + 'static) -> Self {
+        Self { path, load: Box::new(load) }
+    }
+}
+
+/// Що змінилося у файлі відносно робочої конфігурації
+#[derive(Debug)]
+pub struct ReloadPlan {
+    /// Робоча конфігурація з новими значеннями змінених LIVE_SETTINGS
+    pub config: Config,
+    pub applied: Vec<String>,
+    /// Змінені параметри, які почнуть діяти лише після перезапуску
+    pub requires_restart: Vec<String>,
+}
+
+/// Порівнює параметри робочої і нової конфігурації
+pub fn plan_reload(current: &Config, new: &Config) -> ReloadPlan {
+    let current_settings = settings(current);
+    let new_settings = settings(new);
+    let mut merged = serde_json::to_value(current).unwrap_or_default();
+    let mut applied = Vec::new();
+    let mut requires_restart = Vec::new();
+
+    for (key, value) in &new_settings {
+        if current_settings.get(key) == Some(value) {
+            continue;
+        }
+        if !LIVE_SETTINGS.contains(&key.as_str()) {
+            requires_restart.push(key.clone());
+            continue;
+        }
+        if let Some((section, name)) = key.split_once('.') {
+            merged[section][name] = value.clone();
+            applied.push(key.clone());
+        }
+    }
+
+    let config = serde_json::from_value(merged).unwrap_or_else(|_| current.clone());
+    ReloadPlan { config, applied, requires_restart }
+}
+
+/// Параметри конфігурації за ключем "розділ.параметр"
+fn settings(config: &Config) -> BTreeMap<String, Value> {
+    let mut settings = BTreeMap::new();
+    if let Ok(Value::Object(sections)) = serde_json::to_value(config) {
+        for (section, values) in sections {
+            match values {
+                Value::Object(values) => {
+                    for (name, value) in values {
+                        settings.insert(format!("{}.{}", section, name), value);
+                    }
+                }
+                // Масиви розділів ([[collections]]) порівнюються цілком
+                values => {
+                    settings.insert(section, values);
+                }
+            }
+        }
+    }
+    settings
+}
+
+/// Стежить за файлом конфігурації і після кожної зміни надсилає в updates робочу
+/// конфігурацію з застосованими безпечними змінами; рівень журналу змінюється одразу
+pub fn watch_config_file(source: ConfigSource, updates: watch::Sender<Config>) -> JoinHandle<()> {
+    info!("{}", tr!(ConfigWatchStarting, source.path.display()));
+
+    tokio::spawn(async move {
+        let mut timer = tokio::time::interval(CONFIG_WATCH_INTERVAL);
+        let mut last_version = file_version(&source.path);
+
+        loop {
+            timer.tick().await;
+
+            // Файл, якого тимчасово немає (редактор зберігає через перейменування), пропускаємо
+            let version = file_version(&source.path);
+            if version.is_none() || version == last_version {
+                continue;
+            }
+            last_version = version;
+
+            let new_config = match (source.load)() {
+                Ok(config) => config,
+                Err(e) => {
+                    warn!("{}", tr!(ConfigReloadFailed, e));
+                    continue;
+                }
+            };
+
+            let plan = plan_reload(&updates.borrow(), &new_config);
+            if !plan.requires_restart.is_empty() {
+                warn!("{}", tr!(ConfigRestartRequired, plan.requires_restart.join(", ")));
+            }
+            if plan.applied.is_empty() {
+                continue;
+            }
+
+            // Спершу запис у журнал: новий рівень може його приховати
+            info!("{}", tr!(ConfigReloaded, plan.applied.join(", ")));
+            if plan.applied.iter().any(|key| key == "logging.level") {
+                if let Err(e) = logging::set_level(&plan.config.logging.level) {
+                    warn!("{}", tr!(InvalidLogLevel, plan.config.logging.level, e));
+                }
+            }
+            updates.send_replace(plan.config);
+        }
+    })
+}
+
+/// Час зміни і розмір файлу (None - файл недоступний)
+fn file_version(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_live_settings_are_applied() {
+        let current = Config::parse("[server]\nport = 8080\n[indexing]\nsync_interval_secs = 180").unwrap();
+        let new = Config::parse(
+            r#"
+            [paths]
+            index_dir = "/srv/інший-індекс"
+
+            [server]
+            port = 9000
+
+            [indexing]
+            sync_interval_secs = 60
+
+            [search]
+            proximity_window = 30
+
+            [[search.query_rewrites]]
+            pattern = "ЗСУ"
+            replacement = "Збройних Сил України"
+
+            [logging]
+            level = "debug"
+            "#,
+        )
+        .unwrap();
+
+        let plan = plan_reload(&current, &new);
+        assert_eq!(
+            plan.applied,
+            vec!["indexing.sync_interval_secs", "logging.level", "search.proximity_window", "search.query_rewrites"]
+        );
+        assert_eq!(plan.requires_restart, vec!["paths.index_dir", "server.port"]);
+
+        // Шляхи і порт лишаються робочими до перезапуску
+        assert_eq!(plan.config.indexing.sync_interval_secs, 60);
+        assert_eq!(plan.config.search.query_rewrites.len(), 1);
+        assert_eq!(plan.config.server.port, 8080);
+        assert_eq!(plan.config.paths.index_dir, current.paths.index_dir);
+
+        // Файл без змін нічого не застосовує
+        let plan = plan_reload(&current, &current);
+        assert!(plan.applied.is_empty() && plan.requires_restart.is_empty());
+    }
+}
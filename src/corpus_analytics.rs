@@ -0,0 +1,173 @@
+//! Аналітика корпусу для адмінської панелі (GET /api/analytics/corpus): як росте архів
+//! (документи за місяцями), розподіл обсягу документів і частка документів, розібраних
+//! з проблемами. Рахується з покоління в пам'яті пошуку, файли індексу не читаються.
+
+use crate::document_date;
+use crate::document_record::{DocumentIndex, DocumentRecord};
+use crate::index_stats::document_year_month;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Нижні межі кошиків гістограми кількості слів у документі
+const WORD_BUCKETS: [usize; 8] = [0, 50, 100, 250, 500, 1000, 2500, 5000];
+
+/// Документи одного місяця (за датою в назві файлу або шапці документа)
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MonthCount {
+    /// "2024-03"
+    pub month: String,
+    pub documents: usize,
+    pub words: usize,
+}
+
+/// Кошик гістограми: документи з кількістю слів від min_words до max_words включно
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WordsBucket {
+    pub min_words: usize,
+    /// None - останній кошик без верхньої межі
+    pub max_words: Option<usize>,
+    pub documents: usize,
+}
+
+/// Документи, розібрані з проблемами (один документ може мати кілька)
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ParseWarnings {
+    /// Документи з будь-якою проблемою
+    pub documents: usize,
+    /// Частка від усіх документів, 0.0-1.0
+    pub share: f64,
+    /// Текст не вилучено (скан, порожній файл)
+    pub empty_text: usize,
+    /// Дата не знайдена ні в назві файлу, ні в шапці
+    pub no_date: usize,
+    /// Запис не пройшов перевірку цілісності (див. DocumentRecord::integrity_problem)
+    pub integrity: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CorpusAnalytics {
+    pub generation: Option<u64>,
+    pub documents: usize,
+    /// Від найстарішого місяця до найновішого; місяці без документів між ними - з нулем
+    pub per_month: Vec<MonthCount>,
+    /// Документи без місяця (дата невідома або лише рік з папки)
+    pub undated_documents: usize,
+    pub words_histogram: Vec<WordsBucket>,
+    pub average_words: f64,
+    pub average_paragraphs: f64,
+    pub parse_warnings: ParseWarnings,
+}
+
+impl CorpusAnalytics {
+    pub fn from_documents(generation: Option<u64>, doc_index: &DocumentIndex) -> Self {
+        let live: Vec<&DocumentRecord> = doc_index.documents.iter().filter(|d| !d.deleted).collect();
+
+        let mut months: BTreeMap<(u32, u32), (usize, usize)> = BTreeMap::new();
+        let mut undated_documents = 0;
+        let mut histogram = vec![0; WORD_BUCKETS.len()];
+        let mut warnings = ParseWarnings::default();
+
+        for document in &live {
+            match document_year_month(document) {
+                (Some(year), Some(month)) => {
+                    let entry = months.entry((year, month)).or_default();
+                    entry.0 += 1;
+                    entry.1 += document.word_count;
+                }
+                _ => undated_documents += 1,
+            }
+
+            let bucket = WORD_BUCKETS.iter().rposition(|&min| document.word_count >= min).unwrap_or(0);
+            histogram[bucket] += 1;
+
+            let empty_text = document.word_count == 0;
+            let no_date = document_date::file_name_date(&document.file_path).is_none()
+                && document_date::content_date(document).is_none();
+            let integrity = document.integrity_problem().is_some();
+            warnings.empty_text += usize::from(empty_text);
+            warnings.no_date += usize::from(no_date);
+            warnings.integrity += usize::from(integrity);
+            warnings.documents += usize::from(empty_text || no_date || integrity);
+        }
+
+        let documents = live.len();
+        let average = |total: usize| if documents == 0 { 0.0 } else { total as f64 / documents as f64 };
+        warnings.share = average(warnings.documents);
+
+        Self {
+            generation,
+            documents,
+            per_month: fill_months(&months),
+            undated_documents,
+            words_histogram: WORD_BUCKETS
+                .iter()
+                .zip(histogram)
+                .enumerate()
+                .map(|(i, (&min_words, documents))| WordsBucket {
+                    min_words,
+                    max_words: WORD_BUCKETS.get(i + 1).map(|next| next - 1),
+                    documents,
+                })
+                .collect(),
+            average_words: average(live.iter().map(|d| d.word_count).sum()),
+            average_paragraphs: average(live.iter().map(|d| d.paragraph_count).sum()),
+            parse_warnings: warnings,
+        }
+    }
+}
+
+/// Місяці від першого до останнього, пропущені - з нулем (щоб на графіку було видно прогалини)
+fn fill_months(months: &BTreeMap<(u32, u32), (usize, usize)>) -> Vec<MonthCount> {
+    let (Some(&first), Some(&last)) = (months.keys().next(), months.keys().next_back()) else {
+        return Vec::new();
+    };
+
+    let mut result = Vec::new();
+    let (mut year, mut month) = first;
+    while (year, month) <= last {
+        let (documents, words) = months.get(&(year, month)).copied().unwrap_or_default();
+        result.push(MonthCount { month: format!("{:04}-{:02}", year, month), documents, words });
+        (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(path: &str, text: &str) -> DocumentRecord {
+        DocumentRecord::from_texts(path, [text])
+    }
+
+    #[test]
+    fn test_corpus_distributions() {
+        let mut doc_index = DocumentIndex::new();
+        doc_index.push_document(document("cache/Наказ 15.11.2023.docx", "надати відпустку"));
+        doc_index.push_document(document("cache/Наказ 20.01.2024.docx", &"слово ".repeat(120)));
+        doc_index.push_document(document("cache/Наказ 21.01.2024.docx", ""));
+        doc_index.push_document(document("cache/Рапорт.docx", "без дати"));
+        let removed = doc_index.push_document(document("cache/Наказ 01.02.2024.docx", "видалений"));
+        doc_index.tombstone(removed);
+
+        let analytics = CorpusAnalytics::from_documents(Some(7), &doc_index);
+        assert_eq!(analytics.documents, 4);
+        assert_eq!(analytics.undated_documents, 1);
+
+        let months: Vec<(&str, usize)> = analytics.per_month.iter().map(|m| (m.month.as_str(), m.documents)).collect();
+        assert_eq!(months, vec![("2023-11", 1), ("2023-12", 0), ("2024-01", 2)]);
+        assert_eq!(analytics.per_month[2].words, 120);
+
+        let buckets: Vec<(usize, Option<usize>, usize)> =
+            analytics.words_histogram.iter().map(|b| (b.min_words, b.max_words, b.documents)).collect();
+        assert_eq!(buckets[0], (0, Some(49), 3));
+        assert_eq!(buckets[2], (100, Some(249), 1));
+        assert_eq!(buckets.last(), Some(&(5000, None, 0)));
+
+        assert_eq!(analytics.average_paragraphs, 1.0);
+        assert_eq!(analytics.parse_warnings.empty_text, 1);
+        assert_eq!(analytics.parse_warnings.no_date, 1);
+        assert_eq!(analytics.parse_warnings.documents, 2);
+        assert_eq!(analytics.parse_warnings.share, 0.5);
+    }
+}
@@ -0,0 +1,281 @@
+//! Дата наказу для впорядкування документів і результатів: дата в назві файлу (формати -
+//! indexing.filename_date_formats), інакше дата в шапці документа ("05.02.2024" або
+//! "05 лютого 2024"), інакше дата зміни файлу (а якщо й вона невідома - створення).
+//! Без цього накази з назвою без дати опинялися в кінці як "без дати".
+
+use crate::document_record::DocumentRecord;
+use chrono::{DateTime, Datelike, Local};
+use log::warn;
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Формати дати в назвах файлів, обрані при запуску; не задано - DEFAULT_FILENAME_DATE_FORMATS
+static FILENAME_DATE_FORMATS: OnceCell<Vec<FilenameDateFormat>> = OnceCell::new();
+
+/// Формати за замовчуванням, у порядку перевірки
+pub const DEFAULT_FILENAME_DATE_FORMATS: &[FilenameDateFormat] = &[
+    FilenameDateFormat::DayMonthYear,
+    FilenameDateFormat::IsoDate,
+    FilenameDateFormat::Underscored,
+    FilenameDateFormat::ShortYear,
+];
+
+static DAY_MONTH_YEAR_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d{2})\.(\d{2})\.(\d{4})").unwrap());
+static ISO_DATE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d{4})-(\d{2})-(\d{2})").unwrap());
+static UNDERSCORED_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d{2})_(\d{2})_(\d{4})").unwrap());
+/// Дворозрядний рік не має продовжуватись цифрою - інакше це початок DD.MM.YYYY
+static SHORT_YEAR_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\D)(\d{2})\.(\d{2})\.(\d{2})(?:\D|$)").unwrap());
+
+/// Формат дати в назві файлу
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilenameDateFormat {
+    #[serde(rename = "DD.MM.YYYY")]
+    DayMonthYear,
+    #[serde(rename = "YYYY-MM-DD")]
+    IsoDate,
+    #[serde(rename = "DD_MM_YYYY")]
+    Underscored,
+    /// Рік 20YY
+    #[serde(rename = "DD.MM.YY")]
+    ShortYear,
+}
+
+impl std::str::FromStr for FilenameDateFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_uppercase().as_str() {
+            "DD.MM.YYYY" => Ok(Self::DayMonthYear),
+            "YYYY-MM-DD" => Ok(Self::IsoDate),
+            "DD_MM_YYYY" => Ok(Self::Underscored),
+            "DD.MM.YY" => Ok(Self::ShortYear),
+            other => Err(format!(
+                "невідомий формат дати: {} (DD.MM.YYYY, YYYY-MM-DD, DD_MM_YYYY або DD.MM.YY)",
+                other
+            )),
+        }
+    }
+}
+
+impl FilenameDateFormat {
+    /// (рік, місяць, день) з першого збігу формату в назві файлу
+    fn parse(self, file_name: &str) -> Option<(u32, u32, u32)> {
+        let (regex, year, month, day) = match self {
+            Self::DayMonthYear => (&DAY_MONTH_YEAR_REGEX, 3, 2, 1),
+            Self::IsoDate => (&ISO_DATE_REGEX, 1, 2, 3),
+            Self::Underscored => (&UNDERSCORED_REGEX, 3, 2, 1),
+            Self::ShortYear => (&SHORT_YEAR_REGEX, 3, 2, 1),
+        };
+        let captures = regex.captures(file_name)?;
+        let mut year: u32 = captures[year].parse().ok()?;
+        if self == Self::ShortYear {
+            year += 2000;
+        }
+        let month: u32 = captures[month].parse().ok()?;
+        let day: u32 = captures[day].parse().ok()?;
+        ((1..=31).contains(&day) && (1..=12).contains(&month) && year >= 1900).then_some((year, month, day))
+    }
+}
+
+/// Задає формати дати в назвах файлів для всього процесу; повторний виклик нічого не змінює
+pub fn set_filename_date_formats(formats: &[FilenameDateFormat]) {
+    if FILENAME_DATE_FORMATS.set(formats.to_vec()).is_err() && FILENAME_DATE_FORMATS.get().map(Vec::as_slice) != Some(formats) {
+        warn!("⚠️ Формати дати в назвах файлів уже обрано, зміна ігнорується");
+    }
+}
+
+/// Дата з назви файлу: перший формат зі списку, що дав коректну дату
+pub fn file_name_date(file_path: &str) -> Option<(u32, u32, u32)> {
+    let file_name = Path::new(file_path).file_name()?.to_str()?;
+    let formats = FILENAME_DATE_FORMATS.get().map_or(DEFAULT_FILENAME_DATE_FORMATS, Vec::as_slice);
+    formats.iter().find_map(|format| format.parse(file_name))
+}
+
+/// Дата з параметра запиту: ДД.ММ.РРРР або РРРР-ММ-ДД
+pub fn parse_date(value: &str) -> Option<(u32, u32, u32)> {
+    let value = value.trim();
+    [FilenameDateFormat::DayMonthYear, FilenameDateFormat::IsoDate]
+        .iter()
+        .find_map(|format| format.parse(value).filter(|_| value.len() == 10))
+        .filter(|&(year, month, day)| chrono::NaiveDate::from_ymd_opt(year as i32, month, day).is_some())
+}
+
+/// Дата в шапці документа: "05.02.2024" або "05 лютого 2024"
+static CONTENT_DATE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\b(\d{1,2})(?:\.(\d{2})\.|\s+(січня|лютого|березня|квітня|травня|червня|липня|серпня|вересня|жовтня|листопада|грудня)\s+)(\d{4})\b",
+    )
+    .unwrap()
+});
+
+const MONTHS_GENITIVE: [&str; 12] = [
+    "січня", "лютого", "березня", "квітня", "травня", "червня",
+    "липня", "серпня", "вересня", "жовтня", "листопада", "грудня",
+];
+
+/// Скільки перших абзаців переглядати в пошуках дати (шапка наказу)
+const CONTENT_DATE_PARAGRAPHS: usize = 10;
+
+/// Звідки взято дату
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DateSource {
+    FileName,
+    Content,
+    /// Дата зміни файлу - не дата наказу, лише для впорядкування
+    Modified,
+    /// Дата створення файлу (якщо дата зміни невідома) - теж лише для впорядкування
+    Created,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocumentDate {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+    pub source: DateSource,
+}
+
+impl DocumentDate {
+    fn new((year, month, day): (u32, u32, u32), source: DateSource) -> Self {
+        Self { year, month, day, source }
+    }
+
+    /// Дата з назви файлу (див. file_name_date)
+    pub fn from_file_name(file_path: &str) -> Option<Self> {
+        file_name_date(file_path).map(|date| Self::new(date, DateSource::FileName))
+    }
+
+    /// (рік, місяць, день) - для порівняння
+    pub fn ymd(&self) -> (u32, u32, u32) {
+        (self.year, self.month, self.day)
+    }
+
+    /// ДД.ММ.РРРР
+    pub fn formatted(&self) -> String {
+        format!("{:02}.{:02}.{}", self.day, self.month, self.year)
+    }
+
+    /// Дата наказу (з назви файлу чи шапки) у форматі ДД.ММ.РРРР; дата файлу - None
+    pub fn order_date(&self) -> Option<String> {
+        matches!(self.source, DateSource::FileName | DateSource::Content).then(|| self.formatted())
+    }
+}
+
+/// Дата документа: визначена при індексації (DocumentRecord::date) або, для старих
+/// індексів, обчислена зараз (див. detect_document_date)
+pub fn document_date(document: &DocumentRecord) -> Option<DocumentDate> {
+    document.date.or_else(|| detect_document_date(document))
+}
+
+/// Дата документа: з назви файлу, з шапки, дата зміни або створення файлу
+pub fn detect_document_date(document: &DocumentRecord) -> Option<DocumentDate> {
+    if let Some(date) = DocumentDate::from_file_name(&document.file_path) {
+        return Some(date);
+    }
+    if let Some(date) = content_date(document) {
+        return Some(DocumentDate::new(date, DateSource::Content));
+    }
+    if let Some(date) = timestamp_date(document.last_modified) {
+        return Some(DocumentDate::new(date, DateSource::Modified));
+    }
+    timestamp_date(document.created).map(|date| DocumentDate::new(date, DateSource::Created))
+}
+
+/// Перша дата в перших абзацах документа
+pub fn content_date(document: &DocumentRecord) -> Option<(u32, u32, u32)> {
+    let texts: Box<dyn Iterator<Item = &str>> = if document.paragraphs.is_empty() {
+        Box::new(document.content.iter().map(String::as_str))
+    } else {
+        Box::new(document.paragraphs.iter().map(|p| p.text.as_str()))
+    };
+
+    texts.take(CONTENT_DATE_PARAGRAPHS).find_map(|text| {
+        CONTENT_DATE_REGEX.captures_iter(text).find_map(|captures| {
+            let day: u32 = captures[1].parse().ok()?;
+            let month = match (captures.get(2), captures.get(3)) {
+                (Some(digits), _) => digits.as_str().parse().ok()?,
+                (None, Some(name)) => {
+                    let name = name.as_str().to_lowercase();
+                    MONTHS_GENITIVE.iter().position(|m| *m == name)? as u32 + 1
+                }
+                (None, None) => return None,
+            };
+            let year: u32 = captures[4].parse().ok()?;
+            ((1..=31).contains(&day) && (1..=12).contains(&month) && year >= 1900).then_some((year, month, day))
+        })
+    })
+}
+
+/// Дата з Unix timestamp (місцевий час); 0 - невідома
+fn timestamp_date(timestamp: u64) -> Option<(u32, u32, u32)> {
+    if timestamp == 0 {
+        return None;
+    }
+    let time = DateTime::from_timestamp(timestamp as i64, 0)?.with_timezone(&Local);
+    Some((time.year() as u32, time.month(), time.day()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(file_path: &str, paragraphs: &[&str], last_modified: u64, created: u64) -> DocumentRecord {
+        DocumentRecord { last_modified, created, ..DocumentRecord::from_texts(file_path, paragraphs.iter().copied()) }
+    }
+
+    #[test]
+    fn test_file_name_then_content_then_file_times() {
+        let header = ["НАКАЗ", "05 лютого 2024 року   м. Київ   №16"];
+        // Полудень UTC - та сама дата в будь-якому часовому поясі від -11 до +11
+        let (feb_5, mar_1) = (1_707_134_400, 1_709_294_400);
+
+        let date = document_date(&document("2024/Наказ №16 від 06.02.2024.docx", &header, mar_1, mar_1)).unwrap();
+        assert_eq!((date.ymd(), date.source), ((2024, 2, 6), DateSource::FileName));
+
+        let date = document_date(&document("2024/Наказ №16.docx", &header, mar_1, mar_1)).unwrap();
+        assert_eq!((date.ymd(), date.source), ((2024, 2, 5), DateSource::Content));
+        assert_eq!(date.order_date().as_deref(), Some("05.02.2024"));
+
+        let date = document_date(&document("2024/Наказ №16.docx", &["НАКАЗ"], feb_5, mar_1)).unwrap();
+        assert_eq!((date.ymd(), date.source), ((2024, 2, 5), DateSource::Modified));
+        assert_eq!((date.order_date(), date.formatted().as_str()), (None, "05.02.2024"));
+
+        let date = document_date(&document("2024/Наказ №16.docx", &["НАКАЗ"], 0, mar_1)).unwrap();
+        assert_eq!((date.ymd(), date.source), ((2024, 3, 1), DateSource::Created));
+
+        assert_eq!(document_date(&document("2024/Наказ №16.docx", &["НАКАЗ"], 0, 0)), None);
+    }
+
+    #[test]
+    fn test_request_dates() {
+        assert_eq!(parse_date(" 05.03.2024 "), Some((2024, 3, 5)));
+        assert_eq!(parse_date("2024-03-05"), Some((2024, 3, 5)));
+        for invalid in ["31.02.2024", "5.3.2024", "05.03.24", "2024-03-05T10:00", "вчора"] {
+            assert_eq!(parse_date(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_file_name_date_formats() {
+        for file_path in [
+            "2024/Наказ №16 від 05.02.2024.docx",
+            "2024/Наказ №16 від 2024-02-05.docx",
+            "2024/Наказ_16_05_02_2024.docx",
+            "2024/Наказ №16 від 05.02.24.docx",
+            "2024/05.02.24 наказ.docx",
+        ] {
+            assert_eq!(file_name_date(file_path), Some((2024, 2, 5)), "{}", file_path);
+        }
+        // Неможлива дата одного формату не заважає наступному
+        assert_eq!(file_name_date("2024/Наказ 99.99.2024 від 2024-02-05.docx"), Some((2024, 2, 5)));
+        assert_eq!(file_name_date("2024/Наказ №16 від 05.02.2024 (1).docx"), Some((2024, 2, 5)));
+        assert_eq!(file_name_date("2024/Наказ №1605022024.docx"), None);
+        assert_eq!(FilenameDateFormat::DayMonthYear.parse("05.02.2024"), Some((2024, 2, 5)));
+        assert_eq!(FilenameDateFormat::ShortYear.parse("05.02.2024"), None);
+        assert!("dd.mm.yy".parse::<FilenameDateFormat>().is_ok());
+        assert!("MM/DD/YYYY".parse::<FilenameDateFormat>().is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! Порівняння двох проіндексованих документів за абзацами - наприклад, наказу і пізнішого
+//! наказу про внесення змін до нього (GET /api/diff?a=...&b=...). Документи ділляться на
+//! пункти за номерами на початку абзаців ("3.", "2.1."); пункти з однаковими номерами
+//! порівнюються між собою, абзаци в межах пункту - за найдовшою спільною підпослідовністю.
+//! Текст до першого пункту (шапка, преамбула) порівнюється як окремий пункт без номера.
+
+use crate::document_date;
+use crate::document_record::{DocumentIndex, DocumentRecord};
+use crate::result_export::paragraph_section;
+use serde::Serialize;
+
+/// Що сталося з абзацом чи пунктом у документі b порівняно з документом a
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffStatus {
+    Unchanged,
+    Changed,
+    Added,
+    Removed,
+}
+
+/// Рядок порівняння: абзац a, абзац b або обидва (змінений абзац)
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ParagraphDiff {
+    pub status: DiffStatus,
+    pub a: Option<String>,
+    pub b: Option<String>,
+}
+
+/// Пункт наказу в обох документах
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SectionDiff {
+    /// Номер пункту; None - текст до першого пункту
+    pub section: Option<String>,
+    pub status: DiffStatus,
+    pub paragraphs: Vec<ParagraphDiff>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct DiffSummary {
+    pub unchanged: usize,
+    pub changed: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DiffDocument {
+    pub file_path: String,
+    pub file_name: String,
+    /// Дата наказу (ДД.ММ.РРРР)
+    pub date: Option<String>,
+}
+
+impl From<&DocumentRecord> for DiffDocument {
+    fn from(document: &DocumentRecord) -> Self {
+        Self {
+            file_path: document.file_path.clone(),
+            file_name: document.file_name.clone(),
+            date: document_date::document_date(document).map(|date| date.formatted()),
+        }
+    }
+}
+
+/// Порівняння документів за пунктами; summary рахує пункти
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DocumentDiff {
+    pub a: DiffDocument,
+    pub b: DiffDocument,
+    pub summary: DiffSummary,
+    pub sections: Vec<SectionDiff>,
+}
+
+impl DocumentDiff {
+    pub fn compare(a: &DocumentRecord, b: &DocumentRecord) -> Self {
+        let texts = |document: &DocumentRecord| -> Vec<String> {
+            document
+                .get_paragraphs()
+                .into_iter()
+                .map(|p| p.text.trim().to_string())
+                .filter(|text| !text.is_empty())
+                .collect()
+        };
+        let sections = compare_sections(split_sections(texts(a)), split_sections(texts(b)));
+
+        let mut summary = DiffSummary::default();
+        for section in &sections {
+            match section.status {
+                DiffStatus::Unchanged => summary.unchanged += 1,
+                DiffStatus::Changed => summary.changed += 1,
+                DiffStatus::Added => summary.added += 1,
+                DiffStatus::Removed => summary.removed += 1,
+            }
+        }
+        Self {
+            a: a.into(),
+            b: b.into(),
+            summary,
+            sections,
+        }
+    }
+
+    /// Застосовує перетворення до всіх текстів (маскування чутливих даних)
+    pub fn map_texts(&mut self, f: impl Fn(&str) -> String) {
+        for paragraph in self.sections.iter_mut().flat_map(|s| &mut s.paragraphs) {
+            for text in [&mut paragraph.a, &mut paragraph.b].into_iter().flatten() {
+                *text = f(text);
+            }
+        }
+    }
+}
+
+/// Документ за ідентифікатором (doc_id) або шляхом у кеші
+pub fn find_document<'a>(doc_index: &'a DocumentIndex, id: &str) -> Option<&'a DocumentRecord> {
+    let id = id.trim();
+    let by_id = id.parse().ok().and_then(|doc_id| doc_index.slot_of(doc_id));
+    let document = match by_id {
+        Some(slot) => doc_index.documents.get(slot),
+        None => doc_index.documents.iter().find(|d| !d.deleted && d.file_path == id),
+    };
+    document.filter(|d| !d.deleted)
+}
+
+type Section = (Option<String>, Vec<String>);
+
+/// Абзаци за пунктами в порядку документа. Повторний номер (цитата пункту іншого наказу)
+/// продовжує попередній пункт, щоб ключі лишалися унікальними
+fn split_sections(paragraphs: Vec<String>) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    for paragraph in paragraphs {
+        let number = paragraph_section(&paragraph).map(str::to_string);
+        let starts_section = number.is_some() && !sections.iter().any(|(key, _)| *key == number);
+        match sections.last_mut() {
+            Some((_, texts)) if !starts_section => texts.push(paragraph),
+            _ => sections.push((if starts_section { number } else { None }, vec![paragraph])),
+        }
+    }
+    sections
+}
+
+/// Пункти обох документів у порядку a; пункти лише з b - після попереднього пункту b
+/// (і після видалених пунктів, що йдуть за ним)
+fn compare_sections(a: Vec<Section>, b: Vec<Section>) -> Vec<SectionDiff> {
+    let mut result: Vec<SectionDiff> = a
+        .iter()
+        .map(|(key, texts_a)| {
+            let texts_b = b.iter().find(|(k, _)| k == key).map(|(_, texts)| texts.as_slice());
+            section_diff(key.clone(), texts_a, texts_b.unwrap_or_default())
+        })
+        .collect();
+
+    let mut insert_at = 0;
+    for (key, texts_b) in &b {
+        match result.iter().position(|s| s.section == *key) {
+            Some(i) => insert_at = i + 1,
+            None => {
+                // Видалені пункти a - перед доданими замість них
+                while result.get(insert_at).is_some_and(|s| s.status == DiffStatus::Removed) {
+                    insert_at += 1;
+                }
+                result.insert(insert_at, section_diff(key.clone(), &[], texts_b));
+                insert_at += 1;
+            }
+        }
+    }
+    result
+}
+
+fn section_diff(section: Option<String>, a: &[String], b: &[String]) -> SectionDiff {
+    let paragraphs = paragraph_diff(a, b);
+    let status = if b.is_empty() {
+        DiffStatus::Removed
+    } else if a.is_empty() {
+        DiffStatus::Added
+    } else if paragraphs.iter().all(|p| p.status == DiffStatus::Unchanged) {
+        DiffStatus::Unchanged
+    } else {
+        DiffStatus::Changed
+    };
+    SectionDiff { section, status, paragraphs }
+}
+
+/// Різниця абзаців за найдовшою спільною підпослідовністю. Видалені й додані абзаци між
+/// однаковими попарно стають зміненими (нова редакція абзацу)
+fn paragraph_diff(a: &[String], b: &[String]) -> Vec<ParagraphDiff> {
+    // lcs[i][j] - довжина спільної підпослідовності a[i..] і b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut result = Vec::new();
+    let (mut removed, mut added): (Vec<&String>, Vec<&String>) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            flush_changes(&mut result, &mut removed, &mut added);
+            result.push(ParagraphDiff {
+                status: DiffStatus::Unchanged,
+                a: Some(a[i].clone()),
+                b: Some(b[j].clone()),
+            });
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(&b[j]);
+            j += 1;
+        } else {
+            removed.push(&a[i]);
+            i += 1;
+        }
+    }
+    flush_changes(&mut result, &mut removed, &mut added);
+    result
+}
+
+fn flush_changes(result: &mut Vec<ParagraphDiff>, removed: &mut Vec<&String>, added: &mut Vec<&String>) {
+    for k in 0..removed.len().max(added.len()) {
+        let (a, b) = (removed.get(k).map(|t| t.to_string()), added.get(k).map(|t| t.to_string()));
+        let status = match (&a, &b) {
+            (Some(_), Some(_)) => DiffStatus::Changed,
+            (Some(_), None) => DiffStatus::Removed,
+            _ => DiffStatus::Added,
+        };
+        result.push(ParagraphDiff { status, a, b });
+    }
+    removed.clear();
+    added.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(path: &str, paragraphs: &[&str]) -> DocumentRecord {
+        DocumentRecord::from_texts(path, paragraphs.iter().copied())
+    }
+
+    #[test]
+    fn test_sections_aligned_by_number() {
+        let order = document(
+            "Наказ №12 від 05.02.2024.docx",
+            &[
+                "НАКАЗ №12",
+                "1. Надати відпустку:",
+                "солдату Петренку П.П. з 05.02.2024",
+                "солдату Іваненку І.І. з 06.02.2024",
+                "2. Виплатити грошове забезпечення.",
+                "3. Контроль покласти на начальника штабу.",
+            ],
+        );
+        let amended = document(
+            "Наказ №12 від 05.02.2024 (зміни).docx",
+            &[
+                "НАКАЗ №12",
+                "1. Надати відпустку:",
+                "солдату Петренку П.П. з 07.02.2024",
+                "солдату Іваненку І.І. з 06.02.2024",
+                "солдату Коваленку К.К. з 08.02.2024",
+                "2.1. Начальнику фінансової служби забезпечити виплату.",
+                "3. Контроль покласти на начальника штабу.",
+            ],
+        );
+
+        let diff = DocumentDiff::compare(&order, &amended);
+        assert_eq!(diff.a.date.as_deref(), Some("05.02.2024"));
+        let sections: Vec<_> = diff.sections.iter().map(|s| (s.section.as_deref(), s.status)).collect();
+        assert_eq!(
+            sections,
+            vec![
+                (None, DiffStatus::Unchanged),
+                (Some("1"), DiffStatus::Changed),
+                (Some("2"), DiffStatus::Removed),
+                (Some("2.1"), DiffStatus::Added),
+                (Some("3"), DiffStatus::Unchanged),
+            ]
+        );
+        assert_eq!(diff.summary, DiffSummary { unchanged: 2, changed: 1, added: 1, removed: 1 });
+
+        let statuses: Vec<_> = diff.sections[1].paragraphs.iter().map(|p| p.status).collect();
+        assert_eq!(
+            statuses,
+            vec![DiffStatus::Unchanged, DiffStatus::Changed, DiffStatus::Unchanged, DiffStatus::Added]
+        );
+        assert_eq!(diff.sections[1].paragraphs[1].a.as_deref(), Some("солдату Петренку П.П. з 05.02.2024"));
+        assert_eq!(diff.sections[1].paragraphs[1].b.as_deref(), Some("солдату Петренку П.П. з 07.02.2024"));
+
+        let mut masked = DocumentDiff::compare(&order, &order);
+        assert!(masked.sections.iter().all(|s| s.status == DiffStatus::Unchanged));
+        masked.map_texts(|text| text.replace("Петренку", "[ПІБ]"));
+        assert_eq!(masked.sections[1].paragraphs[1].b.as_deref(), Some("солдату [ПІБ] П.П. з 05.02.2024"));
+    }
+}
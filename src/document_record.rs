@@ -1,8 +1,14 @@
+use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::path::Path;
 use std::fs;
 use std::time::SystemTime;
-use std::io::{BufReader, BufWriter};
+use crate::error::{IndexError, ParseError};
+use crate::index_checksum;
+use crate::near_duplicates;
+use crate::document_date::{detect_document_date, DocumentDate};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Paragraph {
@@ -40,77 +46,144 @@ pub struct DocumentRecord {
     pub paragraphs: Vec<Paragraph>, // Нова структура з інформацією про розриви
     pub word_count: usize,
     pub paragraph_count: usize,
+    /// Стабільний ідентифікатор документа: не змінюється при заміні файлу чи ущільненні індексу
+    #[serde(default)]
+    pub doc_id: u64,
+    /// Tombstone: файл видалено, слот зберігається до ущільнення, щоб не зсувати позиції
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+    /// SHA-256 проіндексованого вмісту: перевіряється при завантаженні, щоб пошкодження
+    /// великого JSON локалізувати до окремих документів і перепарсити лише їх
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Вид наказу (кадровий, відрядження ...), визначений класифікатором під час індексації
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// SimHash вмісту для пошуку майже однакових наказів (див. near_duplicates)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub simhash: Option<u64>,
+    /// Формат файлу ("docx") у нижньому регістрі; у старих індексах немає - див. file_type()
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_type: Option<String>,
+    /// Дата наказу, визначена при додаванні в DocumentIndex (див. document_date): фільтр за
+    /// датами і впорядкування не розбирають назву й шапку щоразу. У старих індексах немає
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<DocumentDate>,
 }
 
 impl DocumentRecord {
-    pub fn new(
-        file_path: String,
-        content: Vec<String>,
-    ) -> Result<Self, String> {
-        // Конвертуємо старий формат в новий
-        let paragraphs: Vec<Paragraph> = content.iter()
-            .map(|text| Paragraph::new(text.clone()))
-            .collect();
-
-        Self::new_with_paragraphs(file_path, paragraphs)
-    }
-
     pub fn new_with_paragraphs(
         file_path: String,
         paragraphs: Vec<Paragraph>,
-    ) -> Result<Self, String> {
-        let path = Path::new(&file_path);
-
+    ) -> Result<Self, ParseError> {
         let metadata = fs::metadata(&file_path)
-            .map_err(|e| format!("Помилка отримання метаданих файлу {}: {}", file_path, e))?;
+            .map_err(|e| ParseError::Metadata { path: file_path.clone(), source: e })?;
+
+        let timestamp = |time: std::io::Result<SystemTime>| {
+            time.unwrap_or(SystemTime::UNIX_EPOCH)
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        };
+
+        let mut record = Self::from_paragraphs(file_path, paragraphs);
+        record.file_size = metadata.len();
+        record.last_modified = timestamp(metadata.modified());
+        record.created = timestamp(metadata.created());
+        Ok(record)
+    }
 
-        let file_name = path.file_name()
+    /// Запис без звернення до файлу (розмір і час модифікації - нульові): для індексів,
+    /// побудованих у пам'яті (див. index_builder)
+    pub fn from_paragraphs(file_path: String, paragraphs: Vec<Paragraph>) -> Self {
+        let file_name = Path::new(&file_path)
+            .file_name()
             .and_then(|name| name.to_str())
             .unwrap_or("unknown")
             .to_string();
 
-        let last_modified = metadata.modified()
-            .unwrap_or(SystemTime::UNIX_EPOCH)
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-
-        let created = metadata.created()
-            .unwrap_or(SystemTime::UNIX_EPOCH)
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-
         let word_count = paragraphs.iter()
             .map(|p| p.text.split_whitespace().count())
             .sum();
 
         let paragraph_count = paragraphs.len();
+        let file_type = file_type_of(&file_path);
 
         // Зберігаємо також старий формат для зворотної сумісності
         let content: Vec<String> = paragraphs.iter()
             .map(|p| p.text.clone())
             .collect();
 
-        Ok(DocumentRecord {
+        DocumentRecord {
             file_path,
             file_name,
-            file_size: metadata.len(),
-            last_modified,
-            created,
+            file_size: 0,
+            last_modified: 0,
+            created: 0,
             content,
             paragraphs,
             word_count,
             paragraph_count,
-        })
+            doc_id: 0, // Призначається при додаванні в DocumentIndex
+            deleted: false,
+            content_hash: None, // Обчислюється при додаванні в DocumentIndex
+            category: None, // Визначається класифікатором (FolderProcessor)
+            simhash: None, // Обчислюється при додаванні в DocumentIndex
+            file_type,
+            date: None, // Визначається при додаванні в DocumentIndex
+        }
     }
 
-    /// Повертає текст параграфа за індексом (для зворотної сумісності)
-    pub fn get_paragraph_text(&self, index: usize) -> Option<&str> {
-        if !self.paragraphs.is_empty() {
-            self.paragraphs.get(index).map(|p| p.text.as_str())
-        } else {
-            self.content.get(index).map(|s| s.as_str())
+    /// Запис з текстів абзаців (див. from_paragraphs): для індексів у пам'яті і тестів
+    pub fn from_texts<T: Into<String>>(file_path: &str, texts: impl IntoIterator<Item = T>) -> Self {
+        let paragraphs = texts.into_iter().map(|text| Paragraph::new(text.into())).collect();
+        Self::from_paragraphs(file_path.to_string(), paragraphs)
+    }
+
+    /// Формат файлу: збережений при індексації або, для старих індексів, за розширенням
+    pub fn file_type(&self) -> Option<String> {
+        self.file_type.clone().or_else(|| file_type_of(&self.file_path))
+    }
+
+    /// SHA-256 вмісту параграфів (те, що потрапляє в інвертований індекс)
+    pub fn compute_content_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        for paragraph in &self.content {
+            hasher.update((paragraph.len() as u64).to_le_bytes());
+            hasher.update(paragraph.as_bytes());
+        }
+
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Перевіряє запис документа: узгодженість полів і збіг контрольної суми вмісту.
+    /// Повертає опис проблеми або None, якщо запис цілий.
+    pub fn integrity_problem(&self) -> Option<String> {
+        if self.deleted {
+            return None;
+        }
+
+        if self.content.len() != self.paragraph_count {
+            return Some(format!(
+                "кількість параграфів {} не збігається з paragraph_count {}",
+                self.content.len(),
+                self.paragraph_count
+            ));
+        }
+
+        if !self.paragraphs.is_empty() && self.paragraphs.len() != self.content.len() {
+            return Some("структура параграфів не збігається з вмістом".to_string());
+        }
+
+        match &self.content_hash {
+            Some(expected) if *expected != self.compute_content_hash() => {
+                Some("контрольна сума вмісту не збігається".to_string())
+            }
+            _ => None,
         }
     }
 
@@ -127,12 +200,54 @@ impl DocumentRecord {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Результат перерахунку статистики індексу документів
+#[derive(Debug, Clone, Copy)]
+pub struct StatsRecount {
+    pub documents_was: usize,
+    pub documents_now: usize,
+    pub words_was: usize,
+    pub words_now: usize,
+}
+
+impl StatsRecount {
+    pub fn changed(&self) -> bool {
+        self.documents_was != self.documents_now || self.words_was != self.words_now
+    }
+}
+
+/// Формат файлу за розширенням у нижньому регістрі ("Наказ.DOCX" -> "docx")
+pub fn file_type_of(file_path: &str) -> Option<String> {
+    Path::new(file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DocumentIndex {
     pub documents: Vec<DocumentRecord>,
-    pub total_documents: usize,
+    pub total_documents: usize, // Кількість живих (не видалених) документів
     pub total_words: usize,
     pub indexed_at: u64, // Unix timestamp
+    /// Наступний вільний ідентифікатор документа
+    #[serde(default)]
+    pub next_doc_id: u64,
+    /// Час останнього ущільнення (видалення tombstone-слотів)
+    #[serde(default)]
+    pub last_compacted_at: u64, // Unix timestamp
+    /// Покоління, з яким записано файл (0 - старий файл без номера).
+    /// Записується менеджером індексів при збереженні покоління, див. GenerationStamped.
+    #[serde(default, skip_serializing)]
+    pub generation: u64,
+    /// Таблиця doc_id -> слот у documents (будується при завантаженні)
+    #[serde(skip)]
+    id_to_slot: HashMap<u64, usize>,
+}
+
+impl Default for DocumentIndex {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DocumentIndex {
@@ -147,144 +262,300 @@ impl DocumentIndex {
             total_documents: 0,
             total_words: 0,
             indexed_at,
+            next_doc_id: 1,
+            last_compacted_at: indexed_at,
+            generation: 0,
+            id_to_slot: HashMap::new(),
         }
     }
 
-    pub fn save_to_file(&self, path: &str) -> Result<(), String> {
-        println!("💾 Збереження індексу в файл: {}", path);
-
-        // Атомарне збереження через тимчасовий файл
-        let temp_path = format!("{}.tmp", path);
-        let backup_path = format!("{}.backup", path);
+    /// Призначає ідентифікатори документам зі старих індексів та перебудовує таблицю doc_id -> слот
+    pub fn rebuild_id_map(&mut self) {
+        let max_id = self.documents.iter().map(|d| d.doc_id).max().unwrap_or(0);
+        self.next_doc_id = self.next_doc_id.max(max_id + 1);
 
-        // Створюємо резервну копію існуючого файлу якщо він є
-        if Path::new(path).exists() {
-            fs::copy(path, &backup_path)
-                .map_err(|e| format!("Помилка створення резервної копії: {}", e))?;
+        for document in self.documents.iter_mut().filter(|d| d.doc_id == 0) {
+            document.doc_id = self.next_doc_id;
+            self.next_doc_id += 1;
         }
 
-        // Зберігаємо в тимчасовий файл
-        {
-            let file = std::fs::File::create(&temp_path)
-                .map_err(|e| format!("Помилка створення тимчасового файлу: {}", e))?;
-
-            let writer = BufWriter::with_capacity(1024 * 1024, file); // 1MB буфер
-
-            serde_json::to_writer_pretty(writer, self)
-                .map_err(|e| {
-                    // Видаляємо пошкоджений тимчасовий файл
-                    let _ = fs::remove_file(&temp_path);
-                    format!("Помилка серіалізації JSON: {}", e)
-                })?;
-        } // writer закривається тут, дані записуються на диск
-
-        // Атомарно переміщуємо тимчасовий файл на місце основного
-        fs::rename(&temp_path, path)
-            .map_err(|e| {
-                // Якщо переміщення не вдалося, спробуємо відновити з резервної копії
-                if Path::new(&backup_path).exists() {
-                    let _ = fs::rename(&backup_path, path);
-                }
-                format!("Помилка переміщення тимчасового файлу: {}", e)
-            })?;
+        self.id_to_slot = self
+            .documents
+            .iter()
+            .enumerate()
+            .map(|(slot, d)| (d.doc_id, slot))
+            .collect();
+    }
+
+    /// Слот документа з указаним ідентифікатором
+    pub fn slot_of(&self, doc_id: u64) -> Option<usize> {
+        self.id_to_slot.get(&doc_id).copied()
+    }
+
+    /// Додає новий документ у кінець і повертає його слот
+    pub fn push_document(&mut self, mut document: DocumentRecord) -> usize {
+        document.content_hash = Some(document.compute_content_hash());
+        document.simhash = near_duplicates::simhash(&document.content);
+        document.date = detect_document_date(&document);
+        document.doc_id = self.next_doc_id;
+        self.next_doc_id += 1;
+
+        let slot = self.documents.len();
+        self.id_to_slot.insert(document.doc_id, slot);
+        self.documents.push(document);
+        slot
+    }
+
+    /// Замінює документ у слоті, зберігаючи його ідентифікатор
+    pub fn replace_document(&mut self, slot: usize, mut document: DocumentRecord) {
+        document.content_hash = Some(document.compute_content_hash());
+        document.simhash = near_duplicates::simhash(&document.content);
+        document.date = detect_document_date(&document);
+        document.doc_id = self.documents[slot].doc_id;
+        self.documents[slot] = document;
+    }
 
-        // Видаляємо резервну копію після успішного збереження
-        if Path::new(&backup_path).exists() {
-            let _ = fs::remove_file(&backup_path);
+    /// Записує готовий документ (з doc_id і контрольними сумами, див. IndexDelta) у слот;
+    /// слот одразу після останнього додає документ у кінець
+    pub fn put_document(&mut self, slot: usize, document: DocumentRecord) {
+        self.id_to_slot.insert(document.doc_id, slot);
+        if slot == self.documents.len() {
+            self.documents.push(document);
+        } else {
+            self.documents[slot] = document;
         }
+    }
 
-        println!("✅ Індекс успішно збережено");
-        Ok(())
+    /// Позначає документ видаленим (O(1)): вміст звільняється, слот лишається до ущільнення.
+    /// Записи інвертованого індексу на цей слот ігноруються пошуком до ущільнення.
+    pub fn tombstone(&mut self, slot: usize) {
+        let document = &mut self.documents[slot];
+        document.deleted = true;
+        document.content.clear();
+        document.paragraphs.clear();
+        document.word_count = 0;
+        document.paragraph_count = 0;
+        document.content_hash = None;
+        document.simhash = None;
+        document.date = None;
     }
 
-    pub fn load_from_file(file_path: &str) -> Result<Self, String> {
-        println!("📂 Завантаження індексу з файлу: {}", file_path);
-
-        let backup_path = format!("{}.backup", file_path);
-
-        // Спочатку пробуємо завантажити основний файл
-        let index = Self::try_load_file(file_path);
-        
-        match index {
-            Ok(idx) => {
-                // Перевіряємо цілісність індексу
-                if Self::validate_index(&idx) {
-                    println!("✅ Завантажено {} документів", idx.total_documents);
-                    return Ok(idx);
-                } else {
-                    println!("⚠️  Основний індекс пошкоджений, спробуємо резервну копію...");
+    /// Перевіряє кожен документ (див. DocumentRecord::integrity_problem). Пошкоджені записи
+    /// позначаються для повторного парсингу: last_modified = 0, тому наступна індексація
+    /// перечитає саме ці файли і перебудує їхні записи в інвертованому індексі.
+    /// Документам зі старих індексів без контрольної суми вона обчислюється, без дати - визначається.
+    /// Повертає слоти пошкоджених документів.
+    pub fn verify_documents(&mut self) -> Vec<usize> {
+        let mut corrupted = Vec::new();
+
+        for (slot, document) in self.documents.iter_mut().enumerate() {
+            if let Some(problem) = document.integrity_problem() {
+                warn!(
+                    "⚠️  Документ {} ({}) пошкоджений: {} - last_modified {} -> 0, файл буде перепарсено",
+                    slot, document.file_path, problem, document.last_modified
+                );
+                document.last_modified = 0;
+                corrupted.push(slot);
+            } else if !document.deleted {
+                if document.content_hash.is_none() {
+                    document.content_hash = Some(document.compute_content_hash());
+                }
+                if document.date.is_none() {
+                    document.date = detect_document_date(document);
                 }
-            }
-            Err(e) => {
-                println!("⚠️  Помилка завантаження основного індексу: {}", e);
-                println!("🔄 Спробуємо резервну копію...");
             }
         }
 
-        // Якщо основний файл пошкоджений, пробуємо резервну копію
-        if Path::new(&backup_path).exists() {
-            match Self::try_load_file(&backup_path) {
-                Ok(backup_idx) => {
-                    if Self::validate_index(&backup_idx) {
-                        println!("✅ Завантажено з резервної копії {} документів", backup_idx.total_documents);
-                        // Відновлюємо основний файл з резервної копії
-                        if let Err(e) = fs::copy(&backup_path, file_path) {
-                            println!("⚠️  Не вдалося відновити основний файл: {}", e);
-                        }
-                        return Ok(backup_idx);
-                    } else {
-                        println!("❌ Резервна копія також пошкоджена");
-                    }
-                }
-                Err(e) => {
-                    println!("❌ Помилка завантаження резервної копії: {}", e);
-                }
+        corrupted
+    }
+
+    /// Перераховує загальну статистику зі списку документів.
+    /// Єдине місце, де змінюються total_documents та total_words - інкрементні
+    /// +/- в різних місцях накопичували розбіжності (напр. при помилці парсингу).
+    pub fn recount(&mut self) -> StatsRecount {
+        let recount = StatsRecount {
+            documents_was: self.total_documents,
+            documents_now: self.live_count(),
+            words_was: self.total_words,
+            words_now: self.documents.iter().filter(|d| !d.deleted).map(|d| d.word_count).sum(),
+        };
+
+        self.total_documents = recount.documents_now;
+        self.total_words = recount.words_now;
+        recount
+    }
+
+    pub fn live_count(&self) -> usize {
+        self.documents.iter().filter(|d| !d.deleted).count()
+    }
+
+    pub fn tombstone_count(&self) -> usize {
+        self.documents.iter().filter(|d| d.deleted).count()
+    }
+
+    /// Переставляє документи: order[новий слот] = старий слот (перестановка всіх слотів).
+    /// Повертає таблицю старий слот -> новий слот для перенумерації інвертованого індексу.
+    pub fn reorder(&mut self, order: &[usize]) -> Vec<Option<usize>> {
+        let mut slot_map = vec![None; self.documents.len()];
+        for (new_slot, &old_slot) in order.iter().enumerate() {
+            slot_map[old_slot] = Some(new_slot);
+        }
+
+        let mut old_documents: Vec<Option<DocumentRecord>> =
+            std::mem::take(&mut self.documents).into_iter().map(Some).collect();
+        self.documents = order
+            .iter()
+            .filter_map(|&old_slot| old_documents[old_slot].take())
+            .collect();
+        self.rebuild_id_map();
+
+        slot_map
+    }
+
+    /// Ущільнення: прибирає tombstone-слоти. Повертає таблицю старий слот -> новий слот
+    /// (None для видалених), за якою треба перенумерувати інвертований індекс.
+    pub fn compact(&mut self) -> Vec<Option<usize>> {
+        let mut slot_map = Vec::with_capacity(self.documents.len());
+        let mut next_slot = 0;
+
+        for document in &self.documents {
+            if document.deleted {
+                slot_map.push(None);
+            } else {
+                slot_map.push(Some(next_slot));
+                next_slot += 1;
             }
         }
 
-        Err("Не вдалося завантажити індекс: всі файли пошкоджені або відсутні".to_string())
+        self.documents.retain(|d| !d.deleted);
+        self.recount();
+        self.last_compacted_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.rebuild_id_map();
+
+        slot_map
+    }
+
+    /// Пошкоджений файл нічим не підміняється: відновлення з датованої резервної копії
+    /// записує нове покоління (див. AtomicIndexManager::restore_from_backup)
+    pub fn load_from_file(file_path: &str) -> Result<Self, IndexError> {
+        info!("📂 Завантаження індексу з файлу: {}", file_path);
+
+        if !Path::new(file_path).exists() {
+            return Err(IndexError::NotFound(format!("Індекс не знайдено: {}", file_path)));
+        }
+
+        // Цілісність перевіряється за лічильниками з файлу, перерахунок - лише після неї
+        let mut idx = Self::try_load_file(file_path)
+            .map_err(|e| IndexError::Corrupt(format!("Не вдалося завантажити індекс {}: {}", file_path, e)))?;
+        if !Self::validate_index(&idx) {
+            return Err(IndexError::Corrupt(format!("Індекс пошкоджений: {}", file_path)));
+        }
+        idx.check_loaded();
+        info!("✅ Завантажено {} документів", idx.total_documents);
+        Ok(idx)
     }
 
-    fn try_load_file(file_path: &str) -> Result<Self, String> {
-        let file = std::fs::File::open(file_path)
-            .map_err(|e| format!("Помилка відкриття файлу: {}", e))?;
+    fn try_load_file(file_path: &str) -> Result<Self, IndexError> {
+        let mut index: Self = index_checksum::read_verified_json(file_path, "Помилка парсингу JSON")?;
+
+        index.rebuild_id_map();
+        Ok(index)
+    }
 
-        let reader = BufReader::with_capacity(1024 * 1024, file); // 1MB буфер
+    /// Перевірка щойно завантаженого індексу (з файлу чи зі сховища): пошкоджені документи
+    /// позначаються для перепарсингу, лічильники перераховуються
+    pub(crate) fn check_loaded(&mut self) {
+        // Пошкоджені документи не роблять недійсним весь індекс - лише їх буде перепарсено
+        let corrupted = self.verify_documents();
+        if !corrupted.is_empty() {
+            info!(
+                "🔧 Пошкоджених документів: {} - їх буде перепарсено при наступній індексації",
+                corrupted.len()
+            );
+        }
 
-        serde_json::from_reader(reader)
-            .map_err(|e| format!("Помилка парсингу JSON: {}", e))
+        // Розбіжність лічильників не робить індекс пошкодженим - просто перераховуємо
+        let recount = self.recount();
+        if recount.changed() {
+            warn!(
+                "⚠️  Статистику індексу перераховано: документів {} -> {}, слів {} -> {}",
+                recount.documents_was, recount.documents_now, recount.words_was, recount.words_now
+            );
+        }
     }
 
     fn validate_index(index: &Self) -> bool {
         // Базові перевірки цілісності
         if index.documents.is_empty() && index.total_documents > 0 {
-            println!("❌ Невідповідність: total_documents > 0, але documents порожній");
+            error!("❌ Невідповідність: total_documents > 0, але documents порожній");
             return false;
         }
 
-        if index.documents.len() != index.total_documents {
-            println!("❌ Невідповідність: len(documents) != total_documents");
-            return false;
-        }
-
-        // Перевіряємо, що кожен документ має валідні дані
+        // Без шляху документ неможливо перепарсити, тому такий запис псує весь файл.
+        // Решта проблем окремих документів обробляється в verify_documents.
         for (i, doc) in index.documents.iter().enumerate() {
             if doc.file_path.is_empty() {
-                println!("❌ Документ {} має порожній file_path", i);
-                return false;
-            }
-            
-            if doc.content.is_empty() && doc.paragraph_count > 0 {
-                println!("❌ Документ {} має paragraph_count > 0, але content порожній", i);
-                return false;
-            }
-
-            if doc.content.len() != doc.paragraph_count {
-                println!("❌ Документ {} має невідповідність len(content) != paragraph_count", i);
+                error!("❌ Документ {} має порожній file_path", i);
                 return false;
             }
         }
 
         true
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+
+    fn document(path: &str, content: &[&str]) -> DocumentRecord {
+        DocumentRecord { last_modified: 100, ..DocumentRecord::from_texts(path, content.iter().copied()) }
+    }
+
+    #[test]
+    fn test_corrupted_documents_are_marked_for_reparse() {
+        let mut index = DocumentIndex::new();
+        index.push_document(document("a.docx", &["перший наказ"]));
+        index.push_document(document("b.docx", &["другий", "рапорт"]));
+        index.push_document(document("c.docx", &["третій"]));
+        assert!(index.verify_documents().is_empty());
+
+        // Вміст змінено без оновлення контрольної суми; у третього - зламана структура
+        index.documents[1].content[1] = "рапорт!".to_string();
+        index.documents[2].paragraph_count = 5;
+
+        assert_eq!(index.verify_documents(), vec![1, 2]);
+        assert_eq!(index.documents[0].last_modified, 100);
+        assert_eq!(index.documents[1].last_modified, 0);
+        assert_eq!(index.documents[2].last_modified, 0);
+
+        // Старі записи без контрольної суми отримують її при перевірці
+        index.documents[0].content_hash = None;
+        index.verify_documents();
+        assert_eq!(index.documents[0].content_hash, Some(index.documents[0].compute_content_hash()));
+    }
+
+    #[test]
+    fn test_loaded_totals_validated_before_recount() {
+        let dir = TempIndexDir::new("loaded_totals");
+        let path = dir.join("documents_index.json").to_string_lossy().to_string();
+
+        // Розбіжні лічильники при наявних документах - лише перераховуються
+        let mut index = DocumentIndex::new();
+        index.push_document(document("a.docx", &["перший наказ"]));
+        index.total_documents = 3;
+        fs::write(&path, serde_json::to_string(&index).unwrap()).unwrap();
+        let loaded = DocumentIndex::load_from_file(&path).unwrap();
+        assert_eq!((loaded.total_documents, loaded.total_words), (1, 2));
+
+        // Документів немає, а файл обіцяє три - перерахунок не приховує пошкодження
+        let mut emptied = DocumentIndex::new();
+        emptied.total_documents = 3;
+        fs::write(&path, serde_json::to_string(&emptied).unwrap()).unwrap();
+        assert!(matches!(DocumentIndex::load_from_file(&path), Err(IndexError::Corrupt(_))));
+    }
+}
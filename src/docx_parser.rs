@@ -5,6 +5,7 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, Read};
 use zip::ZipArchive;
+use crate::error::ParseError;
 use once_cell::sync::Lazy;
 
 // Глобальні компільовані регулярні вирази для кращої продуктивності
@@ -15,22 +16,16 @@ static BASIS_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*Підстава
 #[derive(Debug, Clone)]
 pub struct ParagraphInfo {
     pub text: String,
-    #[allow(dead_code)]
-    pub style: Option<String>,
     pub level: Option<usize>,
     pub has_numbering: bool,
     pub calculated_number: Option<String>,
-    #[allow(dead_code)]
-    pub original_text: String,
     pub line_breaks_after: usize, // Кількість порожніх рядків після параграфа
 }
 
 impl ParagraphInfo {
-    fn new(text: String, style: Option<String>) -> Self {
+    fn new(text: String) -> Self {
         Self {
-            original_text: text.clone(),
             text,
-            style,
             level: None,
             has_numbering: false,
             calculated_number: None,
@@ -40,14 +35,11 @@ impl ParagraphInfo {
 
     fn with_numbering(
         text: String,
-        style: Option<String>,
         level: usize,
         calculated_number: String,
     ) -> Self {
         Self {
-            original_text: text.clone(),
             text,
-            style,
             level: Some(level),
             has_numbering: true,
             calculated_number: Some(calculated_number),
@@ -56,22 +48,13 @@ impl ParagraphInfo {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct NumberingData {
     abstract_num_map: HashMap<String, HashMap<String, String>>,
     num_id_map: HashMap<String, HashMap<String, String>>,
 }
 
-impl Default for NumberingData {
-    fn default() -> Self {
-        Self {
-            abstract_num_map: HashMap::new(),
-            num_id_map: HashMap::new(),
-        }
-    }
-}
-
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct CurrentNumbering {
     level_1: usize,
     level_2: usize,
@@ -79,26 +62,12 @@ struct CurrentNumbering {
     level_4: usize,
 }
 
-impl Default for CurrentNumbering {
-    fn default() -> Self {
-        Self {
-            level_1: 0,
-            level_2: 0,
-            level_3: 0,
-            level_4: 0,
-        }
-    }
-}
-
 pub struct DocxParser {
     doc_path: String,
     numbering_data: NumberingData,
 }
 
 impl DocxParser {
-    // XML namespace константи
-    #[allow(dead_code)]
-    const W_NAMESPACE: &'static str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
 
     // Стилі, що відповідають рівням нумерації
     const STYLE_LEVEL_MAP: &'static [(&'static str, usize)] = &[
@@ -118,32 +87,27 @@ impl DocxParser {
         }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<String>, String> {
-        let paragraphs_info = self.extract_hierarchical_numbering()?;
-        Ok(self.format_paragraphs(paragraphs_info))
-    }
-
-    pub fn parse_with_structure(&mut self) -> Result<Vec<crate::document_record::Paragraph>, String> {
+    pub fn parse_with_structure(&mut self) -> Result<Vec<crate::document_record::Paragraph>, ParseError> {
         let paragraphs_info = self.extract_hierarchical_numbering()?;
         Ok(self.format_paragraphs_with_structure(paragraphs_info))
     }
 
-    fn open_docx(&mut self) -> Result<(String, Option<String>), String> {
+    fn open_docx(&mut self) -> Result<(String, Option<String>), ParseError> {
         let file = File::open(&self.doc_path)
-            .map_err(|e| format!("Помилка при відкритті документа: {}", e))?;
+            .map_err(ParseError::Open)?;
 
         let reader = BufReader::new(file);
         let mut archive = ZipArchive::new(reader)
-            .map_err(|e| format!("Помилка при відкритті ZIP архіву: {}", e))?;
+            .map_err(ParseError::Archive)?;
 
         // Читання document.xml
         let doc_contents = {
             let mut doc_file = archive.by_name("word/document.xml")
-                .map_err(|e| format!("Помилка при читанні document.xml: {}", e))?;
+                .map_err(ParseError::MissingDocument)?;
 
             let mut contents = String::new();
             doc_file.read_to_string(&mut contents)
-                .map_err(|e| format!("Помилка при читанні вмісту документа: {}", e))?;
+                .map_err(ParseError::Read)?;
             contents
         };
 
@@ -162,7 +126,7 @@ impl DocxParser {
         Ok((doc_contents, numbering_contents))
     }
 
-    fn process_numbering_xml(&mut self, numbering_xml: &str) -> Result<(), String> {
+    fn process_numbering_xml(&mut self, numbering_xml: &str) -> Result<(), ParseError> {
         let mut reader = Reader::from_str(numbering_xml);
 
         let mut buf = Vec::new();
@@ -239,7 +203,7 @@ impl DocxParser {
                     }
                 }
                 Ok(Event::Eof) => break,
-                Err(e) => return Err(format!("Помилка парсингу numbering.xml: {}", e)),
+                Err(e) => return Err(ParseError::Xml { context: "Помилка парсингу numbering.xml", source: e }),
                 _ => {}
             }
             buf.clear();
@@ -261,7 +225,7 @@ impl DocxParser {
             })
     }
 
-    fn extract_hierarchical_numbering(&mut self) -> Result<Vec<ParagraphInfo>, String> {
+    fn extract_hierarchical_numbering(&mut self) -> Result<Vec<ParagraphInfo>, ParseError> {
         let (doc_xml, numbering_xml) = self.open_docx()?;
 
         // Обробка numbering.xml якщо існує
@@ -309,15 +273,10 @@ impl DocxParser {
                     }
                 }
                 Ok(Event::Empty(ref e)) => {
-                    match e.name().as_ref() {
-                        b"w:pStyle" => {
-                            if in_paragraph {
-                                if let Some(val) = self.get_attribute_value(e, "w:val") {
-                                    paragraph_style = Some(val);
-                                }
-                            }
+                    if e.name().as_ref() == b"w:pStyle" && in_paragraph {
+                        if let Some(val) = self.get_attribute_value(e, "w:val") {
+                            paragraph_style = Some(val);
                         }
-                        _ => {}
                     }
                 }
                 Ok(Event::Text(e)) => {
@@ -369,7 +328,7 @@ impl DocxParser {
                     }
                 }
                 Ok(Event::Eof) => break,
-                Err(e) => return Err(format!("Помилка парсингу XML: {}", e)),
+                Err(e) => return Err(ParseError::Xml { context: "Помилка парсингу XML", source: e }),
                 _ => {}
             }
             buf.clear();
@@ -378,7 +337,7 @@ impl DocxParser {
         Ok(result)
     }
 
-    fn read_num_pr(&self, reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Result<(Option<String>, Option<String>), String> {
+    fn read_num_pr(&self, reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Result<(Option<String>, Option<String>), ParseError> {
         let mut ilvl = None;
         let mut num_id = None;
 
@@ -401,7 +360,7 @@ impl DocxParser {
                     }
                 }
                 Ok(Event::Eof) => break,
-                Err(e) => return Err(format!("Помилка читання numPr: {}", e)),
+                Err(e) => return Err(ParseError::Xml { context: "Помилка читання numPr", source: e }),
                 _ => {}
             }
             buf.clear();
@@ -435,11 +394,11 @@ impl DocxParser {
 
         // Обробка за правилами з Python коду
         if has_basis {
-            return Some(ParagraphInfo::new(text, style));
+            return Some(ParagraphInfo::new(text));
         }
 
         if has_text_numbering && !has_quote_with_numbering {
-            return Some(ParagraphInfo::new(text, style));
+            return Some(ParagraphInfo::new(text));
         }
 
         if has_quote_with_numbering {
@@ -447,17 +406,17 @@ impl DocxParser {
                 if let Some(level) = self.get_numbering_level(&ilvl, &num_id) {
                     self.update_numbering_for_level(level, current_numbering, *last_main_point);
                     let calculated_number = self.format_numbering(level, current_numbering);
-                    return Some(ParagraphInfo::with_numbering(text, style, level, calculated_number));
+                    return Some(ParagraphInfo::with_numbering(text, level, calculated_number));
                 }
             }
-            return Some(ParagraphInfo::new(text, style));
+            return Some(ParagraphInfo::new(text));
         }
 
         if let Some((ilvl, num_id)) = num_pr {
             if let Some(level) = self.get_numbering_level(&ilvl, &num_id) {
                 self.update_numbering_for_level(level, current_numbering, *last_main_point);
                 let calculated_number = self.format_numbering(level, current_numbering);
-                return Some(ParagraphInfo::with_numbering(text, style, level, calculated_number));
+                return Some(ParagraphInfo::with_numbering(text, level, calculated_number));
             }
         }
 
@@ -465,11 +424,11 @@ impl DocxParser {
             if let Some(level) = self.get_style_level(style_name) {
                 self.update_numbering_for_level(level, current_numbering, *last_main_point);
                 let calculated_number = self.format_numbering(level, current_numbering);
-                return Some(ParagraphInfo::with_numbering(text, style, level, calculated_number));
+                return Some(ParagraphInfo::with_numbering(text, level, calculated_number));
             }
         }
 
-        Some(ParagraphInfo::new(text, style))
+        Some(ParagraphInfo::new(text))
     }
 
     fn get_numbering_level(&self, ilvl: &Option<String>, num_id: &Option<String>) -> Option<usize> {
@@ -535,64 +494,6 @@ impl DocxParser {
         }
     }
 
-    fn format_paragraphs(&self, paragraphs_info: Vec<ParagraphInfo>) -> Vec<String> {
-        let mut result = Vec::new();
-        let mut current_section = String::new();
-
-        for p_info in paragraphs_info {
-            let formatted_text = if p_info.has_numbering {
-                if let Some(calculated_number) = p_info.calculated_number {
-                    format!("{}{}", calculated_number, p_info.text)
-                } else {
-                    p_info.text
-                }
-            } else {
-                p_info.text
-            };
-
-            // Якщо це новий нумерований розділ (має numbering)
-            if p_info.has_numbering {
-                // Зберігаємо попередній розділ якщо він не порожній
-                if !current_section.is_empty() {
-                    result.push(current_section.trim().to_string());
-                    current_section.clear();
-                }
-
-                // Починаємо новий розділ
-                current_section = formatted_text;
-            } else {
-                // Це звичайний текст - додаємо до поточного розділу з переносом рядка
-                if !current_section.is_empty() {
-                    current_section.push('\n');
-                }
-                current_section.push_str(&formatted_text);
-            }
-        }
-
-        // Додаємо останній розділ
-        if !current_section.is_empty() {
-            result.push(current_section.trim().to_string());
-        }
-
-        // Розділяємо параграфи що містять '\n' на окремі параграфи
-        let mut final_result = Vec::new();
-        for paragraph in result {
-            if paragraph.contains('\n') {
-                // Розділяємо по переносу рядка і додаємо кожну частину як окремий параграф
-                for part in paragraph.split('\n') {
-                    let trimmed_part = part.trim();
-                    if !trimmed_part.is_empty() {
-                        final_result.push(trimmed_part.to_string());
-                    }
-                }
-            } else {
-                final_result.push(paragraph);
-            }
-        }
-
-        final_result
-    }
-
     fn format_paragraphs_with_structure(&self, paragraphs_info: Vec<ParagraphInfo>) -> Vec<crate::document_record::Paragraph> {
         use crate::document_record::Paragraph;
 
@@ -667,14 +568,8 @@ impl DocxParser {
     }
 }
 
-// Публічна функція для парсингу
-pub fn parse_docx(doc_path: &str) -> Result<Vec<String>, String> {
-    let mut parser = DocxParser::new(doc_path.to_string());
-    parser.parse()
-}
-
 // Публічна функція для парсингу з збереженням структури
-pub fn parse_docx_with_structure(doc_path: &str) -> Result<Vec<crate::document_record::Paragraph>, String> {
+pub fn parse_docx_with_structure(doc_path: &str) -> Result<Vec<crate::document_record::Paragraph>, ParseError> {
     let mut parser = DocxParser::new(doc_path.to_string());
     parser.parse_with_structure()
 }
\ No newline at end of file
@@ -0,0 +1,234 @@
+//! Типізовані помилки ядра. Повідомлення ті самі, що й раніше, але викликачі
+//! (веб-обробники, CLI, фоновий індексер) можуть розрізняти причини:
+//! недоступна мережева папка, пошкоджений індекс, зайняте блокування тощо.
+
+use std::io;
+use thiserror::Error;
+
+/// Помилки парсингу DOCX
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("Помилка при відкритті документа: {0}")]
+    Open(#[source] io::Error),
+
+    #[error("Помилка при відкритті ZIP архіву: {0}")]
+    Archive(#[source] zip::result::ZipError),
+
+    #[error("Помилка при читанні document.xml: {0}")]
+    MissingDocument(#[source] zip::result::ZipError),
+
+    #[error("Помилка при читанні вмісту документа: {0}")]
+    Read(#[source] io::Error),
+
+    #[error("Помилка отримання метаданих файлу {path}: {source}")]
+    Metadata {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    /// Некоректний XML усередині документа (контекст - яка частина)
+    #[error("{context}: {source}")]
+    Xml {
+        context: &'static str,
+        #[source]
+        source: quick_xml::Error,
+    },
+}
+
+/// Помилки читання, запису та обслуговування індексів
+#[derive(Debug, Error)]
+pub enum IndexError {
+    /// Файлова операція (контекст - що саме і з яким файлом)
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+
+    /// Серіалізація або розбір JSON індексу, маніфесту, журналу чи звіту
+    #[error("{context}: {source}")]
+    Json {
+        context: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// Запит до сховища SQLite (storage.backend = "sqlite")
+    #[error("{context}: {source}")]
+    Database {
+        context: String,
+        #[source]
+        source: rusqlite::Error,
+    },
+
+    /// Оновлення вже виконує інший процес (або не дочекались черги)
+    #[error("{0}")]
+    Locked(String),
+
+    /// Немає файлу індексу, покоління, знімка чи документа
+    #[error("{0}")]
+    NotFound(String),
+
+    /// Пошкоджений або неузгоджений індекс (перевірки цілісності, контрольні суми, покоління)
+    #[error("{0}")]
+    Corrupt(String),
+
+    /// Папка з документами для індексації недоступна
+    #[error("{0}")]
+    SourceUnavailable(String),
+}
+
+impl IndexError {
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        Self::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    pub fn json(context: impl Into<String>, source: serde_json::Error) -> Self {
+        Self::Json {
+            context: context.into(),
+            source,
+        }
+    }
+
+    pub fn database(context: impl Into<String>, source: rusqlite::Error) -> Self {
+        Self::Database {
+            context: context.into(),
+            source,
+        }
+    }
+}
+
+/// Помилки синхронізації мережевої папки з локальним кешем
+#[derive(Debug, Error)]
+pub enum SyncError {
+    /// Мережева папка не змонтована або недоступна
+    #[error("Мережева папка недоступна: {0}")]
+    ShareOffline(String),
+
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+
+    /// Серіалізація історії синхронізацій
+    #[error("{context}: {source}")]
+    Json {
+        context: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+impl SyncError {
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        Self::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    pub fn json(context: impl Into<String>, source: serde_json::Error) -> Self {
+        Self::Json {
+            context: context.into(),
+            source,
+        }
+    }
+}
+
+/// Помилки пошуку
+#[derive(Debug, Error)]
+pub enum SearchError {
+    /// Індекс ще не завантажено або його не вдалося прочитати
+    #[error(transparent)]
+    Index(#[from] IndexError),
+
+    /// Потік, що тримав дані пошуку, завершився панікою
+    #[error("Помилка блокування даних: {0}")]
+    LockPoisoned(String),
+
+    /// Одночасних пошуків більше, ніж search.max_concurrent, і черга повна
+    #[error("Сервер зайнятий іншими пошуками (одночасно - не більше {0}), повторіть запит пізніше")]
+    Busy(usize),
+}
+
+/// Помилки конфігурації
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Помилка читання конфігурації {path}: {source}")]
+    Read {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Помилка у файлі конфігурації {path}: {source}")]
+    Parse {
+        path: String,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    /// Значення змінної середовища BLAZING_* не перетворюється у тип параметра
+    #[error("некоректне значення змінної {name}: {value}")]
+    Env { name: String, value: String },
+
+    #[error("Помилка конфігурації: {0}")]
+    Invalid(String),
+}
+
+/// Помилки адрес і доставки сповіщень: тексти - з каталогу повідомлень,
+/// бо повертаються клієнтам API
+#[derive(Debug, Error)]
+pub enum NotificationError {
+    #[error("{}", crate::tr!(InvalidWebhookUrl, .0))]
+    InvalidWebhookUrl(String),
+
+    /// Хост webhook не входить до alerts.saved_query_webhook_hosts
+    #[error("{}", crate::tr!(WebhookHostNotAllowed, .0))]
+    WebhookHostNotAllowed(String),
+
+    #[error("{}", crate::tr!(InvalidEmail, .address, .reason))]
+    InvalidEmail { address: String, reason: String },
+
+    /// Домен пошти не входить до alerts.saved_query_email_domains
+    #[error("{}", crate::tr!(EmailDomainNotAllowed, .0))]
+    EmailDomainNotAllowed(String),
+
+    /// Не задано alerts.smtp_server
+    #[error("{}", crate::tr!(EmailAlertsDisabled))]
+    EmailDisabled,
+
+    /// Лист не складено (некоректні заголовки)
+    #[error("{}", crate::tr!(EmailBuildFailed, .0))]
+    EmailBuild(#[source] lettre::error::Error),
+
+    /// Сервер SMTP не прийняв лист
+    #[error("{}", crate::tr!(EmailSendFailed, .0))]
+    EmailSend(#[source] lettre::transport::smtp::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+    use crate::document_record::DocumentIndex;
+    use std::fs;
+
+    #[test]
+    fn test_missing_and_corrupt_index_are_distinguished() {
+        let dir = TempIndexDir::new("error_test");
+        let path = dir.join("documents_index.json").to_string_lossy().to_string();
+
+        assert!(matches!(DocumentIndex::load_from_file(&path), Err(IndexError::NotFound(_))));
+
+        fs::write(&path, "{ не json").unwrap();
+        assert!(matches!(DocumentIndex::load_from_file(&path), Err(IndexError::Corrupt(_))));
+    }
+}
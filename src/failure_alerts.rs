@@ -0,0 +1,253 @@
+//! Сповіщення про збої фонового індексера: коли синхронізація чи індексація не вдається
+//! кілька циклів поспіль або різко зростає кількість файлів з помилками обробки,
+//! зведення помилок надсилається на webhook і/або поштою (SMTP) - щоб пошкодження
+//! помітили того ж дня, а не через тижні. Налаштування - розділ [alerts].
+
+use crate::config::AlertsConfig;
+use crate::error::{ConfigError, NotificationError};
+use crate::integrity_monitor::hostname;
+use crate::tr;
+use chrono::{DateTime, Local};
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use log::{info, warn};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Скільки невдалих циклів поспіль за замовчуванням викликають сповіщення
+pub const DEFAULT_ALERT_FAILURE_THRESHOLD: u32 = 3;
+/// На скільки за замовчуванням має зрости кількість файлів з помилками обробки
+pub const DEFAULT_ALERT_PARSE_ERROR_JUMP: usize = 20;
+/// Скільки помилок перелічувати в сповіщенні
+const MAX_LISTED_ERRORS: usize = 10;
+
+/// Сповіщення: тема і текст з переліком помилок
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub subject: String,
+    pub body: String,
+}
+
+impl Alert {
+    fn new(subject: String, errors: &[String]) -> Self {
+        let mut body = format!("{}\n\n{}\n", subject, tr!(AlertErrorsHeading));
+        for error in errors.iter().take(MAX_LISTED_ERRORS) {
+            body.push_str(&format!("- {}\n", error));
+        }
+        if errors.len() > MAX_LISTED_ERRORS {
+            body.push_str(&tr!(AlertMoreErrors, errors.len() - MAX_LISTED_ERRORS));
+            body.push('\n');
+        }
+        Self { subject, body }
+    }
+}
+
+/// Рахує невдалі цикли індексера і вирішує, коли надсилати сповіщення
+pub struct FailureTracker {
+    threshold: u32,
+    parse_error_jump: usize,
+    consecutive_failures: u32,
+    failures: Vec<String>,
+    /// Файлів з помилками обробки в останньому циклі з індексацією
+    previous_parse_errors: usize,
+}
+
+impl FailureTracker {
+    pub fn new(config: &AlertsConfig) -> Self {
+        Self {
+            threshold: config.failure_threshold,
+            parse_error_jump: config.parse_error_jump,
+            consecutive_failures: 0,
+            failures: Vec::new(),
+            previous_parse_errors: 0,
+        }
+    }
+
+    /// Підсумок циклу: failures - помилки синхронізації та індексації (порожньо - цикл вдався),
+    /// parse_errors - файли, які не вдалося обробити (None - індексація не запускалась).
+    /// Про серію збоїв сповіщає один раз - коли вона досягає порогу.
+    pub fn finish_cycle(&mut self, failures: Vec<String>, parse_errors: Option<&[String]>) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+
+        if failures.is_empty() {
+            self.consecutive_failures = 0;
+            self.failures.clear();
+        } else {
+            self.consecutive_failures += 1;
+            self.failures.extend(failures);
+            let excess = self.failures.len().saturating_sub(MAX_LISTED_ERRORS);
+            self.failures.drain(..excess);
+            if self.consecutive_failures == self.threshold {
+                let subject = tr!(AlertRepeatedFailures, hostname(), self.consecutive_failures);
+                // Найсвіжіші помилки - першими
+                let errors: Vec<String> = self.failures.iter().rev().cloned().collect();
+                alerts.push(Alert::new(subject, &errors));
+            }
+        }
+
+        // Ті самі пошкоджені файли не обробляються в кожному циклі - сповіщаємо лише про стрибок
+        if let Some(errors) = parse_errors {
+            if errors.len() >= self.previous_parse_errors + self.parse_error_jump {
+                let subject = tr!(AlertParseErrorsJump, hostname(), errors.len(), self.previous_parse_errors);
+                alerts.push(Alert::new(subject, errors));
+            }
+            self.previous_parse_errors = errors.len();
+        }
+
+        alerts
+    }
+}
+
+/// Канали доставки сповіщень
+pub struct AlertNotifier {
+    webhook: Option<String>,
+    mail: Option<MailChannel>,
+}
+
+struct MailChannel {
+    mailer: Mailer,
+    to: Vec<Mailbox>,
+}
+
+/// Поштовий сервер з [alerts]: ним надсилаються і сповіщення про збої, і сповіщення
+/// збережених запитів (кожне - своїм адресатам)
+pub struct Mailer {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl Mailer {
+    /// Сервер SMTP з конфігурації; None - smtp_server не задано.
+    /// Помилка - некоректна адреса відправника або сервер.
+    pub fn from_config(config: &AlertsConfig) -> Result<Option<Self>, ConfigError> {
+        let Some(server) = &config.smtp_server else {
+            return Ok(None);
+        };
+        let from: Mailbox = config
+            .smtp_from
+            .parse()
+            .map_err(|e| ConfigError::Invalid(format!("alerts.smtp_from: {}", e)))?;
+        let mut builder = if config.smtp_starttls {
+            SmtpTransport::starttls_relay(server).map_err(|e| ConfigError::Invalid(format!("alerts.smtp_server: {}", e)))?
+        } else {
+            SmtpTransport::builder_dangerous(server)
+        };
+        builder = builder.port(config.smtp_port).timeout(Some(Duration::from_secs(30)));
+        if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(Some(Self { transport: builder.build(), from }))
+    }
+
+    /// Надсилає сповіщення листом (блокуючий виклик)
+    pub fn send(&self, to: &[Mailbox], alert: &Alert) -> Result<(), NotificationError> {
+        let mut builder = Message::builder().from(self.from.clone()).subject(alert.subject.clone());
+        for recipient in to {
+            builder = builder.to(recipient.clone());
+        }
+        let message = builder
+            .header(ContentType::TEXT_PLAIN)
+            .body(alert.body.clone())
+            .map_err(NotificationError::EmailBuild)?;
+        self.transport.send(&message).map(|_| ()).map_err(NotificationError::EmailSend)
+    }
+}
+
+impl AlertNotifier {
+    /// Канали з конфігурації; None - жодного каналу не налаштовано.
+    /// Помилка - некоректні адреси пошти або сервер SMTP.
+    pub fn from_config(config: &AlertsConfig) -> Result<Option<Self>, ConfigError> {
+        let mail = match Mailer::from_config(config)? {
+            Some(mailer) => {
+                if config.smtp_to.is_empty() {
+                    return Err(ConfigError::Invalid(
+                        "alerts.smtp_to не може бути порожнім, якщо задано smtp_server".to_string(),
+                    ));
+                }
+                let to = config
+                    .smtp_to
+                    .iter()
+                    .map(|address| {
+                        address
+                            .parse()
+                            .map_err(|e| ConfigError::Invalid(format!("alerts.smtp_to {}: {}", address, e)))
+                    })
+                    .collect::<Result<Vec<Mailbox>, ConfigError>>()?;
+                Some(MailChannel { mailer, to })
+            }
+            None => None,
+        };
+
+        if config.webhook.is_none() && mail.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self { webhook: config.webhook.clone(), mail }))
+    }
+
+    /// Надсилає сповіщення всіма каналами (блокуючий виклик); помилки доставки - у журнал
+    pub fn send(&self, alert: &Alert) {
+        if let Some(url) = &self.webhook {
+            let payload = serde_json::json!({
+                "event": "indexing_failure_alert",
+                "host": hostname(),
+                "time": DateTime::<Local>::from(std::time::SystemTime::now()).to_rfc3339(),
+                "subject": alert.subject,
+                "body": alert.body,
+            });
+            match ureq::post(url).timeout(Duration::from_secs(10)).send_json(payload) {
+                Ok(_) => info!("{}", tr!(AlertSent, "webhook", alert.subject)),
+                Err(e) => warn!("{}", tr!(AlertSendFailed, "webhook", e)),
+            }
+        }
+
+        if let Some(mail) = &self.mail {
+            match mail.mailer.send(&mail.to, alert) {
+                Ok(()) => info!("{}", tr!(AlertSent, "SMTP", alert.subject)),
+                Err(e) => warn!("{}", tr!(AlertSendFailed, "SMTP", e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("Помилка обробки наказ_{}.docx: пошкоджений архів", i)).collect()
+    }
+
+    #[test]
+    fn test_alerts_on_failure_streak_and_parse_error_jump() {
+        let mut tracker = FailureTracker::new(&AlertsConfig::default());
+
+        // Третій невдалий цикл поспіль - одне сповіщення, подальші збої серії - без повторів
+        assert!(tracker.finish_cycle(vec!["мережа".to_string()], None).is_empty());
+        assert!(tracker.finish_cycle(vec!["мережа".to_string()], None).is_empty());
+        let alerts = tracker.finish_cycle(vec!["диск заповнено".to_string()], None);
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].subject.contains("3 циклів поспіль"));
+        assert!(alerts[0].body.contains("- диск заповнено\n- мережа\n"));
+        assert!(tracker.finish_cycle(vec!["мережа".to_string()], None).is_empty());
+
+        // Успішний цикл обнуляє серію
+        assert!(tracker.finish_cycle(Vec::new(), None).is_empty());
+        assert!(tracker.finish_cycle(vec!["мережа".to_string()], None).is_empty());
+
+        // Стрибок помилок обробки; ті самі файли в наступному циклі - без повтору
+        assert!(tracker.finish_cycle(Vec::new(), Some(&errors(5))).is_empty());
+        let alerts = tracker.finish_cycle(Vec::new(), Some(&errors(30)));
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].body.contains("... і ще 20"));
+        assert!(tracker.finish_cycle(Vec::new(), Some(&errors(30))).is_empty());
+
+        let config = AlertsConfig {
+            smtp_server: Some("smtp.example.org".to_string()),
+            ..AlertsConfig::default()
+        };
+        assert!(matches!(AlertNotifier::from_config(&config), Err(ConfigError::Invalid(_))));
+        assert!(AlertNotifier::from_config(&AlertsConfig::default()).unwrap().is_none());
+    }
+}
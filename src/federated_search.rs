@@ -0,0 +1,149 @@
+//! Пошук одним запитом у кількох колекціях ([[collections]]). Кожна колекція шукає у
+//! власному поколінні паралельно, списки зливаються в один за тим самим порядком, що й
+//! усередині колекції (sort запиту; за замовчуванням - дата наказу, потім кількість збігів).
+//! Злиття не пересортовує список колекції, тож зсуви за оцінками релевантності зберігаються.
+
+use crate::error::SearchError;
+use crate::search_facets::SearchFacets;
+use crate::search_engine::{SearchEngine, SearchEngineResult, SearchFilters, SearchMode, SearchScope, SortOrder};
+use crate::tr;
+use log::warn;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// Результат пошуку по кількох колекціях
+#[derive(Debug, Default)]
+pub struct FederatedOutcome {
+    pub results: Vec<SearchEngineResult>,
+    /// Колекція кожного результату за file_path (кеші колекцій не спільні)
+    pub collections: HashMap<String, String>,
+    /// Хоча б одна колекція перервала пошук за search.query_timeout_ms
+    pub partial: bool,
+    /// Переглянуто документів у всіх колекціях разом
+    pub scope: Option<SearchScope>,
+    /// Фасети всіх колекцій разом
+    pub facets: SearchFacets,
+}
+
+/// Шукає в кожній колекції (назва, движок) і зливає результати. Колекція з помилкою
+/// (наприклад, ще без індексу) пропускається з попередженням; помилка повертається,
+/// лише якщо не відповіла жодна. Фільтри - див. SearchEngine::search_outcome_filtered
+pub async fn search(
+    collections: &[(String, Arc<SearchEngine>)],
+    query: &str,
+    mode: SearchMode,
+    view_mode: Option<&str>,
+    filters: &SearchFilters,
+) -> Result<FederatedOutcome, SearchError> {
+    let outcomes = futures_util::future::join_all(
+        collections
+            .iter()
+            .map(|(_, engine)| engine.search_outcome_filtered(query, mode, view_mode, filters)),
+    )
+    .await;
+
+    let mut lists = Vec::new();
+    let mut partial = false;
+    let mut documents = 0;
+    let mut remaining_estimate = None;
+    let mut facets = SearchFacets::default();
+    let mut first_error = None;
+    for ((name, _), outcome) in collections.iter().zip(outcomes) {
+        match outcome {
+            Ok(outcome) => {
+                partial |= outcome.partial;
+                if let Some(scope) = outcome.scope {
+                    documents += scope.documents;
+                    if let Some(estimate) = scope.remaining_estimate {
+                        *remaining_estimate.get_or_insert(0) += estimate;
+                    }
+                }
+                facets.merge(outcome.facets);
+                lists.push((name.clone(), outcome.results));
+            }
+            Err(err) => {
+                warn!("{}", tr!(CollectionSearchFailed, name, err));
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    if let (true, Some(err)) = (lists.is_empty(), first_error) {
+        return Err(err);
+    }
+
+    let (results, collections) = merge(lists, filters.sort);
+    Ok(FederatedOutcome {
+        results,
+        collections,
+        partial,
+        scope: Some(SearchScope { mode, documents, quick_since: None, remaining_estimate }),
+        facets,
+    })
+}
+
+/// Зливає впорядковані списки колекцій в один (за порядком sort; за рівності
+/// першою йде колекція, вказана раніше)
+pub fn merge(lists: Vec<(String, Vec<SearchEngineResult>)>, sort: SortOrder) -> (Vec<SearchEngineResult>, HashMap<String, String>) {
+    let mut collections = HashMap::new();
+    let mut lists: Vec<(String, VecDeque<SearchEngineResult>)> =
+        lists.into_iter().map(|(name, results)| (name, results.into())).collect();
+    let mut merged = Vec::new();
+
+    loop {
+        let mut next: Option<usize> = None;
+        for (i, (_, results)) in lists.iter().enumerate() {
+            let Some(candidate) = results.front() else {
+                continue;
+            };
+            let better = match next.and_then(|j| lists[j].1.front()) {
+                Some(best) => sort.compare(candidate, best).is_lt(),
+                None => true,
+            };
+            if better {
+                next = Some(i);
+            }
+        }
+        let Some((name, results)) = next.map(|i| &mut lists[i]) else {
+            break;
+        };
+        if let Some(result) = results.pop_front() {
+            collections.insert(result.file_path.clone(), name.clone());
+            merged.push(result);
+        }
+    }
+    (merged, collections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document_date::{DateSource, DocumentDate};
+
+    fn result(file_path: &str, (year, month, day): (u32, u32, u32)) -> SearchEngineResult {
+        SearchEngineResult {
+            doc_id: 0,
+            file_name: file_path.to_string(),
+            file_path: file_path.to_string(),
+            matches: Vec::new(),
+            all_paragraphs: Vec::new(),
+            file_size: 0,
+            last_modified: 0,
+            category: None,
+            date: Some(DocumentDate { year, month, day, source: DateSource::FileName }),
+        }
+    }
+
+    #[test]
+    fn test_merge_orders_by_date_and_keeps_collection_order() {
+        let orders = vec![result("н/15", (2024, 4, 10)), result("н/2", (2023, 1, 20))];
+        // Оцінки підняли старіше розпорядження вище новішого - злиття цього не скасовує
+        let instructions = vec![result("р/3", (2023, 2, 1)), result("р/9", (2024, 5, 1)), result("р/1", (2022, 1, 1))];
+
+        let (results, collections) =
+            merge(vec![("Накази".to_string(), orders), ("Розпорядження".to_string(), instructions)], SortOrder::default());
+        let paths: Vec<&str> = results.iter().map(|r| r.file_path.as_str()).collect();
+        assert_eq!(paths, vec!["н/15", "р/3", "р/9", "н/2", "р/1"]);
+        assert_eq!(collections["р/9"], "Розпорядження");
+        assert_eq!(collections["н/2"], "Накази");
+    }
+}
@@ -1,14 +1,17 @@
+use log::{debug, error, info, warn};
 use std::path::Path;
+use std::sync::Arc;
 use walkdir::{WalkDir, DirEntry};
-use regex::Regex;
-use once_cell::sync::Lazy;
+use crate::classifier::Classifier;
+use crate::document_date::document_date;
 use crate::docx_parser::parse_docx_with_structure;
 use crate::document_record::{DocumentRecord, DocumentIndex};
+use crate::error::{IndexError, ParseError};
+use crate::indexing_checkpoint::IndexingCheckpoint;
+use crate::shutdown::Shutdown;
 
-// Регулярний вираз для пошуку дати у форматі DD.MM.YYYY
-static DATE_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(\d{2})\.(\d{2})\.(\d{4})").unwrap()
-});
+/// Папки (за назвою, без урахування регістру), які не індексуються, якщо не задано інших
+pub const DEFAULT_EXCLUDED_FOLDERS: &[&str] = &[".git", "ЕРДР (не виключені)"];
 
 pub struct FolderProcessor {
     pub processed_files: usize,
@@ -16,7 +19,26 @@ pub struct FolderProcessor {
     pub deleted_files: usize,
     pub errors: Vec<String>,
     pub new_or_updated_indices: Vec<usize>,
-    pub deleted_indices: Vec<usize>, // Індекси документів для видалення (ДО видалення з document_index)
+    pub deleted_indices: Vec<usize>, // Слоти документів, позначених видаленими (tombstone)
+    /// Якщо документи було пересортовано за датою: старий слот -> новий слот.
+    /// Індекси в new_or_updated_indices та deleted_indices вже перераховано.
+    pub slot_map: Option<Vec<Option<usize>>>,
+    /// Папки виключення
+    pub excluded_folders: Vec<String>,
+    /// Обробку перервано запитом зупинки: оброблено лише частину папки,
+    /// тому відсутні файли не позначалися видаленими
+    pub interrupted: bool,
+    shutdown: Option<Shutdown>,
+    /// Визначає вид наказу для кожного обробленого документа
+    classifier: Option<Arc<Classifier>>,
+    /// Контрольні точки першої індексації (без існуючого індексу)
+    checkpoint: Option<IndexingCheckpoint>,
+}
+
+impl Default for FolderProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl FolderProcessor {
@@ -28,28 +50,43 @@ impl FolderProcessor {
             errors: Vec::new(),
             new_or_updated_indices: Vec::new(),
             deleted_indices: Vec::new(),
+            slot_map: None,
+            excluded_folders: DEFAULT_EXCLUDED_FOLDERS.iter().map(|f| f.to_string()).collect(),
+            interrupted: false,
+            shutdown: None,
+            classifier: None,
+            checkpoint: None,
         }
     }
 
-    // Парсинг дати з назви файлу у форматі DD.MM.YYYY
-    fn extract_date_from_filename(&self, file_path: &str) -> Option<(u32, u32, u32)> {
-        let filename = Path::new(file_path)
-            .file_name()?
-            .to_str()?;
-
-        if let Some(captures) = DATE_REGEX.captures(filename) {
-            let day: u32 = captures.get(1)?.as_str().parse().ok()?;
-            let month: u32 = captures.get(2)?.as_str().parse().ok()?;
-            let year: u32 = captures.get(3)?.as_str().parse().ok()?;
-
-            // Перевірка валідності дати
-            if day >= 1 && day <= 31 && month >= 1 && month <= 12 && year >= 1900 {
-                Some((year, month, day))
-            } else {
-                None
-            }
-        } else {
-            None
+    pub fn with_excluded_folders(mut self, excluded_folders: Vec<String>) -> Self {
+        self.excluded_folders = excluded_folders;
+        self
+    }
+
+    /// Запит зупинки перевіряється перед кожним файлом
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Класифікатор видів наказів (категорія зберігається в DocumentRecord)
+    pub fn with_classifier(mut self, classifier: Arc<Classifier>) -> Self {
+        self.classifier = Some(classifier);
+        self
+    }
+
+    /// Контрольні точки для першої індексації: після збою наступний запуск продовжить
+    /// з останньої з них (див. indexing_checkpoint)
+    pub fn with_checkpoint(mut self, checkpoint: IndexingCheckpoint) -> Self {
+        self.checkpoint = Some(checkpoint);
+        self
+    }
+
+    /// Прибирає контрольні точки, коли результат індексації записано поколінням
+    pub fn clear_checkpoint(&self) {
+        if let Some(checkpoint) = &self.checkpoint {
+            checkpoint.clear();
         }
     }
 
@@ -79,32 +116,58 @@ impl FolderProcessor {
         }
     }
 
-    pub fn process_folder_incremental(&mut self, folder_path: &str, existing_index: Option<DocumentIndex>) -> Result<DocumentIndex, String> {
+    pub fn process_folder_incremental(&mut self, folder_path: &str, existing_index: Option<DocumentIndex>) -> Result<DocumentIndex, IndexError> {
         let folder = Path::new(folder_path);
 
         if !folder.exists() {
-            return Err(format!("Папка не існує: {}", folder_path));
+            return Err(IndexError::SourceUnavailable(format!("Папка не існує: {}", folder_path)));
         }
 
         if !folder.is_dir() {
-            return Err(format!("Шлях не є папкою: {}", folder_path));
+            return Err(IndexError::SourceUnavailable(format!("Шлях не є папкою: {}", folder_path)));
         }
 
-        let mut index = existing_index.unwrap_or_else(|| DocumentIndex::new());
+        // Контрольні точки потрібні лише першій індексації: з існуючим індексом
+        // перерваний запуск і так продовжується інкрементно
+        let resumed = match (&existing_index, &mut self.checkpoint) {
+            (None, Some(checkpoint)) => checkpoint.resume(folder_path).unwrap_or_else(|e| {
+                warn!("⚠️ Контрольні точки недоступні, індексація почнеться з початку: {}", e);
+                Vec::new()
+            }),
+            (Some(_), Some(checkpoint)) => {
+                checkpoint.clear();
+                self.checkpoint = None;
+                Vec::new()
+            }
+            _ => Vec::new(),
+        };
 
-        // Папки виключення
-        let excluded_folders = vec![".git", "ЕРДР (не виключені)"];
+        let mut index = existing_index.unwrap_or_default();
+
+        // Документи з контрольної точки вже розібрано - незмінені файли не парсяться знову,
+        // а в інвертований індекс вони потрапляють як нові
+        let resumed_paths: std::collections::HashSet<String> =
+            resumed.iter().map(|document| document.file_path.clone()).collect();
+        if !resumed.is_empty() {
+            info!("♻️ Продовження першої індексації: {} документів з контрольної точки", resumed.len());
+        }
+        for document in resumed {
+            let slot = index.push_document(document);
+            self.new_or_updated_indices.push(slot);
+            self.processed_files += 1;
+        }
 
         // Створюємо мапу існуючих документів для швидкого пошуку
         let mut existing_docs_map = index.documents.iter()
             .enumerate()
+            .filter(|(_, doc)| !doc.deleted)
             .map(|(i, doc)| (doc.file_path.clone(), (i, doc.last_modified)))
             .collect::<std::collections::HashMap<String, (usize, u64)>>();
 
         // Створюємо сет існуючих файлів для виявлення видалених
         let mut found_files = std::collections::HashSet::new();
 
-        println!("🔍 Пошук DOCX файлів у папці: {}", folder_path);
+        info!("🔍 Пошук DOCX файлів у папці: {}", folder_path);
 
         for entry in WalkDir::new(folder_path)
             .follow_links(false)
@@ -112,10 +175,16 @@ impl FolderProcessor {
             .into_iter()
             .filter_map(|e| e.ok())
         {
+            if self.shutdown.as_ref().is_some_and(Shutdown::is_requested) {
+                warn!("⏸️ Індексацію перервано зупинкою: оброблені файли буде збережено, решту - при наступному запуску");
+                self.interrupted = true;
+                break;
+            }
+
             let path = entry.path();
 
             // Перевіряємо чи потрібно пропустити цей запис
-            if Self::should_skip_entry_static(&entry, &excluded_folders) {
+            if Self::should_skip_entry_static(&entry, &self.excluded_folders) {
                 continue;
             }
 
@@ -134,15 +203,16 @@ impl FolderProcessor {
                             .as_secs();
 
                         // Перевіряємо чи потрібно оновлювати файл
-                        let should_process = if let Some((doc_index, existing_modified)) = existing_docs_map.get(&file_path) {
+                        let should_process = if let Some((_, existing_modified)) = existing_docs_map.get(&file_path) {
                             if file_last_modified > *existing_modified {
-                                // Файл змінився, видаляємо старий запис
-                                index.total_words -= index.documents[*doc_index].word_count;
-                                println!("🔄 Оновлення файлу: {}", path.file_name().unwrap_or_default().to_string_lossy());
+                                // Файл змінився - запис буде замінено після успішного парсингу
+                                debug!("🔄 Оновлення файлу: {}", path.file_name().unwrap_or_default().to_string_lossy());
                                 true
                             } else {
-                                // Файл не змінився
-                                self.skipped_files += 1;
+                                // Файл не змінився (документ з контрольної точки вже враховано)
+                                if !resumed_paths.contains(&file_path) {
+                                    self.skipped_files += 1;
+                                }
                                 false
                             }
                         } else {
@@ -154,23 +224,19 @@ impl FolderProcessor {
                             match self.process_docx_file(&file_path) {
                                 Ok(new_document) => {
                                     let doc_index = if let Some((doc_index, _)) = existing_docs_map.remove(&file_path) {
-                                        // Замінюємо існуючий документ на місці
-                                        index.documents[doc_index] = new_document;
+                                        // Замінюємо існуючий документ на місці (ідентифікатор зберігається)
+                                        index.replace_document(doc_index, new_document);
                                         doc_index
                                     } else {
                                         // Додаємо новий документ
-                                        index.documents.push(new_document);
-                                        index.documents.len() - 1
+                                        index.push_document(new_document)
                                     };
 
-                                    // Оновлюємо загальну статистику
-                                    index.total_words += index.documents[doc_index].word_count;
-                                    index.total_documents = index.documents.len();
-
                                     // Записуємо індекс нового/оновленого документа
                                     self.new_or_updated_indices.push(doc_index);
                                     self.processed_files += 1;
-                                    println!("✅ Оброблено: {} ({} слів)",
+                                    self.record_checkpoint(&index, doc_index);
+                                    debug!("✅ Оброблено: {} ({} слів)",
                                              path.file_name().unwrap_or_default().to_string_lossy(),
                                              index.documents[doc_index].word_count
                                     );
@@ -178,7 +244,7 @@ impl FolderProcessor {
                                 Err(error) => {
                                     let error_msg = format!("Помилка обробки {}: {}", file_path, error);
                                     self.errors.push(error_msg.clone());
-                                    println!("❌ {}", error_msg);
+                                    error!("❌ {}", error_msg);
                                 }
                             }
                         }
@@ -186,50 +252,35 @@ impl FolderProcessor {
                     Err(error) => {
                         let error_msg = format!("Помилка отримання метаданих {}: {}", file_path, error);
                         self.errors.push(error_msg.clone());
-                        println!("❌ {}", error_msg);
+                        error!("❌ {}", error_msg);
                     }
                 }
             }
         }
 
-        // Видаляємо документи для файлів, які більше не існують
-        let mut files_to_remove = Vec::new();
-        for (i, doc) in index.documents.iter().enumerate() {
-            if !found_files.contains(&doc.file_path) {
-                files_to_remove.push((i, doc.file_path.clone()));
+        // Позначаємо видаленими (tombstone) документи для файлів, які більше не існують.
+        // Слоти не зсуваються, тому позиції в інвертованому індексі лишаються дійсними
+        // до ущільнення і видалення коштує O(кількості видалених), а не O(індексу).
+        // Після переривання папку переглянуто не повністю, тож видалених не шукаємо.
+        let checked_slots = if self.interrupted { 0 } else { index.documents.len() };
+        for slot in 0..checked_slots {
+            let document = &index.documents[slot];
+            if document.deleted || found_files.contains(&document.file_path) {
+                continue;
             }
-        }
-
-        // Зберігаємо індекси видалених документів ДО видалення (для інвертованого індексу)
-        // НЕ сортуємо, щоб зберегти оригінальні індекси
-        self.deleted_indices = files_to_remove.iter().map(|(pos, _)| *pos).collect();
 
-        // Сортуємо індекси в зворотному порядку, щоб видаляти з кінця
-        files_to_remove.sort_by(|a, b| b.0.cmp(&a.0));
-
-        for (pos, file_path) in files_to_remove {
-            let removed_doc = index.documents.remove(pos);
-            index.total_words -= removed_doc.word_count;
+            debug!("🗑️  Видалено: {}", Path::new(&document.file_path).file_name().unwrap_or_default().to_string_lossy());
+            index.tombstone(slot);
+            self.deleted_indices.push(slot);
             self.deleted_files += 1;
-            println!("🗑️  Видалено: {}", std::path::Path::new(&file_path).file_name().unwrap_or_default().to_string_lossy());
         }
 
-        // Після видалення документів потрібно скоригувати індекси в new_or_updated_indices
-        // Кожен видалений документ зсуває всі наступні індекси вниз на 1
-        if !self.deleted_indices.is_empty() {
-            // Видаляємо індекси видалених документів та коригуємо інші
-            self.new_or_updated_indices = self.new_or_updated_indices.iter()
-                .filter(|&&idx| !self.deleted_indices.contains(&idx)) // Видаляємо видалені
-                .map(|&idx| {
-                    // Рахуємо скільки видалених документів було ДО цього індексу
-                    let shift = self.deleted_indices.iter().filter(|&&del_idx| del_idx < idx).count();
-                    idx - shift
-                })
-                .collect();
-        }
+        // Зберігаємо документи впорядкованими за датою (від старих до нових),
+        // щоб швидкий пошук по останніх слотах охоплював найновіші накази
+        self.sort_documents_by_date(&mut index);
 
-        // Оновлюємо загальну кількість документів
-        index.total_documents = index.documents.len();
+        // Перераховуємо загальну статистику зі списку документів
+        index.recount();
 
         // Оновлюємо timestamp індексації
         index.indexed_at = std::time::SystemTime::now()
@@ -237,24 +288,64 @@ impl FolderProcessor {
             .unwrap_or_default()
             .as_secs();
 
-        println!("\n📊 Результати інкрементної індексації:");
-        println!("   - Оброблено файлів: {}", self.processed_files);
-        println!("   - Пропущено незмінених: {}", self.skipped_files);
-        println!("   - Видалено файлів: {}", self.deleted_files);
-        println!("   - Помилок: {}", self.errors.len());
-        println!("   - Загальна кількість слів: {}", index.total_words);
+        info!("📊 Результати інкрементної індексації:");
+        info!("   - Оброблено файлів: {}", self.processed_files);
+        info!("   - Пропущено незмінених: {}", self.skipped_files);
+        info!("   - Видалено файлів: {}", self.deleted_files);
+        info!("   - Помилок: {}", self.errors.len());
+        info!("   - Загальна кількість слів: {}", index.total_words);
 
         if !self.errors.is_empty() {
-            println!("\n⚠️  ПОМИЛКИ:");
+            warn!("⚠️  ПОМИЛКИ:");
             for error in &self.errors {
-                println!("{}", error);
+                info!("{}", error);
             }
         }
 
         Ok(index)
     }
 
-    fn is_docx_file(&self, path: &Path) -> bool {
+    /// Впорядковує документи за датою (з назви файлу, шапки документа або дата створення файлу;
+    /// від старих до нових, без дати - на початку).
+    /// Перестановка виконується тільки якщо порядок порушено (напр. додано наказ заднім числом),
+    /// бо вона потребує перенумерації всіх записів інвертованого індексу.
+    pub(crate) fn sort_documents_by_date(&mut self, index: &mut DocumentIndex) {
+        let dates: Vec<Option<(u32, u32, u32)>> = index.documents.iter()
+            .map(|doc| document_date(doc).map(|date| date.ymd()))
+            .collect();
+
+        // compare_dates впорядковує від нових до старих, тому аргументи навпаки
+        // (той самий порядок, що й у sort_by нижче: наступний не старший за попередній)
+        let is_sorted = dates.windows(2)
+            .all(|pair| self.compare_dates(pair[1], pair[0]) != std::cmp::Ordering::Greater);
+        if is_sorted {
+            return;
+        }
+
+        let mut order: Vec<usize> = (0..index.documents.len()).collect();
+        order.sort_by(|&a, &b| self.compare_dates(dates[b], dates[a]));
+
+        info!("📅 Впорядкування {} документів за датою...", index.documents.len());
+        let slot_map = index.reorder(&order);
+
+        let remap = |slots: &[usize]| -> Vec<usize> {
+            slots.iter().filter_map(|&slot| slot_map[slot]).collect()
+        };
+        self.new_or_updated_indices = remap(&self.new_or_updated_indices);
+        self.deleted_indices = remap(&self.deleted_indices);
+        self.slot_map = Some(slot_map);
+    }
+
+    fn record_checkpoint(&mut self, index: &DocumentIndex, slot: usize) {
+        if let Some(checkpoint) = &mut self.checkpoint {
+            // Без контрольної точки індексація триває - лише без можливості продовжити
+            if let Err(e) = checkpoint.record(index, slot) {
+                warn!("⚠️ Не вдалося записати контрольну точку індексації: {}", e);
+            }
+        }
+    }
+
+    pub(crate) fn is_docx_file(&self, path: &Path) -> bool {
         // Пропускаємо тимчасові файли Office (~$) та системні файли
         if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
             let fname_lower = filename.to_lowercase();
@@ -280,13 +371,17 @@ impl FolderProcessor {
         ext_lower == "docx"
     }
 
-    fn process_docx_file(&self, file_path: &str) -> Result<DocumentRecord, String> {
+    pub(crate) fn process_docx_file(&self, file_path: &str) -> Result<DocumentRecord, ParseError> {
         // Використовуємо новий парсер зі збереженням структури
         let paragraphs = parse_docx_with_structure(file_path)?;
-        DocumentRecord::new_with_paragraphs(file_path.to_string(), paragraphs)
+        let mut record = DocumentRecord::new_with_paragraphs(file_path.to_string(), paragraphs)?;
+        if let Some(classifier) = &self.classifier {
+            record.category = classifier.classify(&record.paragraphs);
+        }
+        Ok(record)
     }
 
-    fn should_skip_entry_static(entry: &DirEntry, excluded_folders: &[&str]) -> bool {
+    fn should_skip_entry_static(entry: &DirEntry, excluded_folders: &[String]) -> bool {
         let path = entry.path();
         let path_str = path.to_string_lossy().to_lowercase();
 
@@ -313,4 +408,37 @@ impl FolderProcessor {
 
         false
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+
+    #[test]
+    fn test_interrupted_indexing_keeps_unvisited_documents() {
+        let dir = TempIndexDir::new("folder_interrupt");
+
+        // Документ, файлу якого в папці немає: повна обробка позначає його видаленим
+        let existing = || {
+            let mut index = DocumentIndex::new();
+            index.push_document(DocumentRecord::from_texts(&dir.join("2024/наказ.docx").to_string_lossy(), ["текст наказу"]));
+            index
+        };
+        let folder = dir.to_string_lossy().to_string();
+
+        let shutdown = Shutdown::default();
+        shutdown.request();
+
+        // Перервана обробка бачила не всю папку - нічого не видаляє
+        let mut processor = FolderProcessor::new().with_shutdown(shutdown);
+        let index = processor.process_folder_incremental(&folder, Some(existing())).unwrap();
+        assert!(processor.interrupted);
+        assert_eq!((processor.deleted_files, index.documents[0].deleted), (0, false));
+
+        let mut processor = FolderProcessor::new();
+        let index = processor.process_folder_incremental(&folder, Some(existing())).unwrap();
+        assert!(!processor.interrupted);
+        assert_eq!((processor.deleted_files, index.documents[0].deleted), (1, true));
+    }
+}
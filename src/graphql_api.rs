@@ -0,0 +1,441 @@
+//! GraphQL API поряд з REST: клієнт (портал інтранету) сам вибирає потрібні поля, тож
+//! абзаци документів передаються лише тим, хто їх запитав. POST /api/graphql приймає
+//! {"query", "variables"}, GET /api/graphql повертає схему (SDL). Запити лише читають:
+//! пошук, документи індексу, фасети (види наказів і роки) та статистику індексу.
+//! Маскування чутливих даних діє так само, як для REST.
+
+use async_graphql::{ComplexObject, Context, EmptyMutation, EmptySubscription, Enum, Object, Result, Schema, SimpleObject};
+use blazing_search::classifier;
+use blazing_search::document_date::{self, DateSource, DocumentDate};
+use blazing_search::document_record::{DocumentRecord, Paragraph};
+use blazing_search::index_stats::IndexStats;
+use blazing_search::redaction::Redactor;
+use blazing_search::{tr, SearchEngine, SearchEngineResult, SearchMode};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Найбільша сторінка документів і результатів пошуку
+const MAX_LIMIT: usize = 500;
+/// Сторінка списку документів за замовчуванням
+const DEFAULT_DOCUMENTS_LIMIT: usize = 50;
+
+pub type ApiSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn build_schema(search_engine: Arc<SearchEngine>) -> ApiSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(search_engine)
+        .finish()
+}
+
+/// Маскування для одного запиту (див. web_server::request_redactor)
+pub struct RequestRedactor(pub Arc<Redactor>);
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(name = "DateSource")]
+pub enum GqlDateSource {
+    FileName,
+    Content,
+    Modified,
+    Created,
+}
+
+impl From<DateSource> for GqlDateSource {
+    fn from(source: DateSource) -> Self {
+        match source {
+            DateSource::FileName => Self::FileName,
+            DateSource::Content => Self::Content,
+            DateSource::Modified => Self::Modified,
+            DateSource::Created => Self::Created,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Paragraph")]
+pub struct GqlParagraph {
+    pub text: String,
+    pub line_breaks_after: usize,
+}
+
+#[derive(SimpleObject)]
+pub struct Match {
+    pub context: String,
+    /// Номер абзацу в документі
+    pub position: usize,
+}
+
+/// Знайдений документ
+#[derive(SimpleObject)]
+pub struct SearchHit {
+    pub file_name: String,
+    pub file_path: String,
+    pub file_size: u64,
+    pub last_modified: u64,
+    pub category: Option<String>,
+    /// Дата, за якою впорядковано документ (ДД.ММ.РРРР)
+    pub date: Option<String>,
+    pub date_source: Option<GqlDateSource>,
+    pub matches: Vec<Match>,
+    /// Усі абзаци документа - запитуйте лише за потреби
+    pub paragraphs: Vec<GqlParagraph>,
+}
+
+/// Значення фасета і кількість документів з ним
+#[derive(SimpleObject)]
+pub struct FacetValue {
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(SimpleObject)]
+pub struct Facets {
+    /// Види наказів (див. classification.categories)
+    pub categories: Vec<FacetValue>,
+    /// Роки за датою документа, від найновішого
+    pub years: Vec<FacetValue>,
+}
+
+impl Facets {
+    fn from_documents<'a>(documents: impl Iterator<Item = (Option<&'a str>, Option<DocumentDate>)>) -> Self {
+        let mut categories: BTreeMap<String, usize> = BTreeMap::new();
+        let mut years: BTreeMap<u32, usize> = BTreeMap::new();
+        for (category, date) in documents {
+            if let Some(category) = category {
+                *categories.entry(category.to_string()).or_insert(0) += 1;
+            }
+            if let Some(date) = date {
+                *years.entry(date.year).or_insert(0) += 1;
+            }
+        }
+        Self {
+            categories: categories.into_iter().map(|(value, count)| FacetValue { value, count }).collect(),
+            years: years.into_iter().rev().map(|(year, count)| FacetValue { value: year.to_string(), count }).collect(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct SearchResults {
+    /// Документів після фільтра за видом (без урахування limit)
+    pub count: usize,
+    /// Документів в індексі
+    pub total_count: usize,
+    /// Фасети знайдених документів (до фільтра за видом)
+    pub facets: Facets,
+    pub documents: Vec<SearchHit>,
+    /// Час на запит (search.query_timeout_ms) вичерпано - результати неповні
+    pub partial: bool,
+}
+
+/// Документ індексу; абзаци читаються з індексу, лише якщо їх запитано
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Document {
+    pub file_name: String,
+    pub file_path: String,
+    pub file_size: u64,
+    pub last_modified: u64,
+    pub created: u64,
+    pub word_count: usize,
+    pub category: Option<String>,
+    pub date: Option<String>,
+    pub date_source: Option<GqlDateSource>,
+    #[graphql(skip)]
+    pub order_date: Option<DocumentDate>,
+}
+
+#[ComplexObject]
+impl Document {
+    async fn paragraphs(&self, ctx: &Context<'_>) -> Vec<GqlParagraph> {
+        let paragraphs = search_engine(ctx).with_indices(|_, doc_index, _| {
+            doc_index
+                .documents
+                .iter()
+                .find(|d| !d.deleted && d.file_path == self.file_path)
+                .map(DocumentRecord::get_paragraphs)
+                .unwrap_or_default()
+        });
+        let redactor = ctx.data_opt::<RequestRedactor>();
+        paragraphs
+            .into_iter()
+            .map(|p| paragraph(p, redactor))
+            .collect()
+    }
+}
+
+impl From<&DocumentRecord> for Document {
+    fn from(record: &DocumentRecord) -> Self {
+        let date = document_date::document_date(record);
+        Self {
+            file_name: record.file_name.clone(),
+            file_path: record.file_path.clone(),
+            file_size: record.file_size,
+            last_modified: record.last_modified,
+            created: record.created,
+            word_count: record.word_count,
+            category: record.category.clone(),
+            date: date.map(|d| d.formatted()),
+            date_source: date.map(|d| d.source.into()),
+            order_date: date,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct DocumentPage {
+    /// Документів, що відповідають фільтрам (без урахування сторінки)
+    pub total: usize,
+    pub documents: Vec<Document>,
+}
+
+#[derive(SimpleObject)]
+pub struct YearStats {
+    /// null - рік визначити не вдалося
+    pub year: Option<u32>,
+    pub documents: usize,
+    pub words: usize,
+    /// Документи за місяцями, січень - перший
+    pub months: Vec<usize>,
+    /// Місяці без жодного документа - ймовірні прогалини в архіві
+    pub missing_months: Vec<u32>,
+}
+
+#[derive(SimpleObject)]
+pub struct Stats {
+    pub generation: Option<u64>,
+    pub indexed_at: Option<u64>,
+    pub documents: usize,
+    pub deleted_slots: usize,
+    pub total_words: usize,
+    pub unique_words: Option<usize>,
+    pub by_year: Vec<YearStats>,
+}
+
+impl From<IndexStats> for Stats {
+    fn from(stats: IndexStats) -> Self {
+        Self {
+            generation: stats.generation,
+            indexed_at: stats.indexed_at,
+            documents: stats.documents,
+            deleted_slots: stats.deleted_slots,
+            total_words: stats.total_words,
+            unique_words: stats.unique_words,
+            by_year: stats
+                .by_year
+                .into_iter()
+                .map(|year| YearStats {
+                    year: year.year,
+                    documents: year.documents,
+                    words: year.words,
+                    months: year.months.to_vec(),
+                    missing_months: year.missing_months,
+                })
+                .collect(),
+        }
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object(name = "Query")]
+impl QueryRoot {
+    /// Пошук за фразою: full - усі документи (інакше лише найновіші, як швидкий пошук REST),
+    /// viewMode "fragments" пропускає абзаци "Підстава"
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        query: String,
+        #[graphql(default)] full: bool,
+        view_mode: Option<String>,
+        category: Option<String>,
+        limit: Option<usize>,
+    ) -> Result<SearchResults> {
+        if query.trim().is_empty() {
+            return Err(tr!(EmptyQuery).into());
+        }
+
+        let search_engine = search_engine(ctx);
+        let mode = if full { SearchMode::Full } else { SearchMode::Quick };
+        let outcome = search_engine
+            .search_outcome(&query, mode, view_mode.as_deref())
+            .await
+            .map_err(|e| tr!(SearchFailed, e))?;
+        let mut results = outcome.results;
+
+        let facets = Facets::from_documents(results.iter().map(|r| (r.category.as_deref(), r.date)));
+        classifier::filter_by_category(&mut results, category.as_deref());
+        let count = results.len();
+        results.truncate(limit.unwrap_or(MAX_LIMIT).min(MAX_LIMIT));
+        if let Some(RequestRedactor(redactor)) = ctx.data_opt::<RequestRedactor>() {
+            redactor.redact_results(&mut results);
+        }
+
+        Ok(SearchResults {
+            count,
+            total_count: search_engine.get_stats().0,
+            facets,
+            documents: results.into_iter().map(search_hit).collect(),
+            partial: outcome.partial,
+        })
+    }
+
+    /// Документ за шляхом у кеші; null - такого документа в індексі немає
+    async fn document(&self, ctx: &Context<'_>, path: String) -> Option<Document> {
+        search_engine(ctx).with_indices(|_, doc_index, _| {
+            doc_index
+                .documents
+                .iter()
+                .find(|d| !d.deleted && d.file_path == path)
+                .map(Document::from)
+        })
+    }
+
+    /// Документи індексу від найновіших, з фільтрами за видом і роком
+    async fn documents(
+        &self,
+        ctx: &Context<'_>,
+        category: Option<String>,
+        year: Option<u32>,
+        #[graphql(default)] offset: usize,
+        limit: Option<usize>,
+    ) -> DocumentPage {
+        let limit = limit.unwrap_or(DEFAULT_DOCUMENTS_LIMIT).min(MAX_LIMIT);
+        search_engine(ctx).with_indices(|_, doc_index, _| {
+            let mut documents: Vec<Document> = doc_index
+                .documents
+                .iter()
+                .filter(|d| !d.deleted)
+                .filter(|d| category.is_none() || d.category == category)
+                .map(Document::from)
+                .filter(|d| year.is_none() || d.order_date.map(|date| date.year) == year)
+                .collect();
+            documents.sort_by_key(|d| std::cmp::Reverse(d.order_date.map(|date| date.ymd())));
+            let total = documents.len();
+            DocumentPage {
+                total,
+                documents: documents.into_iter().skip(offset).take(limit).collect(),
+            }
+        })
+    }
+
+    /// Види наказів і роки всіх документів індексу
+    async fn facets(&self, ctx: &Context<'_>) -> Facets {
+        search_engine(ctx).with_indices(|_, doc_index, _| {
+            Facets::from_documents(
+                doc_index
+                    .documents
+                    .iter()
+                    .filter(|d| !d.deleted)
+                    .map(|d| (d.category.as_deref(), document_date::document_date(d))),
+            )
+        })
+    }
+
+    /// Статистика завантаженого покоління (як GET /api/admin/index-stats)
+    async fn stats(&self, ctx: &Context<'_>) -> Stats {
+        search_engine(ctx).with_indices(IndexStats::from_indices).into()
+    }
+}
+
+fn search_engine<'a>(ctx: &Context<'a>) -> &'a Arc<SearchEngine> {
+    ctx.data_unchecked::<Arc<SearchEngine>>()
+}
+
+fn paragraph(paragraph: Paragraph, redactor: Option<&RequestRedactor>) -> GqlParagraph {
+    let text = match redactor {
+        Some(RequestRedactor(redactor)) => redactor.redact(&paragraph.text).into_owned(),
+        None => paragraph.text,
+    };
+    GqlParagraph {
+        text,
+        line_breaks_after: paragraph.line_breaks_after,
+    }
+}
+
+fn search_hit(result: SearchEngineResult) -> SearchHit {
+    SearchHit {
+        file_name: result.file_name,
+        file_path: result.file_path,
+        file_size: result.file_size,
+        last_modified: result.last_modified,
+        category: result.category,
+        date: result.date.map(|d| d.formatted()),
+        date_source: result.date.map(|d| d.source.into()),
+        matches: result
+            .matches
+            .into_iter()
+            .map(|m| Match { context: m.context, position: m.position })
+            .collect(),
+        paragraphs: result.all_paragraphs.into_iter().map(|p| paragraph(p, None)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blazing_search::config::RedactionRuleConfig;
+    use blazing_search::document_record::DocumentIndex;
+    use blazing_search::index_paths::IndexPaths;
+    use blazing_search::inverted_index::InvertedIndex;
+    use std::fs;
+
+    fn document(path: &str, text: &str, category: &str) -> DocumentRecord {
+        DocumentRecord { category: Some(category.to_string()), ..DocumentRecord::from_texts(path, text.split('\n')) }
+    }
+
+    #[tokio::test]
+    async fn test_schema_returns_requested_fields_with_redaction() {
+        let dir = std::env::temp_dir().join(format!("blazing_graphql_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let paths = IndexPaths::new(&dir);
+        paths.ensure_dir().unwrap();
+
+        let mut doc_index = DocumentIndex::new();
+        doc_index.push_document(document(
+            "2024/Наказ №2 від 05.02.2024.docx",
+            "НАКАЗ\nНадати відпустку солдату Петренку, РНОКПП 3012345678",
+            "відпустки",
+        ));
+        doc_index.push_document(document("2023/Наказ №9 від 10.10.2023.docx", "НАКАЗ\nВідрядити солдата Петренка", "відрядження"));
+        doc_index.push_document(document("2023/Наказ №3 від 20.01.2023.docx", "НАКАЗ\nПризначити сержанта Іваненка", "кадровий"));
+        doc_index.recount();
+        let inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+        fs::write(paths.documents_index(), serde_json::to_string(&doc_index).unwrap()).unwrap();
+        fs::write(paths.inverted_index(), serde_json::to_string(&inv_index).unwrap()).unwrap();
+
+        let engine = SearchEngine::new(&paths);
+        engine.reload().unwrap();
+        let schema = build_schema(Arc::new(engine));
+
+        // Лише запитані поля: абзаців у відповіді немає
+        let response = schema
+            .execute(r#"{ search(query: "петренко", full: true) { count facets { categories { value count } years { value } } documents { fileName date } } }"#)
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let json = serde_json::to_value(&response.data).unwrap();
+        assert_eq!(json["search"]["count"], 2);
+        assert_eq!(json["search"]["documents"][0], serde_json::json!({"fileName": "Наказ №2 від 05.02.2024.docx", "date": "05.02.2024"}));
+        assert_eq!(json["search"]["facets"]["years"], serde_json::json!([{"value": "2024"}, {"value": "2023"}]));
+        assert_eq!(json["search"]["facets"]["categories"].as_array().unwrap().len(), 2);
+
+        let redactor = Redactor::new(&[RedactionRuleConfig {
+            name: "ІПН".to_string(),
+            pattern: r"\b\d{10}\b".to_string(),
+            replacement: None,
+        }])
+        .unwrap();
+        let request = async_graphql::Request::new(
+            r#"{ documents(year: 2024) { total documents { filePath paragraphs { text } } } stats { documents } }"#,
+        )
+        .data(RequestRedactor(Arc::new(redactor)));
+        let json = serde_json::to_value(&schema.execute(request).await.data).unwrap();
+        assert_eq!(json["documents"]["total"], 1);
+        assert_eq!(json["documents"]["documents"][0]["paragraphs"][1]["text"], "Надати відпустку солдату Петренку, РНОКПП [ІПН]");
+        assert_eq!(json["stats"]["documents"], 3);
+
+        let response = schema.execute(r#"{ search(query: " ") { count } }"#).await;
+        assert_eq!(response.errors[0].message, tr!(EmptyQuery));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
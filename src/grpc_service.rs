@@ -0,0 +1,317 @@
+//! gRPC-сервер для інтеграції між службами, де JSON через HTTP заповільний для масових
+//! запитів: ті самі операції, що й REST (пошук, документи, статистика). Контракт -
+//! proto/blazing_search.proto, код генерує build.rs. Вмикається параметром server.grpc_port.
+//! Ролей у gRPC немає: якщо маскування увімкнено, тексти маскуються завжди (як у бота).
+
+use blazing_search::classifier;
+use blazing_search::document_date::{self, DateSource, DocumentDate};
+use blazing_search::document_record::{DocumentRecord, Paragraph};
+use blazing_search::error::{IndexError, SearchError};
+use blazing_search::index_stats::IndexStats;
+use blazing_search::redaction::Redactor;
+use blazing_search::shutdown::Shutdown;
+use blazing_search::{tr, SearchEngine, SearchEngineResult, SearchMode};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tonic::{Request, Response, Status};
+
+pub mod proto {
+    tonic::include_proto!("blazing_search.v1");
+}
+
+use proto::search_service_server::{SearchService, SearchServiceServer};
+
+/// Запускає gRPC-сервер, якщо задано server.grpc_port
+pub fn start(
+    search_engine: Arc<SearchEngine>,
+    port: Option<u16>,
+    redactor: Option<Arc<Redactor>>,
+    shutdown: Shutdown,
+) -> Option<JoinHandle<()>> {
+    let port = port?;
+    info!("{}", tr!(GrpcServerStarting, port));
+
+    let service = GrpcSearchService { search_engine, redactor };
+    Some(tokio::spawn(async move {
+        let result = tonic::transport::Server::builder()
+            .add_service(SearchServiceServer::new(service))
+            .serve_with_shutdown(SocketAddr::from(([0, 0, 0, 0], port)), shutdown.wait())
+            .await;
+        if let Err(e) = result {
+            warn!("{}", tr!(GrpcServerFailed, e));
+        }
+        info!("{}", tr!(GrpcServerStopped));
+    }))
+}
+
+pub struct GrpcSearchService {
+    search_engine: Arc<SearchEngine>,
+    redactor: Option<Arc<Redactor>>,
+}
+
+#[tonic::async_trait]
+impl SearchService for GrpcSearchService {
+    async fn search(&self, request: Request<proto::SearchRequest>) -> Result<Response<proto::SearchResponse>, Status> {
+        let request = request.into_inner();
+        if request.query.trim().is_empty() {
+            return Err(Status::invalid_argument(tr!(EmptyQuery)));
+        }
+
+        let mode = if request.full { SearchMode::Full } else { SearchMode::Quick };
+        let view_mode = request.fragments.then_some("fragments");
+        let outcome = self
+            .search_engine
+            .search_outcome(&request.query, mode, view_mode)
+            .await
+            .map_err(search_status)?;
+        let mut results = outcome.results;
+
+        let categories = classifier::category_counts(&results)
+            .into_iter()
+            .map(|(category, count)| (category, count as u32))
+            .collect();
+        classifier::filter_by_category(&mut results, request.category.as_deref());
+        let count = results.len() as u32;
+        if request.limit > 0 {
+            results.truncate(request.limit as usize);
+        }
+        if let Some(redactor) = &self.redactor {
+            redactor.redact_results(&mut results);
+        }
+
+        Ok(Response::new(proto::SearchResponse {
+            count,
+            total_count: self.search_engine.get_stats().0 as u32,
+            documents: results
+                .into_iter()
+                .map(|result| search_document(result, request.include_paragraphs))
+                .collect(),
+            categories,
+            partial: outcome.partial,
+        }))
+    }
+
+    async fn get_document(&self, request: Request<proto::GetDocumentRequest>) -> Result<Response<proto::Document>, Status> {
+        let request = request.into_inner();
+        let document = self.search_engine.with_indices(|_, doc_index, _| {
+            doc_index
+                .documents
+                .iter()
+                .find(|d| !d.deleted && d.file_path == request.path)
+                .map(|d| self.index_document(d, request.include_paragraphs))
+        });
+        document
+            .map(Response::new)
+            .ok_or_else(|| Status::not_found(tr!(DocumentNotInIndex, request.path)))
+    }
+
+    async fn get_documents(
+        &self,
+        request: Request<proto::GetDocumentsRequest>,
+    ) -> Result<Response<proto::GetDocumentsResponse>, Status> {
+        let request = request.into_inner();
+        let response = self.search_engine.with_indices(|_, doc_index, _| {
+            let by_path: HashMap<&str, &DocumentRecord> = doc_index
+                .documents
+                .iter()
+                .filter(|d| !d.deleted)
+                .map(|d| (d.file_path.as_str(), d))
+                .collect();
+
+            let mut response = proto::GetDocumentsResponse::default();
+            for path in request.paths {
+                match by_path.get(path.as_str()) {
+                    Some(document) => response.documents.push(self.index_document(document, request.include_paragraphs)),
+                    None => response.missing_paths.push(path),
+                }
+            }
+            response
+        });
+        Ok(Response::new(response))
+    }
+
+    async fn get_stats(&self, _request: Request<proto::GetStatsRequest>) -> Result<Response<proto::Stats>, Status> {
+        let stats = self.search_engine.with_indices(IndexStats::from_indices);
+        Ok(Response::new(stats_message(stats)))
+    }
+}
+
+impl GrpcSearchService {
+    fn index_document(&self, record: &DocumentRecord, include_paragraphs: bool) -> proto::Document {
+        let paragraphs = if include_paragraphs {
+            record
+                .get_paragraphs()
+                .into_iter()
+                .map(|mut p| {
+                    if let Some(redactor) = &self.redactor {
+                        p.text = redactor.redact(&p.text).into_owned();
+                    }
+                    paragraph(p)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let date = document_date::document_date(record);
+        proto::Document {
+            file_name: record.file_name.clone(),
+            file_path: record.file_path.clone(),
+            file_size: record.file_size,
+            last_modified: record.last_modified,
+            category: record.category.clone(),
+            date: date.map(|d| d.formatted()),
+            date_source: date_source(date) as i32,
+            matches: Vec::new(),
+            paragraphs,
+            word_count: record.word_count as u32,
+        }
+    }
+}
+
+/// Індекс ще не створено або його оновлює інший процес - тимчасова недоступність
+fn search_status(err: SearchError) -> Status {
+    let message = tr!(SearchFailed, err);
+    match err {
+        SearchError::Index(IndexError::NotFound(_) | IndexError::Locked(_)) => Status::unavailable(message),
+        SearchError::Busy(_) => Status::resource_exhausted(message),
+        _ => Status::internal(message),
+    }
+}
+
+fn search_document(result: SearchEngineResult, include_paragraphs: bool) -> proto::Document {
+    proto::Document {
+        file_name: result.file_name,
+        file_path: result.file_path,
+        file_size: result.file_size,
+        last_modified: result.last_modified,
+        category: result.category,
+        date: result.date.map(|d| d.formatted()),
+        date_source: date_source(result.date) as i32,
+        matches: result
+            .matches
+            .into_iter()
+            .map(|m| proto::Match { context: m.context, position: m.position as u32 })
+            .collect(),
+        paragraphs: if include_paragraphs {
+            result.all_paragraphs.into_iter().map(paragraph).collect()
+        } else {
+            Vec::new()
+        },
+        word_count: 0,
+    }
+}
+
+fn paragraph(paragraph: Paragraph) -> proto::Paragraph {
+    proto::Paragraph {
+        text: paragraph.text,
+        line_breaks_after: paragraph.line_breaks_after as u32,
+    }
+}
+
+fn date_source(date: Option<DocumentDate>) -> proto::DateSource {
+    match date.map(|d| d.source) {
+        None => proto::DateSource::Unspecified,
+        Some(DateSource::FileName) => proto::DateSource::FileName,
+        Some(DateSource::Content) => proto::DateSource::Content,
+        Some(DateSource::Modified) => proto::DateSource::Modified,
+        Some(DateSource::Created) => proto::DateSource::Created,
+    }
+}
+
+fn stats_message(stats: IndexStats) -> proto::Stats {
+    proto::Stats {
+        generation: stats.generation,
+        indexed_at: stats.indexed_at,
+        documents: stats.documents as u32,
+        deleted_slots: stats.deleted_slots as u32,
+        total_words: stats.total_words as u64,
+        unique_words: stats.unique_words.map(|words| words as u64),
+        by_year: stats
+            .by_year
+            .into_iter()
+            .map(|year| proto::YearStats {
+                year: year.year.unwrap_or(0),
+                documents: year.documents as u32,
+                words: year.words as u64,
+                months: year.months.iter().map(|&count| count as u32).collect(),
+                missing_months: year.missing_months,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blazing_search::config::RedactionRuleConfig;
+    use blazing_search::document_record::DocumentIndex;
+    use blazing_search::index_paths::IndexPaths;
+    use blazing_search::inverted_index::InvertedIndex;
+    use std::fs;
+
+    fn document(path: &str, text: &str) -> DocumentRecord {
+        DocumentRecord::from_texts(path, text.split('\n'))
+    }
+
+    #[tokio::test]
+    async fn test_search_and_bulk_lookup() {
+        let dir = std::env::temp_dir().join(format!("blazing_grpc_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let paths = IndexPaths::new(&dir);
+        paths.ensure_dir().unwrap();
+
+        let mut doc_index = DocumentIndex::new();
+        doc_index.push_document(document("2024/Наказ №2 від 05.02.2024.docx", "НАКАЗ\nВідпустка солдату Петренку, РНОКПП 3012345678"));
+        doc_index.push_document(document("2023/Наказ №9 від 10.10.2023.docx", "НАКАЗ\nВідрядити солдата Петренка"));
+        doc_index.recount();
+        let inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+        fs::write(paths.documents_index(), serde_json::to_string(&doc_index).unwrap()).unwrap();
+        fs::write(paths.inverted_index(), serde_json::to_string(&inv_index).unwrap()).unwrap();
+
+        let engine = SearchEngine::new(&paths);
+        engine.reload().unwrap();
+        let redactor = Redactor::new(&[RedactionRuleConfig {
+            name: "ІПН".to_string(),
+            pattern: r"\b\d{10}\b".to_string(),
+            replacement: None,
+        }])
+        .unwrap();
+        let service = GrpcSearchService {
+            search_engine: Arc::new(engine),
+            redactor: Some(Arc::new(redactor)),
+        };
+
+        // Без include_paragraphs абзаци не передаються
+        let search = proto::SearchRequest { query: "петренко".to_string(), full: true, limit: 1, ..Default::default() };
+        let response = service.search(Request::new(search)).await.unwrap().into_inner();
+        assert_eq!((response.count, response.total_count, response.documents.len()), (2, 2, 1));
+        let hit = &response.documents[0];
+        assert_eq!(hit.date.as_deref(), Some("05.02.2024"));
+        assert_eq!(hit.date_source, proto::DateSource::FileName as i32);
+        assert_eq!(hit.matches[0].context, "Відпустка солдату Петренку, РНОКПП [ІПН]");
+        assert!(hit.paragraphs.is_empty());
+
+        let bulk = proto::GetDocumentsRequest {
+            paths: vec!["2023/Наказ №9 від 10.10.2023.docx".to_string(), "2022/немає.docx".to_string()],
+            include_paragraphs: true,
+        };
+        let response = service.get_documents(Request::new(bulk)).await.unwrap().into_inner();
+        assert_eq!(response.documents[0].paragraphs[1].text, "Відрядити солдата Петренка");
+        assert_eq!(response.documents[0].word_count, 4);
+        assert_eq!(response.missing_paths, vec!["2022/немає.docx"]);
+
+        let missing = proto::GetDocumentRequest { path: "2022/немає.docx".to_string(), include_paragraphs: false };
+        assert_eq!(service.get_document(Request::new(missing)).await.unwrap_err().code(), tonic::Code::NotFound);
+        let empty = proto::SearchRequest { query: " ".to_string(), ..Default::default() };
+        assert_eq!(service.search(Request::new(empty)).await.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+        let stats = service.get_stats(Request::new(proto::GetStatsRequest {})).await.unwrap().into_inner();
+        assert_eq!(stats.documents, 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,131 @@
+//! Підсвічування знайдених слів на сервері для перегляду "Повний документ". Абзац збігається
+//! з запитом, якщо містить кожен термін після стемінгу (так перевіряє search); підсвічується
+//! ціле слово, в якому знайдено термін ("петренк" -> "ПЕТРЕНКУ"). Межі повертаються в
+//! одиницях UTF-16 - як індекси рядків JavaScript, тож браузер не шукає текст сам.
+
+use regex::Regex;
+
+/// Що підсвічувати в абзацах, що збіглися
+#[derive(Clone, Copy)]
+pub enum Highlight<'a> {
+    /// Слова з термінами запиту (див. term_ranges)
+    Terms(&'a [String]),
+    /// Збіги регулярного виразу (пошук у режимі regex)
+    Pattern(&'a Regex),
+}
+
+impl Highlight<'_> {
+    pub fn ranges(&self, text: &str) -> Vec<[usize; 2]> {
+        match self {
+            Highlight::Terms(terms) => term_ranges(text, terms),
+            Highlight::Pattern(regex) => pattern_ranges(text, regex),
+        }
+    }
+
+    /// Терміни запиту (для виразу - жодного)
+    pub fn terms(&self) -> &[String] {
+        match self {
+            Highlight::Terms(terms) => terms,
+            Highlight::Pattern(_) => &[],
+        }
+    }
+}
+
+/// Апострофи всередині слова ("п'ятий", "пʼятий")
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '\'' | '’' | 'ʼ')
+}
+
+/// Діапазони [початок, кінець) слів тексту, що містять терміни запиту, у одиницях UTF-16,
+/// без перетинів і за зростанням
+pub fn term_ranges(text: &str, terms: &[String]) -> Vec<[usize; 2]> {
+    let original: Vec<char> = text.chars().collect();
+
+    // Текст так, як його перевіряє пошук: нижній регістр без "'"; для кожного символу -
+    // індекс символу оригіналу
+    let mut normalized: Vec<char> = Vec::with_capacity(original.len());
+    let mut origin: Vec<usize> = Vec::with_capacity(original.len());
+    for (i, c) in original.iter().enumerate().filter(|(_, c)| **c != '\'') {
+        for lower in c.to_lowercase() {
+            normalized.push(lower);
+            origin.push(i);
+        }
+    }
+
+    let mut ranges: Vec<[usize; 2]> = Vec::new();
+    for term in terms {
+        let term: Vec<char> = term.chars().collect();
+        if term.is_empty() || term.len() > normalized.len() {
+            continue;
+        }
+        for start in 0..=normalized.len() - term.len() {
+            if normalized[start..start + term.len()] != term[..] {
+                continue;
+            }
+            let (mut from, mut to) = (origin[start], origin[start + term.len() - 1] + 1);
+            while from > 0 && is_word_char(original[from - 1]) {
+                from -= 1;
+            }
+            while to < original.len() && is_word_char(original[to]) {
+                to += 1;
+            }
+            ranges.push([from, to]);
+        }
+    }
+
+    ranges.sort_unstable();
+    let mut merged: Vec<[usize; 2]> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range[0] <= last[1] => last[1] = last[1].max(range[1]),
+            _ => merged.push(range),
+        }
+    }
+
+    // Індекси символів -> одиниці UTF-16
+    let mut utf16 = Vec::with_capacity(original.len() + 1);
+    utf16.push(0);
+    for c in &original {
+        utf16.push(utf16[utf16.len() - 1] + c.len_utf16());
+    }
+    merged.into_iter().map(|[from, to]| [utf16[from], utf16[to]]).collect()
+}
+
+/// Діапазони збігів виразу в тексті, у одиницях UTF-16 (порожні збіги пропускаються)
+pub fn pattern_ranges(text: &str, regex: &Regex) -> Vec<[usize; 2]> {
+    let utf16 = |byte: usize| text[..byte].encode_utf16().count();
+    regex
+        .find_iter(text)
+        .filter(|found| !found.is_empty())
+        .map(|found| [utf16(found.start()), utf16(found.end())])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_whole_words_highlighted_in_utf16_units() {
+        let text = "Лейтенанту ПЕТРЕНКУ Ігорю Миколайовичу, п'ятому взводу";
+        let ranges = term_ranges(text, &terms(&["петренк", "іго", "пятом"]));
+        let words: Vec<String> = ranges
+            .iter()
+            .map(|[from, to]| String::from_utf16(&text.encode_utf16().collect::<Vec<_>>()[*from..*to]).unwrap())
+            .collect();
+        assert_eq!(words, vec!["ПЕТРЕНКУ", "Ігорю", "п'ятому"]);
+
+        // Символи поза BMP займають дві одиниці UTF-16
+        assert_eq!(term_ranges("😀 наказ", &terms(&["наказ"])), vec![[3, 8]]);
+        // Кілька термінів в одному слові - один діапазон
+        assert_eq!(term_ranges("відпустка", &terms(&["відпуст", "пустк"])), vec![[0, 9]]);
+        assert!(term_ranges("Наказ №16", &terms(&["рапорт"])).is_empty());
+
+        let regex = Regex::new(r"№\d+").unwrap();
+        assert_eq!(pattern_ranges("😀 Наказ №16, №7", &regex), vec![[9, 12], [14, 16]]);
+    }
+}
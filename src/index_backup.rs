@@ -0,0 +1,183 @@
+use crate::error::IndexError;
+use crate::index_storage::IndexStorage;
+use chrono::{Local, NaiveDateTime};
+use log::warn;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Папка з резервними копіями індексів (поруч з індексом документів)
+pub const BACKUP_DIR_NAME: &str = "index_backups";
+
+/// Формат мітки часу в назві резервної копії
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Політика зберігання резервних копій одного файлу індексу
+#[derive(Debug, Clone, Copy)]
+pub struct BackupPolicy {
+    /// Скільки найновіших копій зберігати (0 - резервні копії вимкнено)
+    pub max_count: usize,
+    /// Копії, старші за цей вік, видаляються навіть якщо ліміт кількості не вичерпано
+    pub max_age: Option<Duration>,
+}
+
+impl BackupPolicy {
+    pub const fn new(max_count: usize, max_age: Option<Duration>) -> Self {
+        Self { max_count, max_age }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_count > 0
+    }
+}
+
+/// Резервна копія файлу індексу
+#[derive(Debug, Clone)]
+pub struct BackupFile {
+    pub path: PathBuf,
+    pub created_at: NaiveDateTime,
+}
+
+/// Папка резервних копій для індексу, що лежить за base_path
+pub fn backup_dir_for(base_path: &str) -> PathBuf {
+    match Path::new(base_path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(BACKUP_DIR_NAME),
+        _ => PathBuf::from(BACKUP_DIR_NAME),
+    }
+}
+
+/// Назва копії: documents_index.json -> documents_index.20240305-143000.json
+pub fn backup_file_name(base_path: &str, created_at: NaiveDateTime) -> String {
+    let path = Path::new(base_path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("index");
+    let timestamp = created_at.format(BACKUP_TIMESTAMP_FORMAT);
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}.{}.{}", stem, timestamp, ext),
+        None => format!("{}.{}", stem, timestamp),
+    }
+}
+
+/// Час створення копії з її назви, якщо файл є копією цього індексу
+pub fn parse_backup_time(base_path: &str, file_name: &str) -> Option<NaiveDateTime> {
+    let path = Path::new(base_path);
+    let stem = path.file_stem()?.to_str()?;
+    let rest = file_name.strip_prefix(stem)?.strip_prefix('.')?;
+
+    let timestamp = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => rest.strip_suffix(ext)?.strip_suffix('.')?,
+        None => rest,
+    };
+
+    NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP_FORMAT).ok()
+}
+
+/// Створює резервну копію активного файлу індексу.
+/// Файли поколінь незмінні, тому замість копіювання достатньо жорсткого посилання;
+/// якщо файлова система їх не підтримує - звичайна копія.
+pub fn create_backup(storage: &dyn IndexStorage, source_path: &str, base_path: &str) -> Result<PathBuf, IndexError> {
+    let dir = backup_dir_for(base_path);
+    fs::create_dir_all(&dir)
+        .map_err(|e| IndexError::io("Помилка створення папки резервних копій", e))?;
+
+    let backup_path = dir.join(backup_file_name(base_path, Local::now().naive_local()));
+    if backup_path.exists() {
+        // Копія з тією ж секундою вже є
+        return Ok(backup_path);
+    }
+
+    if storage.hard_link(Path::new(source_path), &backup_path).is_err() {
+        storage.copy(Path::new(source_path), &backup_path)
+            .map_err(|e| IndexError::io(format!("Помилка створення резервної копії {}", source_path), e))?;
+    }
+
+    Ok(backup_path)
+}
+
+/// Резервні копії індексу, від найновішої до найстарішої
+pub fn list_backups(base_path: &str) -> Vec<BackupFile> {
+    let mut backups: Vec<BackupFile> = fs::read_dir(backup_dir_for(base_path))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|entry| {
+                    let file_name = entry.file_name().to_string_lossy().to_string();
+                    parse_backup_time(base_path, &file_name).map(|created_at| BackupFile {
+                        path: entry.path(),
+                        created_at,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    backups
+}
+
+/// Вибирає копії, які потрібно видалити за політикою (список - від найновішої)
+pub fn select_expired(backups: &[BackupFile], policy: &BackupPolicy, now: NaiveDateTime) -> Vec<PathBuf> {
+    backups
+        .iter()
+        .enumerate()
+        .filter(|(position, backup)| {
+            let over_count = *position >= policy.max_count;
+            let too_old = policy.max_age.is_some_and(|max_age| {
+                (now - backup.created_at).to_std().unwrap_or_default() > max_age
+            });
+            over_count || too_old
+        })
+        .map(|(_, backup)| backup.path.clone())
+        .collect()
+}
+
+/// Видаляє копії, що не вкладаються в політику зберігання. Повертає кількість видалених.
+pub fn rotate_backups(storage: &dyn IndexStorage, base_path: &str, policy: &BackupPolicy) -> usize {
+    let expired = select_expired(&list_backups(base_path), policy, Local::now().naive_local());
+
+    expired
+        .iter()
+        .filter(|path| match storage.remove_file(path) {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("⚠️ Не вдалося видалити резервну копію {}: {}", path.display(), e);
+                false
+            }
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(timestamp: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn test_backup_name_roundtrip() {
+        let name = backup_file_name("documents_index.json", at("20240305-143000"));
+        assert_eq!(name, "documents_index.20240305-143000.json");
+        assert_eq!(parse_backup_time("documents_index.json", &name), Some(at("20240305-143000")));
+
+        // Файли поколінь та інших індексів не є копіями
+        assert_eq!(parse_backup_time("documents_index.json", "documents_index.42.json"), None);
+        assert_eq!(parse_backup_time("documents_index.json", "inverted_index.20240305-143000.json"), None);
+    }
+
+    #[test]
+    fn test_select_expired_by_count_and_age() {
+        let backups: Vec<BackupFile> = ["20240310-000000", "20240309-000000", "20240301-000000"]
+            .iter()
+            .map(|t| BackupFile { path: PathBuf::from(t), created_at: at(t) })
+            .collect();
+        let now = at("20240310-120000");
+
+        let by_count = select_expired(&backups, &BackupPolicy::new(2, None), now);
+        assert_eq!(by_count, vec![PathBuf::from("20240301-000000")]);
+
+        let by_age = select_expired(&backups, &BackupPolicy::new(10, Some(Duration::from_secs(86_400))), now);
+        assert_eq!(by_age, vec![PathBuf::from("20240309-000000"), PathBuf::from("20240301-000000")]);
+    }
+}
@@ -0,0 +1,148 @@
+//! Побудова індексів у пам'яті з пар (шлях, абзаци) - без читання DOCX і без запису на
+//! диск: для тестів і для вбудовування пошуку в інші програми. Документи впорядковуються
+//! за датою так само, як при індексації папки (без дати - на початку, далі від старих до
+//! нових), тож швидкий пошук охоплює найновіші накази.
+
+#[cfg(test)]
+use crate::atomic_index_manager::AtomicIndexManager;
+use crate::classifier::Classifier;
+use crate::document_date::document_date;
+use crate::document_record::{DocumentIndex, DocumentRecord};
+use crate::index_paths::IndexPaths;
+use crate::inverted_index::InvertedIndex;
+use crate::search_engine::SearchEngine;
+use std::sync::Arc;
+
+#[derive(Default)]
+pub struct IndexBuilder {
+    documents: Vec<DocumentRecord>,
+    classifier: Option<Arc<Classifier>>,
+}
+
+impl IndexBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Класифікатор видів наказів (як FolderProcessor::with_classifier)
+    pub fn with_classifier(mut self, classifier: Arc<Classifier>) -> Self {
+        self.classifier = Some(classifier);
+        self
+    }
+
+    /// Документ з абзацами; дата береться з назви файлу або шапки документа
+    pub fn with_document<P: Into<String>>(self, file_path: &str, paragraphs: impl IntoIterator<Item = P>) -> Self {
+        self.with_record(DocumentRecord::from_texts(file_path, paragraphs))
+    }
+
+    /// Готовий запис (наприклад, з last_modified чи збереженою нумерацією абзаців)
+    pub fn with_record(mut self, record: DocumentRecord) -> Self {
+        self.documents.push(record);
+        self
+    }
+
+    /// Індекс документів (doc_id від 1 у порядку дат) та інвертований індекс до нього
+    pub fn build(self) -> (DocumentIndex, InvertedIndex) {
+        let mut documents = self.documents;
+        if let Some(classifier) = &self.classifier {
+            for document in documents.iter_mut().filter(|d| d.category.is_none()) {
+                document.category = classifier.classify(&document.paragraphs);
+            }
+        }
+        // Стабільне впорядкування: None (без дати) - перед будь-якою датою
+        documents.sort_by_cached_key(|document| document_date(document).map(|date| date.ymd()));
+
+        let mut index = DocumentIndex::new();
+        for document in documents {
+            index.push_document(document);
+        }
+        index.recount();
+        let inverted_index = InvertedIndex::rebuild_from_scratch(&index);
+        (index, inverted_index)
+    }
+
+    /// Пошуковий движок з побудованими індексами. Індекси з paths не читаються; paths
+    /// потрібні лише для оцінок релевантності, приміток і журналу повільних запитів -
+    /// вони читаються й записуються, лише якщо їх використовувати.
+    pub fn build_engine(self, paths: &IndexPaths) -> SearchEngine {
+        let (index, inverted_index) = self.build();
+        SearchEngine::new(paths).with_index(index, inverted_index)
+    }
+}
+
+/// Тимчасова папка індексів для тестів: blazing_<name>_<pid>, створюється порожньою і
+/// видаляється при drop - також коли тест падає посередині
+#[cfg(test)]
+pub(crate) struct TempIndexDir {
+    paths: IndexPaths,
+}
+
+#[cfg(test)]
+impl TempIndexDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("blazing_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self { paths: IndexPaths::new(dir) }
+    }
+
+    pub(crate) fn paths(&self) -> &IndexPaths {
+        &self.paths
+    }
+
+    /// Менеджер індексів цієї папки без очікування блокування
+    pub(crate) fn manager(&self) -> AtomicIndexManager {
+        AtomicIndexManager::new(&self.paths.documents_index(), &self.paths.inverted_index())
+            .with_lock_wait_timeout(std::time::Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempIndexDir {
+    type Target = std::path::Path;
+
+    fn deref(&self) -> &Self::Target {
+        self.paths.dir()
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempIndexDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(self.paths.dir());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classifier::default_categories;
+    use crate::search_engine::SearchMode;
+
+    #[tokio::test]
+    async fn test_engine_from_in_memory_documents() {
+        let paths = IndexPaths::new(std::env::temp_dir().join(format!("blazing_index_builder_{}", std::process::id())));
+        let engine = IndexBuilder::new()
+            .with_classifier(Arc::new(Classifier::new(&default_categories())))
+            .with_document("2024/Наказ №12 від 05.03.2024.docx", ["НАКАЗ №12", "1. Петренку П.П. надати відпустку"])
+            .with_document("Без дати.docx", ["Петренка П.П. відрядити до м. Києва"])
+            .with_document("2023/Наказ №3 від 20.01.2023.docx", ["1. Іваненку І.І. надати відпустку"])
+            .build_engine(&paths);
+
+        let files = engine.with_indices(|_, doc_index, inverted_index| {
+            assert!(inverted_index.is_some());
+            assert_eq!(doc_index.total_documents, 3);
+            doc_index.documents.iter().map(|d| (d.doc_id, d.file_name.clone(), d.category.clone())).collect::<Vec<_>>()
+        });
+        assert_eq!(files, vec![
+            (1, "Без дати.docx".to_string(), Some("відрядження".to_string())),
+            (2, "Наказ №3 від 20.01.2023.docx".to_string(), Some("відпустки".to_string())),
+            (3, "Наказ №12 від 05.03.2024.docx".to_string(), Some("відпустки".to_string())),
+        ]);
+
+        let outcome = engine.search_outcome("Петренко", SearchMode::Full, None).await.unwrap();
+        let found: Vec<&str> = outcome.results.iter().map(|r| r.file_name.as_str()).collect();
+        assert_eq!(found, vec!["Наказ №12 від 05.03.2024.docx", "Без дати.docx"]);
+        assert!(!paths.dir().exists());
+    }
+}
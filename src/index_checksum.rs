@@ -0,0 +1,138 @@
+//! Контрольні суми файлів індексу: поруч з кожним файлом покоління лежить <файл>.sha256
+//! з SHA-256 його вмісту (як він записаний на диск, тобто після стиснення). Завантаження
+//! звіряє суму до використання розібраного вмісту, тож обрізаний чи пошкоджений на диску
+//! файл відкидається як
+//! пошкоджений (і береться резервна копія), а не розбирається наполовину і "ремонтується".
+//!
+//! Файли без контрольної суми (записані старішими версіями, резервні копії) читаються без
+//! перевірки. Так само ігнорується нечитабельний файл суми: він пишеться після файлу
+//! індексу, і обірваний запис суми не повинен робити цілий індекс пошкодженим.
+
+use crate::error::IndexError;
+use crate::index_compression;
+use crate::index_storage::IndexStorage;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Розширення файлу контрольної суми
+pub const CHECKSUM_EXTENSION: &str = "sha256";
+
+/// Файл контрольної суми поруч з файлом індексу
+pub fn checksum_path(path: &str) -> String {
+    format!("{}.{}", path, CHECKSUM_EXTENSION)
+}
+
+/// SHA-256 байтів у вигляді hex-рядка
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Записує контрольну суму поруч з файлом індексу
+pub fn write_checksum(storage: &dyn IndexStorage, path: &str, sha256: &str) -> Result<(), IndexError> {
+    let sidecar = checksum_path(path);
+    storage
+        .write(Path::new(&sidecar), format!("{}\n", sha256).as_bytes())
+        .map_err(|e| IndexError::io(format!("Помилка запису контрольної суми {}", sidecar), e))
+}
+
+/// Записана контрольна сума файлу (None - її немає або вона нечитабельна)
+pub fn expected_checksum(path: &str) -> Option<String> {
+    let content = fs::read_to_string(checksum_path(path)).ok()?;
+    let sha256 = content.trim().to_lowercase();
+    (sha256.len() == 64 && sha256.chars().all(|c| c.is_ascii_hexdigit())).then_some(sha256)
+}
+
+/// Читає файл індексу (стиснутий чи звичайний JSON), звіряючи його з контрольною сумою
+/// поруч, якщо вона є. Обрізаний чи пошкоджений файл відкидається за контрольною сумою
+/// ще до того, як розібраний вміст буде використано: сума рахується по байтах, що
+/// надходять у десеріалізатор, тож файл не буферизується окремо заради перевірки.
+/// Помилка розбору повідомляється з контекстом context, якщо сума збіглася.
+pub fn read_verified_json<T: DeserializeOwned>(path: &str, context: &str) -> Result<T, IndexError> {
+    let read_error = |e| IndexError::io(format!("Помилка читання файлу {}", path), e);
+    let expected = expected_checksum(path);
+    let mut reader = HashingReader::new(File::open(path).map_err(read_error)?);
+    let parsed = index_compression::read_json(&mut reader);
+
+    if let Some(expected) = expected {
+        // Решта файлу (за межами розібраного) теж входить у суму
+        io::copy(&mut reader, &mut io::sink()).map_err(read_error)?;
+        let (actual, len) = reader.finish();
+        if actual != expected {
+            return Err(IndexError::Corrupt(format!(
+                "Контрольна сума {} не збігається (очікувалась {}, файл {} байт має {}) - файл обрізаний або пошкоджений",
+                path, expected, len, actual
+            )));
+        }
+    }
+
+    parsed.map_err(|e| IndexError::json(context, e))
+}
+
+/// Рахує SHA-256 прочитаних байтів
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    len: u64,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, hasher: Sha256::new(), len: 0 }
+    }
+
+    /// Сума в hex і кількість прочитаних байтів
+    fn finish(self) -> (String, u64) {
+        let sha256 = self.hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+        (sha256, self.len)
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        self.len += read as u64;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+    use crate::index_storage::FsStorage;
+
+    #[test]
+    fn test_truncated_or_flipped_file_rejected() {
+        let dir = TempIndexDir::new("index_checksum");
+        let path = dir.join("inverted_index.1.json").to_string_lossy().to_string();
+
+        let content = r#"{"word_to_docs":{"наказ":[0,1]},"total_documents":2}"#.as_bytes();
+        let read = |path: &str| read_verified_json::<serde_json::Value>(path, "Помилка парсингу JSON");
+        let value: serde_json::Value = serde_json::from_slice(content).unwrap();
+        fs::write(&path, content).unwrap();
+
+        // Без контрольної суми файл читається як є
+        assert_eq!(read(&path).unwrap(), value);
+
+        write_checksum(&FsStorage, &path, &sha256_hex(content)).unwrap();
+        assert_eq!(read(&path).unwrap(), value);
+
+        // Обрізаний файл
+        fs::write(&path, &content[..content.len() / 2]).unwrap();
+        assert!(matches!(read(&path), Err(IndexError::Corrupt(_))));
+
+        // Один змінений біт - JSON лишається валідним, але сума вже не та
+        let mut flipped = content.to_vec();
+        flipped[content.len() - 2] ^= 0x01;
+        fs::write(&path, &flipped).unwrap();
+        assert!(matches!(read(&path), Err(IndexError::Corrupt(_))));
+
+        // Обірваний запис самої суми не робить файл пошкодженим
+        fs::write(checksum_path(&path), "3f2a").unwrap();
+        assert_eq!(read(&path).unwrap(), serde_json::from_slice::<serde_json::Value>(&flipped).unwrap());
+    }
+}
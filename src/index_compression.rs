@@ -0,0 +1,138 @@
+//! Стиснення файлів індексу (storage.compression): індекс документів і інвертований індекс
+//! у JSON займають сотні мегабайт, а стиснуті zstd - у кілька разів менше, тож швидше
+//! записуються, копіюються мережею і в резервні копії. Формат файлу визначається за його
+//! першими байтами (сигнатура кадру zstd чи gzip), тому старі нестиснуті файли, файли з
+//! іншим стисненням і резервні копії читаються без перетворення.
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+
+/// Сигнатура кадру zstd
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Сигнатура gzip
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/// Рівень zstd: швидкий запис, а JSON індексу й так стискається в кілька разів
+const ZSTD_LEVEL: i32 = 3;
+
+/// Буфер між серіалізацією і стисненням: serde_json пише дрібними шматками
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Як записуються нові файли індексу. Читаються всі формати незалежно від налаштування
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexCompression {
+    /// Звичайний JSON: його читають і старіші версії програми (відкат оновлення)
+    #[default]
+    None,
+    Zstd,
+    Gzip,
+}
+
+impl std::str::FromStr for IndexCompression {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "zstd" => Ok(Self::Zstd),
+            "gzip" => Ok(Self::Gzip),
+            other => Err(format!("невідоме стиснення: {} (none, zstd або gzip)", other)),
+        }
+    }
+}
+
+impl IndexCompression {
+    /// Записує value як JSON у writer і завершує стиснутий потік; pretty (з відступами) діє
+    /// лише без стиснення - стиснутий файл людина однаково не читає. Повертає writer, щоб
+    /// викликач міг скинути буфер і перевірити помилку запису
+    pub fn write_json<W: Write, T: Serialize>(self, mut writer: W, value: &T, pretty: bool) -> serde_json::Result<W> {
+        match self {
+            Self::None if pretty => serde_json::to_writer_pretty(&mut writer, value).map(|_| writer),
+            Self::None => serde_json::to_writer(&mut writer, value).map(|_| writer),
+            Self::Zstd => {
+                let encoder = zstd::Encoder::new(writer, ZSTD_LEVEL).map_err(serde_json::Error::io)?;
+                write_buffered(encoder, value)?.finish().map_err(serde_json::Error::io)
+            }
+            Self::Gzip => {
+                let encoder = GzEncoder::new(writer, flate2::Compression::default());
+                write_buffered(encoder, value)?.finish().map_err(serde_json::Error::io)
+            }
+        }
+    }
+
+    /// Байти JSON value у цьому форматі (для запису файлу одним викликом)
+    pub fn to_vec<T: Serialize>(self, value: &T) -> serde_json::Result<Vec<u8>> {
+        self.write_json(Vec::new(), value, false)
+    }
+}
+
+fn write_buffered<W: Write, T: Serialize>(encoder: W, value: &T) -> serde_json::Result<W> {
+    let mut writer = BufWriter::with_capacity(BUFFER_SIZE, encoder);
+    serde_json::to_writer(&mut writer, value)?;
+    writer.into_inner().map_err(|e| serde_json::Error::io(e.into_error()))
+}
+
+/// Формат даних за першими байтами
+pub fn detect(head: &[u8]) -> IndexCompression {
+    if head.starts_with(&ZSTD_MAGIC) {
+        IndexCompression::Zstd
+    } else if head.starts_with(&GZIP_MAGIC) {
+        IndexCompression::Gzip
+    } else {
+        IndexCompression::None
+    }
+}
+
+/// Читає JSON файлу індексу в будь-якому форматі. Вміст розпаковується в пам'ять цілком:
+/// розбір з буфера в рази швидший за розбір з потоку
+pub fn read_json<T: DeserializeOwned, R: Read>(reader: R) -> serde_json::Result<T> {
+    let mut reader = BufReader::with_capacity(BUFFER_SIZE, reader);
+    let compression = detect(reader.fill_buf().map_err(serde_json::Error::io)?);
+    let mut content = Vec::new();
+    match compression {
+        IndexCompression::None => reader.read_to_end(&mut content),
+        IndexCompression::Zstd => {
+            zstd::Decoder::with_buffer(reader).and_then(|mut decoder| decoder.read_to_end(&mut content))
+        }
+        IndexCompression::Gzip => MultiGzDecoder::new(reader).read_to_end(&mut content),
+    }
+    .map_err(serde_json::Error::io)?;
+    serde_json::from_slice(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_every_format_reads_back() {
+        let value: BTreeMap<String, Vec<usize>> =
+            (0..200).map(|i| (format!("наказ_{}", i), vec![i, i * 2, i * 3])).collect();
+
+        let plain = IndexCompression::None.write_json(Vec::new(), &value, true).unwrap();
+        assert_eq!(detect(&plain), IndexCompression::None);
+        for compression in [IndexCompression::Zstd, IndexCompression::Gzip] {
+            let bytes = compression.to_vec(&value).unwrap();
+            assert_eq!(detect(&bytes), compression);
+            assert!(bytes.len() * 3 < plain.len(), "{:?}: {} з {}", compression, bytes.len(), plain.len());
+            let read: BTreeMap<String, Vec<usize>> = read_json(bytes.as_slice()).unwrap();
+            assert_eq!(read, value);
+        }
+        // Старий файл - звичайний JSON
+        let read: BTreeMap<String, Vec<usize>> = read_json(plain.as_slice()).unwrap();
+        assert_eq!(read, value);
+
+        // Обрізаний стиснутий файл - помилка, а не порожній індекс
+        let bytes = IndexCompression::Zstd.to_vec(&value).unwrap();
+        assert!(read_json::<BTreeMap<String, Vec<usize>>, _>(&bytes[..bytes.len() / 2]).is_err());
+
+        assert_eq!("ZSTD".parse(), Ok(IndexCompression::Zstd));
+        assert!("lz4".parse::<IndexCompression>().is_err());
+    }
+}
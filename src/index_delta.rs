@@ -0,0 +1,161 @@
+//! Зміни одного покоління індексів для оновлення пошукового движка в пам'яті без
+//! перечитування і парсингу всього documents_index.json (див. SearchEngine::apply_delta).
+//! Дельта можлива, лише коли слоти інших документів не змінились: без перестановки
+//! за датою і без ущільнення. Інакше движок перезавантажує покоління повністю.
+
+use crate::document_record::{DocumentIndex, DocumentRecord};
+use crate::inverted_index::InvertedIndex;
+
+#[derive(Debug, Clone)]
+pub struct IndexDelta {
+    /// Покоління, до якого застосовується дельта
+    pub base_generation: u64,
+    /// Покоління, записане з цими змінами
+    pub generation: u64,
+    /// Нові та змінені документи за слотами (нові - одразу після останнього слота)
+    pub changed: Vec<(usize, DocumentRecord)>,
+    /// Слоти документів, позначених видаленими (tombstone)
+    pub deleted: Vec<usize>,
+    pub next_doc_id: u64,
+    pub indexed_at: u64,
+}
+
+impl IndexDelta {
+    /// Збирає зміни з оновленого індексу документів (generation - ще не записане покоління).
+    /// None - індекс не з покоління (старий файл або новий індекс), дельту нема до чого застосувати.
+    pub fn new(doc_index: &DocumentIndex, changed: &[usize], deleted: &[usize]) -> Option<Self> {
+        if doc_index.generation == 0 {
+            return None;
+        }
+
+        let mut changed: Vec<(usize, DocumentRecord)> = changed
+            .iter()
+            .filter_map(|&slot| doc_index.documents.get(slot).map(|document| (slot, document.clone())))
+            .collect();
+        changed.sort_by_key(|(slot, _)| *slot);
+
+        Some(Self {
+            base_generation: doc_index.generation,
+            generation: doc_index.generation,
+            changed,
+            deleted: deleted.to_vec(),
+            next_doc_id: doc_index.next_doc_id,
+            indexed_at: doc_index.indexed_at,
+        })
+    }
+
+    pub fn with_generation(mut self, generation: u64) -> Self {
+        self.generation = generation;
+        self
+    }
+
+    /// Переносить зміни в копії індексів базового покоління - так само, як їх вносить
+    /// менеджер індексів перед записом (записи інвертованого індексу на видалені слоти
+    /// лишаються до ущільнення). false - слоти дельти не відповідають індексу.
+    pub fn apply(&self, doc_index: &mut DocumentIndex, inverted_index: &mut InvertedIndex) -> bool {
+        // Нові документи мають іти поспіль одразу після останнього слота
+        let mut len = doc_index.documents.len();
+        for (slot, _) in &self.changed {
+            match (*slot).cmp(&len) {
+                std::cmp::Ordering::Less => {}
+                std::cmp::Ordering::Equal => len += 1,
+                std::cmp::Ordering::Greater => return false,
+            }
+        }
+        if self.deleted.iter().any(|&slot| slot >= len) {
+            return false;
+        }
+
+        for (slot, document) in &self.changed {
+            doc_index.put_document(*slot, document.clone());
+        }
+        for &slot in &self.deleted {
+            doc_index.tombstone(slot);
+        }
+        doc_index.next_doc_id = doc_index.next_doc_id.max(self.next_doc_id);
+        doc_index.indexed_at = self.indexed_at;
+        doc_index.generation = self.generation;
+        doc_index.recount();
+
+        let slots: Vec<usize> = self.changed.iter().map(|(slot, _)| *slot).collect();
+        inverted_index.update_incremental(doc_index, &slots);
+        inverted_index.total_documents = doc_index.total_documents;
+        inverted_index.remove_duplicate_entries();
+        inverted_index.generation = self.generation;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document_record::Paragraph;
+    use crate::index_builder::IndexBuilder;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    /// Слоти живих документів для кожного слова (записи на видалені слоти пошук ігнорує)
+    fn live_postings(inverted_index: &InvertedIndex, doc_index: &DocumentIndex) -> BTreeMap<String, BTreeSet<usize>> {
+        inverted_index
+            .word_to_docs
+            .iter()
+            .map(|(word, positions)| {
+                let slots = positions
+                    .iter()
+                    .map(|position| position.doc_index)
+                    .filter(|&slot| doc_index.documents.get(slot).is_some_and(|d| !d.deleted))
+                    .collect::<BTreeSet<_>>();
+                (word.clone(), slots)
+            })
+            .filter(|(_, slots)| !slots.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn test_delta_matches_updated_index() {
+        let (mut base, mut base_inverted) = IndexBuilder::new()
+            .with_document("Наказ №1 від 10.01.2024.docx", ["Петренку П.П. надати відпустку"])
+            .with_document("Наказ №2 від 11.01.2024.docx", ["Іваненка І.І. відрядити до м. Києва"])
+            .with_document("Наказ №3 від 12.01.2024.docx", ["Сидоренку С.С. оголосити подяку"])
+            .build();
+        base.generation = 4;
+        base_inverted.generation = 4;
+
+        // Те саме, що робить FolderProcessor: заміна на місці, новий у кінці, tombstone
+        let mut updated = base.clone();
+        updated.replace_document(1, DocumentRecord::from_paragraphs(
+            "Наказ №2 від 11.01.2024.docx".to_string(),
+            vec![Paragraph::new("Іваненка І.І. відрядити до м. Львова".to_string())],
+        ));
+        let pushed = updated.push_document(DocumentRecord::from_paragraphs(
+            "Наказ №4 від 15.01.2024.docx".to_string(),
+            vec![Paragraph::new("Коваленку К.К. надати відпустку".to_string())],
+        ));
+        updated.tombstone(0);
+        updated.recount();
+
+        let delta = IndexDelta::new(&updated, &[pushed, 1], &[0]).unwrap().with_generation(5);
+        assert_eq!(delta.base_generation, 4);
+        assert_eq!(delta.changed.iter().map(|(slot, _)| *slot).collect::<Vec<_>>(), vec![1, 3]);
+
+        // Слоти не з цього індексу - дельта не застосовується і нічого не змінює
+        let (mut small, mut small_inverted) = IndexBuilder::new()
+            .with_document("Наказ №1 від 10.01.2024.docx", ["Петренку П.П. надати відпустку"])
+            .build();
+        assert!(!delta.apply(&mut small, &mut small_inverted));
+        assert_eq!(small.documents.len(), 1);
+
+        assert!(delta.apply(&mut base, &mut base_inverted));
+        assert_eq!(base.generation, 5);
+        assert_eq!(base_inverted.generation, 5);
+        assert_eq!(base.total_documents, 3);
+        assert_eq!(base.next_doc_id, updated.next_doc_id);
+        assert_eq!(base.slot_of(updated.documents[3].doc_id), Some(3));
+        let summary = |index: &DocumentIndex| -> Vec<_> {
+            index.documents.iter().map(|d| (d.doc_id, d.deleted, d.content.clone(), d.content_hash.clone())).collect()
+        };
+        assert_eq!(summary(&base), summary(&updated));
+
+        let expected = InvertedIndex::rebuild_from_scratch(&updated);
+        assert_eq!(live_postings(&base_inverted, &base), live_postings(&expected, &updated));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::atomic_index_manager::AtomicIndexManager;
+use crate::document_record::DocumentIndex;
+use crate::error::IndexError;
+use crate::index_manifest::resolve_active_paths;
+use crate::index_paths::IndexPaths;
+use crate::index_store::{store_is_empty, IndexStore};
+use crate::sqlite_store::SqliteIndexStore;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Підсумок імпорту індексу іншого екземпляра
+#[derive(Serialize, Debug, Default)]
+pub struct ImportStats {
+    pub added: usize,
+    pub updated: usize,
+    /// Той самий шлях і той самий вміст
+    pub unchanged: usize,
+    /// Локальна версія документа новіша за імпортовану
+    pub older: usize,
+    /// Документ з таким самим вмістом уже є під іншим шляхом
+    pub duplicates: usize,
+    /// Документи, що не пройшли перевірку цілісності у джерелі
+    pub corrupted: usize,
+}
+
+impl ImportStats {
+    pub fn has_changes(&self) -> bool {
+        self.added > 0 || self.updated > 0
+    }
+}
+
+impl std::fmt::Display for ImportStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "додано: {}, оновлено: {}, без змін: {}, локальна версія новіша: {}, дублікатів: {}, пошкоджених: {}",
+            self.added, self.updated, self.unchanged, self.older, self.duplicates, self.corrupted
+        )
+    }
+}
+
+/// Завантажує активний індекс документів з папки індексів іншого екземпляра
+/// (зі сховища SQLite, якщо екземпляр зберігає індекси в ньому)
+pub fn load_source(index_dir: &Path) -> Result<DocumentIndex, IndexError> {
+    let paths = IndexPaths::new(index_dir);
+    let sqlite_index = paths.sqlite_index();
+    if Path::new(&sqlite_index).exists() {
+        return SqliteIndexStore::new(sqlite_index).load_documents()?.ok_or_else(store_is_empty);
+    }
+    let (active_doc_path, _) = resolve_active_paths(&paths.documents_index(), &paths.inverted_index());
+
+    if !Path::new(&active_doc_path).exists() {
+        return Err(IndexError::NotFound(format!("Індекс документів для імпорту не знайдено: {}", active_doc_path)));
+    }
+    DocumentIndex::load_from_file(&active_doc_path)
+}
+
+/// Зливає документи source в локальні індекси однією транзакцією.
+/// Документи зіставляються за шляхом: новий шлях додається, змінений вміст замінює
+/// локальний лише якщо імпортована версія не старша. Вміст, що вже є під іншим
+/// шляхом (той самий content_hash), вважається дублікатом і не додається.
+/// Інвертований індекс оновлюється тим самим конвеєром, що й при індексації папки.
+pub fn import_documents(index_manager: &AtomicIndexManager, source: DocumentIndex) -> Result<ImportStats, IndexError> {
+    let mut stats = ImportStats::default();
+    let mut tx = index_manager.begin()?;
+
+    // шлях -> (doc_id, хеш, час зміни) живих локальних документів
+    let mut local: HashMap<String, (u64, Option<String>, u64)> = tx
+        .documents()
+        .documents
+        .iter()
+        .filter(|d| !d.deleted)
+        .map(|d| (d.file_path.clone(), (d.doc_id, d.content_hash.clone(), d.last_modified)))
+        .collect();
+    let mut known_hashes: HashSet<String> = local.values().filter_map(|(_, hash, _)| hash.clone()).collect();
+
+    for document in source.documents.into_iter().filter(|d| !d.deleted) {
+        if document.integrity_problem().is_some() {
+            stats.corrupted += 1;
+            continue;
+        }
+        let hash = document.compute_content_hash();
+
+        match local.get(&document.file_path) {
+            Some((_, local_hash, _)) if local_hash.as_deref() == Some(hash.as_str()) => stats.unchanged += 1,
+            Some((_, _, local_modified)) if *local_modified > document.last_modified => stats.older += 1,
+            Some(&(doc_id, _, _)) => {
+                let (path, last_modified) = (document.file_path.clone(), document.last_modified);
+                tx.update_document(doc_id, document)?;
+                local.insert(path, (doc_id, Some(hash.clone()), last_modified));
+                known_hashes.insert(hash);
+                stats.updated += 1;
+            }
+            None if known_hashes.contains(&hash) => stats.duplicates += 1,
+            None => {
+                let (path, last_modified) = (document.file_path.clone(), document.last_modified);
+                let doc_id = tx.add_document(document);
+                local.insert(path, (doc_id, Some(hash.clone()), last_modified));
+                known_hashes.insert(hash);
+                stats.added += 1;
+            }
+        }
+    }
+
+    tx.commit()?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+    use crate::document_record::DocumentRecord;
+
+    fn document(path: &str, text: &str, last_modified: u64) -> DocumentRecord {
+        DocumentRecord { last_modified, ..DocumentRecord::from_texts(path, [text]) }
+    }
+
+    #[test]
+    fn test_import_merges_by_path_and_hash() {
+        let dir = TempIndexDir::new("import_test");
+        let manager = dir.manager();
+
+        let mut tx = manager.begin().unwrap();
+        tx.add_document(document("2024/a.docx", "перший наказ", 10));
+        tx.add_document(document("2024/b.docx", "другий наказ", 50));
+        tx.add_document(document("2024/c.docx", "третій наказ", 10));
+        tx.commit().unwrap();
+
+        let mut source = DocumentIndex::new();
+        source.push_document(document("2024/a.docx", "перший наказ", 10));
+        source.push_document(document("2024/b.docx", "застарілий текст", 20));
+        source.push_document(document("2024/c.docx", "оновлений наказ", 30));
+        source.push_document(document("2024/копія a.docx", "перший наказ", 10));
+        source.push_document(document("2024/d.docx", "новий рапорт", 40));
+
+        let stats = import_documents(&manager, source).unwrap();
+        assert_eq!(
+            (stats.added, stats.updated, stats.unchanged, stats.older, stats.duplicates),
+            (1, 1, 1, 1, 1)
+        );
+
+        let (doc_index, inv_index) = manager.load_active_indices();
+        let doc_index = doc_index.unwrap();
+        assert_eq!(doc_index.total_documents, 4);
+        let text_of = |path: &str| {
+            doc_index.documents.iter().find(|d| d.file_path == path).unwrap().content[0].clone()
+        };
+        assert_eq!(text_of("2024/b.docx"), "другий наказ");
+        assert_eq!(text_of("2024/c.docx"), "оновлений наказ");
+        assert!(inv_index.unwrap().word_to_docs.contains_key(&crate::stemmer::stem_word("рапорт")));
+
+        // Повторний імпорт нічого не змінює
+        let mut again = DocumentIndex::new();
+        again.push_document(document("2024/d.docx", "новий рапорт", 40));
+        assert!(!import_documents(&manager, again).unwrap().has_changes());
+    }
+}
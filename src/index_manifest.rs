@@ -0,0 +1,383 @@
+use crate::error::IndexError;
+use crate::index_storage::IndexStorage;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Назва файлу-маніфесту, що вказує на активне покоління індексів
+pub const MANIFEST_FILE_NAME: &str = "index_manifest.json";
+
+/// Назва журналу транзакцій (write-ahead log) оновлення індексів
+pub const WAL_FILE_NAME: &str = "index_update.wal";
+
+/// Маніфест: єдине джерело правди про те, які файли індексів зараз активні.
+/// Перемикання на нове покоління - це атомарна заміна цього файлу через rename.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndexManifest {
+    pub generation: u64,
+    pub documents_index: String,
+    pub inverted_index: String,
+    pub committed_at: u64, // Unix timestamp
+    /// Збережені покоління, до яких можна відкотитися (від старих до нових, включно з активним)
+    #[serde(default)]
+    pub snapshots: Vec<SnapshotEntry>,
+}
+
+/// Знімок - зафіксоване покоління індексів, файли якого ще зберігаються на диску
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SnapshotEntry {
+    pub generation: u64,
+    pub documents_index: String,
+    pub inverted_index: String,
+    pub committed_at: u64, // Unix timestamp
+    pub total_documents: usize,
+    /// SHA-256 файлів покоління на момент фіксації (відсутні у знімках старих версій)
+    #[serde(default)]
+    pub documents_sha256: Option<String>,
+    #[serde(default)]
+    pub inverted_sha256: Option<String>,
+    /// Якщо інвертований файл покоління - сегмент (див. inverted_segments): нижчі шари
+    /// від повного файлу до попереднього сегмента. Їх не можна видаляти, доки живе знімок
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inverted_base: Vec<String>,
+}
+
+/// Запис журналу транзакцій
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+pub enum WalRecord {
+    /// Намір записати нове покоління у вказані файли
+    Begin {
+        generation: u64,
+        documents_index: String,
+        inverted_index: String,
+        timestamp: u64,
+    },
+    /// Маніфест перемкнуто на нове покоління
+    Commit { generation: u64, timestamp: u64 },
+}
+
+impl IndexManifest {
+    /// Шлях до маніфесту поруч з індексом документів
+    pub fn path_for(documents_index_path: &str) -> PathBuf {
+        sibling_path(documents_index_path, MANIFEST_FILE_NAME)
+    }
+
+    pub fn load(documents_index_path: &str) -> Option<Self> {
+        let path = Self::path_for(documents_index_path);
+        let content = fs::read_to_string(&path).ok()?;
+
+        match serde_json::from_str(&content) {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                warn!("⚠️  Пошкоджений маніфест індексів {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Запис знімку для активного покоління
+    pub fn active_snapshot(&self) -> Option<&SnapshotEntry> {
+        self.snapshots.iter().find(|s| s.generation == self.generation)
+    }
+
+    /// Найбільший номер покоління серед активного та збережених знімків.
+    /// Після відкату активне покоління може бути старшим за знімки, тому
+    /// нове покоління рахується від максимуму, щоб не перезаписати їхні файли.
+    pub fn latest_generation(&self) -> u64 {
+        self.snapshots
+            .iter()
+            .map(|s| s.generation)
+            .chain(std::iter::once(self.generation))
+            .max()
+            .unwrap_or(self.generation)
+    }
+
+    /// Атомарно записує маніфест: тимчасовий файл + rename поверх старого.
+    /// При durable = true тимчасовий файл і папка синхронізуються з диском.
+    pub fn store(&self, storage: &dyn IndexStorage, documents_index_path: &str, durable: bool) -> Result<(), IndexError> {
+        let path = Self::path_for(documents_index_path);
+        let temp_path = path.with_extension("json.tmp");
+
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| IndexError::json("Помилка серіалізації маніфесту", e))?;
+
+        storage.write(&temp_path, json.as_bytes()).map_err(|e| {
+            let _ = storage.remove_file(&temp_path);
+            IndexError::io("Помилка запису тимчасового маніфесту", e)
+        })?;
+
+        if durable {
+            sync_file(storage, &temp_path)?;
+        }
+
+        storage.rename(&temp_path, &path).map_err(|e| {
+            let _ = storage.remove_file(&temp_path);
+            IndexError::io("Помилка перемикання маніфесту", e)
+        })?;
+
+        if durable {
+            sync_parent_dir(storage, &path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Повертає шляхи до активних файлів індексів.
+/// Якщо маніфесту ще немає - це старе розташування з фіксованими назвами.
+pub fn resolve_active_paths(documents_index_path: &str, inverted_index_path: &str) -> (String, String) {
+    match IndexManifest::load(documents_index_path) {
+        Some(manifest) => (manifest.documents_index, manifest.inverted_index),
+        None => (documents_index_path.to_string(), inverted_index_path.to_string()),
+    }
+}
+
+/// Індекс разом з номером покоління, з яким він записується у файл.
+/// Номер лежить у самому файлі, тому пару файлів з різних оновлень видно навіть
+/// після ручного копіювання чи перейменування.
+#[derive(Serialize)]
+pub struct GenerationStamped<'a, T> {
+    pub generation: u64,
+    #[serde(flatten)]
+    pub index: &'a T,
+}
+
+/// Чи записані обидва індекси одним оновленням.
+/// 0 - файл записаний старою версією без номера покоління, такий не перевіряється.
+pub fn generations_match(documents_generation: u64, inverted_generation: u64) -> bool {
+    documents_generation == 0 || inverted_generation == 0 || documents_generation == inverted_generation
+}
+
+/// Назва файлу конкретного покоління: documents_index.json -> documents_index.42.json
+pub fn generation_path(base_path: &str, generation: u64) -> String {
+    let path = Path::new(base_path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("index");
+    let file_name = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}.{}.{}", stem, generation, ext),
+        None => format!("{}.{}", stem, generation),
+    };
+
+    sibling_path(base_path, &file_name).to_string_lossy().to_string()
+}
+
+/// Витягує номер покоління з назви файлу, якщо він належить до цього базового шляху
+pub fn parse_generation(base_path: &str, file_name: &str) -> Option<u64> {
+    let path = Path::new(base_path);
+    let stem = path.file_stem()?.to_str()?;
+    let rest = file_name.strip_prefix(stem)?.strip_prefix('.')?;
+
+    let number = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => rest.strip_suffix(ext)?.strip_suffix('.')?,
+        None => rest,
+    };
+
+    number.parse().ok()
+}
+
+/// Журнал транзакцій оновлення індексів (JSON-рядки, тільки дописування)
+pub struct TransactionLog {
+    path: PathBuf,
+    durable: bool,
+}
+
+impl TransactionLog {
+    pub fn new(documents_index_path: &str) -> Self {
+        Self {
+            path: sibling_path(documents_index_path, WAL_FILE_NAME),
+            durable: false,
+        }
+    }
+
+    /// Кожен запис журналу примусово скидається на диск (fsync)
+    pub fn durable(mut self, durable: bool) -> Self {
+        self.durable = durable;
+        self
+    }
+
+    pub fn append(&self, record: &WalRecord) -> Result<(), IndexError> {
+        let line = serde_json::to_string(record)
+            .map_err(|e| IndexError::json("Помилка серіалізації запису журналу", e))?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| IndexError::io("Помилка відкриття журналу транзакцій", e))?;
+
+        writeln!(file, "{}", line).map_err(|e| IndexError::io("Помилка запису в журнал транзакцій", e))?;
+
+        if self.durable {
+            file.sync_all()
+                .map_err(|e| IndexError::io("Помилка синхронізації журналу транзакцій", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Читає всі цілі записи журналу (обірваний останній рядок ігнорується)
+    pub fn read_records(&self) -> Vec<WalRecord> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(_) => return Vec::new(),
+        };
+
+        BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect()
+    }
+
+    /// Повертає незавершену транзакцію (Begin без відповідного Commit), якщо вона є
+    pub fn pending_transaction(&self) -> Option<WalRecord> {
+        let records = self.read_records();
+        let last_begin = records
+            .iter()
+            .rposition(|r| matches!(r, WalRecord::Begin { .. }))?;
+
+        let begin = records[last_begin].clone();
+        let WalRecord::Begin { generation, .. } = begin else {
+            return None;
+        };
+
+        let committed = records[last_begin..].iter().any(|r| {
+            matches!(r, WalRecord::Commit { generation: g, .. } if *g == generation)
+        });
+
+        if committed { None } else { Some(begin) }
+    }
+
+    /// Очищує журнал після того, як усі транзакції завершено
+    pub fn clear(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// SHA-256 вмісту файлу у вигляді hex-рядка
+pub fn file_sha256(path: impl AsRef<Path>) -> Result<String, IndexError> {
+    let path = path.as_ref();
+    let mut file = fs::File::open(path)
+        .map_err(|e| IndexError::io(format!("Помилка відкриття файлу {}", path.display()), e))?;
+
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| IndexError::io(format!("Помилка читання файлу {}", path.display()), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Скидає вміст файлу на диск (fsync)
+pub fn sync_file(storage: &dyn IndexStorage, path: impl AsRef<Path>) -> Result<(), IndexError> {
+    let path = path.as_ref();
+    storage
+        .sync_file(path)
+        .map_err(|e| IndexError::io(format!("Помилка синхронізації файлу {}", path.display()), e))
+}
+
+/// Скидає на диск запис папки, що містить файл, щоб rename пережив втрату живлення
+pub fn sync_parent_dir(storage: &dyn IndexStorage, path: impl AsRef<Path>) -> Result<(), IndexError> {
+    let dir = match path.as_ref().parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    storage
+        .sync_dir(dir)
+        .map_err(|e| IndexError::io(format!("Помилка синхронізації папки {}", dir.display()), e))
+}
+
+/// Шлях до файлу в тій самій папці, що й base_path
+pub fn sibling_path(base_path: &str, file_name: &str) -> PathBuf {
+    match Path::new(base_path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(file_name),
+        _ => PathBuf::from(file_name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+
+    #[test]
+    fn test_generation_path_roundtrip() {
+        let path = generation_path("documents_index.json", 42);
+        assert_eq!(path, "documents_index.42.json");
+        assert_eq!(parse_generation("documents_index.json", "documents_index.42.json"), Some(42));
+
+        // Інші файли в папці не вважаються поколіннями
+        assert_eq!(parse_generation("documents_index.json", "documents_index.json"), None);
+        assert_eq!(parse_generation("documents_index.json", "documents_index.json.tmp"), None);
+        assert_eq!(parse_generation("documents_index.json", "inverted_index.3.json"), None);
+    }
+
+    #[test]
+    fn test_latest_generation_after_rollback() {
+        let snapshot = |generation| SnapshotEntry {
+            generation,
+            documents_index: generation_path("documents_index.json", generation),
+            inverted_index: generation_path("inverted_index.json", generation),
+            committed_at: 0,
+            total_documents: 0,
+            documents_sha256: None,
+            inverted_sha256: None,
+            inverted_base: Vec::new(),
+        };
+
+        // Після відкату на покоління 2 нове покоління не повинно перезаписати знімок 4
+        let manifest = IndexManifest {
+            generation: 2,
+            documents_index: generation_path("documents_index.json", 2),
+            inverted_index: generation_path("inverted_index.json", 2),
+            committed_at: 0,
+            snapshots: vec![snapshot(2), snapshot(3), snapshot(4)],
+        };
+        assert_eq!(manifest.latest_generation(), 4);
+    }
+
+    #[test]
+    fn test_pending_transaction_detection() {
+        let dir = TempIndexDir::new("wal_test");
+        let base = dir.join("documents_index.json").to_string_lossy().to_string();
+
+        let wal = TransactionLog::new(&base);
+        wal.clear();
+        assert!(wal.pending_transaction().is_none());
+
+        wal.append(&WalRecord::Begin {
+            generation: 7,
+            documents_index: "documents_index.7.json".to_string(),
+            inverted_index: "inverted_index.7.json".to_string(),
+            timestamp: 0,
+        })
+        .unwrap();
+        assert!(matches!(wal.pending_transaction(), Some(WalRecord::Begin { generation: 7, .. })));
+
+        wal.append(&WalRecord::Commit { generation: 7, timestamp: 0 }).unwrap();
+        assert!(wal.pending_transaction().is_none());
+    }
+}
@@ -0,0 +1,129 @@
+use crate::error::IndexError;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Папка індексів за замовчуванням - робоча папка процесу (як було раніше)
+pub const DEFAULT_INDEX_DIR: &str = ".";
+
+pub const DOCUMENTS_INDEX_FILE_NAME: &str = "documents_index.json";
+pub const INVERTED_INDEX_FILE_NAME: &str = "inverted_index.json";
+pub const SQLITE_INDEX_FILE_NAME: &str = "index.sqlite";
+pub const SYNC_HISTORY_FILE_NAME: &str = "sync_history.json";
+pub const REPAIR_REPORT_FILE_NAME: &str = "repair_report.json";
+pub const SYNC_JOURNAL_FILE_NAME: &str = "sync_copy.journal";
+pub const SLOW_QUERY_LOG_FILE_NAME: &str = "slow_queries.jsonl";
+pub const SEARCH_HISTORY_FILE_NAME: &str = "search_history.json";
+pub const LAST_VISITS_FILE_NAME: &str = "last_visits.json";
+pub const RELEVANCE_FEEDBACK_FILE_NAME: &str = "relevance_feedback.json";
+pub const ANNOTATIONS_FILE_NAME: &str = "annotations.json";
+pub const SAVED_QUERIES_FILE_NAME: &str = "saved_queries.json";
+
+/// Розташування всіх файлів одного екземпляра: індекси, маніфест, журнал транзакцій,
+/// блокування, тимчасові файли, резервні копії та звіти. Маніфест, журнал, блокування
+/// і резервні копії визначаються відносно індексу документів, тому окремі папки
+/// дозволяють запускати кілька екземплярів на одній машині.
+#[derive(Debug, Clone)]
+pub struct IndexPaths {
+    dir: PathBuf,
+}
+
+impl IndexPaths {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Створює папку індексів, якщо її ще немає
+    pub fn ensure_dir(&self) -> Result<(), IndexError> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| IndexError::io(format!("Помилка створення папки індексів {}", self.dir.display()), e))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn documents_index(&self) -> String {
+        self.file(DOCUMENTS_INDEX_FILE_NAME)
+    }
+
+    pub fn inverted_index(&self) -> String {
+        self.file(INVERTED_INDEX_FILE_NAME)
+    }
+
+    /// База сховища SQLite (storage.backend = "sqlite", див. sqlite_store)
+    pub fn sqlite_index(&self) -> String {
+        self.file(SQLITE_INDEX_FILE_NAME)
+    }
+
+    pub fn sync_history(&self) -> String {
+        self.file(SYNC_HISTORY_FILE_NAME)
+    }
+
+    pub fn repair_report(&self) -> String {
+        self.file(REPAIR_REPORT_FILE_NAME)
+    }
+
+    /// Журнал копіювання файлів у локальний кеш (див. sync_recovery)
+    pub fn sync_journal(&self) -> String {
+        self.file(SYNC_JOURNAL_FILE_NAME)
+    }
+
+    /// Історія пошуку користувачів (див. search_history)
+    pub fn search_history(&self) -> String {
+        self.file(SEARCH_HISTORY_FILE_NAME)
+    }
+
+    /// Останні візити користувачів (див. visit_tracker)
+    pub fn last_visits(&self) -> String {
+        self.file(LAST_VISITS_FILE_NAME)
+    }
+
+    /// Оцінки релевантності результатів (див. relevance_feedback)
+    pub fn relevance_feedback(&self) -> String {
+        self.file(RELEVANCE_FEEDBACK_FILE_NAME)
+    }
+
+    /// Примітки користувачів до абзаців (див. annotations)
+    pub fn annotations(&self) -> String {
+        self.file(ANNOTATIONS_FILE_NAME)
+    }
+
+    /// Збережені запити зі сповіщеннями (див. saved_queries)
+    pub fn saved_queries(&self) -> String {
+        self.file(SAVED_QUERIES_FILE_NAME)
+    }
+
+    /// Журнал повільних запитів (див. slow_query_log)
+    pub fn slow_query_log(&self) -> String {
+        self.file(SLOW_QUERY_LOG_FILE_NAME)
+    }
+
+    /// Для папки за замовчуванням лишаємо відносні назви без "./" (сумісність зі старими шляхами)
+    fn file(&self, file_name: &str) -> String {
+        if self.dir.as_os_str().is_empty() || self.dir == Path::new(DEFAULT_INDEX_DIR) {
+            return file_name.to_string();
+        }
+        self.dir.join(file_name).to_string_lossy().to_string()
+    }
+}
+
+impl Default for IndexPaths {
+    fn default() -> Self {
+        Self::new(DEFAULT_INDEX_DIR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files_resolve_inside_index_dir() {
+        let paths = IndexPaths::new("/srv/a");
+        assert_eq!(paths.documents_index(), "/srv/a/documents_index.json");
+        assert_eq!(paths.sync_journal(), "/srv/a/sync_copy.journal");
+
+        // Папка за замовчуванням - старі відносні назви без "./"
+        assert_eq!(IndexPaths::default().documents_index(), "documents_index.json");
+    }
+}
@@ -0,0 +1,370 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use crate::document_record::DocumentIndex;
+use crate::error::IndexError;
+use crate::index_manifest::unix_now;
+use crate::inverted_index::InvertedIndex;
+
+/// Скільки окремих виправлень зберігати в звіті (лічильники рахують усі)
+const MAX_REPORTED_ACTIONS: usize = 1000;
+
+/// Одне виправлення, внесене під час ремонту
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RepairAction {
+    /// Запис посилається на документ, якого немає в індексі документів
+    DanglingPostingRemoved { word: String, doc_index: usize },
+    /// Позиції параграфів за межами документа
+    ParagraphPositionsRemoved { word: String, doc_index: usize, positions: Vec<usize> },
+    /// Невалідне (порожнє/коротке) слово або слово без документів
+    InvalidWordRemoved { word: String },
+    /// Документ з текстом, для якого в інвертованому індексі немає жодного запису
+    DocumentReindexed { doc_index: usize, file_path: String },
+    /// paragraph_count документа не відповідав фактичній кількості параграфів
+    ParagraphCountFixed { doc_index: usize, file_path: String, was: usize, now: usize },
+    /// Загальні лічильники перераховано
+    TotalsRecomputed { field: String, was: usize, now: usize },
+}
+
+/// Машинозчитуваний звіт про ремонт індексів
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct RepairReport {
+    pub started_at: u64, // Unix timestamp
+    pub generation: Option<u64>,
+    pub documents_checked: usize,
+    pub words_checked: usize,
+    pub postings_checked: usize,
+    pub dangling_postings_removed: usize,
+    pub paragraph_positions_removed: usize,
+    pub duplicate_postings_merged: usize,
+    pub invalid_words_removed: usize,
+    pub documents_reindexed: usize,
+    pub paragraph_counts_fixed: usize,
+    pub totals_recomputed: usize,
+    /// Інвертований індекс не читався або належав іншому поколінню - перебудовано з нуля
+    pub inverted_rebuilt: bool,
+    pub dry_run: bool,
+    pub saved: bool,
+    pub actions: Vec<RepairAction>,
+    pub actions_truncated: bool,
+}
+
+impl RepairReport {
+    pub fn has_fixes(&self) -> bool {
+        self.dangling_postings_removed
+            + self.paragraph_positions_removed
+            + self.duplicate_postings_merged
+            + self.invalid_words_removed
+            + self.documents_reindexed
+            + self.paragraph_counts_fixed
+            + self.totals_recomputed
+            > 0
+            || self.inverted_rebuilt
+    }
+
+    fn record(&mut self, action: RepairAction) {
+        if self.actions.len() < MAX_REPORTED_ACTIONS {
+            self.actions.push(action);
+        } else {
+            self.actions_truncated = true;
+        }
+    }
+}
+
+impl std::fmt::Display for RepairReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "перевірено документів: {}, слів: {}, записів: {}; видалено висячих записів: {}, \
+             позицій параграфів: {}, невалідних слів: {}; об'єднано дублікатів: {}; \
+             переіндексовано документів: {}; виправлено paragraph_count: {}; перераховано лічильників: {}",
+            self.documents_checked,
+            self.words_checked,
+            self.postings_checked,
+            self.dangling_postings_removed,
+            self.paragraph_positions_removed,
+            self.invalid_words_removed,
+            self.duplicate_postings_merged,
+            self.documents_reindexed,
+            self.paragraph_counts_fixed,
+            self.totals_recomputed
+        )?;
+        if self.inverted_rebuilt {
+            write!(f, "; інвертований індекс перебудовано з нуля")?;
+        }
+        Ok(())
+    }
+}
+
+/// Результат однієї перевірки команди validate
+#[derive(Serialize, Debug, Clone)]
+pub struct IntegrityCheck {
+    pub name: String,
+    pub passed: bool,
+    pub details: Vec<String>,
+}
+
+/// Звіт команди validate (нічого не змінює)
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub checked_at: u64, // Unix timestamp
+    pub generation: Option<u64>,
+    pub checks: Vec<IntegrityCheck>,
+}
+
+impl ValidationReport {
+    pub fn is_healthy(&self) -> bool {
+        self.checks.iter().all(|c| c.passed)
+    }
+
+    pub fn add(&mut self, name: &str, details: Vec<String>) {
+        self.checks.push(IntegrityCheck {
+            name: name.to_string(),
+            passed: details.is_empty(),
+            details,
+        });
+    }
+
+    /// Перевірки узгодженості на основі ремонтного проходу по копіях індексів
+    pub fn add_consistency_checks(&mut self, repair: &RepairReport) {
+        let mut consistency = Vec::new();
+        let mut problem = |count: usize, what: &str| {
+            if count > 0 {
+                consistency.push(format!("{}: {}", what, count));
+            }
+        };
+        problem(repair.dangling_postings_removed, "записи на неіснуючі документи");
+        problem(repair.paragraph_positions_removed, "позиції параграфів за межами документа");
+        problem(repair.duplicate_postings_merged, "дублікати записів");
+        problem(repair.invalid_words_removed, "невалідні слова");
+        problem(repair.documents_reindexed, "документи без записів в інвертованому індексі");
+        problem(repair.totals_recomputed, "розбіжності загальних лічильників");
+        self.add("consistency", consistency);
+
+        // Результат визначає лічильник: перелік дій обрізано до MAX_REPORTED_ACTIONS
+        let mut paragraph_counts: Vec<String> = repair
+            .actions
+            .iter()
+            .filter_map(|action| match action {
+                RepairAction::ParagraphCountFixed { file_path, was, now, .. } => {
+                    Some(format!("{}: paragraph_count {} замість {}", file_path, was, now))
+                }
+                _ => None,
+            })
+            .collect();
+        let unlisted = repair.paragraph_counts_fixed.saturating_sub(paragraph_counts.len());
+        if unlisted > 0 {
+            paragraph_counts.push(format!("ще документів з неправильним paragraph_count: {}", unlisted));
+        }
+        self.add("paragraph_counts", paragraph_counts);
+    }
+}
+
+impl std::fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            let status = if check.passed { "✅" } else { "❌" };
+            writeln!(f, "{} {}", status, check.name)?;
+            for detail in &check.details {
+                writeln!(f, "   - {}", detail)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Перевіряє кожен запис інвертованого індексу відносно індексу документів
+/// та виправляє знайдені проблеми на місці
+pub fn repair_indices(doc_index: &mut DocumentIndex, inv_index: &mut InvertedIndex) -> RepairReport {
+    let mut report = RepairReport {
+        started_at: unix_now(),
+        documents_checked: doc_index.documents.len(),
+        words_checked: inv_index.word_to_docs.len(),
+        ..Default::default()
+    };
+
+    // Інвертований індекс будується з content, тому межі позицій беремо звідти.
+    // Записи на tombstone-слоти законні до ущільнення, тому для них межі не перевіряються.
+    let paragraph_limits: Vec<Option<usize>> = doc_index
+        .documents
+        .iter()
+        .map(|d| if d.deleted { None } else { Some(d.content.len()) })
+        .collect();
+
+    // Крок 1: висячі записи та позиції параграфів за межами документа
+    let mut indexed_docs = HashSet::new();
+    for (word, doc_positions) in inv_index.word_to_docs.iter_mut() {
+        report.postings_checked += doc_positions.len();
+
+        doc_positions.retain_mut(|doc_pos| {
+            let Some(&limit) = paragraph_limits.get(doc_pos.doc_index) else {
+                report.dangling_postings_removed += 1;
+                report.record(RepairAction::DanglingPostingRemoved {
+                    word: word.clone(),
+                    doc_index: doc_pos.doc_index,
+                });
+                return false;
+            };
+
+            let Some(limit) = limit else {
+                return true;
+            };
+
+            let (valid, invalid): (Vec<usize>, Vec<usize>) =
+                doc_pos.paragraph_positions.iter().partition(|&&p| p < limit);
+
+            if !invalid.is_empty() {
+                report.paragraph_positions_removed += invalid.len();
+                report.record(RepairAction::ParagraphPositionsRemoved {
+                    word: word.clone(),
+                    doc_index: doc_pos.doc_index,
+                    positions: invalid,
+                });
+                doc_pos.paragraph_positions = valid;
+            }
+
+            if doc_pos.paragraph_positions.is_empty() {
+                return false;
+            }
+
+            indexed_docs.insert(doc_pos.doc_index);
+            true
+        });
+    }
+
+    // Крок 2: дублікати та невалідні слова
+    report.duplicate_postings_merged = inv_index.remove_duplicate_entries();
+
+    let invalid_words: Vec<String> = inv_index
+        .word_to_docs
+        .iter()
+        .filter(|(word, doc_positions)| word.len() < 2 || doc_positions.is_empty())
+        .map(|(word, _)| word.clone())
+        .collect();
+    for word in invalid_words {
+        inv_index.word_to_docs.remove(&word);
+        report.invalid_words_removed += 1;
+        report.record(RepairAction::InvalidWordRemoved { word });
+    }
+
+    // Крок 3: документи з текстом без жодного запису в інвертованому індексі
+    let missing: Vec<usize> = doc_index
+        .documents
+        .iter()
+        .enumerate()
+        .filter(|(idx, doc)| {
+            !doc.deleted && !indexed_docs.contains(idx) && doc.content.iter().any(|p| !p.trim().is_empty())
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+
+    if !missing.is_empty() {
+        inv_index.update_incremental(doc_index, &missing);
+        // Документ без слів довжиною від 2 символів так і лишиться без записів - це не помилка
+        for &idx in &missing {
+            if inv_index.word_to_docs.values().any(|docs| docs.iter().any(|dp| dp.doc_index == idx)) {
+                report.documents_reindexed += 1;
+                report.record(RepairAction::DocumentReindexed {
+                    doc_index: idx,
+                    file_path: doc_index.documents[idx].file_path.clone(),
+                });
+            }
+        }
+    }
+
+    // Крок 4: кількість параграфів у кожному документі
+    for (idx, document) in doc_index.documents.iter_mut().enumerate() {
+        let actual = document.paragraphs.len().max(document.content.len());
+        if document.paragraph_count != actual {
+            report.paragraph_counts_fixed += 1;
+            report.record(RepairAction::ParagraphCountFixed {
+                doc_index: idx,
+                file_path: document.file_path.clone(),
+                was: document.paragraph_count,
+                now: actual,
+            });
+            document.paragraph_count = actual;
+        }
+    }
+
+    // Крок 5: загальні лічильники перераховуються зі списку документів
+    let recount = doc_index.recount();
+    let mut recompute = |field: &str, was: usize, now: usize| {
+        if was != now {
+            report.totals_recomputed += 1;
+            report.record(RepairAction::TotalsRecomputed { field: field.to_string(), was, now });
+        }
+    };
+    recompute("documents.total_documents", recount.documents_was, recount.documents_now);
+    recompute("documents.total_words", recount.words_was, recount.words_now);
+    recompute("inverted.total_documents", inv_index.total_documents, recount.documents_now);
+    inv_index.total_documents = recount.documents_now;
+
+    report
+}
+
+/// Записує звіт у JSON-файл
+pub fn write_report(path: &str, report: &RepairReport) -> Result<(), IndexError> {
+    let json = serde_json::to_string_pretty(report)
+        .map_err(|e| IndexError::json("Помилка серіалізації звіту ремонту", e))?;
+
+    std::fs::write(path, json).map_err(|e| IndexError::io(format!("Помилка запису звіту ремонту {}", path), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document_record::DocumentRecord;
+    use crate::inverted_index::DocPosition;
+
+    fn document(path: &str, content: &[&str]) -> DocumentRecord {
+        DocumentRecord::from_texts(path, content.iter().copied())
+    }
+
+    #[test]
+    fn test_repair_removes_dangling_and_out_of_range_postings() {
+        let mut doc_index = DocumentIndex::new();
+        doc_index.documents.push(document("a.docx", &["наказ"]));
+        doc_index.total_documents = 5;
+
+        let mut inv_index = InvertedIndex::new();
+        inv_index.word_to_docs.insert(
+            "наказ".to_string(),
+            vec![
+                DocPosition { doc_index: 0, paragraph_positions: vec![0, 3] },
+                DocPosition { doc_index: 7, paragraph_positions: vec![0] },
+            ],
+        );
+
+        let report = repair_indices(&mut doc_index, &mut inv_index);
+
+        assert_eq!(report.dangling_postings_removed, 1);
+        assert_eq!(report.paragraph_positions_removed, 1);
+        assert_eq!(doc_index.total_documents, 1);
+        assert_eq!(inv_index.total_documents, 1);
+
+        let postings = &inv_index.word_to_docs["наказ"];
+        assert_eq!(postings.len(), 1);
+        assert_eq!(postings[0].paragraph_positions, vec![0]);
+
+        // Повторний ремонт нічого не змінює
+        assert!(!repair_indices(&mut doc_index, &mut inv_index).has_fixes());
+    }
+
+    #[test]
+    fn test_paragraph_counts_check_uses_counter_beyond_reported_actions() {
+        // Перелік дій уже заповнено іншими виправленнями - paragraph_count у нього не потрапив
+        let mut repair = RepairReport::default();
+        for doc_index in 0..MAX_REPORTED_ACTIONS {
+            repair.record(RepairAction::DanglingPostingRemoved { word: "наказ".to_string(), doc_index });
+        }
+        repair.paragraph_counts_fixed = 3;
+
+        let mut validation = ValidationReport::default();
+        validation.add_consistency_checks(&repair);
+        let check = validation.checks.iter().find(|c| c.name == "paragraph_counts").unwrap();
+        assert!(!check.passed);
+        assert_eq!(check.details, vec!["ще документів з неправильним paragraph_count: 3"]);
+        assert!(!validation.is_healthy());
+    }
+}
@@ -0,0 +1,439 @@
+use crate::atomic_index_manager::AtomicIndexManager;
+use crate::document_date;
+use crate::document_record::{DocumentIndex, DocumentRecord};
+use crate::error::IndexError;
+use crate::index_checksum;
+use crate::index_manifest::IndexManifest;
+use crate::index_store::store_is_empty;
+use crate::inverted_index::InvertedIndex;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const MONTH_LABELS: [&str; 12] = ["Січ", "Лют", "Бер", "Кві", "Тра", "Чер", "Лип", "Сер", "Вер", "Жов", "Лис", "Гру"];
+
+/// Документи одного року (за датою в назві файлу чи в шапці документа або папкою року в кеші)
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct YearStats {
+    /// None - рік визначити не вдалося
+    pub year: Option<u32>,
+    pub documents: usize,
+    pub words: usize,
+    /// Документи за місяцями (січень - перший); документи, рік яких відомий лише з папки, не враховані
+    pub months: [usize; 12],
+    /// Місяці (1-12) без жодного документа між першим і останнім датованим місяцем архіву -
+    /// ймовірні прогалини в архіві
+    pub missing_months: Vec<u32>,
+}
+
+impl YearStats {
+    fn new(year: Option<u32>) -> Self {
+        Self {
+            year,
+            documents: 0,
+            words: 0,
+            months: [0; 12],
+            missing_months: Vec::new(),
+        }
+    }
+}
+
+/// Статистика активного покоління індексів (команда stats і підсумок індексації)
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct IndexStats {
+    pub generation: Option<u64>,
+    /// Час останньої індексації (Unix timestamp)
+    pub indexed_at: Option<u64>,
+    pub documents: usize,
+    /// Слоти видалених документів, що чекають на ущільнення
+    pub deleted_slots: usize,
+    pub total_words: usize,
+    /// None - інвертованого індексу немає або він не читається
+    pub unique_words: Option<usize>,
+    pub documents_index_bytes: Option<u64>,
+    pub inverted_index_bytes: Option<u64>,
+    /// Від найновішого року до найстарішого, документи без року - в кінці
+    pub by_year: Vec<YearStats>,
+}
+
+impl IndexStats {
+    /// Збирає статистику з файлів активного покоління. Файли читаються напряму,
+    /// без відновлення з резервних копій і без виводу в консоль (щоб --json лишався чистим).
+    pub fn collect(index_manager: &AtomicIndexManager) -> Result<Self, IndexError> {
+        if let Some(store) = index_manager.store() {
+            // Обидва індекси лежать в одній базі - окремих розмірів файлів немає
+            let (doc_index, inv_index) = store.load()?.ok_or_else(store_is_empty)?;
+            return Ok(Self::from_indices(Some(doc_index.generation), &doc_index, Some(&inv_index)));
+        }
+
+        let (active_doc_path, active_inv_path) = index_manager.active_paths();
+
+        if !Path::new(&active_doc_path).exists() {
+            return Err(IndexError::NotFound(format!("Індекс документів не знайдено: {}", active_doc_path)));
+        }
+
+        let doc_index: DocumentIndex = read_json(&active_doc_path)?;
+        // Сегмент інвертованого індексу читається разом з нижчими шарами
+        let inv_index = InvertedIndex::load_from_file(&active_inv_path).ok();
+        let manifest = IndexManifest::load(&index_manager.documents_index_path);
+        let generation = manifest.as_ref().map(|m| m.generation);
+        let layers = manifest
+            .as_ref()
+            .and_then(|m| m.active_snapshot())
+            .map(|snapshot| snapshot.inverted_base.clone())
+            .unwrap_or_default();
+
+        let mut stats = Self::from_indices(generation, &doc_index, inv_index.as_ref());
+        stats.documents_index_bytes = fs::metadata(&active_doc_path).ok().map(|m| m.len());
+        stats.inverted_index_bytes = layers
+            .iter()
+            .chain(std::iter::once(&active_inv_path))
+            .map(|path| fs::metadata(path).ok().map(|m| m.len()))
+            .sum();
+        Ok(stats)
+    }
+
+    /// Статистика вже завантажених індексів (сервер рахує її з покоління в пам'яті пошуку)
+    pub fn from_indices(generation: Option<u64>, doc_index: &DocumentIndex, inv_index: Option<&InvertedIndex>) -> Self {
+        let mut stats = Self::from_documents(doc_index);
+        stats.generation = generation;
+        stats.unique_words = inv_index.map(|inv_index| inv_index.word_to_docs.len());
+        stats
+    }
+
+    pub fn from_documents(doc_index: &DocumentIndex) -> Self {
+        let mut years: BTreeMap<Option<u32>, YearStats> = BTreeMap::new();
+
+        for document in doc_index.documents.iter().filter(|d| !d.deleted) {
+            let (year, month) = document_year_month(document);
+            let entry = years.entry(year).or_insert_with(|| YearStats::new(year));
+            entry.documents += 1;
+            entry.words += document.word_count;
+            if let Some(month) = month {
+                entry.months[month as usize - 1] += 1;
+            }
+        }
+
+        mark_missing_months(&mut years);
+
+        // None сортується першим - після розвороту документи без року опиняються в кінці
+        let by_year = years.into_values().rev().collect();
+
+        Self {
+            generation: None,
+            indexed_at: (doc_index.indexed_at > 0).then_some(doc_index.indexed_at),
+            documents: doc_index.live_count(),
+            deleted_slots: doc_index.tombstone_count(),
+            total_words: doc_index.total_words,
+            unique_words: None,
+            documents_index_bytes: None,
+            inverted_index_bytes: None,
+            by_year,
+        }
+    }
+}
+
+impl std::fmt::Display for IndexStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let indexed_at = self
+            .indexed_at
+            .and_then(|t| chrono::DateTime::from_timestamp(t as i64, 0))
+            .map(|t| t.with_timezone(&chrono::Local).format("%d.%m.%Y %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        let megabytes = |bytes: Option<u64>| optional(bytes.map(|b| format!("{:.2} MB", b as f64 / 1_048_576.0)));
+
+        writeln!(f, "📊 Статистика індексів")?;
+        writeln!(f, "   Покоління:             {}", optional(self.generation.map(|g| g.to_string())))?;
+        writeln!(f, "   Остання індексація:    {}", indexed_at)?;
+        writeln!(f, "   Документів:            {} (видалених слотів: {})", self.documents, self.deleted_slots)?;
+        writeln!(f, "   Слів:                  {}", self.total_words)?;
+        writeln!(f, "   Унікальних слів:       {}", optional(self.unique_words.map(|w| w.to_string())))?;
+        writeln!(f, "   Індекс документів:     {}", megabytes(self.documents_index_bytes))?;
+        writeln!(f, "   Інвертований індекс:   {}", megabytes(self.inverted_index_bytes))?;
+
+        if !self.by_year.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "   {:<10} {:>10} {:>12}", "Рік", "Документів", "Слів")?;
+            for year in &self.by_year {
+                let label = year.year.map_or_else(|| "без року".to_string(), |y| y.to_string());
+                writeln!(f, "   {:<10} {:>10} {:>12}", label, year.documents, year.words)?;
+            }
+        }
+
+        let dated: Vec<&YearStats> = self
+            .by_year
+            .iter()
+            .filter(|y| y.year.is_some() && (y.months.iter().any(|&n| n > 0) || !y.missing_months.is_empty()))
+            .collect();
+        if !dated.is_empty() {
+            writeln!(f)?;
+            write!(f, "   {:<6}", "Рік")?;
+            for label in MONTH_LABELS {
+                write!(f, "{:>6}", label)?;
+            }
+            writeln!(f)?;
+            for year in &dated {
+                let mut row = format!("   {:<6}", year.year.unwrap_or_default());
+                for (i, &count) in year.months.iter().enumerate() {
+                    // Прогалина позначається знаком оклику, місяці поза межами архіву - порожні
+                    let cell = if year.missing_months.contains(&(i as u32 + 1)) {
+                        "0!".to_string()
+                    } else if count > 0 {
+                        count.to_string()
+                    } else {
+                        String::new()
+                    };
+                    row.push_str(&format!("{:>6}", cell));
+                }
+                writeln!(f, "{}", row.trim_end())?;
+            }
+
+            let missing: Vec<String> = dated
+                .iter()
+                .rev()
+                .flat_map(|y| y.missing_months.iter().map(move |m| format!("{:02}.{}", m, y.year.unwrap_or_default())))
+                .collect();
+            if !missing.is_empty() {
+                writeln!(f)?;
+                writeln!(f, "   ⚠️ Місяці без документів: {}", missing.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Термін інвертованого індексу з частотою
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TermFrequency {
+    pub term: String,
+    /// У скількох документах трапляється
+    pub documents: usize,
+    /// У скількох абзацах трапляється (сума по документах)
+    pub paragraphs: usize,
+}
+
+/// Словник термінів (команда terms): для складання списків стоп-слів і синонімів
+/// та пошуку сміттєвих токенів, які породжує токенізатор
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct TermReport {
+    pub total_terms: usize,
+    /// Терміни лише з одного документа - серед них зазвичай і сміття
+    pub single_document_terms: usize,
+    pub terms: Vec<TermFrequency>,
+}
+
+impl TermReport {
+    /// Терміни з активного покоління (файл читається напряму, як у IndexStats::collect)
+    pub fn collect(index_manager: &AtomicIndexManager, limit: usize, rarest: bool) -> Result<Self, IndexError> {
+        if let Some(store) = index_manager.store() {
+            let inv_index = store.load_inverted()?.ok_or_else(store_is_empty)?;
+            return Ok(Self::from_inverted(&inv_index, limit, rarest));
+        }
+
+        let (_, active_inv_path) = index_manager.active_paths();
+        if !Path::new(&active_inv_path).exists() {
+            return Err(IndexError::NotFound(format!("Інвертований індекс не знайдено: {}", active_inv_path)));
+        }
+        let inv_index = InvertedIndex::load_from_file(&active_inv_path)?;
+        Ok(Self::from_inverted(&inv_index, limit, rarest))
+    }
+
+    /// limit найчастіших термінів (rarest - найрідкісніших) за кількістю документів
+    pub fn from_inverted(inv_index: &InvertedIndex, limit: usize, rarest: bool) -> Self {
+        Self::from_inverted_where(inv_index, limit, rarest, |_| true)
+    }
+
+    /// Як from_inverted, але лише терміни, для яких keep повертає true (приховані терміни,
+    /// наприклад ІПН під маскуванням, не потрапляють і до лічильника однодокументних)
+    pub fn from_inverted_where(
+        inv_index: &InvertedIndex,
+        limit: usize,
+        rarest: bool,
+        keep: impl Fn(&str) -> bool,
+    ) -> Self {
+        let mut terms: Vec<TermFrequency> = inv_index
+            .word_to_docs
+            .iter()
+            .filter(|(term, _)| keep(term))
+            .map(|(term, docs)| TermFrequency {
+                term: term.clone(),
+                documents: docs.len(),
+                paragraphs: docs.iter().map(|d| d.paragraph_positions.len()).sum(),
+            })
+            .collect();
+
+        let single_document_terms = terms.iter().filter(|t| t.documents == 1).count();
+        terms.sort_by(|a, b| {
+            let frequency = (a.documents, a.paragraphs).cmp(&(b.documents, b.paragraphs));
+            let frequency = if rarest { frequency } else { frequency.reverse() };
+            frequency.then_with(|| a.term.cmp(&b.term))
+        });
+        terms.truncate(limit);
+
+        Self {
+            total_terms: inv_index.word_to_docs.len(),
+            single_document_terms,
+            terms,
+        }
+    }
+}
+
+impl std::fmt::Display for TermReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "📖 Словник індексу")?;
+        writeln!(f, "   Термінів:                  {}", self.total_terms)?;
+        writeln!(f, "   Лише в одному документі:   {}", self.single_document_terms)?;
+        if !self.terms.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "   {:<30} {:>10} {:>10}", "Термін", "Документів", "Абзаців")?;
+            for term in &self.terms {
+                writeln!(f, "   {:<30} {:>10} {:>10}", term.term, term.documents, term.paragraphs)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Рік і місяць документа: дата в назві файлу, інакше дата в шапці документа,
+/// інакше лише рік за папкою (2023, 2024 ...) у шляху
+pub(crate) fn document_year_month(document: &DocumentRecord) -> (Option<u32>, Option<u32>) {
+    if let Some((year, month, _)) = document_date::file_name_date(&document.file_path) {
+        return (Some(year), Some(month));
+    }
+    if let Some((year, month, _)) = document_date::content_date(document) {
+        return (Some(year), Some(month));
+    }
+    (folder_year(&document.file_path), None)
+}
+
+/// Позначає місяці без документів між першим і останнім датованим місяцем;
+/// роки всередині цього проміжку без жодного документа додаються порожніми
+fn mark_missing_months(years: &mut BTreeMap<Option<u32>, YearStats>) {
+    let dated: Vec<(u32, u32)> = years
+        .values()
+        .filter_map(|y| Some((y.year?, &y.months)))
+        .flat_map(|(year, months)| {
+            months
+                .iter()
+                .enumerate()
+                .filter(|&(_, &count)| count > 0)
+                .map(move |(i, _)| (year, i as u32 + 1))
+        })
+        .collect();
+    let (Some(&first), Some(&last)) = (dated.iter().min(), dated.iter().max()) else {
+        return;
+    };
+
+    for year in first.0..=last.0 {
+        let entry = years.entry(Some(year)).or_insert_with(|| YearStats::new(Some(year)));
+        let from = if year == first.0 { first.1 } else { 1 };
+        let to = if year == last.0 { last.1 } else { 12 };
+        entry.missing_months = (from..=to).filter(|&m| entry.months[m as usize - 1] == 0).collect();
+    }
+}
+
+/// Рік за папкою (2023, 2024, "2023 рік" ...) у шляху
+fn folder_year(file_path: &str) -> Option<u32> {
+    let parent = Path::new(file_path).parent()?;
+    parent.components().rev().find_map(|component| {
+        let name = component.as_os_str().to_str()?;
+        let digits = name.get(..4)?;
+        (digits.chars().all(|c| c.is_ascii_digit()) && !name[4..].starts_with(|c: char| c.is_ascii_digit()))
+            .then(|| digits.parse().ok())
+            .flatten()
+    })
+}
+
+pub(crate) fn read_json<T: DeserializeOwned>(path: &str) -> Result<T, IndexError> {
+    index_checksum::read_verified_json(path, &format!("Помилка читання {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document_record::Paragraph;
+    use crate::inverted_index::DocPosition;
+
+    fn document(path: &str, word_count: usize) -> DocumentRecord {
+        DocumentRecord { word_count, ..DocumentRecord::from_texts(path, [""]) }
+    }
+
+    #[test]
+    fn test_documents_grouped_by_year() {
+        let mut doc_index = DocumentIndex::new();
+        doc_index.push_document(document("cache/2024/Наказ 01.02.2024.docx", 10));
+        // Дата в назві має пріоритет над папкою
+        doc_index.push_document(document("cache/2024/Наказ 30.12.2023.docx", 5));
+        doc_index.push_document(document("cache/2023 рік/Рапорт.docx", 7));
+        doc_index.push_document(document("cache/Різне/Рапорт.docx", 1));
+        let removed = doc_index.push_document(document("cache/2022/Наказ.docx", 3));
+        doc_index.tombstone(removed);
+
+        let stats = IndexStats::from_documents(&doc_index);
+        assert_eq!(stats.documents, 4);
+        assert_eq!(stats.deleted_slots, 1);
+        let summary: Vec<_> = stats.by_year.iter().map(|y| (y.year, y.documents, y.words)).collect();
+        assert_eq!(summary, vec![(Some(2024), 1, 10), (Some(2023), 2, 12), (None, 1, 1)]);
+        assert!(stats.to_string().contains("без року"));
+    }
+
+    #[test]
+    fn test_months_from_filename_and_content_with_gaps() {
+        let mut doc_index = DocumentIndex::new();
+        doc_index.push_document(document("cache/2023/Наказ 15.11.2023.docx", 1));
+        let mut from_content = document("cache/2024/Наказ №3.docx", 1);
+        from_content.paragraphs = vec![
+            Paragraph::new("НАКАЗ".to_string()),
+            Paragraph::new("від 05 Лютого 2024 року".to_string()),
+        ];
+        doc_index.push_document(from_content);
+        doc_index.push_document(document("cache/2024/Наказ 20.04.2024.docx", 1));
+        // Рік лише з папки - до місяців не потрапляє
+        doc_index.push_document(document("cache/2024/Рапорт.docx", 1));
+
+        let stats = IndexStats::from_documents(&doc_index);
+        let year_2024 = &stats.by_year[0];
+        assert_eq!(year_2024.year, Some(2024));
+        assert_eq!(year_2024.documents, 3);
+        assert_eq!(year_2024.months[1], 1);
+        assert_eq!(year_2024.months[3], 1);
+        assert_eq!(year_2024.missing_months, vec![1, 3]);
+        assert_eq!(stats.by_year[1].missing_months, vec![12]);
+
+        let table = stats.to_string();
+        assert!(table.contains("Місяці без документів: 12.2023, 01.2024, 03.2024"));
+        assert!(table.contains("0!"));
+    }
+
+    #[test]
+    fn test_terms_sorted_by_document_frequency() {
+        let mut inv_index = InvertedIndex::new();
+        let postings = |docs: &[(usize, usize)]| -> Vec<DocPosition> {
+            docs.iter()
+                .map(|&(doc_index, paragraphs)| DocPosition { doc_index, paragraph_positions: (0..paragraphs).collect() })
+                .collect()
+        };
+        inv_index.word_to_docs.insert("наказ".to_string(), postings(&[(0, 1), (1, 2), (2, 1)]));
+        inv_index.word_to_docs.insert("солдат".to_string(), postings(&[(0, 3), (1, 1)]));
+        inv_index.word_to_docs.insert("призначит".to_string(), postings(&[(0, 1), (2, 1)]));
+        inv_index.word_to_docs.insert("ъъъ".to_string(), postings(&[(1, 1)]));
+
+        // Приховані терміни не рахуються і серед однодокументних
+        let visible = TermReport::from_inverted_where(&inv_index, 10, true, |term| term != "ъъъ");
+        assert!(visible.terms.iter().all(|t| t.term != "ъъъ"));
+        assert_eq!(visible.single_document_terms, 0);
+
+        let top = TermReport::from_inverted(&inv_index, 3, false);
+        assert_eq!(top.total_terms, 4);
+        assert_eq!(top.single_document_terms, 1);
+        let terms: Vec<_> = top.terms.iter().map(|t| (t.term.as_str(), t.documents, t.paragraphs)).collect();
+        assert_eq!(terms, vec![("наказ", 3, 4), ("солдат", 2, 4), ("призначит", 2, 2)]);
+
+        let rare = TermReport::from_inverted(&inv_index, 1, true);
+        assert_eq!(rare.terms[0].term, "ъъъ");
+        assert!(rare.to_string().contains("Лише в одному документі:   1"));
+    }
+}
@@ -0,0 +1,195 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Файлові операції, через які AtomicIndexManager записує покоління, маніфест і резервні копії.
+/// Виробнича реалізація - FsStorage; у тестах її підміняють реалізацією, що імітує збої
+/// (rename не вдався, диск заповнений), щоб перевіряти шляхи відновлення без зламаного сервера.
+pub trait IndexStorage: Send + Sync {
+    /// Створює (або обрізає) файл для запису
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write>>;
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64>;
+
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()>;
+
+    /// Скидає вміст файлу на диск (fsync)
+    fn sync_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Скидає на диск запис папки, щоб rename пережив втрату живлення
+    fn sync_dir(&self, dir: &Path) -> io::Result<()>;
+
+    /// Записує весь вміст у файл
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let mut file = self.create(path)?;
+        file.write_all(contents)?;
+        file.flush()
+    }
+}
+
+/// Звичайна файлова система
+pub struct FsStorage;
+
+impl IndexStorage for FsStorage {
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write>> {
+        Ok(Box::new(File::create(path)?))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        fs::copy(from, to)
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()> {
+        fs::hard_link(original, link)
+    }
+
+    /// Файл відкривається на запис: на Windows FlushFileBuffers потребує права запису,
+    /// і sync_all через дескриптор лише для читання завершується помилкою
+    fn sync_file(&self, path: &Path) -> io::Result<()> {
+        OpenOptions::new().write(true).open(path)?.sync_all()
+    }
+
+    /// На Windows відкрити папку як файл неможливо, тому там це no-op
+    fn sync_dir(&self, dir: &Path) -> io::Result<()> {
+        #[cfg(unix)]
+        File::open(dir)?.sync_all()?;
+
+        #[cfg(not(unix))]
+        let _ = dir;
+
+        Ok(())
+    }
+}
+
+/// Сховище для тестів: працює з реальною тимчасовою папкою, але імітує збій
+/// вказаної операції для шляхів, що містять заданий фрагмент
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Fault {
+        /// Не вдається створити файл
+        Create,
+        /// Диск заповнюється після вказаної кількості байтів
+        DiskFull(usize),
+        Rename,
+        Sync,
+    }
+
+    #[derive(Default)]
+    pub struct FaultyStorage {
+        faults: Mutex<Vec<(Fault, String)>>,
+        /// Видалені файли в порядку видалення - щоб перевірити, що відкат іде через сховище
+        removed: Mutex<Vec<PathBuf>>,
+    }
+
+    impl FaultyStorage {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn removed(&self) -> Vec<PathBuf> {
+            self.removed.lock().unwrap().clone()
+        }
+
+        pub fn fail(self, fault: Fault, path_fragment: &str) -> Self {
+            self.faults.lock().unwrap().push((fault, path_fragment.to_string()));
+            self
+        }
+
+        fn fault_for(&self, path: &Path, matches: impl Fn(Fault) -> bool) -> Option<Fault> {
+            let path = path.to_string_lossy();
+            self.faults
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(fault, fragment)| matches(*fault) && path.contains(fragment.as_str()))
+                .map(|(fault, _)| *fault)
+        }
+    }
+
+    fn injected(operation: &str) -> io::Error {
+        io::Error::other(format!("імітований збій: {}", operation))
+    }
+
+    /// Записує не більше ліміту, далі - помилка "диск заповнений"
+    struct LimitedWriter {
+        inner: File,
+        remaining: usize,
+    }
+
+    impl Write for LimitedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.remaining == 0 {
+                return Err(io::Error::new(io::ErrorKind::StorageFull, "диск заповнений"));
+            }
+            let len = buf.len().min(self.remaining);
+            let written = self.inner.write(&buf[..len])?;
+            self.remaining -= written;
+            Ok(written)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    impl IndexStorage for FaultyStorage {
+        fn create(&self, path: &Path) -> io::Result<Box<dyn Write>> {
+            match self.fault_for(path, |f| matches!(f, Fault::Create | Fault::DiskFull(_))) {
+                Some(Fault::Create) => Err(injected("create")),
+                Some(Fault::DiskFull(limit)) => Ok(Box::new(LimitedWriter {
+                    inner: File::create(path)?,
+                    remaining: limit,
+                })),
+                _ => FsStorage.create(path),
+            }
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+            if self.fault_for(to, |f| f == Fault::Rename).is_some() {
+                return Err(injected("rename"));
+            }
+            FsStorage.rename(from, to)
+        }
+
+        fn remove_file(&self, path: &Path) -> io::Result<()> {
+            self.removed.lock().unwrap().push(path.to_path_buf());
+            FsStorage.remove_file(path)
+        }
+
+        fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+            FsStorage.copy(from, to)
+        }
+
+        fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()> {
+            FsStorage.hard_link(original, link)
+        }
+
+        fn sync_file(&self, path: &Path) -> io::Result<()> {
+            if self.fault_for(path, |f| f == Fault::Sync).is_some() {
+                return Err(injected("fsync"));
+            }
+            FsStorage.sync_file(path)
+        }
+
+        fn sync_dir(&self, dir: &Path) -> io::Result<()> {
+            FsStorage.sync_dir(dir)
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Сховище індексів замість файлів поколінь (storage.backend). Файлове сховище записує
+//! кожне оновлення двома цілими JSON-файлами - на кількох десятках тисяч наказів це сотні
+//! мегабайт навіть для одного зміненого документа. Сховище з таблицями (SQLite, див.
+//! sqlite_store) переписує лише рядки змінених слотів однією транзакцією бази.
+
+use crate::document_record::DocumentIndex;
+use crate::error::IndexError;
+use crate::inverted_index::InvertedIndex;
+use serde::{Deserialize, Serialize};
+
+/// Де зберігаються індекси
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// Файли поколінь з маніфестом, знімками для відкату і резервними копіями
+    #[default]
+    Json,
+    /// Одна база SQLite у папці індексів (index.sqlite)
+    Sqlite,
+}
+
+impl std::str::FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "sqlite" => Ok(Self::Sqlite),
+            other => Err(format!("невідоме сховище індексу: {} (json або sqlite)", other)),
+        }
+    }
+}
+
+/// Що змінилось відносно останньої збереженої версії
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreChanges {
+    /// Усе: повна індексація, перестановка слотів за датою, ущільнення, ремонт
+    All,
+    /// Лише ці слоти (нові, змінені, позначені видаленими); решта збігається зі збереженим
+    Slots(Vec<usize>),
+}
+
+/// Сховище обох індексів. Версія (покоління) зростає з кожним commit, як номер покоління
+/// файлів, тож пошуковий движок так само визначає, чи треба перечитати індекси
+pub trait IndexStore: Send + Sync {
+    /// Номер останньої збереженої версії (None - ще нічого не збережено)
+    fn generation(&self) -> Result<Option<u64>, IndexError>;
+
+    /// Індекс документів останньої версії
+    fn load_documents(&self) -> Result<Option<DocumentIndex>, IndexError>;
+
+    /// Інвертований індекс останньої версії
+    fn load_inverted(&self) -> Result<Option<InvertedIndex>, IndexError>;
+
+    /// Обидва індекси однієї версії (окремі load_* можуть потрапити між двома commit)
+    fn load(&self) -> Result<Option<(DocumentIndex, InvertedIndex)>, IndexError>;
+
+    /// Зберігає нову версію атомарно і повертає її номер
+    fn commit(
+        &self,
+        document_index: &DocumentIndex,
+        inverted_index: &InvertedIndex,
+        changes: &StoreChanges,
+    ) -> Result<u64, IndexError>;
+
+    /// Проблеми самого сховища (пошкоджені сторінки бази тощо) для команди validate
+    fn verify(&self) -> Vec<String>;
+}
+
+/// Помилка читання сховища, в яке ще нічого не записано
+pub(crate) fn store_is_empty() -> IndexError {
+    IndexError::NotFound("Сховище індексу порожнє - індекси ще не створено".to_string())
+}
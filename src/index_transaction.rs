@@ -0,0 +1,211 @@
+use crate::atomic_index_manager::{AtomicIndexManager, UpdateLock, UpdateStats};
+use crate::document_record::{DocumentIndex, DocumentRecord};
+use crate::error::IndexError;
+use crate::folder_processor::FolderProcessor;
+use crate::inverted_index::InvertedIndex;
+use log::info;
+
+/// Транзакція зміни індексів для альтернативних клієнтів (інструменти імпорту, тести):
+/// `begin()` → `add/update/remove` документів → `commit()` або `abort()`.
+/// Утримує блокування оновлення від begin до завершення, а commit проходить той самий
+/// конвеєр, що й індексація папки (впорядкування за датою, інвертований індекс,
+/// статистика, ущільнення, атомарне перемикання покоління).
+/// Транзакція, що вийшла з області видимості без commit, відкочується.
+pub struct IndexTransaction<'a> {
+    manager: &'a AtomicIndexManager,
+    _lock: UpdateLock,
+    doc_index: DocumentIndex,
+    inv_index: Option<InvertedIndex>,
+    /// Облік змінених і видалених слотів - у тому ж вигляді, що й при індексації папки
+    changes: FolderProcessor,
+    finished: bool,
+}
+
+impl<'a> IndexTransaction<'a> {
+    pub(crate) fn begin(manager: &'a AtomicIndexManager) -> Result<Self, IndexError> {
+        let lock = manager.acquire_update_lock()?;
+        let (doc_index, inv_index) = manager.load_active_indices();
+
+        // З порожнього індексу - лише якщо індексу ще немає. Пошкоджений чи нечитабельний
+        // індекс не замінюється: commit записав би покоління без решти архіву
+        let doc_index = match doc_index {
+            Some(doc_index) => doc_index,
+            None => match manager.load_document_index() {
+                Err(IndexError::NotFound(_)) => DocumentIndex::default(),
+                Err(e) => return Err(e),
+                Ok(_) => {
+                    return Err(IndexError::Corrupt(
+                        "Активний індекс документів не завантажено - транзакцію не розпочато".to_string(),
+                    ))
+                }
+            },
+        };
+
+        Ok(Self {
+            manager,
+            _lock: lock,
+            doc_index,
+            inv_index,
+            changes: FolderProcessor::new(),
+            finished: false,
+        })
+    }
+
+    /// Поточний стан індексу документів з урахуванням змін транзакції
+    pub fn documents(&self) -> &DocumentIndex {
+        &self.doc_index
+    }
+
+    /// Ідентифікатор живого документа за шляхом до файлу
+    pub fn find_by_path(&self, file_path: &str) -> Option<u64> {
+        self.doc_index
+            .documents
+            .iter()
+            .find(|d| !d.deleted && d.file_path == file_path)
+            .map(|d| d.doc_id)
+    }
+
+    /// Додає документ; повертає присвоєний йому ідентифікатор
+    pub fn add_document(&mut self, document: DocumentRecord) -> u64 {
+        let slot = self.doc_index.push_document(document);
+        self.changes.new_or_updated_indices.push(slot);
+        self.changes.processed_files += 1;
+        self.doc_index.documents[slot].doc_id
+    }
+
+    /// Замінює вміст документа, зберігаючи його ідентифікатор
+    pub fn update_document(&mut self, doc_id: u64, document: DocumentRecord) -> Result<(), IndexError> {
+        let slot = self.live_slot(doc_id)?;
+        self.doc_index.replace_document(slot, document);
+
+        if !self.changes.new_or_updated_indices.contains(&slot) {
+            self.changes.new_or_updated_indices.push(slot);
+        }
+        self.changes.processed_files += 1;
+        Ok(())
+    }
+
+    /// Позначає документ видаленим (tombstone)
+    pub fn remove_document(&mut self, doc_id: u64) -> Result<(), IndexError> {
+        let slot = self.live_slot(doc_id)?;
+        self.doc_index.tombstone(slot);
+
+        // Документ, доданий або змінений у цій же транзакції, індексувати вже не потрібно
+        self.changes.new_or_updated_indices.retain(|&s| s != slot);
+        self.changes.deleted_indices.push(slot);
+        self.changes.deleted_files += 1;
+        Ok(())
+    }
+
+    fn live_slot(&self, doc_id: u64) -> Result<usize, IndexError> {
+        self.doc_index
+            .slot_of(doc_id)
+            .filter(|&slot| !self.doc_index.documents[slot].deleted)
+            .ok_or_else(|| IndexError::NotFound(format!("Документ з ідентифікатором {} не знайдено", doc_id)))
+    }
+
+    /// Застосовує зміни і атомарно записує нове покоління індексів
+    pub fn commit(mut self) -> Result<UpdateStats, IndexError> {
+        self.finished = true;
+
+        let mut stats = UpdateStats {
+            processed: self.changes.processed_files,
+            skipped: 0,
+            deleted: self.changes.deleted_files,
+            interrupted: false,
+            errors: Vec::new(),
+            delta: None,
+        };
+
+        if !stats.has_changes() {
+            info!("ℹ️ Транзакція не містить змін - нове покоління не створюється");
+            return Ok(stats);
+        }
+
+        let mut doc_index = std::mem::take(&mut self.doc_index);
+        self.changes.sort_documents_by_date(&mut doc_index);
+        doc_index.recount();
+        doc_index.indexed_at = crate::index_manifest::unix_now();
+
+        stats.delta = self.manager
+            .apply_changes(&self.changes, doc_index, self.inv_index.take())?;
+
+        Ok(stats)
+    }
+
+    /// Відкидає всі зміни транзакції; активне покоління не змінюється
+    pub fn abort(mut self) {
+        self.finished = true;
+        info!("↩️ Транзакцію індексів скасовано");
+    }
+}
+
+impl Drop for IndexTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            info!("↩️ Транзакцію індексів завершено без commit - зміни відкинуто");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+    use crate::index_manifest::IndexManifest;
+    use crate::stemmer;
+    use std::fs;
+
+    fn document(path: &str, text: &str) -> DocumentRecord {
+        DocumentRecord::from_texts(path, [text])
+    }
+
+    #[test]
+    fn test_transaction_commit_and_abort() {
+        let dir = TempIndexDir::new("transaction_test");
+        let manager = dir.manager();
+
+        let mut tx = manager.begin().unwrap();
+        let first = tx.add_document(document("Наказ 01.02.2024.docx", "перший наказ"));
+        let second = tx.add_document(document("Наказ 01.01.2024.docx", "другий рапорт"));
+        tx.commit().unwrap();
+
+        let mut tx = manager.begin().unwrap();
+        assert_eq!(tx.find_by_path("Наказ 01.01.2024.docx"), Some(second));
+        tx.update_document(first, document("Наказ 01.02.2024.docx", "оновлений текст")).unwrap();
+        tx.remove_document(second).unwrap();
+        assert!(tx.remove_document(second).is_err());
+        tx.commit().unwrap();
+
+        // Скасована транзакція не створює нового покоління
+        let mut tx = manager.begin().unwrap();
+        tx.add_document(document("Наказ 03.03.2024.docx", "скасований"));
+        tx.abort();
+        assert_eq!(IndexManifest::load(&manager.documents_index_path).unwrap().generation, 2);
+
+        let (doc_index, inv_index) = manager.load_active_indices();
+        let (doc_index, inv_index) = (doc_index.unwrap(), inv_index.unwrap());
+        assert_eq!(doc_index.total_documents, 1);
+        let slot = doc_index.slot_of(first).unwrap();
+        assert_eq!(inv_index.word_to_docs[&stemmer::stem_word("оновлений")][0].doc_index, slot);
+        assert!(!inv_index.word_to_docs.contains_key(&stemmer::stem_word("скасований")));
+    }
+
+    #[test]
+    fn test_begin_fails_on_corrupt_active_generation() {
+        let dir = TempIndexDir::new("transaction_corrupt");
+        let manager = dir.manager();
+
+        let mut tx = manager.begin().unwrap();
+        tx.add_document(document("Наказ 01.02.2024.docx", "перший наказ"));
+        tx.commit().unwrap();
+
+        // Пошкоджене активне покоління не підміняється порожнім індексом
+        let (active_documents, _) = manager.active_paths();
+        let mut bytes = fs::read(&active_documents).unwrap();
+        bytes.truncate(bytes.len() / 2);
+        fs::write(&active_documents, bytes).unwrap();
+        assert!(matches!(manager.begin(), Err(IndexError::Corrupt(_))));
+        assert_eq!(IndexManifest::load(&manager.documents_index_path).unwrap().generation, 1);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::document_record::{DocumentIndex, DocumentRecord};
+use crate::error::IndexError;
+use crate::index_manifest::sync_file;
+use crate::index_storage::IndexStorage;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Папка (поруч з індексами) з контрольними точками незавершеної першої індексації
+pub const CHECKPOINT_DIR_NAME: &str = "indexing_checkpoint";
+
+/// Після скількох оброблених документів записувати контрольну точку
+pub const DEFAULT_CHECKPOINT_DOCUMENTS: usize = 500;
+
+const CHECKPOINT_META_FILE_NAME: &str = "checkpoint.json";
+
+/// Яку папку індексували: продовжувати можна лише індексацію тієї самої папки
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct CheckpointMeta {
+    folder: String,
+}
+
+/// Контрольні точки першої (повної) індексації великого архіву: кожні segment_size
+/// оброблених документів їхні записи дописуються сегментом (тимчасовий файл + rename).
+/// Після збою чи перезавантаження наступний запуск бере документи з сегментів і парсить
+/// лише решту файлів. Після запису покоління контрольні точки видаляються.
+pub struct IndexingCheckpoint {
+    dir: PathBuf,
+    storage: Arc<dyn IndexStorage>,
+    pub segment_size: usize,
+    pub durable_writes: bool,
+    /// Слоти, оброблені після останнього записаного сегмента
+    pending: Vec<usize>,
+    next_segment: usize,
+}
+
+impl IndexingCheckpoint {
+    pub fn new(dir: impl Into<PathBuf>, storage: Arc<dyn IndexStorage>) -> Self {
+        Self {
+            dir: dir.into(),
+            storage,
+            segment_size: DEFAULT_CHECKPOINT_DOCUMENTS,
+            durable_writes: true,
+            pending: Vec::new(),
+            next_segment: 0,
+        }
+    }
+
+    /// Документи, збережені незавершеною індексацією folder (повторно оброблений файл -
+    /// остання версія). Контрольні точки іншої папки відкидаються.
+    pub fn resume(&mut self, folder: &str) -> Result<Vec<DocumentRecord>, IndexError> {
+        let meta = CheckpointMeta { folder: folder.to_string() };
+        let meta_path = self.dir.join(CHECKPOINT_META_FILE_NAME);
+        let existing: Option<CheckpointMeta> = fs::read_to_string(&meta_path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok());
+
+        self.pending.clear();
+        self.next_segment = 0;
+
+        if existing.as_ref() != Some(&meta) {
+            if existing.is_some() || self.dir.exists() {
+                info!("🧹 Контрольні точки іншої індексації застаріли - видаляємо");
+                self.clear();
+            }
+
+            fs::create_dir_all(&self.dir)
+                .map_err(|e| IndexError::io("Помилка створення папки контрольних точок", e))?;
+            let json = serde_json::to_string_pretty(&meta)
+                .map_err(|e| IndexError::json("Помилка серіалізації опису контрольних точок", e))?;
+            self.storage
+                .write(&meta_path, json.as_bytes())
+                .map_err(|e| IndexError::io("Помилка запису опису контрольних точок", e))?;
+            return Ok(Vec::new());
+        }
+
+        let mut documents: Vec<DocumentRecord> = Vec::new();
+        let mut by_path: HashMap<String, usize> = HashMap::new();
+        loop {
+            let segment_path = self.segment_path(self.next_segment);
+            if !segment_path.exists() {
+                break;
+            }
+
+            // Наступний запис перезапише пошкоджений сегмент
+            let segment = match Self::load_segment(&segment_path) {
+                Ok(segment) => segment,
+                Err(e) => {
+                    warn!("⚠️ {}", e);
+                    break;
+                }
+            };
+
+            for document in segment {
+                match by_path.get(&document.file_path) {
+                    Some(&i) => documents[i] = document,
+                    None => {
+                        by_path.insert(document.file_path.clone(), documents.len());
+                        documents.push(document);
+                    }
+                }
+            }
+            self.next_segment += 1;
+        }
+
+        if !documents.is_empty() {
+            info!(
+                "♻️ Знайдено контрольну точку незавершеної індексації: {} документів у {} сегментах",
+                documents.len(),
+                self.next_segment
+            );
+        }
+        Ok(documents)
+    }
+
+    /// Запам'ятовує оброблений документ; кожні segment_size документів пише сегмент
+    pub fn record(&mut self, index: &DocumentIndex, slot: usize) -> Result<(), IndexError> {
+        self.pending.push(slot);
+        if self.pending.len() >= self.segment_size.max(1) {
+            self.flush(index)?;
+        }
+        Ok(())
+    }
+
+    /// Записує сегмент з документами, обробленими після попереднього
+    pub fn flush(&mut self, index: &DocumentIndex) -> Result<(), IndexError> {
+        let documents: Vec<&DocumentRecord> = self
+            .pending
+            .iter()
+            .filter_map(|&slot| index.documents.get(slot))
+            .filter(|document| !document.deleted)
+            .collect();
+        if documents.is_empty() {
+            self.pending.clear();
+            return Ok(());
+        }
+
+        let segment_path = self.segment_path(self.next_segment);
+        self.write_atomically(&segment_path, &documents)?;
+        info!("💾 Контрольна точка {}: {} документів", self.next_segment + 1, documents.len());
+
+        self.pending.clear();
+        self.next_segment += 1;
+        Ok(())
+    }
+
+    /// Прибирає контрольні точки після запису покоління
+    pub fn clear(&self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+
+    fn segment_path(&self, segment: usize) -> PathBuf {
+        self.dir.join(format!("documents.{}.json", segment))
+    }
+
+    fn write_atomically(&self, path: &Path, documents: &[&DocumentRecord]) -> Result<(), IndexError> {
+        let temp_path = path.with_extension("json.tmp");
+        let json = serde_json::to_vec(documents)
+            .map_err(|e| IndexError::json("Помилка серіалізації контрольної точки", e))?;
+
+        let written = self
+            .storage
+            .write(&temp_path, &json)
+            .map_err(|e| IndexError::io(format!("Помилка запису контрольної точки {}", path.display()), e))
+            .and_then(|_| {
+                if self.durable_writes {
+                    sync_file(self.storage.as_ref(), &temp_path)?;
+                }
+                self.storage
+                    .rename(&temp_path, path)
+                    .map_err(|e| IndexError::io(format!("Помилка перейменування контрольної точки {}", path.display()), e))
+            });
+
+        if written.is_err() {
+            let _ = self.storage.remove_file(&temp_path);
+        }
+        written
+    }
+
+    fn load_segment(path: &Path) -> Result<Vec<DocumentRecord>, IndexError> {
+        let file = fs::File::open(path)
+            .map_err(|e| IndexError::io(format!("Помилка відкриття контрольної точки {}", path.display()), e))?;
+        serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| IndexError::json(format!("Помилка читання контрольної точки {}", path.display()), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+    use crate::index_storage::FsStorage;
+
+    fn document(path: &str, text: &str) -> DocumentRecord {
+        DocumentRecord::from_texts(path, [text])
+    }
+
+    #[test]
+    fn test_checkpoint_resumes_same_folder_only() {
+        let dir = TempIndexDir::new("checkpoint_test");
+        let new_checkpoint = || {
+            let mut checkpoint = IndexingCheckpoint::new(&*dir, Arc::new(FsStorage));
+            checkpoint.segment_size = 2;
+            checkpoint.durable_writes = false;
+            checkpoint
+        };
+
+        let mut checkpoint = new_checkpoint();
+        assert!(checkpoint.resume("cache").unwrap().is_empty());
+
+        let mut index = DocumentIndex::new();
+        for (path, text) in [("cache/1.docx", "перший"), ("cache/2.docx", "другий"), ("cache/3.docx", "третій")] {
+            let slot = index.push_document(document(path, text));
+            checkpoint.record(&index, slot).unwrap();
+        }
+        // Файл змінився вже після контрольної точки - в наступному сегменті нова версія
+        index.replace_document(0, document("cache/1.docx", "виправлений"));
+        checkpoint.record(&index, 0).unwrap();
+
+        // "Збій" після двох сегментів: новий запуск бере документи з обох
+        let mut resumed = new_checkpoint();
+        let documents = resumed.resume("cache").unwrap();
+        let summary: Vec<(&str, &str)> =
+            documents.iter().map(|d| (d.file_path.as_str(), d.content[0].as_str())).collect();
+        assert_eq!(summary, vec![("cache/1.docx", "виправлений"), ("cache/2.docx", "другий"), ("cache/3.docx", "третій")]);
+
+        // Нові сегменти дописуються після збережених
+        resumed.record(&index, 1).unwrap();
+        resumed.flush(&index).unwrap();
+        assert!(dir.join("documents.2.json").exists());
+
+        // Індексація іншої папки починається з нуля
+        assert!(new_checkpoint().resume("other").unwrap().is_empty());
+        assert!(!dir.join("documents.0.json").exists());
+
+        new_checkpoint().clear();
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,362 @@
+use crate::config::Config;
+use crate::document_record::DocumentIndex;
+use crate::index_manifest::{file_sha256, unix_now, IndexManifest};
+use crate::inverted_index::InvertedIndex;
+use crate::search_engine::SearchEngine;
+use chrono::{DateTime, Local};
+use log::{info, warn};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Як часто виконувати фонову самоперевірку
+pub(crate) const INTEGRITY_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Затримка першої перевірки, щоб не конкурувати із запуском та першою індексацією
+pub(crate) const INTEGRITY_CHECK_START_DELAY: Duration = Duration::from_secs(600);
+
+/// Скільки випадкових записів інвертованого індексу перевіряти за раз
+pub(crate) const DEFAULT_SAMPLE_SIZE: usize = 500;
+
+/// Частка невідповідностей у вибірці, після якої піднімається тривога
+pub(crate) const DEFAULT_ALERT_THRESHOLD: f64 = 0.01;
+
+/// Змінна середовища з адресою webhook для тривог
+pub const ALERT_WEBHOOK_ENV: &str = "INTEGRITY_ALERT_WEBHOOK";
+
+/// Скільки прикладів невідповідностей зберігати в статусі
+const MAX_REPORTED_MISMATCHES: usize = 10;
+
+/// Результат перевірки випадкової вибірки записів інвертованого індексу
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SampleResult {
+    pub sampled: usize,
+    pub mismatches: usize,
+    pub examples: Vec<String>,
+}
+
+impl SampleResult {
+    pub fn mismatch_ratio(&self) -> f64 {
+        if self.sampled == 0 {
+            0.0
+        } else {
+            self.mismatches as f64 / self.sampled as f64
+        }
+    }
+
+    fn record(&mut self, problem: String) {
+        self.mismatches += 1;
+        if self.examples.len() < MAX_REPORTED_MISMATCHES {
+            self.examples.push(problem);
+        }
+    }
+}
+
+/// Стан останньої самоперевірки (віддається через /api/health)
+#[derive(Serialize, Debug, Clone)]
+pub struct IntegrityStatus {
+    pub healthy: bool,
+    pub checked_at: Option<u64>,
+    pub generation: Option<u64>,
+    pub sample: SampleResult,
+    /// None - контрольних сум у маніфесті немає (покоління записане старою версією)
+    pub checksums_ok: Option<bool>,
+    pub alert: Option<String>,
+}
+
+impl Default for IntegrityStatus {
+    fn default() -> Self {
+        Self {
+            healthy: true,
+            checked_at: None,
+            generation: None,
+            sample: SampleResult::default(),
+            checksums_ok: None,
+            alert: None,
+        }
+    }
+}
+
+/// Простий генератор псевдовипадкових чисел (xorshift) - для вибірки достатньо
+struct SampleRng(u64);
+
+impl SampleRng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+}
+
+/// Перевіряє випадкові записи інвертованого індексу: документ існує, параграф існує
+/// і справді містить слово. Записи на tombstone-слоти пропускаються (їх прибирає ущільнення).
+pub fn sample_postings(
+    doc_index: &DocumentIndex,
+    inv_index: &InvertedIndex,
+    sample_size: usize,
+    seed: u64,
+) -> SampleResult {
+    let mut result = SampleResult::default();
+    let words: Vec<&String> = inv_index.word_to_docs.keys().collect();
+    if words.is_empty() {
+        return result;
+    }
+
+    let mut rng = SampleRng::new(seed);
+    for _ in 0..sample_size {
+        let word = words[rng.below(words.len())];
+        let postings = &inv_index.word_to_docs[word];
+        if postings.is_empty() {
+            continue;
+        }
+        let posting = &postings[rng.below(postings.len())];
+
+        let Some(document) = doc_index.documents.get(posting.doc_index) else {
+            result.sampled += 1;
+            result.record(format!("'{}' -> неіснуючий документ {}", word, posting.doc_index));
+            continue;
+        };
+        if document.deleted {
+            continue;
+        }
+
+        result.sampled += 1;
+        let problem = posting.paragraph_positions.iter().find_map(|&position| {
+            match document.content.get(position) {
+                None => Some(format!(
+                    "'{}' -> {}: параграфа {} немає",
+                    word, document.file_name, position
+                )),
+                Some(paragraph) if !InvertedIndex::extract_words(paragraph).contains(word) => {
+                    Some(format!(
+                        "'{}' -> {}: параграф {} не містить слова",
+                        word, document.file_name, position
+                    ))
+                }
+                Some(_) => None,
+            }
+        });
+
+        if let Some(problem) = problem {
+            result.record(problem);
+        }
+    }
+
+    result
+}
+
+/// Фонова низькопріоритетна самоперевірка індексів: вибірка записів інвертованого
+/// індексу проти вмісту документів та контрольні суми файлів активного покоління.
+/// При перевищенні порогу - тривога в журнал, на webhook і в статус здоров'я.
+pub struct IntegrityMonitor {
+    documents_index_path: String,
+    search_engine: Arc<SearchEngine>,
+    status: Arc<Mutex<IntegrityStatus>>,
+    pub interval: Duration,
+    pub start_delay: Duration,
+    pub sample_size: usize,
+    pub alert_threshold: f64,
+    pub webhook_url: Option<String>,
+    /// Перезавантажена конфігурація: інтервал, вибірка і поріг оновлюються між перевірками
+    config_updates: Option<watch::Receiver<Config>>,
+}
+
+impl IntegrityMonitor {
+    pub fn new(
+        documents_index_path: &str,
+        search_engine: Arc<SearchEngine>,
+        status: Arc<Mutex<IntegrityStatus>>,
+    ) -> Self {
+        Self {
+            documents_index_path: documents_index_path.to_string(),
+            search_engine,
+            status,
+            interval: INTEGRITY_CHECK_INTERVAL,
+            start_delay: INTEGRITY_CHECK_START_DELAY,
+            sample_size: DEFAULT_SAMPLE_SIZE,
+            alert_threshold: DEFAULT_ALERT_THRESHOLD,
+            webhook_url: std::env::var(ALERT_WEBHOOK_ENV).ok().filter(|url| !url.trim().is_empty()),
+            config_updates: None,
+        }
+    }
+
+    pub fn with_config_updates(mut self, updates: watch::Receiver<Config>) -> Self {
+        self.config_updates = Some(updates);
+        self
+    }
+
+    pub fn start(self) {
+        tokio::spawn(async move {
+            tokio::time::sleep(self.start_delay).await;
+            let mut monitor = Arc::new(self);
+
+            loop {
+                let check = Arc::clone(&monitor);
+                // Перевірка читає файли і рахує хеші - виконуємо поза потоками веб-сервера
+                if let Err(e) = tokio::task::spawn_blocking(move || check.run_check()).await {
+                    warn!("⚠️ Фонова перевірка цілісності аварійно завершилася: {}", e);
+                }
+
+                // Перевірку завершено, монітор знову не спільний - застосовуємо нові параметри
+                if let Some(monitor) = Arc::get_mut(&mut monitor) {
+                    monitor.apply_config_updates();
+                }
+
+                tokio::time::sleep(monitor.interval).await;
+            }
+        });
+    }
+
+    fn apply_config_updates(&mut self) {
+        let Some(updates) = self.config_updates.as_mut() else { return };
+        if !updates.has_changed().unwrap_or(false) {
+            return;
+        }
+
+        let config = updates.borrow_and_update();
+        self.interval = Duration::from_secs(config.integrity.interval_secs);
+        self.sample_size = config.integrity.sample_size;
+        self.alert_threshold = config.integrity.alert_threshold;
+    }
+
+    /// Одна самоперевірка; оновлює статус і за потреби піднімає тривогу
+    pub fn run_check(&self) {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+
+        let (generation, sample) = self.search_engine.with_indices(|generation, doc_index, inv_index| {
+            let sample = inv_index
+                .map(|inv_index| sample_postings(doc_index, inv_index, self.sample_size, seed))
+                .unwrap_or_default();
+            (generation, sample)
+        });
+
+        let checksums_ok = self.verify_checksums();
+
+        let mut problems = Vec::new();
+        if sample.mismatch_ratio() > self.alert_threshold {
+            problems.push(format!(
+                "невідповідностей у вибірці: {} з {} ({:.1}%)",
+                sample.mismatches,
+                sample.sampled,
+                sample.mismatch_ratio() * 100.0
+            ));
+        }
+        if checksums_ok == Some(false) {
+            problems.push("контрольні суми файлів активного покоління не збігаються з маніфестом".to_string());
+        }
+
+        let alert = (!problems.is_empty()).then(|| problems.join("; "));
+        let status = IntegrityStatus {
+            healthy: alert.is_none(),
+            checked_at: Some(unix_now()),
+            generation,
+            sample,
+            checksums_ok,
+            alert,
+        };
+
+        let time_str = Local::now().format("%H:%M:%S").to_string();
+        let was_healthy = match self.status.lock() {
+            Ok(mut current) => std::mem::replace(&mut *current, status.clone()).healthy,
+            Err(_) => true,
+        };
+
+        match &status.alert {
+            Some(alert) => {
+                info!("🚨 [{time_str}] Самоперевірка індексів: {}", alert);
+                for example in &status.sample.examples {
+                    info!("   - {}", example);
+                }
+                // Повідомляємо лише при переході в несправний стан, щоб не засипати тривогами
+                if was_healthy {
+                    self.send_webhook(&status);
+                }
+            }
+            None => info!(
+                "✅ [{time_str}] Самоперевірка індексів: {} записів перевірено, проблем не виявлено",
+                status.sample.sampled
+            ),
+        }
+    }
+
+    /// Порівнює SHA-256 файлів активного покоління з маніфестом
+    fn verify_checksums(&self) -> Option<bool> {
+        let manifest = IndexManifest::load(&self.documents_index_path)?;
+        let snapshot = manifest.active_snapshot()?;
+
+        let pairs = [
+            (&snapshot.documents_index, &snapshot.documents_sha256),
+            (&snapshot.inverted_index, &snapshot.inverted_sha256),
+        ];
+
+        let mut verified = false;
+        for (path, expected) in pairs {
+            let Some(expected) = expected else { continue };
+            verified = true;
+            if file_sha256(path).ok().as_ref() != Some(expected) {
+                return Some(false);
+            }
+        }
+
+        verified.then_some(true)
+    }
+
+    fn send_webhook(&self, status: &IntegrityStatus) {
+        let Some(url) = &self.webhook_url else { return };
+
+        let payload = serde_json::json!({
+            "event": "index_integrity_alert",
+            "host": hostname(),
+            "time": DateTime::<Local>::from(std::time::SystemTime::now()).to_rfc3339(),
+            "status": status,
+        });
+
+        match ureq::post(url).timeout(Duration::from_secs(10)).send_json(payload) {
+            Ok(_) => info!("📨 Тривогу надіслано на webhook"),
+            Err(e) => warn!("⚠️ Не вдалося надіслати тривогу на webhook: {}", e),
+        }
+    }
+}
+
+pub(crate) fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::fs::read_to_string("/etc/hostname").map(|h| h.trim().to_string()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document_record::DocumentRecord;
+
+    fn document(path: &str, text: &str) -> DocumentRecord {
+        DocumentRecord::from_texts(path, [text])
+    }
+
+    #[test]
+    fn test_sample_detects_postings_not_matching_content() {
+        let mut doc_index = DocumentIndex::new();
+        doc_index.push_document(document("a.docx", "перший наказ"));
+        doc_index.push_document(document("b.docx", "другий рапорт"));
+        let inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+
+        let healthy = sample_postings(&doc_index, &inv_index, 100, 42);
+        assert_eq!(healthy.sampled, 100);
+        assert_eq!(healthy.mismatches, 0);
+
+        // Вміст документа змінився, а інвертований індекс - ні
+        doc_index.documents[1].content = vec!["зовсім інший текст".to_string()];
+        let broken = sample_postings(&doc_index, &inv_index, 100, 42);
+        assert!(broken.mismatches > 0);
+        assert!(broken.mismatch_ratio() > DEFAULT_ALERT_THRESHOLD);
+    }
+}
@@ -1,36 +1,161 @@
+use log::{error, info, warn};
 use serde::{Serialize, Deserialize};
 use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::time::Instant;
 use crate::document_record::{DocumentRecord, DocumentIndex};
-use crate::search_engine::SearchMode;
+use crate::error::IndexError;
+use crate::index_checksum;
+use crate::index_manifest::sibling_path;
+use crate::inverted_segments::{SegmentHeader, MAX_CHAIN_LAYERS};
 use crate::stemmer;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Слово тексту або імені файлу (апострофи прибираються вже після виділення)
+static WORD_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\p{L}\p{N}']+").unwrap());
+
+fn deadline_passed(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct InvertedIndex {
     // Мапа: слово -> список документів з позиціями
     pub word_to_docs: HashMap<String, Vec<DocPosition>>,
     pub total_documents: usize,
+    /// Покоління, з яким записано файл (0 - старий файл без номера), див. GenerationStamped
+    #[serde(default, skip_serializing)]
+    pub generation: u64,
+    /// Відсортовані слова word_to_docs для пошуку за початком слова ("пол*"); будується
+    /// перед публікацією покоління (build_word_dictionary) і скидається при змінах індексу
+    #[serde(skip)]
+    sorted_words: Vec<String>,
+    /// Слова імен файлів -> слоти документів (запит "filename:123"): номер наказу часто є
+    /// лише в імені файлу. Імена є в індексі документів, тож поле не записується у файл, а
+    /// будується перед публікацією покоління (build_name_index)
+    #[serde(skip)]
+    name_to_docs: HashMap<String, Vec<usize>>,
+    /// Заголовок файлу-сегмента (None - повний індекс); після завантаження шари вже
+    /// накладено, тож у пам'яті індекс завжди повний. Див. inverted_segments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment: Option<SegmentHeader>,
 }
 
+/// Термін запиту, що закінчується на "*", - початок слова: збігається з усіма словами
+/// індексу з цим початком
+pub const PREFIX_WILDCARD: char = '*';
+
+/// Найкоротший початок слова для "*": коротший знаходив би тисячі слів
+pub const MIN_PREFIX_CHARS: usize = 3;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DocPosition {
     pub doc_index: usize,
     pub paragraph_positions: Vec<usize>,
 }
 
+impl Default for InvertedIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl InvertedIndex {
     pub fn new() -> Self {
         Self {
             word_to_docs: HashMap::new(),
             total_documents: 0,
+            generation: 0,
+            sorted_words: Vec::new(),
+            name_to_docs: HashMap::new(),
+            segment: None,
+        }
+    }
+
+    /// Будує словник для пошуку за початком слова. Без нього "пол*" перебирає всі слова
+    pub fn build_word_dictionary(&mut self) {
+        self.sorted_words = self.word_to_docs.keys().cloned().collect();
+        self.sorted_words.sort_unstable();
+    }
+
+    /// Будує індекс слів імен файлів живих документів document_index
+    pub fn build_name_index(&mut self, document_index: &DocumentIndex) {
+        self.name_to_docs.clear();
+        for (slot, document) in document_index.documents.iter().enumerate().filter(|(_, d)| !d.deleted) {
+            for word in Self::extract_name_words(&document.file_name) {
+                let slots = self.name_to_docs.entry(word).or_default();
+                if slots.last() != Some(&slot) {
+                    slots.push(slot);
+                }
+            }
+        }
+    }
+
+    /// Слоти документів у slots, в імені файлу яких є всі слова name_terms ("12*" - слово
+    /// з таким початком)
+    pub fn name_documents(&self, name_terms: &[String], slots: Range<usize>) -> HashSet<usize> {
+        let mut documents: Option<HashSet<usize>> = None;
+        for term in name_terms {
+            let term_slots: HashSet<usize> = match term.strip_suffix(PREFIX_WILDCARD) {
+                Some(prefix) => self
+                    .name_to_docs
+                    .iter()
+                    .filter(|(word, _)| word.starts_with(prefix))
+                    .flat_map(|(_, slots)| slots.iter().copied())
+                    .collect(),
+                None => self.name_to_docs.get(term).into_iter().flatten().copied().collect(),
+            };
+            let term_slots = term_slots.into_iter().filter(|slot| slots.contains(slot));
+            documents = Some(match documents {
+                Some(documents) => term_slots.filter(|slot| documents.contains(slot)).collect(),
+                None => term_slots.collect(),
+            });
+        }
+        documents.unwrap_or_default()
+    }
+
+    /// Слова індексу, що починаються з prefix (діапазон відсортованого словника)
+    pub fn words_with_prefix(&self, prefix: &str) -> Vec<&String> {
+        if self.sorted_words.len() == self.word_to_docs.len() {
+            let start = self.sorted_words.partition_point(|word| word.as_str() < prefix);
+            self.sorted_words[start..].iter().take_while(|word| word.starts_with(prefix)).collect()
+        } else {
+            self.word_to_docs.keys().filter(|word| word.starts_with(prefix)).collect()
+        }
+    }
+
+    /// Підказки для введення запиту: до limit слів індексу з початком prefix, спершу ті,
+    /// що трапляються в більшій кількості документів (з видаленими до ущільнення)
+    pub fn suggest_words(&self, prefix: &str, limit: usize) -> Vec<(&String, usize)> {
+        let mut words: Vec<(&String, usize)> = self
+            .words_with_prefix(prefix)
+            .into_iter()
+            .map(|word| (word, self.word_to_docs.get(word).map_or(0, Vec::len)))
+            .collect();
+        words.sort_unstable_by(|(a, a_docs), (b, b_docs)| b_docs.cmp(a_docs).then_with(|| a.cmp(b)));
+        words.truncate(limit);
+        words
+    }
+
+    /// Списки документів терміна запиту: одного слова або, для "пол*", усіх слів з цим початком
+    fn term_postings(&self, term: &str) -> Vec<&Vec<DocPosition>> {
+        match term.strip_suffix(PREFIX_WILDCARD) {
+            Some(prefix) => self
+                .words_with_prefix(prefix)
+                .into_iter()
+                .filter_map(|word| self.word_to_docs.get(word))
+                .collect(),
+            None => self.word_to_docs.get(term).into_iter().collect(),
         }
     }
 
     pub fn update_incremental(&mut self, document_index: &DocumentIndex, changed_doc_indices: &[usize]) {
-        println!("🚀 Інкрементне оновлення інвертованого індексу...");
-        println!("📄 Оновлюємо {} документів", changed_doc_indices.len());
+        info!("🚀 Інкрементне оновлення інвертованого індексу...");
+        info!("📄 Оновлюємо {} документів", changed_doc_indices.len());
 
         if changed_doc_indices.is_empty() {
-            println!("ℹ️  Немає документів для оновлення інвертованого індексу");
+            info!("ℹ️  Немає документів для оновлення інвертованого індексу");
             return;
         }
 
@@ -47,128 +172,49 @@ impl InvertedIndex {
             if let Some(document) = document_index.documents.get(doc_idx) {
                 let added_count = self.add_document_to_index_with_count(doc_idx, document);
                 actually_added += added_count;
-                println!("📝 Додано {} записів для документа {}", added_count, doc_idx);
+                info!("📝 Додано {} записів для документа {}", added_count, doc_idx);
             } else {
-                println!("⚠️  Документ з індексом {} не знайдено в document_index", doc_idx);
+                warn!("⚠️  Документ з індексом {} не знайдено в document_index", doc_idx);
             }
         }
 
         // Оновлюємо загальну кількість документів
-        self.total_documents = document_index.documents.len();
+        self.total_documents = document_index.live_count();
 
-        println!("✅ Інкрементне оновлення завершено: видалено {} записів, додано {}", actually_removed, actually_added);
+        info!("✅ Інкрементне оновлення завершено: видалено {} записів, додано {}", actually_removed, actually_added);
     }
 
-    pub fn build_incremental(existing_index: Option<Self>, document_index: &DocumentIndex, new_or_changed_docs: &[usize]) -> Self {
-        let mut inverted_index = existing_index.unwrap_or_else(|| InvertedIndex::new());
-
-        if new_or_changed_docs.is_empty() {
-            println!("🚀 Немає нових або змінених документів, індекс залишається незмінним");
-            inverted_index.total_documents = document_index.documents.len();
-            return inverted_index;
-        }
-
-        println!("🔧 build_incremental: обробка {} документів для оновлення індексу", new_or_changed_docs.len());
-
-        // Якщо це перший раз (існуючий індекс порожній), додаємо всі документи
-        if inverted_index.word_to_docs.is_empty() {
-            println!("📝 Створення нового індексу з нуля...");
-            for &doc_idx in new_or_changed_docs {
-                if let Some(document) = document_index.documents.get(doc_idx) {
-                    let added_count = inverted_index.add_document_to_index_with_count(doc_idx, document);
-                    println!("➕ Додано {} записів для документа {} (новий індекс)", added_count, doc_idx);
-                }
-            }
-        } else {
-            // Інкрементне оновлення існуючого індексу
-            inverted_index.update_incremental(document_index, new_or_changed_docs);
-        }
-
-        inverted_index.total_documents = document_index.documents.len();
-        inverted_index
-    }
-
-    pub fn remove_deleted_documents_by_paths(&mut self, deleted_file_paths: &[String], document_index: &DocumentIndex) {
-        if deleted_file_paths.is_empty() {
-            return;
-        }
-
-        println!("🗑️  Видалення {} документів з інвертованого індексу...", deleted_file_paths.len());
-
-        // Знаходимо поточні індекси для видалених файлів в оновленому документному індексі
-        let mut deleted_indices = Vec::new();
-        for deleted_path in deleted_file_paths {
-            // Шукаємо чи є цей файл ще в документному індексі
-            // Якщо так, то знаходимо його індекс для видалення з інвертованого індексу
-            for (doc_idx, document) in document_index.documents.iter().enumerate() {
-                if document.file_path == *deleted_path {
-                    deleted_indices.push(doc_idx);
-                    break;
-                }
-            }
-        }
-
-        // Видаляємо записи з інвертованого індексу використовуючи поточні індекси
-        for &doc_idx in &deleted_indices {
-            self.remove_document_from_index(doc_idx);
-        }
-
-        println!("✅ Видалення з інвертованого індексу завершено");
-    }
-
-    /// Видаляє записи для видалених документів з інвертованого індексу
-    /// ВАЖЛИВО: deleted_indices - це індекси ДО видалення з document_index
-    /// Після видалення коригує індекси всіх інших документів (зсуває вниз)
-    pub fn remove_deleted_documents(&mut self, deleted_indices: &[usize]) {
-        if deleted_indices.is_empty() {
-            return;
-        }
-
-        println!("🗑️  Видалення {} документів з інвертованого індексу...", deleted_indices.len());
-
-        // Видаляємо записи для кожного видаленого документа
-        for &doc_idx in deleted_indices {
-            self.remove_document_from_index(doc_idx);
-        }
-
-        // Після видалення документів потрібно оновити індекси у всіх записах
-        // оскільки видалення зміщує індекси документів в document_index
-        self.reindex_after_deletions(deleted_indices);
-
-        println!("✅ Видалення з інвертованого індексу завершено");
-    }
-
-    fn reindex_after_deletions(&mut self, deleted_indices: &[usize]) {
-        // Сортуємо індекси видалених документів у зворотному порядку
-        let mut sorted_deleted: Vec<usize> = deleted_indices.to_vec();
-        sorted_deleted.sort_by(|a, b| b.cmp(a));
-
-        // Оновлюємо індекси для всіх документів
-        for doc_positions in self.word_to_docs.values_mut() {
-            for doc_pos in doc_positions.iter_mut() {
-                let original_idx = doc_pos.doc_index;
-                let mut new_idx = original_idx;
+    /// Перенумеровує документи після ущільнення індексу документів:
+    /// slot_map[старий слот] = новий слот, None - документ видалено.
+    /// Повертає кількість видалених записів.
+    pub fn remap_documents(&mut self, slot_map: &[Option<usize>]) -> usize {
+        let mut removed_entries = 0;
+        self.sorted_words.clear();
 
-                // Рахуємо скільки документів було видалено перед цим індексом
-                for &deleted_idx in &sorted_deleted {
-                    if deleted_idx < original_idx {
-                        new_idx -= 1;
+        self.word_to_docs.retain(|_, doc_positions| {
+            doc_positions.retain_mut(|doc_pos| {
+                match slot_map.get(doc_pos.doc_index).copied().flatten() {
+                    Some(new_slot) => {
+                        doc_pos.doc_index = new_slot;
+                        true
+                    }
+                    None => {
+                        removed_entries += 1;
+                        false
                     }
                 }
+            });
+            !doc_positions.is_empty()
+        });
 
-                doc_pos.doc_index = new_idx;
-            }
-        }
-    }
-
-    fn remove_document_from_index(&mut self, doc_idx: usize) {
-        self.remove_document_from_index_with_count(doc_idx);
+        removed_entries
     }
 
     fn remove_document_from_index_with_count(&mut self, doc_idx: usize) -> usize {
         // Проходимо по всіх словах і видаляємо посилання на цей документ
         let mut words_to_remove = Vec::new();
         let mut removed_entries = 0;
+        self.sorted_words.clear();
 
         for (word, doc_positions) in self.word_to_docs.iter_mut() {
             let original_len = doc_positions.len();
@@ -191,18 +237,19 @@ impl InvertedIndex {
         }
 
         if removed_entries > 0 {
-            println!("🧹 Видалено {} записів документа {} з інвертованого індексу", removed_entries, doc_idx);
+            info!("🧹 Видалено {} записів документа {} з інвертованого індексу", removed_entries, doc_idx);
         }
 
         removed_entries
     }
 
-    fn add_document_to_index(&mut self, doc_idx: usize, document: &DocumentRecord) {
+    pub(crate) fn add_document_to_index(&mut self, doc_idx: usize, document: &DocumentRecord) {
         self.add_document_to_index_with_count(doc_idx, document);
     }
 
     fn add_document_to_index_with_count(&mut self, doc_idx: usize, document: &DocumentRecord) -> usize {
         let mut added_entries = 0;
+        self.sorted_words.clear();
 
         for (para_idx, paragraph) in document.content.iter().enumerate() {
             let words = Self::extract_words(paragraph);
@@ -210,7 +257,7 @@ impl InvertedIndex {
             for word in words {
                 let entry = self.word_to_docs
                     .entry(word)
-                    .or_insert_with(Vec::new);
+                    .or_default();
 
                 // Перевіряємо чи є вже цей документ
                 if let Some(doc_pos) = entry.iter_mut().find(|dp| dp.doc_index == doc_idx) {
@@ -233,117 +280,170 @@ impl InvertedIndex {
         added_entries
     }
 
-    pub fn search_fast(&self, query_words: &[String], document_index: &DocumentIndex, mode: &SearchMode) -> Vec<(usize, Vec<usize>)> {
+    /// Документи-кандидати зі слотами в slots (частина індексу для режиму пошуку) і позиції
+    /// абзаців, де трапляються слова запиту. None - deadline минув раніше, ніж кандидатів
+    /// зібрано повністю (search.query_timeout_ms)
+    pub fn search_fast(
+        &self,
+        query_words: &[String],
+        slots: Range<usize>,
+        deadline: Option<Instant>,
+    ) -> Option<Vec<(usize, Vec<usize>)>> {
         if query_words.is_empty() {
-            return Vec::new();
-        }
-
-        let total_docs = document_index.documents.len();
-        let (start_index, end_index) = match mode {
-            SearchMode::Quick => {
-                // Беремо ОСТАННІ 170 файлів (найновіші), бо нові додаються в кінець
-                let start = if total_docs > 170 { total_docs - 170 } else { 0 };
-                (start, total_docs)
-            },
-            SearchMode::Remaining => {
-                // Беремо всі файли ДО останніх 170 (найстаріші)
-                let end = if total_docs > 170 { total_docs - 170 } else { 0 };
-                (0, end)
-            },
-            SearchMode::Full => (0, total_docs),
-        };
+            return Some(Vec::new());
+        }
 
-        // ОПТИМІЗАЦІЯ 1: Знаходимо слово з найменшою кількістю документів для першого фільтру
-        let mut min_word_count = usize::MAX;
-        let mut best_first_word_idx = 0;
-
-        for (idx, word) in query_words.iter().enumerate() {
-            if let Some(doc_positions) = self.word_to_docs.get(word) {
-                let filtered_count = doc_positions.iter()
-                    .filter(|dp| dp.doc_index >= start_index && dp.doc_index < end_index)
-                    .count();
-                if filtered_count < min_word_count {
-                    min_word_count = filtered_count;
-                    best_first_word_idx = idx;
+        // Документи і абзаци кожного терміна ("пол*" - об'єднання всіх слів з цим початком)
+        let mut term_docs: Vec<HashMap<usize, HashSet<usize>>> = Vec::with_capacity(query_words.len());
+        for word in query_words {
+            let mut docs: HashMap<usize, HashSet<usize>> = HashMap::new();
+            // Початок слова може розгорнутися в тисячі слів - час перевіряється на кожному
+            for doc_positions in self.term_postings(word) {
+                if deadline_passed(deadline) {
+                    return None;
+                }
+                for doc_pos in doc_positions.iter().filter(|dp| slots.contains(&dp.doc_index)) {
+                    docs.entry(doc_pos.doc_index).or_default().extend(&doc_pos.paragraph_positions);
                 }
-            } else {
-                return Vec::new(); // Якщо якесь слово відсутнє, результат порожній
             }
+            if docs.is_empty() {
+                return Some(Vec::new()); // Якщо якесь слово відсутнє, результат порожній
+            }
+            term_docs.push(docs);
         }
 
-        // Починаємо з найрідшого слова
-        let first_word = &query_words[best_first_word_idx];
-        let mut candidate_docs: HashMap<usize, HashSet<usize>> = HashMap::new();
+        // ОПТИМІЗАЦІЯ: починаємо з найрідшого терміна, далі - в порядку зростання кількості документів
+        term_docs.sort_by_key(|docs| docs.len());
+        let mut term_docs = term_docs.into_iter();
+        let mut candidate_docs = term_docs.next().unwrap_or_default();
 
-        if let Some(doc_positions) = self.word_to_docs.get(first_word) {
-            for doc_pos in doc_positions.iter().filter(|dp| dp.doc_index >= start_index && dp.doc_index < end_index) {
-                candidate_docs.insert(doc_pos.doc_index, doc_pos.paragraph_positions.iter().cloned().collect());
+        for docs_with_current_word in term_docs {
+            if deadline_passed(deadline) {
+                return None;
             }
-        }
+            // Ранній вихід якщо перетину немає
+            candidate_docs.retain(|doc_idx, positions| {
+                if let Some(current_positions) = docs_with_current_word.get(doc_idx) {
+                    // Об'єднуємо позиції параграфів (Union)
+                    positions.extend(current_positions);
+                    true
+                } else {
+                    false
+                }
+            });
 
-        if candidate_docs.is_empty() {
-            return Vec::new();
+            if candidate_docs.is_empty() {
+                return Some(Vec::new()); // Ранній вихід якщо немає кандидатів
+            }
         }
 
-        // ОПТИМІЗАЦІЯ 2: Обробляємо інші слова в порядку зростання кількості документів
-        let mut other_words: Vec<_> = query_words.iter().enumerate()
-            .filter(|(idx, _)| *idx != best_first_word_idx)
-            .map(|(_, word)| word)
+        // Конвертуємо назад у Vec і сортуємо
+        let final_results: Vec<(usize, Vec<usize>)> = candidate_docs.into_iter()
+            .map(|(doc_idx, positions)| {
+                let mut pos_vec: Vec<usize> = positions.into_iter().collect();
+                pos_vec.sort_unstable();
+                (doc_idx, pos_vec)
+            })
             .collect();
 
-        other_words.sort_by_key(|word| {
-            self.word_to_docs.get(*word).map_or(0, |docs|
-                docs.iter().filter(|dp| dp.doc_index >= start_index && dp.doc_index < end_index).count()
-            )
-        });
+        Some(final_results)
+    }
 
-        // ОПТИМІЗАЦІЯ 3: Використовуємо HashSet для швидшого пересічення
-        for word in other_words {
-            if let Some(doc_positions) = self.word_to_docs.get(word) {
-                let docs_with_current_word: HashMap<usize, HashSet<usize>> = doc_positions.iter()
-                    .filter(|dp| dp.doc_index >= start_index && dp.doc_index < end_index)
-                    .map(|dp| (dp.doc_index, dp.paragraph_positions.iter().cloned().collect()))
-                    .collect();
-
-                // ОПТИМІЗАЦІЯ 4: Ранній вихід якщо перетину немає
-                candidate_docs.retain(|doc_idx, positions| {
-                    if let Some(current_positions) = docs_with_current_word.get(doc_idx) {
-                        // Об'єднуємо позиції параграфів (Union)
-                        positions.extend(current_positions);
-                        true
-                    } else {
-                        false
-                    }
-                });
+    /// Кандидати запиту з операторами: перетин слів кожної групи (search_fast), об'єднання
+    /// груп OR (позиції абзаців зливаються) і різниця з документами, де є слова з мінусом.
+    /// name_groups (паралельно groups) - слова імені файлу групи; документ групи лише з
+    /// ними потрапляє до кандидатів без позицій абзаців. None - deadline минув під час
+    /// об'єднання чи різниці: недоперевірені кандидати могли б містити слова з мінусом
+    pub fn search_boolean(
+        &self,
+        groups: &[Vec<String>],
+        name_groups: &[Vec<String>],
+        excluded: &[String],
+        slots: Range<usize>,
+        deadline: Option<Instant>,
+    ) -> Option<Vec<(usize, Vec<usize>)>> {
+        if let ([group], [name_group], []) = (groups, name_groups, excluded) {
+            if name_group.is_empty() {
+                return self.search_fast(group, slots, deadline);
+            }
+        }
 
-                if candidate_docs.is_empty() {
-                    return Vec::new(); // Ранній вихід якщо немає кандидатів
+        // Об'єднання (Union)
+        let mut candidate_docs: HashMap<usize, HashSet<usize>> = HashMap::new();
+        for (group, name_group) in groups.iter().zip(name_groups) {
+            if name_group.is_empty() {
+                for (doc_idx, positions) in self.search_fast(group, slots.clone(), deadline)? {
+                    candidate_docs.entry(doc_idx).or_default().extend(positions);
+                }
+                continue;
+            }
+            let named = self.name_documents(name_group, slots.clone());
+            if group.is_empty() {
+                for doc_idx in named {
+                    candidate_docs.entry(doc_idx).or_default();
+                }
+                continue;
+            }
+            for (doc_idx, positions) in self.search_fast(group, slots.clone(), deadline)? {
+                if named.contains(&doc_idx) {
+                    candidate_docs.entry(doc_idx).or_default().extend(positions);
                 }
-            } else {
-                return Vec::new();
             }
         }
 
-        // Конвертуємо назад у Vec і сортуємо
-        let final_results: Vec<(usize, Vec<usize>)> = candidate_docs.into_iter()
+        // Різниця (Difference)
+        for word in excluded {
+            if candidate_docs.is_empty() {
+                break;
+            }
+            for doc_positions in self.term_postings(word) {
+                if deadline_passed(deadline) {
+                    return None;
+                }
+                for doc_pos in doc_positions {
+                    candidate_docs.remove(&doc_pos.doc_index);
+                }
+            }
+        }
+
+        let candidates = candidate_docs
+            .into_iter()
             .map(|(doc_idx, positions)| {
                 let mut pos_vec: Vec<usize> = positions.into_iter().collect();
                 pos_vec.sort_unstable();
                 (doc_idx, pos_vec)
             })
             .collect();
-
-        final_results
+        Some(candidates)
     }
 
-    fn extract_words(text: &str) -> Vec<String> {
-        use regex::Regex;
-        use once_cell::sync::Lazy;
-
-        static WORD_REGEX: Lazy<Regex> = Lazy::new(|| {
-            Regex::new(r"[\p{L}\p{N}']+").unwrap()
-        });
+    /// Дешева оцінка, скільки документів зі слотами в slots містять усі слова запиту:
+    /// довжина списку найрідшого слова без перетину з іншими (тобто з запасом).
+    /// accept - фільтр документа за слотом, перевіряється лише для найрідшого слова
+    pub fn estimate_documents(&self, query_words: &[String], slots: Range<usize>, accept: impl Fn(usize) -> bool) -> usize {
+        let mut rarest: Option<Vec<&Vec<DocPosition>>> = None;
+        let mut rarest_count = usize::MAX;
+        for word in query_words {
+            let postings = self.term_postings(word);
+            if postings.is_empty() {
+                return 0;
+            }
+            let count = postings.iter().flat_map(|p| p.iter()).filter(|dp| slots.contains(&dp.doc_index)).count();
+            if count < rarest_count {
+                rarest_count = count;
+                rarest = Some(postings);
+            }
+        }
+        rarest.map_or(0, |postings| {
+            postings
+                .iter()
+                .flat_map(|p| p.iter())
+                .filter(|dp| slots.contains(&dp.doc_index) && accept(dp.doc_index))
+                .count()
+        })
+    }
 
+    pub(crate) fn extract_words(text: &str) -> Vec<String> {
         WORD_REGEX
             .find_iter(text)
             .map(|m| {
@@ -354,114 +454,86 @@ impl InvertedIndex {
             .collect()
     }
 
+    /// Слова імені файлу без розширення: у нижньому регістрі, без апострофів і без
+    /// стемінгу - номери ("5", "123") важливі навіть з однієї цифри
+    pub fn extract_name_words(file_name: &str) -> Vec<String> {
+        let stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+        WORD_REGEX
+            .find_iter(&stem.to_lowercase())
+            .map(|m| m.as_str().replace('\'', ""))
+            .filter(|word| !word.is_empty())
+            .collect()
+    }
 
-    pub fn save_to_file(&self, path: &str) -> Result<(), String> {
-        use std::path::Path;
-        use std::fs;
 
-        // Атомарне збереження через тимчасовий файл
-        let temp_path = format!("{}.tmp", path);
-        let backup_path = format!("{}.backup", path);
+    pub fn get_stats(&self) -> (usize, usize) {
+        (self.total_documents, self.word_to_docs.len())
+    }
 
-        // Створюємо резервну копію існуючого файлу якщо він є
-        if Path::new(path).exists() {
-            fs::copy(path, &backup_path)
-                .map_err(|e| format!("Помилка створення резервної копії інвертованого індексу: {}", e))?;
+    pub fn load_from_file(path: &str) -> Result<Self, IndexError> {
+        if !std::path::Path::new(path).exists() {
+            return Err(IndexError::NotFound(format!("Інвертований індекс не знайдено: {}", path)));
         }
 
-        // Зберігаємо в тимчасовий файл
-        let json = serde_json::to_string(self)
-            .map_err(|e| format!("Помилка серіалізації інвертованого індексу: {}", e))?;
-
-        fs::write(&temp_path, json)
-            .map_err(|e| {
-                // Видаляємо пошкоджений тимчасовий файл
-                let _ = fs::remove_file(&temp_path);
-                format!("Помилка запису тимчасового файлу інвертованого індексу: {}", e)
-            })?;
-
-        // Атомарно переміщуємо тимчасовий файл на місце основного
-        fs::rename(&temp_path, path)
-            .map_err(|e| {
-                // Якщо переміщення не вдалося, спробуємо відновити з резервної копії
-                if Path::new(&backup_path).exists() {
-                    let _ = fs::rename(&backup_path, path);
-                }
-                format!("Помилка переміщення тимчасового файлу інвертованого індексу: {}", e)
-            })?;
-
-        // Видаляємо резервну копію після успішного збереження
-        if Path::new(&backup_path).exists() {
-            let _ = fs::remove_file(&backup_path);
+        let idx = Self::try_load_file(path)
+            .map_err(|e| IndexError::Corrupt(format!("Не вдалося завантажити інвертований індекс {}: {}", path, e)))?;
+        if !Self::validate_index(&idx) {
+            return Err(IndexError::Corrupt(format!("Інвертований індекс пошкоджений: {}", path)));
         }
-
-        Ok(())
+        Ok(idx)
     }
 
-    pub fn get_stats(&self) -> (usize, usize) {
-        (self.total_documents, self.word_to_docs.len())
+    fn try_load_file(path: &str) -> Result<Self, IndexError> {
+        Self::try_load_layer(path, 0)
     }
 
-    pub fn load_from_file(path: &str) -> Result<Self, String> {
-        use std::path::Path;
-        use std::fs;
+    /// Файл індексу; для сегмента - разом з нижчими шарами, на які він посилається
+    fn try_load_layer(path: &str, depth: usize) -> Result<Self, IndexError> {
+        if depth >= MAX_CHAIN_LAYERS {
+            return Err(IndexError::Corrupt(format!("Забагато шарів інвертованого індексу під {}", path)));
+        }
 
-        let backup_path = format!("{}.backup", path);
+        let mut index: Self = index_checksum::read_verified_json(path, "Помилка десеріалізації")?;
 
-        // Спочатку пробуємо завантажити основний файл
-        let index = Self::try_load_file(path);
+        let Some(header) = index.segment.clone() else {
+            return Ok(index);
+        };
 
-        match index {
-            Ok(idx) => {
-                // Перевіряємо цілісність індексу
-                if Self::validate_index(&idx) {
-                    return Ok(idx);
-                } else {
-                    println!("⚠️  Основний інвертований індекс пошкоджений, спробуємо резервну копію...");
-                }
-            }
-            Err(e) => {
-                println!("⚠️  Помилка завантаження основного інвертованого індексу: {}", e);
-                println!("🔄 Спробуємо резервну копію...");
-            }
-        }
+        // Без нижчого шару сегмент - лише частина індексу, тож помилка шару - помилка файлу
+        let base_path = sibling_path(path, &header.base).to_string_lossy().to_string();
+        let mut base = Self::try_load_layer(&base_path, depth + 1).map_err(|e| {
+            IndexError::Corrupt(format!("Нижчий шар {} сегмента {} не завантажено: {}", header.base, path, e))
+        })?;
+        index.segment = None;
+        base.apply_segment(&header, index);
+        Ok(base)
+    }
 
-        // Якщо основний файл пошкоджений, пробуємо резервну копію
-        if Path::new(&backup_path).exists() {
-            match Self::try_load_file(&backup_path) {
-                Ok(backup_idx) => {
-                    if Self::validate_index(&backup_idx) {
-                        println!("✅ Завантажено інвертований індекс з резервної копії");
-                        // Відновлюємо основний файл з резервної копії
-                        if let Err(e) = fs::copy(&backup_path, path) {
-                            println!("⚠️  Не вдалося відновити основний файл інвертованого індексу: {}", e);
-                        }
-                        return Ok(backup_idx);
-                    } else {
-                        println!("❌ Резервна копія інвертованого індексу також пошкоджена");
-                    }
-                }
-                Err(e) => {
-                    println!("❌ Помилка завантаження резервної копії інвертованого індексу: {}", e);
-                }
-            }
-        }
+    /// Накладає сегмент: записи його слотів замінюють записи цих слотів з нижчих шарів
+    pub fn apply_segment(&mut self, header: &SegmentHeader, segment: InvertedIndex) {
+        let slots: HashSet<usize> = header.slots.iter().copied().collect();
+        self.sorted_words.clear();
 
-        Err("Не вдалося завантажити інвертований індекс: всі файли пошкоджені або відсутні".to_string())
-    }
+        self.word_to_docs.retain(|_, positions| {
+            positions.retain(|p| !slots.contains(&p.doc_index));
+            !positions.is_empty()
+        });
 
-    fn try_load_file(path: &str) -> Result<Self, String> {
-        let content = std::fs::read_to_string(path)
-            .map_err(|e| format!("Помилка читання файлу: {}", e))?;
+        // Обидва списки вже впорядковані за слотом, тож зливаються без повторного сортування
+        for (word, positions) in segment.word_to_docs {
+            let entry = self.word_to_docs.entry(word).or_default();
+            *entry = merge_by_doc_index(std::mem::take(entry), positions);
+        }
 
-        serde_json::from_str(&content)
-            .map_err(|e| format!("Помилка десеріалізації: {}", e))
+        self.total_documents = segment.total_documents;
+        self.generation = segment.generation;
+        self.segment = None;
     }
 
     fn validate_index(index: &Self) -> bool {
         // Базові перевірки цілісності (м'якіші)
         if index.word_to_docs.is_empty() && index.total_documents > 100 {
-            println!("❌ Невідповідність інвертованого індексу: total_documents > 100, але word_to_docs порожній");
+            error!("❌ Невідповідність інвертованого індексу: total_documents > 100, але word_to_docs порожній");
             return false;
         }
 
@@ -490,15 +562,15 @@ impl InvertedIndex {
 
         // Репортуємо проблеми, але не блокуємо збереження
         if !invalid_words.is_empty() {
-            println!("⚠️  Знайдено {} невалідних слів в інвертованому індексі (будуть виправлені)", invalid_words.len());
+            warn!("⚠️  Знайдено {} невалідних слів в інвертованому індексі (будуть виправлені)", invalid_words.len());
         }
 
         if !empty_doc_lists.is_empty() {
-            println!("⚠️  Знайдено {} слів з порожніми списками документів", empty_doc_lists.len());
+            warn!("⚠️  Знайдено {} слів з порожніми списками документів", empty_doc_lists.len());
         }
 
         if !empty_positions.is_empty() {
-            println!("⚠️  Знайдено {} записів з порожніми позиціями", empty_positions.len());
+            warn!("⚠️  Знайдено {} записів з порожніми позиціями", empty_positions.len());
         }
 
         // Дозволяємо збереження, навіть якщо є проблеми
@@ -508,6 +580,7 @@ impl InvertedIndex {
     // Функція для очищення індексу від невалідних записів
     pub fn cleanup(&mut self) -> usize {
         let mut removed_count = 0;
+        self.sorted_words.clear();
 
         // Видаляємо невалідні слова та порожні записи
         self.word_to_docs.retain(|word, doc_positions| {
@@ -530,7 +603,7 @@ impl InvertedIndex {
         });
 
         if removed_count > 0 {
-            println!("🧹 Очищено {} невалідних записів з інвертованого індексу", removed_count);
+            info!("🧹 Очищено {} невалідних записів з інвертованого індексу", removed_count);
         }
 
         removed_count
@@ -595,7 +668,7 @@ impl InvertedIndex {
         }
 
         if duplicates_removed > 0 {
-            println!("🧹 Видалено {} дублікатів записів з інвертованого індексу", duplicates_removed);
+            info!("🧹 Видалено {} дублікатів записів з інвертованого індексу", duplicates_removed);
         }
 
         duplicates_removed
@@ -603,12 +676,12 @@ impl InvertedIndex {
 
     // Функція для повного перебудування індексу
     pub fn rebuild_from_scratch(document_index: &DocumentIndex) -> Self {
-        println!("🔄 Повне перебудування інвертованого індексу...");
+        info!("🔄 Повне перебудування інвертованого індексу...");
 
         let mut inverted_index = InvertedIndex::new();
-        inverted_index.total_documents = document_index.documents.len();
+        inverted_index.total_documents = document_index.live_count();
 
-        for (doc_idx, document) in document_index.documents.iter().enumerate() {
+        for (doc_idx, document) in document_index.documents.iter().enumerate().filter(|(_, d)| !d.deleted) {
             inverted_index.add_document_to_index(doc_idx, document);
         }
 
@@ -617,8 +690,155 @@ impl InvertedIndex {
         inverted_index.remove_duplicate_entries();
 
         let (docs, words) = inverted_index.get_stats();
-        println!("✅ Перебудування завершено: {} документів, {} слів", docs, words);
+        info!("✅ Перебудування завершено: {} документів, {} слів", docs, words);
 
         inverted_index
     }
 }
+
+/// Зливає два впорядковані за doc_index списки входжень
+fn merge_by_doc_index(lower: Vec<DocPosition>, upper: Vec<DocPosition>) -> Vec<DocPosition> {
+    if lower.is_empty() {
+        return upper;
+    }
+    let mut merged = Vec::with_capacity(lower.len() + upper.len());
+    let mut upper = upper.into_iter().peekable();
+    for position in lower {
+        while let Some(next) = upper.next_if(|p| p.doc_index < position.doc_index) {
+            merged.push(next);
+        }
+        merged.push(position);
+    }
+    merged.extend(upper);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(path: &str, content: &[&str]) -> DocumentRecord {
+        DocumentRecord::from_texts(path, content.iter().copied())
+    }
+
+    #[test]
+    fn test_prefix_terms_match_all_inflections() {
+        let mut doc_index = DocumentIndex::new();
+        for (path, text) in [
+            ("a.docx", "поліцейського Петренка"),
+            ("b.docx", "полковника Іваненка"),
+            ("c.docx", "поле Петренка"),
+            ("d.docx", "наказ"),
+        ] {
+            doc_index.push_document(document(path, &[text]));
+        }
+        let mut inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+        let prefix_words = |inv_index: &InvertedIndex| {
+            let mut words: Vec<String> = inv_index.words_with_prefix("пол").into_iter().cloned().collect();
+            words.sort();
+            words
+        };
+
+        // Без словника - перебір усіх слів, зі словником - той самий діапазон
+        let unsorted = prefix_words(&inv_index);
+        inv_index.build_word_dictionary();
+        assert_eq!(prefix_words(&inv_index), unsorted);
+        assert_eq!(unsorted.len(), 3);
+
+        let docs = |inv_index: &InvertedIndex, words: &[&str]| {
+            let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+            let mut docs: Vec<usize> = inv_index.search_fast(&words, 0..4, None).unwrap().into_iter().map(|(doc, _)| doc).collect();
+            docs.sort();
+            docs
+        };
+        let petrenko = stemmer::stem_word("Петренка");
+        assert_eq!(docs(&inv_index, &["пол*"]), vec![0, 1, 2]);
+        assert_eq!(docs(&inv_index, &["пол*", &petrenko]), vec![0, 2]);
+        assert_eq!(inv_index.estimate_documents(&["пол*".to_string()], 0..4, |slot| slot != 1), 2);
+
+        // Минулий deadline зупиняє розгортання початку слова, об'єднання і різницю
+        let expired = Some(Instant::now());
+        let groups = vec![vec!["пол*".to_string()], vec!["наказ".to_string()]];
+        let excluded = vec![petrenko.clone()];
+        assert!(inv_index.search_fast(&["пол*".to_string()], 0..4, expired).is_none());
+        assert!(inv_index.search_boolean(&groups, &[vec![], vec![]], &excluded, 0..4, expired).is_none());
+        assert_eq!(inv_index.search_boolean(&groups, &[vec![], vec![]], &excluded, 0..4, None).unwrap().len(), 2);
+
+        // Зміна індексу скидає словник: нове слово знаходиться перебором
+        doc_index.push_document(document("e.docx", &["полонених"]));
+        inv_index.update_incremental(&doc_index, &[4]);
+        assert_eq!(prefix_words(&inv_index).len(), 4);
+    }
+
+    #[test]
+    fn test_suggestions_ranked_by_documents() {
+        let mut doc_index = DocumentIndex::new();
+        for (path, text) in [
+            ("a.docx", "полковника Петренка"),
+            ("b.docx", "полковнику Іваненку"),
+            ("c.docx", "поле"),
+            ("d.docx", "полонених"),
+        ] {
+            doc_index.push_document(document(path, &[text]));
+        }
+        let mut inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+        inv_index.build_word_dictionary();
+
+        let polkovnyk = stemmer::stem_word("полковника");
+        let suggestions = inv_index.suggest_words("пол", 2);
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0], (&polkovnyk, 2));
+        // Однаково поширені - за абеткою
+        assert_eq!(suggestions[1].0, &stemmer::stem_word("поле").min(stemmer::stem_word("полонених")));
+        assert!(inv_index.suggest_words("рапорт", 10).is_empty());
+    }
+
+    #[test]
+    fn test_tombstone_then_compact_keeps_postings_consistent() {
+        let mut doc_index = DocumentIndex::new();
+        for (path, text) in [("a.docx", "перший наказ"), ("b.docx", "другий наказ"), ("c.docx", "третій наказ")] {
+            doc_index.push_document(document(path, &[text]));
+        }
+        let c_id = doc_index.documents[2].doc_id;
+        let mut inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+
+        // Видалення не чіпає інвертований індекс і не зсуває слоти
+        doc_index.tombstone(1);
+        assert_eq!(doc_index.live_count(), 2);
+        assert_eq!(doc_index.slot_of(c_id), Some(2));
+
+        let slot_map = doc_index.compact();
+        assert_eq!(slot_map, vec![Some(0), None, Some(1)]);
+        inv_index.remap_documents(&slot_map);
+
+        // Документ "c" переїхав у слот 1, але зберіг ідентифікатор
+        assert_eq!(doc_index.slot_of(c_id), Some(1));
+        let word = stemmer::stem_word("третій");
+        assert_eq!(inv_index.word_to_docs[&word][0].doc_index, 1);
+        assert!(!inv_index.word_to_docs.contains_key(&stemmer::stem_word("другий")));
+    }
+
+    #[test]
+    fn test_reorder_remap_matches_rebuild() {
+        let mut doc_index = DocumentIndex::new();
+        for (path, text) in [("a.docx", "перший наказ"), ("b.docx", "другий наказ"), ("c.docx", "третій")] {
+            doc_index.push_document(document(path, &[text]));
+        }
+        let mut inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+
+        let slot_map = doc_index.reorder(&[2, 0, 1]);
+        inv_index.remap_documents(&slot_map);
+
+        let rebuilt = InvertedIndex::rebuild_from_scratch(&doc_index);
+        let postings = |index: &InvertedIndex| {
+            let mut all: Vec<(String, usize, Vec<usize>)> = index
+                .word_to_docs
+                .iter()
+                .flat_map(|(w, docs)| docs.iter().map(|d| (w.clone(), d.doc_index, d.paragraph_positions.clone())))
+                .collect();
+            all.sort();
+            all
+        };
+        assert_eq!(postings(&inv_index), postings(&rebuilt));
+    }
+}
@@ -0,0 +1,244 @@
+use crate::document_record::DocumentIndex;
+use crate::error::IndexError;
+use crate::index_manifest::sync_file;
+use crate::index_storage::IndexStorage;
+use crate::inverted_index::InvertedIndex;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Папка (поруч з індексами) з сегментами незавершеного перебудування
+pub const REBUILD_DIR_NAME: &str = "inverted_rebuild";
+
+/// Скільки документів обробляти в одному сегменті
+pub const DEFAULT_SEGMENT_SIZE: usize = 500;
+
+const REBUILD_META_FILE_NAME: &str = "rebuild.json";
+
+/// Для якого індексу документів записано сегменти: продовжувати можна лише той самий
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct RebuildMeta {
+    source_sha256: String,
+    segment_size: usize,
+    documents: usize,
+}
+
+/// Підсумок перебудування
+#[derive(Debug, Default)]
+pub struct RebuildStats {
+    pub segments_total: usize,
+    /// Сегменти, збережені незавершеним попереднім запуском
+    pub segments_resumed: usize,
+}
+
+impl std::fmt::Display for RebuildStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "сегментів: {}, продовжено з попереднього запуску: {}",
+            self.segments_total, self.segments_resumed
+        )
+    }
+}
+
+/// Поетапне перебудування інвертованого індексу: документи обробляються сегментами,
+/// кожен сегмент одразу записується на диск (тимчасовий файл + rename). Перерваний
+/// запуск продовжується з першого незаписаного сегмента. Активне покоління при цьому
+/// не змінюється - результат потрапляє в нове покоління лише через перемикання маніфесту.
+pub struct SegmentedRebuild<'a> {
+    dir: PathBuf,
+    storage: &'a dyn IndexStorage,
+    pub segment_size: usize,
+    pub durable_writes: bool,
+}
+
+impl<'a> SegmentedRebuild<'a> {
+    pub fn new(dir: impl Into<PathBuf>, storage: &'a dyn IndexStorage) -> Self {
+        Self {
+            dir: dir.into(),
+            storage,
+            segment_size: DEFAULT_SEGMENT_SIZE,
+            durable_writes: true,
+        }
+    }
+
+    /// Будує інвертований індекс для doc_index. source_sha256 - контрольна сума файлу
+    /// індексу документів, з якого його завантажено (щоб не змішати сегменти різних індексів).
+    pub fn run(&self, doc_index: &DocumentIndex, source_sha256: &str) -> Result<(InvertedIndex, RebuildStats), IndexError> {
+        let meta = RebuildMeta {
+            source_sha256: source_sha256.to_string(),
+            segment_size: self.segment_size.max(1),
+            documents: doc_index.documents.len(),
+        };
+        self.prepare(&meta)?;
+
+        let chunks: Vec<&[_]> = doc_index.documents.chunks(meta.segment_size).collect();
+        let mut stats = RebuildStats {
+            segments_total: chunks.len(),
+            ..Default::default()
+        };
+
+        info!(
+            "🔄 Поетапне перебудування інвертованого індексу: {} документів, {} сегментів",
+            meta.documents,
+            chunks.len()
+        );
+
+        for (segment, documents) in chunks.iter().enumerate() {
+            let segment_path = self.segment_path(segment);
+            if segment_path.exists() {
+                stats.segments_resumed += 1;
+                continue;
+            }
+
+            let first_slot = segment * meta.segment_size;
+            let mut partial = InvertedIndex::new();
+            for (offset, document) in documents.iter().enumerate().filter(|(_, d)| !d.deleted) {
+                partial.add_document_to_index(first_slot + offset, document);
+            }
+
+            self.write_atomically(&segment_path, &partial)?;
+            info!("   📦 Сегмент {}/{} записано", segment + 1, chunks.len());
+        }
+
+        // Злиття: сегменти йдуть за зростанням слотів, тому записи лишаються впорядкованими
+        let mut inverted_index = InvertedIndex::new();
+        for segment in 0..chunks.len() {
+            let partial = Self::load_segment(&self.segment_path(segment))?;
+            for (word, postings) in partial.word_to_docs {
+                inverted_index.word_to_docs.entry(word).or_default().extend(postings);
+            }
+        }
+
+        inverted_index.total_documents = doc_index.live_count();
+        inverted_index.cleanup();
+        inverted_index.remove_duplicate_entries();
+
+        Ok((inverted_index, stats))
+    }
+
+    /// Прибирає сегменти після успішного перемикання на нове покоління
+    pub fn clear(&self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+
+    /// Створює папку сегментів; сегменти іншого індексу документів відкидаються
+    fn prepare(&self, meta: &RebuildMeta) -> Result<(), IndexError> {
+        let meta_path = self.dir.join(REBUILD_META_FILE_NAME);
+        let existing: Option<RebuildMeta> = fs::read_to_string(&meta_path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok());
+
+        if existing.as_ref() == Some(meta) {
+            info!("♻️ Знайдено сегменти незавершеного перебудування - продовжуємо");
+            return Ok(());
+        }
+
+        if existing.is_some() || self.dir.exists() {
+            info!("🧹 Сегменти попереднього перебудування застаріли - видаляємо");
+            self.clear();
+        }
+
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| IndexError::io("Помилка створення папки перебудування", e))?;
+
+        let json = serde_json::to_string_pretty(meta)
+            .map_err(|e| IndexError::json("Помилка серіалізації опису перебудування", e))?;
+        self.storage
+            .write(&meta_path, json.as_bytes())
+            .map_err(|e| IndexError::io("Помилка запису опису перебудування", e))
+    }
+
+    fn segment_path(&self, segment: usize) -> PathBuf {
+        self.dir.join(format!("segment.{}.json", segment))
+    }
+
+    fn write_atomically(&self, path: &Path, partial: &InvertedIndex) -> Result<(), IndexError> {
+        let temp_path = path.with_extension("json.tmp");
+        let json = serde_json::to_vec(partial)
+            .map_err(|e| IndexError::json("Помилка серіалізації сегмента", e))?;
+
+        let written = self
+            .storage
+            .write(&temp_path, &json)
+            .map_err(|e| IndexError::io(format!("Помилка запису сегмента {}", path.display()), e))
+            .and_then(|_| {
+                if self.durable_writes {
+                    sync_file(self.storage, &temp_path)?;
+                }
+                self.storage
+                    .rename(&temp_path, path)
+                    .map_err(|e| IndexError::io(format!("Помилка перейменування сегмента {}", path.display()), e))
+            });
+
+        if written.is_err() {
+            let _ = self.storage.remove_file(&temp_path);
+        }
+        written
+    }
+
+    fn load_segment(path: &Path) -> Result<InvertedIndex, IndexError> {
+        let file = fs::File::open(path)
+            .map_err(|e| IndexError::io(format!("Помилка відкриття сегмента {}", path.display()), e))?;
+        serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| IndexError::json(format!("Помилка читання сегмента {}", path.display()), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+    use crate::document_record::DocumentRecord;
+    use crate::index_storage::testing::{Fault, FaultyStorage};
+    use crate::index_storage::FsStorage;
+
+    fn postings(index: &InvertedIndex) -> Vec<(String, usize, Vec<usize>)> {
+        let mut all: Vec<(String, usize, Vec<usize>)> = index
+            .word_to_docs
+            .iter()
+            .flat_map(|(w, docs)| docs.iter().map(|d| (w.clone(), d.doc_index, d.paragraph_positions.clone())))
+            .collect();
+        all.sort();
+        all
+    }
+
+    #[test]
+    fn test_interrupted_rebuild_resumes_and_matches_full_rebuild() {
+        let dir = TempIndexDir::new("rebuild_test");
+
+        let mut doc_index = DocumentIndex::new();
+        for i in 0..7 {
+            let text = format!("наказ номер{} рапорт", i);
+            doc_index.push_document(DocumentRecord::from_texts(&format!("{}.docx", i), [text]));
+        }
+        doc_index.tombstone(4);
+
+        // Збій на третьому сегменті: перші два лишаються на диску
+        let faulty = FaultyStorage::new().fail(Fault::Create, "segment.2");
+        let mut rebuild = SegmentedRebuild::new(&*dir, &faulty);
+        rebuild.segment_size = 3;
+        rebuild.durable_writes = false;
+        assert!(rebuild.run(&doc_index, "abc").is_err());
+        assert!(dir.join("segment.1.json").exists());
+        assert!(!dir.join("segment.2.json.tmp").exists());
+
+        let mut rebuild = SegmentedRebuild::new(&*dir, &FsStorage);
+        rebuild.segment_size = 3;
+        rebuild.durable_writes = false;
+        let (inv_index, stats) = rebuild.run(&doc_index, "abc").unwrap();
+        assert_eq!(stats.segments_total, 3);
+        assert_eq!(stats.segments_resumed, 2);
+        assert_eq!(postings(&inv_index), postings(&InvertedIndex::rebuild_from_scratch(&doc_index)));
+        assert_eq!(inv_index.total_documents, 6);
+
+        // Інший індекс документів - старі сегменти не використовуються
+        let (_, stats) = rebuild.run(&doc_index, "def").unwrap();
+        assert_eq!(stats.segments_resumed, 0);
+
+        rebuild.clear();
+        assert!(!dir.exists());
+    }
+}
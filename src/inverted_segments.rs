@@ -0,0 +1,126 @@
+//! Сегменти інвертованого індексу у файлах поколінь. Інкрементне оновлення, що не зсуває
+//! слоти (без перестановки за датою і ущільнення), записує у файл нового покоління не
+//! весь інвертований індекс, а лише записи змінених слотів - незмінний сегмент поверх
+//! інвертованого файлу попереднього покоління. Завантаження накладає шари від повного
+//! файлу до верхнього сегмента (InvertedIndex::load_from_file), тож для решти програми
+//! покоління, як і раніше, - один файл.
+//!
+//! Ланцюжок сегментів зливається в повний файл новим поколінням: у фоні після оновлення
+//! (AtomicIndexManager::merge_segments, коли сегментів накопичилось max_segments) або
+//! під час самого оновлення, якщо фонове злиття так і не відбулось (удвічі довший ланцюжок).
+//! Повним файлом записують і ущільнення, ремонт та повна індексація.
+//!
+//! Не плутати з сегментами перебудування (inverted_rebuild) - ті є тимчасовими частинами
+//! повного перебудування і в покоління не потрапляють.
+
+use crate::inverted_index::InvertedIndex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Скільки сегментів накопичується поверх повного файлу до фонового злиття
+pub const DEFAULT_MAX_SEGMENTS: usize = 8;
+
+/// Найдовший ланцюжок шарів, який читається (захист від зациклених посилань)
+pub const MAX_CHAIN_LAYERS: usize = 256;
+
+/// Заголовок файлу-сегмента
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SegmentHeader {
+    /// Назва файлу попереднього шару в тій самій папці (повний індекс або старіший сегмент)
+    pub base: String,
+    /// Слоти, записи яких сегмент замінює: їхні записи з нижчих шарів відкидаються
+    pub slots: Vec<usize>,
+}
+
+/// Сегмент із записами слотів slots індексу inverted_index поверх файлу base_path
+pub fn segment_of(inverted_index: &InvertedIndex, slots: &[usize], base_path: &str) -> InvertedIndex {
+    let slots: BTreeSet<usize> = slots.iter().copied().collect();
+
+    let mut segment = InvertedIndex::new();
+    segment.total_documents = inverted_index.total_documents;
+    segment.generation = inverted_index.generation;
+    segment.word_to_docs = inverted_index
+        .word_to_docs
+        .iter()
+        .filter_map(|(word, positions)| {
+            let positions: Vec<_> = positions.iter().filter(|p| slots.contains(&p.doc_index)).cloned().collect();
+            (!positions.is_empty()).then(|| (word.clone(), positions))
+        })
+        .collect();
+    segment.segment = Some(SegmentHeader {
+        base: Path::new(base_path)
+            .file_name()
+            .map_or_else(|| base_path.to_string(), |name| name.to_string_lossy().to_string()),
+        slots: slots.into_iter().collect(),
+    });
+    segment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+    use crate::index_builder::IndexBuilder;
+    use crate::index_compression::IndexCompression;
+    use crate::index_manifest::GenerationStamped;
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    fn postings(inverted_index: &InvertedIndex) -> BTreeMap<String, Vec<(usize, Vec<usize>)>> {
+        inverted_index
+            .word_to_docs
+            .iter()
+            .map(|(word, positions)| {
+                let positions = positions.iter().map(|p| (p.doc_index, p.paragraph_positions.clone())).collect();
+                (word.clone(), positions)
+            })
+            .collect()
+    }
+
+    fn write(path: &Path, index: &InvertedIndex, generation: u64) {
+        let bytes = IndexCompression::None.to_vec(&GenerationStamped { generation, index }).unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn test_segment_chain_loads_as_updated_index() {
+        let dir = TempIndexDir::new("inverted_segments");
+
+        let (mut doc_index, mut inverted_index) = IndexBuilder::new()
+            .with_document("Наказ №1 від 10.01.2024.docx", ["Петренку П.П. надати відпустку"])
+            .with_document("Наказ №2 від 11.01.2024.docx", ["Іваненка І.І. відрядити до м. Києва"])
+            .build();
+        let base_path = dir.join("inverted_index.1.json");
+        write(&base_path, &inverted_index, 1);
+
+        // Покоління 2: змінено документ 1; покоління 3: додано документ 2
+        let (updated, _) = IndexBuilder::new()
+            .with_document("Наказ №2 від 11.01.2024.docx", ["Іваненка І.І. надати відпустку"])
+            .with_document("Наказ №3 від 12.01.2024.docx", ["Сидоренку С.С. оголосити подяку"])
+            .build();
+        let mut layer_path = base_path;
+        for (generation, slot, document) in [(2, 1, &updated.documents[0]), (3, 2, &updated.documents[1])] {
+            doc_index.put_document(slot, document.clone());
+            doc_index.recount();
+            inverted_index.update_incremental(&doc_index, &[slot]);
+            inverted_index.remove_duplicate_entries();
+
+            let segment = segment_of(&inverted_index, &[slot], &layer_path.to_string_lossy());
+            assert!(segment.word_to_docs.values().flatten().all(|p| p.doc_index == slot));
+            layer_path = dir.join(format!("inverted_index.{}.json", generation));
+            write(&layer_path, &segment, generation);
+        }
+
+        let loaded = InvertedIndex::load_from_file(&layer_path.to_string_lossy()).unwrap();
+        assert_eq!(loaded.generation, 3);
+        assert!(loaded.segment.is_none());
+        assert_eq!(loaded.total_documents, 3);
+        assert_eq!(postings(&loaded), postings(&inverted_index));
+        assert_eq!(postings(&loaded), postings(&InvertedIndex::rebuild_from_scratch(&doc_index)));
+
+        // Без нижчого шару сегмент не завантажується як неповний індекс
+        fs::remove_file(dir.join("inverted_index.2.json")).unwrap();
+        assert!(InvertedIndex::load_from_file(&layer_path.to_string_lossy()).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! Ядро Blazing Search: парсинг DOCX, індекс документів, інвертований індекс,
+//! пошук і атомарне оновлення індексів. Веб-сервер і команди командного рядка
+//! (бінарник blazing_SEARCH) - тонкий шар поверх цієї бібліотеки.
+
+pub mod annotations;
+pub mod atomic_index_manager;
+pub mod auto_indexer;
+pub mod classifier;
+pub mod config;
+pub mod config_reload;
+pub mod corpus_analytics;
+pub mod document_date;
+pub mod document_diff;
+pub mod document_record;
+pub mod error;
+pub mod failure_alerts;
+pub mod federated_search;
+pub mod docx_parser;
+pub mod folder_processor;
+pub mod highlight;
+pub mod index_backup;
+pub mod index_builder;
+pub mod index_checksum;
+pub mod index_compression;
+pub mod index_delta;
+pub mod index_import;
+pub mod index_manifest;
+pub mod index_paths;
+pub mod index_repair;
+pub mod index_stats;
+pub mod index_storage;
+pub mod index_store;
+pub mod index_transaction;
+pub mod indexing_checkpoint;
+pub mod integrity_monitor;
+pub mod logging;
+pub mod messages;
+pub mod mutation_journal;
+pub mod near_duplicates;
+pub mod pdf_report;
+pub mod permalink;
+pub mod person_names;
+pub mod query_rewrite;
+pub mod regex_query;
+pub mod redaction;
+pub mod relevance_feedback;
+pub mod search_admission;
+pub mod result_cache;
+pub mod result_export;
+pub mod saved_queries;
+pub mod inverted_index;
+pub mod inverted_rebuild;
+pub mod inverted_segments;
+pub mod search_engine;
+pub mod search_etag;
+pub mod search_facets;
+pub mod search_history;
+pub mod service_history;
+pub mod session;
+pub mod shutdown;
+pub mod slow_query_log;
+pub mod sqlite_store;
+pub mod stemmer;
+pub mod sync_recovery;
+pub mod sync_report;
+pub mod visit_tracker;
+
+pub use atomic_index_manager::{AtomicIndexManager, UpdateStats};
+pub use config::Config;
+pub use document_record::{DocumentIndex, DocumentRecord};
+pub use error::{ConfigError, IndexError, ParseError, SearchError, SyncError};
+pub use docx_parser::DocxParser;
+pub use folder_processor::FolderProcessor;
+pub use index_paths::IndexPaths;
+pub use index_transaction::IndexTransaction;
+pub use inverted_index::InvertedIndex;
+pub use search_engine::{SearchEngine, SearchEngineResult, SearchMode};
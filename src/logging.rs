@@ -0,0 +1,137 @@
+//! Журнал роботи: повідомлення ядра йдуть через макроси log (info!, warn!, error!).
+//! У консоль вони виводяться як раніше - лише текст, а у файл журналу (якщо задано
+//! logging.directory) - з часом і рівнем, з ротацією за розміром і добою.
+//! Рівень можна змінити під час роботи (set_level), наприклад через адмін-ендпоінт сервера.
+
+use crate::config::LoggingConfig;
+use flexi_logger::{
+    Age, Cleanup, Criterion, DeferredNow, Duplicate, FileSpec, FlexiLoggerError, LogSpecification, Logger,
+    LoggerHandle, Naming,
+};
+use log::Record;
+use once_cell::sync::OnceCell;
+use std::io::Write;
+use std::sync::Mutex;
+
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+pub const DEFAULT_LOG_FILE_NAME: &str = "blazing_search";
+pub const DEFAULT_LOG_MAX_FILE_SIZE_MB: u64 = 10;
+pub const DEFAULT_LOG_KEEP_FILES: usize = 30;
+
+/// Запущений журнал і його поточна специфікація рівнів
+struct ActiveLogger {
+    handle: LoggerHandle,
+    level: String,
+    /// Журнал пишеться у файл (консоль - лише дублікат)
+    to_file: bool,
+}
+
+/// Handle живе до завершення процесу: з його знищенням журнал зупинився б
+static ACTIVE: OnceCell<Mutex<ActiveLogger>> = OnceCell::new();
+
+/// Запускає журнал (один раз на процес)
+pub fn init(config: &LoggingConfig) -> Result<(), FlexiLoggerError> {
+    let logger = Logger::with(parse_level(&config.level)?).format_for_stdout(console_format);
+
+    let logger = match &config.directory {
+        None => logger.log_to_stdout(),
+        Some(directory) => {
+            let file_spec = FileSpec::default()
+                .directory(directory)
+                .basename(&config.file_name)
+                .suppress_timestamp();
+            let max_size = config.max_file_size_mb * 1024 * 1024;
+            let criterion = if config.rotate_daily {
+                Criterion::AgeOrSize(Age::Day, max_size)
+            } else {
+                Criterion::Size(max_size)
+            };
+
+            logger
+                .log_to_file(file_spec)
+                .format_for_files(file_format)
+                .duplicate_to_stdout(Duplicate::All)
+                .rotate(criterion, Naming::Timestamps, Cleanup::KeepLogFiles(config.keep_files))
+                .append()
+        }
+    };
+
+    let handle = logger.start()?;
+    let _ = ACTIVE.set(Mutex::new(ActiveLogger {
+        handle,
+        level: config.level.clone(),
+        to_file: config.directory.is_some(),
+    }));
+    Ok(())
+}
+
+/// Розбирає рівень ("warn") або специфікацію з модулями ("info,blazing_search::folder_processor=debug")
+pub fn parse_level(level: &str) -> Result<LogSpecification, FlexiLoggerError> {
+    LogSpecification::parse(level)
+}
+
+/// Змінює рівень журналу без перезапуску. Якщо журнал не запущено - лише перевіряє рівень.
+pub fn set_level(level: &str) -> Result<(), FlexiLoggerError> {
+    let spec = parse_level(level)?;
+
+    if let Some(active) = ACTIVE.get() {
+        let mut active = active.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        active.handle.set_new_spec(spec);
+        active.level = level.to_string();
+    }
+    Ok(())
+}
+
+/// Вимикає і знову вмикає вивід журналу в консоль, поки екран зайнятий термінальним
+/// інтерфейсом. Файл журналу (якщо задано) пишеться як і раніше.
+pub fn set_console_output(enabled: bool) {
+    let Some(active) = ACTIVE.get() else { return };
+    let mut active = active.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    if active.to_file {
+        let duplicate = if enabled { Duplicate::All } else { Duplicate::None };
+        let _ = active.handle.adapt_duplication_to_stdout(duplicate);
+    } else if enabled {
+        if let Ok(spec) = parse_level(&active.level) {
+            active.handle.set_new_spec(spec);
+        }
+    } else {
+        active.handle.set_new_spec(LogSpecification::off());
+    }
+}
+
+/// Поточна специфікація рівнів (None - журнал не запущено)
+pub fn current_level() -> Option<String> {
+    let active = ACTIVE.get()?;
+    let active = active.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Some(active.level.clone())
+}
+
+/// Консоль: лише текст повідомлення, як у println! до появи журналу
+fn console_format(w: &mut dyn Write, _now: &mut DeferredNow, record: &Record) -> std::io::Result<()> {
+    write!(w, "{}", record.args())
+}
+
+/// Файл: час, рівень і модуль, щоб записи можна було фільтрувати grep'ом
+fn file_format(w: &mut dyn Write, now: &mut DeferredNow, record: &Record) -> std::io::Result<()> {
+    write!(
+        w,
+        "{} {:<5} [{}] {}",
+        now.format("%Y-%m-%d %H:%M:%S"),
+        record.level(),
+        record.module_path().unwrap_or("-"),
+        record.args()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_specifications() {
+        assert!(parse_level("warn").is_ok());
+        assert!(parse_level("info,blazing_search::folder_processor=debug").is_ok());
+        assert!(parse_level("blazing_search=гучно").is_err());
+    }
+}
@@ -1,123 +1,571 @@
-mod atomic_index_manager;
-mod auto_indexer;
-mod document_record;
-mod docx_parser;
-mod folder_processor;
-mod inverted_index;
-mod search_engine;
-mod stemmer;
+mod cli;
+mod graphql_api;
+mod grpc_service;
+mod service;
+mod telegram_bot;
+mod tui;
 mod web_server;
 
-use atomic_index_manager::AtomicIndexManager;
-use document_record::DocumentIndex;
-use inverted_index::InvertedIndex;
-use search_engine::SearchEngine;
-use std::env;
+use blazing_search::index_stats::{IndexStats, TermReport};
+use blazing_search::near_duplicates::DuplicateReport;
+use blazing_search::sync_recovery::{self, CopyJournal};
+use blazing_search::shutdown::Shutdown;
+use blazing_search::sync_report::{self, SyncReport};
+use blazing_search::config::LoggingConfig;
+use blazing_search::config_reload::ConfigSource;
+use blazing_search::{document_date, index_import, index_repair, logging, messages, stemmer, tr};
+use blazing_search::{AtomicIndexManager, Config, IndexError, SearchEngine, SearchError, SearchMode};
+use clap::Parser;
+use cli::{Cli, Command, SnapshotAction};
+use log::{error, info, warn};
 use std::path::Path;
 
+fn main() {
+    let cli = Cli::parse();
+
+    // blazing_search.toml (або --config <файл>) з профілем, поверх них - параметри командного рядка
+    let mut config = match Config::load(cli.config.as_deref(), cli.profile.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("❌ {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = cli.apply(&mut config) {
+        println!("❌ {}", e);
+        std::process::exit(1);
+    }
+
+    // Перехід у фон - до запуску журналу і tokio: після fork їхні потоки не переносяться
+    if let Command::Serve { daemon: true, .. } = cli.command {
+        if config.logging.directory.is_none() {
+            eprintln!("⚠️ У фоновому режимі консоль недоступна - задайте logging.directory, щоб зберігати журнал");
+        }
+        if let Err(e) = service::daemonize() {
+            eprintln!("❌ Не вдалося перейти у фон: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    run(cli, config);
+}
+
 #[tokio::main]
-async fn main() {
-    let args: Vec<String> = env::args().collect();
+async fn run(cli: Cli, config: Config) {
+    // Журнал: консоль і (якщо задано logging.directory) файл з ротацією
+    if let Err(e) = logging::init(&config.logging) {
+        eprintln!("⚠️ Не вдалося відкрити файл журналу ({}), журнал лише в консоль", e);
+        let console_only = LoggingConfig {
+            directory: None,
+            ..config.logging.clone()
+        };
+        let _ = logging::init(&console_only);
+    }
+    stemmer::set_stemmer(config.search.stemmer);
+    document_date::set_filename_date_formats(&config.indexing.filename_date_formats);
+    messages::set_locale(config.server.locale);
+
+    // Папка з індексами, блокуванням, резервними копіями та звітами
+    if let Err(e) = config.index_paths().ensure_dir() {
+        error!("❌ {}", e);
+        std::process::exit(1);
+    }
+
+    match cli.command {
+        Command::Serve { .. } => start_web_mode(config, shutdown_on_signals(), config_source(&cli)).await,
+        Command::Index { files_from: Some(list), .. } => index_listed_files(&config, &list, &shutdown_on_signals()),
+        Command::Index { files_from: None, .. } => start_cli_mode(&config, &shutdown_on_signals()).await,
+        Command::Search { query, full, limit } => search_from_cli(&config, &query.join(" "), full, limit).await,
+        Command::Tui => search_in_terminal(&config).await,
+        Command::Rebuild { inverted: true, .. } => rebuild_inverted_index(&config),
+        Command::Rebuild { inverted: false, .. } => rebuild_indices(&config),
+        Command::Validate { report } => validate_indices(&config, report.as_deref()),
+        Command::Stats { json } => show_stats(&config, json),
+        Command::Terms { limit, rarest, json } => show_terms(&config, limit, rarest, json),
+        Command::Duplicates { max_distance, json } => show_duplicates(&config, max_distance, json),
+        Command::Repair { dry_run, report } => repair_indices(&config, dry_run, report),
+        Command::Snapshot { action: SnapshotAction::List } => list_snapshots(&config),
+        Command::Rollback { generation } => rollback_to_snapshot(&config, generation),
+        Command::Compact => compact_indices(&config),
+        Command::Import { from } => import_index(&config, &from),
+    }
+}
+
+/// Показує збережені знімки індексів
+fn list_snapshots(config: &Config) {
+    let index_manager = config.index_manager();
+    let (snapshots, active) = index_manager.list_snapshots();
+
+    if snapshots.is_empty() {
+        info!("ℹ️ Знімків індексів ще немає");
+        return;
+    }
+
+    info!("📸 Знімки індексів:");
+    for snapshot in snapshots {
+        let committed = chrono::DateTime::from_timestamp(snapshot.committed_at as i64, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%d.%m.%Y %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let marker = if Some(snapshot.generation) == active { " (активне)" } else { "" };
+
+        info!(
+            "   - покоління {}: {} документів, {}{}",
+            snapshot.generation, snapshot.total_documents, committed, marker
+        );
+    }
+}
+
+/// Ремонтує індекси та записує машинозчитуваний звіт
+/// (за замовчуванням repair_report.json у папці індексів)
+fn repair_indices(config: &Config, dry_run: bool, report_path: Option<String>) {
+    let report_path = report_path.unwrap_or_else(|| config.index_paths().repair_report());
+
+    let index_manager = config.index_manager();
+
+    match index_manager.repair(dry_run) {
+        Ok(report) => {
+            info!("📊 Результат ремонту: {}", report);
+            if !report.has_fixes() {
+                info!("✅ Проблем не знайдено");
+            } else if report.saved {
+                info!("✅ Виправлені індекси збережено як нове покоління");
+            } else {
+                info!("ℹ️ Режим --dry-run: зміни не збережено");
+            }
 
-    // Перевіряємо аргументи командного рядка
-    if args.len() > 1 && args[1] == "web" {
-        start_web_mode().await;
+            match index_repair::write_report(&report_path, &report) {
+                Ok(_) => info!("📄 Звіт збережено: {}", report_path),
+                Err(e) => warn!("⚠️ {}", e),
+            }
+        }
+        Err(e) => {
+            error!("❌ Помилка ремонту: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Перевіряє цілісність індексів без змін; код виходу 1 при проблемах.
+/// report_path додатково зберігає звіт у JSON (для планових перевірок стану).
+fn validate_indices(config: &Config, report_path: Option<&str>) {
+    let index_manager = config.index_manager();
+    let report = index_manager.check_integrity();
+
+    print!("{}", report);
+
+    if let Some(report_path) = report_path {
+        match serde_json::to_string_pretty(&report)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(report_path, json).map_err(|e| e.to_string()))
+        {
+            Ok(_) => info!("📄 Звіт збережено: {}", report_path),
+            Err(e) => warn!("⚠️ Не вдалося зберегти звіт {}: {}", report_path, e),
+        }
+    }
+
+    if report.is_healthy() {
+        info!("✅ Індекси цілі");
     } else {
-        start_cli_mode().await;
+        error!("❌ Виявлено проблеми з індексами (виправлення: blazing_SEARCH repair)");
+        std::process::exit(1);
+    }
+}
+
+/// Примусове ущільнення tombstone-слотів (зазвичай виконується за розкладом під час оновлення).
+/// Ущільнення записує інвертований індекс повним файлом, а без нього сегменти зливаються окремо
+fn compact_indices(config: &Config) {
+    let index_manager = config.index_manager();
+
+    let merged = match index_manager.compact() {
+        Ok(0) => {
+            info!("ℹ️ Видалених документів немає - ущільнення не потрібне");
+            index_manager.merge_segments().map(|delta| delta.map(|delta| delta.generation))
+        }
+        Ok(compacted) => {
+            info!("✅ Прибрано {} видалених документів", compacted);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    };
+
+    match merged {
+        Ok(Some(generation)) => info!("✅ Сегменти інвертованого індексу злито (покоління {})", generation),
+        Ok(None) => {}
+        Err(e) => {
+            error!("❌ Помилка ущільнення: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Імпорт індексу іншого екземпляра: одна транзакція, нове покоління лише при змінах
+fn import_index(config: &Config, from: &Path) {
+    info!("📥 Імпорт індексу з {}", from.display());
+
+    let result = index_import::load_source(from)
+        .and_then(|source| index_import::import_documents(&config.index_manager(), source));
+
+    match result {
+        Ok(stats) => {
+            info!("✅ Імпорт завершено: {}", stats);
+            if stats.has_changes() {
+                print_index_stats(&config.index_manager());
+            }
+        }
+        Err(e) => {
+            error!("❌ Помилка імпорту: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Відкочує індекси до вказаного знімку; код виходу 1, якщо відкат не вдався
+fn rollback_to_snapshot(config: &Config, generation: u64) {
+    let index_manager = config.index_manager();
+
+    match index_manager.rollback_to(generation) {
+        Ok(()) => {
+            let (_, active) = index_manager.list_snapshots();
+            info!(
+                "✅ Відкат завершено, активне покоління: {}",
+                active.map_or_else(|| "невідоме".to_string(), |generation| generation.to_string())
+            );
+        }
+        Err(e) => {
+            error!("❌ Помилка відкату: {}", e);
+            std::process::exit(1);
+        }
     }
 }
 
-async fn start_web_mode() {
-    println!("🔥 Blazing Search - Web Mode");
-    println!("=============================");
+/// Файл конфігурації, за яким стежить сервер: при зміні він перечитується так само,
+/// як при запуску (профіль, змінні середовища, параметри командного рядка)
+fn config_source(cli: &Cli) -> Option<ConfigSource> {
+    let path = Config::file_path(cli.config.as_deref())?;
+    let cli = cli.clone();
+    let load_path = path.clone();
+
+    Some(ConfigSource::new(path, move || {
+        let mut config = Config::load(Some(&load_path), cli.profile.as_deref())?;
+        cli.apply(&mut config)?;
+        Ok(config)
+    }))
+}
+
+/// Сервер та індексація зупиняються за SIGTERM/Ctrl+C у безпечній точці (див. Shutdown);
+/// інші команди короткі й переривають роботу одразу, як і раніше
+fn shutdown_on_signals() -> Shutdown {
+    let shutdown = Shutdown::default();
+    service::handle_signals(shutdown.clone());
+    shutdown
+}
+
+async fn start_web_mode(config: Config, shutdown: Shutdown, config_source: Option<ConfigSource>) {
+    info!("🔥 Blazing Search - Web Mode");
+    info!("=============================");
+
+    // Файл PID живе, доки працює сервер (видаляється при поверненні з функції)
+    let _pid_file = match &config.server.pid_file {
+        Some(path) => match service::PidFile::create(path) {
+            Ok(pid_file) => Some(pid_file),
+            Err(e) => {
+                error!("{}", tr!(PidFileCreateFailed, path.display(), e));
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
 
-    let index_path = "documents_index.json";
-    println!("🔍 Перевірка індексу: {}", index_path);
+    let paths = &config.index_paths();
+    let index_path = paths.documents_index();
+    info!("{}", tr!(IndexDir, paths.dir().display()));
+    info!("{}", tr!(IndexChecking, index_path));
 
     // Якщо індексів немає - створюємо їх автоматично
-    if !Path::new(index_path).exists() {
-        println!("⚠️  Файл індексу не знайдено: {}", index_path);
-        println!("🔧 Створюємо початковий індекс...");
-        println!("");
+    let index_manager = config.index_manager();
+    if !index_manager.has_indices() {
+        warn!("{}", tr!(IndexFileMissing, index_path));
+        info!("{}", tr!(InitialIndexCreating));
 
         // Викликаємо початкову індексацію
-        perform_initial_indexing().await;
+        perform_initial_indexing(&config, &shutdown).await;
+        if shutdown.is_requested() {
+            return;
+        }
 
-        println!("");
-        println!("=============================");
+        info!("=============================");
     }
 
     // Завантажуємо пошуковий движок
-    let mut search_engine = SearchEngine::new();
-
-    if Path::new(index_path).exists() {
-        if let Ok(metadata) = std::fs::metadata(index_path) {
-            println!(
-                "📁 Розмір файлу індексу: {:.2} MB",
-                metadata.len() as f64 / 1_048_576.0
-            );
+    let search_engine = config.search_engine();
+    // Сховище SQLite - один файл з обома індексами
+    let active_index_path = match index_manager.store() {
+        Some(_) => paths.sqlite_index(),
+        None => index_manager.active_paths().0,
+    };
+
+    if index_manager.has_indices() {
+        if let Ok(metadata) = std::fs::metadata(&active_index_path) {
+            let size_mb = format!("{:.2}", metadata.len() as f64 / 1_048_576.0);
+            info!("{}", tr!(IndexFileSize, size_mb));
         }
 
-        println!("⏳ Завантаження індексу...");
-        match search_engine.load_from_file(index_path) {
+        info!("{}", tr!(IndexLoading));
+        match search_engine.reload() {
             Ok(_) => {
                 let (docs, words) = search_engine.get_stats();
-                println!("✅ Завантажено {} документів з {} слів", docs, words);
+                info!("{}", tr!(IndexLoaded, docs, words));
             }
             Err(e) => {
-                println!("❌ Помилка завантаження індексу: {}", e);
-                println!("💡 Спробуйте видалити файли індексів та перезапустити");
+                error!("{}", tr!(IndexLoadFailed, e));
+                info!("{}", tr!(IndexLoadHint));
                 return;
             }
         }
     } else {
-        println!("❌ Не вдалося створити індекс");
-        println!("💡 Перевірте доступ до мережевої папки /mnt/salem-documents/Накази");
+        error!("{}", tr!(IndexCreateFailed));
+        info!("{}", tr!(RemoteFolderHint, config.paths.remote_folder));
         return;
     }
 
+    // Додаткові колекції ([[collections]]) - у тому самому сервері
+    let mut collections = Vec::new();
+    for collection in config.collection_configs().into_iter().skip(1) {
+        collections.push(load_collection(collection, &shutdown).await);
+        if shutdown.is_requested() {
+            return;
+        }
+    }
+
     // Запуск веб-сервера
-    if let Err(e) = web_server::start_web_server(search_engine).await {
-        eprintln!("❌ Помилка запуску сервера: {}", e);
+    if let Err(e) = web_server::start_web_server(search_engine, collections, config.clone(), shutdown, config_source).await {
+        error!("{}", tr!(ServerStartFailed, e));
     }
 }
 
-async fn start_cli_mode() {
-    println!("🔥 Blazing Search - Auto Indexer");
-    println!("================================");
+/// Додаткова колекція для сервера: початкова індексація, якщо індексу ще немає, і завантаження.
+/// Збій не зупиняє сервер - пошук у колекції відповідає 503, доки автоіндексер її не створить
+async fn load_collection(config: Config, shutdown: &Shutdown) -> (Config, SearchEngine) {
+    let name = config.paths.collection_name.clone();
+    let paths = config.index_paths();
+    info!("{}", tr!(CollectionLoading, name, paths.dir().display()));
+    if let Err(e) = paths.ensure_dir() {
+        warn!("{}", tr!(CollectionLoadFailed, name, e));
+    }
+
+    if !config.index_manager().has_indices() {
+        info!("{}", tr!(InitialIndexCreating));
+        perform_initial_indexing(&config, shutdown).await;
+    }
+
+    let search_engine = config.search_engine();
+    match search_engine.reload() {
+        Ok(()) => info!("{}", tr!(CollectionLoaded, name, search_engine.get_stats().0)),
+        Err(e) => warn!("{}", tr!(CollectionLoadFailed, name, e)),
+    }
+    (config, search_engine)
+}
+
+async fn start_cli_mode(config: &Config, shutdown: &Shutdown) {
+    info!("🔥 Blazing Search - Auto Indexer");
+    info!("================================");
 
     // Автоматично запускаємо індексацію папки
-    perform_initial_indexing().await;
+    perform_initial_indexing(config, shutdown).await;
+}
+
+/// Завантажує активне покоління індексів для пошуку з командного рядка або терміналу
+fn load_search_engine(config: &Config) -> SearchEngine {
+    let search_engine = config.search_engine();
+    match search_engine.reload() {
+        Ok(()) => search_engine,
+        Err(SearchError::Index(IndexError::NotFound(e))) => {
+            error!("❌ {}", e);
+            info!("💡 Спочатку створіть індекс командою index");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            error!("❌ Помилка завантаження індексу: {}", e);
+            std::process::exit(1);
+        }
+    }
 }
 
-async fn perform_initial_indexing() {
-    let remote_folder = "/mnt/salem-documents/Накази";
-    // let remote_folder = "C:\\Users\\vladr\\Desktop\\НАКАЗИ\\";
-    let local_cache = "./nakazi_cache";
-    let documents_index_path = "documents_index.json";
-    let inverted_index_path = "inverted_index.json";
+/// Пошук з командного рядка по активному поколінню індексів
+async fn search_from_cli(config: &Config, query: &str, full: bool, limit: usize) {
+    let search_engine = load_search_engine(config);
+
+    let mode = if full { SearchMode::Remaining } else { SearchMode::Quick };
+    let results = match search_engine.search(query, mode, None).await {
+        Ok(results) => results,
+        Err(e) => {
+            error!("❌ Помилка пошуку: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("🔍 Знайдено документів: {}", results.len());
+    for result in results.iter().take(limit) {
+        println!("\n📄 {} ({})", result.file_name, result.file_path);
+        for m in &result.matches {
+            println!("   - [{}] {}", m.position, m.context);
+        }
+    }
+
+    if results.len() > limit {
+        println!("\n… ще {} документів (--limit)", results.len() - limit);
+    }
+}
+
+/// Термінальний інтерфейс пошуку; журнал на цей час пишеться лише у файл (якщо задано)
+async fn search_in_terminal(config: &Config) {
+    let search_engine = load_search_engine(config);
+
+    logging::set_console_output(false);
+    let result = tui::run(&search_engine).await;
+    logging::set_console_output(true);
 
-    println!("🔍 Автоматична індексація папки: {}", remote_folder);
-    println!("📥 Копіювання файлів до локального кешу: {}", local_cache);
-    println!(
+    if let Err(e) = result {
+        error!("❌ Помилка термінального інтерфейсу: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Повна переіндексація з нуля (попереднє покоління лишається знімком для відкату)
+fn rebuild_indices(config: &Config) {
+    info!("🔥 Blazing Search - Full Rebuild");
+    info!("================================");
+
+    if !sync_to_cache(config) {
+        std::process::exit(1);
+    }
+
+    let index_manager = config.index_manager();
+    index_manager.cleanup_temp_files();
+
+    match index_manager.rebuild_from_folder(&config.paths.local_cache) {
+        Ok(stats) => {
+            info!("✅ Повну переіндексацію завершено: {}", stats);
+            print_index_stats(&index_manager);
+        }
+        Err(e) => {
+            error!("❌ Помилка переіндексації: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Індексація лише файлів зі списку ("-" - стандартний вхід), без синхронізації кешу
+/// й обходу папки: для точкового оновлення після ручних виправлень
+fn index_listed_files(config: &Config, list: &Path, shutdown: &Shutdown) {
+    let content = if list == Path::new("-") {
+        std::io::read_to_string(std::io::stdin())
+    } else {
+        std::fs::read_to_string(list)
+    };
+    let files: Vec<String> = match content {
+        Ok(content) => content.lines().map(str::to_string).collect(),
+        Err(e) => {
+            error!("❌ Не вдалося прочитати список файлів {}: {}", list.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    info!("📋 Індексація {} файлів зі списку в {}", files.len(), config.paths.local_cache);
+    let index_manager = config.index_manager().with_shutdown(shutdown.clone());
+    index_manager.cleanup_temp_files();
+
+    match index_manager.index_files(&config.paths.local_cache, &files) {
+        Ok(stats) => {
+            info!("✅ Файли зі списку проіндексовано: {}", stats);
+            print_index_stats(&index_manager);
+            if !stats.errors.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            error!("❌ Помилка індексації файлів зі списку: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Перебудова інвертованого індексу з активного індексу документів під блокуванням
+/// оновлення (не може виконуватися одночасно з автоіндексером)
+fn rebuild_inverted_index(config: &Config) {
+    info!("🔄 Перебудова інвертованого індексу...");
+
+    let index_manager = config.index_manager();
+    match index_manager.rebuild_inverted_index() {
+        Ok(stats) => {
+            info!("✅ Інвертований індекс перебудовано і збережено новим поколінням: {}", stats);
+            print_index_stats(&index_manager);
+        }
+        Err(e) => {
+            error!("❌ Помилка перебудови інвертованого індексу: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Відновлює кеш після незавершеної синхронізації і синхронізує його з мережевою папкою.
+/// false - синхронізацію перервано, індексувати не можна.
+fn sync_to_cache(config: &Config) -> bool {
+    let paths = config.index_paths();
+    let remote_folder = config.paths.remote_folder.as_str();
+    let local_cache = config.paths.local_cache.as_str();
+
+    // Перекопійовуємо файли, які могла обрізати незавершена попередня синхронізація
+    let journal = CopyJournal::new(paths.sync_journal());
+    let recovery = sync_recovery::recover_partial_files(remote_folder, local_cache, &journal);
+    if recovery.found_problems() {
+        info!("🩹 Відновлення кешу після незавершеної синхронізації: {}", recovery);
+        for error in &recovery.errors {
+            warn!("   ⚠️ {}", error);
+        }
+    }
+
+    // Копіюємо файли з сервера до локального кешу
+    let report = sync_files_to_cache(remote_folder, local_cache, &journal);
+    if let Err(e) = sync_report::append_to_history(&paths.sync_history(), &report) {
+        warn!("⚠️ Не вдалося зберегти звіт синхронізації: {}", e);
+    }
+
+    if report.aborted {
+        error!("❌ Помилка копіювання файлів: {}", report.errors.join("; "));
+        return false;
+    }
+
+    info!("✅ Синхронізацію з локальним кешем завершено: {}", report);
+    for error in &report.errors {
+        warn!("   ⚠️ {}", error);
+    }
+    true
+}
+
+async fn perform_initial_indexing(config: &Config, shutdown: &Shutdown) {
+    let paths = config.index_paths();
+    let documents_index_path = paths.documents_index();
+    let inverted_index_path = paths.inverted_index();
+    let remote_folder = config.paths.remote_folder.as_str();
+    let local_cache = config.paths.local_cache.as_str();
+
+    info!("🔍 Автоматична індексація папки: {}", remote_folder);
+    info!("📥 Копіювання файлів до локального кешу: {}", local_cache);
+    info!(
         "📄 Результат буде збережено в: {} та {}",
         documents_index_path, inverted_index_path
     );
 
-    // Копіюємо файли з сервера до локального кешу
-    match sync_files_to_cache(remote_folder, local_cache) {
-        Ok(count) => println!("✅ Скопійовано {} файлів до локального кешу", count),
-        Err(e) => {
-            println!("❌ Помилка копіювання файлів: {}", e);
-            return;
-        }
+    if !sync_to_cache(config) {
+        return;
     }
 
     // Тепер індексуємо ЛОКАЛЬНИЙ кеш замість мережевої папки
     let folder_path = local_cache;
 
     // Створюємо атомарний менеджер індексів
-    let index_manager = AtomicIndexManager::new(documents_index_path, inverted_index_path);
+    let index_manager = config.index_manager().with_shutdown(shutdown.clone());
 
     // Очищуємо старі тимчасові файли на початку
     index_manager.cleanup_temp_files();
@@ -125,26 +573,26 @@ async fn perform_initial_indexing() {
     // Виконуємо інкрементне оновлення з атомарним збереженням
     match index_manager.perform_incremental_update_atomically(folder_path) {
         Ok(stats) => {
-            println!("\n✅ Інкрементне оновлення завершено!");
-            println!("📊 Статистика: {}", stats);
+            info!("✅ Інкрементне оновлення завершено!");
+            info!("📊 Статистика: {}", stats);
 
             // Перевіряємо цілісність індексів та виправляємо при необхідності
             match index_manager.validate_indices() {
-                Ok(_) => println!("✅ Перевірка цілісності пройшла успішно"),
+                Ok(_) => info!("✅ Перевірка цілісності пройшла успішно"),
                 Err(e) => {
-                    println!("⚠️ Попередження при перевірці цілісності: {}", e);
+                    warn!("⚠️ Попередження при перевірці цілісності: {}", e);
 
                     // Спробуємо перебудувати інвертований індекс якщо потрібно
                     match index_manager.rebuild_inverted_index_if_needed() {
                         Ok(rebuilt) => {
                             if rebuilt {
-                                println!(
+                                info!(
                                     "✅ Критичні проблеми виправлено шляхом перебудови індексу"
                                 );
                             }
                         }
                         Err(rebuild_error) => {
-                            println!(
+                            error!(
                                 "❌ Помилка при спробі перебудови індексу: {}",
                                 rebuild_error
                             );
@@ -153,44 +601,90 @@ async fn perform_initial_indexing() {
                 }
             }
 
-            // Показуємо розміри файлів
-            let doc_path = Path::new(documents_index_path);
-            if let Ok(metadata) = std::fs::metadata(doc_path) {
-                println!(
-                    "📦 Розмір індексу документів: {:.2} MB",
-                    metadata.len() as f64 / 1_048_576.0
-                );
-            }
+            print_index_stats(&index_manager);
+        }
+        Err(error) => {
+            error!("❌ Помилка інкрементного оновлення: {}", error);
+            info!("🔧 Спробуємо очистити тимчасові файли...");
+            index_manager.cleanup_temp_files();
+        }
+    }
+}
+
+/// Статистика активного покоління після індексації
+fn print_index_stats(index_manager: &AtomicIndexManager) {
+    match IndexStats::collect(index_manager) {
+        Ok(stats) => print!("{}", stats),
+        Err(e) => warn!("⚠️ Не вдалося зібрати статистику: {}", e),
+    }
+}
 
-            let inv_path = Path::new(inverted_index_path);
-            if let Ok(metadata) = std::fs::metadata(inv_path) {
-                println!(
-                    "📦 Розмір інвертованого індексу: {:.2} MB",
-                    metadata.len() as f64 / 1_048_576.0
-                );
+/// Команда stats: таблиця або JSON (для моніторингу)
+fn show_stats(config: &Config, json: bool) {
+    let stats = match IndexStats::collect(&config.index_manager()) {
+        Ok(stats) => stats,
+        Err(e) => {
+            error!("❌ Помилка збору статистики: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if json {
+        match serde_json::to_string_pretty(&stats) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                error!("❌ Помилка серіалізації статистики: {}", e);
+                std::process::exit(1);
             }
+        }
+    } else {
+        print!("{}", stats);
+    }
+}
 
-            // Показуємо загальну статистику
-            if let Ok(doc_index) = DocumentIndex::load_from_file(documents_index_path) {
-                println!("📊 Загальна статистика:");
-                println!(
-                    "   - Загальна кількість документів: {}",
-                    doc_index.total_documents
-                );
-                println!("   - Загальна кількість слів: {}", doc_index.total_words);
-
-                if let Ok(inv_index) = InvertedIndex::load_from_file(inverted_index_path) {
-                    let (docs, words) = inv_index.get_stats();
-                    println!("   - Документів в інвертованому індексі: {}", docs);
-                    println!("   - Унікальних слів в індексі: {}", words);
-                }
+/// Команда terms: найчастіші або найрідкісніші терміни індексу
+fn show_terms(config: &Config, limit: usize, rarest: bool, json: bool) {
+    let report = match TermReport::collect(&config.index_manager(), limit, rarest) {
+        Ok(report) => report,
+        Err(e) => {
+            error!("❌ Помилка збору словника: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                error!("❌ Помилка серіалізації словника: {}", e);
+                std::process::exit(1);
             }
         }
-        Err(error) => {
-            println!("❌ Помилка інкрементного оновлення: {}", error);
-            println!("🔧 Спробуємо очистити тимчасові файли...");
-            index_manager.cleanup_temp_files();
+    } else {
+        print!("{}", report);
+    }
+}
+
+/// Команда duplicates: групи майже однакових документів
+fn show_duplicates(config: &Config, max_distance: u32, json: bool) {
+    let report = match DuplicateReport::collect(&config.index_manager(), max_distance) {
+        Ok(report) => report,
+        Err(e) => {
+            error!("❌ Помилка пошуку дублікатів: {}", e);
+            std::process::exit(1);
         }
+    };
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                error!("❌ Помилка серіалізації звіту про дублікати: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print!("{}", report);
     }
 }
 
@@ -226,15 +720,18 @@ fn should_sync_file(relative_path: &Path) -> bool {
     is_year_folder && !is_excluded
 }
 
-fn sync_files_to_cache(remote_path: &str, local_cache_path: &str) -> Result<usize, String> {
+fn sync_files_to_cache(remote_path: &str, local_cache_path: &str, journal: &CopyJournal) -> SyncReport {
     use std::collections::HashSet;
     use std::fs;
     use walkdir::WalkDir;
 
+    let mut report = SyncReport::start(remote_path, local_cache_path);
+
     // Створюємо локальну папку якщо не існує
-    fs::create_dir_all(local_cache_path).map_err(|e| format!("Помилка створення кешу: {}", e))?;
+    if let Err(e) = fs::create_dir_all(local_cache_path) {
+        return report.abort(format!("Помилка створення кешу: {}", e));
+    }
 
-    let mut file_count = 0;
     let mut remote_files = HashSet::new();
 
     // Копіюємо файли з сервера
@@ -245,9 +742,13 @@ fn sync_files_to_cache(remote_path: &str, local_cache_path: &str) -> Result<usiz
     {
         if entry.file_type().is_file() {
             let remote_file = entry.path();
-            let relative_path = remote_file
-                .strip_prefix(remote_path)
-                .map_err(|e| format!("Помилка шляху: {}", e))?;
+            let relative_path = match remote_file.strip_prefix(remote_path) {
+                Ok(relative_path) => relative_path,
+                Err(e) => {
+                    report.errors.push(format!("Помилка шляху: {}", e));
+                    continue;
+                }
+            };
 
             // Фільтруємо файли - тільки папки з роками
             if !should_sync_file(relative_path) {
@@ -280,15 +781,20 @@ fn sync_files_to_cache(remote_path: &str, local_cache_path: &str) -> Result<usiz
             if should_copy {
                 // Створюємо підпапки якщо потрібно
                 if let Some(parent) = local_file.parent() {
-                    fs::create_dir_all(parent)
-                        .map_err(|e| format!("Помилка створення папки: {}", e))?;
+                    if let Err(e) = fs::create_dir_all(parent) {
+                        report.errors.push(format!("Помилка створення папки: {}", e));
+                        continue;
+                    }
                 }
 
-                // Копіюємо файл
-                fs::copy(remote_file, &local_file)
-                    .map_err(|e| format!("Помилка копіювання {}: {}", remote_file.display(), e))?;
-
-                file_count += 1;
+                // Копіюємо файл (через журнал і тимчасовий .partial файл)
+                match sync_recovery::copy_with_journal(journal, remote_file, &local_file, relative_path) {
+                    Ok(bytes) => {
+                        report.files_copied += 1;
+                        report.bytes_transferred += bytes;
+                    }
+                    Err(e) => report.errors.push(e.to_string()),
+                }
             }
         }
     }
@@ -301,16 +807,26 @@ fn sync_files_to_cache(remote_path: &str, local_cache_path: &str) -> Result<usiz
     {
         if entry.file_type().is_file() {
             let local_file = entry.path();
-            let relative_path = local_file
-                .strip_prefix(local_cache_path)
-                .map_err(|e| format!("Помилка шляху: {}", e))?;
+            let relative_path = match local_file.strip_prefix(local_cache_path) {
+                Ok(relative_path) => relative_path,
+                Err(e) => {
+                    report.errors.push(format!("Помилка шляху: {}", e));
+                    continue;
+                }
+            };
 
             if !remote_files.contains(relative_path) {
-                fs::remove_file(local_file)
-                    .map_err(|e| format!("Помилка видалення {}: {}", local_file.display(), e))?;
+                match fs::remove_file(local_file) {
+                    Ok(_) => report.files_deleted += 1,
+                    Err(e) => report.errors.push(format!(
+                        "Помилка видалення {}: {}",
+                        local_file.display(),
+                        e
+                    )),
+                }
             }
         }
     }
 
-    Ok(file_count)
+    report.finish()
 }
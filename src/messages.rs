@@ -0,0 +1,537 @@
+//! Каталог повідомлень сервера (відповіді API та журнал веб-режиму) українською
+//! та англійською. Мова задається server.locale; англійська зручна для сторонніх
+//! систем моніторингу і пошуку в журналах. Повідомлення форматуються макросом tr!:
+//! `tr!(FileIndexBuilt, files.len())` підставляє аргументи замість {} по черзі.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Write};
+
+static LOCALE: OnceCell<Locale> = OnceCell::new();
+
+/// Мова повідомлень сервера
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    Uk,
+    En,
+}
+
+impl std::str::FromStr for Locale {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "uk" => Ok(Self::Uk),
+            "en" => Ok(Self::En),
+            other => Err(format!("невідома мова: {} (uk або en)", other)),
+        }
+    }
+}
+
+/// Задає мову повідомлень для всього процесу; повторний виклик нічого не змінює
+pub fn set_locale(locale: Locale) {
+    let _ = LOCALE.set(locale);
+}
+
+/// Поточна мова повідомлень (за замовчуванням українська)
+pub fn locale() -> Locale {
+    LOCALE.get().copied().unwrap_or_default()
+}
+
+/// Оголошує Msg і тексти для кожної мови одним списком, щоб переклад не можна було пропустити
+macro_rules! catalog {
+    ($($name:ident { uk: $uk:literal, en: $en:literal })*) => {
+        /// Ключ повідомлення каталогу
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Msg {
+            $($name,)*
+        }
+
+        impl Msg {
+            pub const ALL: &'static [Msg] = &[$(Msg::$name,)*];
+
+            /// Шаблон повідомлення вказаною мовою
+            pub fn text_in(self, locale: Locale) -> &'static str {
+                match (self, locale) {
+                    $(
+                        (Msg::$name, Locale::Uk) => $uk,
+                        (Msg::$name, Locale::En) => $en,
+                    )*
+                }
+            }
+        }
+    };
+}
+
+catalog! {
+    // Відповіді API
+    EmptyQuery { uk: "Порожній запит пошуку", en: "Empty search query" }
+    InvalidDate {
+        uk: "Некоректна дата {}: потрібно ДД.ММ.РРРР або РРРР-ММ-ДД",
+        en: "Invalid date {}: expected DD.MM.YYYY or YYYY-MM-DD"
+    }
+    InvalidPattern {
+        uk: "Некоректний регулярний вираз: {}",
+        en: "Invalid regular expression: {}"
+    }
+    SearchFailed { uk: "Помилка пошуку: {}", en: "Search error: {}" }
+    QueryTimedOut {
+        uk: "⏱️ Запит '{}' перервано після {} мс - повернуто частину результатів",
+        en: "⏱️ Query '{}' aborted after {} ms - returning partial results"
+    }
+    ExportPrepared { uk: "📤 Вивантаження: {} абзаців за запитом '{}'", en: "📤 Export: {} paragraphs for query '{}'" }
+    ServiceHistoryPrepared { uk: "📋 Послужний витяг: {} - наказів {}, абзаців {}", en: "📋 Service history: {} - {} orders, {} paragraphs" }
+    ExportFailed { uk: "Не вдалося сформувати вивантаження: {}", en: "Failed to build the export: {}" }
+    FileNotFound { uk: "Файл не знайдено", en: "File not found" }
+    UnknownCollection { uk: "Колекцію {} не знайдено (доступні: {})", en: "Collection {} not found (available: {})" }
+    CollectionSearchFailed {
+        uk: "⚠️ Колекцію {} пропущено в пошуку по всіх колекціях: {}",
+        en: "⚠️ Collection {} skipped in the cross-collection search: {}"
+    }
+    WrongPassword { uk: "Неправильний пароль", en: "Wrong password" }
+    FileOpened { uk: "Файл відкрито", en: "File opened" }
+    FileOpenFailed { uk: "Помилка відкриття файлу: {}", en: "Failed to open file: {}" }
+    FileReadFailed { uk: "Помилка читання файлу", en: "Failed to read file" }
+    PdfConversionUnavailable {
+        uk: "Не вдалося конвертувати документ у PDF. Переконайтеся, що LibreOffice встановлено.",
+        en: "Could not convert the document to PDF. Make sure LibreOffice is installed."
+    }
+    InvalidLogLevel { uk: "Некоректний рівень журналу {}: {}", en: "Invalid log level {}: {}" }
+
+    // Веб-сервер
+    FolderNotFound { uk: "⚠️  Папка не знайдена: {}", en: "⚠️  Folder not found: {}" }
+    FileIndexBuilding { uk: "🔍 Побудова індексу файлів у: {}", en: "🔍 Building file index in: {}" }
+    FileIndexBuilt { uk: "✅ Індекс побудовано: {} файлів", en: "✅ File index built: {} files" }
+    DocumentConverted { uk: "✅ Документ успішно конвертовано: {}", en: "✅ Document converted: {}" }
+    ConvertedPdfReadFailed { uk: "⚠️  Помилка читання конвертованого PDF", en: "⚠️  Failed to read the converted PDF" }
+    ConversionFailed { uk: "⚠️  Помилка конвертації: {}", en: "⚠️  Conversion error: {}" }
+    LibreOfficeNotFound {
+        uk: "⚠️  LibreOffice не знайдено у жодному зі стандартних місць",
+        en: "⚠️  LibreOffice not found in any of the standard locations"
+    }
+    LogLevelChanged { uk: "🔧 Рівень журналу змінено на {}", en: "🔧 Log level changed to {}" }
+    AutoIndexerStarting {
+        uk: "🚀 Запуск автоматичного індексера (перевірка кожні {} секунд)...",
+        en: "🚀 Starting the auto indexer (checking every {} seconds)..."
+    }
+    IntegrityMonitorStarting {
+        uk: "🚀 Запуск фонової самоперевірки індексів (кожні {} секунд)...",
+        en: "🚀 Starting the background index self-check (every {} seconds)..."
+    }
+    FileIndexRefreshStarting {
+        uk: "🚀 Запуск оновлення індексу файлів (кожні {} секунд)...",
+        en: "🚀 Starting the file index refresh (every {} seconds)..."
+    }
+    FileIndexRefreshing { uk: "🔄 Оновлення індексу файлів...", en: "🔄 Refreshing the file index..." }
+    FileIndexRefreshed { uk: "✅ Індекс файлів оновлено", en: "✅ File index refreshed" }
+    ServerStarting { uk: "Запуск веб-сервера на http://0.0.0.0:{}", en: "Starting the web server on http://0.0.0.0:{}" }
+    ServerLanAccess { uk: "Доступ з локальної мережі: http://{}:{}", en: "LAN access: http://{}:{}" }
+    LocalIpUnknown { uk: "⚠️  Не вдалося визначити локальну IP-адресу", en: "⚠️  Could not determine the local IP address" }
+    LocalIpHint { uk: "💡 Використовуйте localhost або перевірте ipconfig", en: "💡 Use localhost or check ipconfig" }
+
+    // Запуск веб-режиму
+    IndexDir { uk: "📂 Папка індексів: {}", en: "📂 Index directory: {}" }
+    IndexChecking { uk: "🔍 Перевірка індексу: {}", en: "🔍 Checking the index: {}" }
+    IndexFileMissing { uk: "⚠️  Файл індексу не знайдено: {}", en: "⚠️  Index file not found: {}" }
+    InitialIndexCreating { uk: "🔧 Створюємо початковий індекс...", en: "🔧 Creating the initial index..." }
+    IndexFileSize { uk: "📁 Розмір файлу індексу: {} MB", en: "📁 Index file size: {} MB" }
+    IndexLoading { uk: "⏳ Завантаження індексу...", en: "⏳ Loading the index..." }
+    IndexLoaded { uk: "✅ Завантажено {} документів з {} слів", en: "✅ Loaded {} documents with {} words" }
+    IndexLoadFailed { uk: "❌ Помилка завантаження індексу: {}", en: "❌ Failed to load the index: {}" }
+    IndexLoadHint {
+        uk: "💡 Спробуйте видалити файли індексів та перезапустити",
+        en: "💡 Try deleting the index files and restarting"
+    }
+    IndexCreateFailed { uk: "❌ Не вдалося створити індекс", en: "❌ Failed to create the index" }
+    RemoteFolderHint { uk: "💡 Перевірте доступ до мережевої папки {}", en: "💡 Check access to the network folder {}" }
+    CollectionLoading { uk: "📚 Колекція {}: індекси в {}", en: "📚 Collection {}: indexes in {}" }
+    CollectionLoaded { uk: "✅ Колекція {}: {} документів", en: "✅ Collection {}: {} documents" }
+    CollectionLoadFailed {
+        uk: "⚠️ Колекцію {} не завантажено ({}) - пошук у ній недоступний до наступної індексації",
+        en: "⚠️ Collection {} not loaded ({}) - searching it is unavailable until the next indexing"
+    }
+    ServerStartFailed { uk: "❌ Помилка запуску сервера: {}", en: "❌ Failed to start the server: {}" }
+
+    // Робота як служба
+    PidFileInUse {
+        uk: "файл PID належить запущеному процесу {}",
+        en: "the PID file belongs to running process {}"
+    }
+    PidFileCreateFailed { uk: "❌ Не вдалося створити файл PID {}: {}", en: "❌ Failed to create the PID file {}: {}" }
+    SystemdNotifyFailed { uk: "⚠️ Не вдалося надіслати повідомлення systemd: {}", en: "⚠️ Failed to notify systemd: {}" }
+    WatchdogEnabled { uk: "🐕 Watchdog systemd: сигнал кожні {} мс", en: "🐕 systemd watchdog: pinging every {} ms" }
+    ShutdownRequested { uk: "🛑 Отримано {} - завершення роботи...", en: "🛑 {} received - shutting down..." }
+    ForcedShutdown {
+        uk: "⚠️ Повторний {} - негайний вихід без очікування",
+        en: "⚠️ {} received again - exiting immediately"
+    }
+    WaitingForIndexer {
+        uk: "⏳ Очікування завершення циклу індексації (до {} с)...",
+        en: "⏳ Waiting for the indexing cycle to finish (up to {} s)..."
+    }
+    IndexerStopTimeout {
+        uk: "⚠️ Цикл індексації не завершився за {} с - зупинка без очікування",
+        en: "⚠️ The indexing cycle did not finish within {} s - stopping anyway"
+    }
+    ServerStopped { uk: "👋 Сервер зупинено", en: "👋 Server stopped" }
+    AutoIndexerStopped { uk: "⏹️ Автоматичний індексер зупинено", en: "⏹️ Auto indexer stopped" }
+
+    // Перезавантаження конфігурації
+    ConfigWatchStarting {
+        uk: "👀 Стежимо за змінами конфігурації: {}",
+        en: "👀 Watching the configuration for changes: {}"
+    }
+    ConfigReloaded { uk: "⚙️ Конфігурацію оновлено без перезапуску: {}", en: "⚙️ Configuration reloaded without restart: {}" }
+    ConfigRestartRequired {
+        uk: "⚠️ Зміни потребують перезапуску сервера і поки не застосовані: {}",
+        en: "⚠️ These changes require a server restart and were not applied: {}"
+    }
+    ConfigReloadFailed {
+        uk: "❌ Нову конфігурацію відхилено, працюємо з попередньою: {}",
+        en: "❌ New configuration rejected, keeping the previous one: {}"
+    }
+
+    // Журнал повільних запитів
+    SlowQueryLogged { uk: "🐢 Повільний запит ({} мс): {}", en: "🐢 Slow query ({} ms): {}" }
+    SlowQueryLogFailed {
+        uk: "⚠️ Помилка журналу повільних запитів {}: {}",
+        en: "⚠️ Slow query log error {}: {}"
+    }
+
+    // Історія пошуку
+    HistoryLoadFailed {
+        uk: "⚠️ Не вдалося прочитати історію пошуку {}: {}",
+        en: "⚠️ Failed to read the search history {}: {}"
+    }
+    HistorySaveFailed {
+        uk: "⚠️ Не вдалося зберегти історію пошуку {}: {}",
+        en: "⚠️ Failed to save the search history {}: {}"
+    }
+
+    VisitsLoadFailed {
+        uk: "⚠️ Не вдалося прочитати останні візити користувачів {}: {}",
+        en: "⚠️ Failed to read the users' last visits {}: {}"
+    }
+    VisitsSaveFailed {
+        uk: "⚠️ Не вдалося зберегти останні візити користувачів {}: {}",
+        en: "⚠️ Failed to save the users' last visits {}: {}"
+    }
+
+    // Оцінки релевантності
+    FeedbackLoadFailed {
+        uk: "⚠️ Не вдалося прочитати оцінки релевантності {}: {}",
+        en: "⚠️ Failed to read the relevance feedback {}: {}"
+    }
+    FeedbackSaveFailed {
+        uk: "⚠️ Не вдалося зберегти оцінки релевантності {}: {}",
+        en: "⚠️ Failed to save the relevance feedback {}: {}"
+    }
+    DocumentNotInIndex {
+        uk: "Документ не знайдено в індексі: {}",
+        en: "Document not found in the index: {}"
+    }
+
+    // Примітки до абзаців
+    AnnotationsLoadFailed {
+        uk: "⚠️ Не вдалося прочитати примітки до абзаців {}: {}",
+        en: "⚠️ Failed to read the paragraph annotations {}: {}"
+    }
+    AnnotationsSaveFailed {
+        uk: "⚠️ Не вдалося зберегти примітки до абзаців {}: {}",
+        en: "⚠️ Failed to save the paragraph annotations {}: {}"
+    }
+    EmptyAnnotation {
+        uk: "Текст примітки порожній або довший за {} символів",
+        en: "The annotation text is empty or longer than {} characters"
+    }
+    ParagraphNotFound {
+        uk: "Абзац {} не знайдено в документі {}",
+        en: "Paragraph {} not found in document {}"
+    }
+    AnnotationNotFound {
+        uk: "Примітку {} не знайдено",
+        en: "Annotation {} not found"
+    }
+
+    // Збережені запити
+    SavedQueriesLoadFailed {
+        uk: "⚠️ Не вдалося прочитати збережені запити {}: {}",
+        en: "⚠️ Failed to read the saved queries {}: {}"
+    }
+    SavedQueriesSaveFailed {
+        uk: "⚠️ Не вдалося зберегти збережені запити {}: {}",
+        en: "⚠️ Failed to save the saved queries {}: {}"
+    }
+    SavedQueriesFailed {
+        uk: "⚠️ Не вдалося виконати збережений запит '{}': {}",
+        en: "⚠️ Failed to run the saved query '{}': {}"
+    }
+    SavedQueryAlert {
+        uk: "🔔 Нові документи за збереженим запитом «{}»: {}",
+        en: "🔔 New documents for the saved query «{}»: {}"
+    }
+    EmptySavedQuery { uk: "Запит порожній", en: "The query is empty" }
+    NoNotificationTargets {
+        uk: "Потрібна хоча б одна адреса сповіщення (webhook або email)",
+        en: "At least one notification target (webhook or email) is required"
+    }
+    InvalidWebhookUrl {
+        uk: "Адреса webhook має починатися з http:// або https://: {}",
+        en: "The webhook URL must start with http:// or https://: {}"
+    }
+    InvalidEmail { uk: "Некоректна адреса пошти {}: {}", en: "Invalid email address {}: {}" }
+    WebhookHostNotAllowed {
+        uk: "Хост webhook {} не дозволено (alerts.saved_query_webhook_hosts)",
+        en: "The webhook host {} is not allowed (alerts.saved_query_webhook_hosts)"
+    }
+    EmailDomainNotAllowed {
+        uk: "Домен пошти {} не дозволено (alerts.saved_query_email_domains)",
+        en: "The email domain {} is not allowed (alerts.saved_query_email_domains)"
+    }
+    NotificationTargetSkipped {
+        uk: "⚠️ Сповіщення збереженого запиту «{}» не надіслано: {}",
+        en: "⚠️ Saved query «{}» notification not sent: {}"
+    }
+    EmailBuildFailed { uk: "Не вдалося скласти лист: {}", en: "Failed to build the email: {}" }
+    EmailSendFailed { uk: "Сервер SMTP не прийняв лист: {}", en: "The SMTP server rejected the email: {}" }
+    EmailAlertsDisabled {
+        uk: "Сповіщення поштою недоступні: не задано alerts.smtp_server",
+        en: "Email notifications are unavailable: alerts.smtp_server is not set"
+    }
+    AdminDisabled {
+        uk: "Адміністративні запити вимкнено: не задано server.admin_password_sha256",
+        en: "Admin requests are disabled: server.admin_password_sha256 is not set"
+    }
+    AdminAuthRequired {
+        uk: "Потрібен пароль адміністратора (Authorization: Bearer <пароль>)",
+        en: "The admin password is required (Authorization: Bearer <password>)"
+    }
+    SessionRequired {
+        uk: "Потрібен сеанс браузера (cookie blazing_session)",
+        en: "A browser session is required (the blazing_session cookie)"
+    }
+    SavedQueryNotFound {
+        uk: "Збережений запит {} не знайдено",
+        en: "Saved query {} not found"
+    }
+
+    // Маскування чутливих даних
+    RedactionEnabled {
+        uk: "🕶️ Маскування чутливих даних увімкнено (правил: {})",
+        en: "🕶️ Sensitive data redaction enabled ({} rules)"
+    }
+    PreviewRequiresUnredactedRole {
+        uk: "Перегляд цього файлу потребує ролі перегляду без маскування",
+        en: "Previewing this file requires the unredacted role"
+    }
+    UnredactedRoleUnavailable {
+        uk: "Роль перегляду без маскування не налаштовано",
+        en: "The unredacted role is not configured"
+    }
+    UnredactedRoleDenied {
+        uk: "⚠️ Неправильний пароль ролі перегляду без маскування з {}",
+        en: "⚠️ Wrong unredacted role password from {}"
+    }
+
+    // gRPC
+    GrpcServerStarting { uk: "🔌 gRPC-сервер на порту {}", en: "🔌 gRPC server on port {}" }
+    GrpcServerStopped { uk: "⏹️ gRPC-сервер зупинено", en: "⏹️ gRPC server stopped" }
+    GrpcServerFailed { uk: "❌ Помилка gRPC-сервера: {}", en: "❌ gRPC server error: {}" }
+
+    // Бот Telegram
+    TelegramBotStarting {
+        uk: "🤖 Запуск бота Telegram (дозволених чатів: {})...",
+        en: "🤖 Starting the Telegram bot (allowed chats: {})..."
+    }
+    TelegramBotStopped { uk: "⏹️ Бот Telegram зупинено", en: "⏹️ Telegram bot stopped" }
+    TelegramApiFailed { uk: "⚠️ Помилка API Telegram: {}", en: "⚠️ Telegram API error: {}" }
+    TelegramQuery { uk: "🤖 Запит '{}' з чату Telegram {}", en: "🤖 Query '{}' from Telegram chat {}" }
+    TelegramChatDenied {
+        uk: "⛔ Запит з недозволеного чату Telegram {} відхилено",
+        en: "⛔ Rejected a query from Telegram chat {} that is not allowed"
+    }
+    TelegramAccessDenied {
+        uk: "⛔ Доступ заборонено. Передайте адміністратору ваш chat id: {}",
+        en: "⛔ Access denied. Send your chat id to the administrator: {}"
+    }
+    TelegramHelp {
+        uk: "🔍 Надішліть текст для пошуку в наказах, наприклад: Петренко відпустка",
+        en: "🔍 Send any text to search the orders, for example: Петренко відпустка"
+    }
+    TelegramNothingFound { uk: "🤷 Нічого не знайдено за запитом: {}", en: "🤷 Nothing found for: {}" }
+    TelegramFound { uk: "🔍 Знайдено документів: {} (показано {})", en: "🔍 Documents found: {} (showing {})" }
+    TelegramResult { uk: "{}. {} (збігів: {})", en: "{}. {} (matches: {})" }
+    TelegramSection { uk: "п. {} - {}", en: "cl. {} - {}" }
+
+    // Сповіщення про збої
+    AlertRepeatedFailures {
+        uk: "⚠️ Blazing Search ({}): синхронізація/індексація не вдається {} циклів поспіль",
+        en: "⚠️ Blazing Search ({}): sync/indexing failed {} cycles in a row"
+    }
+    AlertParseErrorsJump {
+        uk: "⚠️ Blazing Search ({}): файлів з помилками обробки - {} (у попередньому циклі {})",
+        en: "⚠️ Blazing Search ({}): {} files failed to parse (previous cycle: {})"
+    }
+    AlertErrorsHeading { uk: "Останні помилки:", en: "Latest errors:" }
+    AlertMoreErrors { uk: "... і ще {}", en: "... and {} more" }
+    AlertSent { uk: "📨 Сповіщення надіслано ({}): {}", en: "📨 Alert sent ({}): {}" }
+    AlertSendFailed { uk: "⚠️ Не вдалося надіслати сповіщення ({}): {}", en: "⚠️ Failed to send the alert ({}): {}" }
+
+    // Фоновий індексер
+    AutoCheckStarting {
+        uk: "🚀 [{}] Запуск автоматичної перевірки файлів кожні {} секунд...",
+        en: "🚀 [{}] Starting automatic file checks every {} seconds..."
+    }
+    AutoCheckRunning { uk: "🔄 [{}] Автоматична перевірка файлів...", en: "🔄 [{}] Automatic file check..." }
+    RemoteChangesDetected {
+        uk: "📥 [{}] Виявлено зміни на сервері - копіюємо файли...",
+        en: "📥 [{}] Changes detected on the server - copying files..."
+    }
+    RemoteUnchanged {
+        uk: "ℹ️ [{}] Змін на сервері не виявлено - пропускаємо копіювання",
+        en: "ℹ️ [{}] No changes on the server - skipping the copy"
+    }
+    OfflineMode {
+        uk: "🌐 [{}] ОФЛАЙН-РЕЖИМ: Мережева папка недоступна: {}",
+        en: "🌐 [{}] OFFLINE MODE: network folder unavailable: {}"
+    }
+    OfflineHint {
+        uk: "💡 [{}] Працюємо в офлайн-режимі з локальним кешем",
+        en: "💡 [{}] Working offline with the local cache"
+    }
+    RemoteCheckFailed {
+        uk: "⚠️ [{}] Помилка перевірки змін на сервері: {}",
+        en: "⚠️ [{}] Failed to check the server for changes: {}"
+    }
+    CopyFailed { uk: "❌ [{}] Помилка копіювання: {}", en: "❌ [{}] Copy error: {}" }
+    SyncCompleted { uk: "📥 [{}] Синхронізацію завершено: {}", en: "📥 [{}] Sync finished: {}" }
+    SyncReportSaveFailed {
+        uk: "⚠️ [{}] Не вдалося зберегти звіт синхронізації: {}",
+        en: "⚠️ [{}] Failed to save the sync report: {}"
+    }
+    UnindexedFilesFound {
+        uk: "🔍 [{}] Виявлено неіндексовані файли в кеші - запускаємо індексацію...",
+        en: "🔍 [{}] Unindexed files found in the cache - starting indexing..."
+    }
+    CacheInSync {
+        uk: "✅ [{}] Кеш синхронізований з індексом - індексування не потрібне",
+        en: "✅ [{}] The cache matches the index - no indexing needed"
+    }
+    CacheCheckFailed { uk: "⚠️ Помилка перевірки кешу vs індекс: {}", en: "⚠️ Failed to compare the cache with the index: {}" }
+    CacheRecovery { uk: "🩹 [{}] Відновлення кешу: {}", en: "🩹 [{}] Cache recovery: {}" }
+    AutoUpdateCompleted { uk: "✅ [{}] Автоматичне оновлення завершено: {}", en: "✅ [{}] Automatic update finished: {}" }
+    IndexingNoChanges { uk: "ℹ️ [{}] Індексація завершена без змін", en: "ℹ️ [{}] Indexing finished with no changes" }
+    IndexingFailed { uk: "❌ [{}] Помилка індексації: {}", en: "❌ [{}] Indexing error: {}" }
+    IntegrityWarning {
+        uk: "⚠️ Попередження при перевірці цілісності індексів: {}",
+        en: "⚠️ Index integrity check warning: {}"
+    }
+    SearchEngineReloadFailed {
+        uk: "⚠️  Помилка оновлення пошукового движка: {}",
+        en: "⚠️  Failed to reload the search engine: {}"
+    }
+    AtomicUpdateFailed { uk: "❌ Помилка атомарного оновлення: {}", en: "❌ Atomic update error: {}" }
+    SearchEngineReloaded {
+        uk: "✅ Пошуковий індекс успішно оновлено в пам'яті",
+        en: "✅ Search index reloaded in memory"
+    }
+    IndexDeltaApplied {
+        uk: "⚡ Покоління {} застосовано в пам'яті: змінено {}, видалено {}",
+        en: "⚡ Generation {} applied in memory: {} changed, {} deleted"
+    }
+    IndexDeltaSkipped {
+        uk: "🔄 Покоління {} не можна застосувати до завантаженого - повне перезавантаження",
+        en: "🔄 Generation {} does not follow the loaded one - reloading in full"
+    }
+    IndexDeltaMismatch {
+        uk: "⚠️ Зміни покоління {} не відповідають індексу в пам'яті - повне перезавантаження",
+        en: "⚠️ Changes of generation {} do not match the in-memory index - reloading in full"
+    }
+    AtomicUpdateAborted {
+        uk: "Оновлення індексів аварійно завершилося",
+        en: "The index update terminated abnormally"
+    }
+    SegmentMergeStarted {
+        uk: "🧩 Фонове злиття {} сегментів інвертованого індексу...",
+        en: "🧩 Merging {} inverted index segments in the background..."
+    }
+    SegmentsMerged {
+        uk: "✅ Сегменти інвертованого індексу злито (покоління {})",
+        en: "✅ Inverted index segments merged (generation {})"
+    }
+    IndexingDeferredForMerge {
+        uk: "⏳ [{}] Триває злиття сегментів індексу - індексацію відкладено до наступного циклу",
+        en: "⏳ [{}] Index segments are being merged - indexing deferred to the next cycle"
+    }
+    SegmentMergeFailed {
+        uk: "⚠️ Помилка фонового злиття сегментів інвертованого індексу: {}",
+        en: "⚠️ Background merge of inverted index segments failed: {}"
+    }
+    CacheReadFailed { uk: "⚠️  Помилка читання кешу: {}", en: "⚠️  Failed to read the cache: {}" }
+    IndexMissingFullIndexing {
+        uk: "ℹ️  Індекс не знайдено - потрібне повне індексування",
+        en: "ℹ️  Index not found - full indexing required"
+    }
+    CacheFileChanged { uk: "🔄 Файл змінився: {}", en: "🔄 File changed: {}" }
+    CacheFileAdded { uk: "➕ Новий файл в кеші: {}", en: "➕ New file in the cache: {}" }
+    CacheFileRemoved { uk: "➖ Файл видалено з кешу: {}", en: "➖ File removed from the cache: {}" }
+}
+
+impl Msg {
+    /// Шаблон повідомлення поточною мовою
+    pub fn text(self) -> &'static str {
+        self.text_in(locale())
+    }
+}
+
+/// Підставляє аргументи замість {} у шаблоні по черзі
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut parts = template.split("{}");
+    let mut result = parts.next().unwrap_or_default().to_string();
+    let mut args = args.iter();
+
+    for part in parts {
+        match args.next() {
+            Some(arg) => {
+                let _ = write!(result, "{}", arg);
+            }
+            None => result.push_str("{}"),
+        }
+        result.push_str(part);
+    }
+    result
+}
+
+/// Повідомлення каталогу поточною мовою: `tr!(EmptyQuery)`, `tr!(SearchFailed, err)`
+#[macro_export]
+macro_rules! tr {
+    ($msg:ident) => {
+        $crate::messages::Msg::$msg.text().to_string()
+    };
+    ($msg:ident, $($arg:expr),+ $(,)?) => {
+        $crate::messages::fill(
+            $crate::messages::Msg::$msg.text(),
+            &[$(&$arg as &dyn ::std::fmt::Display),+],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_locales_match() {
+        for msg in Msg::ALL {
+            let (uk, en) = (msg.text_in(Locale::Uk), msg.text_in(Locale::En));
+            assert_eq!(uk.matches("{}").count(), en.matches("{}").count(), "{:?}", msg);
+            assert_ne!(uk, en, "{:?}", msg);
+        }
+
+        assert_eq!(fill(Msg::IndexLoaded.text_in(Locale::En), &[&3, &"10"]), "✅ Loaded 3 documents with 10 words");
+        assert_eq!("EN".parse::<Locale>(), Ok(Locale::En));
+        assert!("de".parse::<Locale>().is_err());
+    }
+}
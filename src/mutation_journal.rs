@@ -0,0 +1,168 @@
+//! Журнал змін документів (index_mutations.journal): перед записом нового покоління
+//! менеджер індексів дописує сюди додані, змінені та видалені документи. Якщо процес
+//! упаде посеред збереження, зміни не губляться - наступне оновлення під блокуванням
+//! відтворює їх поверх того самого покоління (див. AtomicIndexManager::replay_mutation_journal),
+//! а не чекає повної переіндексації. Журнал index_update.wal натомість лише відкочує
+//! недописані файли покоління.
+//!
+//! Пакет змін - рядки JSON між Begin і End; пакет без End (обірваний запис) не відтворюється.
+//! Журналюються лише зміни без зсуву слотів (як IndexDelta): перестановку за датою та
+//! ущільнення наступна індексація папки знаходить заново.
+
+use crate::document_record::DocumentRecord;
+use crate::error::IndexError;
+use crate::index_delta::IndexDelta;
+use crate::index_manifest::{sibling_path, unix_now};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+/// Файл журналу змін поруч з індексом документів
+pub const MUTATION_JOURNAL_FILE_NAME: &str = "index_mutations.journal";
+
+/// Запис журналу змін
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Mutation {
+    /// Початок пакета змін поверх покоління base_generation
+    Begin { base_generation: u64, timestamp: u64 },
+    /// Новий (одразу після останнього слота) або змінений документ
+    Put { slot: usize, document: Box<DocumentRecord> },
+    /// Документ позначено видаленим
+    Delete { slot: usize },
+    /// Пакет записано повністю
+    End { next_doc_id: u64, indexed_at: u64 },
+}
+
+pub struct MutationJournal {
+    path: PathBuf,
+    durable: bool,
+}
+
+impl MutationJournal {
+    pub fn new(documents_index_path: &str) -> Self {
+        Self {
+            path: sibling_path(documents_index_path, MUTATION_JOURNAL_FILE_NAME),
+            durable: false,
+        }
+    }
+
+    /// Пакет примусово скидається на диск (fsync) до запису покоління
+    pub fn durable(mut self, durable: bool) -> Self {
+        self.durable = durable;
+        self
+    }
+
+    /// Записує зміни дельти новим пакетом. Попередній пакет на цей момент уже відтворено
+    /// або відкинуто (див. replay_mutation_journal), тож файл починається заново.
+    pub fn record(&self, delta: &IndexDelta) -> Result<(), IndexError> {
+        let file = File::create(&self.path).map_err(|e| IndexError::io("Помилка створення журналу змін", e))?;
+        let mut writer = BufWriter::new(file);
+
+        let mut records = vec![Mutation::Begin { base_generation: delta.base_generation, timestamp: unix_now() }];
+        records.extend(
+            delta
+                .changed
+                .iter()
+                .map(|(slot, document)| Mutation::Put { slot: *slot, document: Box::new(document.clone()) }),
+        );
+        records.extend(delta.deleted.iter().map(|&slot| Mutation::Delete { slot }));
+        records.push(Mutation::End { next_doc_id: delta.next_doc_id, indexed_at: delta.indexed_at });
+
+        for record in &records {
+            serde_json::to_writer(&mut writer, record).map_err(|e| IndexError::json("Помилка запису журналу змін", e))?;
+            writer.write_all(b"\n").map_err(|e| IndexError::io("Помилка запису журналу змін", e))?;
+        }
+
+        let file = writer.into_inner().map_err(|e| IndexError::io("Помилка запису журналу змін", e.into_error()))?;
+        if self.durable {
+            file.sync_all().map_err(|e| IndexError::io("Помилка синхронізації журналу змін", e))?;
+        }
+        Ok(())
+    }
+
+    /// Повністю записаний пакет змін як дельта базового покоління (None - журналу немає
+    /// або пакет обірвано до End)
+    pub fn pending(&self) -> Option<IndexDelta> {
+        let file = File::open(&self.path).ok()?;
+        let records = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .map(|line| serde_json::from_str::<Mutation>(&line))
+            .map_while(Result::ok);
+
+        let mut delta: Option<IndexDelta> = None;
+        for record in records {
+            match record {
+                Mutation::Begin { base_generation, .. } => {
+                    delta = Some(IndexDelta {
+                        base_generation,
+                        generation: base_generation,
+                        changed: Vec::new(),
+                        deleted: Vec::new(),
+                        next_doc_id: 0,
+                        indexed_at: 0,
+                    });
+                }
+                Mutation::Put { slot, document } => delta.as_mut()?.changed.push((slot, *document)),
+                Mutation::Delete { slot } => delta.as_mut()?.deleted.push(slot),
+                Mutation::End { next_doc_id, indexed_at } => {
+                    let mut delta = delta?;
+                    delta.next_doc_id = next_doc_id;
+                    delta.indexed_at = indexed_at;
+                    return Some(delta);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    /// Видаляє журнал після запису покоління (або відкинутого пакета)
+    pub fn clear(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+    use crate::index_builder::IndexBuilder;
+
+    #[test]
+    fn test_complete_batch_reads_back_torn_batch_ignored() {
+        let dir = TempIndexDir::new("mutation_journal");
+        let journal = MutationJournal::new(&dir.join("documents_index.json").to_string_lossy());
+        assert!(journal.pending().is_none());
+
+        let (mut doc_index, _) = IndexBuilder::new()
+            .with_document("Наказ №1 від 10.01.2024.docx", ["Петренку П.П. надати відпустку"])
+            .with_document("Наказ №2 від 11.01.2024.docx", ["Іваненка І.І. відрядити"])
+            .build();
+        doc_index.generation = 3;
+        let delta = IndexDelta::new(&doc_index, &[1], &[0]).unwrap();
+        journal.record(&delta).unwrap();
+
+        let pending = journal.pending().unwrap();
+        assert_eq!(pending.base_generation, 3);
+        assert_eq!(pending.changed.len(), 1);
+        assert_eq!(pending.changed[0].0, 1);
+        assert_eq!(pending.changed[0].1.file_name, "Наказ №2 від 11.01.2024.docx");
+        assert_eq!(pending.deleted, vec![0]);
+        assert_eq!(pending.next_doc_id, doc_index.next_doc_id);
+
+        // Збій посеред запису пакета: без End пакет не відтворюється
+        let content = fs::read_to_string(&journal.path).unwrap();
+        let torn: Vec<&str> = content.lines().collect();
+        fs::write(&journal.path, format!("{}\n{}", torn[..torn.len() - 1].join("\n"), "{\"op\":\"end\",")).unwrap();
+        assert!(journal.pending().is_none());
+
+        journal.clear();
+        assert!(!journal.exists());
+    }
+}
@@ -0,0 +1,233 @@
+//! Пошук майже однакових наказів: той самий наказ, збережений двічі під різними назвами
+//! (копія з виправленою опискою, інший формат дати в назві). Для кожного документа під час
+//! індексації обчислюється SimHash - 64-бітний відбиток тексту за трійками слів; у схожих
+//! текстів відбитки відрізняються небагатьма бітами. Документи, відбитки яких відрізняються
+//! не більше ніж на max_distance бітів, об'єднуються в групи (команда duplicates,
+//! GET /api/admin/duplicates).
+
+use crate::atomic_index_manager::AtomicIndexManager;
+use crate::document_record::{DocumentIndex, DocumentRecord};
+use crate::error::IndexError;
+use crate::index_stats;
+use crate::index_store::store_is_empty;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Скільки бітів відбитка можуть відрізнятися у майже однакових документів за замовчуванням:
+/// виправлена описка в короткому наказі змінює 4-5 бітів, різні накази - близько 32
+pub const DEFAULT_MAX_DISTANCE: u32 = 6;
+/// Слів у шинглі (трійки слів стійкі до перестановки абзаців і чутливі до зміни змісту)
+const SHINGLE_WORDS: usize = 3;
+
+static WORD_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\p{L}\p{N}]+").unwrap());
+
+/// SimHash вмісту документа; None - у тексті менше SHINGLE_WORDS слів (порожні й
+/// майже порожні документи однакові між собою, але дублікатами не є)
+pub fn simhash(content: &[String]) -> Option<u64> {
+    let words: Vec<String> = content
+        .iter()
+        .flat_map(|paragraph| WORD_REGEX.find_iter(paragraph).map(|m| m.as_str().to_lowercase()))
+        .collect();
+    if words.len() < SHINGLE_WORDS {
+        return None;
+    }
+
+    let mut weights = [0i64; 64];
+    for shingle in words.windows(SHINGLE_WORDS) {
+        let hash = fnv1a(shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if hash & (1 << bit) != 0 { 1 } else { -1 };
+        }
+    }
+    Some(weights.iter().enumerate().filter(|(_, w)| **w > 0).fold(0, |acc, (bit, _)| acc | (1 << bit)))
+}
+
+/// FNV-1a 64: стабільний між запусками і версіями (на відміну від DefaultHasher)
+fn fnv1a(words: &[String]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in words.join(" ").bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Документ групи
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DuplicateDocument {
+    pub file_path: String,
+    pub file_name: String,
+    pub word_count: usize,
+    pub last_modified: u64,
+}
+
+/// Група майже однакових документів
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DuplicateCluster {
+    /// Найбільша відстань (бітів) між відбитками документів групи; 0 - однаковий текст
+    pub max_distance: u32,
+    pub documents: Vec<DuplicateDocument>,
+}
+
+/// Звіт про майже однакові документи (команда duplicates)
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct DuplicateReport {
+    pub max_distance: u32,
+    /// Документів з відбитком (порожні не порівнюються)
+    pub documents_checked: usize,
+    /// Від найбільших груп до найменших
+    pub clusters: Vec<DuplicateCluster>,
+}
+
+impl DuplicateReport {
+    /// Звіт з активного покоління (файл читається напряму, як у IndexStats::collect)
+    pub fn collect(index_manager: &AtomicIndexManager, max_distance: u32) -> Result<Self, IndexError> {
+        if let Some(store) = index_manager.store() {
+            let doc_index = store.load_documents()?.ok_or_else(store_is_empty)?;
+            return Ok(Self::from_documents(&doc_index, max_distance));
+        }
+
+        let (active_doc_path, _) = index_manager.active_paths();
+        if !Path::new(&active_doc_path).exists() {
+            return Err(IndexError::NotFound(format!("Індекс документів не знайдено: {}", active_doc_path)));
+        }
+        let doc_index: DocumentIndex = index_stats::read_json(&active_doc_path)?;
+        Ok(Self::from_documents(&doc_index, max_distance))
+    }
+
+    /// Групує документи попарним порівнянням відбитків (кілька тисяч наказів - мілісекунди).
+    /// Документам зі старих індексів без відбитка він обчислюється на льоту.
+    pub fn from_documents(doc_index: &DocumentIndex, max_distance: u32) -> Self {
+        let documents: Vec<(&DocumentRecord, u64)> = doc_index
+            .documents
+            .iter()
+            .filter(|d| !d.deleted)
+            .filter_map(|d| Some((d, d.simhash.or_else(|| simhash(&d.content))?)))
+            .collect();
+
+        // Об'єднання пар у групи (union-find): A~B і B~C - одна група, навіть якщо A і C далі
+        let mut parents: Vec<usize> = (0..documents.len()).collect();
+        for i in 0..documents.len() {
+            for j in i + 1..documents.len() {
+                if (documents[i].1 ^ documents[j].1).count_ones() <= max_distance {
+                    let (root_i, root_j) = (find(&mut parents, i), find(&mut parents, j));
+                    parents[root_j] = root_i;
+                }
+            }
+        }
+
+        let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for i in 0..documents.len() {
+            let root = find(&mut parents, i);
+            groups.entry(root).or_default().push(i);
+        }
+
+        let mut clusters: Vec<DuplicateCluster> = groups
+            .into_values()
+            .filter(|members| members.len() > 1)
+            .map(|members| {
+                let max_distance = members
+                    .iter()
+                    .flat_map(|&a| members.iter().map(move |&b| (a, b)))
+                    .map(|(a, b)| (documents[a].1 ^ documents[b].1).count_ones())
+                    .max()
+                    .unwrap_or(0);
+                let mut cluster_documents: Vec<DuplicateDocument> = members
+                    .iter()
+                    .map(|&i| DuplicateDocument {
+                        file_path: documents[i].0.file_path.clone(),
+                        file_name: documents[i].0.file_name.clone(),
+                        word_count: documents[i].0.word_count,
+                        last_modified: documents[i].0.last_modified,
+                    })
+                    .collect();
+                cluster_documents.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+                DuplicateCluster { max_distance, documents: cluster_documents }
+            })
+            .collect();
+        clusters.sort_by(|a, b| {
+            b.documents.len().cmp(&a.documents.len()).then_with(|| a.documents[0].file_path.cmp(&b.documents[0].file_path))
+        });
+
+        Self {
+            max_distance,
+            documents_checked: documents.len(),
+            clusters,
+        }
+    }
+}
+
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+impl std::fmt::Display for DuplicateReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "👯 Майже однакові документи (відстань до {} бітів)", self.max_distance)?;
+        writeln!(f, "   Перевірено документів:     {}", self.documents_checked)?;
+        writeln!(f, "   Груп:                      {}", self.clusters.len())?;
+        for (i, cluster) in self.clusters.iter().enumerate() {
+            writeln!(f)?;
+            let kind = if cluster.max_distance == 0 { "однаковий текст".to_string() } else { format!("відстань {}", cluster.max_distance) };
+            writeln!(f, "   {}. Документів: {}, {}", i + 1, cluster.documents.len(), kind)?;
+            for document in &cluster.documents {
+                writeln!(f, "      - {} ({} слів)", document.file_path, document.word_count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(path: &str, text: &str) -> DocumentRecord {
+        DocumentRecord::from_texts(path, text.split('\n'))
+    }
+
+    #[test]
+    fn test_near_duplicates_clustered() {
+        let order = "НАКАЗ\n1. Надати солдату Петренку Петру Петровичу, стрільцю першого відділення, \
+                     щорічну основну відпустку тривалістю 15 діб з 05.02.2024 року з виїздом до м. Полтава\n\
+                     2. Начальнику фінансової служби виплатити грошове забезпечення за лютий 2024 року\n\
+                     3. Контроль за виконанням наказу покласти на начальника штабу військової частини";
+        let typo_fixed = order.replace("Полтава", "Полтави");
+        let other = "НАКАЗ\n1. Відрядити сержанта Іваненка Івана Івановича до м. Києва для отримання \
+                     матеріальних засобів строком на 5 діб\n2. Витрати на відрядження здійснити за рахунок \
+                     коштів військової частини згідно з кошторисом";
+
+        let mut doc_index = DocumentIndex::new();
+        doc_index.push_document(document("2024/Наказ №12 від 05.02.2024.docx", order));
+        doc_index.push_document(document("2024/Наказ №12 (копія).docx", order));
+        doc_index.push_document(document("2024/Наказ 12 від 2024-02-05.docx", &typo_fixed));
+        doc_index.push_document(document("2024/Наказ №13 від 06.02.2024.docx", other));
+        let removed = doc_index.push_document(document("2024/Наказ №12 (стара копія).docx", order));
+        doc_index.tombstone(removed);
+        // Старий індекс без відбитка
+        doc_index.documents[0].simhash = None;
+        doc_index.push_document(document("2024/порожній.docx", "НАКАЗ"));
+
+        let report = DuplicateReport::from_documents(&doc_index, DEFAULT_MAX_DISTANCE);
+        assert_eq!(report.documents_checked, 4);
+        assert_eq!(report.clusters.len(), 1);
+        let paths: Vec<_> = report.clusters[0].documents.iter().map(|d| d.file_path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["2024/Наказ 12 від 2024-02-05.docx", "2024/Наказ №12 (копія).docx", "2024/Наказ №12 від 05.02.2024.docx"]
+        );
+        assert!(report.clusters[0].max_distance <= DEFAULT_MAX_DISTANCE);
+
+        // Лише однаковий текст
+        let exact = DuplicateReport::from_documents(&doc_index, 0);
+        assert_eq!(exact.clusters[0].documents.len(), 2);
+        assert_eq!(exact.clusters[0].max_distance, 0);
+    }
+}
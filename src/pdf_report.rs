@@ -0,0 +1,224 @@
+//! Звіт PDF про результати пошуку для долучення до справи: запит, дата формування
+//! і знайдені абзаци, згруповані за документами, з номерами пунктів наказу.
+//! Шрифт з кирилицею (DejaVu Sans, fonts/) вбудовано в бінарник; текст переноситься
+//! за шириною гліфів, нові сторінки додаються автоматично.
+
+use crate::result_export::ExportRow;
+use chrono::NaiveDateTime;
+use printpdf::{IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference};
+use ttf_parser::Face;
+
+static FONT: &[u8] = include_bytes!("../fonts/DejaVuSans.ttf");
+
+/// Сторінка A4
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+/// Рівень номера сторінки (від нижнього краю)
+const FOOTER_Y: f32 = 10.0;
+/// Відступ абзаців під назвою документа
+const INDENT: f32 = 6.0;
+
+const TITLE_SIZE: f32 = 14.0;
+const HEADING_SIZE: f32 = 11.0;
+const TEXT_SIZE: f32 = 10.0;
+const NOTE_SIZE: f32 = 8.0;
+
+/// Міліметрів у типографському пункті
+const MM_PER_PT: f32 = 25.4 / 72.0;
+/// Міжрядковий інтервал відносно кегля
+const LINE_SPACING: f32 = 1.35;
+
+/// Назва звіту за замовчуванням
+pub const SEARCH_REPORT_TITLE: &str = "Звіт про результати пошуку";
+
+/// Формує звіт; rows - у порядку результатів (абзаци одного документа йдуть поспіль)
+pub fn render_pdf(query: &str, generated_at: NaiveDateTime, rows: &[ExportRow]) -> Result<Vec<u8>, printpdf::Error> {
+    render_titled_pdf(SEARCH_REPORT_TITLE, query, generated_at, rows)
+}
+
+/// Звіт з власною назвою (наприклад, послужний витяг)
+pub fn render_titled_pdf(title: &str, query: &str, generated_at: NaiveDateTime, rows: &[ExportRow]) -> Result<Vec<u8>, printpdf::Error> {
+    let mut writer = ReportWriter::new(title)?;
+
+    let documents = rows.chunk_by(|a, b| a.file_path == b.file_path).count();
+    writer.write(title, TITLE_SIZE, 0.0);
+    writer.space(2.0);
+    writer.write(&format!("Запит: {}", query), TEXT_SIZE, 0.0);
+    writer.write(&format!("Сформовано: {}", generated_at.format("%d.%m.%Y %H:%M")), TEXT_SIZE, 0.0);
+    writer.write(&format!("Документів: {}, абзаців: {}", documents, rows.len()), TEXT_SIZE, 0.0);
+    writer.space(6.0);
+
+    if rows.is_empty() {
+        writer.write("Нічого не знайдено.", TEXT_SIZE, 0.0);
+    }
+
+    for (number, document) in rows.chunk_by(|a, b| a.file_path == b.file_path).enumerate() {
+        // Назва документа не лишається сама внизу сторінки
+        writer.keep_together(line_height(HEADING_SIZE) + line_height(NOTE_SIZE) + 2.0 * line_height(TEXT_SIZE));
+        writer.write(&format!("{}. {}", number + 1, document[0].file_name), HEADING_SIZE, 0.0);
+        writer.write(&document[0].file_path, NOTE_SIZE, 0.0);
+        writer.space(1.5);
+
+        for row in document {
+            let text = match &row.section {
+                Some(section) => format!("п. {} - {}", section, row.paragraph),
+                None => row.paragraph.clone(),
+            };
+            writer.write(&text, TEXT_SIZE, INDENT);
+            writer.space(1.5);
+        }
+        writer.space(3.0);
+    }
+
+    writer.finish()
+}
+
+fn line_height(size: f32) -> f32 {
+    size * LINE_SPACING * MM_PER_PT
+}
+
+/// Розкладка тексту сторінками: поточна сторінка і позиція рядка
+struct ReportWriter {
+    document: PdfDocumentReference,
+    layer: PdfLayerReference,
+    font: IndirectFontRef,
+    face: Face<'static>,
+    page: usize,
+    /// Верх наступного рядка, мм від нижнього краю
+    y: f32,
+}
+
+impl ReportWriter {
+    fn new(title: &str) -> Result<Self, printpdf::Error> {
+        let (document, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Шар 1");
+        let font = document.add_external_font(FONT)?;
+        let face = Face::parse(FONT, 0).map_err(|_| printpdf::Error::Pdf(printpdf::PdfError::FontFaceError))?;
+        let layer = document.get_page(page).get_layer(layer);
+
+        let writer = Self {
+            document,
+            layer,
+            font,
+            face,
+            page: 1,
+            y: PAGE_HEIGHT - MARGIN,
+        };
+        writer.footer();
+        Ok(writer)
+    }
+
+    /// Пише текст з переносом рядків у межах полів (indent - додатковий лівий відступ)
+    fn write(&mut self, text: &str, size: f32, indent: f32) {
+        let width = PAGE_WIDTH - 2.0 * MARGIN - indent;
+        for line in wrap(&self.face, text, size, width) {
+            self.keep_together(line_height(size));
+            self.y -= line_height(size);
+            self.layer.use_text(line, size, Mm(MARGIN + indent), Mm(self.y), &self.font);
+        }
+    }
+
+    fn space(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    /// Починає нову сторінку, якщо на поточній не вміщується height мм
+    fn keep_together(&mut self, height: f32) {
+        if self.y - height >= MARGIN {
+            return;
+        }
+        let (page, layer) = self.document.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Шар 1");
+        self.layer = self.document.get_page(page).get_layer(layer);
+        self.page += 1;
+        self.y = PAGE_HEIGHT - MARGIN;
+        self.footer();
+    }
+
+    fn footer(&self) {
+        let text = format!("Сторінка {}", self.page);
+        let x = PAGE_WIDTH - MARGIN - text_width(&self.face, &text, NOTE_SIZE);
+        self.layer.use_text(text, NOTE_SIZE, Mm(x), Mm(FOOTER_Y), &self.font);
+    }
+
+    fn finish(self) -> Result<Vec<u8>, printpdf::Error> {
+        self.document.save_to_bytes()
+    }
+}
+
+/// Ширина тексту в мм за шириною гліфів шрифту
+fn text_width(face: &Face, text: &str, size: f32) -> f32 {
+    let units: u32 = text
+        .chars()
+        .map(|c| {
+            face.glyph_index(c)
+                .and_then(|glyph| face.glyph_hor_advance(glyph))
+                .unwrap_or(face.units_per_em() / 2) as u32
+        })
+        .sum();
+    units as f32 / face.units_per_em() as f32 * size * MM_PER_PT
+}
+
+/// Розбиває текст на рядки не ширші за width мм: за пробілами, а надто довге слово - посимвольно
+fn wrap(face: &Face, text: &str, size: f32, width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if text_width(face, &candidate, size) <= width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for c in word.chars() {
+            line.push(c);
+            if text_width(face, &line, size) > width && line.chars().count() > 1 {
+                line.pop();
+                lines.push(std::mem::replace(&mut line, c.to_string()));
+            }
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_paragraphs_wrap_and_continue_on_new_pages() {
+        let face = Face::parse(FONT, 0).unwrap();
+        let text = "солдата Петренка Петра Петровича призначити на посаду стрільця ".repeat(10);
+        let lines = wrap(&face, &text, TEXT_SIZE, 100.0);
+        assert!(lines.len() > 5);
+        assert!(lines.iter().all(|line| text_width(&face, line, TEXT_SIZE) <= 100.0));
+        assert_eq!(lines.join(" "), text.trim_end());
+        // Слово, довше за рядок, ділиться посимвольно
+        assert_eq!(wrap(&face, &"ш".repeat(200), TEXT_SIZE, 50.0).concat(), "ш".repeat(200));
+
+        let rows: Vec<ExportRow> = (0..60)
+            .map(|i| ExportRow {
+                file_name: format!("наказ №{} від 05.02.2024.docx", i / 5),
+                file_path: format!("2024/наказ №{} від 05.02.2024.docx", i / 5),
+                date: Some("05.02.2024".to_string()),
+                section: Some(format!("{}", i % 5 + 1)),
+                paragraph: text.clone(),
+            })
+            .collect();
+        let generated_at = NaiveDateTime::parse_from_str("2024-02-05 10:30", "%Y-%m-%d %H:%M").unwrap();
+        let pdf = render_pdf("Петренко", generated_at, &rows).unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+
+        let mut writer = ReportWriter::new("тест").unwrap();
+        for row in &rows {
+            writer.write(&row.paragraph, TEXT_SIZE, INDENT);
+        }
+        assert!(writer.page > 1);
+        assert!(writer.y >= MARGIN);
+    }
+}
@@ -0,0 +1,79 @@
+//! Постійні посилання на пункти наказу: /doc/<doc_id>#p-2.3 відкриває документ і
+//! прокручує до пункту 2.3. Номер пункту береться з нумерації, збереженої в тексті абзаців
+//! під час індексації (див. docx_parser), а документ - за стабільним doc_id, тож посилання
+//! переживає заміну файлу і зсув абзаців. Після повної перебудови індексу doc_id змінюються.
+
+use crate::document_record::Paragraph;
+use crate::result_export::paragraph_section;
+
+/// Префікс якоря пункту в посиланні (#p-2.3)
+pub const SECTION_ANCHOR_PREFIX: &str = "p-";
+
+/// Номер пункту абзацу: власний або найближчого пронумерованого абзацу над ним
+pub fn section_at(paragraphs: &[Paragraph], position: usize) -> Option<String> {
+    paragraphs
+        .get(..=position)?
+        .iter()
+        .rev()
+        .find_map(|p| paragraph_section(&p.text))
+        .map(str::to_string)
+}
+
+/// Абзац, з якого починається пункт section. Пункти в лапках («1. Внести зміни...») - цитати
+/// іншого наказу, вони беруться, лише якщо власного пункту з таким номером немає
+pub fn resolve_section(paragraphs: &[Paragraph], section: &str) -> Option<usize> {
+    let section = section.trim().trim_end_matches('.');
+    let matches = |p: &Paragraph| paragraph_section(&p.text) == Some(section);
+    let quoted = |p: &Paragraph| p.text.trim_start().starts_with('«');
+
+    paragraphs
+        .iter()
+        .position(|p| matches(p) && !quoted(p))
+        .or_else(|| paragraphs.iter().position(matches))
+}
+
+/// Посилання на документ (і пункт); колекція - для документів не з основної колекції
+pub fn permalink(doc_id: u64, section: Option<&str>, collection: Option<&str>) -> String {
+    let mut link = format!("/doc/{}", doc_id);
+    if let Some(collection) = collection {
+        link.push_str("?collection=");
+        link.push_str(&urlencoding::encode(collection));
+    }
+    if let Some(section) = section {
+        link.push('#');
+        link.push_str(SECTION_ANCHOR_PREFIX);
+        link.push_str(section);
+    }
+    link
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraphs(texts: &[&str]) -> Vec<Paragraph> {
+        texts.iter().map(|text| Paragraph::new(text.to_string())).collect()
+    }
+
+    #[test]
+    fn test_sections_resolve_by_stored_numbering() {
+        let document = paragraphs(&[
+            "НАКАЗ №16",
+            "1. Внести зміни до наказу №3:",
+            "«2.3. Відрядити Іваненка І.І.»",
+            "2. По особовому складу",
+            "2.3. Надати відпустку Петренку П.П.",
+            "строком на 10 діб",
+        ]);
+
+        assert_eq!(resolve_section(&document, "2.3"), Some(4));
+        assert_eq!(resolve_section(&document, "1."), Some(1));
+        assert_eq!(resolve_section(&document, "7"), None);
+        assert_eq!(section_at(&document, 5).as_deref(), Some("2.3"));
+        assert_eq!(section_at(&document, 0), None);
+        assert_eq!(section_at(&document, 99), None);
+
+        assert_eq!(permalink(42, Some("2.3"), None), "/doc/42#p-2.3");
+        assert_eq!(permalink(42, None, Some("Розпорядження")), "/doc/42?collection=%D0%A0%D0%BE%D0%B7%D0%BF%D0%BE%D1%80%D1%8F%D0%B4%D0%B6%D0%B5%D0%BD%D0%BD%D1%8F");
+    }
+}
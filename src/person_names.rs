@@ -0,0 +1,183 @@
+//! Розпізнавання ПІБ у знайдених абзацах. Коли запит схожий на ПІБ ("Петренко Іван"),
+//! збіги групуються за повним ім'ям людини з тексту - однофамільці (ПЕТРЕНКО Іван
+//! Миколайович і ПЕТРЕНКО Ігор Петрович) не перемішуються в результатах.
+//!
+//! Ім'я в тексті наказу - одна з форм:
+//! - ПРІЗВИЩЕ Ім'я [По батькові] або ПРІЗВИЩЕ І.П. (прізвище великими літерами)
+//! - Прізвище І.П.
+//! - Прізвище Ім'я По батькові (по батькові - із закінченням -ович/-івна тощо)
+//!
+//! Відмінки не розрізняють людей: ключ групи складається з основ слів після стемінгу
+//! ("ПЕТРЕНКУ Івану" і "ПЕТРЕНКА Івана" - одна людина). Ім'я з ініціалами - окрема група:
+//! "ПЕТРЕНКО І.М." може бути і Іваном, і Ігорем.
+
+use crate::stemmer;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+/// Слово або ініціали ("І.М.", "І. М.")
+static TOKEN_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\p{Lu}\.\s?\p{Lu}\.|[\p{L}'’ʼ-]+").unwrap());
+
+/// Закінчення по батькові в будь-якому відмінку
+static PATRONYMIC_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:ович|евич|йович|івн|ївн|овн)\p{Ll}*$").unwrap());
+
+/// Людина, знайдена в абзаці
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PersonName {
+    /// Ключ групи: основи слів імені ("петренк іван миколайович")
+    pub key: String,
+    /// Ім'я так, як його написано в тексті
+    pub name: String,
+}
+
+/// Запит схожий на ПІБ: 2-3 слова з літер (або ініціали), хоча б одне - довше за ініціал
+pub fn looks_like_person_query(query: &str) -> bool {
+    let words: Vec<&str> = query.split_whitespace().collect();
+    (2..=3).contains(&words.len())
+        && words
+            .iter()
+            .all(|word| word.chars().all(|c| c.is_alphabetic() || matches!(c, '.' | '-' | '\'' | '’' | 'ʼ')))
+        && words.iter().any(|word| word.chars().filter(|c| c.is_alphabetic()).count() > 2)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TokenKind {
+    Initials,
+    /// Слово великими літерами (прізвище в наказі)
+    Upper,
+    /// Слово з великої літери
+    Capitalized,
+    Other,
+}
+
+struct Token<'a> {
+    text: &'a str,
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+impl Token<'_> {
+    fn is_patronymic(&self) -> bool {
+        self.kind == TokenKind::Capitalized && PATRONYMIC_REGEX.is_match(self.text)
+    }
+}
+
+fn token_kind(text: &str) -> TokenKind {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    if text.ends_with('.') {
+        TokenKind::Initials
+    } else if letters.len() >= 2 && letters.iter().all(|c| c.is_uppercase()) {
+        TokenKind::Upper
+    } else if letters.first().is_some_and(|c| c.is_uppercase()) && letters.len() >= 2 {
+        TokenKind::Capitalized
+    } else {
+        TokenKind::Other
+    }
+}
+
+/// Перше ім'я в тексті, що містить усі терміни запиту (основи після стемінгу)
+pub fn resolve(text: &str, terms: &[String]) -> Option<PersonName> {
+    let tokens: Vec<Token> = TOKEN_REGEX
+        .find_iter(text)
+        .map(|m| Token { text: m.as_str(), kind: token_kind(m.as_str()), start: m.start(), end: m.end() })
+        .collect();
+    // Частини імені йдуть підряд через пробіли, без розділових знаків між ними
+    let adjacent = |a: &Token, b: &Token| text[a.end..b.start].chars().all(char::is_whitespace);
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let len = name_length(&tokens[i..], adjacent);
+        if len == 0 {
+            i += 1;
+            continue;
+        }
+        let parts = &tokens[i..i + len];
+        let normalized: Vec<String> = parts.iter().map(|t| t.text.to_lowercase().replace('\'', "")).collect();
+        if terms.iter().all(|term| normalized.iter().any(|word| word.contains(term.as_str()))) {
+            return Some(PersonName {
+                key: parts.iter().map(name_part_key).collect::<Vec<_>>().join(" "),
+                name: text[parts[0].start..parts[len - 1].end].to_string(),
+            });
+        }
+        i += len;
+    }
+    None
+}
+
+/// Скільки токенів з початку зрізу складають ім'я (0 - не ім'я)
+fn name_length(tokens: &[Token], adjacent: impl Fn(&Token, &Token) -> bool) -> usize {
+    let next = |k: usize| tokens.get(k).filter(|t| adjacent(&tokens[k - 1], t));
+    let (Some(surname), Some(second)) = (tokens.first(), next(1)) else {
+        return 0;
+    };
+    match (surname.kind, second.kind) {
+        (TokenKind::Upper | TokenKind::Capitalized, TokenKind::Initials) => 2,
+        (TokenKind::Upper, TokenKind::Capitalized) => match next(2) {
+            Some(third) if third.is_patronymic() => 3,
+            _ => 2,
+        },
+        (TokenKind::Capitalized, TokenKind::Capitalized) => match next(2) {
+            Some(third) if third.is_patronymic() => 3,
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// Основа слова імені; ініціали - самі літери ("і.м." -> "і м")
+fn name_part_key(token: &Token) -> String {
+    if token.kind == TokenKind::Initials {
+        let letters: Vec<String> = token
+            .text
+            .chars()
+            .filter(|c| c.is_alphabetic())
+            .flat_map(char::to_lowercase)
+            .map(String::from)
+            .collect();
+        letters.join(" ")
+    } else {
+        stemmer::stem_word(&token.text.replace(['\'', '’', 'ʼ'], ""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(query: &str) -> Vec<String> {
+        query.split_whitespace().map(stemmer::stem_word).collect()
+    }
+
+    #[test]
+    fn test_homonyms_resolved_to_different_people() {
+        assert!(looks_like_person_query("Петренко Іван"));
+        assert!(looks_like_person_query("петренко і.м."));
+        assert!(!looks_like_person_query("петренко"));
+        assert!(!looks_like_person_query("наказ №15 від 2024"));
+        assert!(!looks_like_person_query("відпустка солдату Петренку за 2024 рік"));
+
+        let terms = terms("Петренко");
+        let ivan = resolve("1. Надати відпустку солдату ПЕТРЕНКУ Івану Миколайовичу з 05.02.2024.", &terms).unwrap();
+        let ivan_genitive = resolve("Призначити солдата ПЕТРЕНКА Івана Миколайовича стрільцем", &terms).unwrap();
+        let ihor = resolve("Відрядити сержанта ПЕТРЕНКА Ігоря Петровича", &terms).unwrap();
+        assert_eq!(ivan.name, "ПЕТРЕНКУ Івану Миколайовичу");
+        assert_eq!(ivan.key, ivan_genitive.key);
+        assert_ne!(ivan.key, ihor.key);
+
+        // Ініціали - окрема група; без по батькові ім'я з великої літери не вважається ПІБ
+        let initials = resolve("Петренко І.М. - 2 доби", &terms).unwrap();
+        assert_eq!((initials.name.as_str(), initials.key.as_str()), ("Петренко І.М.", "петренк і м"));
+        let title = resolve("Петренко Іван Миколайович, стрілець", &terms).unwrap();
+        assert_eq!(title.key, ivan.key);
+        assert_eq!(resolve("Призначити Петренка відповідальним", &terms), None);
+        // Ім'я, що не містить термінів запиту, пропускається
+        let other = resolve("КОВАЛЕНКУ Олегу Івановичу та ПЕТРЕНКУ Ігорю Петровичу", &terms).unwrap();
+        assert_eq!(other.key, ihor.key);
+        // Кома розділяє людей у переліку
+        assert_eq!(resolve("ПЕТРЕНКУ, Іваненку", &terms), None);
+    }
+}
@@ -0,0 +1,73 @@
+//! Переписування пошукових запитів до обробки: наприклад, внутрішній жаргон
+//! ("відпустка по догляду" -> "відпустка для догляду") замінюється офіційним
+//! формулюванням наказів. Застосовується в SearchEngine::process_search_query,
+//! до стемінгу. Правила з конфігурації - [[search.query_rewrites]]; для вбудовування
+//! бібліотеки можна передати власну реалізацію QueryRewriter.
+
+use crate::config::QueryRewriteConfig;
+use regex::Regex;
+use std::borrow::Cow;
+
+/// Перетворення запиту перед пошуком
+pub trait QueryRewriter: Send + Sync {
+    fn rewrite<'a>(&self, query: &'a str) -> Cow<'a, str>;
+}
+
+/// Правила "регулярний вираз -> заміна", що застосовуються по черзі
+pub struct RegexRewriter {
+    rules: Vec<(Regex, String)>,
+}
+
+impl RegexRewriter {
+    /// Компілює правила; заміна може посилатися на групи виразу ($1, ${назва})
+    pub fn new(rules: &[QueryRewriteConfig]) -> Result<Self, regex::Error> {
+        let rules = rules
+            .iter()
+            .map(|rule| Ok((Regex::new(&rule.pattern)?, rule.replacement.clone())))
+            .collect::<Result<_, regex::Error>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+impl QueryRewriter for RegexRewriter {
+    fn rewrite<'a>(&self, query: &'a str) -> Cow<'a, str> {
+        let mut query = Cow::Borrowed(query);
+        for (pattern, replacement) in &self.rules {
+            if let Cow::Owned(rewritten) = pattern.replace_all(&query, replacement.as_str()) {
+                query = Cow::Owned(rewritten);
+            }
+        }
+        query
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, replacement: &str) -> QueryRewriteConfig {
+        QueryRewriteConfig {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rules_apply_in_order() {
+        let rewriter = RegexRewriter::new(&[
+            rule(r"(?i)\bвідпустка по догляду\b", "відпустка для догляду"),
+            rule(r"(?i)\bЗСУ\b", "Збройних Сил України"),
+            rule(r"\bдогляду\b", "догляду за дитиною"),
+        ])
+        .unwrap();
+
+        assert_eq!(rewriter.rewrite("Відпустка по догляду зсу"), "відпустка для догляду за дитиною Збройних Сил України");
+        // Запит без збігів не копіюється
+        assert!(matches!(rewriter.rewrite("наказ №16"), Cow::Borrowed("наказ №16")));
+        assert!(RegexRewriter::new(&[rule("(", "")]).is_err());
+    }
+}
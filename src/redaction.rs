@@ -0,0 +1,236 @@
+//! Маскування чутливих даних (ІПН, телефони, адреси) у відповідях API: тексти абзаців
+//! проходять через правила [[redaction.patterns]] перед відправленням, індекс не змінюється.
+//! Користувачі з роллю "unredacted" бачать текст як є: роль дає пароль
+//! redaction.unredacted_password_sha256 (POST /api/role/unredacted ставить cookie з
+//! випадковим токеном, який діє UNREDACTED_ROLE_TTL). Бот Telegram ролі не має - його
+//! відповіді маскуються завжди.
+
+use crate::config::{password_matches, RedactionConfig, RedactionRuleConfig};
+use crate::search_engine::SearchEngineResult;
+use crate::session;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Cookie з токеном ролі "unredacted"
+pub const UNREDACTED_COOKIE: &str = "blazing_unredacted";
+
+/// Скільки діє виданий токен ролі; далі пароль вводиться знову
+pub const UNREDACTED_ROLE_TTL: Duration = Duration::from_secs(8 * 60 * 60);
+
+/// Вбудовані правила (діють, якщо в конфігурації не задано власних). Телефони перевіряються
+/// раніше за ІПН: номер 0671234567 теж має 10 цифр
+pub fn default_patterns() -> Vec<RedactionRuleConfig> {
+    let rule = |name: &str, pattern: &str| RedactionRuleConfig {
+        name: name.to_string(),
+        pattern: pattern.to_string(),
+        replacement: None,
+    };
+    vec![
+        rule("телефон", r"(?:\+?38[\s-]?)?\(?0\d{2}\)?[\s-]?\d{3}[\s-]?\d{2}[\s-]?\d{2}\b"),
+        rule("ІПН", r"\b\d{10}\b"),
+        rule(
+            "адреса",
+            r"(?i)\b(?:вулиця|вул|проспект|просп|провулок|пров|бульвар|бульв)\.?\s+[^,;\n]+(?:,\s*(?:будинок|буд|б)\.?\s*\d+[\p{L}/\d]*)?(?:,\s*(?:квартира|кв)\.?\s*\d+)?",
+        ),
+    ]
+}
+
+/// Правила маскування: "регулярний вираз -> заміна", по черзі
+pub struct Redactor {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Redactor {
+    /// Компілює правила; заміна за замовчуванням - "[назва правила]"
+    pub fn new(rules: &[RedactionRuleConfig]) -> Result<Self, regex::Error> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let replacement = rule.replacement.clone().unwrap_or_else(|| format!("[{}]", rule.name));
+                Ok((Regex::new(&rule.pattern)?, replacement))
+            })
+            .collect::<Result<_, regex::Error>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for (pattern, replacement) in &self.rules {
+            // Заміна буквальна: "$" у масці не означає групу виразу
+            if let Cow::Owned(redacted) = pattern.replace_all(&text, regex::NoExpand(replacement)) {
+                text = Cow::Owned(redacted);
+            }
+        }
+        text
+    }
+
+    /// Чи містить текст дані, які маскуються (слова індексу з ІПН чи номером телефону
+    /// не показуються в підказках і переліках термінів)
+    pub fn matches(&self, text: &str) -> bool {
+        self.rules.iter().any(|(pattern, _)| pattern.is_match(text))
+    }
+
+    /// Маскує знайдені абзаци і повний текст документів - з них будуються відповіді пошуку,
+    /// вивантаження і послужні витяги
+    pub fn redact_results(&self, results: &mut [SearchEngineResult]) {
+        for result in results {
+            for found in &mut result.matches {
+                if let Cow::Owned(redacted) = self.redact(&found.context) {
+                    found.context = redacted;
+                }
+            }
+            for paragraph in &mut result.all_paragraphs {
+                if let Cow::Owned(redacted) = self.redact(&paragraph.text) {
+                    paragraph.text = redacted;
+                }
+            }
+        }
+    }
+}
+
+/// Хто бачить дані без маскування
+#[derive(Default)]
+pub struct RedactionPolicy {
+    /// None - маскування вимкнено
+    redactor: Option<Arc<Redactor>>,
+    /// SHA-256 пароля ролі "unredacted"; None - ролі немає, маскування для всіх
+    unredacted_password_sha256: Option<String>,
+    /// Видані токени ролі і час, до якого вони діють. Токени живуть лише в пам'яті:
+    /// перезапуск сервера чи зміна пароля скасовує всі ролі
+    grants: Mutex<HashMap<String, Instant>>,
+    role_ttl: Duration,
+}
+
+impl RedactionPolicy {
+    pub fn from_config(config: &RedactionConfig) -> Result<Self, regex::Error> {
+        if !config.enabled {
+            return Ok(Self::default());
+        }
+        Ok(Self {
+            redactor: Some(Arc::new(Redactor::new(&config.patterns)?)),
+            unredacted_password_sha256: config.unredacted_password_sha256.clone(),
+            grants: Mutex::default(),
+            role_ttl: UNREDACTED_ROLE_TTL,
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.redactor.is_some()
+    }
+
+    /// Чи можна отримати роль "unredacted" (задано пароль)
+    pub fn role_available(&self) -> bool {
+        self.enabled() && self.unredacted_password_sha256.is_some()
+    }
+
+    /// Маскування для каналів без ролей (бот Telegram)
+    pub fn redactor(&self) -> Option<Arc<Redactor>> {
+        self.redactor.clone()
+    }
+
+    /// Новий токен ролі для cookie, якщо пароль правильний. Кожен вхід отримує власний
+    /// токен, що діє role_ttl
+    pub fn unlock(&self, password: &str) -> Option<String> {
+        let expected = self.unredacted_password_sha256.as_deref()?;
+        if !self.enabled() || !password_matches(password, expected) {
+            return None;
+        }
+        let token = session::new_token();
+        let now = Instant::now();
+        let mut grants = self.grants.lock().unwrap();
+        grants.retain(|_, expires| *expires > now);
+        grants.insert(token.clone(), now + self.role_ttl);
+        Some(token)
+    }
+
+    /// Скасовує токен ролі (вихід з ролі)
+    pub fn revoke(&self, token: &str) {
+        self.grants.lock().unwrap().remove(token);
+    }
+
+    /// Маскування для запиту з cookie ролі; None - текст віддається як є
+    pub fn for_token(&self, token: Option<&str>) -> Option<Arc<Redactor>> {
+        let redactor = self.redactor.as_ref()?;
+        let unredacted = token.is_some_and(|token| {
+            self.grants.lock().unwrap().get(token).is_some_and(|expires| *expires > Instant::now())
+        });
+        if unredacted {
+            None
+        } else {
+            Some(Arc::clone(redactor))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::password_sha256;
+    use crate::document_record::Paragraph;
+    use crate::search_engine::SearchEngineMatch;
+
+    #[test]
+    fn test_sensitive_fields_masked_unless_unredacted() {
+        let redactor = Redactor::new(&default_patterns()).unwrap();
+        assert_eq!(
+            redactor.redact("солдат Петренко П.П., РНОКПП 3012345678, тел. +38 (067) 123-45-67, 0501234567"),
+            "солдат Петренко П.П., РНОКПП [ІПН], тел. [телефон], [телефон]"
+        );
+        assert_eq!(
+            redactor.redact("проживає: м. Полтава, вул. Соборності, буд. 12а, кв. 5; прибув 05.02.2024"),
+            "проживає: м. Полтава, [адреса]; прибув 05.02.2024"
+        );
+        // Номер наказу і дати не маскуються, текст без збігів не копіюється
+        assert!(matches!(redactor.redact("Наказ №16 від 11.04.2024"), Cow::Borrowed(_)));
+        assert!(redactor.matches("3012345678") && !redactor.matches("11.04.2024"));
+
+        let mut results = vec![SearchEngineResult {
+            doc_id: 0,
+            file_name: "наказ.docx".to_string(),
+            file_path: "2024/наказ.docx".to_string(),
+            matches: vec![SearchEngineMatch { context: "Петренко, ІПН 3012345678".to_string(), position: 0 }],
+            all_paragraphs: vec![Paragraph::new("Петренко, ІПН 3012345678".to_string())],
+            file_size: 0,
+            last_modified: 0,
+            category: None,
+            date: None,
+        }];
+        redactor.redact_results(&mut results);
+        assert_eq!(results[0].matches[0].context, "Петренко, ІПН [ІПН]");
+        assert_eq!(results[0].all_paragraphs[0].text, "Петренко, ІПН [ІПН]");
+
+        let config = RedactionConfig {
+            enabled: true,
+            unredacted_password_sha256: Some(password_sha256("секрет").to_uppercase()),
+            patterns: vec![RedactionRuleConfig {
+                name: "ІПН".to_string(),
+                pattern: r"\d{10}".to_string(),
+                replacement: Some("██████████".to_string()),
+            }],
+        };
+        let mut policy = RedactionPolicy::from_config(&config).unwrap();
+        assert!(policy.unlock("не той").is_none());
+        let token = policy.unlock("секрет").unwrap();
+        assert!(policy.for_token(Some(&token)).is_none());
+        assert!(policy.for_token(Some(&password_sha256("секрет"))).is_some());
+        assert_eq!(policy.for_token(None).unwrap().redact("ІПН 3012345678"), "ІПН ██████████");
+
+        // Кожен вхід - окремий токен: вихід одного користувача не знімає роль з іншого
+        let other = policy.unlock("секрет").unwrap();
+        assert_ne!(token, other);
+        policy.revoke(&token);
+        assert!(policy.for_token(Some(&token)).is_some());
+        assert!(policy.for_token(Some(&other)).is_none());
+
+        // Прострочений токен більше не дає ролі
+        policy.role_ttl = Duration::ZERO;
+        let expired = policy.unlock("секрет").unwrap();
+        assert!(policy.for_token(Some(&expired)).is_some());
+
+        let disabled = RedactionPolicy::from_config(&RedactionConfig { enabled: false, ..config }).unwrap();
+        assert!(disabled.for_token(None).is_none() && !disabled.role_available());
+    }
+}
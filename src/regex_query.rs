@@ -0,0 +1,109 @@
+//! Пошук регулярним виразом (mode: "regex" у /api/search) - для тих, кому мало слів і
+//! операторів запиту: номери наказів за шаблоном, дати, частини слів. Вираз не розкладається
+//! на слова інвертованого індексу, тож пошук переглядає абзаци документів від найновіших (з
+//! обмеженням часу search.query_timeout_ms), а дорогий вираз перевіряється лише в абзацах,
+//! що містять обов'язковий фрагмент виразу, якщо такий є ("наказ №\d+" - "наказ №").
+
+use regex::{Regex, RegexBuilder};
+use regex_syntax::hir::{Hir, HirKind};
+use regex_syntax::ParserBuilder;
+
+/// Найбільший розмір скомпільованого виразу, байт: довгі повторення ("\w{1000}") відхиляються
+/// ще до пошуку
+pub const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Регулярний вираз запиту; без різниці в регістрі, як і звичайний пошук
+#[derive(Debug, Clone)]
+pub struct RegexQuery {
+    regex: Regex,
+    /// Фрагмент (нижній регістр), без якого в абзаці не може бути збігу
+    literal: Option<String>,
+}
+
+impl RegexQuery {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .dfa_size_limit(REGEX_SIZE_LIMIT)
+            .build()?;
+        Ok(Self { regex, literal: required_literal(pattern) })
+    }
+
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// Чи є збіг в абзаці; paragraph_lower - той самий текст у нижньому регістрі (для
+    /// швидкої перевірки фрагмента перед виразом)
+    pub fn matches_paragraph(&self, paragraph: &str, paragraph_lower: &str) -> bool {
+        if let Some(literal) = &self.literal {
+            if !paragraph_lower.contains(literal.as_str()) {
+                return false;
+            }
+        }
+        self.regex.is_match(paragraph)
+    }
+}
+
+/// Найдовший фрагмент, що є в кожному збігу виразу (нижній регістр); None - такого немає
+/// ("а|б", "\d+") або вираз не вдалося розібрати
+fn required_literal(pattern: &str) -> Option<String> {
+    // Без різниці в регістрі букви стали б класами ("[аА]") - фрагмент береться як написано
+    let hir = ParserBuilder::new().build().parse(pattern).ok()?;
+    let mut literals = Vec::new();
+    collect_literals(&hir, &mut literals);
+    literals
+        .into_iter()
+        .filter(|literal| !literal.trim().is_empty())
+        .max_by_key(|literal| literal.chars().count())
+        .map(|literal| literal.to_lowercase())
+}
+
+/// Фрагменти, обов'язкові в кожному збігу: сусідні літерали послідовності склеюються
+fn collect_literals(hir: &Hir, literals: &mut Vec<String>) {
+    match hir.kind() {
+        HirKind::Literal(literal) => literals.push(String::from_utf8_lossy(&literal.0).into_owned()),
+        HirKind::Capture(capture) => collect_literals(&capture.sub, literals),
+        HirKind::Repetition(repetition) if repetition.min > 0 => collect_literals(&repetition.sub, literals),
+        HirKind::Concat(parts) => {
+            let mut run = String::new();
+            for part in parts {
+                match part.kind() {
+                    HirKind::Literal(literal) => run.push_str(&String::from_utf8_lossy(&literal.0)),
+                    _ => {
+                        literals.push(std::mem::take(&mut run));
+                        collect_literals(part, literals);
+                    }
+                }
+            }
+            literals.push(run);
+        }
+        // Альтернативи, класи символів, необов'язкові частини - жодного спільного фрагмента
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_literal_and_matching() {
+        assert_eq!(required_literal(r"Наказ №\s*\d+"), Some("наказ №".to_string()));
+        assert_eq!(required_literal(r"(відпуст(ка|ки))+ \d{2}"), Some("відпуст".to_string()));
+        assert_eq!(required_literal(r"\d{2}\.\d{2}\.2024"), Some(".2024".to_string()));
+        assert_eq!(required_literal("рапорт|наказ"), None);
+        assert_eq!(required_literal(r"(наказ)?\d+"), None);
+        assert_eq!(required_literal("[абв]+"), None);
+
+        let query = RegexQuery::new(r"наказ №\s*\d+").unwrap();
+        let paragraph = "Згідно з НАКАЗОМ №16 та Наказ № 25";
+        assert!(query.matches_paragraph(paragraph, &paragraph.to_lowercase()));
+        let paragraph = "Наказ без номера";
+        assert!(!query.matches_paragraph(paragraph, &paragraph.to_lowercase()));
+
+        assert!(RegexQuery::new("наказ(").is_err());
+        assert!(RegexQuery::new(r"\w{1000}{1000}").is_err());
+    }
+}
@@ -0,0 +1,284 @@
+//! Оцінки релевантності: користувач позначає знайдений документ як доречний (👍) чи ні (👎)
+//! для свого запиту. Оцінка зберігається для кожної пари (термін запиту після стемінгу,
+//! документ) у relevance_feedback.json у папці індексів, тож діє і для інших запитів з тим
+//! самим терміном ("Петренко", "Петренка П.П."). Впорядкування за датою лишається головним:
+//! документ з перевагою 👍 піднімається, з перевагою 👎 - опускається не більше ніж на
+//! search.feedback_max_shift позицій. Зведення для налаштування - GET /api/admin/feedback.
+
+use crate::search_engine::SearchEngineResult;
+use crate::tr;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// На скільки позицій оцінки можуть зсунути документ за замовчуванням
+pub const DEFAULT_FEEDBACK_MAX_SHIFT: usize = 3;
+
+/// Оцінки однієї пари (термін, документ)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct FeedbackCounts {
+    pub relevant: u32,
+    pub irrelevant: u32,
+    /// Unix timestamp останньої оцінки
+    pub updated_at: u64,
+}
+
+impl FeedbackCounts {
+    /// Перевага доречних оцінок над недоречними
+    pub fn net(&self) -> i64 {
+        i64::from(self.relevant) - i64::from(self.irrelevant)
+    }
+}
+
+/// Рядок зведення оцінок
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FeedbackEntry {
+    pub term: String,
+    pub file_path: String,
+    pub relevant: u32,
+    pub irrelevant: u32,
+    pub net: i64,
+    pub updated_at: u64,
+}
+
+/// Зведення оцінок: загальні суми і пари з найбільшою кількістю оцінок
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct FeedbackSummary {
+    /// Пар (термін, документ) з оцінками
+    pub pairs: usize,
+    pub relevant: u64,
+    pub irrelevant: u64,
+    pub entries: Vec<FeedbackEntry>,
+}
+
+/// Оцінки за термінами: термін -> шлях документа -> оцінки
+type FeedbackPairs = BTreeMap<String, BTreeMap<String, FeedbackCounts>>;
+
+pub struct RelevanceFeedback {
+    path: String,
+    /// 0 - оцінки зберігаються, але на впорядкування не впливають
+    max_shift: AtomicUsize,
+    pairs: Mutex<FeedbackPairs>,
+    /// Зростає з кожною оцінкою: впорядкування могло змінитися (див. search_etag)
+    revision: AtomicU64,
+}
+
+impl RelevanceFeedback {
+    /// Завантажує збережені оцінки; пошкоджений файл - попередження і порожні оцінки
+    pub fn load(path: String) -> Self {
+        let pairs = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("{}", tr!(FeedbackLoadFailed, path, e));
+                FeedbackPairs::new()
+            }),
+            Err(e) if Path::new(&path).exists() => {
+                warn!("{}", tr!(FeedbackLoadFailed, path, e));
+                FeedbackPairs::new()
+            }
+            Err(_) => FeedbackPairs::new(),
+        };
+        Self {
+            path,
+            max_shift: AtomicUsize::new(DEFAULT_FEEDBACK_MAX_SHIFT),
+            pairs: Mutex::new(pairs),
+            revision: AtomicU64::new(0),
+        }
+    }
+
+    pub fn set_max_shift(&self, max_shift: usize) {
+        self.max_shift.store(max_shift, Ordering::Relaxed);
+    }
+
+    /// Записує оцінку документа для кожного терміна запиту і зберігає файл
+    pub fn record(&self, terms: &[String], file_path: &str, relevant: bool) {
+        if terms.is_empty() || file_path.is_empty() {
+            return;
+        }
+
+        let mut pairs = self.lock();
+        let now = unix_now();
+        for term in unique(terms) {
+            let counts = pairs.entry(term.clone()).or_default().entry(file_path.to_string()).or_default();
+            if relevant {
+                counts.relevant += 1;
+            } else {
+                counts.irrelevant += 1;
+            }
+            counts.updated_at = now;
+        }
+        self.save(&pairs);
+        self.revision.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    /// Зсув документа для запиту: сума переваг за всіма термінами, обмежена max_shift
+    /// (додатний - вгору по списку)
+    pub fn boost(&self, terms: &[String], file_path: &str) -> i64 {
+        let max_shift = self.max_shift.load(Ordering::Relaxed) as i64;
+        Self::boost_in(&self.lock(), terms, file_path).clamp(-max_shift, max_shift)
+    }
+
+    /// Зсуває результати відповідно до оцінок, не змінюючи порядку решти
+    pub fn rerank(&self, results: &mut Vec<SearchEngineResult>, terms: &[String]) {
+        let max_shift = self.max_shift.load(Ordering::Relaxed) as i64;
+        if max_shift == 0 || results.len() < 2 {
+            return;
+        }
+
+        let pairs = self.lock();
+        if pairs.is_empty() {
+            return;
+        }
+        let shifts: Vec<i64> = results
+            .iter()
+            .map(|result| Self::boost_in(&pairs, terms, &result.file_path).clamp(-max_shift, max_shift))
+            .collect();
+        drop(pairs);
+
+        if shifts.iter().all(|&shift| shift == 0) {
+            return;
+        }
+        // Піднятий документ стає перед тим, чию позицію зайняв, опущений - після
+        let mut keyed: Vec<_> = std::mem::take(results)
+            .into_iter()
+            .zip(shifts)
+            .enumerate()
+            .map(|(position, (result, shift))| ((position as i64 - shift, -shift), result))
+            .collect();
+        keyed.sort_by_key(|(key, _)| *key);
+        results.extend(keyed.into_iter().map(|(_, result)| result));
+    }
+
+    /// Зведення: суми і limit пар з найбільшою кількістю оцінок
+    pub fn summary(&self, limit: usize) -> FeedbackSummary {
+        let pairs = self.lock();
+        let mut summary = FeedbackSummary::default();
+        let mut entries = Vec::new();
+        for (term, documents) in pairs.iter() {
+            for (file_path, counts) in documents {
+                summary.pairs += 1;
+                summary.relevant += u64::from(counts.relevant);
+                summary.irrelevant += u64::from(counts.irrelevant);
+                entries.push(FeedbackEntry {
+                    term: term.clone(),
+                    file_path: file_path.clone(),
+                    relevant: counts.relevant,
+                    irrelevant: counts.irrelevant,
+                    net: counts.net(),
+                    updated_at: counts.updated_at,
+                });
+            }
+        }
+        entries.sort_by_key(|e| std::cmp::Reverse(e.relevant + e.irrelevant));
+        entries.truncate(limit);
+        summary.entries = entries;
+        summary
+    }
+
+    fn boost_in(pairs: &FeedbackPairs, terms: &[String], file_path: &str) -> i64 {
+        unique(terms)
+            .filter_map(|term| pairs.get(term)?.get(file_path))
+            .map(FeedbackCounts::net)
+            .sum()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, FeedbackPairs> {
+        match self.pairs.lock() {
+            Ok(pairs) => pairs,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Записує через тимчасовий файл, щоб збій не пошкодив оцінки
+    fn save(&self, pairs: &FeedbackPairs) {
+        let temp_path = format!("{}.tmp", self.path);
+        let result = serde_json::to_string(pairs)
+            .map_err(std::io::Error::from)
+            .and_then(|json| fs::write(&temp_path, json))
+            .and_then(|()| fs::rename(&temp_path, &self.path));
+        if let Err(e) = result {
+            warn!("{}", tr!(FeedbackSaveFailed, self.path, e));
+        }
+    }
+}
+
+/// Терміни без повторів ("наказ наказ" - одна оцінка)
+fn unique(terms: &[String]) -> impl Iterator<Item = &String> {
+    terms.iter().enumerate().filter(move |(i, term)| !terms[..*i].contains(term)).map(|(_, term)| term)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+
+    fn result(file_path: &str) -> SearchEngineResult {
+        SearchEngineResult {
+            doc_id: 0,
+            file_name: file_path.to_string(),
+            file_path: file_path.to_string(),
+            matches: Vec::new(),
+            all_paragraphs: Vec::new(),
+            file_size: 0,
+            last_modified: 0,
+            category: None,
+            date: None,
+        }
+    }
+
+    fn terms(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_feedback_shifts_results_and_persists() {
+        let dir = TempIndexDir::new("relevance_feedback");
+        let path = dir.join("relevance_feedback.json").to_string_lossy().to_string();
+
+        let feedback = RelevanceFeedback::load(path.clone());
+        for _ in 0..5 {
+            feedback.record(&terms(&["петренк", "петренк"]), "2023/наказ 7.docx", true);
+        }
+        feedback.record(&terms(&["петренк", "відпустк"]), "2024/наказ 2.docx", false);
+        feedback.record(&terms(&["іваненк"]), "2024/наказ 1.docx", true);
+
+        // Оцінки за іншим терміном не впливають; зсув обмежено max_shift
+        let feedback = RelevanceFeedback::load(path.clone());
+        assert_eq!(feedback.boost(&terms(&["петренк", "п"]), "2023/наказ 7.docx"), DEFAULT_FEEDBACK_MAX_SHIFT as i64);
+        assert_eq!(feedback.boost(&terms(&["петренк", "відпустк"]), "2024/наказ 2.docx"), -2);
+
+        let paths = ["2024/наказ 3.docx", "2024/наказ 2.docx", "2024/наказ 1.docx", "2023/наказ 9.docx", "2023/наказ 7.docx"];
+        let mut results: Vec<_> = paths.iter().map(|p| result(p)).collect();
+        feedback.rerank(&mut results, &terms(&["петренк"]));
+        let order: Vec<_> = results.iter().map(|r| r.file_path.as_str()).collect();
+        assert_eq!(
+            order,
+            vec!["2024/наказ 3.docx", "2023/наказ 7.docx", "2024/наказ 1.docx", "2024/наказ 2.docx", "2023/наказ 9.docx"]
+        );
+
+        feedback.set_max_shift(0);
+        let mut unchanged: Vec<_> = paths.iter().map(|p| result(p)).collect();
+        feedback.rerank(&mut unchanged, &terms(&["петренк"]));
+        assert_eq!(unchanged[4].file_path, "2023/наказ 7.docx");
+
+        let summary = feedback.summary(2);
+        assert_eq!((summary.pairs, summary.relevant, summary.irrelevant), (4, 6, 2));
+        assert_eq!(summary.entries.len(), 2);
+        assert_eq!((summary.entries[0].term.as_str(), summary.entries[0].net), ("петренк", 5));
+    }
+}
@@ -0,0 +1,101 @@
+//! Кеш результатів повторних запитів: перемикання виду перегляду, повернення до попереднього
+//! запиту чи кілька користувачів з тим самим запитом не перевіряють абзаци кандидатів заново.
+//! Запис прив'язаний до покоління індексу, з яким його знайдено: після підміни покоління
+//! (reload, дельта) старі записи не віддаються, навіть якщо пошук зі старим знімком
+//! завершився вже після підміни. Найдавніше використаний запис витісняється першим.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+/// Скільки різних запитів тримає кеш пошукового движка
+pub const DEFAULT_RESULT_CACHE_ENTRIES: usize = 64;
+
+/// LRU-кеш значень V за ключем запиту; G - покоління даних, з яким знайдено значення
+pub struct ResultCache<G, V> {
+    capacity: usize,
+    /// Від щойно використаного до найдавнішого
+    entries: Mutex<VecDeque<Entry<G, V>>>,
+}
+
+struct Entry<G, V> {
+    key: String,
+    /// Weak не тримає старе покоління в пам'яті, але й не дає його адресі дістатися новому
+    generation: Weak<G>,
+    value: Arc<V>,
+}
+
+impl<G, V> ResultCache<G, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: Mutex::new(VecDeque::new()) }
+    }
+
+    /// Значення для ключа, знайдене в тому самому поколінні
+    pub fn get(&self, key: &str, generation: &Arc<G>) -> Option<Arc<V>> {
+        let mut entries = self.lock();
+        let position = entries.iter().position(|entry| entry.key == key)?;
+        let entry = entries.remove(position)?;
+        if !std::ptr::eq(entry.generation.as_ptr(), Arc::as_ptr(generation)) {
+            return None;
+        }
+        let value = Arc::clone(&entry.value);
+        entries.push_front(entry);
+        Some(value)
+    }
+
+    pub fn insert(&self, key: String, generation: &Arc<G>, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.lock();
+        entries.retain(|entry| entry.key != key);
+        entries.push_front(Entry { key, generation: Arc::downgrade(generation), value: Arc::new(value) });
+        entries.truncate(self.capacity);
+    }
+
+    /// Звільняє всі записи (нове покоління або інші налаштування пошуку)
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<Entry<G, V>>> {
+        match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_entries_bound_to_generation() {
+        let cache: ResultCache<u64, &str> = ResultCache::new(2);
+        let generation = Arc::new(1);
+        cache.insert("наказ".to_string(), &generation, "1");
+        cache.insert("рапорт".to_string(), &generation, "2");
+        assert_eq!(cache.get("наказ", &generation).as_deref(), Some(&"1"));
+
+        // Витісняється найдавніше використаний - "рапорт"
+        cache.insert("відпустка".to_string(), &generation, "3");
+        assert!(cache.get("рапорт", &generation).is_none());
+        assert_eq!(cache.len(), 2);
+
+        // Інше покоління - промах, запис прибирається
+        let next = Arc::new(2);
+        assert!(cache.get("наказ", &next).is_none());
+        assert!(cache.get("наказ", &generation).is_none());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+//! Вивантаження результатів пошуку для електронних таблиць: один рядок на кожен
+//! знайдений абзац (файл, дата наказу з назви файлу чи шапки, номер пункту, текст абзацу).
+//! Формат CSV - UTF-8 з BOM і CRLF, щоб Excel коректно показував кирилицю;
+//! XLSX - книга з одним аркушем, де дата записана датою Excel (сортується й фільтрується).
+
+use crate::permalink;
+use crate::search_engine::SearchEngineResult;
+use chrono::{Datelike, NaiveDate};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rust_xlsxwriter::{ExcelDateTime, Format, FormatAlign, Workbook, XlsxError};
+use serde::Deserialize;
+
+/// Номер пункту на початку абзацу: "3.", "12.4.", "«2.1." (пункт у цитаті наказу)
+static SECTION_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*«?\s*(\d+(?:\.\d+)*)\.").unwrap());
+
+/// Заголовки стовпців вивантаження
+pub const EXPORT_COLUMNS: [&str; 4] = ["Файл", "Дата", "Пункт", "Абзац"];
+
+/// Позначка порядку байтів: без неї Excel відкриває UTF-8 як ANSI
+pub const CSV_BOM: &str = "\u{feff}";
+
+/// Один знайдений абзац
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportRow {
+    pub file_name: String,
+    pub file_path: String,
+    /// Дата наказу з назви файлу чи шапки документа (ДД.ММ.РРРР)
+    pub date: Option<String>,
+    /// Номер пункту наказу, до якого належить абзац
+    pub section: Option<String>,
+    pub paragraph: String,
+}
+
+impl ExportRow {
+    /// Значення стовпців у порядку EXPORT_COLUMNS
+    pub fn fields(&self) -> [&str; 4] {
+        [
+            &self.file_name,
+            self.date.as_deref().unwrap_or(""),
+            self.section.as_deref().unwrap_or(""),
+            &self.paragraph,
+        ]
+    }
+}
+
+/// Рядки вивантаження в порядку результатів пошуку і абзаців у документі
+pub fn export_rows(results: &[SearchEngineResult]) -> Vec<ExportRow> {
+    let mut rows = Vec::new();
+    for result in results {
+        let date = result.date.and_then(|date| date.order_date());
+
+        let mut positions: Vec<usize> = result.matches.iter().map(|m| m.position).collect();
+        positions.sort_unstable();
+        positions.dedup();
+
+        for position in positions {
+            let Some(paragraph) = result.all_paragraphs.get(position) else {
+                continue;
+            };
+            rows.push(ExportRow {
+                file_name: result.file_name.clone(),
+                file_path: result.file_path.clone(),
+                date: date.clone(),
+                section: permalink::section_at(&result.all_paragraphs, position),
+                paragraph: paragraph.text.trim().to_string(),
+            });
+        }
+    }
+    rows
+}
+
+/// Номер пункту, з якого починається абзац ("3", "12.4")
+pub(crate) fn paragraph_section(text: &str) -> Option<&str> {
+    SECTION_REGEX.captures(text).and_then(|captures| captures.get(1)).map(|m| m.as_str())
+}
+
+/// Роздільник полів CSV: кома - стандарт, крапка з комою - для Excel з українською локаллю
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CsvDelimiter {
+    #[default]
+    Comma,
+    Semicolon,
+}
+
+impl CsvDelimiter {
+    fn as_char(self) -> char {
+        match self {
+            CsvDelimiter::Comma => ',',
+            CsvDelimiter::Semicolon => ';',
+        }
+    }
+}
+
+/// Рядок CSV (з CRLF в кінці). Поля з роздільником, лапками чи переносом беруться в лапки;
+/// поля, що починаються з = + - @, отримують апостроф, щоб таблиця не виконала їх як формулу.
+pub fn csv_line(fields: &[&str], delimiter: CsvDelimiter) -> String {
+    let delimiter = delimiter.as_char();
+    let mut line = String::new();
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            line.push(delimiter);
+        }
+        let guarded;
+        let field = if field.starts_with(['=', '+', '-', '@']) {
+            guarded = format!("'{}", field);
+            guarded.as_str()
+        } else {
+            field
+        };
+        if field.contains([delimiter, '"', '\n', '\r']) {
+            line.push('"');
+            line.push_str(&field.replace('"', "\"\""));
+            line.push('"');
+        } else {
+            line.push_str(field);
+        }
+    }
+    line.push_str("\r\n");
+    line
+}
+
+/// Назва аркуша книги XLSX
+pub const XLSX_SHEET_NAME: &str = "Результати пошуку";
+
+/// Ширина стовпців у символах (порядок EXPORT_COLUMNS)
+const XLSX_COLUMN_WIDTHS: [f64; 4] = [45.0, 12.0, 8.0, 100.0];
+
+/// Книга XLSX з рядками вивантаження: жирний закріплений заголовок з фільтром,
+/// абзаци з перенесенням рядків. Рядки пишуться як текст, тож формул у них не буде.
+pub fn xlsx_workbook(rows: &[ExportRow]) -> Result<Vec<u8>, XlsxError> {
+    let header_format = Format::new().set_bold().set_align(FormatAlign::Center);
+    let date_format = Format::new().set_num_format("dd.mm.yyyy").set_align(FormatAlign::Top);
+    let text_format = Format::new().set_align(FormatAlign::Top);
+    let paragraph_format = Format::new().set_text_wrap().set_align(FormatAlign::Top);
+
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name(XLSX_SHEET_NAME)?;
+
+    for (col, (title, width)) in EXPORT_COLUMNS.iter().zip(XLSX_COLUMN_WIDTHS).enumerate() {
+        sheet.set_column_width(col as u16, width)?;
+        sheet.write_string_with_format(0, col as u16, *title, &header_format)?;
+    }
+
+    for (i, row) in rows.iter().enumerate() {
+        let line = i as u32 + 1;
+        sheet.write_string_with_format(line, 0, &row.file_name, &text_format)?;
+        let date = row
+            .date
+            .as_deref()
+            .and_then(|date| NaiveDate::parse_from_str(date, "%d.%m.%Y").ok());
+        if let Some(date) = date {
+            let date = ExcelDateTime::from_ymd(date.year() as u16, date.month() as u8, date.day() as u8)?;
+            sheet.write_datetime_with_format(line, 1, &date, &date_format)?;
+        }
+        if let Some(section) = &row.section {
+            sheet.write_string_with_format(line, 2, section, &text_format)?;
+        }
+        sheet.write_string_with_format(line, 3, &row.paragraph, &paragraph_format)?;
+    }
+
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.autofilter(0, 0, rows.len() as u32, EXPORT_COLUMNS.len() as u16 - 1)?;
+    workbook.save_to_buffer()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document_date::DocumentDate;
+    use crate::document_record::Paragraph;
+    use crate::search_engine::SearchEngineMatch;
+
+    #[test]
+    fn test_rows_carry_date_and_section_and_csv_is_escaped() {
+        let paragraphs = [
+            "НАКАЗ",
+            "1. Призначити:",
+            "солдата Петренка П.П. на посаду стрільця;",
+            "«2.1. Вважати таким, що прибув",
+        ];
+        let result = SearchEngineResult {
+            doc_id: 0,
+            file_name: "наказ №16 від 05.02.2024.docx".to_string(),
+            file_path: "2024/наказ №16 від 05.02.2024.docx".to_string(),
+            matches: [3, 2, 3, 0]
+                .iter()
+                .map(|&position| SearchEngineMatch { context: String::new(), position })
+                .collect(),
+            all_paragraphs: paragraphs.iter().map(|text| Paragraph::new(text.to_string())).collect(),
+            file_size: 0,
+            last_modified: 0,
+            category: None,
+            date: DocumentDate::from_file_name("2024/наказ №16 від 05.02.2024.docx"),
+        };
+
+        let rows = export_rows(&[result]);
+        let sections: Vec<_> = rows.iter().map(|r| r.section.as_deref()).collect();
+        assert_eq!(sections, vec![None, Some("1"), Some("2.1")]);
+        assert_eq!(rows[1].date.as_deref(), Some("05.02.2024"));
+        assert_eq!(rows[1].fields()[3], "солдата Петренка П.П. на посаду стрільця;");
+
+        assert_eq!(
+            csv_line(&["a;b", "сказав \"так\"", "=СУМ(A1)", "абзац"], CsvDelimiter::Semicolon),
+            "\"a;b\";\"сказав \"\"так\"\"\";'=СУМ(A1);абзац\r\n"
+        );
+        assert_eq!(csv_line(&["a;b", "1,5"], CsvDelimiter::Comma), "a;b,\"1,5\"\r\n");
+    }
+
+    #[test]
+    fn test_xlsx_keeps_cyrillic_text() {
+        let row = ExportRow {
+            file_name: "наказ №16 від 05.02.2024.docx".to_string(),
+            file_path: "2024/наказ №16 від 05.02.2024.docx".to_string(),
+            date: Some("05.02.2024".to_string()),
+            section: Some("1".to_string()),
+            paragraph: "=солдата Петренка П.П. на посаду стрільця;".to_string(),
+        };
+        let bytes = xlsx_workbook(&[row]).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+        let mut strings = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("xl/sharedStrings.xml").unwrap(), &mut strings).unwrap();
+        assert!(strings.contains("=солдата Петренка П.П. на посаду стрільця;"));
+        assert!(strings.contains("Абзац"));
+
+        let mut sheet = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("xl/worksheets/sheet1.xml").unwrap(), &mut sheet).unwrap();
+        // Дата - число Excel (05.02.2024 = 45327), а не текст
+        assert!(sheet.contains("<v>45327</v>"));
+        assert!(!sheet.contains("<f>"));
+    }
+}
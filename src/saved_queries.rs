@@ -0,0 +1,462 @@
+//! Збережені запити зі сповіщеннями: користувач реєструє запит ("ПЕТРЕНКО") з адресами
+//! сповіщень (webhook, пошта), і після кожного циклу індексації запит виконується лише по
+//! нових і змінених документах - якщо є збіги, надсилається сповіщення з переліком
+//! документів. Запити зберігаються в saved_queries.json у папці індексів.
+//!
+//! Нові й змінені документи визначаються порівнянням версій (час модифікації, хеш вмісту)
+//! до і після оновлення за шляхом файлу, а не за doc_id - тож перебудова індексу з нуля,
+//! яка перенумеровує документи, не викликає сповіщень про весь архів.
+//!
+//! Адреси сповіщень задають користувачі, тому сервер надсилає лише на хости webhook і
+//! домени пошти з [alerts] (NotificationPolicy), а запит належить сеансу, який його
+//! створив (див. session).
+
+use crate::config::AlertsConfig;
+use crate::document_record::DocumentIndex;
+use crate::error::NotificationError;
+use crate::failure_alerts::{Alert, Mailer};
+use crate::integrity_monitor::hostname;
+use crate::redaction::Redactor;
+use crate::search_engine::{SearchEngine, SearchFilters, SearchMode};
+use crate::tr;
+use chrono::{DateTime, Local};
+use lettre::message::Mailbox;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+/// Скільки документів перелічувати в сповіщенні
+const MAX_LISTED_DOCUMENTS: usize = 20;
+/// Найбільша довжина фрагмента абзацу в сповіщенні, символів
+const MAX_CONTEXT_LENGTH: usize = 300;
+
+/// Куди надсилати сповіщення
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum NotificationTarget {
+    /// POST з JSON (event = "saved_query_hits")
+    Webhook { url: String },
+    /// Лист через поштовий сервер з [alerts]
+    Email { address: String },
+}
+
+/// Куди дозволено надсилати сповіщення збережених запитів
+#[derive(Debug, Clone, Default)]
+pub struct NotificationPolicy {
+    /// Хости webhook (без урахування регістру)
+    webhook_hosts: Vec<String>,
+    /// Домени пошти (без урахування регістру)
+    email_domains: Vec<String>,
+    /// Чи задано alerts.smtp_server
+    email_enabled: bool,
+}
+
+impl NotificationPolicy {
+    pub fn from_config(config: &AlertsConfig) -> Self {
+        let lowercase = |values: &[String]| values.iter().map(|v| v.trim().to_lowercase()).collect();
+        Self {
+            webhook_hosts: lowercase(&config.saved_query_webhook_hosts),
+            email_domains: lowercase(&config.saved_query_email_domains),
+            email_enabled: config.smtp_server.is_some(),
+        }
+    }
+
+    /// Перевірка адреси: формат і перелік дозволених хостів чи доменів
+    pub fn validate(&self, target: &NotificationTarget) -> Result<(), NotificationError> {
+        match target {
+            NotificationTarget::Webhook { url } => {
+                let parsed = url::Url::parse(url)
+                    .ok()
+                    .filter(|parsed| matches!(parsed.scheme(), "http" | "https"))
+                    .ok_or_else(|| NotificationError::InvalidWebhookUrl(url.clone()))?;
+                let host = parsed.host_str().unwrap_or_default().to_lowercase();
+                if self.webhook_hosts.contains(&host) {
+                    Ok(())
+                } else {
+                    Err(NotificationError::WebhookHostNotAllowed(host))
+                }
+            }
+            NotificationTarget::Email { address } => {
+                if !self.email_enabled {
+                    return Err(NotificationError::EmailDisabled);
+                }
+                let mailbox = address.parse::<Mailbox>().map_err(|e| NotificationError::InvalidEmail {
+                    address: address.clone(),
+                    reason: e.to_string(),
+                })?;
+                let domain = mailbox.email.domain().to_lowercase();
+                if self.email_domains.contains(&domain) {
+                    Ok(())
+                } else {
+                    Err(NotificationError::EmailDomainNotAllowed(domain))
+                }
+            }
+        }
+    }
+}
+
+/// Збережений запит
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedQuery {
+    pub id: u64,
+    /// Назва для сповіщень (без неї - сам запит)
+    pub name: String,
+    pub query: String,
+    /// Ключ сеансу, який створив запит (див. session::user_key)
+    pub owner: String,
+    pub targets: Vec<NotificationTarget>,
+    /// Unix timestamp
+    pub created_at: u64,
+}
+
+pub struct SavedQueryStore {
+    path: String,
+    queries: Mutex<Vec<SavedQuery>>,
+}
+
+impl SavedQueryStore {
+    /// Завантажує збережені запити; пошкоджений файл - попередження і порожній список
+    pub fn load(path: String) -> Self {
+        let queries = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("{}", tr!(SavedQueriesLoadFailed, path, e));
+                Vec::new()
+            }),
+            Err(e) if Path::new(&path).exists() => {
+                warn!("{}", tr!(SavedQueriesLoadFailed, path, e));
+                Vec::new()
+            }
+            Err(_) => Vec::new(),
+        };
+        Self { path, queries: Mutex::new(queries) }
+    }
+
+    /// Додає запит і зберігає файл (адреси перевіряються до виклику, див. NotificationPolicy::validate)
+    pub fn add(&self, name: Option<&str>, query: &str, owner: &str, targets: Vec<NotificationTarget>) -> SavedQuery {
+        let mut queries = self.lock();
+        let query = query.trim();
+        let saved_query = SavedQuery {
+            id: queries.iter().map(|q| q.id).max().unwrap_or(0) + 1,
+            name: name.map(str::trim).filter(|name| !name.is_empty()).unwrap_or(query).to_string(),
+            query: query.to_string(),
+            owner: owner.to_string(),
+            targets,
+            created_at: unix_now(),
+        };
+        queries.push(saved_query.clone());
+        self.save(&queries);
+        saved_query
+    }
+
+    /// Видаляє запит власника; false - такого запиту в нього немає
+    pub fn remove(&self, id: u64, owner: &str) -> bool {
+        let mut queries = self.lock();
+        let before = queries.len();
+        queries.retain(|q| !(q.id == id && q.owner == owner));
+        if queries.len() == before {
+            return false;
+        }
+        self.save(&queries);
+        true
+    }
+
+    /// Запити власника в порядку додавання
+    pub fn for_owner(&self, owner: &str) -> Vec<SavedQuery> {
+        self.lock().iter().filter(|q| q.owner == owner).cloned().collect()
+    }
+
+    pub fn all(&self) -> Vec<SavedQuery> {
+        self.lock().clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<SavedQuery>> {
+        match self.queries.lock() {
+            Ok(queries) => queries,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Записує через тимчасовий файл, щоб збій не пошкодив запити
+    fn save(&self, queries: &[SavedQuery]) {
+        let temp_path = format!("{}.tmp", self.path);
+        let result = serde_json::to_string_pretty(queries)
+            .map_err(std::io::Error::from)
+            .and_then(|json| fs::write(&temp_path, json))
+            .and_then(|()| fs::rename(&temp_path, &self.path));
+        if let Err(e) = result {
+            warn!("{}", tr!(SavedQueriesSaveFailed, self.path, e));
+        }
+    }
+}
+
+/// Версії документів індексу: шлях файлу -> (час модифікації, хеш вмісту)
+pub type DocumentVersions = HashMap<String, (u64, Option<String>)>;
+
+pub fn document_versions(doc_index: &DocumentIndex) -> DocumentVersions {
+    doc_index
+        .documents
+        .iter()
+        .filter(|d| !d.deleted)
+        .map(|d| (d.file_path.clone(), (d.last_modified, d.content_hash.clone())))
+        .collect()
+}
+
+/// doc_id документів, доданих або змінених після знімка before. Порожній знімок - індекс
+/// щойно створено, і новим виявився б увесь архів: тоді змін немає.
+pub fn changed_documents(before: &DocumentVersions, doc_index: &DocumentIndex) -> HashSet<u64> {
+    if before.is_empty() {
+        return HashSet::new();
+    }
+    doc_index
+        .documents
+        .iter()
+        .filter(|d| !d.deleted)
+        .filter(|d| before.get(&d.file_path) != Some(&(d.last_modified, d.content_hash.clone())))
+        .map(|d| d.doc_id)
+        .collect()
+}
+
+/// Документ, у якому знайдено збережений запит
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SavedQueryHit {
+    pub doc_id: u64,
+    pub file_name: String,
+    pub file_path: String,
+    /// Перший знайдений абзац (скорочений)
+    pub context: String,
+}
+
+/// Збіги одного запиту в нових і змінених документах
+#[derive(Debug, Clone)]
+pub struct SavedQueryHits {
+    pub saved_query: SavedQuery,
+    pub documents: Vec<SavedQueryHit>,
+}
+
+impl SavedQueryHits {
+    pub fn alert(&self) -> Alert {
+        let subject = tr!(SavedQueryAlert, self.saved_query.name, self.documents.len());
+        let mut body = format!("{}\n\n", subject);
+        for hit in self.documents.iter().take(MAX_LISTED_DOCUMENTS) {
+            body.push_str(&format!("- {}\n  {}\n", hit.file_path, hit.context));
+        }
+        if self.documents.len() > MAX_LISTED_DOCUMENTS {
+            body.push_str(&tr!(AlertMoreErrors, self.documents.len() - MAX_LISTED_DOCUMENTS));
+            body.push('\n');
+        }
+        Alert { subject, body }
+    }
+}
+
+/// Виконує всі збережені запити лише по документах changed; повертає запити зі збігами.
+/// redactor - маскування чутливих даних: сповіщення виходять за межі сервера.
+pub async fn run(
+    store: &SavedQueryStore,
+    search_engine: &SearchEngine,
+    changed: HashSet<u64>,
+    redactor: Option<&Redactor>,
+) -> Vec<SavedQueryHits> {
+    if changed.is_empty() {
+        return Vec::new();
+    }
+    let filters = SearchFilters::default().with_documents(changed);
+
+    let mut found = Vec::new();
+    for saved_query in store.all() {
+        let mut results = match search_engine
+            .search_outcome_filtered(&saved_query.query, SearchMode::Full, None, &filters)
+            .await
+        {
+            Ok(outcome) => outcome.results,
+            Err(e) => {
+                warn!("{}", tr!(SavedQueriesFailed, saved_query.query, e));
+                continue;
+            }
+        };
+        if results.is_empty() {
+            continue;
+        }
+        if let Some(redactor) = redactor {
+            redactor.redact_results(&mut results);
+        }
+        let documents = results
+            .into_iter()
+            .map(|result| SavedQueryHit {
+                doc_id: result.doc_id,
+                context: result
+                    .matches
+                    .first()
+                    .map(|m| m.context.trim().chars().take(MAX_CONTEXT_LENGTH).collect())
+                    .unwrap_or_default(),
+                file_name: result.file_name,
+                file_path: result.file_path,
+            })
+            .collect();
+        found.push(SavedQueryHits { saved_query, documents });
+    }
+    found
+}
+
+/// Доставка сповіщень збережених запитів на їхні адреси
+pub struct SavedQueryNotifier {
+    mailer: Option<Mailer>,
+    policy: NotificationPolicy,
+}
+
+impl SavedQueryNotifier {
+    /// mailer - поштовий сервер з [alerts]; без нього адреси пошти пропускаються
+    pub fn new(mailer: Option<Mailer>, policy: NotificationPolicy) -> Self {
+        Self { mailer, policy }
+    }
+
+    /// Надсилає сповіщення на всі адреси запиту (блокуючий виклик); помилки - у журнал.
+    /// Адреси перевіряються ще раз: перелік дозволених міг звузитися після збереження запиту
+    pub fn send(&self, hits: &SavedQueryHits) {
+        let alert = hits.alert();
+        for target in &hits.saved_query.targets {
+            if let Err(e) = self.policy.validate(target) {
+                warn!("{}", tr!(NotificationTargetSkipped, hits.saved_query.name, e));
+                continue;
+            }
+            match target {
+                NotificationTarget::Webhook { url } => {
+                    let payload = serde_json::json!({
+                        "event": "saved_query_hits",
+                        "host": hostname(),
+                        "time": DateTime::<Local>::from(SystemTime::now()).to_rfc3339(),
+                        "id": hits.saved_query.id,
+                        "name": hits.saved_query.name,
+                        "query": hits.saved_query.query,
+                        "subject": alert.subject,
+                        "documents": hits.documents,
+                    });
+                    match ureq::post(url).timeout(Duration::from_secs(10)).send_json(payload) {
+                        Ok(_) => info!("{}", tr!(AlertSent, "webhook", alert.subject)),
+                        Err(e) => warn!("{}", tr!(AlertSendFailed, "webhook", e)),
+                    }
+                }
+                NotificationTarget::Email { address } => {
+                    let result = match (&self.mailer, address.parse::<Mailbox>()) {
+                        (Some(mailer), Ok(to)) => mailer.send(&[to], &alert),
+                        (None, _) => Err(NotificationError::EmailDisabled),
+                        (_, Err(e)) => Err(NotificationError::InvalidEmail {
+                            address: address.clone(),
+                            reason: e.to_string(),
+                        }),
+                    };
+                    match result {
+                        Ok(()) => info!("{}", tr!(AlertSent, "SMTP", alert.subject)),
+                        Err(e) => warn!("{}", tr!(AlertSendFailed, "SMTP", e)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+    use crate::document_record::DocumentRecord;
+    use crate::index_paths::IndexPaths;
+    use crate::inverted_index::InvertedIndex;
+
+    fn write_indices(paths: &IndexPaths, documents: &[(&str, u64, &str)]) {
+        let mut doc_index = DocumentIndex::new();
+        for (file_path, last_modified, text) in documents {
+            doc_index.push_document(DocumentRecord {
+                last_modified: *last_modified,
+                ..DocumentRecord::from_texts(file_path, [*text])
+            });
+        }
+        doc_index.recount();
+        let inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+        fs::write(paths.documents_index(), serde_json::to_string(&doc_index).unwrap()).unwrap();
+        fs::write(paths.inverted_index(), serde_json::to_string(&inv_index).unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_saved_queries_run_only_on_new_and_changed_documents() {
+        let dir = TempIndexDir::new("saved_queries");
+        let paths = dir.paths();
+
+        let store = SavedQueryStore::load(paths.saved_queries());
+        let webhook = NotificationTarget::Webhook { url: "https://hooks.example.org/hook".to_string() };
+        let saved = store.add(Some(" "), " петренко ", "оператор", vec![webhook.clone()]);
+        store.add(Some("Відпустки"), "відпустка", "інший", vec![webhook.clone()]);
+        assert_eq!((saved.id, saved.name.as_str()), (1, "петренко"));
+        assert!(!store.remove(1, "інший"));
+
+        let alerts = AlertsConfig {
+            smtp_server: Some("smtp.example.org".to_string()),
+            saved_query_webhook_hosts: vec!["Hooks.Example.org".to_string()],
+            saved_query_email_domains: vec!["example.org".to_string()],
+            ..AlertsConfig::default()
+        };
+        let policy = NotificationPolicy::from_config(&alerts);
+        assert!(policy.validate(&webhook).is_ok());
+        let webhook_to = |url: &str| NotificationTarget::Webhook { url: url.to_string() };
+        assert!(matches!(
+            policy.validate(&webhook_to("ftp://hooks.example.org")),
+            Err(NotificationError::InvalidWebhookUrl(_))
+        ));
+        // Внутрішні адреси та хости, схожі на дозволений, не приймаються
+        for url in ["http://169.254.169.254/latest", "http://localhost:8080/", "https://hooks.example.org.evil.net/"] {
+            assert!(matches!(policy.validate(&webhook_to(url)), Err(NotificationError::WebhookHostNotAllowed(_))));
+        }
+        let email = NotificationTarget::Email { address: "chief@Example.org".to_string() };
+        assert!(policy.validate(&email).is_ok());
+        let outside = NotificationTarget::Email { address: "leak@evil.net".to_string() };
+        assert!(matches!(policy.validate(&outside), Err(NotificationError::EmailDomainNotAllowed(_))));
+        let no_smtp = NotificationPolicy::from_config(&AlertsConfig { smtp_server: None, ..alerts });
+        assert!(matches!(no_smtp.validate(&email), Err(NotificationError::EmailDisabled)));
+        // Без переліку хостів webhook недоступний
+        assert!(NotificationPolicy::default().validate(&webhook).is_err());
+
+        write_indices(paths, &[
+            ("2024/1.docx", 1, "Петренку надати відпустку"),
+            ("2024/2.docx", 1, "Іваненка призначити"),
+        ]);
+        let engine = SearchEngine::new(paths);
+        engine.reload().unwrap();
+        let before = engine.with_indices(|_, doc_index, _| document_versions(doc_index));
+        // Індекс щойно створено - новим був би весь архів
+        assert!(engine.with_indices(|_, doc_index, _| changed_documents(&DocumentVersions::new(), doc_index)).is_empty());
+
+        // Змінено другий документ і додано третій; перший (теж з Петренком) не змінився
+        write_indices(paths, &[
+            ("2024/1.docx", 1, "Петренку надати відпустку"),
+            ("2024/2.docx", 2, "Іваненка і Петренка призначити"),
+            ("2024/3.docx", 3, "Сидоренка призначити"),
+        ]);
+        engine.reload().unwrap();
+        let changed = engine.with_indices(|_, doc_index, _| changed_documents(&before, doc_index));
+        assert_eq!(changed.len(), 2);
+
+        let found = run(&SavedQueryStore::load(paths.saved_queries()), &engine, changed, None).await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].saved_query.owner, "оператор");
+        let files: Vec<&str> = found[0].documents.iter().map(|hit| hit.file_path.as_str()).collect();
+        assert_eq!(files, vec!["2024/2.docx"]);
+        assert!(found[0].alert().body.contains("Іваненка і Петренка призначити"));
+
+        assert!(store.remove(1, "оператор"));
+        assert!(SavedQueryStore::load(paths.saved_queries()).for_owner("оператор").is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+//! Обмеження одночасних пошуків: ранковий наплив запитів з усього штабу інакше
+//! матеріалізує десятки великих наборів результатів одночасно. Понад
+//! search.max_concurrent запити чекають у черзі на search.queue_size місць;
+//! коли й черга повна, пошук одразу відхиляється (SearchError::Busy, HTTP 429).
+
+use crate::error::SearchError;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Скільки пошуків виконується одночасно
+pub const DEFAULT_MAX_CONCURRENT_SEARCHES: usize = 8;
+
+/// Скільки пошуків може чекати на виконання
+pub const DEFAULT_SEARCH_QUEUE_SIZE: usize = 16;
+
+pub struct SearchAdmission {
+    max_concurrent: usize,
+    queue_size: usize,
+    /// None - без обмеження (max_concurrent = 0)
+    permits: Option<Semaphore>,
+    queued: AtomicUsize,
+}
+
+/// Місце в черзі звільняється і тоді, коли клієнт не дочекався (запит скасовано)
+struct QueueSlot<'a>(&'a AtomicUsize);
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl SearchAdmission {
+    pub fn new(max_concurrent: usize, queue_size: usize) -> Self {
+        Self {
+            max_concurrent,
+            queue_size,
+            permits: (max_concurrent > 0).then(|| Semaphore::new(max_concurrent)),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn limits(&self) -> (usize, usize) {
+        (self.max_concurrent, self.queue_size)
+    }
+
+    /// Дозвіл на пошук (тримати до кінця пошуку); Busy - усі місця і черга зайняті
+    pub async fn admit(&self) -> Result<Option<SemaphorePermit<'_>>, SearchError> {
+        let Some(permits) = &self.permits else {
+            return Ok(None);
+        };
+        if let Ok(permit) = permits.try_acquire() {
+            return Ok(Some(permit));
+        }
+
+        let slot = QueueSlot(&self.queued);
+        if self.queued.fetch_add(1, Ordering::SeqCst) >= self.queue_size {
+            return Err(SearchError::Busy(self.max_concurrent));
+        }
+        let permit = permits.acquire().await.map_err(|_| SearchError::Busy(self.max_concurrent))?;
+        drop(slot);
+        Ok(Some(permit))
+    }
+}
+
+impl Default for SearchAdmission {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT_SEARCHES, DEFAULT_SEARCH_QUEUE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_queue_overflow_rejected() {
+        let admission = SearchAdmission::new(1, 1);
+        let running = admission.admit().await.unwrap();
+        assert!(running.is_some());
+
+        // Друге місце - у черзі, третій запит відхиляється
+        let queued = admission.admit();
+        tokio::pin!(queued);
+        assert!(poll_once(queued.as_mut()).await);
+        assert!(matches!(admission.admit().await, Err(SearchError::Busy(1))));
+
+        drop(running);
+        assert!(queued.await.unwrap().is_some());
+        assert_eq!(admission.queued.load(Ordering::SeqCst), 0);
+
+        let unlimited = SearchAdmission::new(0, 0);
+        assert!(unlimited.admit().await.unwrap().is_none());
+    }
+
+    /// Один раз опитує future: true - ще чекає
+    async fn poll_once<F: std::future::Future>(future: std::pin::Pin<&mut F>) -> bool {
+        let mut future = Some(future);
+        std::future::poll_fn(|cx| std::task::Poll::Ready(future.take().unwrap().poll(cx).is_pending())).await
+    }
+}
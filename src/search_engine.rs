@@ -1,28 +1,56 @@
-use crate::document_record::DocumentIndex;
-use crate::inverted_index::InvertedIndex;
+use crate::annotations::AnnotationStore;
+use crate::document_date::{document_date, DocumentDate};
+use crate::document_record::{DocumentIndex, DocumentRecord};
+use crate::error::{IndexError, SearchError};
+use crate::index_delta::IndexDelta;
+use crate::index_manifest::{generations_match, resolve_active_paths, IndexManifest};
+use crate::index_paths::IndexPaths;
+use crate::index_store::{store_is_empty, IndexStore};
+use crate::inverted_index::{InvertedIndex, MIN_PREFIX_CHARS, PREFIX_WILDCARD};
+use crate::query_rewrite::QueryRewriter;
+use crate::regex_query::RegexQuery;
+use crate::relevance_feedback::RelevanceFeedback;
+use crate::result_cache::{ResultCache, DEFAULT_RESULT_CACHE_ENTRIES};
+use crate::search_admission::SearchAdmission;
+use crate::search_facets::SearchFacets;
+use crate::slow_query_log::{self, SearchTimings, SlowQuery, SlowQueryLog};
 use crate::stemmer;
+use crate::tr;
+use chrono::{Datelike, Local, Months};
+use log::{debug, info, warn};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::fs;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
-static WORD_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b[\p{L}\p{N}]+\b").unwrap());
+/// Швидкий пошук переглядає накази, датовані за стільки останніх місяців (решта - у режимі Remaining)
+pub const DEFAULT_QUICK_MODE_MONTHS: u32 = 6;
+
+/// Скільки найновіших документів переглядає швидкий пошук, якщо вікно в місяцях вимкнено
+pub const DEFAULT_QUICK_MODE_DOCUMENTS: usize = 170;
 
-// Регулярний вираз для пошуку дати у форматі DD.MM.YYYY
-static DATE_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(\d{2})\.(\d{2})\.(\d{4})").unwrap()
-});
+/// Скільки символів допускається між сусідніми словами запиту в абзаці
+/// (для урахування відмінків і розділових знаків)
+pub const DEFAULT_PROXIMITY_WINDOW: usize = 15;
 
-// Регулярний вираз для пунктів з нумерацією (1.4., 2.3.3., тощо)
-static NUMBERING_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"^\s*\d+(\.\d+)*\.").unwrap()
-});
+/// Час на один запит, мс: після нього решта кандидатів не перевіряється
+pub const DEFAULT_QUERY_TIMEOUT_MS: u64 = 5000;
 
-// Словник слів для припинення пошуку в файлах "особовий*"
-static PERSONAL_FILE_STOP_WORDS: &[&str] = &[
-    "старш", "молодш", "солдат", "сержант", "штаб", "лейтенант", "майор", "матрос", "рекрут"
-];
+/// Скільки документів щонайбільше повертає один запит до /api/search
+pub const DEFAULT_MAX_RESULTS: usize = 1000;
+
+/// Скільки абзаців документа щонайбільше потрапляє у відповідь
+pub const DEFAULT_MAX_PARAGRAPHS: usize = 2000;
+
+static WORD_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b[\p{L}\p{N}]+\b").unwrap());
+
+/// Поля запиту для слів імені файлу: "filename:123", "назва:123"
+const NAME_FIELDS: [&str; 2] = ["filename", "назва"];
 
 #[derive(Debug, Clone)]
 pub struct SearchEngineMatch {
@@ -34,70 +62,821 @@ use crate::document_record::Paragraph;
 
 #[derive(Debug, Clone)]
 pub struct SearchEngineResult {
+    /// Стабільний ідентифікатор документа (див. DocumentRecord::doc_id)
+    pub doc_id: u64,
     pub file_name: String,
     pub file_path: String,
     pub matches: Vec<SearchEngineMatch>,
     pub all_paragraphs: Vec<Paragraph>,
     pub file_size: u64,
     pub last_modified: u64,
+    /// Вид наказу (див. classifier)
+    pub category: Option<String>,
+    /// Дата для впорядкування (див. document_date)
+    pub date: Option<DocumentDate>,
+}
+
+/// Види перегляду, для яких можна задати фільтри абзаців (search.paragraph_filters)
+pub const VIEW_MODES: [&str; 2] = ["fragments", "full-document"];
+
+/// Фільтри абзаців за замовчуванням: "Витяг" пропускає абзаци "Підстава: ..."
+pub fn default_paragraph_filters() -> BTreeMap<String, Vec<String>> {
+    BTreeMap::from([("fragments".to_string(), vec!["підстава".to_string()])])
+}
+
+/// Фільтри одного запиту поверх налаштувань пошуку (див. search_outcome_filtered)
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters {
+    /// Початки абзаців, які не вважаються збігами, замість search.paragraph_filters для
+    /// виду перегляду (порожній список - без фільтрів)
+    pub skip_prefixes: Option<Vec<String>>,
+    /// Лише документи цих форматів ("docx", "pdf"; без різниці в регістрі); порожній - усі
+    pub file_types: Vec<String>,
+    /// Лише документи з цієї папки та її підпапок ("2024/нр/": шлях від папки наказів,
+    /// нижній регістр, "/" як роздільник і в кінці)
+    pub folder: Option<String>,
+    /// Лише документи з цими doc_id (збережені запити перевіряють лише нові та змінені)
+    pub documents: Option<HashSet<u64>>,
+    /// Лише документи, датовані з цього дня (рік, місяць, день) включно; див. document_date
+    pub date_from: Option<(u32, u32, u32)>,
+    /// Лише документи, датовані до цього дня включно
+    pub date_to: Option<(u32, u32, u32)>,
+    /// Точний пошук: слова без стемінгу ("петренку" не знаходить "Петренка")
+    pub exact: bool,
+    /// Запит - регулярний вираз (див. regex_query): абзаци перевіряються ним, а не словами
+    pub regex: Option<RegexQuery>,
+    /// Порядок результатів
+    pub sort: SortOrder,
+}
+
+impl SearchFilters {
+    pub fn with_skip_prefixes(mut self, skip_prefixes: Option<Vec<String>>) -> Self {
+        self.skip_prefixes = skip_prefixes;
+        self
+    }
+
+    pub fn with_file_types(mut self, file_types: Vec<String>) -> Self {
+        self.file_types = file_types
+            .iter()
+            .map(|file_type| file_type.trim().trim_start_matches('.').to_lowercase())
+            .filter(|file_type| !file_type.is_empty())
+            .collect();
+        self
+    }
+
+    /// Папка без різниці в регістрі та роздільниках ("2024\\НР", "/2024/НР/"); порожня - усі
+    pub fn with_folder(mut self, folder: Option<&str>) -> Self {
+        self.folder = folder
+            .map(|folder| folder.replace('\\', "/").trim().trim_matches('/').to_lowercase())
+            .filter(|folder| !folder.is_empty())
+            .map(|folder| format!("{}/", folder));
+        self
+    }
+
+    pub fn with_documents(mut self, documents: HashSet<u64>) -> Self {
+        self.documents = Some(documents);
+        self
+    }
+
+    pub fn with_regex(mut self, regex: Option<RegexQuery>) -> Self {
+        self.regex = regex;
+        self
+    }
+
+    pub fn with_sort(mut self, sort: SortOrder) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn with_exact(mut self, exact: bool) -> Self {
+        self.exact = exact;
+        self
+    }
+
+    /// Проміжок дат (межі включно); документи без дати до нього не потрапляють
+    pub fn with_date_range(mut self, date_from: Option<(u32, u32, u32)>, date_to: Option<(u32, u32, u32)>) -> Self {
+        self.date_from = date_from;
+        self.date_to = date_to;
+        self
+    }
+
+    /// Чи підходить документ за форматом, папкою, doc_id і датою. Шляхи в індексі містять і папку наказів
+    /// (nakazi_cache/2024/...), тож папка шукається з початку шляху або після будь-якого "/"
+    pub fn accepts(&self, document: &DocumentRecord) -> bool {
+        let type_matches =
+            self.file_types.is_empty() || document.file_type().is_some_and(|file_type| self.file_types.contains(&file_type));
+        type_matches
+            && self.documents.as_ref().is_none_or(|documents| documents.contains(&document.doc_id))
+            && self.folder.as_ref().is_none_or(|folder| {
+                let path = document.file_path.replace('\\', "/").to_lowercase();
+                path.starts_with(folder.as_str())
+                    || path.match_indices('/').any(|(i, _)| path[i + 1..].starts_with(folder.as_str()))
+            })
+            && (self.date_from.is_none() && self.date_to.is_none()
+                || document_date(document).is_some_and(|date| {
+                    let date = date.ymd();
+                    self.date_from.is_none_or(|from| date >= from) && self.date_to.is_none_or(|to| date <= to)
+                }))
+    }
+}
+
+/// Порядок результатів пошуку (поле sort у /api/search)
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// Від нових наказів до старих, за однакової дати - більше збігів першими
+    #[default]
+    DateDesc,
+    /// Більше збігів першими, за однакової кількості - новіші
+    Relevance,
+    /// Від старих наказів до нових; недатовані - в кінці
+    DateAsc,
+    /// За назвою файлу, без різниці в регістрі
+    Name,
+    /// Більші файли першими
+    Size,
+}
+
+impl SortOrder {
+    /// Порівняння результатів; за рівності - звичайний порядок (SearchEngine::compare_results)
+    pub fn compare(self, a: &SearchEngineResult, b: &SearchEngineResult) -> std::cmp::Ordering {
+        let by_date = || SearchEngine::compare_results(a, b);
+        match self {
+            SortOrder::DateDesc => by_date(),
+            SortOrder::Relevance => b.matches.len().cmp(&a.matches.len()).then_with(by_date),
+            SortOrder::DateAsc => match (a.date, b.date) {
+                (Some(date_a), Some(date_b)) => date_a.ymd().cmp(&date_b.ymd()).then_with(by_date),
+                _ => by_date(),
+            },
+            SortOrder::Name => a.file_name.to_lowercase().cmp(&b.file_name.to_lowercase()).then_with(by_date),
+            SortOrder::Size => b.file_size.cmp(&a.file_size).then_with(by_date),
+        }
+    }
+
+    /// Оцінки користувачів зсувають документи лише в порядках за датою і релевантністю:
+    /// порядок за назвою чи розміром користувач вибирає явно
+    pub fn reranked(self) -> bool {
+        matches!(self, SortOrder::DateDesc | SortOrder::Relevance)
+    }
+}
+
+/// Найбільше абзаців контексту з кожного боку збігу (параметр context у запиті)
+pub const MAX_CONTEXT_PARAGRAPHS: usize = 20;
+
+impl SearchEngineResult {
+    /// Абзаци до і після збігу в position: не більше context з кожного боку
+    pub fn context_window(&self, position: usize, context: usize) -> (Range<usize>, Range<usize>) {
+        let len = self.all_paragraphs.len();
+        let position = position.min(len);
+        let after = (position + 1).min(len);
+        (position.saturating_sub(context)..position, after..(after + context).min(len))
+    }
+
+    /// Скільки абзаців документа не потрапляє ні в збіги, ні в їх контекст
+    pub fn context_omitted(&self, context: usize) -> usize {
+        let mut covered = vec![false; self.all_paragraphs.len()];
+        for m in &self.matches {
+            let (before, after) = self.context_window(m.position, context);
+            for position in before.chain(after).chain(m.position..m.position + 1) {
+                if let Some(covered) = covered.get_mut(position) {
+                    *covered = true;
+                }
+            }
+        }
+        covered.iter().filter(|covered| !**covered).count()
+    }
 }
 
-#[derive(Debug)]
+/// Обмеження розміру відповіді: пошук слова, що є майже в кожному наказі, інакше
+/// серіалізує десятки мегабайт. 0 - без обмеження
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResultLimits {
+    /// Документів у відповіді
+    pub max_results: usize,
+    /// Абзаців і збігів на документ
+    pub max_paragraphs: usize,
+}
+
+impl ResultLimits {
+    /// Обмеження із запиту можуть лише зменшити налаштовані (0 в запиті - без змін)
+    pub fn with_request(self, max_results: Option<usize>, max_paragraphs: Option<usize>) -> Self {
+        fn lower(limit: usize, requested: Option<usize>) -> usize {
+            match requested {
+                Some(requested) if requested > 0 && (limit == 0 || requested < limit) => requested,
+                _ => limit,
+            }
+        }
+        Self {
+            max_results: lower(self.max_results, max_results),
+            max_paragraphs: lower(self.max_paragraphs, max_paragraphs),
+        }
+    }
+
+    /// Відкидає документи понад max_results; повертає, скільки відкинуто
+    pub fn truncate_results(&self, results: &mut Vec<SearchEngineResult>) -> usize {
+        let omitted = match self.max_results {
+            0 => 0,
+            max => results.len().saturating_sub(max),
+        };
+        results.truncate(results.len() - omitted);
+        omitted
+    }
+
+    /// Відкидає збіги документа понад max_paragraphs (абзаци потрібні для контексту збігів)
+    pub fn truncate_matches(&self, result: &mut SearchEngineResult) {
+        if self.max_paragraphs > 0 {
+            result.matches.truncate(self.max_paragraphs);
+        }
+    }
+
+    /// Відкидає абзаци і збіги документа понад max_paragraphs; повертає, скільки абзаців відкинуто
+    pub fn truncate_paragraphs(&self, result: &mut SearchEngineResult) -> usize {
+        if self.max_paragraphs == 0 {
+            return 0;
+        }
+        result.matches.truncate(self.max_paragraphs);
+        let omitted = result.all_paragraphs.len().saturating_sub(self.max_paragraphs);
+        result.all_paragraphs.truncate(self.max_paragraphs);
+        omitted
+    }
+}
+
+/// Розібраний запит з операторами: "петренко OR іваненко -відпустка". Групи, розділені
+/// OR (або АБО), об'єднуються; у групі мають бути всі слова, як у звичайному запиті.
+/// Документ, де є хоч одне слово з мінусом, до результатів не потрапляє.
+/// "петренко NEAR/3 відпустка" (або ПОРУЧ/3) - слова в абзаці не далі 3 слів одне від одного.
+/// "filename:123" (або назва:123) - слово імені файлу: номер наказу часто є лише в ньому.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryPlan {
+    /// Групи термінів після переписування і стемінгу; група без термінів можлива лише
+    /// зі словами імені файлу
+    pub groups: Vec<Vec<String>>,
+    /// Слова імені файлу кожної групи ("filename:123"), паралельно groups
+    pub name_groups: Vec<Vec<String>>,
+    /// Слова імені файлу з мінусом ("-filename:чернетка")
+    pub name_excluded: Vec<String>,
+    /// Оператори NEAR/n кожної групи, паралельно groups
+    pub near: Vec<Vec<NearConstraint>>,
+    /// Терміни з мінусом
+    pub excluded: Vec<String>,
+    /// Точний пошук (exact): абзац має містити саме написані слова, а не їх основи.
+    /// Інвертований індекс і далі шукає кандидатів за основами (groups) - основа точного
+    /// слова завжди є в індексі документа з ним, тож окремий індекс слів не потрібен
+    pub exact: bool,
+    /// Лише для exact: слова груп і слова з мінусом без стемінгу, паралельно groups і excluded
+    pub literal_groups: Vec<Vec<String>>,
+    pub literal_excluded: Vec<String>,
+}
+
+impl QueryPlan {
+    fn parse(query: &str, exact: bool) -> Self {
+        let mut plan = QueryPlan { exact, ..Default::default() };
+        let (mut group, mut literal_group, mut near) = (Vec::new(), Vec::new(), Vec::new());
+        let mut name_group = Vec::new();
+        // Відстань NEAR/n, що чекає на слово праворуч
+        let mut pending_near = None;
+        for token in query.split_whitespace() {
+            if token == "OR" || token == "АБО" {
+                if !group.is_empty() || !name_group.is_empty() {
+                    plan.groups.push(std::mem::take(&mut group));
+                    plan.literal_groups.push(std::mem::take(&mut literal_group));
+                    plan.near.push(std::mem::take(&mut near));
+                    plan.name_groups.push(std::mem::take(&mut name_group));
+                }
+                pending_near = None;
+                continue;
+            }
+            if let Some(distance) = Self::near_distance(token) {
+                pending_near = Some(distance);
+                continue;
+            }
+            let negated = token.strip_prefix('-').filter(|word| !word.is_empty());
+            if let Some(value) = Self::name_field(negated.unwrap_or(token)) {
+                let terms = Self::name_terms(value);
+                match negated {
+                    Some(_) => plan.name_excluded.extend(terms),
+                    None => name_group.extend(terms),
+                }
+                pending_near = None;
+                continue;
+            }
+            let literal = |word: &str| if exact { Self::literal_terms(word) } else { Vec::new() };
+            match negated {
+                Some(word) => {
+                    plan.excluded.extend(Self::token_terms(word));
+                    plan.literal_excluded.extend(literal(word));
+                    pending_near = None;
+                }
+                None => {
+                    // Ліве слово NEAR - останній термін групи до цього слова, праве - перший його
+                    let terms = if exact { &literal_group } else { &group };
+                    let left = terms.last().cloned();
+                    let start = terms.len();
+                    group.extend(Self::token_terms(token));
+                    literal_group.extend(literal(token));
+                    let terms = if exact { &literal_group } else { &group };
+                    if let (Some(distance), Some(left), Some(right)) = (pending_near.take(), left, terms.get(start)) {
+                        near.push(NearConstraint { left, right: right.clone(), distance });
+                    }
+                }
+            }
+        }
+        if !group.is_empty() || !name_group.is_empty() {
+            plan.groups.push(group);
+            plan.literal_groups.push(literal_group);
+            plan.near.push(near);
+            plan.name_groups.push(name_group);
+        }
+        plan
+    }
+
+    /// Значення поля імені файлу: "filename:123" або "назва:123" -> "123"
+    fn name_field(token: &str) -> Option<&str> {
+        let (field, value) = token.split_once(':')?;
+        NAME_FIELDS.iter().any(|name| field.to_lowercase() == *name).then_some(value)
+    }
+
+    /// Слова значення поля імені файлу, як у InvertedIndex::extract_name_words; "12*" -
+    /// початок слова (імен небагато, тож обмеження довжини немає)
+    fn name_terms(value: &str) -> Vec<String> {
+        let value = value.to_lowercase().replace('\'', "");
+        if let Some(prefix) = value.strip_suffix(PREFIX_WILDCARD) {
+            if WORD_REGEX.find(prefix).is_some_and(|m| m.len() == prefix.len()) {
+                return vec![value];
+            }
+        }
+        WORD_REGEX.find_iter(&value).map(|m| m.as_str().to_string()).collect()
+    }
+
+    /// Чи є в запиті щось для пошуку (слова з мінусом самі нічого не знаходять)
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// "NEAR/3" або "ПОРУЧ/3" - найбільша кількість слів між сусідніми словами запиту
+    fn near_distance(token: &str) -> Option<usize> {
+        let (operator, distance) = token.split_once('/')?;
+        if operator == "NEAR" || operator == "ПОРУЧ" {
+            distance.parse().ok()
+        } else {
+            None
+        }
+    }
+
+    /// Терміни одного слова запиту: без апострофів, після стемінгу, у нижньому регістрі.
+    /// "пол*" - початок слова без стемінгу (див. InvertedIndex::words_with_prefix)
+    fn token_terms(token: &str) -> Vec<String> {
+        let token = token.replace('\'', "");
+        if let Some(prefix) = Self::prefix_term(&token) {
+            return vec![prefix];
+        }
+        let stemmed = stemmer::stem_word(&token);
+        WORD_REGEX.find_iter(&stemmed).map(|m| m.as_str().to_lowercase()).collect()
+    }
+
+    /// Слова одного слова запиту як написані: без апострофів, у нижньому регістрі
+    fn literal_terms(token: &str) -> Vec<String> {
+        let token = token.replace('\'', "");
+        if let Some(prefix) = Self::prefix_term(&token) {
+            return vec![prefix];
+        }
+        WORD_REGEX.find_iter(&token).map(|m| m.as_str().to_lowercase()).collect()
+    }
+
+    /// "пол*" - початок слова, якщо це одне слово не коротше MIN_PREFIX_CHARS
+    fn prefix_term(token: &str) -> Option<String> {
+        let prefix = token.strip_suffix(PREFIX_WILDCARD)?.to_lowercase();
+        let is_word = WORD_REGEX.find(&prefix).is_some_and(|m| m.len() == prefix.len());
+        (is_word && prefix.chars().count() >= MIN_PREFIX_CHARS).then(|| format!("{}{}", prefix, PREFIX_WILDCARD))
+    }
+
+    /// Усі терміни груп без повторів і без "*" (для підсвічування і ранжування)
+    pub fn terms(&self) -> Vec<String> {
+        Self::unique_terms(&self.groups)
+    }
+
+    /// Терміни для підсвічування: для exact - слова як написані
+    pub fn highlight_terms(&self) -> Vec<String> {
+        Self::unique_terms(if self.exact { &self.literal_groups } else { &self.groups })
+    }
+
+    fn unique_terms(groups: &[Vec<String>]) -> Vec<String> {
+        let mut terms: Vec<String> = Vec::new();
+        for term in groups.iter().flatten() {
+            let term = term.trim_end_matches(PREFIX_WILDCARD);
+            if !terms.iter().any(|t| t == term) {
+                terms.push(term.to_string());
+            }
+        }
+        terms
+    }
+
+    /// Абзац (нижній регістр, без апострофів) містить усі слова хоч однієї групи; для
+    /// 2-3 слів (ПІБ) вони ще й мають іти поспіль у межах proximity_window, а з NEAR/n -
+    /// у межах n слів. named - групи, яким підходить ім'я файлу документа (name_filter);
+    /// групи лише зі словами імені абзаців не шукають
+    fn matches_paragraph(&self, engine: &SearchEngine, normalized_paragraph: &str, named: &[bool]) -> bool {
+        let groups = if self.exact { &self.literal_groups } else { &self.groups };
+        let mut groups = groups.iter().zip(&self.near).zip(named).filter(|(_, named)| **named).map(|(group, _)| group);
+        if self.exact {
+            let words: HashSet<&str> = WORD_REGEX.find_iter(normalized_paragraph).map(|m| m.as_str()).collect();
+            return groups.any(|(group, near)| {
+                !group.is_empty()
+                    && group.iter().all(|word| Self::has_word(&words, word))
+                    && self.near_or_name_proximity(engine, normalized_paragraph, group, near)
+            });
+        }
+        groups.any(|(group, near)| {
+            let has_all_words =
+                group.iter().all(|word| normalized_paragraph.contains(word.trim_end_matches(PREFIX_WILDCARD)));
+            !group.is_empty() && has_all_words && self.near_or_name_proximity(engine, normalized_paragraph, group, near)
+        })
+    }
+
+    /// Для кожної групи - чи є в імені файлу всі її слова імені (групи без них - завжди);
+    /// None - в імені є слово з мінусом або воно не підходить жодній групі
+    fn name_filter(&self, file_name: &str) -> Option<Vec<bool>> {
+        if self.name_excluded.is_empty() && self.name_groups.iter().all(Vec::is_empty) {
+            return Some(vec![true; self.groups.len()]);
+        }
+        let name_words = InvertedIndex::extract_name_words(file_name);
+        let words: HashSet<&str> = name_words.iter().map(String::as_str).collect();
+        if self.name_excluded.iter().any(|word| Self::has_word(&words, word)) {
+            return None;
+        }
+        let named: Vec<bool> =
+            self.name_groups.iter().map(|group| group.iter().all(|word| Self::has_word(&words, word))).collect();
+        named.contains(&true).then_some(named)
+    }
+
+    /// Документ знайдено за самим іменем файлу: підійшла група без слів для абзаців
+    fn matches_by_name(&self, named: &[bool]) -> bool {
+        self.groups.iter().zip(named).any(|(group, &named)| named && group.is_empty())
+    }
+
+    /// Явний NEAR/n замінює перевірку близькості ПІБ, яка інакше діє для 2-3 слів
+    fn near_or_name_proximity(
+        &self,
+        engine: &SearchEngine,
+        normalized_paragraph: &str,
+        group: &[String],
+        near: &[NearConstraint],
+    ) -> bool {
+        if near.is_empty() {
+            let is_name_search = group.len() >= 2 && group.len() <= 3;
+            return !is_name_search || engine.check_words_proximity(normalized_paragraph, group);
+        }
+        // Відстань - у словах, тож розділові знаки і довжина відмінків не важать
+        let words: Vec<&str> = WORD_REGEX.find_iter(normalized_paragraph).map(|m| m.as_str()).collect();
+        let positions = |term: &str| -> Vec<usize> {
+            words.iter().enumerate().filter(|(_, word)| self.word_matches(word, term)).map(|(i, _)| i).collect()
+        };
+        near.iter().all(|constraint| {
+            let right = positions(&constraint.right);
+            positions(&constraint.left).into_iter().any(|left| {
+                right.iter().any(|&right| right != left && left.abs_diff(right) <= constraint.distance + 1)
+            })
+        })
+    }
+
+    /// Слово абзацу - це термін: точне слово (exact), слово з початком "пол*" або форма основи
+    fn word_matches(&self, word: &str, term: &str) -> bool {
+        match term.strip_suffix(PREFIX_WILDCARD) {
+            Some(prefix) => word.starts_with(prefix),
+            None if self.exact => word == term,
+            None => word.starts_with(term),
+        }
+    }
+
+    /// Чи є в документі слово з мінусом (для exact - саме таке слово)
+    fn excludes_document(&self, paragraphs: &[Paragraph]) -> bool {
+        let excluded = if self.exact { &self.literal_excluded } else { &self.excluded };
+        !excluded.is_empty()
+            && paragraphs.iter().any(|paragraph| {
+                let normalized_paragraph = paragraph.text.to_lowercase().replace('\'', "");
+                if self.exact {
+                    let words: HashSet<&str> = WORD_REGEX.find_iter(&normalized_paragraph).map(|m| m.as_str()).collect();
+                    excluded.iter().any(|word| Self::has_word(&words, word))
+                } else {
+                    excluded.iter().any(|word| normalized_paragraph.contains(word.trim_end_matches(PREFIX_WILDCARD)))
+                }
+            })
+    }
+
+    /// Ціле слово абзацу або, для "пол*", слово з таким початком
+    fn has_word(words: &HashSet<&str>, word: &str) -> bool {
+        match word.strip_suffix(PREFIX_WILDCARD) {
+            Some(prefix) => words.iter().any(|w| w.starts_with(prefix)),
+            None => words.contains(word),
+        }
+    }
+}
+
+/// Оператор NEAR/n: між словами left і right (у будь-якому порядку) не більше distance слів
+#[derive(Debug, Clone, PartialEq)]
+pub struct NearConstraint {
+    pub left: String,
+    pub right: String,
+    pub distance: usize,
+}
+
+/// Результати пошуку і чи встиг він перевірити всіх кандидатів
+#[derive(Debug, Default)]
+pub struct SearchOutcome {
+    pub results: Vec<SearchEngineResult>,
+    /// Час на запит (search.query_timeout_ms) вичерпано - результати неповні
+    pub partial: bool,
+    /// Яку частину індексу переглянуто (None - порожній запит, пошуку не було)
+    pub scope: Option<SearchScope>,
+    /// Знайдені документи за папками років і їх підпапками
+    pub facets: SearchFacets,
+}
+
+/// Частина індексу, яку переглянув пошук
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SearchScope {
+    /// quick - швидкий набір найновіших документів, remaining - решта, full - усі
+    pub mode: SearchMode,
+    /// Документів у переглянутій частині
+    pub documents: usize,
+    /// Дата найстаршого документа швидкого набору (ДД.ММ.РРРР): quick переглядає накази
+    /// з неї, remaining - до неї
+    pub quick_since: Option<String>,
+    /// Лише quick: оцінка за інвертованим індексом, скільки ще документів зі збігами в решті
+    /// архіву (з запасом - без перевірки абзаців); None - оцінки немає
+    pub remaining_estimate: Option<usize>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
 pub enum SearchMode {
     Quick,
     Full,
     Remaining,
 }
 
+/// Пошук читає незмінне завантажене покоління індексів. Оновлення будує нове
+/// покоління повністю поза блокуванням і лише підміняє вказівник, тому
+/// пошукові запити не чекають на завантаження і не бачать напівоновлених даних.
+/// Налаштування пошуку можна змінювати на ходу (set_*), наприклад при перезавантаженні конфігурації.
 pub struct SearchEngine {
-    data: Mutex<SearchEngineData>,
+    documents_index_path: String,
+    inverted_index_path: String,
+    /// Сховище індексів замість файлів поколінь (storage.backend)
+    store: Option<Arc<dyn IndexStore>>,
+    /// Розмір вікна швидкого пошуку
+    quick_mode_documents: AtomicUsize,
+    /// Швидкий набір - накази за стільки останніх місяців (0 - останні quick_mode_documents)
+    quick_mode_months: AtomicU32,
+    /// Максимальна відстань між сусідніми словами запиту в абзаці (символів)
+    proximity_window: AtomicUsize,
+    max_results: AtomicUsize,
+    max_paragraphs: AtomicUsize,
+    /// 0 - без обмеження часу
+    query_timeout_ms: AtomicU64,
+    /// Одночасні пошуки і черга; при зміні обмежень пошуки, що вже йдуть, дотримують старих
+    admission: RwLock<Arc<SearchAdmission>>,
+    /// Переписування запитів перед обробкою (None - запит як є)
+    query_rewriter: RwLock<Option<Arc<dyn QueryRewriter>>>,
+    slow_queries: SlowQueryLog,
+    /// Оцінки користувачів, що зсувають документи у впорядкованих результатах
+    feedback: RelevanceFeedback,
+    /// Примітки користувачів до абзаців
+    annotations: AnnotationStore,
+    /// Початки абзаців (у нижньому регістрі), які пропускаються у кожному виді перегляду
+    paragraph_filters: RwLock<BTreeMap<String, Vec<String>>>,
+    /// Зростає з кожним застосуванням налаштувань пошуку (див. results_version)
+    settings_revision: AtomicU64,
+    /// Знайдене повторними запитами в поточному поколінні (див. result_cache)
+    result_cache: ResultCache<SearchEngineData, CachedOutcome>,
+    current: RwLock<Arc<SearchEngineData>>,
+}
+
+/// Результати запиту в кеші без тексту документів: doc_id і абзаци, що збіглися, у
+/// впорядкованому вигляді (до зсувів за оцінками - вони змінюються між запитами)
+struct CachedOutcome {
+    results: Vec<(u64, Vec<usize>)>,
+    scope: SearchScope,
+    facets: SearchFacets,
 }
 
 struct SearchEngineData {
+    /// Покоління з маніфесту (None - індекс без маніфесту або порожній)
+    generation: Option<u64>,
     index: DocumentIndex,
     inverted_index: Option<InvertedIndex>,
 }
 
-// Функція для перевірки чи ПОЧИНАЄТЬСЯ параграф з заборонених слів для особових файлів
-fn starts_with_personal_stop_words(paragraph: &str) -> bool {
-    let binding = paragraph.to_lowercase();
-    let lower_paragraph = binding.trim();
-    PERSONAL_FILE_STOP_WORDS.iter().any(|stop_word| lower_paragraph.starts_with(stop_word))
+/// Початки абзаців без пробілів по краях, у нижньому регістрі, без порожніх
+fn normalize_prefixes(prefixes: &[String]) -> Vec<String> {
+    prefixes
+        .iter()
+        .map(|prefix| prefix.trim().to_lowercase())
+        .filter(|prefix| !prefix.is_empty())
+        .collect()
 }
 
 impl SearchEngine {
-    pub fn new() -> Self {
+    pub fn new(paths: &IndexPaths) -> Self {
         Self {
-            data: Mutex::new(SearchEngineData {
+            documents_index_path: paths.documents_index(),
+            inverted_index_path: paths.inverted_index(),
+            store: None,
+            quick_mode_documents: AtomicUsize::new(DEFAULT_QUICK_MODE_DOCUMENTS),
+            quick_mode_months: AtomicU32::new(DEFAULT_QUICK_MODE_MONTHS),
+            proximity_window: AtomicUsize::new(DEFAULT_PROXIMITY_WINDOW),
+            max_results: AtomicUsize::new(DEFAULT_MAX_RESULTS),
+            max_paragraphs: AtomicUsize::new(DEFAULT_MAX_PARAGRAPHS),
+            query_timeout_ms: AtomicU64::new(DEFAULT_QUERY_TIMEOUT_MS),
+            admission: RwLock::new(Arc::new(SearchAdmission::default())),
+            query_rewriter: RwLock::new(None),
+            slow_queries: SlowQueryLog::new(paths.slow_query_log()),
+            feedback: RelevanceFeedback::load(paths.relevance_feedback()),
+            annotations: AnnotationStore::load(paths.annotations()),
+            paragraph_filters: RwLock::new(default_paragraph_filters()),
+            settings_revision: AtomicU64::new(0),
+            result_cache: ResultCache::new(DEFAULT_RESULT_CACHE_ENTRIES),
+            current: RwLock::new(Arc::new(SearchEngineData {
+                generation: None,
                 index: DocumentIndex::new(),
                 inverted_index: None,
-            }),
+            })),
         }
     }
 
-    /// Витягує дату з назви файлу у форматі DD.MM.YYYY
-    fn extract_date_from_filename(file_path: &str) -> Option<(u32, u32, u32)> {
-        let filename = Path::new(file_path)
-            .file_name()?
-            .to_str()?;
+    /// Індекси, побудовані в пам'яті (див. index_builder), замість завантаження з диска.
+    /// reload() після цього знову читає файли з paths.
+    pub fn with_index(self, index: DocumentIndex, inverted_index: InvertedIndex) -> Self {
+        let mut index = index;
+        index.rebuild_id_map();
+        let mut inverted_index = inverted_index;
+        inverted_index.build_word_dictionary();
+        inverted_index.build_name_index(&index);
+        let data = SearchEngineData { generation: None, index, inverted_index: Some(inverted_index) };
+        match self.current.write() {
+            Ok(mut current) => *current = Arc::new(data),
+            Err(poisoned) => *poisoned.into_inner() = Arc::new(data),
+        }
+        self.result_cache.clear();
+        self
+    }
 
-        if let Some(captures) = DATE_REGEX.captures(filename) {
-            let day: u32 = captures.get(1)?.as_str().parse().ok()?;
-            let month: u32 = captures.get(2)?.as_str().parse().ok()?;
-            let year: u32 = captures.get(3)?.as_str().parse().ok()?;
+    /// Читає індекси зі сховища (див. index_store) замість файлів поколінь
+    pub fn with_store(mut self, store: Arc<dyn IndexStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
 
-            // Перевірка валідності дати
-            if day >= 1 && day <= 31 && month >= 1 && month <= 12 && year >= 1900 {
-                Some((year, month, day))
-            } else {
-                None
-            }
-        } else {
-            None
+    /// Задає, скільки найновіших документів переглядає швидкий пошук
+    pub fn with_quick_mode_documents(self, quick_mode_documents: usize) -> Self {
+        self.set_quick_mode_documents(quick_mode_documents);
+        self
+    }
+
+    /// Задає переписування запитів (наприклад, жаргон -> офіційне формулювання)
+    pub fn with_query_rewriter(self, rewriter: Arc<dyn QueryRewriter>) -> Self {
+        self.set_query_rewriter(Some(rewriter));
+        self
+    }
+
+    pub fn set_quick_mode_documents(&self, quick_mode_documents: usize) {
+        self.quick_mode_documents.store(quick_mode_documents, Ordering::Relaxed);
+        self.result_cache.clear();
+    }
+
+    /// Швидкий набір - накази, датовані не раніше ніж months місяців тому
+    /// (None - останні quick_mode_documents документів індексу)
+    pub fn set_quick_mode_months(&self, months: Option<u32>) {
+        self.quick_mode_months.store(months.unwrap_or(0), Ordering::Relaxed);
+        self.result_cache.clear();
+    }
+
+    pub fn set_proximity_window(&self, proximity_window: usize) {
+        self.proximity_window.store(proximity_window, Ordering::Relaxed);
+        self.result_cache.clear();
+    }
+
+    /// Обмеження розміру відповіді (див. ResultLimits)
+    pub fn set_result_limits(&self, limits: ResultLimits) {
+        self.max_results.store(limits.max_results, Ordering::Relaxed);
+        self.max_paragraphs.store(limits.max_paragraphs, Ordering::Relaxed);
+    }
+
+    pub fn result_limits(&self) -> ResultLimits {
+        ResultLimits {
+            max_results: self.max_results.load(Ordering::Relaxed),
+            max_paragraphs: self.max_paragraphs.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Час на один запит, мс (0 - без обмеження)
+    pub fn set_query_timeout_ms(&self, timeout_ms: u64) {
+        self.query_timeout_ms.store(timeout_ms, Ordering::Relaxed);
+    }
+
+    /// Скільки пошуків виконується одночасно (0 - без обмеження) і скільки чекає в черзі
+    pub fn set_admission_limits(&self, max_concurrent: usize, queue_size: usize) {
+        let mut admission = match self.admission.write() {
+            Ok(admission) => admission,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if admission.limits() != (max_concurrent, queue_size) {
+            *admission = Arc::new(SearchAdmission::new(max_concurrent, queue_size));
+        }
+    }
+
+    /// Поріг журналу повільних запитів, мс (0 - не записувати)
+    pub fn set_slow_query_threshold_ms(&self, threshold_ms: u64) {
+        self.slow_queries.set_threshold_ms(threshold_ms);
+    }
+
+    /// На скільки позицій оцінки релевантності можуть зсунути документ (0 - не зсувати)
+    pub fn set_feedback_max_shift(&self, max_shift: usize) {
+        self.feedback.set_max_shift(max_shift);
+    }
+
+    /// Задає початки абзаців, які пропускаються у кожному виді перегляду (без різниці в регістрі)
+    pub fn set_paragraph_filters(&self, filters: &BTreeMap<String, Vec<String>>) {
+        let filters = filters
+            .iter()
+            .map(|(view_mode, prefixes)| (view_mode.clone(), normalize_prefixes(prefixes)))
+            .collect();
+        match self.paragraph_filters.write() {
+            Ok(mut current) => *current = filters,
+            Err(poisoned) => *poisoned.into_inner() = filters,
+        }
+        self.result_cache.clear();
+    }
+
+    /// Початки абзаців, які пропускає пошук: з запиту, якщо задано, інакше - для виду перегляду
+    pub fn skip_prefixes(&self, view_mode: Option<&str>, overrides: Option<&[String]>) -> Vec<String> {
+        if let Some(overrides) = overrides {
+            return normalize_prefixes(overrides);
+        }
+        let Some(view_mode) = view_mode else {
+            return Vec::new();
+        };
+        let filters = match self.paragraph_filters.read() {
+            Ok(filters) => filters,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        filters.get(view_mode).cloned().unwrap_or_default()
+    }
+
+    /// Оцінки релевантності (запис і зведення)
+    pub fn feedback(&self) -> &RelevanceFeedback {
+        &self.feedback
+    }
+
+    /// Примітки до абзаців (додавання і показ у результатах)
+    pub fn annotations(&self) -> &AnnotationStore {
+        &self.annotations
+    }
+
+    /// Терміни запиту після переписування і стемінгу - так само, як їх шукає search
+    /// (слова всіх груп OR, без слів з мінусом)
+    pub fn query_terms(&self, query: &str) -> Vec<String> {
+        self.query_plan(query, false).terms()
+    }
+
+    /// Запит з операторами OR і -слово після переписування і стемінгу; exact - ще й слова
+    /// як написані (див. SearchFilters::exact)
+    pub fn query_plan(&self, query: &str, exact: bool) -> QueryPlan {
+        QueryPlan::parse(&self.rewrite_query(query), exact)
+    }
+
+    pub fn set_query_rewriter(&self, rewriter: Option<Arc<dyn QueryRewriter>>) {
+        match self.query_rewriter.write() {
+            Ok(mut current) => *current = rewriter,
+            Err(poisoned) => *poisoned.into_inner() = rewriter,
+        }
+        self.result_cache.clear();
+    }
+
+    /// Поточне покоління індексів. Утримання Arc не блокує публікацію нового покоління.
+    fn snapshot(&self) -> Arc<SearchEngineData> {
+        match self.current.read() {
+            Ok(current) => Arc::clone(&current),
+            Err(poisoned) => Arc::clone(&poisoned.into_inner()),
         }
     }
 
     /// Порівняння дат для сортування (від нової до старої)
+    /// Порядок результатів: за датою наказу (від нових до старих), потім за кількістю збігів
+    pub fn compare_results(a: &SearchEngineResult, b: &SearchEngineResult) -> std::cmp::Ordering {
+        let date_a = a.date.map(|date| date.ymd());
+        let date_b = b.date.map(|date| date.ymd());
+
+        // Порівнюємо за датою
+        match Self::compare_dates(date_a, date_b) {
+            std::cmp::Ordering::Equal => {
+                // Якщо дати однакові, сортуємо за кількістю збігів
+                b.matches.len().cmp(&a.matches.len())
+            }
+            other => other,
+        }
+    }
+
     fn compare_dates(date1: Option<(u32, u32, u32)>, date2: Option<(u32, u32, u32)>) -> std::cmp::Ordering {
         match (date1, date2) {
             (Some((y1, m1, d1)), Some((y2, m2, d2))) => {
@@ -123,58 +902,138 @@ impl SearchEngine {
         }
     }
 
-    pub fn load_from_file(&mut self, index_path: &str) -> Result<(), String> {
-        let content = fs::read_to_string(index_path)
-            .map_err(|e| format!("Помилка читання індексу: {}", e))?;
+    /// Номер активного покоління: з маніфесту або версія сховища
+    fn active_generation(&self) -> Option<u64> {
+        match &self.store {
+            Some(store) => store.generation().unwrap_or_else(|e| {
+                warn!("⚠️ Не вдалося прочитати версію сховища індексу: {}", e);
+                None
+            }),
+            None => IndexManifest::load(&self.documents_index_path).map(|m| m.generation),
+        }
+    }
+
+    pub fn reload(&self) -> Result<(), SearchError> {
+        // Активне покоління визначається маніфестом (або сховищем)
+        let generation = self.active_generation();
+        let current = self.snapshot();
+        if generation.is_some()
+            && current.generation == generation
+            && current.inverted_index.is_some()
+        {
+            // Це покоління вже завантажене
+            return Ok(());
+        }
 
-        let index: DocumentIndex =
-            serde_json::from_str(&content).map_err(|e| format!("Помилка парсингу JSON: {}", e))?;
+        // Нове покоління завантажується повністю, поки пошук продовжує працювати зі старим
+        let data = self.load_generation(generation)?;
 
-        // ❌ НЕ сортуємо документи тут, бо це зламає інвертований індекс!
-        // Замість цього сортуємо РЕЗУЛЬТАТИ ПОШУКУ в методі search()
+        let mut current = self.current.write()
+            .map_err(|e| SearchError::LockPoisoned(e.to_string()))?;
+        *current = Arc::new(data);
+        self.result_cache.clear();
 
-        // Спробуємо завантажити інвертований індекс
-        let inverted_path = "inverted_index.json";
-        let inverted_index = if std::path::Path::new(inverted_path).exists() {
-            InvertedIndex::load_from_file(inverted_path).ok()
-        } else {
-            None
+        Ok(())
+    }
+
+    /// Оновлює індекси в пам'яті змінами щойно записаного покоління (див. IndexDelta),
+    /// не перечитуючи файли. Якщо завантажене інше покоління (або дельта не підходить) -
+    /// повне перезавантаження через reload().
+    pub fn apply_delta(&self, delta: &IndexDelta) -> Result<(), SearchError> {
+        // Маніфест міг уже перемкнутись далі (виправлення, інше оновлення)
+        let generation = self.active_generation();
+        let current = self.snapshot();
+        let inverted_index = match &current.inverted_index {
+            Some(inverted_index)
+                if generation == Some(delta.generation) && current.generation == Some(delta.base_generation) =>
+            {
+                inverted_index
+            }
+            _ => {
+                info!("{}", tr!(IndexDeltaSkipped, delta.generation));
+                return self.reload();
+            }
+        };
+
+        // Пошук продовжує працювати зі старим знімком, поки готується новий
+        let mut index = current.index.clone();
+        let mut inverted_index = inverted_index.clone();
+        if !delta.apply(&mut index, &mut inverted_index) {
+            warn!("{}", tr!(IndexDeltaMismatch, delta.generation));
+            return self.reload();
+        }
+        inverted_index.build_word_dictionary();
+        inverted_index.build_name_index(&index);
+
+        let data = SearchEngineData {
+            generation: Some(delta.generation),
+            index,
+            inverted_index: Some(inverted_index),
         };
 
-        // Оновлюємо дані з блокуванням
-        let mut data = self.data.lock()
-            .map_err(|e| format!("Помилка блокування даних: {}", e))?;
-        data.index = index;
-        data.inverted_index = inverted_index;
+        let mut current = self.current.write()
+            .map_err(|e| SearchError::LockPoisoned(e.to_string()))?;
+        *current = Arc::new(data);
+        self.result_cache.clear();
 
+        info!("{}", tr!(IndexDeltaApplied, delta.generation, delta.changed.len(), delta.deleted.len()));
         Ok(())
     }
 
-    pub fn reload(&self, index_path: &str) -> Result<(), String> {
-        let content = fs::read_to_string(index_path)
-            .map_err(|e| format!("Помилка читання індексу: {}", e))?;
+    fn load_generation(&self, generation: Option<u64>) -> Result<SearchEngineData, IndexError> {
+        if let Some(store) = &self.store {
+            // Обидва індекси - з однієї транзакції, покоління завжди узгоджені
+            let (index, mut inverted_index) = store.load()?.ok_or_else(store_is_empty)?;
+            inverted_index.build_word_dictionary();
+            inverted_index.build_name_index(&index);
+            return Ok(SearchEngineData {
+                generation: Some(index.generation),
+                index,
+                inverted_index: Some(inverted_index),
+            });
+        }
+
+        let (index_path, inverted_path) =
+            resolve_active_paths(&self.documents_index_path, &self.inverted_index_path);
+
+        if !Path::new(&index_path).exists() {
+            return Err(IndexError::NotFound(format!("Індекс документів не знайдено: {}", index_path)));
+        }
 
-        let index: DocumentIndex =
-            serde_json::from_str(&content).map_err(|e| format!("Помилка парсингу JSON: {}", e))?;
+        let index = DocumentIndex::load_from_file(&index_path)?;
 
         // ❌ НЕ сортуємо документи тут, бо це зламає інвертований індекс!
         // Замість цього сортуємо РЕЗУЛЬТАТИ ПОШУКУ в методі search()
 
         // Спробуємо завантажити інвертований індекс
-        let inverted_path = "inverted_index.json";
-        let inverted_index = if std::path::Path::new(inverted_path).exists() {
-            InvertedIndex::load_from_file(inverted_path).ok()
+        let inverted_index = if std::path::Path::new(&inverted_path).exists() {
+            InvertedIndex::load_from_file(&inverted_path).ok()
         } else {
             None
         };
 
-        // Оновлюємо дані з блокуванням
-        let mut data = self.data.lock()
-            .map_err(|e| format!("Помилка блокування даних: {}", e))?;
-        data.index = index;
-        data.inverted_index = inverted_index;
+        // Інвертований індекс іншого оновлення дав би записи на чужі документи -
+        // перебудовуємо його в пам'яті, доки наступне оновлення не запише узгоджену пару
+        let inverted_index = inverted_index.map(|inverted_index| {
+            let mut inverted_index = if generations_match(index.generation, inverted_index.generation) {
+                inverted_index
+            } else {
+                warn!(
+                    "⚠️ Інвертований індекс з покоління {}, індекс документів - з {}: перебудовуємо в пам'яті",
+                    inverted_index.generation, index.generation
+                );
+                InvertedIndex::rebuild_from_scratch(&index)
+            };
+            inverted_index.build_word_dictionary();
+            inverted_index.build_name_index(&index);
+            inverted_index
+        });
 
-        Ok(())
+        Ok(SearchEngineData {
+            generation,
+            index,
+            inverted_index,
+        })
     }
 
     pub async fn search(
@@ -182,42 +1041,186 @@ impl SearchEngine {
         query: &str,
         mode: SearchMode,
         view_mode: Option<&str>,
-    ) -> Result<Vec<SearchEngineResult>, String> {
+    ) -> Result<Vec<SearchEngineResult>, SearchError> {
+        self.search_outcome(query, mode, view_mode).await.map(|outcome| outcome.results)
+    }
+
+    /// Пошук з ознакою неповних результатів: відбір кандидатів в індексі і перевірка абзаців
+    /// (від найновіших документів) зупиняються, щойно вичерпано search.query_timeout_ms, тож
+    /// один важкий запит не тримає воркер сервера довше за цей час
+    pub async fn search_outcome(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        view_mode: Option<&str>,
+    ) -> Result<SearchOutcome, SearchError> {
+        self.search_outcome_filtered(query, mode, view_mode, &SearchFilters::default()).await
+    }
+
+    /// Те саме, що search_outcome, з фільтрами запиту: власні початки абзаців, які не
+    /// вважаються збігами, і формати документів
+    pub async fn search_outcome_filtered(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        view_mode: Option<&str>,
+        filters: &SearchFilters,
+    ) -> Result<SearchOutcome, SearchError> {
         if query.trim().is_empty() {
-            return Ok(Vec::new());
+            return Ok(SearchOutcome::default());
         }
 
+        let admission = match self.admission.read() {
+            Ok(admission) => Arc::clone(&admission),
+            Err(poisoned) => Arc::clone(&poisoned.into_inner()),
+        };
+        let _permit = admission.admit().await?;
+
         // Спробуємо автоматично перезавантажити індекси якщо потрібно
         self.try_reload_indices_if_needed();
 
-        let processed_query = self.process_search_query(query);
-        let query_words = self.extract_search_words(&processed_query);
+        let started = Instant::now();
+        let plan = self.query_plan(query, filters.exact);
+        // Регулярний вираз не ділиться на слова запиту
+        let query_words = if filters.regex.is_some() { Vec::new() } else { plan.terms() };
 
-        if query_words.is_empty() {
-            return Ok(Vec::new());
+        if plan.is_empty() && filters.regex.is_none() {
+            return Ok(SearchOutcome::default());
+        }
+
+        // Працюємо з незмінним поколінням: паралельне перезавантаження його не змінить
+        let data = self.snapshot();
+        let cache_key = self.cache_key(query, mode, view_mode, filters);
+        if let Some(cached) = cache_key.as_deref().and_then(|key| self.result_cache.get(key, &data)) {
+            if let Some(mut results) = Self::cached_results(&data.index, &cached) {
+                if filters.sort.reranked() {
+                    self.feedback.rerank(&mut results, &query_words);
+                }
+                let facets = cached.facets.clone();
+                return Ok(SearchOutcome { results, partial: false, scope: Some(cached.scope.clone()), facets });
+            }
         }
 
+        let skip_prefixes = self.skip_prefixes(view_mode, filters.skip_prefixes.as_deref());
+        let skipped = |paragraph_lower: &str| {
+            let paragraph_lower = paragraph_lower.trim();
+            skip_prefixes.iter().any(|prefix| paragraph_lower.starts_with(prefix.as_str()))
+        };
+
+        let timeout_ms = self.query_timeout_ms.load(Ordering::Relaxed);
+        let deadline = (timeout_ms > 0).then(|| started + Duration::from_millis(timeout_ms));
+        let timed_out = || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        let mut partial = false;
+
         let mut results = Vec::new();
+        let prepare_time = started.elapsed();
+        let mut candidates_time = Duration::ZERO;
+        let candidate_count;
+
+        let (slots, mut scope) = self.scope(&data.index, mode);
+
+        if let Some(pattern) = &filters.regex {
+            // Вираз не розкладається на слова індексу: абзаци переглядаються від найновіших
+            // документів, доки не вичерпано час на запит
+            candidate_count = scope.documents;
+            for document in data.index.documents[slots].iter().rev().filter(|d| !d.deleted && filters.accepts(d)) {
+                if timed_out() {
+                    partial = true;
+                    break;
+                }
+                let paragraphs = document.get_paragraphs();
+                let mut document_matches = Vec::new();
+                // Вираз може бути повільним на довгих абзацах - час перевіряється на кожному
+                for (pos, paragraph) in paragraphs.iter().enumerate() {
+                    if timed_out() {
+                        partial = true;
+                        break;
+                    }
+                    let paragraph_lower = paragraph.text.to_lowercase();
+                    if !skipped(&paragraph_lower) && pattern.matches_paragraph(&paragraph.text, &paragraph_lower) {
+                        document_matches.push(SearchEngineMatch {
+                            context: paragraph.text.clone(),
+                            position: pos,
+                        });
+                    }
+                }
 
-        // Отримуємо доступ до даних
-        let data = self.data.lock()
-            .map_err(|e| format!("Помилка блокування даних: {}", e))?;
+                if !document_matches.is_empty() {
+                    results.push(SearchEngineResult {
+                        doc_id: document.doc_id,
+                        file_name: document.file_name.clone(),
+                        file_path: document.file_path.clone(),
+                        matches: document_matches,
+                        all_paragraphs: paragraphs,
+                        file_size: document.file_size,
+                        last_modified: document.last_modified,
+                        category: document.category.clone(),
+                        date: document_date(document),
+                    });
+                }
+            }
+        } else if let Some(ref inverted_index) = data.inverted_index {
+            // Використовуємо інвертований індекс якщо доступний
+            // Швидкий пошук одразу оцінює решту архіву - до пошуку в ній
+            if mode == SearchMode::Quick {
+                let accept = |slot: usize| {
+                    data.index.documents.get(slot).is_some_and(|d| !d.deleted && filters.accepts(d))
+                };
+                // Для кількох груп OR - сума оцінок груп (теж з запасом)
+                let estimate = plan
+                    .groups
+                    .iter()
+                    .zip(&plan.name_groups)
+                    .map(|(group, name_group)| {
+                        if group.is_empty() {
+                            let named = inverted_index.name_documents(name_group, 0..slots.start);
+                            named.into_iter().filter(|&slot| accept(slot)).count()
+                        } else {
+                            inverted_index.estimate_documents(group, 0..slots.start, accept)
+                        }
+                    })
+                    .sum();
+                scope.remaining_estimate = Some(estimate);
+            }
 
-        // Використовуємо інвертований індекс якщо доступний
-        if let Some(ref inverted_index) = data.inverted_index {
-            // println!("🔍 Пошук через інвертований індекс для слів: {:?}", query_words);
-            // let (inv_docs, inv_words) = inverted_index.get_stats();
-            // println!("📊 Інвертований індекс: {} документів, {} унікальних слів", inv_docs, inv_words);
+            debug!("🔍 Пошук через інвертований індекс для слів: {:?}", query_words);
 
             // Отримуємо кандидатів документів з інвертованого індексу
-            let candidates = inverted_index.search_fast(&query_words, &data.index, &mode);
-            // println!("🎯 Знайдено {} кандидатів документів", candidates.len());
-
+            let phase = Instant::now();
+            // Точні слова з мінусом перевіряються в тексті: за основою індекс відкинув би й інші форми
+            let excluded = if plan.exact { &[][..] } else { &plan.excluded[..] };
+            // Розгортання "пол*" і OR теж обмежене часом на запит
+            let mut candidates = inverted_index
+                .search_boolean(&plan.groups, &plan.name_groups, excluded, slots.clone(), deadline)
+                .unwrap_or_else(|| {
+                    partial = true;
+                    Vec::new()
+                });
+            candidates_time = phase.elapsed();
+            candidate_count = candidates.len();
+            debug!("🎯 Знайдено {} кандидатів документів", candidate_count);
+
+            // Документи впорядковано за датою: при перериванні лишаються найновіші
+            candidates.sort_unstable_by_key(|(doc_idx, _)| std::cmp::Reverse(*doc_idx));
             for (doc_idx, paragraph_positions) in candidates {
-                if doc_idx < data.index.documents.len() {
+                if timed_out() {
+                    partial = true;
+                    break;
+                }
+                // Записи на tombstone-слоти лишаються в індексі до ущільнення
+                if doc_idx < data.index.documents.len()
+                    && !data.index.documents[doc_idx].deleted
+                    && filters.accepts(&data.index.documents[doc_idx])
+                {
                     let document = &data.index.documents[doc_idx];
+                    let Some(named) = plan.name_filter(&document.file_name) else {
+                        continue;
+                    };
                     let paragraphs = document.get_paragraphs();
                     let mut document_matches = Vec::new();
+                    if plan.exact && plan.excludes_document(&paragraphs) {
+                        continue;
+                    }
 
                     // Перевіряємо тільки ті параграфи, які є в позиціях
                     for &pos in &paragraph_positions {
@@ -225,147 +1228,269 @@ impl SearchEngine {
                             let paragraph = &paragraphs[pos];
                             let paragraph_lower = paragraph.text.to_lowercase();
 
-                            // Пропускаємо параграфи які починаються з "Підстава" тільки в режимі "Витяг"
-                            if view_mode == Some("fragments")
-                                && paragraph_lower.trim().starts_with("підстава")
-                            {
+                            // Пропускаємо параграфи з початками з search.paragraph_filters ("Підстава" у "Витягу")
+                            if skipped(&paragraph_lower) {
                                 continue;
                             }
 
                             // Нормалізуємо параграф для пошуку (видаляємо апострофи)
                             let normalized_paragraph = paragraph_lower.replace('\'', "");
 
-                            // Перевіряємо чи всі слова дійсно є в цьому нормалізованому параграфі
-                            let has_all_words = query_words
-                                .iter()
-                                .all(|word| normalized_paragraph.contains(word));
-
-                            if has_all_words {
-                                // Перевіряємо близькість для ПІБ
-                                let is_name_search =
-                                    query_words.len() >= 2 && query_words.len() <= 3;
-
-                                let proximity_check = !is_name_search
-                                    || self
-                                        .check_words_proximity(&normalized_paragraph, &query_words);
-
-                                if proximity_check {
-                                    // Знайдений параграф з персоною завжди додаємо (фільтрація наступних параграфів буде в JS)
-                                    document_matches.push(SearchEngineMatch {
-                                        context: paragraph.text.clone(),
-                                        position: pos,
-                                    });
-                                }
+                            // Перевіряємо чи всі слова групи дійсно є в цьому нормалізованому параграфі
+                            // (і близькість для ПІБ)
+                            if plan.matches_paragraph(self, &normalized_paragraph, &named) {
+                                // Знайдений параграф з персоною завжди додаємо (фільтрація наступних параграфів буде в JS)
+                                document_matches.push(SearchEngineMatch {
+                                    context: paragraph.text.clone(),
+                                    position: pos,
+                                });
                             }
                         }
                     }
+                    if document_matches.is_empty() && plan.matches_by_name(&named) {
+                        document_matches.extend(Self::title_match(&paragraphs, skipped));
+                    }
 
                     if !document_matches.is_empty() {
                         results.push(SearchEngineResult {
+                            doc_id: document.doc_id,
                             file_name: document.file_name.clone(),
                             file_path: document.file_path.clone(),
                             matches: document_matches,
                             all_paragraphs: paragraphs,
                             file_size: document.file_size,
                             last_modified: document.last_modified,
+                            category: document.category.clone(),
+                            date: document_date(document),
                         });
                     }
                 }
             }
         } else {
-            println!("⚠️  Інвертований індекс не доступний, використовуємо звичайний пошук");
+            warn!("⚠️  Інвертований індекс не доступний, використовуємо звичайний пошук");
+            candidate_count = scope.documents;
             // Звичайний пошук як резервний варіант
-            for document in data.index.documents.iter() {
+            for document in data.index.documents[slots].iter().rev().filter(|d| !d.deleted && filters.accepts(d)) {
+                if timed_out() {
+                    partial = true;
+                    break;
+                }
+                let Some(named) = plan.name_filter(&document.file_name) else {
+                    continue;
+                };
                 let paragraphs = document.get_paragraphs();
                 let mut document_matches = Vec::new();
                 let mut has_any_match = false;
 
+                // Слова з мінусом виключають увесь документ, а не лише абзац
+                if plan.excludes_document(&paragraphs) {
+                    continue;
+                }
+
                 for (pos, paragraph) in paragraphs.iter().enumerate() {
+                    if timed_out() {
+                        partial = true;
+                        break;
+                    }
                     let paragraph_lower = paragraph.text.to_lowercase();
 
-                    // Пропускаємо параграфи які починаються з "Підстава" тільки в режимі "Витяг"
-                    if view_mode == Some("fragments")
-                        && paragraph_lower.trim().starts_with("підстава")
-                    {
+                    // Пропускаємо параграфи з початками з search.paragraph_filters ("Підстава" у "Витягу")
+                    if skipped(&paragraph_lower) {
                         continue;
                     }
 
                     // Нормалізуємо параграф для пошуку (видаляємо апострофи)
                     let normalized_paragraph = paragraph_lower.replace('\'', "");
 
-                    let has_all_words = query_words
-                        .iter()
-                        .all(|word| normalized_paragraph.contains(word));
-
-                    if has_all_words {
-                        let is_name_search = query_words.len() >= 2 && query_words.len() <= 3;
-
-                        let proximity_check = !is_name_search
-                            || self.check_words_proximity(&normalized_paragraph, &query_words);
-
-                        if proximity_check {
-                            // Знайдений параграф з персоною завжди додаємо (фільтрація наступних параграфів буде в JS)
-                            document_matches.push(SearchEngineMatch {
-                                context: paragraph.text.clone(),
-                                position: pos,
-                            });
-                            has_any_match = true;
-                        }
+                    if plan.matches_paragraph(self, &normalized_paragraph, &named) {
+                        // Знайдений параграф з персоною завжди додаємо (фільтрація наступних параграфів буде в JS)
+                        document_matches.push(SearchEngineMatch {
+                            context: paragraph.text.clone(),
+                            position: pos,
+                        });
+                        has_any_match = true;
                     }
                 }
+                if !has_any_match && plan.matches_by_name(&named) {
+                    document_matches.extend(Self::title_match(&paragraphs, skipped));
+                    has_any_match = !document_matches.is_empty();
+                }
 
                 if has_any_match {
                     results.push(SearchEngineResult {
+                        doc_id: document.doc_id,
                         file_name: document.file_name.clone(),
                         file_path: document.file_path.clone(),
                         matches: document_matches,
                         all_paragraphs: paragraphs,
                         file_size: document.file_size,
                         last_modified: document.last_modified,
+                        category: document.category.clone(),
+                        date: document_date(document),
                     });
                 }
             }
         }
 
-        // Сортуємо за датою з назви файлу (від нових до старих), потім за кількістю збігів
-        results.sort_by(|a, b| {
-            // Витягуємо дати з назв файлів
-            let date_a = Self::extract_date_from_filename(&a.file_path);
-            let date_b = Self::extract_date_from_filename(&b.file_path);
+        let matched_time = started.elapsed();
 
-            // Порівнюємо за датою
-            match Self::compare_dates(date_a, date_b) {
-                std::cmp::Ordering::Equal => {
-                    // Якщо дати однакові, сортуємо за кількістю збігів
-                    b.matches.len().cmp(&a.matches.len())
-                }
-                other => other,
-            }
-        });
+        results.sort_by(|a, b| filters.sort.compare(a, b));
+        let facets = SearchFacets::from_results(&results);
+        // Перерваний пошук наступного разу може встигнути більше - такий не кешується
+        if let (Some(key), false) = (cache_key, partial) {
+            let cached = CachedOutcome {
+                results: results.iter().map(|r| (r.doc_id, r.matches.iter().map(|m| m.position).collect())).collect(),
+                scope: scope.clone(),
+                facets: facets.clone(),
+            };
+            self.result_cache.insert(key, &data, cached);
+        }
+        if filters.sort.reranked() {
+            self.feedback.rerank(&mut results, &query_words);
+        }
 
-        Ok(results)
-    }
+        let total_time = started.elapsed();
+        if partial {
+            warn!("{}", tr!(QueryTimedOut, query, total_time.as_millis()));
+        }
+        if self.slow_queries.is_slow(total_time) {
+            self.slow_queries.record(&SlowQuery {
+                at: slow_query_log::unix_now(),
+                query: query.to_string(),
+                terms: query_words,
+                mode: format!("{:?}", mode).to_lowercase(),
+                view_mode: view_mode.map(str::to_string),
+                candidates: candidate_count,
+                results: results.len(),
+                partial,
+                timings: SearchTimings {
+                    prepare_ms: SearchTimings::ms(prepare_time),
+                    candidates_ms: SearchTimings::ms(candidates_time),
+                    matching_ms: SearchTimings::ms(matched_time.saturating_sub(prepare_time + candidates_time)),
+                    sorting_ms: SearchTimings::ms(total_time - matched_time),
+                    total_ms: SearchTimings::ms(total_time),
+                },
+            });
+        }
 
-    fn process_search_query(&self, query: &str) -> String {
-        // Видаляємо апострофи
-        let without_apostrophes = query.replace('\'', "");
+        Ok(SearchOutcome { results, partial, scope: Some(scope), facets })
+    }
 
-        // Розбиваємо на слова та обробляємо стемінг
-        let words: Vec<String> = without_apostrophes
-            .split_whitespace()
-            .map(|word| stemmer::stem_word(word))
-            .collect();
+    /// Ключ кешу результатів: запит і все, від чого залежать знайдені абзаци, разом з
+    /// ревізією налаштувань і датою (швидкий набір визначається від сьогодні). None - не
+    /// кешується: збережені запити перевіряють щоразу інший набір документів
+    fn cache_key(&self, query: &str, mode: SearchMode, view_mode: Option<&str>, filters: &SearchFilters) -> Option<String> {
+        if filters.documents.is_some() {
+            return None;
+        }
+        // Пробіли в регулярному виразі значущі, у запиті словами - ні
+        let query = match &filters.regex {
+            Some(regex) => format!("regex:{}", regex.regex().as_str()),
+            None => query.split_whitespace().collect::<Vec<_>>().join(" "),
+        };
+        let parts = [
+            query,
+            format!("{:?}", mode),
+            format!("{:?}", view_mode),
+            format!("{:?}", filters.skip_prefixes),
+            format!("{:?}", filters.file_types),
+            format!("{:?}", filters.folder),
+            format!("{:?}", filters.date_from),
+            format!("{:?}", filters.date_to),
+            filters.exact.to_string(),
+            format!("{:?}", filters.sort),
+            self.settings_revision.load(Ordering::Relaxed).to_string(),
+            Local::now().date_naive().to_string(),
+        ];
+        Some(parts.join("\u{1f}"))
+    }
 
-        words.join(" ")
+    /// Збіг документа, знайденого за іменем файлу: перший непорожній абзац (заголовок
+    /// наказу), що не пропускається за search.paragraph_filters
+    fn title_match(paragraphs: &[Paragraph], skipped: impl Fn(&str) -> bool) -> Option<SearchEngineMatch> {
+        paragraphs
+            .iter()
+            .enumerate()
+            .find(|(_, paragraph)| !paragraph.text.trim().is_empty() && !skipped(&paragraph.text.to_lowercase()))
+            .map(|(position, paragraph)| SearchEngineMatch { context: paragraph.text.clone(), position })
     }
 
-    fn extract_search_words(&self, query: &str) -> Vec<String> {
-        WORD_REGEX
-            .find_iter(query)
-            .map(|m| m.as_str().to_lowercase())
+    /// Результати з кешу з текстом абзаців поточного покоління (None - документа вже немає)
+    fn cached_results(index: &DocumentIndex, cached: &CachedOutcome) -> Option<Vec<SearchEngineResult>> {
+        cached
+            .results
+            .iter()
+            .map(|(doc_id, positions)| {
+                let document = &index.documents[index.slot_of(*doc_id)?];
+                let paragraphs = document.get_paragraphs();
+                let matches = positions
+                    .iter()
+                    .map(|&position| {
+                        let context = paragraphs.get(position)?.text.clone();
+                        Some(SearchEngineMatch { context, position })
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(SearchEngineResult {
+                    doc_id: document.doc_id,
+                    file_name: document.file_name.clone(),
+                    file_path: document.file_path.clone(),
+                    matches,
+                    all_paragraphs: paragraphs,
+                    file_size: document.file_size,
+                    last_modified: document.last_modified,
+                    category: document.category.clone(),
+                    date: document_date(document),
+                })
+            })
             .collect()
     }
 
+    /// Слоти індексу для режиму пошуку. Документи зберігаються від найстаріших до найновіших,
+    /// тож швидкий набір - кінець індексу: накази, датовані за останні quick_mode_months
+    /// місяців, або (якщо вікно вимкнено) останні quick_mode_documents документів
+    fn scope(&self, index: &DocumentIndex, mode: SearchMode) -> (Range<usize>, SearchScope) {
+        let total = index.documents.len();
+        let quick_start = match self.quick_mode_months.load(Ordering::Relaxed) {
+            0 => total.saturating_sub(self.quick_mode_documents.load(Ordering::Relaxed)),
+            months => {
+                let today = Local::now().date_naive();
+                let since = today.checked_sub_months(Months::new(months)).unwrap_or(today);
+                let since = (since.year() as u32, since.month(), since.day());
+                // Недатовані документи - на початку індексу
+                index.documents.partition_point(|d| document_date(d).is_none_or(|date| date.ymd() < since))
+            }
+        };
+
+        let slots = match mode {
+            SearchMode::Quick => quick_start..total,
+            SearchMode::Remaining => 0..quick_start,
+            SearchMode::Full => 0..total,
+        };
+        let scope = SearchScope {
+            mode,
+            documents: index.documents[slots.clone()].iter().filter(|d| !d.deleted).count(),
+            quick_since: index.documents[quick_start..]
+                .iter()
+                .find(|d| !d.deleted)
+                .and_then(document_date)
+                .map(|date| date.formatted()),
+            remaining_estimate: None,
+        };
+        (slots, scope)
+    }
+
+    fn rewrite_query(&self, query: &str) -> String {
+        // Спочатку правила переписування - вони задаються звичайними словами, до стемінгу
+        let rewriter = match self.query_rewriter.read() {
+            Ok(rewriter) => rewriter.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        };
+        match &rewriter {
+            Some(rewriter) => rewriter.rewrite(query).into_owned(),
+            None => query.to_string(),
+        }
+    }
+
     fn check_words_proximity(&self, paragraph: &str, query_words: &[String]) -> bool {
         if query_words.len() < 2 {
             return true;
@@ -378,26 +1503,41 @@ impl SearchEngine {
         let mut last_position = 0;
 
         for (i, word) in query_words.iter().enumerate() {
+            // Після початку слова ("пол*") відстань рахується від кінця всього слова
+            let (word, is_prefix) = match word.strip_suffix(PREFIX_WILDCARD) {
+                Some(prefix) => (prefix, true),
+                None => (word.as_str(), false),
+            };
+            let word_end = |pos: usize| {
+                let end = pos + word.len();
+                if is_prefix {
+                    normalized_paragraph[end..]
+                        .find(|c: char| !c.is_alphanumeric())
+                        .map_or(normalized_paragraph.len(), |offset| end + offset)
+                } else {
+                    end
+                }
+            };
             if let Some(word_pos) = normalized_paragraph[last_position..].find(word) {
                 let absolute_pos = last_position + word_pos;
 
                 // Для першого слова встановлюємо початкову позицію
                 if i == 0 {
-                    last_position = absolute_pos + word.len();
+                    last_position = word_end(absolute_pos);
                     continue;
                 }
 
                 // Для наступних слів перевіряємо відстань
                 let distance = absolute_pos - last_position;
 
-                // Дозволяємо до 15 символів між словами (для урахування відмінків і розділових знаків)
+                // Дозволяємо до proximity_window символів між словами (для урахування відмінків і розділових знаків)
                 // Це дозволить знайти "ДОНА Анатолія" при пошуку "дон анатол"
-                if distance > 15 {
+                if distance > self.proximity_window.load(Ordering::Relaxed) {
                     return false;
                 }
 
                 // Оновлюємо позицію для пошуку наступного слова
-                last_position = absolute_pos + word.len();
+                last_position = word_end(absolute_pos);
             } else {
                 // Слово не знайдено після попередніх - порядок порушено
                 return false;
@@ -408,35 +1548,499 @@ impl SearchEngine {
     }
 
 
+    /// Налаштування пошуку змінено: відповіді з попередніми ETag застаріли
+    pub fn mark_settings_changed(&self) {
+        self.settings_revision.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Версія результатів пошуку: та сама версія і той самий запит - та сама відповідь.
+    /// Складається з покоління індексу, оцінок, приміток, налаштувань і дати (швидкий набір
+    /// визначається від сьогодні); None - індекс без покоління, кешувати не можна
+    pub fn results_version(&self) -> Option<String> {
+        let generation = self.snapshot().generation?;
+        Some(format!(
+            "{}-{}-{}-{}-{}",
+            generation,
+            self.feedback.revision(),
+            self.annotations.revision(),
+            self.settings_revision.load(Ordering::Relaxed),
+            Local::now().date_naive()
+        ))
+    }
+
+    /// Доступ до завантаженого покоління (номер, індекси) без блокування пошуку
+    pub fn with_indices<R>(
+        &self,
+        f: impl FnOnce(Option<u64>, &DocumentIndex, Option<&InvertedIndex>) -> R,
+    ) -> R {
+        let data = self.snapshot();
+        f(data.generation, &data.index, data.inverted_index.as_ref())
+    }
+
     pub fn get_stats(&self) -> (usize, usize) {
-        let data = self.data.lock()
-            .expect("Критична помилка блокування даних при отриманні статистики");
+        let data = self.snapshot();
         (data.index.total_documents, data.index.total_words)
     }
 
     fn try_reload_indices_if_needed(&self) {
-        let documents_path = "documents_index.json";
-        let inverted_path = "inverted_index.json";
+        let indices_exist = match &self.store {
+            Some(store) => matches!(store.generation(), Ok(Some(_))),
+            None => {
+                let (active_documents_path, active_inverted_path) =
+                    resolve_active_paths(&self.documents_index_path, &self.inverted_index_path);
+                std::path::Path::new(&active_documents_path).exists()
+                    && std::path::Path::new(&active_inverted_path).exists()
+            }
+        };
 
-        // Перевіряємо чи існують файли індексів і чи вони новіші за поточні
-        if std::path::Path::new(documents_path).exists() && std::path::Path::new(inverted_path).exists() {
+        // Перевіряємо чи існують індекси і чи вони новіші за поточні
+        if indices_exist {
             let should_reload = {
-                if let Ok(data) = self.data.lock() {
-                    // Якщо інвертований індекс відсутній, перезавантажуємо
-                    data.inverted_index.is_none() || data.index.documents.is_empty()
-                } else {
-                    return; // Не можемо отримати блокування, пропускаємо
-                }
+                let data = self.snapshot();
+                // Якщо інвертований індекс відсутній, перезавантажуємо
+                data.inverted_index.is_none() || data.index.documents.is_empty()
             };
 
             if should_reload {
-                println!("🔄 Автоматичне перезавантаження індексів...");
-                if let Err(e) = self.reload(documents_path) {
-                    println!("⚠️  Помилка автоматичного перезавантаження індексів: {}", e);
+                info!("🔄 Автоматичне перезавантаження індексів...");
+                if let Err(e) = self.reload() {
+                    warn!("⚠️  Помилка автоматичного перезавантаження індексів: {}", e);
                 } else {
-                    println!("✅ Індекси автоматично перезавантажено");
+                    info!("✅ Індекси автоматично перезавантажено");
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+    use crate::document_date::DateSource;
+    use crate::document_record::DocumentRecord;
+    use crate::index_builder::IndexBuilder;
+    use std::fs;
+
+    fn write_indices(paths: &IndexPaths, texts: &[&str]) {
+        let mut doc_index = DocumentIndex::new();
+        for (i, text) in texts.iter().enumerate() {
+            doc_index.push_document(DocumentRecord::from_texts(&format!("{}.docx", i), [*text]));
+        }
+        doc_index.recount();
+        let inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+
+        fs::write(paths.documents_index(), serde_json::to_string(&doc_index).unwrap()).unwrap();
+        fs::write(paths.inverted_index(), serde_json::to_string(&inv_index).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_reload_keeps_held_snapshot_intact() {
+        let dir = TempIndexDir::new("cow_test");
+        let paths = dir.paths();
+
+        write_indices(paths, &["наказ"]);
+        let engine = SearchEngine::new(paths);
+        engine.reload().unwrap();
+        assert!(engine.snapshot().inverted_index.is_some());
+
+        // Пошук, що триває, утримує старе покоління
+        let held = engine.snapshot();
+
+        write_indices(paths, &["наказ", "рапорт"]);
+        engine.reload().unwrap();
+
+        assert_eq!(held.index.documents.len(), 1);
+        assert_eq!(engine.snapshot().index.documents.len(), 2);
+        assert_eq!(engine.get_stats().0, 2);
+
+        // Інвертований індекс прочитано з файлу в папці тесту, а не з робочої папки
+        let report = engine.query_terms("рапорт");
+        let found = |data: &SearchEngineData| data.inverted_index.as_ref().unwrap().search_fast(&report, 0..2, None).unwrap();
+        assert!(found(&held).is_empty());
+        assert_eq!(found(&engine.snapshot()), vec![(1, vec![0])]);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_query_served_from_cache() {
+        let dir = TempIndexDir::new("result_cache");
+        let paths = dir.paths();
+        write_indices(paths, &["наказ про відпустку"]);
+        let engine = SearchEngine::new(paths);
+        engine.reload().unwrap();
+
+        let found = |query: &'static str, view_mode: Option<&'static str>| {
+            let engine = &engine;
+            async move {
+                let outcome = engine.search_outcome(query, SearchMode::Full, view_mode).await.unwrap();
+                outcome.results.iter().map(|r| (r.doc_id, r.matches.len(), r.all_paragraphs.len())).collect::<Vec<_>>()
+            }
+        };
+        let first = found("наказ", None).await;
+        assert_eq!(first.len(), 1);
+        // Той самий запит з іншими пробілами - з кешу, з текстом документа
+        assert_eq!(found("  наказ ", None).await, first);
+        assert_eq!(engine.result_cache.len(), 1);
+        found("наказ", Some("fragments")).await;
+        assert_eq!(engine.result_cache.len(), 2);
+
+        // Нове покоління - кеш порожній, знаходиться й новий документ
+        write_indices(paths, &["наказ про відпустку", "наказ про відрядження"]);
+        engine.reload().unwrap();
+        assert!(engine.result_cache.is_empty());
+        assert_eq!(found("наказ", None).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_quick_set_by_recency() {
+        let dir = TempIndexDir::new("quick_recency");
+
+        // Індекс зберігається від найстаріших документів до найновіших
+        let today = Local::now().date_naive().format("%d.%m.%Y").to_string();
+        let engine = IndexBuilder::new()
+            .with_document(&format!("Наказ №2 від {}.docx", today), ["відпустка"])
+            .with_document("Наказ №1 від 10.01.2020.docx", ["відпустка"])
+            .with_document("Наказ без дати.docx", ["відпустка"])
+            .build_engine(dir.paths());
+        engine.set_quick_mode_months(Some(1));
+
+        let quick = engine.search_outcome("відпустка", SearchMode::Quick, None).await.unwrap();
+        assert_eq!(quick.results.len(), 1);
+        let scope = quick.scope.unwrap();
+        assert_eq!((scope.mode, scope.documents), (SearchMode::Quick, 1));
+        assert_eq!(scope.quick_since, Some(today));
+        assert_eq!(scope.remaining_estimate, Some(2));
+        let estimate = |query: &str| {
+            let data = engine.snapshot();
+            data.inverted_index.as_ref().unwrap().estimate_documents(&engine.query_terms(query), 0..2, |slot| slot > 0)
+        };
+        assert_eq!((estimate("відпустка"), estimate("відпустка рапорт")), (1, 0));
+
+        let remaining = engine.search_outcome("відпустка", SearchMode::Remaining, None).await.unwrap();
+        let remaining_scope = remaining.scope.unwrap();
+        assert_eq!((remaining_scope.documents, remaining_scope.remaining_estimate), (2, None));
+        assert_eq!(remaining.results.len(), 2);
+
+        // Без вікна в місяцях - останні quick_mode_documents документів
+        engine.set_quick_mode_months(None);
+        engine.set_quick_mode_documents(2);
+        let quick = engine.search_outcome("відпустка", SearchMode::Quick, None).await.unwrap();
+        assert_eq!(quick.scope.unwrap().quick_since.as_deref(), Some("10.01.2020"));
+    }
+
+    #[test]
+    fn test_result_limits_only_lowered_by_request() {
+        let limits = ResultLimits { max_results: 2, max_paragraphs: 3 };
+        assert_eq!(limits.with_request(Some(100), Some(0)), limits);
+        assert_eq!(limits.with_request(Some(1), None), ResultLimits { max_results: 1, max_paragraphs: 3 });
+        let unlimited = ResultLimits { max_results: 0, max_paragraphs: 0 };
+        assert_eq!(unlimited.with_request(None, Some(5)), ResultLimits { max_results: 0, max_paragraphs: 5 });
+
+        let result = |paragraphs: usize| SearchEngineResult {
+            doc_id: 0,
+            file_name: String::new(),
+            file_path: String::new(),
+            matches: (0..paragraphs).map(|position| SearchEngineMatch { context: String::new(), position }).collect(),
+            all_paragraphs: (0..paragraphs).map(|i| Paragraph::new(i.to_string())).collect(),
+            file_size: 0,
+            last_modified: 0,
+            category: None,
+            date: None,
+        };
+        let mut results = vec![result(5), result(1), result(1)];
+        assert_eq!(limits.truncate_results(&mut results), 1);
+        assert_eq!(limits.truncate_paragraphs(&mut results[0]), 2);
+        assert_eq!((results[0].all_paragraphs.len(), results[0].matches.len()), (3, 3));
+        assert_eq!(limits.truncate_paragraphs(&mut results[1]), 0);
+        assert_eq!(unlimited.truncate_results(&mut results), 0);
+    }
+
+    #[test]
+    fn test_paragraph_filters_per_view_mode_and_request() {
+        let dir = TempIndexDir::new("paragraph_filters");
+        let engine = SearchEngine::new(dir.paths());
+        assert_eq!(engine.skip_prefixes(Some("fragments"), None), vec!["підстава"]);
+        assert!(engine.skip_prefixes(Some("full-document"), None).is_empty());
+        assert!(engine.skip_prefixes(None, None).is_empty());
+
+        let filters = BTreeMap::from([("full-document".to_string(), vec!["  Витяг з ".to_string(), " ".to_string()])]);
+        engine.set_paragraph_filters(&filters);
+        assert!(engine.skip_prefixes(Some("fragments"), None).is_empty());
+        assert_eq!(engine.skip_prefixes(Some("full-document"), None), vec!["витяг з"]);
+
+        // Список із запиту замінює налаштований, порожній - вимикає фільтри
+        assert_eq!(engine.skip_prefixes(Some("full-document"), Some(&["Підстава".to_string()])), vec!["підстава"]);
+        assert!(engine.skip_prefixes(Some("full-document"), Some(&[])).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_query_operators() {
+        let dir = TempIndexDir::new("boolean_query");
+        let engine = IndexBuilder::new()
+            .with_document("Наказ №1 від 10.01.2024.docx", ["Петренку Петру надати відпустку", "Підстава: рапорт"])
+            .with_document("Наказ №2 від 11.01.2024.docx", ["Іваненка Івана відрядити до м. Києва"])
+            .with_document("Наказ №3 від 12.01.2024.docx", ["Петренка Петра відрядити до м. Львова"])
+            .build_engine(dir.paths());
+
+        let plan = engine.query_plan("Петренко Петро OR іваненко -рапорт", false);
+        assert_eq!(plan.groups.len(), 2);
+        assert_eq!(plan.excluded.len(), 1);
+        assert_eq!(engine.query_terms("Петренко Петро OR іваненко -рапорт"), plan.groups.concat());
+
+        let found = |query: &'static str| {
+            let engine = &engine;
+            async move {
+                let outcome = engine.search_outcome(query, SearchMode::Full, None).await.unwrap();
+                let mut names: Vec<String> = outcome.results.into_iter().map(|r| r.file_name).collect();
+                names.sort();
+                names
+            }
+        };
+        assert_eq!(found("відрядити").await, ["Наказ №2 від 11.01.2024.docx", "Наказ №3 від 12.01.2024.docx"]);
+        assert_eq!(found("відрядити Львова OR відпустку").await, ["Наказ №1 від 10.01.2024.docx", "Наказ №3 від 12.01.2024.docx"]);
+        assert_eq!(found("Петренко АБО Іваненко").await.len(), 3);
+        // Слово з мінусом виключає документ, навіть якщо воно в іншому абзаці
+        assert_eq!(found("Петренко -рапорт").await, ["Наказ №3 від 12.01.2024.docx"]);
+        assert_eq!(found("відрядити -Києва -Львова").await, Vec::<String>::new());
+        // Лише виключення - шукати нічого
+        assert!(found("-рапорт").await.is_empty());
+        // Початок слова без стемінгу; занадто короткий - звичайне слово
+        assert_eq!(engine.query_terms("ПЕТР* Льв*"), ["петр", "льв"]);
+        assert_eq!(engine.query_plan("ПЕТР*", false).groups, [["петр*"]]);
+        assert_ne!(engine.query_plan("Пе*", false).groups, [["пе*"]]);
+        assert_eq!(found("відр* Льв*").await, ["Наказ №3 від 12.01.2024.docx"]);
+        assert_eq!(found("петр* -відпуст*").await, ["Наказ №3 від 12.01.2024.docx"]);
+    }
+
+    #[tokio::test]
+    async fn test_near_operator_counts_words() {
+        let dir = TempIndexDir::new("near_query");
+        let engine = IndexBuilder::new()
+            .with_document("Наказ №1 від 10.01.2024.docx", ["Солдату ПЕТРЕНКУ Петру Івановичу надати відпустку"])
+            .with_document("Наказ №2 від 11.01.2024.docx", ["Відпустку надати ПЕТРЕНКУ"])
+            .with_document("Наказ №3 від 12.01.2024.docx", ["ПЕТРЕНКУ Петру, водію-електрику автомобільного взводу, надати відпустку"])
+            .build_engine(dir.paths());
+
+        let plan = engine.query_plan("Петренку NEAR/3 відпустку АБО рапорт ПОРУЧ/1", false);
+        assert_eq!(plan.groups.len(), 2);
+        assert_eq!(plan.near[0].len(), 1);
+        assert_eq!(plan.near[0][0].distance, 3);
+        // Оператор без слова праворуч ігнорується, а не шукається як слово
+        assert!(plan.near[1].is_empty());
+        assert_eq!(plan.groups[1].len(), 1);
+
+        let found = |query: &'static str, exact: bool| {
+            let engine = &engine;
+            async move {
+                let filters = SearchFilters::default().with_exact(exact);
+                let outcome = engine.search_outcome_filtered(query, SearchMode::Full, None, &filters).await.unwrap();
+                let mut ids: Vec<u64> = outcome.results.iter().map(|r| r.doc_id).collect();
+                ids.sort();
+                ids
+            }
+        };
+        // Без оператора два слова - перевірка ПІБ: поспіль у межах proximity_window
+        assert!(found("Петренку відпустку", false).await.is_empty());
+        // Між словами 3 слова, у будь-якому порядку; розділові знаки не рахуються
+        assert_eq!(found("Петренку NEAR/3 відпустку", false).await, [1, 2]);
+        assert_eq!(found("Петренку NEAR/6 відпустку", false).await, [1, 2, 3]);
+        assert_eq!(found("надати NEAR/0 відпустку", false).await, [1, 2, 3]);
+        assert_eq!(found("Петренку NEAR/0 Петру", false).await, [1, 3]);
+        assert_eq!(found("петренку NEAR/1 надати", true).await, [2]);
+        assert!(found("Петренка NEAR/5 відпустку", true).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_filename_field_query() {
+        let dir = TempIndexDir::new("filename_query");
+        let engine = IndexBuilder::new()
+            .with_document("Наказ №123 від 10.01.2024.docx", ["НАКАЗ", "Петренку надати відпустку"])
+            .with_document("Наказ №124 від 11.01.2024.docx", ["НАКАЗ", "Іваненку надати відпустку"])
+            .with_document("Наказ №5 від 15.01.2024 (чернетка).docx", ["НАКАЗ", "Петренку виплатити премію"])
+            .build_engine(dir.paths());
+
+        let plan = engine.query_plan("FILENAME:123 OR назва:№5 відпустку -filename:чернетка", false);
+        assert_eq!(plan.name_groups, [vec!["123".to_string()], vec!["5".to_string()]]);
+        assert!(plan.groups[0].is_empty());
+        assert_eq!(plan.name_excluded, ["чернетка"]);
+
+        let found = |query: &'static str| {
+            let engine = &engine;
+            async move {
+                let outcome =
+                    engine.search_outcome_filtered(query, SearchMode::Full, None, &SearchFilters::default()).await.unwrap();
+                let mut ids: Vec<u64> = outcome.results.iter().map(|r| r.doc_id).collect();
+                ids.sort();
+                (ids, outcome.results.first().map(|r| r.matches[0].position))
+            }
+        };
+        // Лише ім'я файлу: збігом показується заголовок наказу; номер - ціле слово
+        assert_eq!(found("filename:123").await, (vec![1], Some(0)));
+        assert_eq!(found("filename:12*").await.0, [1, 2]);
+        // Разом зі словами абзаців: ім'я звужує документи, абзаци шукаються як завжди
+        assert_eq!(found("filename:5 Петренку").await, (vec![3], Some(1)));
+        assert!(found("filename:124 Петренку").await.0.is_empty());
+        assert_eq!(found("Петренку -назва:чернетка").await.0, [1]);
+        assert_eq!(found("filename:123 OR filename:5").await.0, [1, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_exact_mode_skips_stemmer() {
+        let dir = TempIndexDir::new("exact_query");
+        let engine = IndexBuilder::new()
+            .with_document("Наказ №1 від 10.01.2024.docx", ["Петренку Петру надати відпустку"])
+            .with_document("Наказ №2 від 11.01.2024.docx", ["Петренка Петра відрядити", "Петренку П.П. виплатити"])
+            .build_engine(dir.paths());
+
+        let found = |query: &'static str, exact: bool| {
+            let engine = &engine;
+            async move {
+                let filters = SearchFilters::default().with_exact(exact);
+                let outcome = engine.search_outcome_filtered(query, SearchMode::Full, None, &filters).await.unwrap();
+                let mut found: Vec<(u64, usize)> = outcome
+                    .results
+                    .iter()
+                    .flat_map(|r| r.matches.iter().map(move |m| (r.doc_id, m.position)))
+                    .collect();
+                found.sort();
+                found
+            }
+        };
+        assert_eq!(found("Петренку", false).await.len(), 3);
+        assert_eq!(found("Петренку", true).await, [(1, 0), (2, 1)]);
+        assert!(found("петренк", true).await.is_empty());
+        assert_eq!(found("Петра відрядити", true).await, [(2, 0)]);
+        // Точне слово з мінусом не відкидає документи з іншими формами
+        assert_eq!(found("Петренку -Петра", true).await, [(1, 0)]);
+        assert_eq!(found("Петренку -Петрові", true).await.len(), 2);
+
+        let plan = engine.query_plan("Петренку OR Іваненкові", true);
+        assert_eq!(plan.highlight_terms(), ["петренку", "іваненкові"]);
+        assert_eq!(plan.terms(), engine.query_terms("Петренку OR Іваненкові"));
+    }
+
+    #[tokio::test]
+    async fn test_regex_mode_scans_paragraphs() {
+        let dir = TempIndexDir::new("regex_query");
+        let engine = IndexBuilder::new()
+            .with_document("Наказ №1 від 10.01.2024.docx", ["Відповідно до наказу №16/ОС", "Підстава: рапорт №7"])
+            .with_document("Наказ №2 від 11.01.2024.docx", ["Рапорт №125 від 09.01.2024"])
+            .build_engine(dir.paths());
+
+        let found = |pattern: &'static str, view_mode: Option<&'static str>| {
+            let engine = &engine;
+            async move {
+                let filters = SearchFilters::default().with_regex(Some(RegexQuery::new(pattern).unwrap()));
+                let outcome = engine.search_outcome_filtered(pattern, SearchMode::Full, view_mode, &filters).await.unwrap();
+                let mut found: Vec<(u64, usize)> = outcome
+                    .results
+                    .iter()
+                    .flat_map(|r| r.matches.iter().map(move |m| (r.doc_id, m.position)))
+                    .collect();
+                found.sort();
+                found
+            }
+        };
+        assert_eq!(found(r"№\d+/ос", None).await, [(1, 0)]);
+        assert_eq!(found(r"рапорт №\d+", None).await, [(1, 1), (2, 0)]);
+        // Фільтри абзаців виду перегляду діють і на вираз ("Підстава" у "Витягу")
+        assert_eq!(found(r"рапорт №\d+", Some("fragments")).await, [(2, 0)]);
+        // Без слів запиту: вираз лише з цифр і класів
+        assert_eq!(found(r"\d{3}", None).await, [(2, 0)]);
+    }
+
+    #[test]
+    fn test_file_type_folder_and_date_filters() {
+        let document = |file_path: &str, file_type: Option<&str>| DocumentRecord {
+            file_type: file_type.map(str::to_string),
+            ..DocumentRecord::from_texts(file_path, std::iter::empty::<&str>())
+        };
+        let filters = SearchFilters::default().with_file_types(vec![" .PDF".to_string(), String::new()]);
+        assert_eq!(filters.file_types, vec!["pdf"]);
+        assert!(filters.accepts(&document("2024/Наказ 1.pdf", Some("pdf"))));
+        // Старий індекс без формату - за розширенням
+        assert!(filters.accepts(&document("2024/Наказ 2.PDF", None)));
+        assert!(!filters.accepts(&document("2024/Наказ 3.docx", None)));
+        assert!(!filters.accepts(&document("2024/Без розширення", None)));
+        assert!(SearchFilters::default().accepts(&document("2024/Без розширення", None)));
+
+        let filters = SearchFilters::default().with_folder(Some("\\2024\\НР"));
+        assert_eq!(filters.folder.as_deref(), Some("2024/нр/"));
+        assert!(filters.accepts(&document("nakazi_cache/2024/НР/Наказ 4.docx", None)));
+        assert!(filters.accepts(&document("2024/нр/2 квартал/Наказ 5.docx", None)));
+        assert!(!filters.accepts(&document("nakazi_cache/2024/НРЗ/Наказ 6.docx", None)));
+        assert!(!filters.accepts(&document("nakazi_cache/2023/НР/Наказ 7.docx", None)));
+        assert!(SearchFilters::default().with_folder(Some(" / ")).folder.is_none());
+
+        // Дата визначається при додаванні в індекс, фільтр бере її звідти
+        let mut index = DocumentIndex::new();
+        let march = index.push_document(document("2024/Наказ 8 від 05.03.2024.docx", None));
+        let undated = index.push_document(document("2024/Наказ 9.docx", None));
+        assert_eq!(index.documents[march].date.map(|date| date.ymd()), Some((2024, 3, 5)));
+        let filters = SearchFilters::default().with_date_range(Some((2024, 3, 1)), Some((2024, 3, 5)));
+        assert!(filters.accepts(&index.documents[march]));
+        assert!(!filters.accepts(&index.documents[undated]));
+        assert!(!SearchFilters::default().with_date_range(None, Some((2024, 3, 4))).accepts(&index.documents[march]));
+        assert!(SearchFilters::default().with_date_range(Some((2024, 3, 5)), None).accepts(&index.documents[march]));
+    }
+
+    #[test]
+    fn test_context_window_around_matches() {
+        let mut result = SearchEngineResult {
+            doc_id: 0,
+            file_name: String::new(),
+            file_path: String::new(),
+            matches: [1, 3, 9].iter().map(|&position| SearchEngineMatch { context: String::new(), position }).collect(),
+            all_paragraphs: (0..10).map(|i| Paragraph::new(i.to_string())).collect(),
+            file_size: 0,
+            last_modified: 0,
+            category: None,
+            date: None,
+        };
+        assert_eq!(result.context_window(1, 2), (0..1, 2..4));
+        assert_eq!(result.context_window(9, 2), (7..9, 10..10));
+        assert_eq!(result.context_window(3, 0), (3..3, 4..4));
+        // Покрито 0-5 і 7-9: лишається абзац 6
+        assert_eq!(result.context_omitted(2), 1);
+        assert_eq!(result.context_omitted(0), 7);
+
+        ResultLimits { max_results: 0, max_paragraphs: 2 }.truncate_matches(&mut result);
+        assert_eq!((result.matches.len(), result.all_paragraphs.len()), (2, 10));
+    }
+
+    #[test]
+    fn test_sort_orders() {
+        let result = |file_name: &str, date: Option<(u32, u32, u32)>, matches: usize, file_size: u64| SearchEngineResult {
+            doc_id: 0,
+            file_name: file_name.to_string(),
+            file_path: file_name.to_string(),
+            matches: (0..matches).map(|position| SearchEngineMatch { context: String::new(), position }).collect(),
+            all_paragraphs: Vec::new(),
+            file_size,
+            last_modified: 0,
+            category: None,
+            date: date.map(|(year, month, day)| DocumentDate { year, month, day, source: DateSource::FileName }),
+        };
+        let mut results = vec![
+            result("в.docx", Some((2024, 3, 1)), 1, 300),
+            result("Б.docx", None, 5, 100),
+            result("а.docx", Some((2023, 1, 20)), 2, 200),
+            result("г.docx", Some((2024, 3, 1)), 3, 200),
+        ];
+        let sorted = |results: &mut Vec<SearchEngineResult>, sort: SortOrder| {
+            results.sort_by(|a, b| sort.compare(a, b));
+            results.iter().map(|r| r.file_name.chars().next().unwrap()).collect::<String>()
+        };
+        assert_eq!(sorted(&mut results, SortOrder::DateDesc), "гваБ");
+        assert_eq!(sorted(&mut results, SortOrder::Relevance), "Бгав");
+        // Недатовані - в кінці і від старих до нових
+        assert_eq!(sorted(&mut results, SortOrder::DateAsc), "агвБ");
+        assert_eq!(sorted(&mut results, SortOrder::Name), "аБвг");
+        // Однаковий розмір - за датою
+        assert_eq!(sorted(&mut results, SortOrder::Size), "вгаБ");
+        assert!(!SortOrder::Name.reranked());
+
+        let sort: SortOrder = serde_json::from_str(r#""date_asc""#).unwrap();
+        assert_eq!(sort, SortOrder::DateAsc);
+    }
+}
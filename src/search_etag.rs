@@ -0,0 +1,56 @@
+//! ETag відповідей пошуку: той самий запит з тими самими параметрами між оновленнями
+//! індексу дає ту саму відповідь, тож інтерфейс надсилає If-None-Match і отримує 304
+//! замість повторного тіла на кілька мегабайт. Версію результатів (покоління індексу,
+//! оцінки, налаштування) дає SearchEngine::results_version.
+
+use sha2::{Digest, Sha256};
+
+/// Запит без різниці в регістрі та пробілах ("  Наказ  №16" і "наказ №16" - один запит)
+fn normalize_query(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// ETag (у лапках) для версії результатів, запиту і параметрів, що змінюють відповідь
+pub fn search_etag(version: &str, query: &str, params: &[(&str, String)]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(version.as_bytes());
+    hasher.update([0]);
+    hasher.update(normalize_query(query).as_bytes());
+    for (name, value) in params {
+        hasher.update([0]);
+        hasher.update(name.as_bytes());
+        hasher.update(b"=");
+        hasher.update(value.as_bytes());
+    }
+    let hash: String = hasher.finalize()[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("\"{}\"", hash)
+}
+
+/// Чи збігається заголовок If-None-Match з ETag (список через кому, "*", слабкі W/"...")
+pub fn if_none_match(header: &str, etag: &str) -> bool {
+    header
+        .split(',')
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_depends_on_version_query_and_params() {
+        let params = |full: bool| vec![("full_search", full.to_string()), ("view_mode", "fragments".to_string())];
+        let etag = search_etag("7", "Наказ  №16 ", &params(false));
+
+        assert_eq!(etag, search_etag("7", "наказ №16", &params(false)));
+        assert_ne!(etag, search_etag("8", "наказ №16", &params(false)));
+        assert_ne!(etag, search_etag("7", "наказ №17", &params(false)));
+        assert_ne!(etag, search_etag("7", "наказ №16", &params(true)));
+
+        assert!(if_none_match(&etag, &etag));
+        assert!(if_none_match(&format!("\"old\", W/{}", etag), &etag));
+        assert!(if_none_match("*", &etag));
+        assert!(!if_none_match("\"old\"", &etag));
+    }
+}
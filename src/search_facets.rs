@@ -0,0 +1,92 @@
+//! Фасети результатів пошуку: скільки знайдених документів у кожній папці року ("2024") і
+//! в кожній її підпапці ("2024/НР"), щоб інтерфейс міг запропонувати фільтр folder. Накази
+//! лежать у папках років (див. синхронізацію), тож рахуються за шляхом документа без
+//! звернення до файлів.
+
+use crate::search_engine::SearchEngineResult;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Кількість документів за папками; ключі - як у шляху, придатні для поля folder запиту
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct SearchFacets {
+    /// Папка року -> документів у ній (разом з підпапками)
+    pub years: BTreeMap<String, usize>,
+    /// "Рік/підпапка" -> документів у ній; документи просто в папці року не враховуються
+    pub folders: BTreeMap<String, usize>,
+}
+
+impl SearchFacets {
+    pub fn from_results(results: &[SearchEngineResult]) -> Self {
+        let mut facets = Self::default();
+        for result in results {
+            let Some((year, folder)) = year_folder(&result.file_path) else {
+                continue;
+            };
+            if let Some(folder) = folder {
+                *facets.folders.entry(format!("{}/{}", year, folder)).or_insert(0) += 1;
+            }
+            *facets.years.entry(year.to_string()).or_insert(0) += 1;
+        }
+        facets
+    }
+
+    /// Додає лічильники іншої колекції (пошук у кількох колекціях)
+    pub fn merge(&mut self, other: SearchFacets) {
+        for (year, count) in other.years {
+            *self.years.entry(year).or_insert(0) += count;
+        }
+        for (folder, count) in other.folders {
+            *self.folders.entry(folder).or_insert(0) += count;
+        }
+    }
+}
+
+/// Папка року (перша папка шляху, що починається з 4 цифр: шлях містить і папку кешу) і
+/// підпапка в ній, якщо документ лежить не просто в папці року
+fn year_folder(file_path: &str) -> Option<(&str, Option<&str>)> {
+    let mut folders: Vec<&str> = file_path.split(['/', '\\']).filter(|part| !part.is_empty()).collect();
+    // Останнє - ім'я файлу
+    folders.pop();
+    let year = folders
+        .iter()
+        .position(|folder| folder.len() >= 4 && folder.chars().take(4).all(|c| c.is_ascii_digit()))?;
+    Some((folders[year], folders.get(year + 1).copied()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(file_path: &str) -> SearchEngineResult {
+        SearchEngineResult {
+            doc_id: 0,
+            file_name: file_path.rsplit('/').next().unwrap_or_default().to_string(),
+            file_path: file_path.to_string(),
+            matches: Vec::new(),
+            all_paragraphs: Vec::new(),
+            file_size: 0,
+            last_modified: 0,
+            category: None,
+            date: None,
+        }
+    }
+
+    #[test]
+    fn test_facets_by_year_and_subfolder() {
+        let results = [
+            result("nakazi_cache/2024/НР/Наказ №1.docx"),
+            result("nakazi_cache\\2024\\НР\\Архів\\Наказ №2.docx"),
+            result("nakazi_cache/2024/Наказ №3.docx"),
+            result("nakazi_cache/2023 рік/ОС/Наказ №4.docx"),
+            result("nakazi_cache/Наказ 2024.docx"),
+        ];
+        let mut facets = SearchFacets::from_results(&results);
+        assert_eq!(facets.years, BTreeMap::from([("2023 рік".to_string(), 1), ("2024".to_string(), 3)]));
+        assert_eq!(facets.folders, BTreeMap::from([("2023 рік/ОС".to_string(), 1), ("2024/НР".to_string(), 2)]));
+
+        facets.merge(SearchFacets::from_results(&results[..1]));
+        assert_eq!(facets.years["2024"], 4);
+        assert_eq!(facets.folders["2024/НР"], 3);
+    }
+}
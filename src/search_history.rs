@@ -0,0 +1,144 @@
+//! Історія пошуку користувачів на сервері: останні запити кожного користувача
+//! зберігаються в search_history.json у папці індексів, тож "нещодавні запити"
+//! доступні після перезапуску. Облікових записів немає - користувача визначає сеанс
+//! браузера (див. session), а не ім'я з запиту.
+//! Вимикається параметром history.enabled = false.
+
+use crate::tr;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Скільки запитів на користувача зберігати за замовчуванням
+pub const DEFAULT_HISTORY_MAX_ENTRIES: usize = 20;
+/// Найдовше ім'я користувача (символів); довші обрізаються
+const MAX_USER_CHARS: usize = 64;
+
+/// Запит в історії
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub query: String,
+    /// Unix timestamp останнього пошуку
+    pub searched_at: u64,
+}
+
+/// Історії всіх користувачів (найновіші запити першими)
+pub struct SearchHistory {
+    path: String,
+    max_entries: usize,
+    users: Mutex<HashMap<String, Vec<HistoryEntry>>>,
+}
+
+impl SearchHistory {
+    /// Завантажує збережену історію; пошкоджений файл - попередження і порожня історія
+    pub fn load(path: String, max_entries: usize) -> Self {
+        let users = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("{}", tr!(HistoryLoadFailed, path, e));
+                HashMap::new()
+            }),
+            Err(e) if Path::new(&path).exists() => {
+                warn!("{}", tr!(HistoryLoadFailed, path, e));
+                HashMap::new()
+            }
+            Err(_) => HashMap::new(),
+        };
+        Self { path, max_entries, users: Mutex::new(users) }
+    }
+
+    /// Ключ користувача: ім'я без зайвих пробілів, обрізане до MAX_USER_CHARS
+    pub fn user_key(name: &str) -> String {
+        name.trim().chars().take(MAX_USER_CHARS).collect()
+    }
+
+    /// Запити користувача, найновіші першими
+    pub fn recent(&self, user: &str) -> Vec<HistoryEntry> {
+        self.lock().get(user).cloned().unwrap_or_default()
+    }
+
+    /// Додає запит на початок історії (повторний запит переноситься нагору) і зберігає файл
+    pub fn record(&self, user: &str, query: &str) {
+        let query = query.trim();
+        if user.is_empty() || query.is_empty() {
+            return;
+        }
+
+        let mut users = self.lock();
+        let entries = users.entry(user.to_string()).or_default();
+        entries.retain(|entry| entry.query != query);
+        entries.insert(0, HistoryEntry { query: query.to_string(), searched_at: unix_now() });
+        entries.truncate(self.max_entries);
+        self.save(&users);
+    }
+
+    /// Видаляє історію користувача
+    pub fn clear(&self, user: &str) {
+        let mut users = self.lock();
+        if users.remove(user).is_some() {
+            self.save(&users);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Vec<HistoryEntry>>> {
+        match self.users.lock() {
+            Ok(users) => users,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Записує через тимчасовий файл, щоб збій не пошкодив історію
+    fn save(&self, users: &HashMap<String, Vec<HistoryEntry>>) {
+        let temp_path = format!("{}.tmp", self.path);
+        let result = serde_json::to_string(users)
+            .map_err(std::io::Error::from)
+            .and_then(|json| fs::write(&temp_path, json))
+            .and_then(|()| fs::rename(&temp_path, &self.path));
+        if let Err(e) = result {
+            warn!("{}", tr!(HistorySaveFailed, self.path, e));
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+
+    #[test]
+    fn test_history_per_user_deduplicated_and_persisted() {
+        let dir = TempIndexDir::new("search_history");
+        let path = dir.join("search_history.json").to_string_lossy().to_string();
+
+        let history = SearchHistory::load(path.clone(), 3);
+        for query in ["Петренко", "наказ 16", "відпустка", "Петренко ", "Іваненко"] {
+            history.record("оператор", query);
+        }
+        history.record("чергова", "Коваленко");
+        history.record("чергова", "   ");
+
+        let queries = |history: &SearchHistory, user: &str| -> Vec<String> {
+            history.recent(user).into_iter().map(|e| e.query).collect()
+        };
+        assert_eq!(queries(&history, "оператор"), vec!["Іваненко", "Петренко", "відпустка"]);
+
+        // Після перезапуску історія та сама
+        let reloaded = SearchHistory::load(path.clone(), 3);
+        assert_eq!(queries(&reloaded, "оператор"), vec!["Іваненко", "Петренко", "відпустка"]);
+        assert_eq!(queries(&reloaded, "чергова"), vec!["Коваленко"]);
+
+        reloaded.clear("оператор");
+        assert!(SearchHistory::load(path, 3).recent("оператор").is_empty());
+        assert_eq!(SearchHistory::user_key(&format!("  {}  ", "я".repeat(100))), "я".repeat(MAX_USER_CHARS));
+    }
+}
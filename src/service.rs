@@ -0,0 +1,161 @@
+//! Робота сервера як служби Linux: файл PID, перехід у фон (--daemon), повідомлення
+//! systemd для Type=notify (READY, STOPPING, WATCHDOG) і сигнал зупинки (SIGTERM).
+//! Поза systemd (немає NOTIFY_SOCKET) повідомлення нічого не роблять.
+
+use blazing_search::shutdown::Shutdown;
+use blazing_search::tr;
+use log::{info, warn};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Файл з PID сервера; видаляється, коли сервер зупиняється
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Записує PID поточного процесу. Файл, що належить іншому запущеному процесу,
+    /// не перезаписується; застарілий (процес завершився аварійно) - замінюється.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let previous = fs::read_to_string(path).ok().and_then(|text| text.trim().parse::<u32>().ok());
+        if let Some(pid) = previous {
+            if pid != std::process::id() && process_running(pid) {
+                return Err(io::Error::new(io::ErrorKind::AlreadyExists, tr!(PidFileInUse, pid)));
+            }
+        }
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, format!("{}\n", std::process::id()))?;
+        Ok(Self { path: path.to_path_buf() })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn process_running(pid: u32) -> bool {
+    Path::new(&format!("/proc/{}", pid)).exists()
+}
+
+/// Без /proc перевірити процес нічим - вважаємо файл застарілим
+#[cfg(not(target_os = "linux"))]
+fn process_running(_pid: u32) -> bool {
+    false
+}
+
+/// Переводить процес у фон: батьківський процес завершується, дочірній продовжує з тією
+/// самою робочою папкою (відносні шляхи конфігурації лишаються дійсними).
+/// Викликати до запуску журналу і tokio - після fork інші потоки не переносяться.
+#[cfg(unix)]
+pub fn daemonize() -> io::Result<()> {
+    daemonize::Daemonize::new()
+        .working_directory(std::env::current_dir()?)
+        .umask(0o022)
+        .start()
+        .map_err(io::Error::other)
+}
+
+#[cfg(not(unix))]
+pub fn daemonize() -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "перехід у фон підтримується лише на Linux/Unix"))
+}
+
+/// Сервер прийняв порт і готовий обслуговувати запити
+pub fn notify_ready() {
+    #[cfg(unix)]
+    notify(&[sd_notify::NotifyState::Ready]);
+}
+
+/// Сервер почав зупинку (systemd чекає до TimeoutStopSec)
+fn notify_stopping() {
+    #[cfg(unix)]
+    notify(&[sd_notify::NotifyState::Stopping]);
+}
+
+#[cfg(unix)]
+fn notify(state: &[sd_notify::NotifyState]) {
+    if let Err(e) = sd_notify::notify(false, state) {
+        warn!("{}", tr!(SystemdNotifyFailed, e));
+    }
+}
+
+/// Якщо в юніті задано WatchdogSec, надсилає systemd сигнал життя вдвічі частіше.
+/// Сигнал іде з задачі tokio, тож завислий runtime призведе до перезапуску служби.
+pub fn start_watchdog() {
+    #[cfg(unix)]
+    {
+        let mut usec = 0;
+        if sd_notify::watchdog_enabled(false, &mut usec) {
+            let period = std::time::Duration::from_micros(usec / 2);
+            info!("{}", tr!(WatchdogEnabled, period.as_millis()));
+            tokio::spawn(async move {
+                let mut timer = tokio::time::interval(period);
+                loop {
+                    timer.tick().await;
+                    notify(&[sd_notify::NotifyState::Watchdog]);
+                }
+            });
+        }
+    }
+}
+
+/// Перший SIGTERM/Ctrl+C запитує коректну зупинку, повторний - негайний вихід
+pub fn handle_signals(shutdown: Shutdown) {
+    tokio::spawn(async move {
+        let signal = shutdown_signal().await;
+        info!("{}", tr!(ShutdownRequested, signal));
+        notify_stopping();
+        shutdown.request();
+
+        let signal = shutdown_signal().await;
+        warn!("{}", tr!(ForcedShutdown, signal));
+        std::process::exit(1);
+    });
+}
+
+/// Чекає сигналу зупинки: SIGTERM (systemctl stop, docker stop) або Ctrl+C.
+/// Повертає назву сигналу для журналу.
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            return tokio::select! {
+                _ = terminate.recv() => "SIGTERM",
+                _ = tokio::signal::ctrl_c() => "SIGINT",
+            };
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+    "SIGINT"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pid_file_lifecycle() {
+        let dir = std::env::temp_dir().join(format!("blazing_pid_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("run").join("blazing.pid");
+
+        // Застарілий файл від процесу, якого вже немає, замінюється
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "4294967295\n").unwrap();
+
+        let pid_file = PidFile::create(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().trim(), std::process::id().to_string());
+        drop(pid_file);
+        assert!(!path.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
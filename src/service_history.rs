@@ -0,0 +1,152 @@
+//! Послужний витяг: усі абзаци з прізвищем людини з наказів за всі роки, впорядковані
+//! хронологічно (дата наказу, номер наказу, пункт) - замість десятків окремих пошуків,
+//! з яких діловоди складали довідку вручну. GET /api/person?name=...
+
+use crate::result_export::{self, ExportRow};
+use crate::search_engine::SearchEngineResult;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+/// Номер наказу в назві файлу: "Наказ №16 від ..."
+static ORDER_NUMBER_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"№\s*(\d+)").unwrap());
+
+/// Абзац витягу з номером пункту наказу
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ExtractParagraph {
+    pub section: Option<String>,
+    pub text: String,
+}
+
+/// Наказ з абзацами про людину
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct OrderExtract {
+    /// Дата наказу (ДД.ММ.РРРР, з назви файлу чи шапки); без неї наказ впорядковується за датою
+    /// створення файлу, а якщо невідома й вона - потрапляє в кінець витягу
+    pub date: Option<String>,
+    pub file_name: String,
+    pub file_path: String,
+    pub category: Option<String>,
+    pub paragraphs: Vec<ExtractParagraph>,
+}
+
+/// Послужний витяг
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ServiceHistory {
+    pub person: String,
+    pub orders: Vec<OrderExtract>,
+    /// Усього абзаців у витягу
+    pub paragraphs: usize,
+}
+
+impl ServiceHistory {
+    /// Витяг з результатів повного пошуку за ім'ям: від найстаршого наказу до найновішого
+    pub fn from_results(person: &str, results: &[SearchEngineResult]) -> Self {
+        let mut orders: Vec<_> = results
+            .iter()
+            .filter_map(|result| {
+                let rows = result_export::export_rows(std::slice::from_ref(result));
+                let first = rows.first()?;
+                let date = result.date.map(|date| date.ymd());
+                let order = OrderExtract {
+                    date: first.date.clone(),
+                    file_name: result.file_name.clone(),
+                    file_path: result.file_path.clone(),
+                    category: result.category.clone(),
+                    paragraphs: rows
+                        .into_iter()
+                        .map(|row| ExtractParagraph { section: row.section, text: row.paragraph })
+                        .collect(),
+                };
+                Some(((date.is_none(), date, order_number(&result.file_name)), order))
+            })
+            .collect();
+
+        // Стабільне сортування: накази без дати лишаються в порядку пошуку
+        orders.sort_by_key(|(key, _)| *key);
+        let orders: Vec<OrderExtract> = orders.into_iter().map(|(_, order)| order).collect();
+
+        Self {
+            person: person.trim().to_string(),
+            paragraphs: orders.iter().map(|o| o.paragraphs.len()).sum(),
+            orders,
+        }
+    }
+
+    /// Рядки для вивантаження (CSV, XLSX, PDF) у порядку витягу
+    pub fn rows(&self) -> Vec<ExportRow> {
+        self.orders
+            .iter()
+            .flat_map(|order| {
+                order.paragraphs.iter().map(|paragraph| ExportRow {
+                    file_name: order.file_name.clone(),
+                    file_path: order.file_path.clone(),
+                    date: order.date.clone(),
+                    section: paragraph.section.clone(),
+                    paragraph: paragraph.text.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+fn order_number(file_name: &str) -> Option<u32> {
+    ORDER_NUMBER_REGEX.captures(file_name)?[1].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document_date::DocumentDate;
+    use crate::document_record::Paragraph;
+    use crate::search_engine::SearchEngineMatch;
+
+    fn result(file_name: &str, paragraphs: &[&str], positions: &[usize]) -> SearchEngineResult {
+        SearchEngineResult {
+            doc_id: 0,
+            file_name: file_name.to_string(),
+            file_path: format!("накази/{}", file_name),
+            matches: positions
+                .iter()
+                .map(|&position| SearchEngineMatch { context: String::new(), position })
+                .collect(),
+            all_paragraphs: paragraphs.iter().map(|text| Paragraph::new(text.to_string())).collect(),
+            file_size: 0,
+            last_modified: 0,
+            category: None,
+            date: DocumentDate::from_file_name(file_name),
+        }
+    }
+
+    #[test]
+    fn test_orders_sorted_chronologically_with_sections() {
+        // Пошук повертає найновіші першими
+        let results = vec![
+            result("Наказ №40 від 10.04.2024.docx", &["5. Звільнити солдата Петренка П.П."], &[0]),
+            result("Наказ без дати.docx", &["Петренко П.П."], &[0]),
+            result("Наказ №12 від 05.03.2024.docx", &["2. Відрядити:", "солдата Петренка П.П.", "3. Петренку П.П. виплатити"], &[2, 1]),
+            result("Наказ №3 від 05.03.2024.docx", &["1. Призначити Петренка П.П."], &[0]),
+            result("Наказ №7 від 20.01.2023.docx", &["1. Зарахувати Петренка П.П."], &[0]),
+        ];
+
+        let history = ServiceHistory::from_results(" Петренко П.П. ", &results);
+        let orders: Vec<_> = history.orders.iter().map(|o| o.file_name.as_str()).collect();
+        assert_eq!(
+            orders,
+            vec![
+                "Наказ №7 від 20.01.2023.docx",
+                "Наказ №3 від 05.03.2024.docx",
+                "Наказ №12 від 05.03.2024.docx",
+                "Наказ №40 від 10.04.2024.docx",
+                "Наказ без дати.docx",
+            ]
+        );
+        assert_eq!(history.person, "Петренко П.П.");
+        assert_eq!(history.paragraphs, 6);
+
+        let sections: Vec<_> = history.orders[2].paragraphs.iter().map(|p| p.section.as_deref()).collect();
+        assert_eq!(sections, vec![Some("2"), Some("3")]);
+        assert_eq!(history.rows().len(), 6);
+        assert_eq!(history.rows()[0].date.as_deref(), Some("20.01.2023"));
+    }
+}
@@ -0,0 +1,46 @@
+//! Сеанси веб-інтерфейсу. Облікових записів немає, тож користувача визначає сеанс браузера:
+//! сервер видає випадковий токен у cookie (HttpOnly), а історія пошуку, збережені запити
+//! і візити прив'язуються до ключа, виведеного з цього токена. Ім'я, яке вводить
+//! користувач, лишається лише підписом (автор примітки) і доступу ні до чого не дає.
+
+use crate::config::password_sha256;
+
+/// Cookie з токеном сеансу
+pub const SESSION_COOKIE: &str = "blazing_session";
+
+/// Скільки символів хешу токена входить у ключ користувача
+const SESSION_KEY_CHARS: usize = 16;
+
+/// Новий випадковий токен (128 біт у hex)
+pub fn new_token() -> String {
+    rand::random::<[u8; 16]>().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Чи схожий рядок на токен, виданий new_token (чужі значення cookie відкидаються)
+pub fn is_token(value: &str) -> bool {
+    value.len() == 32 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Ключ користувача сеансу для історії та збережених запитів. Сам токен у відповіді
+/// не потрапляє: ключ - початок його хешу
+pub fn user_key(token: &str) -> String {
+    let hash = password_sha256(&format!("session:{}", token.to_lowercase()));
+    format!("сеанс {}", &hash[..SESSION_KEY_CHARS])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_tokens_are_random_and_keys_stable() {
+        let (first, second) = (new_token(), new_token());
+        assert!(is_token(&first) && is_token(&second));
+        assert_ne!(first, second);
+        assert!(!is_token("петренко") && !is_token(""));
+
+        assert_eq!(user_key(&first), user_key(&first.to_uppercase()));
+        assert_ne!(user_key(&first), user_key(&second));
+        assert!(!user_key(&first).contains(&first));
+    }
+}
@@ -0,0 +1,56 @@
+//! Запит зупинки процесу (SIGTERM/Ctrl+C), спільний для сервера, фонового індексера
+//! і обробки папки. Індексація перевіряє його між файлами і зупиняється в безпечній
+//! точці: вже оброблені документи фіксуються звичайною атомарною транзакцією.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    state: Arc<ShutdownState>,
+}
+
+#[derive(Default)]
+struct ShutdownState {
+    requested: AtomicBool,
+    notify: Notify,
+}
+
+impl Shutdown {
+    pub fn request(&self) {
+        self.state.requested.store(true, Ordering::SeqCst);
+        self.state.notify.notify_waiters();
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.state.requested.load(Ordering::SeqCst)
+    }
+
+    /// Завершується, коли запитано зупинку
+    pub async fn wait(&self) {
+        // Notified створюється до перевірки прапорця, тож запит між ними не загубиться
+        let notified = self.state.notify.notified();
+        if self.is_requested() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_returns_after_earlier_request() {
+        let shutdown = Shutdown::default();
+        let clone = shutdown.clone();
+        assert!(!clone.is_requested());
+
+        // Запит до очікування не губиться, і його бачать усі клони
+        shutdown.request();
+        clone.wait().await;
+        assert!(clone.is_requested());
+    }
+}
@@ -0,0 +1,167 @@
+//! Журнал повільних запитів: запити, що виконувались довше за search.slow_query_threshold_ms,
+//! записуються окремим рядком JSON у slow_queries.jsonl у папці індексів - з термінами після
+//! стемінгу, режимом, кількістю кандидатів і часом кожного етапу. За ним видно, які саме
+//! запити з реального навантаження сповільнились після оновлення (GET /api/admin/slow-queries).
+
+use crate::tr;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// Поріг за замовчуванням, мс
+pub const DEFAULT_SLOW_QUERY_THRESHOLD_MS: u64 = 1000;
+/// Розмір журналу, після якого він переноситься в .1 (попередній .1 видаляється)
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Час етапів пошуку, мс
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SearchTimings {
+    /// Переписування запиту, стемінг, виділення слів
+    pub prepare_ms: f64,
+    /// Вибір кандидатів з інвертованого індексу
+    pub candidates_ms: f64,
+    /// Перевірка абзаців кандидатів (усі слова, близькість)
+    pub matching_ms: f64,
+    pub sorting_ms: f64,
+    pub total_ms: f64,
+}
+
+impl SearchTimings {
+    pub fn ms(duration: Duration) -> f64 {
+        (duration.as_secs_f64() * 100_000.0).round() / 100.0
+    }
+}
+
+/// Запис про повільний запит
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SlowQuery {
+    /// Unix timestamp
+    pub at: u64,
+    pub query: String,
+    /// Терміни після переписування і стемінгу
+    pub terms: Vec<String>,
+    pub mode: String,
+    pub view_mode: Option<String>,
+    /// Документів-кандидатів (без інвертованого індексу - усі документи)
+    pub candidates: usize,
+    pub results: usize,
+    /// Запит перервано за search.query_timeout_ms
+    #[serde(default)]
+    pub partial: bool,
+    pub timings: SearchTimings,
+}
+
+/// Поріг і файл журналу; поріг можна змінювати на ходу
+pub struct SlowQueryLog {
+    path: String,
+    /// 0 - журнал вимкнено
+    threshold_ms: AtomicU64,
+}
+
+impl SlowQueryLog {
+    pub fn new(path: String) -> Self {
+        Self {
+            path,
+            threshold_ms: AtomicU64::new(DEFAULT_SLOW_QUERY_THRESHOLD_MS),
+        }
+    }
+
+    /// 0 - не записувати запити
+    pub fn set_threshold_ms(&self, threshold_ms: u64) {
+        self.threshold_ms.store(threshold_ms, Ordering::Relaxed);
+    }
+
+    /// Чи варто записати запит такої тривалості
+    pub fn is_slow(&self, elapsed: Duration) -> bool {
+        let threshold_ms = self.threshold_ms.load(Ordering::Relaxed);
+        threshold_ms > 0 && elapsed >= Duration::from_millis(threshold_ms)
+    }
+
+    /// Дописує запис у журнал; помилка запису лише попереджає - пошук від неї не залежить
+    pub fn record(&self, entry: &SlowQuery) {
+        warn!("{}", tr!(SlowQueryLogged, entry.timings.total_ms, entry.query));
+        if let Err(e) = self.append(entry) {
+            warn!("{}", tr!(SlowQueryLogFailed, self.path, e));
+        }
+    }
+
+    fn append(&self, entry: &SlowQuery) -> std::io::Result<()> {
+        if fs::metadata(&self.path).is_ok_and(|m| m.len() > MAX_LOG_BYTES) {
+            fs::rename(&self.path, format!("{}.1", self.path))?;
+        }
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        OpenOptions::new().create(true).append(true).open(&self.path)?.write_all(line.as_bytes())
+    }
+}
+
+/// Останні limit записів журналу, найновіші першими (пошкоджені рядки пропускаються)
+pub fn load_recent(path: &str, limit: usize) -> Vec<SlowQuery> {
+    if !Path::new(path).exists() {
+        return Vec::new();
+    }
+    match fs::read_to_string(path) {
+        Ok(content) => content
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .take(limit)
+            .collect(),
+        Err(e) => {
+            warn!("{}", tr!(SlowQueryLogFailed, path, e));
+            Vec::new()
+        }
+    }
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+
+    #[test]
+    fn test_slow_queries_appended_and_read_newest_first() {
+        let dir = TempIndexDir::new("slow_queries");
+        let path = dir.join("slow_queries.jsonl").to_string_lossy().to_string();
+
+        let log = SlowQueryLog::new(path.clone());
+        log.set_threshold_ms(500);
+        assert!(!log.is_slow(Duration::from_millis(499)));
+        assert!(log.is_slow(Duration::from_millis(500)));
+
+        for query in ["Петренко", "наказ 16"] {
+            log.record(&SlowQuery {
+                at: unix_now(),
+                query: query.to_string(),
+                terms: vec![query.to_lowercase()],
+                mode: "quick".to_string(),
+                view_mode: None,
+                candidates: 170,
+                results: 3,
+                partial: false,
+                timings: SearchTimings { total_ms: 812.5, ..SearchTimings::default() },
+            });
+        }
+        fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"broken\n").unwrap();
+
+        let recent = load_recent(&path, 10);
+        let queries: Vec<_> = recent.iter().map(|q| q.query.as_str()).collect();
+        assert_eq!(queries, vec!["наказ 16", "Петренко"]);
+        assert_eq!(load_recent(&path, 1).len(), 1);
+
+        log.set_threshold_ms(0);
+        assert!(!log.is_slow(Duration::from_secs(60)));
+        assert_eq!(SearchTimings::ms(Duration::from_micros(12_346)), 12.35);
+    }
+}
@@ -0,0 +1,391 @@
+//! Сховище індексів у SQLite (storage.backend = "sqlite"): документ - рядок таблиці
+//! documents, запис інвертованого індексу - рядок postings (слово, слот, абзаци). Оновлення
+//! кількох документів переписує лише їхні рядки однією транзакцією, а журнал WAL бази дає
+//! пошуковому серверу читати попередню версію, поки індексер записує нову.
+
+use crate::document_record::{DocumentIndex, DocumentRecord};
+use crate::error::IndexError;
+use crate::index_store::{IndexStore, StoreChanges};
+use crate::inverted_index::{DocPosition, InvertedIndex};
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Скільки чекати, поки інше з'єднання (індексер іншого процесу) завершить запис
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value INTEGER NOT NULL);
+    CREATE TABLE IF NOT EXISTS documents (slot INTEGER PRIMARY KEY, record TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS postings (
+        word TEXT NOT NULL,
+        slot INTEGER NOT NULL,
+        positions TEXT NOT NULL,
+        PRIMARY KEY (word, slot)
+    ) WITHOUT ROWID;
+    CREATE INDEX IF NOT EXISTS postings_slot ON postings (slot);
+";
+
+pub struct SqliteIndexStore {
+    path: PathBuf,
+    /// synchronous = FULL: підтверджена транзакція переживає втрату живлення
+    durable_writes: bool,
+}
+
+impl SqliteIndexStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), durable_writes: true }
+    }
+
+    /// Вмикає або вимикає fsync кожної транзакції
+    pub fn with_durable_writes(mut self, durable_writes: bool) -> Self {
+        self.durable_writes = durable_writes;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn open(&self) -> Result<Connection, IndexError> {
+        let connection = Connection::open(&self.path)
+            .map_err(|e| IndexError::database(format!("Помилка відкриття сховища {}", self.path.display()), e))?;
+        connection.busy_timeout(BUSY_TIMEOUT).map_err(query_error)?;
+        connection
+            .pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+            .map_err(query_error)?;
+        connection
+            .pragma_update(None, "synchronous", if self.durable_writes { "FULL" } else { "NORMAL" })
+            .map_err(query_error)?;
+        connection.execute_batch(SCHEMA).map_err(query_error)?;
+        Ok(connection)
+    }
+
+    /// З'єднання для читання; None - бази ще немає (читання її не створює)
+    fn open_existing(&self) -> Result<Option<Connection>, IndexError> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        self.open().map(Some)
+    }
+
+    fn read_documents(&self, connection: &Connection, generation: u64) -> Result<DocumentIndex, IndexError> {
+        let mut index = DocumentIndex::new();
+        index.total_documents = read_meta(connection, "total_documents")?.unwrap_or(0) as usize;
+        index.total_words = read_meta(connection, "total_words")?.unwrap_or(0) as usize;
+        index.indexed_at = read_meta(connection, "indexed_at")?.unwrap_or(0);
+        index.next_doc_id = read_meta(connection, "next_doc_id")?.unwrap_or(1);
+        index.last_compacted_at = read_meta(connection, "last_compacted_at")?.unwrap_or(0);
+
+        let mut statement = connection.prepare("SELECT slot, record FROM documents ORDER BY slot").map_err(query_error)?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+            .map_err(query_error)?;
+        for row in rows {
+            let (slot, record) = row.map_err(query_error)?;
+            // Слот - позиція документа: пропуск зсунув би всі записи інвертованого індексу
+            if slot != index.documents.len() as i64 {
+                return Err(IndexError::Corrupt(format!(
+                    "Сховище {}: після слоту {} йде слот {}",
+                    self.path.display(),
+                    index.documents.len() as i64 - 1,
+                    slot
+                )));
+            }
+            let document: DocumentRecord = serde_json::from_str(&record)
+                .map_err(|e| IndexError::json(format!("Пошкоджений документ у слоті {}", slot), e))?;
+            index.documents.push(document);
+        }
+
+        index.generation = generation;
+        index.rebuild_id_map();
+        index.check_loaded();
+        Ok(index)
+    }
+
+    fn read_inverted(connection: &Connection, generation: u64) -> Result<InvertedIndex, IndexError> {
+        let mut index = InvertedIndex::new();
+        index.total_documents = read_meta(connection, "inverted_total_documents")?.unwrap_or(0) as usize;
+
+        let mut statement = connection
+            .prepare("SELECT word, slot, positions FROM postings ORDER BY word, slot")
+            .map_err(query_error)?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?)))
+            .map_err(query_error)?;
+        for row in rows {
+            let (word, slot, positions) = row.map_err(query_error)?;
+            let paragraph_positions: Vec<usize> = serde_json::from_str(&positions)
+                .map_err(|e| IndexError::json(format!("Пошкоджений запис слова \"{}\"", word), e))?;
+            index
+                .word_to_docs
+                .entry(word)
+                .or_default()
+                .push(DocPosition { doc_index: slot as usize, paragraph_positions });
+        }
+
+        index.generation = generation;
+        Ok(index)
+    }
+}
+
+impl IndexStore for SqliteIndexStore {
+    fn generation(&self) -> Result<Option<u64>, IndexError> {
+        match self.open_existing()? {
+            Some(connection) => read_meta(&connection, "generation"),
+            None => Ok(None),
+        }
+    }
+
+    fn load_documents(&self) -> Result<Option<DocumentIndex>, IndexError> {
+        let Some(mut connection) = self.open_existing()? else {
+            return Ok(None);
+        };
+        let transaction = connection.transaction().map_err(query_error)?;
+        match read_meta(&transaction, "generation")? {
+            Some(generation) => self.read_documents(&transaction, generation).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn load_inverted(&self) -> Result<Option<InvertedIndex>, IndexError> {
+        let Some(mut connection) = self.open_existing()? else {
+            return Ok(None);
+        };
+        let transaction = connection.transaction().map_err(query_error)?;
+        match read_meta(&transaction, "generation")? {
+            Some(generation) => Self::read_inverted(&transaction, generation).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn load(&self) -> Result<Option<(DocumentIndex, InvertedIndex)>, IndexError> {
+        let Some(mut connection) = self.open_existing()? else {
+            return Ok(None);
+        };
+        // Обидві таблиці читаються з одного знімка бази
+        let transaction = connection.transaction().map_err(query_error)?;
+        let Some(generation) = read_meta(&transaction, "generation")? else {
+            return Ok(None);
+        };
+        let documents = self.read_documents(&transaction, generation)?;
+        let inverted = Self::read_inverted(&transaction, generation)?;
+        Ok(Some((documents, inverted)))
+    }
+
+    fn commit(
+        &self,
+        document_index: &DocumentIndex,
+        inverted_index: &InvertedIndex,
+        changes: &StoreChanges,
+    ) -> Result<u64, IndexError> {
+        let mut connection = self.open()?;
+        // IMMEDIATE: два процеси не прочитають однаковий номер версії
+        let transaction = connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(query_error)?;
+        let previous = read_meta(&transaction, "generation")?;
+        let generation = previous.unwrap_or(0) + 1;
+        let slot_count = document_index.documents.len();
+
+        match changes {
+            // Частково можна оновити лише вже збережену версію
+            StoreChanges::Slots(slots) if previous.is_some() => {
+                let slots: HashSet<usize> = slots.iter().copied().filter(|&slot| slot < slot_count).collect();
+                // Слоти за кінцем індексу лишились від довшої версії
+                transaction
+                    .execute("DELETE FROM documents WHERE slot >= ?1", [slot_count as i64])
+                    .map_err(query_error)?;
+                transaction
+                    .execute("DELETE FROM postings WHERE slot >= ?1", [slot_count as i64])
+                    .map_err(query_error)?;
+                {
+                    let mut delete = transaction.prepare("DELETE FROM postings WHERE slot = ?1").map_err(query_error)?;
+                    for &slot in &slots {
+                        delete.execute([slot as i64]).map_err(query_error)?;
+                    }
+                }
+                write_documents(&transaction, document_index, slots.iter().copied())?;
+                write_postings(&transaction, inverted_index, Some(&slots))?;
+            }
+            _ => {
+                transaction
+                    .execute_batch("DELETE FROM documents; DELETE FROM postings;")
+                    .map_err(query_error)?;
+                write_documents(&transaction, document_index, 0..slot_count)?;
+                write_postings(&transaction, inverted_index, None)?;
+            }
+        }
+
+        for (key, value) in [
+            ("generation", generation),
+            ("total_documents", document_index.total_documents as u64),
+            ("total_words", document_index.total_words as u64),
+            ("indexed_at", document_index.indexed_at),
+            ("next_doc_id", document_index.next_doc_id),
+            ("last_compacted_at", document_index.last_compacted_at),
+            ("inverted_total_documents", inverted_index.total_documents as u64),
+        ] {
+            transaction
+                .execute("INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)", params![key, value as i64])
+                .map_err(query_error)?;
+        }
+
+        transaction.commit().map_err(query_error)?;
+        Ok(generation)
+    }
+
+    fn verify(&self) -> Vec<String> {
+        let connection = match self.open_existing() {
+            Ok(Some(connection)) => connection,
+            Ok(None) => return vec![format!("сховище не існує: {}", self.path.display())],
+            Err(e) => return vec![e.to_string()],
+        };
+        let problems = connection.prepare("PRAGMA integrity_check").and_then(|mut statement| {
+            statement
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()
+        });
+        match problems {
+            Ok(problems) => problems.into_iter().filter(|problem| problem != "ok").collect(),
+            Err(e) => vec![query_error(e).to_string()],
+        }
+    }
+}
+
+fn query_error(source: rusqlite::Error) -> IndexError {
+    IndexError::database("Помилка запиту до сховища індексу", source)
+}
+
+fn read_meta(connection: &Connection, key: &str) -> Result<Option<u64>, IndexError> {
+    connection
+        .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| row.get::<_, i64>(0))
+        .optional()
+        .map(|value| value.map(|value| value as u64))
+        .map_err(query_error)
+}
+
+fn write_documents(
+    connection: &Connection,
+    document_index: &DocumentIndex,
+    slots: impl Iterator<Item = usize>,
+) -> Result<(), IndexError> {
+    let mut insert = connection
+        .prepare("INSERT OR REPLACE INTO documents (slot, record) VALUES (?1, ?2)")
+        .map_err(query_error)?;
+    for slot in slots {
+        let record = serde_json::to_string(&document_index.documents[slot])
+            .map_err(|e| IndexError::json(format!("Помилка серіалізації документа у слоті {}", slot), e))?;
+        insert.execute(params![slot as i64, record]).map_err(query_error)?;
+    }
+    Ok(())
+}
+
+/// Записи інвертованого індексу лише для slots (None - усі)
+fn write_postings(
+    connection: &Connection,
+    inverted_index: &InvertedIndex,
+    slots: Option<&HashSet<usize>>,
+) -> Result<(), IndexError> {
+    let mut insert = connection
+        .prepare("INSERT OR REPLACE INTO postings (word, slot, positions) VALUES (?1, ?2, ?3)")
+        .map_err(query_error)?;
+    for (word, documents) in &inverted_index.word_to_docs {
+        for position in documents {
+            if slots.is_some_and(|slots| !slots.contains(&position.doc_index)) {
+                continue;
+            }
+            let positions = serde_json::to_string(&position.paragraph_positions)
+                .map_err(|e| IndexError::json("Помилка серіалізації інвертованого індексу", e))?;
+            insert
+                .execute(params![word, position.doc_index as i64, positions])
+                .map_err(query_error)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+
+    fn document(name: &str, text: &str) -> DocumentRecord {
+        DocumentRecord::from_texts(name, [text])
+    }
+
+    fn slots(inverted: &InvertedIndex, word: &str) -> Vec<usize> {
+        let mut slots: Vec<usize> = inverted
+            .word_to_docs
+            .get(&crate::stemmer::stem_word(word))
+            .map(|documents| documents.iter().map(|d| d.doc_index).collect())
+            .unwrap_or_default();
+        slots.sort_unstable();
+        slots
+    }
+
+    #[test]
+    fn test_roundtrip_and_partial_update() {
+        let dir = TempIndexDir::new("sqlite_store");
+        let store = SqliteIndexStore::new(dir.join("index.sqlite")).with_durable_writes(false);
+
+        // Порожнє сховище не створює базу при читанні
+        assert_eq!(store.generation().unwrap(), None);
+        assert!(store.load().unwrap().is_none());
+        assert!(!store.path().exists());
+
+        let mut doc_index = DocumentIndex::new();
+        doc_index.push_document(document("1.docx", "наказ про відпустку"));
+        doc_index.push_document(document("2.docx", "рапорт про відрядження"));
+        doc_index.recount();
+        let mut inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+        // Часткове оновлення порожнього сховища записує все
+        assert_eq!(store.commit(&doc_index, &inv_index, &StoreChanges::Slots(vec![1])).unwrap(), 1);
+
+        let (loaded_docs, loaded_inv) = store.load().unwrap().unwrap();
+        assert_eq!(loaded_docs.generation, 1);
+        assert_eq!(loaded_inv.generation, 1);
+        assert_eq!(loaded_docs.documents.len(), 2);
+        assert_eq!(loaded_docs.slot_of(doc_index.documents[1].doc_id), Some(1));
+        assert_eq!(loaded_docs.next_doc_id, doc_index.next_doc_id);
+        assert_eq!(slots(&loaded_inv, "про"), vec![0, 1]);
+        assert_eq!(loaded_inv.word_to_docs.len(), inv_index.word_to_docs.len());
+
+        // Змінено другий документ і додано третій
+        doc_index.replace_document(1, document("2.docx", "рапорт про звільнення"));
+        doc_index.push_document(document("3.docx", "наказ про звільнення"));
+        doc_index.recount();
+        inv_index.update_incremental(&doc_index, &[1, 2]);
+        // Зміна в пам'яті поза переліченими слотами не записується - рядки слоту 0 не чіпаються
+        inv_index.word_to_docs.remove(&crate::stemmer::stem_word("відпустку"));
+        assert_eq!(store.commit(&doc_index, &inv_index, &StoreChanges::Slots(vec![1, 2])).unwrap(), 2);
+
+        let (loaded_docs, loaded_inv) = store.load().unwrap().unwrap();
+        assert_eq!(loaded_docs.generation, 2);
+        assert_eq!(loaded_docs.documents.len(), 3);
+        assert_eq!(loaded_docs.documents[1].content, vec!["рапорт про звільнення".to_string()]);
+        assert_eq!(slots(&loaded_inv, "звільнення"), vec![1, 2]);
+        assert!(slots(&loaded_inv, "відрядження").is_empty());
+        assert_eq!(slots(&loaded_inv, "відпустку"), vec![0]);
+        assert_eq!(store.load_documents().unwrap().unwrap().total_documents, 3);
+
+        // Ущільнення зменшує індекс - повний запис прибирає зайві слоти
+        doc_index.tombstone(0);
+        doc_index.compact();
+        let inv_index = InvertedIndex::rebuild_from_scratch(&doc_index);
+        assert_eq!(store.commit(&doc_index, &inv_index, &StoreChanges::All).unwrap(), 3);
+        let loaded_inv = store.load_inverted().unwrap().unwrap();
+        assert_eq!(slots(&loaded_inv, "звільнення"), vec![0, 1]);
+        assert!(slots(&loaded_inv, "відпустку").is_empty());
+        assert_eq!(store.load_documents().unwrap().unwrap().documents.len(), 2);
+        assert!(store.verify().is_empty());
+
+        // Лічильники зі сховища перераховуються при завантаженні, як і з файлу
+        Connection::open(store.path())
+            .unwrap()
+            .execute("UPDATE meta SET value = 7 WHERE key = 'total_documents'", [])
+            .unwrap();
+        assert_eq!(store.load_documents().unwrap().unwrap().total_documents, 2);
+    }
+}
@@ -1,17 +1,62 @@
-/// Модуль для стемінгу (нормалізації) українських слів
-/// Використовується як в пошуку, так і при створенні індексу
+//! Модуль для стемінгу (нормалізації) українських слів
+//! Використовується як в пошуку, так і при створенні індексу
+
+use log::warn;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
 
 static UKRAINIAN_VOWELS: &str = "аеєиіїоуюяь";
 
+/// Стемер, обраний при запуску (до першої індексації чи пошуку)
+static STEMMER: OnceCell<StemmerKind> = OnceCell::new();
+
+/// Спосіб нормалізації слів. Індекс і запити мають використовувати однаковий,
+/// тому після зміни потрібна повна переіндексація.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StemmerKind {
+    /// Відкидання українських закінчень
+    #[default]
+    Ukrainian,
+    /// Лише переведення в нижній регістр
+    None,
+}
+
+impl std::str::FromStr for StemmerKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "ukrainian" => Ok(Self::Ukrainian),
+            "none" => Ok(Self::None),
+            other => Err(format!("невідомий стемер: {} (ukrainian або none)", other)),
+        }
+    }
+}
+
+/// Задає стемер для всього процесу; повторний виклик нічого не змінює
+pub fn set_stemmer(kind: StemmerKind) {
+    if let Err(kind) = STEMMER.set(kind) {
+        let current = STEMMER.get().copied().unwrap_or_default();
+        if current != kind {
+            warn!("⚠️ Стемер уже обрано ({:?}), зміна на {:?} ігнорується", current, kind);
+        }
+    }
+}
+
 /// Виконує стемінг слова (приведення до основи)
 pub fn stem_word(word: &str) -> String {
     let word = word.to_lowercase();
 
+    if STEMMER.get() == Some(&StemmerKind::None) {
+        return word;
+    }
+
     // Обробка слів з дефісом
     if word.contains('-') {
         let parts: Vec<String> = word
             .split('-')
-            .map(|part| stem_word_part(part))
+            .map(stem_word_part)
             .collect();
         return parts.join("-");
     }
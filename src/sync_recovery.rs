@@ -0,0 +1,282 @@
+use crate::error::SyncError;
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Суфікс тимчасового файлу, в який копіюється документ перед перейменуванням
+pub const PARTIAL_SUFFIX: &str = ".partial";
+
+/// Журнал копіювання файлів у локальний кеш (рядки "begin\t<шлях>" / "done\t<шлях>").
+/// Якщо процес завершився посеред копіювання, незавершені записи показують,
+/// які файли кешу можуть бути обрізаними.
+pub struct CopyJournal {
+    path: PathBuf,
+}
+
+impl CopyJournal {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Записує намір скопіювати файл (до початку копіювання)
+    pub fn begin(&self, relative_path: &Path) -> Result<(), SyncError> {
+        self.append("begin", relative_path)
+    }
+
+    /// Записує успішне завершення копіювання
+    pub fn complete(&self, relative_path: &Path) -> Result<(), SyncError> {
+        self.append("done", relative_path)
+    }
+
+    fn append(&self, phase: &str, relative_path: &Path) -> Result<(), SyncError> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| SyncError::io("Помилка відкриття журналу копіювання", e))?;
+
+        writeln!(file, "{}\t{}", phase, relative_path.to_string_lossy())
+            .map_err(|e| SyncError::io("Помилка запису в журнал копіювання", e))
+    }
+
+    /// Файли, копіювання яких почалося, але не завершилося
+    pub fn pending(&self) -> Vec<PathBuf> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut pending = Vec::new();
+        let mut seen = HashSet::new();
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            let Some((phase, path)) = line.split_once('\t') else {
+                continue; // обірваний останній рядок
+            };
+
+            match phase {
+                "begin" => {
+                    if seen.insert(path.to_string()) {
+                        pending.push(PathBuf::from(path));
+                    }
+                }
+                "done" => {
+                    seen.remove(path);
+                    pending.retain(|p| p.as_os_str() != path);
+                }
+                _ => {}
+            }
+        }
+
+        pending
+    }
+
+    /// Очищує журнал, коли незавершених копіювань не лишилося
+    pub fn clear(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Копіює файл через тимчасовий `<назва>.partial` і перейменування: під справжньою
+/// назвою ніколи не з'являється обрізаний файл, навіть якщо процес завершиться посеред копіювання
+pub fn copy_file_atomically(from: &Path, to: &Path) -> io::Result<u64> {
+    let mut partial = to.as_os_str().to_os_string();
+    partial.push(PARTIAL_SUFFIX);
+    let partial = PathBuf::from(partial);
+
+    let bytes = fs::copy(from, &partial).inspect_err(|_| {
+        let _ = fs::remove_file(&partial);
+    })?;
+
+    fs::rename(&partial, to).inspect_err(|_| {
+        let _ = fs::remove_file(&partial);
+    })?;
+
+    Ok(bytes)
+}
+
+/// Копіює файл у кеш із записом у журнал
+pub fn copy_with_journal(
+    journal: &CopyJournal,
+    remote_file: &Path,
+    local_file: &Path,
+    relative_path: &Path,
+) -> Result<u64, SyncError> {
+    journal.begin(relative_path)?;
+
+    let bytes = copy_file_atomically(remote_file, local_file)
+        .map_err(|e| SyncError::io(format!("Помилка копіювання {}", remote_file.display()), e))?;
+
+    journal.complete(relative_path)?;
+    Ok(bytes)
+}
+
+/// Результат відновлення кешу після незавершеної синхронізації
+#[derive(Debug, Default)]
+pub struct RecoveryReport {
+    /// Незавершені копіювання з журналу
+    pub journaled: usize,
+    /// Файли кешу, розмір яких не збігається з файлом на сервері
+    pub size_mismatches: usize,
+    pub recopied: usize,
+    /// Залишки тимчасових .partial файлів
+    pub partial_removed: usize,
+    pub remote_available: bool,
+    pub errors: Vec<String>,
+}
+
+impl RecoveryReport {
+    pub fn found_problems(&self) -> bool {
+        self.journaled > 0 || self.size_mismatches > 0 || self.partial_removed > 0
+    }
+}
+
+impl std::fmt::Display for RecoveryReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "незавершених копіювань: {}, розбіжностей розміру: {}, перекопійовано: {}, прибрано .partial: {}, помилок: {}",
+            self.journaled,
+            self.size_mismatches,
+            self.recopied,
+            self.partial_removed,
+            self.errors.len()
+        )?;
+        if !self.remote_available {
+            write!(f, " (мережева папка недоступна - відновлення відкладено)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Виявляє в кеші файли, що могли бути обрізані незавершеною синхронізацією
+/// (незавершені записи журналу, розбіжність розміру з сервером), і перекопійовує їх
+/// до парсингу. Якщо сервер недоступний - журнал зберігається до наступного запуску.
+pub fn recover_partial_files(
+    remote_path: &str,
+    cache_path: &str,
+    journal: &CopyJournal,
+) -> RecoveryReport {
+    let remote_root = Path::new(remote_path);
+    let cache_root = Path::new(cache_path);
+    let pending = journal.pending();
+
+    let mut report = RecoveryReport {
+        journaled: pending.len(),
+        remote_available: remote_root.is_dir(),
+        ..Default::default()
+    };
+
+    if !cache_root.is_dir() {
+        journal.clear();
+        return report;
+    }
+
+    // Підозрілі файли: з журналу + розмір не збігається з сервером
+    let mut suspects: Vec<PathBuf> = pending;
+    for entry in WalkDir::new(cache_root).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let local_file = entry.path();
+        if local_file.to_string_lossy().ends_with(PARTIAL_SUFFIX) {
+            if fs::remove_file(local_file).is_ok() {
+                report.partial_removed += 1;
+            }
+            continue;
+        }
+
+        if !report.remote_available {
+            continue;
+        }
+
+        let Ok(relative_path) = local_file.strip_prefix(cache_root) else {
+            continue;
+        };
+        if let (Ok(local_meta), Ok(remote_meta)) =
+            (entry.metadata(), remote_root.join(relative_path).metadata())
+        {
+            if local_meta.len() != remote_meta.len() && !suspects.iter().any(|p| p == relative_path) {
+                report.size_mismatches += 1;
+                suspects.push(relative_path.to_path_buf());
+            }
+        }
+    }
+
+    if !report.remote_available {
+        return report;
+    }
+
+    for relative_path in suspects {
+        let remote_file = remote_root.join(&relative_path);
+        let local_file = cache_root.join(&relative_path);
+
+        if !remote_file.is_file() {
+            // На сервері файлу вже немає - його прибере звичайна синхронізація
+            continue;
+        }
+
+        if let Some(parent) = local_file.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+
+        match copy_file_atomically(&remote_file, &local_file) {
+            Ok(_) => report.recopied += 1,
+            Err(e) => report.errors.push(format!(
+                "Помилка повторного копіювання {}: {}",
+                remote_file.display(),
+                e
+            )),
+        }
+    }
+
+    if report.errors.is_empty() {
+        journal.clear();
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+
+    #[test]
+    fn test_partial_files_are_recopied() {
+        let dir = TempIndexDir::new("sync_recovery_test");
+        let remote = dir.join("remote/2024");
+        let cache = dir.join("cache/2024");
+        fs::create_dir_all(&remote).unwrap();
+        fs::create_dir_all(&cache).unwrap();
+
+        // Обрізаний файл (розмір інший) і файл, копіювання якого обірвалося (розмір той самий)
+        fs::write(remote.join("a.docx"), vec![1u8; 100]).unwrap();
+        fs::write(cache.join("a.docx"), vec![1u8; 10]).unwrap();
+        fs::write(remote.join("b.docx"), vec![2u8; 50]).unwrap();
+        fs::write(cache.join("b.docx"), vec![0u8; 50]).unwrap();
+        fs::write(cache.join("c.docx.partial"), vec![3u8; 5]).unwrap();
+
+        let journal = CopyJournal::new(dir.join("sync_copy.journal"));
+        journal.begin(Path::new("2024/a.docx")).unwrap();
+        journal.complete(Path::new("2024/a.docx")).unwrap();
+        journal.begin(Path::new("2024/b.docx")).unwrap();
+        assert_eq!(journal.pending(), vec![PathBuf::from("2024/b.docx")]);
+
+        let report = recover_partial_files(
+            &dir.join("remote").to_string_lossy(),
+            &dir.join("cache").to_string_lossy(),
+            &journal,
+        );
+
+        assert_eq!(report.journaled, 1);
+        assert_eq!(report.size_mismatches, 1);
+        assert_eq!(report.recopied, 2);
+        assert_eq!(report.partial_removed, 1);
+        assert_eq!(fs::read(cache.join("a.docx")).unwrap(), vec![1u8; 100]);
+        assert_eq!(fs::read(cache.join("b.docx")).unwrap(), vec![2u8; 50]);
+        assert!(journal.pending().is_empty());
+    }
+}
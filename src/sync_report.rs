@@ -0,0 +1,123 @@
+use crate::error::SyncError;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{Instant, SystemTime};
+
+/// Скільки останніх звітів зберігаємо в історії
+const MAX_HISTORY_ENTRIES: usize = 500;
+
+/// Звіт про один цикл синхронізації мережевої папки з локальним кешем
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SyncReport {
+    pub started_at: u64,  // Unix timestamp
+    pub finished_at: u64, // Unix timestamp
+    pub duration_ms: u64,
+    pub remote_path: String,
+    pub local_cache_path: String,
+    pub files_copied: usize,
+    pub files_deleted: usize,
+    pub bytes_transferred: u64,
+    pub errors: Vec<String>,
+    pub aborted: bool, // Цикл перервано критичною помилкою (напр. неможливо створити кеш)
+    #[serde(skip)]
+    timer: Option<Instant>,
+}
+
+impl SyncReport {
+    pub fn start(remote_path: &str, local_cache_path: &str) -> Self {
+        Self {
+            started_at: unix_now(),
+            finished_at: 0,
+            duration_ms: 0,
+            remote_path: remote_path.to_string(),
+            local_cache_path: local_cache_path.to_string(),
+            files_copied: 0,
+            files_deleted: 0,
+            bytes_transferred: 0,
+            errors: Vec::new(),
+            aborted: false,
+            timer: Some(Instant::now()),
+        }
+    }
+
+    /// Фіксує час завершення циклу
+    pub fn finish(mut self) -> Self {
+        self.finished_at = unix_now();
+        self.duration_ms = self
+            .timer
+            .map(|t| t.elapsed().as_millis() as u64)
+            .unwrap_or_default();
+        self
+    }
+
+    /// Завершує цикл критичною помилкою
+    pub fn abort(mut self, error: String) -> Self {
+        self.errors.push(error);
+        self.aborted = true;
+        self.finish()
+    }
+}
+
+impl std::fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "скопійовано: {}, видалено: {}, передано: {:.2} MB, тривалість: {} мс, помилок: {}",
+            self.files_copied,
+            self.files_deleted,
+            self.bytes_transferred as f64 / 1_048_576.0,
+            self.duration_ms,
+            self.errors.len()
+        )
+    }
+}
+
+/// Завантажує історію звітів (від старих до нових)
+pub fn load_history(path: &str) -> Vec<SyncReport> {
+    if !Path::new(path).exists() {
+        return Vec::new();
+    }
+
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("⚠️  Пошкоджена історія синхронізацій {}: {}", path, e);
+            Vec::new()
+        }),
+        Err(e) => {
+            warn!("⚠️  Не вдалося прочитати історію синхронізацій {}: {}", path, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Додає звіт до історії, відкидаючи найстаріші записи понад ліміт
+pub fn append_to_history(path: &str, report: &SyncReport) -> Result<(), SyncError> {
+    let mut history = load_history(path);
+    history.push(report.clone());
+
+    if history.len() > MAX_HISTORY_ENTRIES {
+        let excess = history.len() - MAX_HISTORY_ENTRIES;
+        history.drain(..excess);
+    }
+
+    let json = serde_json::to_string_pretty(&history)
+        .map_err(|e| SyncError::json("Помилка серіалізації історії синхронізацій", e))?;
+
+    // Записуємо через тимчасовий файл, щоб не пошкодити історію при збої
+    let temp_path = format!("{}.tmp", path);
+    fs::write(&temp_path, json)
+        .map_err(|e| SyncError::io("Помилка запису історії синхронізацій", e))?;
+    fs::rename(&temp_path, path)
+        .map_err(|e| SyncError::io("Помилка переміщення історії синхронізацій", e))?;
+
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
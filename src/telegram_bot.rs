@@ -0,0 +1,247 @@
+//! Бот Telegram для пошуку з телефона - для працівників, які мають зв'язок, але не мають
+//! доступу до веб-інтерфейсу через VPN. Вмикається параметром telegram.token; відповідає
+//! лише чатам з telegram.allowed_chats. Оновлення отримуються довгим опитуванням
+//! (getUpdates), тож серверу не потрібна публічна адреса для webhook.
+
+use blazing_search::config::TelegramConfig;
+use blazing_search::redaction::Redactor;
+use blazing_search::result_export;
+use blazing_search::shutdown::Shutdown;
+use blazing_search::{tr, SearchEngine, SearchEngineResult, SearchMode};
+use log::{info, warn};
+use serde::Deserialize;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+const API_URL: &str = "https://api.telegram.org";
+/// Скільки сервер Telegram тримає запит getUpdates, поки не з'явиться повідомлення
+const POLL_TIMEOUT_SECS: u64 = 30;
+/// Пауза перед повтором після помилки мережі чи API
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+/// Скільки символів абзацу показувати для кожного документа
+const EXCERPT_CHARS: usize = 300;
+/// Ліміт Telegram - 4096 символів; лишаємо запас
+const MESSAGE_CHARS: usize = 4000;
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    chat: Chat,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+/// Виклики Bot API (блокуючі - виконуються в spawn_blocking)
+struct TelegramApi {
+    token: String,
+}
+
+impl TelegramApi {
+    fn url(&self, method: &str) -> String {
+        format!("{}/bot{}/{}", API_URL, self.token, method)
+    }
+
+    /// Токен входить в адресу запиту - прибираємо його з повідомлень про помилки
+    fn hide_token(&self, error: impl Display) -> String {
+        error.to_string().replace(&self.token, "***")
+    }
+
+    fn get_updates(&self, offset: i64) -> Result<Vec<Update>, String> {
+        let response: ApiResponse<Vec<Update>> = ureq::get(&self.url("getUpdates"))
+            .query("offset", &offset.to_string())
+            .query("timeout", &POLL_TIMEOUT_SECS.to_string())
+            .query("allowed_updates", r#"["message"]"#)
+            .timeout(Duration::from_secs(POLL_TIMEOUT_SECS + 10))
+            .call()
+            .map_err(|e| self.hide_token(e))?
+            .into_json()
+            .map_err(|e| self.hide_token(e))?;
+
+        match response {
+            ApiResponse { ok: true, result: Some(updates), .. } => Ok(updates),
+            ApiResponse { description, .. } => Err(description.unwrap_or_default()),
+        }
+    }
+
+    fn send_message(&self, chat_id: i64, text: &str) -> Result<(), String> {
+        ureq::post(&self.url("sendMessage"))
+            .timeout(Duration::from_secs(10))
+            .send_json(serde_json::json!({ "chat_id": chat_id, "text": text }))
+            .map(|_| ())
+            .map_err(|e| self.hide_token(e))
+    }
+}
+
+/// Запускає бота, якщо задано токен (список чатів перевіряє Config::validate).
+/// redactor - маскування чутливих даних (у бота немає ролі "unredacted")
+pub fn start(
+    search_engine: Arc<SearchEngine>,
+    config: &TelegramConfig,
+    redactor: Option<Arc<Redactor>>,
+    shutdown: Shutdown,
+) -> Option<JoinHandle<()>> {
+    let token = config.token.clone()?;
+    info!("{}", tr!(TelegramBotStarting, config.allowed_chats.len()));
+
+    let api = Arc::new(TelegramApi { token });
+    let allowed_chats = config.allowed_chats.clone();
+    let max_results = config.max_results;
+
+    Some(tokio::spawn(async move {
+        let mut offset = 0;
+        loop {
+            let poll_api = api.clone();
+            let updates = tokio::select! {
+                _ = shutdown.wait() => break,
+                updates = tokio::task::spawn_blocking(move || poll_api.get_updates(offset)) => updates,
+            };
+
+            let updates = match updates {
+                Ok(Ok(updates)) => updates,
+                Ok(Err(e)) => {
+                    warn!("{}", tr!(TelegramApiFailed, e));
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    continue;
+                }
+                Err(e) => {
+                    warn!("{}", tr!(TelegramApiFailed, e));
+                    continue;
+                }
+            };
+
+            for update in updates {
+                // Наступне опитування підтверджує отримані оновлення
+                offset = update.update_id + 1;
+                let Some(Message { chat, text: Some(text) }) = update.message else {
+                    continue;
+                };
+
+                let reply = if allowed_chats.contains(&chat.id) {
+                    answer(&search_engine, redactor.as_deref(), text.trim(), chat.id, max_results).await
+                } else {
+                    warn!("{}", tr!(TelegramChatDenied, chat.id));
+                    tr!(TelegramAccessDenied, chat.id)
+                };
+
+                let send_api = api.clone();
+                match tokio::task::spawn_blocking(move || send_api.send_message(chat.id, &reply)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => warn!("{}", tr!(TelegramApiFailed, e)),
+                    Err(e) => warn!("{}", tr!(TelegramApiFailed, e)),
+                }
+            }
+        }
+        info!("{}", tr!(TelegramBotStopped));
+    }))
+}
+
+/// Відповідь на повідомлення дозволеного чату: довідка або результати пошуку
+async fn answer(
+    search_engine: &SearchEngine,
+    redactor: Option<&Redactor>,
+    text: &str,
+    chat_id: i64,
+    max_results: usize,
+) -> String {
+    if text.is_empty() || text.starts_with("/start") || text.starts_with("/help") {
+        return tr!(TelegramHelp);
+    }
+
+    info!("{}", tr!(TelegramQuery, text, chat_id));
+    match search_engine.search(text, SearchMode::Full, None).await {
+        Ok(mut results) => {
+            if let Some(redactor) = redactor {
+                redactor.redact_results(&mut results);
+            }
+            format_reply(text, &results, max_results)
+        }
+        Err(e) => tr!(SearchFailed, e),
+    }
+}
+
+/// Текст відповіді: найновіші документи з першим знайденим абзацом і номером пункту
+fn format_reply(query: &str, results: &[SearchEngineResult], max_results: usize) -> String {
+    if results.is_empty() {
+        return tr!(TelegramNothingFound, query);
+    }
+
+    let shown = &results[..results.len().min(max_results)];
+    let mut reply = tr!(TelegramFound, results.len(), shown.len());
+    for (number, result) in shown.iter().enumerate() {
+        reply.push_str("\n\n");
+        reply.push_str(&tr!(TelegramResult, number + 1, result.file_name, result.matches.len()));
+
+        let first_match = result_export::export_rows(std::slice::from_ref(result)).into_iter().next();
+        if let Some(row) = first_match {
+            let excerpt = truncate(&row.paragraph, EXCERPT_CHARS);
+            reply.push('\n');
+            match row.section {
+                Some(section) => reply.push_str(&tr!(TelegramSection, section, excerpt)),
+                None => reply.push_str(&excerpt),
+            }
+        }
+    }
+    truncate(&reply, MESSAGE_CHARS)
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blazing_search::document_record::Paragraph;
+    use blazing_search::search_engine::SearchEngineMatch;
+
+    #[test]
+    fn test_reply_lists_top_documents_with_sections() {
+        let results: Vec<SearchEngineResult> = (1..=7)
+            .map(|i| SearchEngineResult {
+                doc_id: 0,
+                file_name: format!("наказ №{} від 05.02.2024.docx", i),
+                file_path: format!("2024/наказ №{} від 05.02.2024.docx", i),
+                matches: vec![SearchEngineMatch { context: String::new(), position: 1 }],
+                all_paragraphs: vec![
+                    Paragraph::new("3. Призначити:".to_string()),
+                    Paragraph::new(format!("солдата Петренка {}", "П".repeat(400))),
+                ],
+                file_size: 0,
+                last_modified: 0,
+                category: None,
+                date: None,
+            })
+            .collect();
+
+        let reply = format_reply("Петренко", &results, 5);
+        assert!(reply.starts_with("🔍 Знайдено документів: 7 (показано 5)"));
+        assert!(reply.contains("5. наказ №5 від 05.02.2024.docx (збігів: 1)\nп. 3 - солдата Петренка П"));
+        assert!(!reply.contains("наказ №6"));
+        assert!(reply.contains("П…"));
+        assert!(reply.chars().count() <= MESSAGE_CHARS + 1);
+
+        assert_eq!(format_reply("Іваненко", &[], 5), "🤷 Нічого не знайдено за запитом: Іваненко");
+        assert_eq!(truncate("абв", 2), "аб…");
+    }
+}
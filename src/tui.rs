@@ -0,0 +1,289 @@
+//! Термінальний інтерфейс пошуку (команда tui) - для адміністраторів, які працюють прямо
+//! на сервері через RDP/SSH без браузера: рядок запиту, список знайдених документів
+//! і перегляд абзаців документа з виділеними збігами.
+
+use blazing_search::document_record::Paragraph as DocumentParagraph;
+use blazing_search::{SearchEngine, SearchEngineResult, SearchMode};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Position};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Скільки абзаців над збігом лишати видимими для контексту
+const CONTEXT_PARAGRAPHS: usize = 1;
+/// На скільки абзаців гортає PgUp/PgDn
+const PAGE_PARAGRAPHS: usize = 10;
+
+const HELP: &str = "Enter - пошук · Tab - швидкий/повний · ↑↓ - документ · ←→ - збіг · PgUp/PgDn - гортати · Esc - вихід";
+
+/// Що робити після натиснутої клавіші
+#[derive(Debug, PartialEq)]
+enum Action {
+    None,
+    Search,
+    Quit,
+}
+
+/// Стан екрана; малювання і пошук - окремо, щоб клавіші можна було перевірити без терміналу
+#[derive(Default)]
+struct App {
+    query: String,
+    /// Повний пошук (всі документи, а не лише найновіші)
+    full: bool,
+    results: Vec<SearchEngineResult>,
+    list: ListState,
+    /// Номери абзаців зі збігами у вибраному документі (за зростанням)
+    match_positions: Vec<usize>,
+    current_match: usize,
+    /// Перший видимий абзац перегляду
+    preview_top: usize,
+    status: String,
+}
+
+impl App {
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Esc => return Action::Quit,
+            KeyCode::Char('c') if ctrl => return Action::Quit,
+            KeyCode::Char('u') if ctrl => self.query.clear(),
+            KeyCode::Char(c) if !ctrl => self.query.push(c),
+            KeyCode::Backspace => {
+                self.query.pop();
+            }
+            KeyCode::Enter if !self.query.trim().is_empty() => return Action::Search,
+            KeyCode::Tab => {
+                self.full = !self.full;
+                if !self.query.trim().is_empty() {
+                    return Action::Search;
+                }
+            }
+            KeyCode::Down => self.select(self.list.selected().map_or(0, |i| i + 1)),
+            KeyCode::Up => self.select(self.list.selected().map_or(0, |i| i.saturating_sub(1))),
+            KeyCode::Right if self.current_match + 1 < self.match_positions.len() => {
+                self.show_match(self.current_match + 1)
+            }
+            KeyCode::Left if self.current_match > 0 => self.show_match(self.current_match - 1),
+            KeyCode::PageDown => {
+                let last = self.selected().map_or(0, |r| r.all_paragraphs.len().saturating_sub(1));
+                self.preview_top = (self.preview_top + PAGE_PARAGRAPHS).min(last);
+            }
+            KeyCode::PageUp => self.preview_top = self.preview_top.saturating_sub(PAGE_PARAGRAPHS),
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn mode(&self) -> SearchMode {
+        if self.full { SearchMode::Remaining } else { SearchMode::Quick }
+    }
+
+    fn mode_name(&self) -> &'static str {
+        if self.full { "повний пошук" } else { "швидкий пошук" }
+    }
+
+    fn show_results(&mut self, results: Vec<SearchEngineResult>, elapsed: Duration) {
+        self.status = format!("🔍 Знайдено документів: {} за {} мс", results.len(), elapsed.as_millis());
+        self.results = results;
+        self.list = ListState::default();
+        self.select(0);
+    }
+
+    fn selected(&self) -> Option<&SearchEngineResult> {
+        self.list.selected().and_then(|i| self.results.get(i))
+    }
+
+    /// Вибирає документ і показує його перший збіг
+    fn select(&mut self, index: usize) {
+        if self.results.is_empty() {
+            self.list.select(None);
+            self.match_positions.clear();
+            self.preview_top = 0;
+            return;
+        }
+
+        let index = index.min(self.results.len() - 1);
+        self.list.select(Some(index));
+
+        let mut positions: Vec<usize> = self.results[index].matches.iter().map(|m| m.position).collect();
+        positions.sort_unstable();
+        positions.dedup();
+        self.match_positions = positions;
+        self.show_match(0);
+    }
+
+    fn show_match(&mut self, index: usize) {
+        self.current_match = index;
+        self.preview_top = self
+            .match_positions
+            .get(index)
+            .map_or(0, |position| position.saturating_sub(CONTEXT_PARAGRAPHS));
+    }
+}
+
+/// Відкриває інтерфейс на весь екран; журнал на цей час не виводиться в консоль
+pub async fn run(search_engine: &SearchEngine) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, search_engine).await;
+    ratatui::restore();
+    result
+}
+
+async fn event_loop(terminal: &mut DefaultTerminal, search_engine: &SearchEngine) -> io::Result<()> {
+    let (documents, _) = search_engine.get_stats();
+    let mut app = App {
+        status: format!("📚 Документів в індексі: {}", documents),
+        ..App::default()
+    };
+
+    loop {
+        terminal.draw(|frame| draw(frame, &mut app))?;
+
+        // Очікування клавіші блокує потік - звільняємо його для інших задач tokio
+        let Event::Key(key) = tokio::task::block_in_place(event::read)? else {
+            continue;
+        };
+        // Windows надсилає ще й відпускання клавіші
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match app.handle_key(key) {
+            Action::None => {}
+            Action::Quit => return Ok(()),
+            Action::Search => {
+                app.status = format!("🔍 Пошук ({})...", app.mode_name());
+                terminal.draw(|frame| draw(frame, &mut app))?;
+
+                let start = Instant::now();
+                match search_engine.search(&app.query, app.mode(), None).await {
+                    Ok(results) => app.show_results(results, start.elapsed()),
+                    Err(e) => app.status = format!("❌ Помилка пошуку: {}", e),
+                }
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [query_area, body_area, status_area] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0), Constraint::Length(2)]).areas(frame.area());
+    let [list_area, preview_area] =
+        Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)]).areas(body_area);
+
+    let query_block = Block::bordered().title(format!(" Запит ({}) ", app.mode_name()));
+    let cursor = Position::new(
+        query_area.x + 1 + Span::raw(&app.query).width() as u16,
+        query_area.y + 1,
+    );
+    frame.render_widget(Paragraph::new(app.query.as_str()).block(query_block), query_area);
+    frame.set_cursor_position(cursor);
+
+    let items: Vec<ListItem> = app
+        .results
+        .iter()
+        .map(|r| ListItem::new(format!("{} ({})", r.file_name, r.matches.len())))
+        .collect();
+    let list = List::new(items)
+        .block(Block::bordered().title(format!(" Документи: {} ", app.results.len())))
+        .highlight_style(Style::new().reversed())
+        .highlight_symbol("▶ ");
+    frame.render_stateful_widget(list, list_area, &mut app.list);
+
+    let (title, text) = match app.selected() {
+        Some(result) => {
+            let title = match app.match_positions.len() {
+                0 => format!(" {} ", result.file_path),
+                total => format!(" {} - збіг {}/{} ", result.file_path, app.current_match + 1, total),
+            };
+            (title, preview_text(&result.all_paragraphs, &app.match_positions, app.preview_top))
+        }
+        None => (" Перегляд ".to_string(), Text::default()),
+    };
+    let preview = Paragraph::new(text)
+        .block(Block::bordered().title(title))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(preview, preview_area);
+
+    let status = Text::from(vec![Line::from(app.status.as_str()), Line::from(HELP).dim()]);
+    frame.render_widget(Paragraph::new(status), status_area);
+}
+
+/// Абзаци документа від top; абзаци зі збігами виділені
+fn preview_text<'a>(paragraphs: &'a [DocumentParagraph], matches: &[usize], top: usize) -> Text<'a> {
+    let mut lines = Vec::new();
+    for (position, paragraph) in paragraphs.iter().enumerate().skip(top) {
+        let line = if matches.binary_search(&position).is_ok() {
+            Line::from(paragraph.text.as_str()).style(Style::new().yellow().add_modifier(Modifier::BOLD))
+        } else {
+            Line::from(paragraph.text.as_str())
+        };
+        lines.push(line);
+        lines.extend((0..paragraph.line_breaks_after).map(|_| Line::default()));
+    }
+    Text::from(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blazing_search::search_engine::SearchEngineMatch;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn result(file_name: &str, positions: &[usize]) -> SearchEngineResult {
+        SearchEngineResult {
+            doc_id: 0,
+            file_name: file_name.to_string(),
+            file_path: format!("2024/{}", file_name),
+            matches: positions
+                .iter()
+                .map(|&position| SearchEngineMatch { context: String::new(), position })
+                .collect(),
+            all_paragraphs: (0..40).map(|i| DocumentParagraph::new(format!("абзац {}", i))).collect(),
+            file_size: 0,
+            last_modified: 0,
+            category: None,
+            date: None,
+        }
+    }
+
+    #[test]
+    fn test_keys_edit_query_and_navigate_results() {
+        let mut app = App::default();
+        assert_eq!(app.handle_key(key(KeyCode::Enter)), Action::None);
+        for c in "наказз".chars() {
+            app.handle_key(key(KeyCode::Char(c)));
+        }
+        app.handle_key(key(KeyCode::Backspace));
+        assert_eq!(app.query, "наказ");
+        assert_eq!(app.handle_key(key(KeyCode::Enter)), Action::Search);
+
+        // Перемикання режиму повторює пошук
+        assert_eq!(app.handle_key(key(KeyCode::Tab)), Action::Search);
+        assert!(matches!(app.mode(), SearchMode::Remaining));
+
+        // Збіги показуються по порядку, з абзацом контексту зверху
+        app.show_results(vec![result("a.docx", &[30, 5, 5]), result("b.docx", &[0])], Duration::ZERO);
+        assert_eq!((app.match_positions.clone(), app.preview_top), (vec![5, 30], 4));
+        app.handle_key(key(KeyCode::Right));
+        app.handle_key(key(KeyCode::Right));
+        assert_eq!((app.current_match, app.preview_top), (1, 29));
+        app.handle_key(key(KeyCode::PageDown));
+        assert_eq!(app.preview_top, 39);
+
+        app.handle_key(key(KeyCode::Down));
+        app.handle_key(key(KeyCode::Down));
+        assert_eq!((app.list.selected(), app.preview_top), (Some(1), 0));
+
+        assert_eq!(app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)), Action::Quit);
+        assert_eq!(app.query, "наказ");
+    }
+}
@@ -0,0 +1,147 @@
+//! "Нове з минулого візиту": для кожного користувача запам'ятовується, до якого документа
+//! він уже бачив індекс (наступний doc_id на момент перегляду). doc_id призначаються
+//! по зростанню і не змінюються при заміні файлу, тож нові накази - це документи з
+//! doc_id не меншим за збережений. Стан - last_visits.json у папці індексів.
+
+use crate::document_record::{DocumentIndex, DocumentRecord};
+use crate::tr;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Що користувач бачив востаннє
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LastVisit {
+    /// Покоління індексу на момент перегляду (для довідки)
+    pub generation: Option<u64>,
+    /// next_doc_id індексу на момент перегляду: документи з меншим doc_id уже бачені
+    pub next_doc_id: u64,
+}
+
+impl LastVisit {
+    pub fn of(generation: Option<u64>, doc_index: &DocumentIndex) -> Self {
+        Self { generation, next_doc_id: doc_index.next_doc_id }
+    }
+}
+
+/// Останні візити всіх користувачів
+pub struct VisitTracker {
+    path: String,
+    users: Mutex<HashMap<String, LastVisit>>,
+}
+
+impl VisitTracker {
+    /// Завантажує збережений стан; пошкоджений файл - попередження і порожній стан
+    pub fn load(path: String) -> Self {
+        let users = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("{}", tr!(VisitsLoadFailed, path, e));
+                HashMap::new()
+            }),
+            Err(e) if Path::new(&path).exists() => {
+                warn!("{}", tr!(VisitsLoadFailed, path, e));
+                HashMap::new()
+            }
+            Err(_) => HashMap::new(),
+        };
+        Self { path, users: Mutex::new(users) }
+    }
+
+    /// Останній візит користувача. Першого візиту ще немає - поточний стан стає точкою відліку
+    /// (інакше новим виявився б увесь архів); так само після перебудови індексу з нуля,
+    /// коли doc_id почались спочатку.
+    pub fn last_visit(&self, user: &str, current: LastVisit) -> LastVisit {
+        let mut users = self.lock();
+        match users.get(user) {
+            Some(visit) if visit.next_doc_id <= current.next_doc_id => *visit,
+            _ => {
+                users.insert(user.to_string(), current);
+                self.save(&users);
+                current
+            }
+        }
+    }
+
+    /// Позначає все в поточному індексі як переглянуте
+    pub fn mark_seen(&self, user: &str, current: LastVisit) {
+        let mut users = self.lock();
+        if users.insert(user.to_string(), current) != Some(current) {
+            self.save(&users);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, LastVisit>> {
+        match self.users.lock() {
+            Ok(users) => users,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Записує через тимчасовий файл, щоб збій не пошкодив стан
+    fn save(&self, users: &HashMap<String, LastVisit>) {
+        let temp_path = format!("{}.tmp", self.path);
+        let result = serde_json::to_string(users)
+            .map_err(std::io::Error::from)
+            .and_then(|json| fs::write(&temp_path, json))
+            .and_then(|()| fs::rename(&temp_path, &self.path));
+        if let Err(e) = result {
+            warn!("{}", tr!(VisitsSaveFailed, self.path, e));
+        }
+    }
+}
+
+/// Документи, додані після візиту, найновіші першими
+pub fn new_documents<'a>(doc_index: &'a DocumentIndex, since: &LastVisit) -> Vec<&'a DocumentRecord> {
+    let mut documents: Vec<&DocumentRecord> = doc_index
+        .documents
+        .iter()
+        .filter(|d| !d.deleted && d.doc_id >= since.next_doc_id)
+        .collect();
+    documents.sort_by_key(|d| std::cmp::Reverse(d.doc_id));
+    documents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_builder::TempIndexDir;
+
+    fn document(path: &str) -> DocumentRecord {
+        DocumentRecord::from_texts(path, std::iter::empty::<&str>())
+    }
+
+    #[test]
+    fn test_new_documents_since_last_visit() {
+        let dir = TempIndexDir::new("last_visits");
+        let path = dir.join("last_visits.json").to_string_lossy().to_string();
+
+        let mut doc_index = DocumentIndex::new();
+        doc_index.push_document(document("2024/наказ №1.docx"));
+        let tracker = VisitTracker::load(path.clone());
+
+        // Перший візит - точка відліку, нового немає
+        let visit = tracker.last_visit("оператор", LastVisit::of(Some(1), &doc_index));
+        assert!(new_documents(&doc_index, &visit).is_empty());
+
+        doc_index.push_document(document("2024/наказ №2.docx"));
+        let removed = doc_index.push_document(document("2024/наказ №3.docx"));
+        doc_index.push_document(document("2024/наказ №4.docx"));
+        doc_index.tombstone(removed);
+
+        let visit = VisitTracker::load(path.clone()).last_visit("оператор", LastVisit::of(Some(2), &doc_index));
+        let names: Vec<_> = new_documents(&doc_index, &visit).iter().map(|d| d.file_name.as_str()).collect();
+        assert_eq!(names, vec!["наказ №4.docx", "наказ №2.docx"]);
+
+        tracker.mark_seen("оператор", LastVisit::of(Some(2), &doc_index));
+        let visit = tracker.last_visit("оператор", LastVisit::of(Some(2), &doc_index));
+        assert!(new_documents(&doc_index, &visit).is_empty());
+
+        // Індекс перебудовано з нуля - точка відліку скидається
+        let rebuilt = DocumentIndex::new();
+        let visit = tracker.last_visit("оператор", LastVisit::of(Some(3), &rebuilt));
+        assert_eq!(visit.next_doc_id, rebuilt.next_doc_id);
+    }
+}
@@ -1,9 +1,57 @@
-use actix_web::{web, App, HttpServer, Result, HttpResponse, middleware::Logger};
+use actix_web::{web, App, HttpMessage, HttpRequest, HttpServer, Result, HttpResponse, middleware::{self, Logger, Next}};
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::http::header;
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::sync::{Arc, Mutex};
 use std::process::Command;
-use crate::search_engine::{SearchEngine, SearchMode};
-use crate::auto_indexer::AutoIndexer;
+use tokio::sync::watch;
+use blazing_search::annotations::{Annotation, AnnotationStore, MAX_ANNOTATION_LENGTH};
+use blazing_search::classifier;
+use blazing_search::config::{self, Config};
+use blazing_search::document_date::{self, document_date, DateSource};
+use blazing_search::document_diff::{self, DocumentDiff};
+use blazing_search::document_record::Paragraph;
+use blazing_search::docx_parser::parse_docx_with_structure;
+use blazing_search::highlight::Highlight;
+use blazing_search::person_names::{self, PersonName};
+use blazing_search::config_reload::{self, ConfigSource};
+use blazing_search::error::{IndexError, SearchError};
+use blazing_search::federated_search::{self, FederatedOutcome};
+use blazing_search::index_paths::IndexPaths;
+use blazing_search::inverted_index::{InvertedIndex, PREFIX_WILDCARD};
+use blazing_search::corpus_analytics::CorpusAnalytics;
+use blazing_search::index_stats::{IndexStats, TermReport};
+use blazing_search::logging;
+use blazing_search::messages::Msg;
+use blazing_search::near_duplicates::{self, DuplicateReport};
+use blazing_search::pdf_report;
+use blazing_search::permalink;
+use blazing_search::redaction::{RedactionPolicy, Redactor, UNREDACTED_COOKIE};
+use blazing_search::regex_query::RegexQuery;
+use crate::graphql_api::{self, ApiSchema, RequestRedactor};
+use blazing_search::result_export::{self, CsvDelimiter, EXPORT_COLUMNS};
+use blazing_search::shutdown::Shutdown;
+use blazing_search::slow_query_log::{self, SlowQuery};
+use blazing_search::tr;
+use crate::service;
+use blazing_search::integrity_monitor::{IntegrityMonitor, IntegrityStatus};
+use blazing_search::search_engine::{
+    SearchEngine, SearchEngineMatch, SearchEngineResult, SearchFilters, SearchMode, SearchScope, SortOrder,
+    MAX_CONTEXT_PARAGRAPHS,
+};
+use blazing_search::search_etag;
+use blazing_search::search_facets::SearchFacets;
+use blazing_search::search_history::{HistoryEntry, SearchHistory};
+use blazing_search::service_history::ServiceHistory;
+use blazing_search::session::{self, SESSION_COOKIE};
+use blazing_search::saved_queries::{NotificationPolicy, NotificationTarget, SavedQueryStore};
+use blazing_search::visit_tracker::{self, LastVisit, VisitTracker};
+use blazing_search::auto_indexer::AutoIndexer;
+use blazing_search::sync_report::{self, SyncReport};
 use std::net::UdpSocket;
 use walkdir::WalkDir;
 use rayon::prelude::*;
@@ -13,6 +61,95 @@ pub struct SearchRequest {
     pub query: String,
     pub full_search: Option<bool>,
     pub view_mode: Option<String>, // "fragments" або "full-document"
+    /// Лише накази цього виду (див. classification.categories)
+    pub category: Option<String>,
+    /// Менше документів, ніж search.max_results (більше не дозволяється)
+    pub max_results: Option<usize>,
+    /// Менше абзаців на документ, ніж search.max_paragraphs
+    pub max_paragraphs: Option<usize>,
+    /// Замість усіх абзаців документа - лише N абзаців до і після кожного збігу
+    /// (before/after у збігах, не більше MAX_CONTEXT_PARAGRAPHS)
+    pub context: Option<usize>,
+    /// Початки абзаців, які не вважаються збігами, замість search.paragraph_filters для
+    /// view_mode (порожній список - без фільтрів)
+    pub skip_prefixes: Option<Vec<String>>,
+    /// Лише документи цих форматів (["docx", "pdf"]); без поля або порожній - усі
+    #[serde(default)]
+    pub file_types: Vec<String>,
+    /// Лише документи з папки та її підпапок ("2024/НР/", шлях від папки наказів);
+    /// приймається і як path_prefix
+    #[serde(alias = "path_prefix")]
+    pub folder: Option<String>,
+    /// Точний пошук: слова як написані, без стемінгу
+    #[serde(default)]
+    pub exact: bool,
+    /// regex - query є регулярним виразом (без різниці в регістрі), що перевіряється в
+    /// кожному абзаці; без поля - слова й оператори
+    #[serde(default)]
+    pub mode: QueryMode,
+    /// Порядок результатів: date_desc (за замовчуванням), relevance, date_asc, name, size
+    #[serde(default)]
+    pub sort: SortOrder,
+    /// Лише накази, датовані з цього дня включно (ДД.ММ.РРРР або РРРР-ММ-ДД)
+    pub date_from: Option<String>,
+    /// Лише накази, датовані до цього дня включно
+    pub date_to: Option<String>,
+    /// Колекція (див. /api/collections); без неї - основна
+    pub collection: Option<String>,
+    /// Пошук у кількох колекціях одним запитом (порожній список - у всіх); має пріоритет
+    /// над collection, кожен результат позначається колекцією
+    pub collections: Option<Vec<String>>,
+}
+
+/// Як розуміти текст запиту до /api/search
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryMode {
+    #[default]
+    Words,
+    Regex,
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    pub query: String,
+    pub view_mode: Option<String>,
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub delimiter: CsvDelimiter,
+    pub category: Option<String>,
+    pub collection: Option<String>,
+    /// Кілька колекцій через кому (порожньо - усі)
+    pub collections: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PersonQuery {
+    /// Прізвище та ініціали (або ім'я повністю)
+    pub name: String,
+    /// Без формату - JSON
+    pub format: Option<ExportFormat>,
+    #[serde(default)]
+    pub delimiter: CsvDelimiter,
+    pub collection: Option<String>,
+    /// Кілька колекцій через кому (порожньо - усі)
+    pub collections: Option<String>,
+}
+
+/// Через скільки секунд повторити пошук, відхилений через чергу (Retry-After)
+const BUSY_RETRY_AFTER_SECS: u64 = 2;
+
+/// Назва PDF з послужним витягом
+const SERVICE_HISTORY_TITLE: &str = "Витяг з наказів щодо особи";
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Xlsx,
+    Pdf,
 }
 
 #[derive(Deserialize)]
@@ -24,7 +161,6 @@ pub struct OpenFileRequest {
 #[derive(Deserialize)]
 pub struct SearchFilesRequest {
     pub query: String,
-    pub folder_path: String,
 }
 
 #[derive(Serialize, Clone)]
@@ -53,6 +189,18 @@ pub struct SearchResponse {
     pub total_count: usize,
     pub query: String,
     pub processing_time_ms: u128,
+    /// Скільки знайдених документів кожного виду (до фільтра за категорією)
+    pub categories: BTreeMap<String, usize>,
+    /// Скільки знайдених документів у кожній папці року і підпапці (теж до фільтра за
+    /// категорією); ключі підходять для поля folder
+    pub facets: SearchFacets,
+    /// Скільки знайдених документів не ввійшло у відповідь (search.max_results)
+    pub omitted_results: usize,
+    /// Час на запит вичерпано (search.query_timeout_ms) - перевірено не всі документи
+    pub partial: bool,
+    /// Швидкий набір, решта чи весь індекс (див. search.quick_mode_months)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_scope: Option<SearchScope>,
 }
 
 #[derive(Serialize, Clone)]
@@ -60,10 +208,22 @@ pub struct ParagraphData {
     pub text: String,
     #[serde(default)]
     pub line_breaks_after: usize,
+    /// Абзац збігається з запитом (для перегляду "Повний документ")
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub matched: bool,
+    /// Слова з термінами запиту в абзаці, що збігся, - [початок, кінець) в одиницях UTF-16
+    /// (див. highlight)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<[usize; 2]>,
+    /// Примітки користувачів до абзацу (див. annotations)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
 }
 
 #[derive(Serialize, Clone)]
 pub struct SearchResult {
+    /// Стабільний ідентифікатор документа - для приміток до абзаців
+    pub doc_id: u64,
     pub file_name: String,
     pub file_path: String,
     pub full_path: String,
@@ -71,12 +231,226 @@ pub struct SearchResult {
     pub all_paragraphs: Vec<ParagraphData>,
     pub file_size: u64,
     pub last_modified: u64,
+    pub category: Option<String>,
+    /// Дата, за якою впорядковано документ (ДД.ММ.РРРР), і звідки вона взята
+    pub date: Option<String>,
+    pub date_source: Option<DateSource>,
+    /// Скільки абзаців документа не ввійшло у відповідь (search.max_paragraphs)
+    pub omitted_paragraphs: usize,
+    /// Колекція документа - у пошуку по кількох колекціях
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection: Option<String>,
 }
 
 #[derive(Serialize, Clone)]
 pub struct MatchInfo {
     pub context: String,
     pub position: usize,
+    /// Слова з термінами запиту в context - [початок, кінець) в одиницях UTF-16: ціле слово,
+    /// навіть якщо збігся лише стемінгований термін (див. highlight)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<[usize; 2]>,
+    /// Людина з абзацу, якщо запит схожий на ПІБ - за нею інтерфейс групує однофамільців
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person: Option<PersonName>,
+    /// Номер пункту збігу - для постійного посилання /doc/<doc_id>#p-<пункт>
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+    /// Абзаци перед збігом і після нього - лише в запиті з context
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<ParagraphData>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<ParagraphData>,
+}
+
+#[derive(Deserialize)]
+pub struct SyncHistoryQuery {
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct HistoryResponse {
+    pub enabled: bool,
+    pub user: String,
+    pub queries: Vec<HistoryEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct NewDocumentsQuery {
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct NewDocument {
+    pub file_name: String,
+    pub file_path: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct NewDocumentsResponse {
+    pub enabled: bool,
+    pub user: String,
+    /// Покоління індексу на момент останнього візиту
+    pub since_generation: Option<u64>,
+    pub generation: Option<u64>,
+    /// Скільки всього нових документів (documents - не більше limit найновіших)
+    pub count: usize,
+    pub documents: Vec<NewDocument>,
+}
+
+#[derive(Deserialize)]
+pub struct RoleRequest {
+    pub password: String,
+}
+
+#[derive(Serialize)]
+pub struct RoleResponse {
+    /// Чи маскуються чутливі дані
+    pub redaction: bool,
+    /// Чи можна отримати роль "unredacted" (задано пароль)
+    pub role_available: bool,
+    /// Чи має цей користувач роль "unredacted"
+    pub unredacted: bool,
+}
+
+#[derive(Deserialize)]
+pub struct FeedbackRequest {
+    /// Запит, за яким знайдено документ
+    pub query: String,
+    pub file_path: String,
+    /// true - 👍 доречний, false - 👎 недоречний
+    pub relevant: bool,
+}
+
+#[derive(Serialize)]
+pub struct FeedbackResponse {
+    /// Терміни запиту, для яких записано оцінку
+    pub terms: Vec<String>,
+    /// Поточний зсув документа для цього запиту (додатний - вгору)
+    pub boost: i64,
+}
+
+#[derive(Deserialize)]
+pub struct AnnotationRequest {
+    pub doc_id: u64,
+    /// Номер абзацу в документі (position з результатів пошуку)
+    pub position: usize,
+    pub text: String,
+    /// Підпис автора (без імені - IP-адреса)
+    pub user: Option<String>,
+    pub collection: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SavedQueryRequest {
+    pub query: String,
+    /// Назва для сповіщень (без неї - сам запит)
+    pub name: Option<String>,
+    pub targets: Vec<NotificationTarget>,
+}
+
+#[derive(Deserialize)]
+pub struct AnnotationsQuery {
+    pub doc_id: u64,
+    pub collection: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct DocumentQuery {
+    /// Пункт з якоря посилання (#p-2.3)
+    pub section: Option<String>,
+    pub collection: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DocumentResponse {
+    #[serde(flatten)]
+    pub document: SearchResult,
+    /// Абзац, з якого починається пункт section (None - пункт не знайдено)
+    pub target: Option<usize>,
+    pub permalink: String,
+}
+
+#[derive(Deserialize)]
+pub struct CollectionQuery {
+    pub collection: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct FeedbackSummaryQuery {
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct SlowQueriesQuery {
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct SlowQueriesResponse {
+    pub count: usize,
+    pub queries: Vec<SlowQuery>,
+}
+
+#[derive(Deserialize)]
+pub struct SuggestQuery {
+    /// Введений текст запиту; підказуються продовження останнього слова
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct SuggestResponse {
+    /// Основи слів індексу, що починаються з останнього слова запиту (з "*" в кінці
+    /// знаходять усі форми), спершу найпоширеніші
+    pub terms: Vec<TermSuggestion>,
+    /// Нещодавні запити користувача, що починаються з введеного тексту
+    pub queries: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct TermSuggestion {
+    pub term: String,
+    /// У скількох документах трапляється
+    pub documents: usize,
+}
+
+#[derive(Deserialize)]
+pub struct TermsQuery {
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub rarest: bool,
+}
+
+#[derive(Deserialize)]
+pub struct DiffQuery {
+    /// doc_id або шлях документа в кеші (file_path з результатів пошуку)
+    pub a: String,
+    pub b: String,
+}
+
+#[derive(Deserialize)]
+pub struct DuplicatesQuery {
+    pub max_distance: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct LogLevelRequest {
+    /// Рівень або специфікація з модулями, як у --log-level
+    pub level: String,
+}
+
+#[derive(Serialize)]
+pub struct LogLevelResponse {
+    pub level: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SyncHistoryResponse {
+    pub reports: Vec<SyncReport>,
+    pub count: usize,
 }
 
 #[derive(Serialize)]
@@ -87,6 +461,167 @@ pub struct ErrorResponse {
 pub struct AppState {
     pub search_engine: Arc<SearchEngine>,
     pub file_index_cache: Arc<Mutex<Vec<FileInfo>>>,
+    pub index_paths: IndexPaths,
+    pub integrity_status: Arc<Mutex<IntegrityStatus>>,
+    /// SHA-256 (hex) пароля для відкриття файлів
+    pub open_file_password_sha256: String,
+    /// None - історію пошуку вимкнено (history.enabled = false)
+    pub search_history: Option<Arc<SearchHistory>>,
+    /// Останні візити для "нове з минулого візиту" (вимикається разом з історією)
+    pub visit_tracker: Option<Arc<VisitTracker>>,
+    /// Маскування чутливих даних і роль "unredacted"
+    pub redaction: Arc<RedactionPolicy>,
+    /// Схема GraphQL API (див. graphql_api)
+    pub graphql_schema: ApiSchema,
+    /// Усі колекції сервера: спершу основна (той самий search_engine), далі [[collections]]
+    pub collections: Vec<Collection>,
+    /// Збережені запити зі сповіщеннями (основна колекція)
+    pub saved_queries: Arc<SavedQueryStore>,
+    /// Дозволені адреси сповіщень збережених запитів
+    pub notification_policy: NotificationPolicy,
+}
+
+pub struct Collection {
+    pub name: String,
+    pub search_engine: Arc<SearchEngine>,
+}
+
+#[derive(Serialize)]
+pub struct CollectionInfo {
+    pub name: String,
+    pub documents: usize,
+}
+
+impl AppState {
+    /// Колекції для пошуку: одна за collection або (collections задано) кілька, порожній
+    /// список - усі; пари (назва, движок)
+    fn search_targets(
+        &self,
+        collection: Option<&str>,
+        collections: Option<&[String]>,
+    ) -> std::result::Result<Vec<(String, Arc<SearchEngine>)>, ErrorResponse> {
+        let selected: Vec<&Collection> = match collections {
+            Some([]) => self.collections.iter().collect(),
+            Some(names) => names.iter().map(|name| self.find_collection(Some(name))).collect::<std::result::Result<_, _>>()?,
+            None => vec![self.find_collection(collection)?],
+        };
+        let mut targets: Vec<(String, Arc<SearchEngine>)> = Vec::new();
+        for collection in selected {
+            if !targets.iter().any(|(name, _)| *name == collection.name) {
+                targets.push((collection.name.clone(), collection.search_engine.clone()));
+            }
+        }
+        Ok(targets)
+    }
+
+    /// Колекція за назвою (без різниці в регістрі; None - основна); невідома назва -
+    /// помилка з переліком колекцій (відповідь 400)
+    fn find_collection(&self, name: Option<&str>) -> std::result::Result<&Collection, ErrorResponse> {
+        let name = name.map(str::trim).filter(|name| !name.is_empty());
+        let found = match name {
+            Some(name) => self.collections.iter().find(|c| c.name.to_lowercase() == name.to_lowercase()),
+            None => self.collections.first(),
+        };
+        match found {
+            Some(collection) => Ok(collection),
+            None => {
+                let name = name.unwrap_or_default();
+                let available: Vec<&str> = self.collections.iter().map(|c| c.name.as_str()).collect();
+                Err(ErrorResponse {
+                    error: tr!(UnknownCollection, name, available.join(", ")),
+                })
+            }
+        }
+    }
+}
+
+/// Підпис автора примітки: ім'я з інтерфейсу або IP-адреса клієнта. Лише підпис -
+/// власника (історія, збережені запити) визначає session_user
+fn author_name(req: &HttpRequest, name: Option<&str>) -> String {
+    let name = SearchHistory::user_key(name.unwrap_or_default());
+    if !name.is_empty() {
+        return name;
+    }
+    req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_default()
+}
+
+/// Користувач сеансу браузера (див. session): ключ історії пошуку і візитів. Без cookie
+/// сеансу - порожній рядок, і нічого не запам'ятовується
+fn session_user(req: &HttpRequest) -> String {
+    let token = req.extensions().get::<SessionToken>().map(|token| token.0.clone());
+    token
+        .or_else(|| req.cookie(SESSION_COOKIE).map(|c| c.value().to_string()))
+        .filter(|token| session::is_token(token))
+        .map(|token| session::user_key(&token))
+        .unwrap_or_default()
+}
+
+/// Скільки днів браузер зберігає cookie сеансу (з ним - історію пошуку)
+const SESSION_COOKIE_DAYS: i64 = 365;
+
+/// Токен сеансу поточного запиту (ставить session_middleware)
+#[derive(Clone)]
+struct SessionToken(String);
+
+/// Видає браузеру без cookie сеансу новий токен. Ідентичність користувача визначає сервер,
+/// а не параметр запиту: чужу історію не прочитати, підставивши ім'я
+async fn session_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>> {
+    let existing = req.cookie(SESSION_COOKIE).map(|c| c.value().to_string()).filter(|t| session::is_token(t));
+    let issued = existing.is_none();
+    let token = existing.unwrap_or_else(session::new_token);
+    req.extensions_mut().insert(SessionToken(token.clone()));
+
+    let mut response = next.call(req).await?;
+    if issued {
+        let cookie = Cookie::build(SESSION_COOKIE, token)
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Strict)
+            .max_age(actix_web::cookie::time::Duration::days(SESSION_COOKIE_DAYS))
+            .finish();
+        response.response_mut().add_cookie(&cookie)?;
+    }
+    Ok(response)
+}
+
+/// Хеш пароля адміністратора (server.admin_password_sha256); None - адміністративні запити вимкнено
+pub struct AdminCredential(pub Option<String>);
+
+/// Пропускає до /api/admin/* лише з паролем адміністратора в заголовку
+/// "Authorization: Bearer <пароль>": словник індексу, журнали і рівень журналу -
+/// не для кожного в мережі. Без server.admin_password_sha256 розділ вимкнено
+async fn admin_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>> {
+    let expected = req.app_data::<web::Data<AdminCredential>>().and_then(|admin| admin.0.clone());
+    let password = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        // Пароль може бути не ASCII (кирилиця) - заголовок читається як UTF-8
+        .and_then(|value| std::str::from_utf8(value.as_bytes()).ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+
+    let denied = match (expected, password) {
+        (None, _) => HttpResponse::Forbidden().json(ErrorResponse { error: tr!(AdminDisabled) }),
+        (Some(expected), Some(password)) if config::password_matches(&password, &expected) => {
+            return Ok(next.call(req).await?.map_into_left_body());
+        }
+        (Some(_), _) => HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .json(ErrorResponse { error: tr!(AdminAuthRequired) }),
+    };
+    Ok(req.into_response(denied).map_into_right_body())
+}
+
+/// Маскування для запиту: None - маскування вимкнено або користувач має роль "unredacted"
+fn request_redactor(req: &HttpRequest, data: &AppState) -> Option<Arc<Redactor>> {
+    let cookie = req.cookie(UNREDACTED_COOKIE);
+    data.redaction.for_token(cookie.as_ref().map(|c| c.value()))
 }
 
 // Функція для отримання локальної IP-адреси
@@ -104,11 +639,11 @@ fn build_file_index(folder_path: &str) -> Vec<FileInfo> {
 
     let path = std::path::Path::new(folder_path);
     if !path.exists() || !path.is_dir() {
-        println!("⚠️  Папка не знайдена: {}", folder_path);
+        warn!("{}", tr!(FolderNotFound, folder_path));
         return Vec::new();
     }
 
-    println!("🔍 Побудова індексу файлів у: {}", folder_path);
+    info!("{}", tr!(FileIndexBuilding, folder_path));
 
     // Паралельно збираємо всі файли
     let files: Vec<FileInfo> = WalkDir::new(path)
@@ -126,20 +661,45 @@ fn build_file_index(folder_path: &str) -> Vec<FileInfo> {
         })
         .collect();
 
-    println!("✅ Індекс побудовано: {} файлів", files.len());
+    info!("{}", tr!(FileIndexBuilt, files.len()));
     files
 }
 
+/// Відповідь на помилку пошуку
+fn search_error_response(err: SearchError) -> HttpResponse {
+    let body = ErrorResponse {
+        error: tr!(SearchFailed, err),
+    };
+    // Індекс ще не створено або його оновлює інший процес - тимчасова недоступність
+    match err {
+        SearchError::Index(IndexError::NotFound(_) | IndexError::Locked(_)) => {
+            HttpResponse::ServiceUnavailable().json(body)
+        }
+        SearchError::Busy(_) => HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", BUSY_RETRY_AFTER_SECS.to_string()))
+            .json(body),
+        _ => HttpResponse::InternalServerError().json(body),
+    }
+}
+
+/// Межа проміжку дат із запиту (порожня - без межі)
+fn request_date(value: &Option<String>) -> std::result::Result<Option<(u32, u32, u32)>, String> {
+    match value.as_deref().map(str::trim).filter(|value| !value.is_empty()) {
+        Some(value) => document_date::parse_date(value).map(Some).ok_or_else(|| tr!(InvalidDate, value)),
+        None => Ok(None),
+    }
+}
+
 pub async fn search_handler(
+    req: HttpRequest,
     data: web::Data<AppState>,
     query: web::Json<SearchRequest>,
 ) -> Result<HttpResponse> {
     let start_time = std::time::Instant::now();
 
-
     if query.query.trim().is_empty() {
         return Ok(HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Порожній запит пошуку".to_string(),
+            error: tr!(EmptyQuery),
         }));
     }
 
@@ -149,35 +709,142 @@ pub async fn search_handler(
         SearchMode::Quick
     };
 
-    let results = match data.search_engine.search(&query.query, search_mode, query.view_mode.as_deref()).await {
-        Ok(all_results) => all_results,
-        Err(err) => {
-            return Ok(HttpResponse::InternalServerError().json(ErrorResponse {
-                error: format!("Помилка пошуку: {}", err),
-            }));
-        }
+    let targets = match data.search_targets(query.collection.as_deref(), query.collections.as_deref()) {
+        Ok(targets) => targets,
+        Err(error) => return Ok(HttpResponse::BadRequest().json(error)),
+    };
+    let federated = query.collections.is_some();
+    let target_names: Vec<&str> = targets.iter().map(|(name, _)| name.as_str()).collect();
+
+    let (date_from, date_to) = match (request_date(&query.date_from), request_date(&query.date_to)) {
+        (Ok(date_from), Ok(date_to)) => (date_from, date_to),
+        (Err(error), _) | (_, Err(error)) => return Ok(HttpResponse::BadRequest().json(ErrorResponse { error })),
+    };
+    let regex = match query.mode {
+        QueryMode::Words => None,
+        QueryMode::Regex => match RegexQuery::new(&query.query) {
+            Ok(regex) => Some(regex),
+            Err(err) => return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: tr!(InvalidPattern, err) })),
+        },
     };
+    let filters = SearchFilters::default()
+        .with_skip_prefixes(query.skip_prefixes.clone())
+        .with_file_types(query.file_types.clone())
+        .with_folder(query.folder.as_deref())
+        .with_date_range(date_from, date_to)
+        .with_exact(query.exact)
+        .with_regex(regex)
+        .with_sort(query.sort);
+
+    // Той самий запит між оновленнями індексу - 304 без повторного пошуку
+    let redactor = request_redactor(&req, &data);
+    let version: Option<Vec<String>> = targets.iter().map(|(_, engine)| engine.results_version()).collect();
+    let etag = version.map(|version| {
+        search_etag::search_etag(&version.join("|"), &query.query, &[
+            ("collections", target_names.join("|")),
+            ("federated", federated.to_string()),
+            ("full_search", query.full_search.unwrap_or(false).to_string()),
+            ("view_mode", query.view_mode.clone().unwrap_or_default()),
+            ("category", query.category.clone().unwrap_or_default()),
+            ("max_results", format!("{:?}", query.max_results)),
+            ("max_paragraphs", format!("{:?}", query.max_paragraphs)),
+            ("context", format!("{:?}", query.context)),
+            ("skip_prefixes", format!("{:?}", query.skip_prefixes)),
+            ("file_types", format!("{:?}", filters.file_types)),
+            ("folder", filters.folder.clone().unwrap_or_default()),
+            ("date_from", format!("{:?}", filters.date_from)),
+            ("date_to", format!("{:?}", filters.date_to)),
+            ("exact", query.exact.to_string()),
+            ("mode", format!("{:?}", query.mode)),
+            ("sort", format!("{:?}", query.sort)),
+            ("redacted", redactor.is_some().to_string()),
+        ])
+    });
+    let not_modified = etag.as_deref().is_some_and(|etag| {
+        req.headers()
+            .get(actix_web::http::header::IF_NONE_MATCH)
+            .and_then(|header| header.to_str().ok())
+            .is_some_and(|header| search_etag::if_none_match(header, etag))
+    });
+    if not_modified {
+        record_search_history(&req, &data, &query).await?;
+        return Ok(HttpResponse::NotModified()
+            .insert_header(("ETag", etag.unwrap_or_default()))
+            .insert_header(("Cache-Control", "private, no-cache"))
+            .finish());
+    }
+
+    let search_engine = targets[0].1.clone();
+    let outcome = if federated {
+        federated_search::search(&targets, &query.query, search_mode, query.view_mode.as_deref(), &filters).await
+    } else {
+        search_engine.search_outcome_filtered(&query.query, search_mode, query.view_mode.as_deref(), &filters).await.map(|outcome| {
+            FederatedOutcome {
+                results: outcome.results,
+                partial: outcome.partial,
+                scope: outcome.scope,
+                facets: outcome.facets,
+                ..Default::default()
+            }
+        })
+    };
+    let FederatedOutcome { mut results, collections: result_collections, partial, scope: search_scope, facets } = match outcome {
+        Ok(outcome) => outcome,
+        Err(err) => return Ok(search_error_response(err)),
+    };
+    let categories = classifier::category_counts(&results);
+    classifier::filter_by_category(&mut results, query.category.as_deref());
+    let limits = search_engine.result_limits().with_request(query.max_results, query.max_paragraphs);
+    let omitted_results = limits.truncate_results(&mut results);
+    let context = query.context.map(|context| context.min(MAX_CONTEXT_PARAGRAPHS));
+    let omitted_paragraphs: Vec<usize> = results
+        .iter_mut()
+        .map(|r| match context {
+            Some(context) => {
+                limits.truncate_matches(r);
+                r.context_omitted(context)
+            }
+            None => limits.truncate_paragraphs(r),
+        })
+        .collect();
+    // Примітки шукаються за незамаскованим текстом абзацу
+    let annotations: Vec<ResultAnnotations> = results
+        .iter()
+        .map(|r| {
+            let collection = result_collections.get(&r.file_path);
+            let engine = targets
+                .iter()
+                .find(|(name, _)| Some(name) == collection)
+                .map_or(&search_engine, |(_, engine)| engine);
+            ResultAnnotations::find(engine.annotations(), r, redactor.as_deref())
+        })
+        .collect();
+    if let Some(redactor) = &redactor {
+        redactor.redact_results(&mut results);
+    }
+    record_search_history(&req, &data, &query).await?;
 
-    let total_doc_count = data.search_engine.get_stats().0;
+    let total_doc_count: usize = targets.iter().map(|(_, engine)| engine.get_stats().0).sum();
     let processing_time = start_time.elapsed().as_millis();
+    // Межі слів для підсвічування - лише в абзацах, що збіглися: інтерфейс перемикає
+    // витяг і повний документ без повторного пошуку
+    let terms = search_engine.query_plan(&query.query, query.exact).highlight_terms();
+    let highlight = match &filters.regex {
+        Some(regex) => Highlight::Pattern(regex.regex()),
+        None => Highlight::Terms(&terms),
+    };
+    // Ім'я шукається вже в замаскованому тексті - приховане ПІБ не групується
+    let person_query = person_names::looks_like_person_query(&query.query);
 
-    let search_results: Vec<SearchResult> = results.into_iter().map(|r| {
-        SearchResult {
-            file_name: r.file_name,
-            file_path: r.file_path.clone(),
-            full_path: r.file_path,
-            matches: r.matches.into_iter().map(|m| MatchInfo {
-                context: m.context,
-                position: m.position,
-            }).collect(),
-            all_paragraphs: r.all_paragraphs.into_iter().map(|p| ParagraphData {
-                text: p.text,
-                line_breaks_after: p.line_breaks_after,
-            }).collect(),
-            file_size: r.file_size,
-            last_modified: r.last_modified,
-        }
-    }).collect();
+    let search_results: Vec<SearchResult> = results
+        .into_iter()
+        .zip(omitted_paragraphs)
+        .zip(annotations)
+        .map(|((r, omitted_paragraphs), annotations)| {
+            let collection = result_collections.get(&r.file_path).cloned();
+            search_result(r, omitted_paragraphs, annotations, collection, highlight, person_query, context)
+        })
+        .collect();
 
     let response = SearchResponse {
         count: search_results.len(),
@@ -185,11 +852,278 @@ pub async fn search_handler(
         results: search_results,
         query: query.query.clone(),
         processing_time_ms: processing_time,
+        categories,
+        facets,
+        omitted_results,
+        partial,
+        search_scope,
     };
 
-    Ok(HttpResponse::Ok().json(response))
+    // Перерваний пошук наступного разу може встигнути більше - такий не кешується
+    match etag.filter(|_| !partial) {
+        Some(etag) => Ok(HttpResponse::Ok()
+            .insert_header(("ETag", etag))
+            .insert_header(("Cache-Control", "private, no-cache"))
+            .json(response)),
+        None => Ok(HttpResponse::Ok().json(response)),
+    }
+}
+
+/// Результат пошуку для відповіді API: межі слів для підсвічування - у збігах і лише в
+/// абзацах, що збіглися; людина з абзацу - лише для запиту, схожого на ПІБ. З context замість усіх
+/// абзаців документа кожен збіг несе свої сусідні абзаци
+fn search_result(
+    r: SearchEngineResult,
+    omitted_paragraphs: usize,
+    annotations: ResultAnnotations,
+    collection: Option<String>,
+    highlight: Highlight,
+    person_query: bool,
+    context: Option<usize>,
+) -> SearchResult {
+    let matched: HashSet<usize> = r.matches.iter().map(|m| m.position).collect();
+    let ResultAnnotations { matches: mut match_annotations, paragraphs: mut paragraph_annotations } = annotations;
+    let paragraph_data = |position: usize, p: Paragraph, annotations: Vec<Annotation>| {
+        let matched = matched.contains(&position);
+        ParagraphData {
+            highlights: if matched { highlight.ranges(&p.text) } else { Vec::new() },
+            text: p.text,
+            line_breaks_after: p.line_breaks_after,
+            matched,
+            annotations,
+        }
+    };
+    let windows: Vec<_> = match context {
+        Some(context) => r.matches.iter().map(|m| r.context_window(m.position, context)).collect(),
+        None => Vec::new(),
+    };
+    let neighbours = |range: &std::ops::Range<usize>| -> Vec<ParagraphData> {
+        range
+            .clone()
+            .map(|position| paragraph_data(position, r.all_paragraphs[position].clone(), paragraph_annotations[position].clone()))
+            .collect()
+    };
+    let matches = r.matches.into_iter().enumerate().map(|(i, m)| MatchInfo {
+        person: if person_query { person_names::resolve(&m.context, highlight.terms()) } else { None },
+        section: permalink::section_at(&r.all_paragraphs, m.position),
+        highlights: highlight.ranges(&m.context),
+        context: m.context,
+        position: m.position,
+        annotations: std::mem::take(&mut match_annotations[i]),
+        before: windows.get(i).map(|(before, _)| neighbours(before)).unwrap_or_default(),
+        after: windows.get(i).map(|(_, after)| neighbours(after)).unwrap_or_default(),
+    }).collect();
+    let all_paragraphs = match context {
+        Some(_) => Vec::new(),
+        None => r.all_paragraphs.into_iter().enumerate().map(|(position, p)| {
+            paragraph_data(position, p, std::mem::take(&mut paragraph_annotations[position]))
+        }).collect(),
+    };
+    SearchResult {
+        doc_id: r.doc_id,
+        file_name: r.file_name,
+        file_path: r.file_path.clone(),
+        full_path: r.file_path,
+        matches,
+        all_paragraphs,
+        file_size: r.file_size,
+        last_modified: r.last_modified,
+        category: r.category,
+        date: r.date.map(|date| date.formatted()),
+        date_source: r.date.map(|date| date.source),
+        omitted_paragraphs,
+        collection,
+    }
 }
 
+/// Примітки результату: до кожного збігу і до кожного абзацу документа
+struct ResultAnnotations {
+    matches: Vec<Vec<Annotation>>,
+    paragraphs: Vec<Vec<Annotation>>,
+}
+
+impl ResultAnnotations {
+    /// Примітки до абзаців результату (текст примітки маскується так само, як документ)
+    fn find(store: &AnnotationStore, result: &SearchEngineResult, redactor: Option<&Redactor>) -> Self {
+        let mut found = Self {
+            matches: store.for_paragraphs(result.doc_id, result.matches.iter().map(|m| m.context.as_str())),
+            paragraphs: store.for_paragraphs(result.doc_id, result.all_paragraphs.iter().map(|p| p.text.as_str())),
+        };
+        if let Some(redactor) = redactor {
+            found.redact(redactor);
+        }
+        found
+    }
+
+    fn redact(&mut self, redactor: &Redactor) {
+        for annotation in self.matches.iter_mut().chain(self.paragraphs.iter_mut()).flatten() {
+            annotation.text = redactor.redact(&annotation.text).into_owned();
+        }
+    }
+}
+
+/// Повний пошук - продовження швидкого з тим самим запитом, в історію пишемо лише швидкий
+async fn record_search_history(req: &HttpRequest, data: &AppState, query: &SearchRequest) -> Result<()> {
+    if let (Some(history), false) = (&data.search_history, query.full_search.unwrap_or(false)) {
+        let history = history.clone();
+        let user = session_user(req);
+        let search_query = query.query.clone();
+        web::block(move || history.record(&user, &search_query))
+            .await
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+    }
+    Ok(())
+}
+
+/// Вивантаження результатів пошуку у файл: GET /api/export?query=...&format=csv|xlsx|pdf
+pub async fn export_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<ExportQuery>,
+) -> Result<HttpResponse> {
+    if query.query.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: tr!(EmptyQuery),
+        }));
+    }
+
+    let collections = query.collections.as_deref().map(collection_list);
+    let targets = match data.search_targets(query.collection.as_deref(), collections.as_deref()) {
+        Ok(targets) => targets,
+        Err(error) => return Ok(HttpResponse::BadRequest().json(error)),
+    };
+
+    // Вивантажується все знайдене, а не лише найновіші документи
+    let mut results = match search_full(&targets, &query.query, query.view_mode.as_deref()).await {
+        Ok(results) => results,
+        Err(err) => return Ok(search_error_response(err)),
+    };
+    classifier::filter_by_category(&mut results, query.category.as_deref());
+    if let Some(redactor) = request_redactor(&req, &data) {
+        redactor.redact_results(&mut results);
+    }
+    let rows = result_export::export_rows(&results);
+    info!("{}", tr!(ExportPrepared, rows.len(), query.query));
+
+    rows_file_response(rows, query.format, query.delimiter, pdf_report::SEARCH_REPORT_TITLE, &query.query, "search-results").await
+}
+
+/// Назви колекцій з параметра запиту через кому
+fn collection_list(names: &str) -> Vec<String> {
+    names.split(',').map(str::trim).filter(|name| !name.is_empty()).map(str::to_string).collect()
+}
+
+/// Увесь архів колекцій для вивантажень: одна колекція - її пошук, кілька - злиті результати
+async fn search_full(
+    targets: &[(String, Arc<SearchEngine>)],
+    query: &str,
+    view_mode: Option<&str>,
+) -> std::result::Result<Vec<SearchEngineResult>, SearchError> {
+    match targets {
+        [(_, search_engine)] => search_engine.search(query, SearchMode::Full, view_mode).await,
+        targets => federated_search::search(targets, query, SearchMode::Full, view_mode, &SearchFilters::default()).await.map(|outcome| outcome.results),
+    }
+}
+
+/// Рядки вивантаження у вибраному форматі; file_stem - назва файлу без розширення
+async fn rows_file_response(
+    rows: Vec<result_export::ExportRow>,
+    format: ExportFormat,
+    delimiter: CsvDelimiter,
+    report_title: &'static str,
+    query_text: &str,
+    file_stem: &str,
+) -> Result<HttpResponse> {
+    match format {
+        ExportFormat::Csv => {
+            let header = format!("{}{}", result_export::CSV_BOM, result_export::csv_line(&EXPORT_COLUMNS, delimiter));
+            // Рядки формуються по одному під час відправлення
+            let lines = std::iter::once(header)
+                .chain(rows.into_iter().map(move |row| result_export::csv_line(&row.fields(), delimiter)))
+                .map(|line| Ok::<_, actix_web::Error>(web::Bytes::from(line)));
+
+            Ok(HttpResponse::Ok()
+                .content_type("text/csv; charset=utf-8")
+                .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.csv\"", file_stem)))
+                .streaming(futures_util::stream::iter(lines)))
+        }
+        ExportFormat::Xlsx => {
+            // Книга складається цілком у пам'яті - поза потоками обробки запитів
+            match web::block(move || result_export::xlsx_workbook(&rows)).await {
+                Ok(Ok(workbook)) => Ok(HttpResponse::Ok()
+                    .content_type("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+                    .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.xlsx\"", file_stem)))
+                    .body(workbook)),
+                Ok(Err(e)) => Ok(export_failed(e)),
+                Err(e) => Ok(export_failed(e)),
+            }
+        }
+        ExportFormat::Pdf => {
+            let query_text = query_text.to_string();
+            let generated_at = chrono::Local::now().naive_local();
+            match web::block(move || pdf_report::render_titled_pdf(report_title, &query_text, generated_at, &rows)).await {
+                // Звіт відкривається в переглядачі браузера - звідти його друкують
+                Ok(Ok(report)) => Ok(HttpResponse::Ok()
+                    .content_type("application/pdf")
+                    .insert_header(("Content-Disposition", format!("inline; filename=\"{}.pdf\"", file_stem)))
+                    .body(report)),
+                Ok(Err(e)) => Ok(export_failed(e)),
+                Err(e) => Ok(export_failed(e)),
+            }
+        }
+    }
+}
+
+/// Послужний витяг: GET /api/person?name=...[&format=csv|xlsx|pdf] - усі абзаци з ім'ям
+/// за всі роки, від найстаршого наказу до найновішого; без format - JSON
+pub async fn person_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<PersonQuery>,
+) -> Result<HttpResponse> {
+    if query.name.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: tr!(EmptyQuery),
+        }));
+    }
+
+    let collections = query.collections.as_deref().map(collection_list);
+    let targets = match data.search_targets(query.collection.as_deref(), collections.as_deref()) {
+        Ok(targets) => targets,
+        Err(error) => return Ok(HttpResponse::BadRequest().json(error)),
+    };
+
+    let mut results = match search_full(&targets, &query.name, Some("fragments")).await {
+        Ok(results) => results,
+        Err(err) => return Ok(search_error_response(err)),
+    };
+    if let Some(redactor) = request_redactor(&req, &data) {
+        redactor.redact_results(&mut results);
+    }
+    let history = ServiceHistory::from_results(&query.name, &results);
+    info!("{}", tr!(ServiceHistoryPrepared, history.person, history.orders.len(), history.paragraphs));
+
+    match query.format {
+        Some(format) => {
+            rows_file_response(history.rows(), format, query.delimiter, SERVICE_HISTORY_TITLE, &history.person, "service-history").await
+        }
+        None => Ok(HttpResponse::Ok().json(history)),
+    }
+}
+
+fn export_failed(error: impl std::fmt::Display) -> HttpResponse {
+    warn!("{}", tr!(ExportFailed, error));
+    HttpResponse::InternalServerError().json(ErrorResponse {
+        error: tr!(ExportFailed, error),
+    })
+}
+
+/// Файли веб-інтерфейсу (папка web/) вбудовані в бінарник: сервер не залежить від
+/// робочої папки, а для розгортання достатньо одного виконуваного файлу
+#[derive(rust_embed::RustEmbed)]
+#[folder = "web/"]
+struct WebAssets;
+
 pub async fn index_handler() -> Result<HttpResponse> {
     Ok(HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
@@ -200,41 +1134,38 @@ pub async fn index_handler() -> Result<HttpResponse> {
 }
 
 pub async fn static_handler(req: actix_web::HttpRequest) -> Result<HttpResponse> {
-    let path: std::path::PathBuf = req.match_info()
-        .query("filename")
-        .parse()
-        .map_err(|_| actix_web::error::ErrorBadRequest("Invalid file path"))?;
-    let file_path = std::path::Path::new("./web").join(path);
+    let file_name = req.match_info().query("filename");
 
-    match std::fs::read(&file_path) {
-        Ok(content) => {
-            let content_type = mime_guess::from_path(&file_path).first_or_octet_stream().to_string();
+    match WebAssets::get(file_name) {
+        Some(file) => {
+            let content_type = mime_guess::from_path(file_name).first_or_octet_stream().to_string();
             Ok(HttpResponse::Ok()
                 .content_type(content_type)
                 .insert_header(("Cache-Control", "no-cache, no-store, must-revalidate"))
                 .insert_header(("Pragma", "no-cache"))
                 .insert_header(("Expires", "0"))
-                .body(content))
+                .body(file.data.into_owned()))
         },
-        Err(_) => Ok(HttpResponse::NotFound().body("File not found"))
+        None => Ok(HttpResponse::NotFound().body(Msg::FileNotFound.text()))
     }
 }
 
 pub async fn open_file_handler(
+    data: web::Data<AppState>,
     request: web::Json<OpenFileRequest>,
 ) -> Result<HttpResponse> {
-    // Перевіряємо пароль
-    const CORRECT_PASSWORD: &str = "4053@115";
-    if request.password != CORRECT_PASSWORD {
+    // Перевіряємо пароль (у конфігурації зберігається лише його SHA-256)
+    let password_sha256 = config::password_sha256(&request.password);
+    if !password_sha256.eq_ignore_ascii_case(&data.open_file_password_sha256) {
         return Ok(HttpResponse::Unauthorized().json(ErrorResponse {
-            error: "Неправильний пароль".to_string(),
+            error: tr!(WrongPassword),
         }));
     }
 
     // Перевіряємо чи файл існує
     if !std::path::Path::new(&request.file_path).exists() {
         return Ok(HttpResponse::NotFound().json(ErrorResponse {
-            error: "Файл не знайдено".to_string(),
+            error: tr!(FileNotFound),
         }));
     }
 
@@ -242,7 +1173,7 @@ pub async fn open_file_handler(
     let result = if cfg!(target_os = "windows") {
         // Для Windows використовуємо cmd /c start
         Command::new("cmd")
-            .args(&["/c", "start", "", &request.file_path])
+            .args(["/c", "start", "", &request.file_path])
             .spawn()
     } else if cfg!(target_os = "macos") {
         // Для macOS використовуємо open
@@ -260,12 +1191,12 @@ pub async fn open_file_handler(
         Ok(_) => {
             Ok(HttpResponse::Ok().json(serde_json::json!({
                 "success": true,
-                "message": "Файл відкрито"
+                "message": Msg::FileOpened.text()
             })))
         }
         Err(e) => {
             Ok(HttpResponse::InternalServerError().json(ErrorResponse {
-                error: format!("Помилка відкриття файлу: {}", e),
+                error: tr!(FileOpenFailed, e),
             }))
         }
     }
@@ -285,6 +1216,8 @@ pub async fn get_file_index_handler(
 
 // Handler для отримання вмісту файлу для превью
 pub async fn get_file_preview_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
     path: web::Path<String>,
 ) -> Result<HttpResponse> {
     let file_path = path.into_inner();
@@ -298,7 +1231,7 @@ pub async fn get_file_preview_handler(
     let path = std::path::Path::new(&decoded_path);
     if !path.exists() || !path.is_file() {
         return Ok(HttpResponse::NotFound().json(ErrorResponse {
-            error: "Файл не знайдено".to_string(),
+            error: tr!(FileNotFound),
         }));
     }
 
@@ -308,6 +1241,11 @@ pub async fn get_file_preview_handler(
         .unwrap_or("")
         .to_lowercase();
 
+    // Без ролі "unredacted" файл як є не віддається
+    if let Some(redactor) = request_redactor(&req, &data) {
+        return Ok(redacted_preview(&decoded_path, &ext, &redactor));
+    }
+
     // Обробка документів (конвертація в PDF)
     if ext == "doc" || ext == "docx" {
         return convert_doc_to_pdf(&decoded_path).await;
@@ -332,12 +1270,34 @@ pub async fn get_file_preview_handler(
         }
         Err(_) => {
             Ok(HttpResponse::InternalServerError().json(ErrorResponse {
-                error: "Помилка читання файлу".to_string(),
+                error: tr!(FileReadFailed),
             }))
         }
     }
 }
 
+/// Перегляд файлу для користувача без ролі "unredacted": текст DOCX віддається з маскуванням,
+/// а зображення, PDF і DOC замаскувати не можна, тож вони не віддаються зовсім
+fn redacted_preview(path: &str, ext: &str, redactor: &Redactor) -> HttpResponse {
+    if ext != "docx" {
+        return HttpResponse::Forbidden().json(ErrorResponse {
+            error: tr!(PreviewRequiresUnredactedRole),
+        });
+    }
+    match parse_docx_with_structure(path) {
+        Ok(paragraphs) => {
+            let text: String = paragraphs
+                .iter()
+                .map(|p| format!("{}\n{}", redactor.redact(&p.text), "\n".repeat(p.line_breaks_after)))
+                .collect();
+            HttpResponse::Ok().content_type("text/plain; charset=utf-8").body(text)
+        }
+        Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: tr!(FileReadFailed),
+        }),
+    }
+}
+
 // Функція для конвертації .doc/.docx у PDF
 async fn convert_doc_to_pdf(file_path: &str) -> Result<HttpResponse> {
     use std::process::Command;
@@ -359,25 +1319,14 @@ async fn convert_doc_to_pdf(file_path: &str) -> Result<HttpResponse> {
 
     // Спробуємо кожен можливий шлях
     for libreoffice_path in possible_paths {
-        let cmd_result = if cfg!(target_os = "windows") {
-            Command::new(libreoffice_path)
-                .args(&[
-                    "--headless",
-                    "--convert-to", "pdf",
-                    "--outdir", temp_dir.to_str().unwrap_or("."),
-                    file_path
-                ])
-                .output()
-        } else {
-            Command::new(libreoffice_path)
-                .args(&[
-                    "--headless",
-                    "--convert-to", "pdf",
-                    "--outdir", temp_dir.to_str().unwrap_or("."),
-                    file_path
-                ])
-                .output()
-        };
+        let cmd_result = Command::new(libreoffice_path)
+            .args([
+                "--headless",
+                "--convert-to", "pdf",
+                "--outdir", temp_dir.to_str().unwrap_or("."),
+                file_path
+            ])
+            .output();
 
         if let Ok(output) = cmd_result {
             if output.status.success() {
@@ -388,28 +1337,28 @@ async fn convert_doc_to_pdf(file_path: &str) -> Result<HttpResponse> {
                         Ok(content) => {
                             // Видаляємо тимчасовий файл після читання
                             let _ = std::fs::remove_file(&expected_pdf);
-                            println!("✅ Документ успішно конвертовано: {}", file_path);
+                            info!("{}", tr!(DocumentConverted, file_path));
                             return Ok(HttpResponse::Ok()
                                 .content_type("application/pdf")
                                 .body(content));
                         }
                         Err(_) => {
-                            println!("⚠️  Помилка читання конвертованого PDF");
+                            warn!("{}", tr!(ConvertedPdfReadFailed));
                         }
                     }
                 }
             } else {
                 let error_msg = String::from_utf8_lossy(&output.stderr);
-                println!("⚠️  Помилка конвертації: {}", error_msg);
+                warn!("{}", tr!(ConversionFailed, error_msg));
             }
         }
     }
 
-    println!("⚠️  LibreOffice не знайдено у жодному зі стандартних місць");
+    warn!("{}", tr!(LibreOfficeNotFound));
 
     // Якщо конвертація не вдалася, повертаємо помилку
     Ok(HttpResponse::InternalServerError().json(ErrorResponse {
-        error: "Не вдалося конвертувати документ у PDF. Переконайтеся, що LibreOffice встановлено.".to_string(),
+        error: tr!(PdfConversionUnavailable),
     }))
 }
 
@@ -421,7 +1370,7 @@ pub async fn search_files_handler(
 
     if request.query.trim().is_empty() {
         return Ok(HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Порожній запит пошуку".to_string(),
+            error: tr!(EmptyQuery),
         }));
     }
 
@@ -451,66 +1400,1074 @@ pub async fn search_files_handler(
     Ok(HttpResponse::Ok().json(response))
 }
 
-pub async fn start_web_server(search_engine: SearchEngine) -> std::io::Result<()> {
+// Handler для адміністративного перегляду історії синхронізацій (від нових до старих)
+pub async fn sync_history_handler(
+    data: web::Data<AppState>,
+    query: web::Query<SyncHistoryQuery>,
+) -> Result<HttpResponse> {
+    const DEFAULT_LIMIT: usize = 50;
+
+    let mut reports = sync_report::load_history(&data.index_paths.sync_history());
+    reports.reverse();
+    reports.truncate(query.limit.unwrap_or(DEFAULT_LIMIT));
+
+    Ok(HttpResponse::Ok().json(SyncHistoryResponse {
+        count: reports.len(),
+        reports,
+    }))
+}
+
+// Handler статистики індексу: документи за роками і місяцями, прогалини в архіві.
+// Рахується з покоління, завантаженого для пошуку, без читання файлів індексу
+pub async fn index_stats_handler(data: web::Data<AppState>) -> Result<HttpResponse> {
+    let search_engine = data.search_engine.clone();
+    let stats = web::block(move || {
+        search_engine.with_indices(|generation, doc_index, inv_index| {
+            IndexStats::from_indices(generation, doc_index, inv_index)
+        })
+    })
+    .await
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+// Handler аналітики корпусу для адмінської панелі: ріст архіву за місяцями, розподіл
+// обсягу документів, частка документів з проблемами розбору (з покоління в пам'яті)
+pub async fn corpus_analytics_handler(data: web::Data<AppState>) -> Result<HttpResponse> {
+    let search_engine = data.search_engine.clone();
+    let analytics = web::block(move || {
+        search_engine.with_indices(|generation, doc_index, _| CorpusAnalytics::from_documents(generation, doc_index))
+    })
+    .await
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(analytics))
+}
+
+// Handler історії пошуку користувача: GET - останні запити, DELETE - очистити
+pub async fn history_handler(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let user = session_user(&req);
+    let Some(history) = data.search_history.clone() else {
+        return Ok(HttpResponse::Ok().json(HistoryResponse { enabled: false, user, queries: Vec::new() }));
+    };
+
+    let queries = if req.method() == actix_web::http::Method::DELETE {
+        let user = user.clone();
+        web::block(move || history.clear(&user))
+            .await
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+        Vec::new()
+    } else {
+        history.recent(&user)
+    };
+
+    Ok(HttpResponse::Ok().json(HistoryResponse { enabled: true, user, queries }))
+}
+
+// Handler підказок під час введення запиту: слова індексу і нещодавні запити користувача
+// з тим самим початком
+pub async fn suggest_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<SuggestQuery>,
+) -> Result<HttpResponse> {
+    const DEFAULT_LIMIT: usize = 10;
+    const MAX_LIMIT: usize = 50;
+    // Коротший початок підходить до надто багатьох слів
+    const MIN_PREFIX_CHARS: usize = 2;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let typed = query.q.trim_start().to_lowercase();
+    // Останнє слово - так, як його розбирає пошук: без мінуса, "*" і апострофів
+    let prefix = typed
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches('-')
+        .trim_end_matches(PREFIX_WILDCARD)
+        .replace('\'', "");
+
+    let terms = if prefix.chars().count() >= MIN_PREFIX_CHARS {
+        let redactor = request_redactor(&req, &data);
+        data.search_engine.with_indices(|_, _, inv_index| {
+            inv_index
+                .map(|inv_index| term_suggestions(inv_index, &prefix, limit, redactor.as_deref()))
+                .unwrap_or_default()
+        })
+    } else {
+        Vec::new()
+    };
+
+    let queries = match (&data.search_history, typed.trim_end().is_empty()) {
+        (Some(history), false) => history
+            .recent(&session_user(&req))
+            .into_iter()
+            .map(|entry| entry.query)
+            .filter(|recent| recent.to_lowercase().starts_with(typed.trim_end()))
+            .take(limit)
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(HttpResponse::Ok().json(SuggestResponse { terms, queries }))
+}
+
+/// Слова індексу з початком prefix. Слова, які маскуються (ІПН, номери телефонів), не
+/// підказуються: інакше підказка відкрила б те, що маскування ховає у відповідях
+fn term_suggestions(
+    inv_index: &InvertedIndex,
+    prefix: &str,
+    limit: usize,
+    redactor: Option<&Redactor>,
+) -> Vec<TermSuggestion> {
+    // Із запасом: частина слів може відпасти через маскування
+    let candidates = if redactor.is_some() { limit.saturating_mul(4) } else { limit };
+    inv_index
+        .suggest_words(prefix, candidates)
+        .into_iter()
+        .filter(|(term, _)| !redactor.is_some_and(|redactor| redactor.matches(term)))
+        .take(limit)
+        .map(|(term, documents)| TermSuggestion { term: term.clone(), documents })
+        .collect()
+}
+
+// Handler "нове з минулого візиту": документи, додані після останнього перегляду.
+// GET лише показує список, POST .../seen позначає поточний індекс переглянутим
+pub async fn new_documents_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<NewDocumentsQuery>,
+) -> Result<HttpResponse> {
+    const DEFAULT_LIMIT: usize = 50;
+
+    let user = session_user(&req);
+    let Some(tracker) = data.visit_tracker.clone() else {
+        return Ok(HttpResponse::Ok().json(NewDocumentsResponse { user, ..NewDocumentsResponse::default() }));
+    };
+
+    let search_engine = data.search_engine.clone();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    let response = web::block(move || {
+        search_engine.with_indices(|generation, doc_index, _| {
+            let visit = tracker.last_visit(&user, LastVisit::of(generation, doc_index));
+            let documents = visit_tracker::new_documents(doc_index, &visit);
+            NewDocumentsResponse {
+                enabled: true,
+                since_generation: visit.generation,
+                generation,
+                count: documents.len(),
+                documents: documents
+                    .into_iter()
+                    .take(limit)
+                    .map(|d| NewDocument { file_name: d.file_name.clone(), file_path: d.file_path.clone() })
+                    .collect(),
+                user,
+            }
+        })
+    })
+    .await
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+pub async fn mark_documents_seen_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let user = session_user(&req);
+    if let Some(tracker) = data.visit_tracker.clone() {
+        let search_engine = data.search_engine.clone();
+        web::block(move || {
+            search_engine.with_indices(|generation, doc_index, _| {
+                tracker.mark_seen(&user, LastVisit::of(generation, doc_index))
+            })
+        })
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn role_response(req: &HttpRequest, data: &AppState, unredacted: Option<bool>) -> RoleResponse {
+    let redaction = data.redaction.enabled();
+    RoleResponse {
+        redaction,
+        role_available: data.redaction.role_available(),
+        unredacted: redaction && unredacted.unwrap_or_else(|| request_redactor(req, data).is_none()),
+    }
+}
+
+// Handler ролі користувача: GET - чи маскуються дані для цього користувача
+pub async fn role_handler(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(role_response(&req, &data, None)))
+}
+
+// Handler ролі "unredacted": POST з паролем ставить cookie, DELETE - знімає роль
+pub async fn unredacted_role_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    request: Option<web::Json<RoleRequest>>,
+) -> Result<HttpResponse> {
+    if req.method() == actix_web::http::Method::DELETE {
+        if let Some(cookie) = req.cookie(UNREDACTED_COOKIE) {
+            data.redaction.revoke(cookie.value());
+        }
+        let mut cookie = Cookie::build(UNREDACTED_COOKIE, "").path("/").finish();
+        cookie.make_removal();
+        return Ok(HttpResponse::Ok().cookie(cookie).json(role_response(&req, &data, Some(false))));
+    }
+
+    if !data.redaction.role_available() {
+        return Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: tr!(UnredactedRoleUnavailable),
+        }));
+    }
+    let password = request.map(|r| r.into_inner().password).unwrap_or_default();
+    let Some(token) = data.redaction.unlock(&password) else {
+        warn!("{}", tr!(UnredactedRoleDenied, req.peer_addr().map(|a| a.ip().to_string()).unwrap_or_default()));
+        return Ok(HttpResponse::Unauthorized().json(ErrorResponse {
+            error: tr!(WrongPassword),
+        }));
+    };
+
+    // Токен діє UNREDACTED_ROLE_TTL і до закриття браузера - що настане раніше
+    let cookie = Cookie::build(UNREDACTED_COOKIE, token)
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .finish();
+    Ok(HttpResponse::Ok().cookie(cookie).json(role_response(&req, &data, Some(true))))
+}
+
+// Handler GraphQL: запит {query, variables} з маскуванням, як у REST
+pub async fn graphql_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    request: web::Json<async_graphql::Request>,
+) -> Result<HttpResponse> {
+    let mut request = request.into_inner();
+    if let Some(redactor) = request_redactor(&req, &data) {
+        request = request.data(RequestRedactor(redactor));
+    }
+    Ok(HttpResponse::Ok().json(data.graphql_schema.execute(request).await))
+}
+
+// Handler схеми GraphQL (SDL) для клієнтів і генераторів коду
+pub async fn graphql_schema_handler(data: web::Data<AppState>) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body(data.graphql_schema.sdl()))
+}
+
+// Handler порівняння двох документів за пунктами: GET /api/diff?a=...&b=...
+pub async fn diff_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<DiffQuery>,
+) -> Result<HttpResponse> {
+    let search_engine = data.search_engine.clone();
+    let DiffQuery { a, b } = query.into_inner();
+    let diff = web::block(move || {
+        search_engine.with_indices(|_, doc_index, _| {
+            let document_a = document_diff::find_document(doc_index, &a).ok_or(a)?;
+            let document_b = document_diff::find_document(doc_index, &b).ok_or(b)?;
+            Ok::<_, String>(DocumentDiff::compare(document_a, document_b))
+        })
+    })
+    .await
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    match diff {
+        Ok(mut diff) => {
+            if let Some(redactor) = request_redactor(&req, &data) {
+                diff.map_texts(|text| redactor.redact(text).into_owned());
+            }
+            Ok(HttpResponse::Ok().json(diff))
+        }
+        Err(missing) => Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: tr!(DocumentNotInIndex, missing),
+        })),
+    }
+}
+
+// Handler документа за постійним посиланням: GET /api/documents/{doc_id}?section=2.3
+pub async fn document_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    path: web::Path<u64>,
+    query: web::Query<DocumentQuery>,
+) -> Result<HttpResponse> {
+    let doc_id = path.into_inner();
+    let query = query.into_inner();
+    let collection = match data.find_collection(query.collection.as_deref()) {
+        Ok(collection) => collection,
+        Err(error) => return Ok(HttpResponse::BadRequest().json(error)),
+    };
+    // Основна колекція в посиланні не вказується
+    let collection_name = query.collection.as_ref().map(|_| collection.name.clone());
+    let search_engine = collection.search_engine.clone();
+    let section = query.section.clone();
+
+    let found = web::block(move || {
+        search_engine.with_indices(|_, doc_index, _| {
+            let slot = doc_index.slot_of(doc_id).filter(|&slot| !doc_index.documents[slot].deleted)?;
+            let document = &doc_index.documents[slot];
+            let paragraphs = document.get_paragraphs();
+            let target = section.as_deref().and_then(|section| permalink::resolve_section(&paragraphs, section));
+            // Пункт з посилання показується як збіг: інтерфейс виділяє його і прокручує до нього
+            let matches = target
+                .map(|position| SearchEngineMatch { context: paragraphs[position].text.clone(), position })
+                .into_iter()
+                .collect();
+            let mut result = SearchEngineResult {
+                doc_id,
+                file_name: document.file_name.clone(),
+                file_path: document.file_path.clone(),
+                matches,
+                all_paragraphs: paragraphs,
+                file_size: document.file_size,
+                last_modified: document.last_modified,
+                category: document.category.clone(),
+                date: document_date(document),
+            };
+            let omitted = search_engine.result_limits().truncate_paragraphs(&mut result);
+            let annotations = ResultAnnotations::find(search_engine.annotations(), &result, None);
+            Some((result, omitted, annotations, target))
+        })
+    })
+    .await
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    let Some((result, omitted, mut annotations, target)) = found else {
+        return Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: tr!(DocumentNotInIndex, doc_id),
+        }));
+    };
+    let mut results = vec![result];
+    if let Some(redactor) = request_redactor(&req, &data) {
+        redactor.redact_results(&mut results);
+        annotations.redact(&redactor);
+    }
+    let section = target.and(query.section.as_deref()).map(|s| s.trim().trim_end_matches('.'));
+    let permalink = permalink::permalink(doc_id, section, collection_name.as_deref());
+    let document = search_result(results.remove(0), omitted, annotations, collection_name, Highlight::Terms(&[]), false, None);
+    Ok(HttpResponse::Ok().json(DocumentResponse { document, target, permalink }))
+}
+
+// Handler приміток документа: GET /api/annotations?doc_id=...&collection=...
+pub async fn annotations_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<AnnotationsQuery>,
+) -> Result<HttpResponse> {
+    let collection = match data.find_collection(query.collection.as_deref()) {
+        Ok(collection) => collection,
+        Err(error) => return Ok(HttpResponse::BadRequest().json(error)),
+    };
+    let mut annotations = collection.search_engine.annotations().for_document(query.doc_id);
+    if let Some(redactor) = request_redactor(&req, &data) {
+        for annotation in &mut annotations {
+            annotation.text = redactor.redact(&annotation.text).into_owned();
+        }
+    }
+    Ok(HttpResponse::Ok().json(annotations))
+}
+
+// Handler додавання примітки: POST /api/annotations {doc_id, position, text, user}
+pub async fn add_annotation_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    request: web::Json<AnnotationRequest>,
+) -> Result<HttpResponse> {
+    let request = request.into_inner();
+    let text = request.text.trim().to_string();
+    if text.is_empty() || text.chars().count() > MAX_ANNOTATION_LENGTH {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: tr!(EmptyAnnotation, MAX_ANNOTATION_LENGTH),
+        }));
+    }
+    let search_engine = match data.find_collection(request.collection.as_deref()) {
+        Ok(collection) => collection.search_engine.clone(),
+        Err(error) => return Ok(HttpResponse::BadRequest().json(error)),
+    };
+    let author = author_name(&req, request.user.as_deref());
+
+    let added = web::block(move || {
+        // Текст абзацу береться з індексу, а не від клієнта: замаскований текст дав би інший хеш
+        let paragraph = search_engine.with_indices(|_, doc_index, _| {
+            let slot = doc_index.slot_of(request.doc_id).filter(|&slot| !doc_index.documents[slot].deleted);
+            slot.map(|slot| doc_index.documents[slot].get_paragraphs().into_iter().nth(request.position))
+        });
+        match paragraph {
+            Some(Some(paragraph)) => {
+                Ok(search_engine.annotations().add(request.doc_id, &paragraph.text, &text, &author))
+            }
+            Some(None) => Err(tr!(ParagraphNotFound, request.position, request.doc_id)),
+            None => Err(tr!(DocumentNotInIndex, request.doc_id)),
+        }
+    })
+    .await
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    match added {
+        Ok(mut annotation) => {
+            if let Some(redactor) = request_redactor(&req, &data) {
+                annotation.text = redactor.redact(&annotation.text).into_owned();
+            }
+            Ok(HttpResponse::Created().json(annotation))
+        }
+        Err(error) => Ok(HttpResponse::NotFound().json(ErrorResponse { error })),
+    }
+}
+
+// Handler видалення примітки: DELETE /api/annotations/{id}?collection=...
+pub async fn delete_annotation_handler(
+    data: web::Data<AppState>,
+    path: web::Path<u64>,
+    query: web::Query<CollectionQuery>,
+) -> Result<HttpResponse> {
+    let id = path.into_inner();
+    let search_engine = match data.find_collection(query.collection.as_deref()) {
+        Ok(collection) => collection.search_engine.clone(),
+        Err(error) => return Ok(HttpResponse::BadRequest().json(error)),
+    };
+    let removed = web::block(move || search_engine.annotations().remove(id))
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if removed {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: tr!(AnnotationNotFound, id),
+        }))
+    }
+}
+
+// Handler збережених запитів сеансу: GET /api/saved-queries
+pub async fn saved_queries_handler(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let owner = session_user(&req);
+    Ok(HttpResponse::Ok().json(data.saved_queries.for_owner(&owner)))
+}
+
+// Handler збереження запиту: POST /api/saved-queries {query, name, targets}
+pub async fn add_saved_query_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    request: web::Json<SavedQueryRequest>,
+) -> Result<HttpResponse> {
+    let request = request.into_inner();
+    let invalid = if request.query.trim().is_empty() {
+        Some(tr!(EmptySavedQuery))
+    } else if request.targets.is_empty() {
+        Some(tr!(NoNotificationTargets))
+    } else {
+        request
+            .targets
+            .iter()
+            .find_map(|target| data.notification_policy.validate(target).err())
+            .map(|e| e.to_string())
+    };
+    if let Some(error) = invalid {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse { error }));
+    }
+
+    let owner = session_user(&req);
+    if owner.is_empty() {
+        return Ok(HttpResponse::Unauthorized().json(ErrorResponse { error: tr!(SessionRequired) }));
+    }
+    let saved_queries = data.saved_queries.clone();
+    let saved_query = web::block(move || {
+        saved_queries.add(request.name.as_deref(), &request.query, &owner, request.targets)
+    })
+    .await
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created().json(saved_query))
+}
+
+// Handler видалення збереженого запиту сеансу: DELETE /api/saved-queries/{id}
+pub async fn delete_saved_query_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    path: web::Path<u64>,
+) -> Result<HttpResponse> {
+    let id = path.into_inner();
+    let owner = session_user(&req);
+    let saved_queries = data.saved_queries.clone();
+    let removed = web::block(move || saved_queries.remove(id, &owner))
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if removed {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: tr!(SavedQueryNotFound, id),
+        }))
+    }
+}
+
+// Handler оцінки результату: POST /api/feedback {query, file_path, relevant}
+pub async fn feedback_handler(
+    data: web::Data<AppState>,
+    request: web::Json<FeedbackRequest>,
+) -> Result<HttpResponse> {
+    let request = request.into_inner();
+    let terms = data.search_engine.query_terms(&request.query);
+    if terms.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: tr!(EmptyQuery),
+        }));
+    }
+
+    let search_engine = data.search_engine.clone();
+    let response = web::block(move || {
+        let indexed = search_engine.with_indices(|_, doc_index, _| {
+            doc_index.documents.iter().any(|d| !d.deleted && d.file_path == request.file_path)
+        });
+        if !indexed {
+            return Err(request.file_path);
+        }
+        let feedback = search_engine.feedback();
+        feedback.record(&terms, &request.file_path, request.relevant);
+        let boost = feedback.boost(&terms, &request.file_path);
+        Ok(FeedbackResponse { terms, boost })
+    })
+    .await
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    match response {
+        Ok(response) => Ok(HttpResponse::Ok().json(response)),
+        Err(file_path) => Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: tr!(DocumentNotInIndex, file_path),
+        })),
+    }
+}
+
+// Handler зведення оцінок релевантності: суми і пари з найбільшою кількістю оцінок
+pub async fn feedback_summary_handler(
+    data: web::Data<AppState>,
+    query: web::Query<FeedbackSummaryQuery>,
+) -> Result<HttpResponse> {
+    const DEFAULT_LIMIT: usize = 100;
+
+    let search_engine = data.search_engine.clone();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    let summary = web::block(move || search_engine.feedback().summary(limit))
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+// Handler журналу повільних запитів (найновіші першими)
+pub async fn slow_queries_handler(
+    data: web::Data<AppState>,
+    query: web::Query<SlowQueriesQuery>,
+) -> Result<HttpResponse> {
+    const DEFAULT_LIMIT: usize = 100;
+
+    let path = data.index_paths.slow_query_log();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    let queries = web::block(move || slow_query_log::load_recent(&path, limit))
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(SlowQueriesResponse {
+        count: queries.len(),
+        queries,
+    }))
+}
+
+// Handler словника індексу: найчастіші (або rarest=true - найрідкісніші) терміни.
+// Під маскуванням терміни, що маскуються (ІПН, телефони), не показуються, як і в підказках
+pub async fn terms_handler(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<TermsQuery>,
+) -> Result<HttpResponse> {
+    const DEFAULT_LIMIT: usize = 100;
+
+    let search_engine = data.search_engine.clone();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    let rarest = query.rarest;
+    let redactor = request_redactor(&req, &data);
+    let report = web::block(move || {
+        search_engine.with_indices(|_, _, inv_index| {
+            inv_index
+                .map(|inv_index| {
+                    TermReport::from_inverted_where(inv_index, limit, rarest, |term| {
+                        !redactor.as_ref().is_some_and(|redactor| redactor.matches(term))
+                    })
+                })
+                .unwrap_or_default()
+        })
+    })
+    .await
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+// Handler груп майже однакових документів (з покоління в пам'яті пошуку)
+pub async fn duplicates_handler(
+    data: web::Data<AppState>,
+    query: web::Query<DuplicatesQuery>,
+) -> Result<HttpResponse> {
+    let search_engine = data.search_engine.clone();
+    let max_distance = query.max_distance.unwrap_or(near_duplicates::DEFAULT_MAX_DISTANCE);
+    let report = web::block(move || {
+        search_engine.with_indices(|_, doc_index, _| DuplicateReport::from_documents(doc_index, max_distance))
+    })
+    .await
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+// Handler поточного рівня журналу
+pub async fn get_log_level_handler() -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(LogLevelResponse {
+        level: logging::current_level(),
+    }))
+}
+
+// Handler зміни рівня журналу без перезапуску (доступ - паролем адміністратора, див. admin_scope).
+// Наприклад "debug" вмикає детальний вивід індексації по кожному файлу
+pub async fn set_log_level_handler(request: web::Json<LogLevelRequest>) -> Result<HttpResponse> {
+    let level = request.level.trim();
+    if let Err(e) = logging::set_level(level) {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: tr!(InvalidLogLevel, level, e),
+        }));
+    }
+
+    warn!("{}", tr!(LogLevelChanged, level));
+    Ok(HttpResponse::Ok().json(LogLevelResponse {
+        level: logging::current_level(),
+    }))
+}
+
+// Handler переліку колекцій для вибору в інтерфейсі (спершу основна)
+pub async fn collections_handler(data: web::Data<AppState>) -> Result<HttpResponse> {
+    let collections: Vec<CollectionInfo> = data
+        .collections
+        .iter()
+        .map(|collection| CollectionInfo {
+            name: collection.name.clone(),
+            documents: collection.search_engine.get_stats().0,
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(collections))
+}
+
+// Handler стану здоров'я: результат останньої фонової самоперевірки індексів.
+// 503, якщо самоперевірка виявила проблеми (для моніторингу та балансувальників)
+pub async fn health_handler(data: web::Data<AppState>) -> Result<HttpResponse> {
+    let status = data.integrity_status.lock()
+        .map(|status| status.clone())
+        .unwrap_or_default();
+    let (documents, _) = data.search_engine.get_stats();
+
+    let body = serde_json::json!({
+        "status": if status.healthy { "ok" } else { "degraded" },
+        "documents": documents,
+        "integrity": status,
+    });
+
+    if status.healthy {
+        Ok(HttpResponse::Ok().json(body))
+    } else {
+        Ok(HttpResponse::ServiceUnavailable().json(body))
+    }
+}
+
+pub async fn start_web_server(
+    search_engine: SearchEngine,
+    extra_collections: Vec<(Config, SearchEngine)>,
+    config: Config,
+    shutdown: Shutdown,
+    config_source: Option<ConfigSource>,
+) -> std::io::Result<()> {
     let search_engine_arc = Arc::new(search_engine);
+    let index_paths = config.index_paths();
+    let extra_collections: Vec<(Config, Arc<SearchEngine>)> =
+        extra_collections.into_iter().map(|(config, engine)| (config, Arc::new(engine))).collect();
+    let collections: Vec<Collection> = std::iter::once(Collection {
+        name: config.paths.collection_name.clone(),
+        search_engine: search_engine_arc.clone(),
+    })
+    .chain(extra_collections.iter().map(|(config, engine)| Collection {
+        name: config.paths.collection_name.clone(),
+        search_engine: engine.clone(),
+    }))
+    .collect();
+
+    // Перезавантаження конфігурації: безпечні зміни розходяться фоновим задачам через канал
+    let (config_updates, _) = watch::channel(config.clone());
+    if let Some(source) = config_source {
+        config_reload::watch_config_file(source, config_updates.clone());
+    }
+    let engines_for_updates: Vec<Arc<SearchEngine>> = collections.iter().map(|c| c.search_engine.clone()).collect();
+    let mut search_updates = config_updates.subscribe();
+    tokio::spawn(async move {
+        while search_updates.changed().await.is_ok() {
+            let config = search_updates.borrow_and_update().clone();
+            for search_engine in &engines_for_updates {
+                config.apply_search_settings(search_engine);
+            }
+        }
+    });
 
     // Побудова індексу файлів при старті
-    const DEFAULT_FOLDER_PATH: &str = "/mnt/salem-documents/ФОТО ВК";
-    let file_index = build_file_index(DEFAULT_FOLDER_PATH);
+    let photo_folder = config.paths.photo_folder.clone();
+    let file_index = build_file_index(&photo_folder);
     let file_index_cache = Arc::new(Mutex::new(file_index));
 
+    let integrity_status = Arc::new(Mutex::new(IntegrityStatus::default()));
+
+    let saved_queries = Arc::new(SavedQueryStore::load(index_paths.saved_queries()));
+
+    let redaction = Arc::new(config.redaction_policy());
+    if redaction.enabled() {
+        info!("{}", tr!(RedactionEnabled, config.redaction.patterns.len()));
+    }
+
     let app_state = web::Data::new(AppState {
         search_engine: search_engine_arc.clone(),
         file_index_cache: file_index_cache.clone(),
+        index_paths: index_paths.clone(),
+        integrity_status: integrity_status.clone(),
+        open_file_password_sha256: config.server.open_file_password_sha256.clone(),
+        search_history: config.history.enabled.then(|| {
+            Arc::new(SearchHistory::load(index_paths.search_history(), config.history.max_entries))
+        }),
+        visit_tracker: config.history.enabled.then(|| Arc::new(VisitTracker::load(index_paths.last_visits()))),
+        redaction: redaction.clone(),
+        graphql_schema: graphql_api::build_schema(search_engine_arc.clone()),
+        collections,
+        saved_queries: saved_queries.clone(),
+        notification_policy: NotificationPolicy::from_config(&config.alerts),
     });
 
     // Запускаємо автоматичний індексер
-    println!("🚀 Запуск автоматичного індексера (перевірка кожні 3 хвилини)...");
-    let auto_indexer = AutoIndexer::new(search_engine_arc);
-    auto_indexer.start_background_indexing().await;
+    info!("{}", tr!(AutoIndexerStarting, config.indexing.sync_interval_secs));
+    let auto_indexer = AutoIndexer::new(search_engine_arc.clone(), &config)
+        .with_saved_queries(saved_queries)
+        .with_shutdown(shutdown.clone())
+        .with_config_updates(config_updates.subscribe());
+    let indexer_task = auto_indexer.start_background_indexing().await;
+    // Кожна додаткова колекція синхронізується й індексується своїм індексером
+    let mut collection_indexer_tasks = Vec::new();
+    for (collection_config, collection_engine) in &extra_collections {
+        let indexer = AutoIndexer::new(collection_engine.clone(), collection_config)
+            .with_shutdown(shutdown.clone())
+            .with_config_updates(config_updates.subscribe());
+        collection_indexer_tasks.push(indexer.start_background_indexing().await);
+    }
 
-    // Запускаємо автоматичне оновлення індексу файлів кожні 3 хвилини
-    println!("🚀 Запуск оновлення індексу файлів (кожні 3 хвилини)...");
+    // Бот Telegram (якщо задано токен) зупиняється разом із сервером
+    crate::telegram_bot::start(search_engine_arc.clone(), &config.telegram, redaction.redactor(), shutdown.clone());
+    crate::grpc_service::start(search_engine_arc.clone(), config.server.grpc_port, redaction.redactor(), shutdown.clone());
+
+    // Запускаємо фонову самоперевірку цілісності індексів
+    info!("{}", tr!(IntegrityMonitorStarting, config.integrity.interval_secs));
+    let mut integrity_monitor =
+        IntegrityMonitor::new(&index_paths.documents_index(), search_engine_arc, integrity_status)
+            .with_config_updates(config_updates.subscribe());
+    integrity_monitor.interval = tokio::time::Duration::from_secs(config.integrity.interval_secs);
+    integrity_monitor.start_delay = tokio::time::Duration::from_secs(config.integrity.start_delay_secs);
+    integrity_monitor.sample_size = config.integrity.sample_size;
+    integrity_monitor.alert_threshold = config.integrity.alert_threshold;
+    if integrity_monitor.webhook_url.is_none() {
+        integrity_monitor.webhook_url = config.integrity.alert_webhook.clone();
+    }
+    integrity_monitor.start();
+
+    // Запускаємо автоматичне оновлення індексу файлів
+    info!("{}", tr!(FileIndexRefreshStarting, config.indexing.file_index_refresh_secs));
     let file_index_cache_clone = file_index_cache.clone();
+    let file_index_updates = config_updates.subscribe();
     tokio::spawn(async move {
         loop {
-            tokio::time::sleep(tokio::time::Duration::from_secs(180)).await; // 3 хвилини
+            // Інтервал читається щоразу - перезавантажена конфігурація діє з наступного оновлення
+            let file_index_refresh = file_index_updates.borrow().indexing.file_index_refresh_secs;
+            tokio::time::sleep(tokio::time::Duration::from_secs(file_index_refresh)).await;
 
-            println!("🔄 Оновлення індексу файлів...");
-            let updated_index = build_file_index(DEFAULT_FOLDER_PATH);
+            info!("{}", tr!(FileIndexRefreshing));
+            let updated_index = build_file_index(&photo_folder);
 
             // Оновлюємо кеш
             if let Ok(mut cache) = file_index_cache_clone.lock() {
                 *cache = updated_index;
-                println!("✅ Індекс файлів оновлено");
+                info!("{}", tr!(FileIndexRefreshed));
             }
         }
     });
 
-    println!("Запуск веб-сервера на http://0.0.0.0:8080");
+    let port = config.server.port;
+    info!("{}", tr!(ServerStarting, port));
 
     // Виводимо актуальну локальну IP-адресу
     if let Some(local_ip) = get_local_ip() {
-        println!("Доступ з локальної мережі: http://{}:8080", local_ip);
+        info!("{}", tr!(ServerLanAccess, local_ip, port));
     } else {
-        println!("⚠️  Не вдалося визначити локальну IP-адресу");
-        println!("💡 Використовуйте localhost або перевірте ipconfig");
+        warn!("{}", tr!(LocalIpUnknown));
+        info!("{}", tr!(LocalIpHint));
     }
 
-    HttpServer::new(move || {
+    let admin_credential = web::Data::new(AdminCredential(config.server.admin_password_sha256.clone()));
+
+    let server = HttpServer::new(move || {
         App::new()
             .app_data(app_state.clone())
+            .app_data(admin_credential.clone())
+            .wrap(middleware::from_fn(session_middleware))
             .wrap(Logger::default())
             .route("/", web::get().to(index_handler))
+            .route("/doc/{doc_id}", web::get().to(index_handler))
             .route("/api/search", web::post().to(search_handler))
+            .route("/api/export", web::get().to(export_handler))
+            .route("/api/person", web::get().to(person_handler))
+            .route("/api/history", web::get().to(history_handler))
+            .route("/api/suggest", web::get().to(suggest_handler))
+            .route("/api/history", web::delete().to(history_handler))
+            .route("/api/new-documents", web::get().to(new_documents_handler))
+            .route("/api/new-documents/seen", web::post().to(mark_documents_seen_handler))
+            .route("/api/feedback", web::post().to(feedback_handler))
+            .route("/api/documents/{doc_id}", web::get().to(document_handler))
+            .route("/api/annotations", web::get().to(annotations_handler))
+            .route("/api/annotations", web::post().to(add_annotation_handler))
+            .route("/api/annotations/{id}", web::delete().to(delete_annotation_handler))
+            .route("/api/saved-queries", web::get().to(saved_queries_handler))
+            .route("/api/saved-queries", web::post().to(add_saved_query_handler))
+            .route("/api/saved-queries/{id}", web::delete().to(delete_saved_query_handler))
+            .route("/api/diff", web::get().to(diff_handler))
+            .route("/api/graphql", web::post().to(graphql_handler))
+            .route("/api/graphql", web::get().to(graphql_schema_handler))
+            .route("/api/role", web::get().to(role_handler))
+            .route("/api/role/unredacted", web::post().to(unredacted_role_handler))
+            .route("/api/role/unredacted", web::delete().to(unredacted_role_handler))
             .route("/api/file-index", web::get().to(get_file_index_handler))
             .route("/api/file-preview/{path:.*}", web::get().to(get_file_preview_handler))
             .route("/api/search-files", web::post().to(search_files_handler))
             .route("/api/open-file", web::post().to(open_file_handler))
+            .route("/api/analytics/corpus", web::get().to(corpus_analytics_handler))
+            .service(admin_scope())
+            .route("/api/collections", web::get().to(collections_handler))
+            .route("/api/health", web::get().to(health_handler))
             .route("/static/{filename:.*}", web::get().to(static_handler))
             .route("/static/{filename:.*}", web::head().to(static_handler))
     })
-        .bind("0.0.0.0:8080")?
-        .run()
-        .await
+        // Сигнали обробляє main (service::handle_signals) через спільний Shutdown
+        .disable_signals()
+        .bind(("0.0.0.0", port))?
+        .run();
+
+    service::notify_ready();
+    service::start_watchdog();
+
+    let server_handle = server.handle();
+    tokio::spawn(async move {
+        shutdown.wait().await;
+        // Нові з'єднання не приймаються, розпочаті пошуки завершуються
+        server_handle.stop(true).await;
+    });
+
+    server.await?;
+
+    // Індексер зупиняється в безпечній точці: після синхронізації або після атомарного
+    // збереження вже оброблених файлів; блокування індексів знімається разом з ним
+    let timeout = tokio::time::Duration::from_secs(config.server.shutdown_timeout_secs);
+    info!("{}", tr!(WaitingForIndexer, timeout.as_secs()));
+    let indexers = futures_util::future::join_all(std::iter::once(indexer_task).chain(collection_indexer_tasks));
+    if tokio::time::timeout(timeout, indexers).await.is_err() {
+        warn!("{}", tr!(IndexerStopTimeout, timeout.as_secs()));
+    }
+
+    info!("{}", tr!(ServerStopped));
+    Ok(())
+}
+
+/// Адміністративні запити: усі під перевіркою пароля адміністратора (admin_middleware)
+fn admin_scope() -> actix_web::Scope<
+    impl actix_web::dev::ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    web::scope("/api/admin")
+        .wrap(middleware::from_fn(admin_middleware))
+        .route("/sync-history", web::get().to(sync_history_handler))
+        .route("/index-stats", web::get().to(index_stats_handler))
+        .route("/terms", web::get().to(terms_handler))
+        .route("/duplicates", web::get().to(duplicates_handler))
+        .route("/slow-queries", web::get().to(slow_queries_handler))
+        .route("/feedback", web::get().to(feedback_summary_handler))
+        .route("/log-level", web::get().to(get_log_level_handler))
+        .route("/log-level", web::put().to(set_log_level_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_web_assets_embedded() {
+        for file_name in ["app.js", "auto-reload.js", "style.css", "full.png"] {
+            assert!(WebAssets::get(file_name).is_some(), "{file_name}");
+        }
+        // Шлях поза web/ не віддається
+        assert!(WebAssets::get("../Cargo.toml").is_none());
+    }
+
+    #[test]
+    fn test_path_prefix_is_folder_filter() {
+        let request: SearchRequest = serde_json::from_str(r#"{"query": "наказ", "path_prefix": "2024\\НР\\"}"#).unwrap();
+        let filters = SearchFilters::default().with_folder(request.folder.as_deref());
+        assert_eq!(filters.folder.as_deref(), Some("2024/нр/"));
+    }
+
+    #[test]
+    fn test_match_highlights_cover_whole_words() {
+        let text = "Лейтенанту ПЕТРЕНКУ Івану надати відпустку";
+        let result = SearchEngineResult {
+            doc_id: 1,
+            file_name: "Наказ №1.docx".to_string(),
+            file_path: "2024/Наказ №1.docx".to_string(),
+            matches: vec![SearchEngineMatch { context: text.to_string(), position: 1 }],
+            all_paragraphs: vec![Paragraph::new("НАКАЗ".to_string()), Paragraph::new(text.to_string())],
+            file_size: 0,
+            last_modified: 0,
+            category: None,
+            date: None,
+        };
+        let annotations = ResultAnnotations { matches: vec![Vec::new()], paragraphs: vec![Vec::new(), Vec::new()] };
+        let terms = vec!["петренк".to_string(), "відпуст".to_string()];
+
+        let result = search_result(result, 0, annotations, None, Highlight::Terms(&terms), false, None);
+        // Стемінгований термін підсвічує ціле слово ("петренк" -> "ПЕТРЕНКУ")
+        let words: Vec<String> = result.matches[0]
+            .highlights
+            .iter()
+            .map(|[from, to]| text.chars().skip(*from).take(to - from).collect())
+            .collect();
+        assert_eq!(words, ["ПЕТРЕНКУ", "відпустку"]);
+        assert_eq!(result.all_paragraphs[1].highlights, result.matches[0].highlights);
+        assert!(result.all_paragraphs[0].highlights.is_empty());
+    }
+
+    #[test]
+    fn test_suggestions_skip_redacted_terms() {
+        use blazing_search::index_builder::IndexBuilder;
+        use blazing_search::redaction::default_patterns;
+
+        let (_, inv_index) = IndexBuilder::new()
+            .with_document("Наказ №1.docx", ["Петренко П.П., РНОКПП 3012345678, відрядження 30 діб"])
+            .build();
+        let redactor = Redactor::new(&default_patterns()).unwrap();
+
+        let terms = |redactor| -> Vec<String> {
+            term_suggestions(&inv_index, "30", 10, redactor).into_iter().map(|s| s.term).collect()
+        };
+        assert!(terms(None).contains(&"3012345678".to_string()));
+        assert!(!terms(Some(&redactor)).contains(&"3012345678".to_string()));
+        assert!(terms(Some(&redactor)).contains(&"30".to_string()));
+    }
+
+    #[actix_web::test]
+    async fn test_preview_without_role_is_redacted() {
+        use actix_web::test;
+        use blazing_search::redaction::default_patterns;
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("blazing_redacted_preview_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let docx = dir.join("наказ.docx");
+        let mut archive = zip::ZipWriter::new(std::fs::File::create(&docx).unwrap());
+        archive.start_file("word/document.xml", zip::write::FileOptions::default()).unwrap();
+        archive
+            .write_all(
+                r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body><w:p><w:r><w:t>ІПН 3012345678</w:t></w:r></w:p></w:body></w:document>"#.as_bytes(),
+            )
+            .unwrap();
+        archive.finish().unwrap();
+        let pdf = dir.join("наказ.pdf");
+        std::fs::write(&pdf, b"%PDF-1.4 3012345678").unwrap();
+        let redactor = Redactor::new(&default_patterns()).unwrap();
+
+        let response = redacted_preview(&docx.to_string_lossy(), "docx", &redactor);
+        assert_eq!(response.status(), 200);
+        let text = test::read_body(test::TestRequest::default().to_srv_response(response)).await;
+        assert_eq!(String::from_utf8(text.to_vec()).unwrap().trim(), "ІПН [ІПН]");
+
+        // Зображення і PDF замаскувати не можна - без ролі вони не віддаються
+        assert_eq!(redacted_preview(&pdf.to_string_lossy(), "pdf", &redactor).status(), 403);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[actix_web::test]
+    async fn test_history_user_comes_from_session_cookie() {
+        use actix_web::test;
+
+        let app = test::init_service(
+            App::new()
+                .wrap(middleware::from_fn(session_middleware))
+                .route("/", web::get().to(|req: HttpRequest| async move { session_user(&req) })),
+        )
+        .await;
+
+        // Перший запит отримує новий сеанс
+        let response = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+        let cookie = response.response().cookies().find(|c| c.name() == SESSION_COOKIE).unwrap().into_owned();
+        assert!(session::is_token(cookie.value()) && cookie.http_only() == Some(true));
+        let user = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+        assert_eq!(user, session::user_key(cookie.value()));
+
+        // Той самий сеанс - той самий користувач; ім'я в запиті нічого не змінює
+        let request = test::TestRequest::get().uri("/?user=%D0%BE%D0%BF%D0%B5%D1%80%D0%B0%D1%82%D0%BE%D1%80").cookie(cookie.clone());
+        let response = test::call_service(&app, request.to_request()).await;
+        assert!(response.response().cookies().next().is_none());
+        assert_eq!(test::read_body(response).await, user.as_bytes());
+
+        // Підроблене значення cookie не приймається - видається новий сеанс
+        let forged = Cookie::new(SESSION_COOKIE, "оператор");
+        let response = test::call_service(&app, test::TestRequest::get().uri("/").cookie(forged).to_request()).await;
+        let renewed = response.response().cookies().find(|c| c.name() == SESSION_COOKIE).unwrap().into_owned();
+        assert_ne!(renewed.value(), cookie.value());
+    }
+
+    #[actix_web::test]
+    async fn test_admin_scope_requires_admin_password() {
+        use actix_web::test;
+
+        let call = |credential: Option<String>, authorization: Option<&'static str>| async move {
+            let app = test::init_service(
+                App::new().app_data(web::Data::new(AdminCredential(credential))).service(
+                    web::scope("/api/admin")
+                        .wrap(middleware::from_fn(admin_middleware))
+                        .route("/terms", web::get().to(|| async { "терміни" })),
+                ),
+            )
+            .await;
+            let mut request = test::TestRequest::get().uri("/api/admin/terms");
+            if let Some(authorization) = authorization {
+                request = request.insert_header((header::AUTHORIZATION, authorization));
+            }
+            test::call_service(&app, request.to_request()).await.status()
+        };
+
+        let admin = Some(config::password_sha256("Адмін-пароль").to_uppercase());
+        assert_eq!(call(admin.clone(), Some("Bearer Адмін-пароль")).await, 200);
+        assert_eq!(call(admin.clone(), None).await, 401);
+        // Пароль порівнюється з урахуванням регістру, пароль відкриття файлів не підходить
+        assert_eq!(call(admin.clone(), Some("Bearer адмін-пароль")).await, 401);
+        assert_eq!(call(admin, Some("Bearer 12345")).await, 401);
+        // Без пароля адміністратора в конфігурації розділ вимкнено для всіх
+        assert_eq!(call(None, Some("Bearer Адмін-пароль")).await, 403);
+    }
 }
@@ -6,7 +6,22 @@ const documentPreview = document.getElementById('document-preview');
 const searchStats = document.getElementById('search-stats');
 const processingTime = document.getElementById('processing-time');
 const infoPanel = document.getElementById('info-panel');
+const exportLinks = document.getElementById('export-links');
+const exportCsvLink = document.getElementById('export-csv');
+const exportXlsxLink = document.getElementById('export-xlsx');
+const exportPdfLink = document.getElementById('export-pdf');
+const exportPersonLink = document.getElementById('export-person');
 const loader = document.getElementById('loader');
+const recentSearches = document.getElementById('recent-searches');
+const historyUserButton = document.getElementById('history-user');
+const roleToggleButton = document.getElementById('role-toggle');
+const categoryFilter = document.getElementById('category-filter');
+const folderFilter = document.getElementById('folder-filter');
+const collectionSelect = document.getElementById('collection-select');
+const newDocumentsBadge = document.getElementById('new-documents-badge');
+const newDocumentsList = document.getElementById('new-documents-list');
+const newDocumentsItems = document.getElementById('new-documents-items');
+const newDocumentsSeenButton = document.getElementById('new-documents-seen');
 const errorMessage = document.getElementById('error-message');
 
 // Дефолтний шлях для пошуку файлів
@@ -18,6 +33,10 @@ let isIndexLoaded = false;
 
 // Helper функція для отримання тексту параграфа (підтримка старого і нового формату)
 function getParagraphText(paragraphData) {
+    // Абзаців понад search.max_paragraphs у відповіді немає
+    if (paragraphData === undefined) {
+        return '';
+    }
     return typeof paragraphData === 'string' ? paragraphData : paragraphData.text;
 }
 
@@ -303,6 +322,8 @@ let activeFileIndex = -1;
 let displayedResults = [];
 let currentQuery = ''; // Поточний запит пошуку
 let totalCount = 0; // Загальна кількість результатів
+let omittedResults = 0; // Знайдені документи понад search.max_results
+let partialResults = false; // Пошук перервано за search.query_timeout_ms
 
 // Українські голосні для стемінгу
 const UKRAINIAN_VOWELS = 'аеєиіїоуюяь';
@@ -417,10 +438,262 @@ document.addEventListener('copy', (event) => {
     }
 });
 
+// Ім'я для історії пошуку на сервері (порожнє - сервер визначає користувача за IP-адресою)
+// Кількість знайдених документів кожного виду (швидкий і повний пошук разом)
+let categoryCounts = {};
+
+// Список видів наказів з кількістю знайденого; вибраний вид лишається вибраним
+function updateCategoryFilter(categories, reset) {
+    if (reset) {
+        categoryCounts = {};
+    }
+    for (const [name, count] of Object.entries(categories || {})) {
+        categoryCounts[name] = (categoryCounts[name] || 0) + count;
+    }
+
+    const selected = categoryFilter.value;
+    categoryFilter.innerHTML = '<option value="">Усі види</option>';
+    const names = Object.keys(categoryCounts);
+    if (selected && !names.includes(selected)) {
+        names.push(selected);
+    }
+    names.sort().forEach(name => {
+        const option = document.createElement('option');
+        option.value = name;
+        option.textContent = `${name} (${categoryCounts[name] || 0})`;
+        categoryFilter.appendChild(option);
+    });
+    categoryFilter.value = selected;
+}
+
+// Кількість знайдених документів у папках років і їх підпапках (facets відповіді)
+let folderCounts = {};
+
+// Список папок з кількістю знайденого: рік, під ним - його підпапки; вибрана лишається вибраною
+function updateFolderFilter(facets, reset) {
+    if (reset) {
+        folderCounts = {};
+    }
+    const counts = { ...((facets && facets.years) || {}), ...((facets && facets.folders) || {}) };
+    for (const [name, count] of Object.entries(counts)) {
+        folderCounts[name] = (folderCounts[name] || 0) + count;
+    }
+
+    const selected = folderFilter.value;
+    folderFilter.innerHTML = '<option value="">Усі папки</option>';
+    const names = Object.keys(folderCounts);
+    if (selected && !names.includes(selected)) {
+        names.push(selected);
+    }
+    // Нові роки першими, підпапки - за абеткою під своїм роком
+    names.sort((a, b) => {
+        const [yearA, yearB] = [a.split('/')[0], b.split('/')[0]];
+        return yearA === yearB ? a.localeCompare(b, 'uk') : yearB.localeCompare(yearA, 'uk');
+    }).forEach(name => {
+        const option = document.createElement('option');
+        option.value = name;
+        const label = name.includes('/') ? `\u00a0\u00a0${name.split('/').slice(1).join('/')}` : name;
+        option.textContent = `${label} (${folderCounts[name] || 0})`;
+        folderFilter.appendChild(option);
+    });
+    folderFilter.value = selected;
+}
+
+// Колекції сервера (/api/collections); вибір видимий, лише якщо їх кілька
+async function loadCollections() {
+    try {
+        const response = await fetch('/api/collections');
+        if (!response.ok) {
+            return;
+        }
+        const collections = await response.json();
+        collectionSelect.innerHTML = '';
+        collections.forEach(collection => {
+            const option = document.createElement('option');
+            option.value = collection.name;
+            option.textContent = `${collection.name} (${collection.documents})`;
+            collectionSelect.appendChild(option);
+        });
+        // Пошук одним запитом у всіх колекціях
+        if (collections.length > 1) {
+            const all = document.createElement('option');
+            all.value = ALL_COLLECTIONS;
+            all.textContent = 'Усі колекції';
+            collectionSelect.appendChild(all);
+        }
+        const saved = localStorage.getItem('searchCollection');
+        if (saved === ALL_COLLECTIONS || collections.some(collection => collection.name === saved)) {
+            collectionSelect.value = saved;
+        }
+        collectionSelect.classList.toggle('hidden', collections.length < 2);
+    } catch (error) {
+        console.error('❌ Помилка завантаження колекцій:', error);
+    }
+}
+
+const ALL_COLLECTIONS = '*';
+
+// Вибрана колекція (null - основна або всі колекції)
+function getCollection() {
+    const value = collectionSelect.value;
+    return value && value !== ALL_COLLECTIONS ? value : null;
+}
+
+// Параметри колекції для запиту пошуку: одна колекція або всі (collections: [])
+function collectionParams() {
+    return collectionSelect.value === ALL_COLLECTIONS ? { collections: [] } : { collection: getCollection() };
+}
+
+function getHistoryUser() {
+    return localStorage.getItem('searchHistoryUser') || '';
+}
+
+// Нещодавні запити користувача - підказки поля пошуку
+async function loadSearchHistory() {
+    try {
+        const response = await fetch('/api/history');
+        if (!response.ok) {
+            return;
+        }
+        const history = await response.json();
+        historyUserButton.classList.toggle('hidden', !history.enabled);
+        recentSearches.innerHTML = '';
+        history.queries.forEach(entry => {
+            const option = document.createElement('option');
+            option.value = entry.query;
+            recentSearches.appendChild(option);
+        });
+    } catch (error) {
+        console.error('❌ Помилка завантаження історії пошуку:', error);
+    }
+}
+
+// Маскування чутливих даних: кнопка 🔒/🔓 видима, лише якщо можна отримати роль без маскування
+let unredacted = false;
+
+async function loadRole() {
+    try {
+        const response = await fetch('/api/role');
+        if (!response.ok) {
+            return;
+        }
+        const role = await response.json();
+        unredacted = role.unredacted;
+        roleToggleButton.classList.toggle('hidden', !role.role_available);
+        roleToggleButton.textContent = unredacted ? '🔓' : '🔒';
+        roleToggleButton.title = unredacted ? 'Чутливі дані показано (натисніть, щоб приховати)' : 'Чутливі дані приховано';
+    } catch (error) {
+        console.error('❌ Помилка завантаження ролі:', error);
+    }
+}
+
+async function toggleUnredactedRole() {
+    let response;
+    if (unredacted) {
+        response = await fetch('/api/role/unredacted', { method: 'DELETE' });
+    } else {
+        const password = prompt('Пароль для перегляду без маскування:');
+        if (!password) {
+            return;
+        }
+        response = await fetch('/api/role/unredacted', {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ password })
+        });
+    }
+    if (!response.ok) {
+        const error = await response.json().catch(() => ({}));
+        showToast(error.error || response.statusText, 'error');
+        return;
+    }
+    await loadRole();
+    // Показані результати - заново, з маскуванням чи без
+    if (searchInput.value.trim() && getCurrentViewMode() !== 'file-search') {
+        performSearch();
+    }
+}
+
+// Документи, додані з минулого візиту: значок з кількістю і список назв
+async function loadNewDocuments() {
+    try {
+        const response = await fetch('/api/new-documents');
+        if (!response.ok) {
+            return;
+        }
+        const result = await response.json();
+        newDocumentsBadge.classList.toggle('hidden', result.count === 0);
+        newDocumentsBadge.textContent = `🆕 ${result.count}`;
+        newDocumentsItems.innerHTML = '';
+        result.documents.forEach(document_ => {
+            const item = document.createElement('li');
+            item.textContent = document_.file_name;
+            item.title = document_.file_path;
+            newDocumentsItems.appendChild(item);
+        });
+        if (result.count > result.documents.length) {
+            const more = document.createElement('li');
+            more.textContent = `... і ще ${result.count - result.documents.length}`;
+            newDocumentsItems.appendChild(more);
+        }
+    } catch (error) {
+        console.error('❌ Помилка завантаження нових документів:', error);
+    }
+}
+
 // Ініціалізація при завантаженні сторінки
 window.addEventListener('load', async () => {
     // Завантажуємо індекс файлів при старті
     await loadFileIndex();
+    loadSearchHistory();
+    loadNewDocuments();
+    loadRole();
+    loadCollections();
+    // Постійне посилання: інший пункт того самого документа - без перезавантаження сторінки
+    openPermalink();
+    window.addEventListener('hashchange', openPermalink);
+
+    roleToggleButton.addEventListener('click', toggleUnredactedRole);
+
+    newDocumentsBadge.addEventListener('click', () => {
+        newDocumentsList.classList.toggle('hidden');
+    });
+
+    newDocumentsSeenButton.addEventListener('click', async () => {
+        await fetch('/api/new-documents/seen', { method: 'POST' });
+        newDocumentsList.classList.add('hidden');
+        loadNewDocuments();
+    });
+
+    // Інша колекція - той самий запит заново, вибір запам'ятовується
+    collectionSelect.addEventListener('change', () => {
+        localStorage.setItem('searchCollection', collectionSelect.value);
+        if (searchInput.value.trim() && getCurrentViewMode() !== 'file-search') {
+            performSearch();
+        }
+    });
+
+    // Інший вид наказу - той самий запит заново
+    categoryFilter.addEventListener('change', () => {
+        if (searchInput.value.trim() && getCurrentViewMode() !== 'file-search') {
+            performSearch();
+        }
+    });
+
+    // Інша папка - той самий запит заново
+    folderFilter.addEventListener('change', () => {
+        if (searchInput.value.trim() && getCurrentViewMode() !== 'file-search') {
+            performSearch();
+        }
+    });
+
+    historyUserButton.addEventListener('click', () => {
+        // Історію визначає сеанс браузера, ім'я - лише підпис приміток
+        const name = prompt('Ваше ім\'я для підпису приміток:', getHistoryUser());
+        if (name !== null) {
+            localStorage.setItem('searchHistoryUser', name.trim());
+        }
+    });
 
     // Обробник для Enter в полі пошуку
     searchInput.addEventListener('keyup', (event) => {
@@ -529,6 +802,41 @@ window.addEventListener('load', async () => {
 });
 
 // Основна функція пошуку
+// Останні відповіді пошуку з їх ETag: поки індекс не оновився, сервер на той самий
+// запит відповідає 304 і тіло береться звідси
+const searchResponseCache = new Map();
+const SEARCH_CACHE_SIZE = 20;
+
+// POST /api/search з If-None-Match; повертає статус і розібрану відповідь
+async function fetchSearch(body) {
+    const key = JSON.stringify({ ...body, user: undefined });
+    const cached = searchResponseCache.get(key);
+    const headers = { 'Content-Type': 'application/json' };
+    if (cached) {
+        headers['If-None-Match'] = cached.etag;
+    }
+
+    const response = await fetch('/api/search', {
+        method: 'POST',
+        headers: headers,
+        body: JSON.stringify(body)
+    });
+    if (response.status === 304 && cached) {
+        return { status: 200, ok: true, result: structuredClone(cached.result) };
+    }
+
+    const result = await response.json().catch(() => ({}));
+    const etag = response.headers.get('ETag');
+    searchResponseCache.delete(key);
+    if (response.ok && etag) {
+        searchResponseCache.set(key, { etag: etag, result: result });
+        if (searchResponseCache.size > SEARCH_CACHE_SIZE) {
+            searchResponseCache.delete(searchResponseCache.keys().next().value);
+        }
+    }
+    return { status: response.status, ok: response.ok, result: result };
+}
+
 async function performSearch() {
     const query = searchInput.value.trim();
     const viewMode = getCurrentViewMode();
@@ -560,23 +868,26 @@ async function performSearch() {
 
         // Перший (швидкий) запит
         const viewMode = getCurrentViewMode();
-        const response = await fetch('/api/search', {
-            method: 'POST',
-            headers: {
-                'Content-Type': 'application/json',
-            },
-            body: JSON.stringify({
-                query: query,
-                full_search: false,
-                view_mode: viewMode
-            })
+        const response = await fetchSearch({
+            query: query,
+            full_search: false,
+            view_mode: viewMode,
+            category: categoryFilter.value || null,
+            folder: folderFilter.value || null,
+            ...collectionParams()
         });
 
+        // Сервер зайнятий іншими пошуками - показуємо його пояснення
+        if (response.status === 429) {
+            showError(response.result.error);
+            return;
+        }
+
         if (!response.ok) {
             throw new Error(`HTTP Error: ${response.status}`);
         }
 
-        const result = await response.json();
+        const result = response.result;
 
         if (result.error) {
             showError(result.error);
@@ -586,8 +897,13 @@ async function performSearch() {
         // Оновлюємо глобальні змінні
         displayedResults = result.results;
         totalCount = result.total_count;
+        omittedResults = result.omitted_results || 0;
+        partialResults = Boolean(result.partial);
 
+        updateCategoryFilter(result.categories, true);
+        updateFolderFilter(result.facets, true);
         displayResults(result, query);
+        loadSearchHistory();
 
         // Якщо є ще результати, запускаємо другий (повний) пошук
         if (displayedResults.length < totalCount) {
@@ -607,23 +923,20 @@ async function performFullSearch(query) {
     showLazyLoadingIndicator(); // Показуємо індикатор завантаження решти
     try {
         const viewMode = getCurrentViewMode();
-        const response = await fetch('/api/search', {
-            method: 'POST',
-            headers: {
-                'Content-Type': 'application/json',
-            },
-            body: JSON.stringify({
-                query: query,
-                full_search: true,
-                view_mode: viewMode
-            })
+        const response = await fetchSearch({
+            query: query,
+            full_search: true,
+            view_mode: viewMode,
+            category: categoryFilter.value || null,
+            folder: folderFilter.value || null,
+            ...collectionParams()
         });
 
         if (!response.ok) {
             throw new Error(`HTTP Error: ${response.status}`);
         }
 
-        const result = await response.json();
+        const result = response.result;
 
         if (result.error) {
             // Не показуємо помилку, щоб не перекривати вже знайдені результати
@@ -631,6 +944,8 @@ async function performFullSearch(query) {
             return;
         }
 
+        updateCategoryFilter(result.categories, false);
+        updateFolderFilter(result.facets, false);
         appendResults(result, query);
 
     } catch (error) {
@@ -722,13 +1037,20 @@ function showFilePreview(file) {
                 <img src="/api/file-preview/${encodedPath}" alt="${file.name}" style="max-width: 100%; max-height: 500px; object-fit: contain;">
             </div>
         `;
-    } else if (isPdf || isDoc) {
-        // Показуємо embed для PDF або конвертованого .doc файлу через API
+    } else if (isPdf) {
+        // Показуємо embed для PDF через API
         previewHTML += `
             <div class="pdf-preview">
                 <embed src="/api/file-preview/${encodedPath}" type="application/pdf" width="100%" height="600">
             </div>
         `;
+    } else if (isDoc) {
+        // Документ приходить PDF-ом, а без ролі перегляду - текстом з маскуванням
+        previewHTML += `
+            <div class="pdf-preview">
+                <iframe src="/api/file-preview/${encodedPath}" width="100%" height="600"></iframe>
+            </div>
+        `;
     } else {
         // Для інших файлів показуємо інформацію
         previewHTML += `
@@ -828,7 +1150,30 @@ function displayResults(result, query) {
 
     // Показуємо інформацію про кількість результатів
     updateResultsStats();
-    processingTime.textContent = `Час пошуку: ${processing_time_ms}мс`;
+    processingTime.textContent = `Час пошуку: ${processing_time_ms}мс${describeSearchScope(result.search_scope)}`;
+
+    // Вивантаження всіх знайдених абзаців (крапка з комою - роздільник для Excel з українською локаллю)
+    const exportParams = new URLSearchParams({ query, view_mode: getCurrentViewMode() });
+    if (categoryFilter.value) {
+        exportParams.set('category', categoryFilter.value);
+    }
+    if (collectionSelect.value === ALL_COLLECTIONS) {
+        exportParams.set('collections', '');
+    } else if (getCollection()) {
+        exportParams.set('collection', getCollection());
+    }
+    exportCsvLink.href = `/api/export?${exportParams}&format=csv&delimiter=semicolon`;
+    exportXlsxLink.href = `/api/export?${exportParams}&format=xlsx`;
+    exportPdfLink.href = `/api/export?${exportParams}&format=pdf`;
+    // Послужний витяг: запит вважається ім'ям людини
+    const personParams = new URLSearchParams({ name: query, format: 'pdf' });
+    if (collectionSelect.value === ALL_COLLECTIONS) {
+        personParams.set('collections', '');
+    } else if (getCollection()) {
+        personParams.set('collection', getCollection());
+    }
+    exportPersonLink.href = `/api/person?${personParams}`;
+    exportLinks.classList.remove('hidden');
 
     // Завжди показуємо контейнер результатів
     resultsContainer.classList.remove('hidden');
@@ -865,7 +1210,24 @@ function displayResults(result, query) {
     }
 }
 
+// Яку частину архіву переглянув пошук: спершу швидкий набір найновіших наказів, потім решта
+function describeSearchScope(scope) {
+    if (!scope) {
+        return '';
+    }
+    if (scope.mode === 'quick') {
+        const since = scope.quick_since ? ` з ${scope.quick_since}` : '';
+        // Оцінка за індексом - до пошуку в решті архіву
+        const more = scope.remaining_estimate ? ` · ≈${scope.remaining_estimate} ще в повному архіві` : '';
+        return ` · найновіші накази${since} (${scope.documents})${more}`;
+    }
+    return ' · увесь архів';
+}
+
 function appendResults(result, query) {
+    if (result.search_scope) {
+        processingTime.textContent = processingTime.textContent.split(' · ')[0] + describeSearchScope(result.search_scope);
+    }
     const newResults = result.results;
 
     if (newResults.length === 0) {
@@ -873,6 +1235,8 @@ function appendResults(result, query) {
     }
 
     const currentLength = displayedResults.length;
+    omittedResults += result.omitted_results || 0;
+    partialResults = partialResults || Boolean(result.partial);
     displayedResults = displayedResults.concat(newResults);
     totalCount = displayedResults.length;
 
@@ -896,6 +1260,9 @@ function appendResults(result, query) {
             // Спочатку очищуємо documentPreview, щоб не було старого контенту
             documentPreview.innerHTML = '';
             showAllExtracts(query);
+        } else if (groupMatchesByPerson(displayedResults)) {
+            // Нові збіги можуть належати вже показаним людям - перегруповуємо все
+            showAllExtracts(query, true);
         } else {
             // Додаємо нові витяги до існуючого контенту
             appendExtracts(newResults, query);
@@ -948,6 +1315,45 @@ function createFileElement(file, index, query) {
 
     fileElement.appendChild(fileName);
 
+    // Дата не з назви файлу - показуємо, за якою датою документ стоїть у списку
+    const dateSourceLabels = {
+        content: 'дата з шапки документа',
+        modified: 'дата зміни файлу',
+        created: 'дата створення файлу'
+    };
+    if (file.date && dateSourceLabels[file.date_source]) {
+        const fileDate = document.createElement('div');
+        fileDate.className = 'file-date';
+        fileDate.textContent = `📅 ${file.date} (${dateSourceLabels[file.date_source]})`;
+        fileElement.appendChild(fileDate);
+    }
+
+    // Пошук у всіх колекціях: з якої колекції документ
+    if (file.collection) {
+        const fileCollection = document.createElement('div');
+        fileCollection.className = 'file-date';
+        fileCollection.textContent = `📚 ${file.collection}`;
+        fileElement.appendChild(fileCollection);
+    }
+
+    // Оцінка результату: документ з перевагою 👍 піднімається для цього запиту, з 👎 - опускається
+    const feedback = document.createElement('div');
+    feedback.className = 'file-feedback';
+    if (query) {
+        [['👍', true, 'Доречний результат'], ['👎', false, 'Недоречний результат']].forEach(([label, relevant, title]) => {
+            const button = document.createElement('button');
+            button.textContent = label;
+            button.title = title;
+            button.addEventListener('click', (event) => {
+                event.stopPropagation();
+                sendFeedback(query, file.file_path, relevant, feedback);
+            });
+            feedback.appendChild(button);
+        });
+    }
+    feedback.appendChild(permalinkButton(file, null, 'Скопіювати посилання на документ'));
+    fileElement.appendChild(feedback);
+
     fileElement.addEventListener('click', (event) => {
         // Якщо клікнули на назву файлу, не виконуємо вибір файлу
         if (event.target.className === 'file-name') {
@@ -981,7 +1387,7 @@ function createFileElement(file, index, query) {
 }
 
 // Створює HTML контент для одного витягу (match)
-function buildExtractContent(file, match, query) {
+function buildExtractContent(file, match) {
     const isPersonalFile = file.file_name.toLowerCase().startsWith('особовий');
     let content = '';
 
@@ -1014,7 +1420,7 @@ function buildExtractContent(file, match, query) {
     }
 
     // Основний текст збігу
-    content += `<div style="margin-bottom: 10px; line-height: 1.4;">${highlightText(match.context, query).replace(/\n/g, '<br>')}</div>`;
+    content += `<div style="margin-bottom: 10px; line-height: 1.4;">${applyHighlights(match.context, match.highlights || []).replace(/\n/g, '<br>')}</div>`;
 
     // Додаємо додаткові параграфи
     if (file.all_paragraphs && Array.isArray(file.all_paragraphs)) {
@@ -1128,7 +1534,7 @@ function createExtractElement(file, match, query) {
     const extractSection = document.createElement('div');
     extractSection.className = 'extract-section';
     extractSection.style.cssText = 'padding: 15px 0 15px 20px; border-bottom: 1px solid #e0e0e0; border-left: 4px solid #ffc107; margin-bottom: 15px;';
-    extractSection.innerHTML = buildExtractContent(file, match, query);
+    extractSection.innerHTML = buildExtractContent(file, match);
     return extractSection;
 }
 
@@ -1146,25 +1552,88 @@ function createFileContainer(fileName) {
     return fileContainer;
 }
 
+// Збіги за людьми (сервер визначає ПІБ, якщо запит схожий на ПІБ): null, якщо в результатах
+// менше двох різних людей - тоді групувати нема кого
+function groupMatchesByPerson(results) {
+    const groups = new Map();
+    const others = [];
+    results.forEach((file) => {
+        file.matches.forEach((match) => {
+            if (!match.person) {
+                others.push({ file, match });
+                return;
+            }
+            if (!groups.has(match.person.key)) {
+                groups.set(match.person.key, { name: match.person.name, items: [] });
+            }
+            groups.get(match.person.key).items.push({ file, match });
+        });
+    });
+    if (groups.size < 2) {
+        return null;
+    }
+    const result = Array.from(groups.values());
+    if (others.length > 0) {
+        result.push({ name: 'Інші збіги', items: others });
+    }
+    return result;
+}
+
+// Витяги однієї людини: документи в порядку результатів
+function createPersonGroup(group, query) {
+    const personGroup = document.createElement('div');
+    personGroup.className = 'person-group';
+
+    const header = document.createElement('div');
+    header.className = 'person-group-header';
+    header.textContent = `👤 ${group.name} - збігів: ${group.items.length}`;
+    personGroup.appendChild(header);
+
+    let fileContainer = null;
+    let currentFile = null;
+    group.items.forEach(({ file, match }) => {
+        if (file !== currentFile) {
+            currentFile = file;
+            fileContainer = createFileContainer(file.file_name);
+            personGroup.appendChild(fileContainer);
+        }
+        fileContainer.appendChild(createExtractElement(file, match, query));
+    });
+    return personGroup;
+}
+
 // Функція для відображення всіх витягів
-function showAllExtracts(query) {
+function showAllExtracts(query, keepScroll = false) {
+    const scrollTop = documentPreview.scrollTop;
     documentPreview.innerHTML = '';
 
     const documentContent = document.createElement('div');
     documentContent.className = 'document-content';
 
-    displayedResults.forEach((file) => {
-        const fileContainer = createFileContainer(file.file_name);
-
-        file.matches.forEach((match) => {
-            fileContainer.appendChild(createExtractElement(file, match, query));
+    const personGroups = groupMatchesByPerson(displayedResults);
+    if (personGroups) {
+        // Однофамільці - окремими групами, а не вперемішку за датою
+        personGroups.forEach((group) => {
+            documentContent.appendChild(createPersonGroup(group, query));
         });
+    } else {
+        displayedResults.forEach((file) => {
+            const fileContainer = createFileContainer(file.file_name);
 
-        documentContent.appendChild(fileContainer);
-    });
+            file.matches.forEach((match) => {
+                fileContainer.appendChild(createExtractElement(file, match, query));
+            });
+
+            documentContent.appendChild(fileContainer);
+        });
+    }
 
     documentPreview.appendChild(documentContent);
 
+    if (keepScroll) {
+        documentPreview.scrollTop = scrollTop;
+        return;
+    }
     setTimeout(() => {
         documentPreview.scrollTop = 0;
     }, 100);
@@ -1214,6 +1683,12 @@ function scrollToFileInExtracts(fileName) {
 // Функція для оновлення статистики результатів
 function updateResultsStats() {
     searchStats.textContent = `Знайдено: ${totalCount} файл(ів)`;
+    if (omittedResults > 0) {
+        searchStats.textContent += ` (ще ${omittedResults} не показано - уточніть запит)`;
+    }
+    if (partialResults) {
+        searchStats.textContent += ' ⏱️ пошук перервано за часом - знайдено не все, уточніть запит';
+    }
 }
 
 // Отримати поточний режим відображення
@@ -1322,25 +1797,32 @@ function selectFile(fileIndex, query) {
                 }
             }
 
-            let isMatch = false;
-            for (const match of file.matches) {
-                if (match.context.trim() === text.trim()) {
-                    isMatch = true;
-                    paragraph.className += ' found-text';
-                    paragraph.innerHTML = highlightText(text, query).replace(/\n/g, '<br>');
+            // Збіг і межі слів визначає сервер - з тими самими термінами після стемінгу, що й пошук
+            if (paragraphData.matched) {
+                paragraph.className += ' found-text';
+                paragraph.innerHTML = applyHighlights(text, paragraphData.highlights || []).replace(/\n/g, '<br>');
 
-                    if (!firstMatchElement) {
-                        firstMatchElement = paragraph;
-                    }
-                    break;
+                if (!firstMatchElement) {
+                    firstMatchElement = paragraph;
                 }
+            } else {
+                paragraph.innerHTML = text.replace(/\n/g, '<br>');
             }
 
-            if (!isMatch) {
-                paragraph.innerHTML = text.replace(/\n/g, '<br>');
+            // Пункт збігу - посилання на нього
+            const matchedSection = (file.matches.find(match => match.position === index) || {}).section;
+            if (paragraphData.matched && matchedSection) {
+                paragraph.prepend(permalinkButton(file, matchedSection, `Скопіювати посилання на пункт ${matchedSection}`));
             }
 
+            // Подвійний клік по абзацу - нова примітка до нього
+            paragraph.title = 'Подвійний клік - додати примітку';
+            paragraph.addEventListener('dblclick', () => addAnnotation(file, index, paragraph));
+
             documentContent.appendChild(paragraph);
+            (paragraphData.annotations || []).forEach(annotation => {
+                paragraph.after(annotationElement(file, annotation));
+            });
 
             // Додаємо розриви рядків після параграфа якщо вони є
             for (let i = 0; i < lineBreaksAfter; i++) {
@@ -1349,6 +1831,13 @@ function selectFile(fileIndex, query) {
                 documentContent.appendChild(emptyLine);
             }
         });
+
+        if (file.omitted_paragraphs > 0) {
+            const omitted = document.createElement('div');
+            omitted.className = 'no-results';
+            omitted.textContent = `… ще ${file.omitted_paragraphs} абзац(ів) не показано - відкрийте файл`;
+            documentContent.appendChild(omitted);
+        }
     } else {
         // Режим фрагментів - показуємо знайдені фрагменти з батьківськими параграфами
         file.matches.forEach((match) => {
@@ -1358,9 +1847,15 @@ function selectFile(fileIndex, query) {
 
             // Додаємо назву файлу як заголовок витягу
             let content = `<div style="font-size: 1.2em; color: #0066cc; margin-bottom: 15px; padding-bottom: 8px; border-bottom: 1px solid #ddd; font-weight: bold;">${file.file_name}</div>`;
-            content += buildExtractContent(file, match, query);
+            content += buildExtractContent(file, match);
 
             extractSection.innerHTML = content;
+            if (match.section) {
+                extractSection.prepend(permalinkButton(file, match.section, `Скопіювати посилання на пункт ${match.section}`));
+            }
+            (match.annotations || []).forEach(annotation => {
+                extractSection.appendChild(annotationElement(file, annotation));
+            });
 
             if (!firstMatchElement) {
                 firstMatchElement = extractSection;
@@ -1388,88 +1883,18 @@ function selectFile(fileIndex, query) {
     }, 300);
 }
 
-// Виділення знайденого тексту
-function highlightText(text, query) {
-
-    const queryParts = query.toLowerCase().trim().split(/\s+/);
-    const isNameSearch = queryParts.length >= 2 && queryParts.length <= 3 &&
-        queryParts.every(part => part.length >= 2);
-
+// Виділення слів за межами від сервера: [початок, кінець) в одиницях UTF-16 (індекси рядка JS)
+function applyHighlights(text, ranges) {
     let highlightedText = text;
-    let hasHighlight = false;
-
-    if (isNameSearch) {
-        // Пошук повної послідовності
-        const fullQueryRegex = new RegExp(
-            `(${queryParts.map(part => `${escapeRegExp(part)}[а-яіїєґ]*`).join('\\s+')})`,
-            'gi'
-        );
-
-        let fullMatches = [];
-        let match;
-
-        while ((match = fullQueryRegex.exec(text)) !== null) {
-            fullMatches.push({
-                index: match.index,
-                text: match[0]
-            });
-            hasHighlight = true;
-        }
-
-        if (fullMatches.length > 0) {
-            for (let i = fullMatches.length - 1; i >= 0; i--) {
-                const matchData = fullMatches[i];
-                const prefix = highlightedText.substring(0, matchData.index);
-                const suffix = highlightedText.substring(matchData.index + matchData.text.length);
-                highlightedText = prefix + `<span class="highlight">${matchData.text}</span>` + suffix;
-            }
-            return highlightedText;
-        }
-
-        // Пошук патерну ПРІЗВИЩЕ Ім'я
-        const capsNameRegex = /\b([A-ZА-ЯІЇЄҐ]{2,})\s+([A-ZА-ЯІЇЄҐ][a-zа-яіїєґ]+)\b/g;
-        const capsMatches = [];
-
-        while ((match = capsNameRegex.exec(text)) !== null) {
-            const fullName = match[0].toLowerCase();
-            if (queryParts.every(part => fullName.includes(part))) {
-                capsMatches.push({
-                    index: match.index,
-                    text: match[0]
-                });
-                hasHighlight = true;
-            }
-        }
-
-        for (let i = capsMatches.length - 1; i >= 0; i--) {
-            const matchData = capsMatches[i];
-            const prefix = highlightedText.substring(0, matchData.index);
-            const suffix = highlightedText.substring(matchData.index + matchData.text.length);
-            highlightedText = prefix + `<span class="highlight">${matchData.text}</span>` + suffix;
-        }
-
-        if (hasHighlight) {
-            return highlightedText;
-        }
+    for (let i = ranges.length - 1; i >= 0; i--) {
+        const [start, end] = ranges[i];
+        highlightedText = highlightedText.substring(0, start) +
+            `<span class="highlight">${highlightedText.substring(start, end)}</span>` +
+            highlightedText.substring(end);
     }
-
-    // Звичайне виділення для кожного слова окремо
-    queryParts.forEach(queryPart => {
-        const regex = new RegExp(escapeRegExp(queryPart), 'gi');
-        if (regex.test(text)) {
-            hasHighlight = true;
-            highlightedText = highlightedText.replace(regex, match => `<span class="highlight">${match}</span>`);
-        }
-    });
-
-
     return highlightedText;
 }
 
-function escapeRegExp(string) {
-    return string.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
-}
-
 // Відкриття файлу з паролем
 function openFile(filePath) {
     const savedPassword = localStorage.getItem('fileOpenPassword');
@@ -1610,11 +2035,14 @@ function clearResults() {
     documentPreview.innerHTML = '';
     resultsContainer.classList.add('hidden');
     infoPanel.style.display = 'none';
+    exportLinks.classList.add('hidden');
     searchResults = [];
     displayedResults = [];
     activeFileIndex = -1;
     currentQuery = '';
     totalCount = 0;
+    omittedResults = 0;
+    partialResults = false;
 }
 
 function showError(message) {
@@ -1917,9 +2345,12 @@ function openFullscreenPreview(encodedPath, fileType, fileName) {
             zoomInfo.textContent = `${zoomLevel}%`;
         });
     } else if (fileType === 'pdf' || fileType === 'doc') {
-        const embed = document.createElement('embed');
+        // Документ без ролі перегляду приходить текстом, тож для нього - iframe
+        const embed = document.createElement(fileType === 'doc' ? 'iframe' : 'embed');
         embed.src = `/api/file-preview/${encodedPath}`;
-        embed.type = 'application/pdf';
+        if (fileType === 'pdf') {
+            embed.type = 'application/pdf';
+        }
         embed.style.cssText = `
             width: 100%;
             height: 100%;
@@ -1963,6 +2394,171 @@ function openFullscreenPreview(encodedPath, fileType, fileName) {
     });
 }
 
+// Відправляє оцінку результату; після оцінки кнопки документа вимикаються до наступного пошуку
+async function sendFeedback(query, filePath, relevant, container) {
+    try {
+        const response = await fetch('/api/feedback', {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ query, file_path: filePath, relevant })
+        });
+        if (!response.ok) {
+            const error = await response.json().catch(() => ({}));
+            throw new Error(error.error || response.statusText);
+        }
+        container.querySelectorAll('button').forEach(button => { button.disabled = true; });
+        showToast(relevant ? 'Дякуємо: документ позначено доречним' : 'Дякуємо: документ позначено недоречним', 'success');
+    } catch (error) {
+        showToast(`Не вдалося зберегти оцінку: ${error.message}`, 'error');
+    }
+}
+
+// Постійне посилання на документ і пункт (/doc/<doc_id>#p-2.3)
+function permalinkFor(file, section) {
+    let url = `/doc/${file.doc_id}`;
+    const collection = file.collection || getCollection();
+    if (collection) {
+        url += `?collection=${encodeURIComponent(collection)}`;
+    }
+    if (section) {
+        url += `#p-${section}`;
+    }
+    return location.origin + url;
+}
+
+// Кнопка 🔗: копіює посилання; без HTTPS буфер обміну недоступний - показуємо посилання
+function permalinkButton(file, section, title) {
+    const button = document.createElement('button');
+    button.className = 'permalink-button';
+    button.textContent = '🔗';
+    button.title = title;
+    button.addEventListener('click', async (event) => {
+        event.stopPropagation();
+        const url = permalinkFor(file, section);
+        if (navigator.clipboard && window.isSecureContext) {
+            await navigator.clipboard.writeText(url);
+            showToast('Посилання скопійовано', 'success');
+        } else {
+            prompt('Посилання для копіювання:', url);
+        }
+    });
+    return button;
+}
+
+// Відкриває документ за постійним посиланням /doc/<doc_id>#p-<пункт> у перегляді
+// "Повний документ"; пункт виділяється як збіг. false - сторінка не є посиланням
+async function openPermalink() {
+    const path = location.pathname.match(/^\/doc\/(\d+)$/);
+    if (!path) {
+        return false;
+    }
+    const params = new URLSearchParams(location.search);
+    const anchor = decodeURIComponent(location.hash.slice(1));
+    if (anchor.startsWith('p-')) {
+        params.set('section', anchor.slice(2));
+    }
+
+    hideError();
+    showLoader();
+    try {
+        const response = await fetch(`/api/documents/${path[1]}?${params}`);
+        const result = await response.json();
+        if (!response.ok) {
+            throw new Error(result.error || response.statusText);
+        }
+        document.querySelector('input[name="view-mode"][value="full-document"]').checked = true;
+        displayedResults = [result];
+        totalCount = 1;
+        activeFileIndex = -1;
+
+        infoPanel.style.display = 'flex';
+        exportLinks.classList.add('hidden');
+        searchStats.textContent = `📄 ${result.file_name}`;
+        processingTime.textContent = params.has('section') && result.target === null
+            ? `Пункт ${params.get('section')} не знайдено`
+            : '';
+        resultsContainer.classList.remove('hidden');
+        filesList.innerHTML = '';
+        const fileElement = createFileElement(result, 0, '');
+        if (fileElement) {
+            filesList.appendChild(fileElement);
+        }
+        selectFile(0, '');
+    } catch (error) {
+        showError(`Не вдалося відкрити документ: ${error.message}`);
+    } finally {
+        hideLoader();
+    }
+    return true;
+}
+
+// Колекція документа для запитів приміток
+function annotationCollection(file) {
+    return file.collection || getCollection();
+}
+
+// Примітка під абзацом: текст, автор, дата і кнопка видалення
+function annotationElement(file, annotation) {
+    const element = document.createElement('div');
+    element.className = 'paragraph-annotation';
+    const created = new Date(annotation.created_at * 1000).toLocaleDateString('uk-UA');
+    element.textContent = `📝 ${annotation.text} — ${annotation.author}, ${created}`;
+
+    const remove = document.createElement('button');
+    remove.textContent = '✖';
+    remove.title = 'Видалити примітку';
+    remove.addEventListener('click', async (event) => {
+        event.stopPropagation();
+        if (!confirm('Видалити примітку?')) return;
+        const collection = annotationCollection(file);
+        const params = collection ? `?collection=${encodeURIComponent(collection)}` : '';
+        try {
+            const response = await fetch(`/api/annotations/${annotation.id}${params}`, { method: 'DELETE' });
+            if (!response.ok) {
+                const error = await response.json().catch(() => ({}));
+                throw new Error(error.error || response.statusText);
+            }
+            element.remove();
+        } catch (error) {
+            showToast(`Не вдалося видалити примітку: ${error.message}`, 'error');
+        }
+    });
+    element.appendChild(remove);
+    return element;
+}
+
+// Додає примітку до абзацу position документа; сервер шукає абзац у індексі за doc_id
+async function addAnnotation(file, position, paragraph) {
+    const text = prompt('Примітка до абзацу:');
+    if (!text || !text.trim()) return;
+    try {
+        const response = await fetch('/api/annotations', {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({
+                doc_id: file.doc_id,
+                position,
+                text,
+                user: getHistoryUser() || null,
+                collection: annotationCollection(file)
+            })
+        });
+        if (!response.ok) {
+            const error = await response.json().catch(() => ({}));
+            throw new Error(error.error || response.statusText);
+        }
+        const annotation = await response.json();
+        let last = paragraph;
+        while (last.nextElementSibling && last.nextElementSibling.classList.contains('paragraph-annotation')) {
+            last = last.nextElementSibling;
+        }
+        last.after(annotationElement(file, annotation));
+        showToast('Примітку збережено', 'success');
+    } catch (error) {
+        showToast(`Не вдалося зберегти примітку: ${error.message}`, 'error');
+    }
+}
+
 // Вспомагає функція для показу toast повідомлень
 function showToast(message, type = 'info') {
     const toast = document.createElement('div');